@@ -0,0 +1,20 @@
+    .section .text
+    .global _fn_trap_from_kernel
+    .align 4
+
+# the entry of the trap from the kernel, which is fatal and never returns.
+# the stack pointer may be in the guard page when the kernel stack was overflowed,
+# so we switch to the emergency stack before calling the rust handler
+_fn_trap_from_kernel:
+    la sp, _addr_kernel_trap_stack_bigger_bound
+    call trap_from_kernel
+
+    .section .bss.kernel_trap_stack
+    # the stack pointer must be aligned to 16 bytes by the calling convention
+    .balign 16
+    .global _addr_kernel_trap_stack_smaller_bound
+_addr_kernel_trap_stack_smaller_bound:
+    # the emergency stack is only used for reporting the fatal trap
+    .space 4096 * 4
+    .global _addr_kernel_trap_stack_bigger_bound
+_addr_kernel_trap_stack_bigger_bound:
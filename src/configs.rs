@@ -4,7 +4,7 @@
 // self mods
 
 // use other mods
-use log::Level;
+use log::{Level, LevelFilter};
 
 // use self mods
 pub(crate) const MEMORY_PAGE_BYTE_SIZE: usize = 4096;
@@ -14,9 +14,24 @@ pub(crate) const MEMORY_PAGE_BIT_SITE: usize = 12;
 /// Because for the safety reasons
 /// We inject some guard page between stack area and other area
 pub(crate) const USER_TASK_STACK_BYTE_SIZE: usize = MEMORY_PAGE_BYTE_SIZE * 8;
+/// Each user stack reserves the virtual address range of the max byte size,
+/// but only the byte size requested by the task will be mapped
+pub(crate) const USER_TASK_STACK_MAX_BYTE_SIZE: usize = MEMORY_PAGE_BYTE_SIZE * 64;
 pub(crate) const KERNEL_TASK_STACK_BYTE_SIZE: usize = MEMORY_PAGE_BYTE_SIZE * 2;
+/// Each kernel stack reserves the virtual address range of the max byte size,
+/// but only the byte size requested by the task will be mapped
+pub(crate) const KERNEL_TASK_STACK_MAX_BYTE_SIZE: usize = MEMORY_PAGE_BYTE_SIZE * 8;
 pub(crate) const KERNEL_HEAP_BYTE_SIZE: usize = MEMORY_PAGE_BYTE_SIZE * 1024;
 pub(crate) const KERNEL_GUARD_PAGE_COUNT: usize = 1;
+/// Scan the current process's anonymous areas and promote them into huge pages in each timer tick
+pub(crate) const MEMORY_TRANSPARENT_HUGE_PAGE: bool = false;
+/// Map the aligned runs of the pages in the large areas into huge pages directly when the areas are created,
+/// the framed areas will fall back to the normal pages if there are no contiguous frames
+pub(crate) const MEMORY_HUGE_PAGE_MAPPING: bool = true;
+/// Enable the completion interrupts of the virtio block devices,
+/// the task doing the file system I/O is blocked until the interrupt arrives,
+/// otherwise the requests are completed by polling the device
+pub(crate) const VIRTIO_BLOCK_INTERRUPT: bool = true;
 pub(crate) const MAX_VIRTUAL_ADDRESS: usize = usize::MAX;
 pub(crate) const MAX_PID_COUNT: usize = 65536;
 pub(crate) const MAX_TID_COUNT: usize = 10240;
@@ -24,13 +39,78 @@ pub(crate) const INIT_PROCESS_PATH: &'static str = "/initproc";
 pub(crate) const TRAMPOLINE_VIRTUAL_BASE_ADDR: usize = MAX_VIRTUAL_ADDRESS - MEMORY_PAGE_BYTE_SIZE + 1;
 pub(crate) const TRAP_CTX_VIRTUAL_BASE_ADDR: usize = TRAMPOLINE_VIRTUAL_BASE_ADDR - MEMORY_PAGE_BYTE_SIZE;
 pub(crate) const TICKS_PER_SEC: usize = 100;
+/// The count of syscalls between two preemptions and the virtual microseconds elapsed by each syscall,
+/// only used when the feature `sched_deterministic` was enabled
+pub(crate) const SCHED_DETERMINISTIC_SYSCALL_QUANTUM: usize = 8;
+pub(crate) const SCHED_DETERMINISTIC_SYSCALL_US: usize = 100;
+/// The count of levels of the multi-level feedback queues,
+/// and the interval in microseconds to boost all ready tasks to the highest level.
+/// Only used when the feature `sched_fifo` was not enabled
+pub(crate) const SCHED_MLFQ_LEVEL_COUNT: usize = 3;
+pub(crate) const SCHED_MLFQ_BOOST_INTERVAL_US: usize = 1_000_000;
+/// The max count of consecutive directed yields before falling back to a normal yield,
+/// so that the tasks which hand off to each other cannot starve the other ready tasks
+pub(crate) const SCHED_MAX_DIRECTED_YIELD_COUNT: usize = 16;
+/// The interval in microseconds to check the polled files again when none of them is ready
+pub(crate) const POLL_INTERVAL_US: usize = 10_000;
 pub(crate) const LOG_LEVEL: Level = Level::Info;
+/// The log levels of the specified targets at boot, which override the [`LOG_LEVEL`].
+/// The target is the module path without the crate name, such as `("memory", LevelFilter::Debug)`,
+/// and the longest matched target will be used.
+pub(crate) const LOG_TARGET_LEVELS: &[(&str, LevelFilter)] = &[];
+/// The byte size of the ring buffer which keeps the recent kernel log lines,
+/// the oldest bytes will be dropped once it is full
+pub(crate) const LOG_RING_BUFFER_BYTE_SIZE: usize = 16 * 1024;
+/// The range of the real-time signal numbers, the real-time signals will be queued instead of being collapsed
+pub(crate) const SIGNAL_RT_MIN: usize = 34;
+pub(crate) const SIGNAL_RT_MAX: usize = 64;
+pub(crate) const MAX_RT_SIGNAL_QUEUE_LEN: usize = 32;
+/// When the count of the free frames is less than the watermark, the kernel will warn and skip the non-essential allocations
+pub(crate) const FRAME_LOW_WATERMARK_COUNT: usize = 64;
+/// The count of the page slots in the swap device, each slot keeps one evicted page
+pub(crate) const SWAP_SLOT_COUNT: usize = 4096;
+/// The max count of the cold pages which will be evicted to the swap device
+/// in each round of the idle loop when the free frames are below the low watermark
+pub(crate) const SWAP_RECLAIM_PAGE_COUNT: usize = 16;
 pub(crate) const MAX_FD_COUNT: usize = 65536;
 pub(crate) const MAX_MUTEX_COUNT: usize = 1024;
+/// The max iterations of the adaptive mutex spinning before the waiting task is blocked
+pub(crate) const MUTEX_ADAPTIVE_SPIN_LIMIT: usize = 128;
+/// Check the wait-for graph before a task is blocked by a mutex,
+/// it is disabled by default because each blocking will walk the graph
+pub(crate) const ENABLE_DEADLOCK_DETECTION: bool = false;
 pub(crate) const MAX_SEMAPHORE_COUNT: usize = MAX_MUTEX_COUNT;
 pub(crate) const MAX_CONDVAR_COUNT: usize = MAX_MUTEX_COUNT;
+pub(crate) const MAX_BARRIER_COUNT: usize = MAX_MUTEX_COUNT;
+pub(crate) const FUTEX_HASH_BUCKET_COUNT: usize = 256;
+pub(crate) const MAX_SHM_COUNT: usize = 1024;
+pub(crate) const MAX_CGROUP_COUNT: usize = 64;
+/// The lowest virtual address where the shared memory segments will be attached in the user space
+pub(crate) const SHM_VIRTUAL_BASE_ADDR: usize = 0x20_0000_0000;
 pub(crate) const PIPE_RING_BUFFER_LENGTH: usize = 32;
+pub(crate) const MAX_MQUEUE_COUNT: usize = 256;
+/// The max count of the messages in one message queue, the sender will be blocked when it is full
+pub(crate) const MQUEUE_MAX_MESSAGE_COUNT: usize = 16;
+pub(crate) const MQUEUE_MESSAGE_BYTE_SIZE: usize = 256;
+pub(crate) const FS_READAHEAD_BLOCK_COUNT: usize = 4;
+/// The access time is only updated by reading if it is older than the modification time
+/// or it was updated more than this interval ago
+pub(crate) const FS_ATIME_UPDATE_INTERVAL_US: usize = 24 * 60 * 60 * 1_000_000;
 pub(crate) const COMMAND_LINE_ARGUMENTS_BYTE_SIZE: usize = 512;
+/// The max byte length of the path passed from the user, including the NULL terminator
+pub(crate) const MAX_PATH_LEN: usize = 256;
+/// The max count of the symbolic links which can be followed when resolving one path
+pub(crate) const MAX_SYMLINK_HOPS: usize = 40;
+pub(crate) const PROCESS_INFO_PATH_BYTE_SIZE: usize = 64;
+/// The byte size of each field in the kernel identity, including the NULL terminator
+pub(crate) const UTS_NAME_FIELD_BYTE_SIZE: usize = 65;
+/// The name of the kernel
+pub(crate) const KERNEL_NAME: &'static str = "frontier";
+/// The release version of the kernel
+pub(crate) const KERNEL_RELEASE: &'static str = env!("CARGO_PKG_VERSION");
+pub(crate) const CONSOLE_ECHO: bool = false;
+pub(crate) const CONSOLE_ERASE: bool = true;
+pub(crate) const CONSOLE_CANONICAL: bool = false;
 
 // the frequency of the board clock in Hz
 cfg_if! {
@@ -41,8 +121,20 @@ cfg_if! {
             // VIRT_TEST/RTC  in virt machine
             (0x0010_0000, 0x0010_2000),
             // Virtio Block in virt machine
-            (0x1000_1000, 0x1000_2000),
+            (0x1000_1000, 0x1000_9000),
+            // PLIC priorities, enable bits and the supervisor context of hart zero in virt machine
+            (0x0c00_0000, 0x0c20_2000),
         ];
+        // the base address of the platform-level interrupt controller
+        pub(crate) const PLIC_BASE_ADDR: usize = 0x0c00_0000;
+        // the PLIC context of the supervisor mode in hart zero, which claims all external interrupts
+        pub(crate) const PLIC_CONTEXT: usize = 1;
+        // the virtio mmio slots and their interrupt source ids which will be probed as block devices,
+        // the index of the slot will be used as the device id in the mount table
+        pub(crate) const VIRTIO_BLOCK_SLOTS: &[(usize, usize)] =
+            &[(0x1000_1000, 1), (0x1000_2000, 2)];
+        // the virtio mmio slot which will be probed as the raw swap device
+        pub(crate) const VIRTIO_SWAP_SLOT: usize = 0x1000_3000;
     } else {
         compile_error!("Unknown feature for board");
     }
@@ -0,0 +1,104 @@
+// @author:    olinex
+// @time:      2024/08/13
+
+// self mods
+
+// use other mods
+use frontier_fs::block::BlockDevice;
+
+// use self mods
+use crate::task::PROCESSOR;
+
+/// The block device wrapper which counts the blocks read and written
+/// into the resource usage of the issuing task.
+/// The requests are serviced in the issue order, because the file system operations
+/// are already serialized and handed over between the processes in turn by [`crate::fs::FS_LOCK`],
+/// so there is never more than one pending request to be scheduled here.
+pub(crate) struct AccountedBlockDevice<D: BlockDevice> {
+    device: D,
+}
+impl<D: BlockDevice> AccountedBlockDevice<D> {
+    /// Wrap the block device with the accounting
+    ///
+    /// - Arguments
+    ///     - device: the underlying block device
+    pub(crate) fn new(device: D) -> Self {
+        Self { device }
+    }
+
+    /// Count the serviced block into the resource usage of the current task,
+    /// the requests issued without a current task are not counted
+    ///
+    /// - Arguments
+    ///     - write: whether the block was written
+    fn account(&self, write: bool) {
+        if let Some(task) = PROCESSOR.try_current_task() {
+            task.account_block_io(write);
+        }
+    }
+}
+impl<D: BlockDevice> BlockDevice for AccountedBlockDevice<D> {
+    fn read_block(&self, id: usize, buffer: &mut [u8]) -> Option<isize> {
+        let result = self.device.read_block(id, buffer);
+        self.account(false);
+        result
+    }
+
+    fn write_block(&self, id: usize, buffer: &[u8]) -> Option<isize> {
+        let result = self.device.write_block(id, buffer);
+        self.account(true);
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::collections::BTreeMap;
+    use alloc::vec::Vec;
+    use crate::lang::container::UserPromiseRefCell;
+    use crate::task::model::ForkedProcess;
+
+    /// The block device which keeps the blocks in memory
+    struct MemoryBlockDevice {
+        blocks: UserPromiseRefCell<BTreeMap<usize, Vec<u8>>>,
+    }
+    impl BlockDevice for MemoryBlockDevice {
+        fn read_block(&self, id: usize, buffer: &mut [u8]) -> Option<isize> {
+            match self.blocks.access().get(&id) {
+                Some(block) => buffer.copy_from_slice(block),
+                None => buffer.fill(0),
+            }
+            None
+        }
+
+        fn write_block(&self, id: usize, buffer: &[u8]) -> Option<isize> {
+            self.blocks.exclusive_access().insert(id, buffer.to_vec());
+            None
+        }
+    }
+
+    #[test_case]
+    fn test_accounted_block_device_services_real_io() {
+        let device = AccountedBlockDevice::new(MemoryBlockDevice {
+            blocks: unsafe { UserPromiseRefCell::new(BTreeMap::new()) },
+        });
+        let data = [0x5au8; 8];
+        assert_eq!(device.write_block(7, &data), None);
+        let mut buffer = [0u8; 8];
+        assert_eq!(device.read_block(7, &mut buffer), None);
+        assert_eq!(buffer, data);
+        assert_eq!(device.read_block(8, &mut buffer), None);
+        assert_eq!(buffer, [0u8; 8]);
+        // the blocks are counted into the issuing task
+        let process = ForkedProcess::new();
+        let task = process.inner_access().root_task();
+        let current = process.enter();
+        assert_eq!(device.write_block(7, &data), None);
+        assert_eq!(device.read_block(7, &mut buffer), None);
+        assert_eq!(device.read_block(8, &mut buffer), None);
+        drop(current);
+        assert_eq!(task.rusage().output_blocks, 1);
+        assert_eq!(task.rusage().input_blocks, 2);
+    }
+}
@@ -2,10 +2,15 @@
 // @time:      2024/01/09
 
 // self mods
+mod accounting;
 mod virtio_blk;
 
 // use other mods
 // use self mods
 
+pub(crate) use accounting::AccountedBlockDevice;
+
 #[cfg(feature = "board_qemu")]
 pub(crate) type BlockDeviceImpl = virtio_blk::VirtIOBlock;
+#[cfg(feature = "board_qemu")]
+pub(crate) use virtio_blk::handle_interrupt;
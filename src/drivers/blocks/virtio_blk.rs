@@ -4,21 +4,28 @@
 // self mods
 
 // use other mods
+use alloc::collections::BTreeMap;
+use alloc::sync::{Arc, Weak};
 use alloc::vec::Vec;
+use core::hint::spin_loop;
 use core::ptr::NonNull;
 use frontier_fs::block::BlockDevice;
 use frontier_fs::configs::BLOCK_BYTE_SIZE;
-use virtio_drivers::device::blk::VirtIOBlk;
+use virtio_drivers::device::blk::{BlkReq, BlkResp, VirtIOBlk};
 use virtio_drivers::transport::mmio::{MmioTransport, VirtIOHeader};
 use virtio_drivers::{BufferDirection, Hal, PhysAddr};
 
 // use self mods
+use crate::configs;
+use crate::drivers::plic;
+use crate::fs::FS_LOCK;
 use crate::lang::container::UserPromiseRefCell;
 use crate::memory::frame::{FrameTracker, FRAME_ALLOCATOR};
 use crate::memory::space::KERNEL_SPACE;
 use crate::prelude::*;
+use crate::task::model::TaskControlBlock;
+use crate::task::{block_current_and_run_other_task, PROCESSOR, TASK_SCHEDULER};
 
-const VIRTIO_0: usize = 0x1000_1000;
 const BLK_BYTE_SIZE: usize = 512;
 const BLK_GROUP_COUNT: usize = BLOCK_BYTE_SIZE / BLK_BYTE_SIZE;
 
@@ -91,28 +98,158 @@ unsafe impl Hal for HalImpl {
     }
 }
 
-/// Virtual IO block devices via memory mapping
+/// The mutable states of the virtio block device
+struct VirtIOBlockInner {
+    blk: VirtIOBlk<HalImpl, MmioTransport>,
+    /// The tasks which are blocked until their requests are completed, the token as key
+    waiters: BTreeMap<u16, Weak<TaskControlBlock>>,
+}
+impl VirtIOBlockInner {
+    /// Acknowledge the interrupt of the device and wake up the task waiting for the completion
+    fn handle_interrupt(&mut self) {
+        self.blk.ack_interrupt();
+        self.wake_used();
+    }
+
+    /// Wake up the task whose request is the next one used by the device.
+    /// The requests are completed in the used order, so only the first used one can be taken,
+    /// the next waiter will be woken up after it is completed.
+    fn wake_used(&mut self) {
+        let token = match self.blk.peek_used() {
+            Some(token) => token,
+            None => return,
+        };
+        if let Some(task) = self.waiters.remove(&token).and_then(|task| task.upgrade()) {
+            task.mark_suspended();
+            TASK_SCHEDULER.put_read_task(task);
+        }
+    }
+}
+
+/// Virtual IO block devices via memory mapping.
+/// If the interrupt is enabled, the issuing task is blocked during the disk latency
+/// and woken up by the completion interrupt, so that the hart can run the other tasks meanwhile.
+/// Only the task holding the [`FS_LOCK`] can be blocked, because the file system holds
+/// its spin locks during the I/O, the other issuers like the booting and the swapping poll instead.
+/// The interrupt is only taken in the user mode or by the idle hart,
+/// so it never arrives between registering the issuer and blocking it.
 pub(crate) struct VirtIOBlock {
-    blk: UserPromiseRefCell<VirtIOBlk<HalImpl, MmioTransport>>,
+    /// The id of the interrupt source, None if the requests are always completed by polling
+    irq: Option<usize>,
+    inner: Arc<UserPromiseRefCell<VirtIOBlockInner>>,
 }
 impl VirtIOBlock {
-    pub(crate) fn new() -> Result<Self> {
-        let header = VIRTIO_0 as *mut VirtIOHeader;
+    /// Create a new virtio block device by probing the mmio slot
+    ///
+    /// - Arguments
+    ///     - base: the base address of the virtio mmio slot
+    ///     - irq: the id of the interrupt source, None if the interrupt will be disabled
+    ///
+    /// - Errors
+    ///     - DriverMMIOError
+    ///     - DriverVirtIOError
+    pub(crate) fn new(base: usize, irq: Option<usize>) -> Result<Self> {
+        let header = base as *mut VirtIOHeader;
         let transport = unsafe { MmioTransport::new(NonNull::new(header).unwrap())? };
         let blk = VirtIOBlk::<HalImpl, _>::new(transport)?;
-        Ok(Self {
-            blk: unsafe { UserPromiseRefCell::new(blk) },
-        })
+        let inner = Arc::new(unsafe {
+            UserPromiseRefCell::new(VirtIOBlockInner {
+                blk,
+                waiters: BTreeMap::new(),
+            })
+        });
+        let irq = irq.filter(|_| configs::VIRTIO_BLOCK_INTERRUPT);
+        if let Some(irq) = irq {
+            VIRTIO_BLOCK_IRQS
+                .exclusive_access()
+                .insert(irq, Arc::clone(&inner));
+            plic::enable(irq);
+        }
+        Ok(Self { irq, inner })
+    }
+
+    /// Wait until the request in flight is used by the device.
+    /// The current task is blocked if it is allowed, otherwise the device is polled.
+    ///
+    /// - Arguments
+    ///     - token: the token of the request in flight
+    ///
+    /// - Errors
+    ///     - ProcessHaveNotTask
+    fn wait(&self, token: u16) -> Result<()> {
+        loop {
+            let mut inner = self.inner.exclusive_access();
+            if inner.blk.peek_used() == Some(token) {
+                return Ok(());
+            }
+            if self.irq.is_some() && FS_LOCK.may_sleep() {
+                let current_task = PROCESSOR.current_task()?;
+                inner.waiters.insert(token, Arc::downgrade(&current_task));
+                drop(current_task);
+                drop(inner);
+                block_current_and_run_other_task()?;
+            } else {
+                drop(inner);
+                spin_loop();
+            }
+        }
+    }
+
+    /// Read one sector of the device
+    ///
+    /// - Arguments
+    ///     - sector: the id of the sector
+    ///     - buffer: the buffer of the sector
+    fn read_sector(&self, sector: usize, buffer: &mut [u8]) -> Result<()> {
+        let mut request = BlkReq::default();
+        let mut response = BlkResp::default();
+        unsafe {
+            let token = self
+                .inner
+                .exclusive_access()
+                .blk
+                .read_blocks_nb(sector, &mut request, buffer, &mut response)?;
+            self.wait(token)?;
+            let mut inner = self.inner.exclusive_access();
+            inner
+                .blk
+                .complete_read_blocks(token, &request, buffer, &mut response)?;
+            inner.wake_used();
+        }
+        Ok(())
+    }
+
+    /// Write one sector of the device
+    ///
+    /// - Arguments
+    ///     - sector: the id of the sector
+    ///     - buffer: the buffer of the sector
+    fn write_sector(&self, sector: usize, buffer: &[u8]) -> Result<()> {
+        let mut request = BlkReq::default();
+        let mut response = BlkResp::default();
+        unsafe {
+            let token = self
+                .inner
+                .exclusive_access()
+                .blk
+                .write_blocks_nb(sector, &mut request, buffer, &mut response)?;
+            self.wait(token)?;
+            let mut inner = self.inner.exclusive_access();
+            inner
+                .blk
+                .complete_write_blocks(token, &request, buffer, &mut response)?;
+            inner.wake_used();
+        }
+        Ok(())
     }
 }
 impl BlockDevice for VirtIOBlock {
     fn read_block(&self, id: usize, buffer: &mut [u8]) -> Option<isize> {
-        let mut device = self.blk.exclusive_access();
         for i in 0..BLK_GROUP_COUNT {
             let start_offset = i * BLK_BYTE_SIZE;
             let end_offset = start_offset + BLK_BYTE_SIZE;
-            if device
-                .read_blocks(
+            if self
+                .read_sector(
                     id * BLK_GROUP_COUNT + i,
                     &mut buffer[start_offset..end_offset],
                 )
@@ -125,12 +262,11 @@ impl BlockDevice for VirtIOBlock {
     }
 
     fn write_block(&self, id: usize, buffer: &[u8]) -> Option<isize> {
-        let mut device = self.blk.exclusive_access();
         for i in 0..BLK_GROUP_COUNT {
             let start_offset = i * BLK_BYTE_SIZE;
             let end_offset = start_offset + BLK_BYTE_SIZE;
-            if device
-                .write_blocks(id * BLK_GROUP_COUNT + i, &buffer[start_offset..end_offset])
+            if self
+                .write_sector(id * BLK_GROUP_COUNT + i, &buffer[start_offset..end_offset])
                 .is_err()
             {
                 return Some(-1);
@@ -140,7 +276,88 @@ impl BlockDevice for VirtIOBlock {
     }
 }
 
+/// Handle the interrupt of the virtio block device
+///
+/// - Arguments
+///     - irq: the id of the interrupt source
+///
+/// - Returns
+///     - true: the interrupt was signaled by a virtio block device
+///     - false: the interrupt source does not belong to any virtio block device
+pub(crate) fn handle_interrupt(irq: usize) -> bool {
+    let inner = match VIRTIO_BLOCK_IRQS.access().get(&irq) {
+        Some(inner) => Arc::clone(inner),
+        None => return false,
+    };
+    inner.exclusive_access().handle_interrupt();
+    true
+}
+
+/// The map of the interrupt source id and the states of the virtio block device
+type VirtIOBlockIrqTable = BTreeMap<usize, Arc<UserPromiseRefCell<VirtIOBlockInner>>>;
+
 lazy_static! {
+    /// The virtio block devices which complete the requests by the interrupts
+    static ref VIRTIO_BLOCK_IRQS: UserPromiseRefCell<VirtIOBlockIrqTable> =
+        unsafe { UserPromiseRefCell::new(BTreeMap::new()) };
     pub(crate) static ref QUEUE_FRAMES: UserPromiseRefCell<Vec<FrameTracker>> =
         unsafe { UserPromiseRefCell::new(Vec::new()) };
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::inode::ROOT_INODE;
+    use crate::fs::MOUNT_TABLE;
+    use crate::task::model::{Credential, ForkedProcess};
+    use frontier_fs::OpenFlags;
+
+    #[test_case]
+    fn test_virtio_block_interrupt_wakes_waiting_task() {
+        // the block devices in the mount table are registered by their interrupt source ids
+        assert!(!MOUNT_TABLE.is_empty());
+        let (_, irq) = configs::VIRTIO_BLOCK_SLOTS[0];
+        let inner = Arc::clone(VIRTIO_BLOCK_IRQS.access().get(&irq).unwrap());
+        assert!(!handle_interrupt(usize::MAX));
+        // the tasks are never blocked by the device before the scheduler started
+        assert!(!FS_LOCK.may_sleep());
+        let process = ForkedProcess::new();
+        let task = process.inner_access().root_task();
+        let mut buffer = [0u8; BLK_BYTE_SIZE];
+        let mut request = BlkReq::default();
+        let mut response = BlkResp::default();
+        let token = unsafe {
+            inner
+                .exclusive_access()
+                .blk
+                .read_blocks_nb(0, &mut request, &mut buffer, &mut response)
+                .unwrap()
+        };
+        // the issuer is blocked just like it is in the wait of the device
+        task.mark_blocked();
+        inner.exclusive_access().waiters.insert(token, Arc::downgrade(&task));
+        while inner.exclusive_access().blk.peek_used() != Some(token) {
+            spin_loop();
+        }
+        assert!(task.is_blocked());
+        assert!(handle_interrupt(irq));
+        assert!(!task.is_blocked());
+        assert!(inner.access().waiters.is_empty());
+        assert!(TASK_SCHEDULER.fetch_specific(&task));
+        assert!(Arc::ptr_eq(&TASK_SCHEDULER.pop_ready_task().unwrap(), &task));
+        unsafe {
+            inner
+                .exclusive_access()
+                .blk
+                .complete_read_blocks(token, &request, &mut buffer, &mut response)
+                .unwrap();
+        }
+        // the stray interrupts do not disturb the following requests
+        assert!(handle_interrupt(irq));
+        let path = configs::INIT_PROCESS_PATH;
+        let file = ROOT_INODE.find_inode(path, OpenFlags::READ, Credential::ROOT).unwrap();
+        let data = file.read_all().unwrap();
+        assert_eq!(&data[..4], b"\x7fELF");
+        assert!(handle_interrupt(irq));
+    }
+}
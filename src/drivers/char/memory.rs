@@ -0,0 +1,75 @@
+// @author:    olinex
+// @time:      2024/08/22
+
+// self mods
+
+// use other mods
+use core::any::Any;
+
+// use self mods
+use crate::fs::File;
+use crate::lang::buffer::ByteBuffers;
+use crate::prelude::*;
+
+/// The null device, which discards all the written bytes and is always at the end of file when reading
+pub(crate) struct Null;
+impl File for Null {
+    /// Always at the end of file, return zero
+    fn read(&self, _: ByteBuffers) -> Result<u64> {
+        Ok(0)
+    }
+
+    /// Discard all bytes without touching the buffers, return the whole length
+    fn write(&self, buffers: ByteBuffers) -> Result<u64> {
+        Ok(buffers.len() as u64)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// The zero device, which fills the reading buffers with zeros and discards all the written bytes
+pub(crate) struct Zero;
+impl File for Zero {
+    /// Fill the whole buffers with zeros in place, return the whole length
+    fn read(&self, buffers: ByteBuffers) -> Result<u64> {
+        let len = buffers.len();
+        for slice in buffers.into_slices() {
+            slice.fill(0);
+        }
+        Ok(len as u64)
+    }
+
+    /// Discard all bytes without touching the buffers, return the whole length
+    fn write(&self, buffers: ByteBuffers) -> Result<u64> {
+        Ok(buffers.len() as u64)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::frame::FRAME_ALLOCATOR;
+    use alloc::vec;
+
+    #[test_case]
+    fn test_null_and_zero() {
+        let free = FRAME_ALLOCATOR.access().free_count();
+        // the buffers of one megabyte are never touched, so no page is needed
+        assert_eq!(Null.write(ByteBuffers::new(vec![], 1 << 20)).unwrap(), 1 << 20);
+        assert_eq!(Zero.write(ByteBuffers::new(vec![], 1 << 20)).unwrap(), 1 << 20);
+        assert_eq!(FRAME_ALLOCATOR.access().free_count(), free);
+        let mut bytes = [0xffu8; 16];
+        let slice = unsafe { core::slice::from_raw_parts_mut(bytes.as_mut_ptr(), bytes.len()) };
+        assert_eq!(Null.read(ByteBuffers::new(vec![slice], 16)).unwrap(), 0);
+        assert_eq!(bytes, [0xff; 16]);
+        let slice = unsafe { core::slice::from_raw_parts_mut(bytes.as_mut_ptr(), bytes.len()) };
+        assert_eq!(Zero.read(ByteBuffers::new(vec![slice], 16)).unwrap(), 16);
+        assert_eq!(bytes, [0; 16]);
+    }
+}
@@ -0,0 +1,19 @@
+// @author:    olinex
+// @time:      2024/07/08
+
+// self mods
+mod memory;
+mod random;
+mod uart;
+
+// use other mods
+
+// use self mods
+
+// reexports
+pub(crate) use memory::{Null, Zero};
+pub(crate) use random::{fill_random, Random};
+pub(crate) use uart::LineDiscipline;
+
+#[cfg(feature = "board_qemu")]
+pub(crate) type CharDeviceImpl = uart::Uart;
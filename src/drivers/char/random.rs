@@ -0,0 +1,178 @@
+// @author:    olinex
+// @time:      2024/08/22
+
+// self mods
+
+// use other mods
+use core::any::Any;
+use spin::Mutex;
+
+// use self mods
+use crate::fs::File;
+use crate::lang::buffer::ByteBuffers;
+use crate::prelude::*;
+use crate::sbi::{SBIApi, SBI};
+
+cfg_if! {
+    if #[cfg(feature = "rng_zkr")] {
+        /// Read 64 bits entropy from the `seed` CSR of the RISC-V `Zkr` extension.
+        /// Each reading returns 16 bits entropy when the status is `ES16`,
+        /// None will be returned if the entropy source is dead.
+        fn hardware_seed() -> Option<u64> {
+            const SEED_STATUS_SHIFT: usize = 30;
+            const SEED_STATUS_ES16: usize = 0b10;
+            const SEED_STATUS_DEAD: usize = 0b11;
+            let mut seed = 0u64;
+            let mut count = 0;
+            while count < 4 {
+                let value: usize;
+                unsafe { core::arch::asm!("csrrw {0}, 0x015, zero", out(reg) value) };
+                match (value >> SEED_STATUS_SHIFT) & 0b11 {
+                    SEED_STATUS_ES16 => {
+                        seed = (seed << 16) | (value & 0xffff) as u64;
+                        count += 1;
+                    }
+                    SEED_STATUS_DEAD => return None,
+                    // the entropy source is not ready yet, try again
+                    _ => continue,
+                }
+            }
+            Some(seed)
+        }
+    } else {
+        /// The board has no hardware entropy source, the generator will only be seeded by the timer
+        fn hardware_seed() -> Option<u64> {
+            None
+        }
+    }
+}
+
+/// Scramble the value by the finalizer of the SplitMix64,
+/// so that the close values such as the timer ticks will be spread over all bits
+///
+/// - Arguments
+///     - value: the value to scramble
+fn splitmix64(value: u64) -> u64 {
+    let mut z = value.wrapping_add(0x9e3779b97f4a7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+/// The xorshift64* pseudo random number generator,
+/// it is not cryptographically secure but is enough for the user programs without hardware entropy.
+pub(crate) struct Xorshift {
+    state: u64,
+}
+impl Xorshift {
+    /// Create a new generator by the seed, the seed will be scrambled so that zero is also acceptable
+    ///
+    /// - Arguments
+    ///     - seed: the initial seed
+    pub(crate) fn new(seed: u64) -> Self {
+        let mut rng = Self { state: 0 };
+        rng.mix(seed);
+        rng
+    }
+
+    /// Mix more entropy into the state, the state will never be zero
+    ///
+    /// - Arguments
+    ///     - entropy: the entropy to mix
+    pub(crate) fn mix(&mut self, entropy: u64) {
+        self.state = splitmix64(self.state ^ entropy);
+        if self.state == 0 {
+            self.state = splitmix64(entropy.wrapping_add(1));
+        }
+    }
+
+    /// Generate the next random number
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545f4914f6cdd1d)
+    }
+
+    /// Fill the whole bytes with random numbers
+    ///
+    /// - Arguments
+    ///     - bytes: the bytes to fill
+    pub(crate) fn fill(&mut self, bytes: &mut [u8]) {
+        for chunk in bytes.chunks_mut(8) {
+            let value = self.next_u64().to_le_bytes();
+            chunk.copy_from_slice(&value[..chunk.len()]);
+        }
+    }
+}
+
+lazy_static! {
+    /// The global random number generator, which is seeded by the hardware entropy if it exists,
+    /// and the timer ticks at the first use
+    static ref RNG: Mutex<Xorshift> = {
+        let seed = hardware_seed().unwrap_or(0) ^ SBI::get_timer() as u64;
+        Mutex::new(Xorshift::new(seed))
+    };
+}
+
+/// Fill the whole bytes with random numbers.
+/// The timer ticks at each calling will be mixed in, which jitter with the scheduling of the tasks.
+///
+/// - Arguments
+///     - bytes: the bytes to fill
+pub(crate) fn fill_random(bytes: &mut [u8]) {
+    let mut rng = RNG.lock();
+    rng.mix(hardware_seed().unwrap_or(0) ^ SBI::get_timer() as u64);
+    rng.fill(bytes);
+}
+
+/// The random device, which fills the reading buffers with random bytes and discards all the written bytes.
+/// It will never block even if the hardware entropy source does not exist.
+pub(crate) struct Random;
+impl File for Random {
+    /// Fill the whole buffers with random bytes, return the whole length
+    fn read(&self, buffers: ByteBuffers) -> Result<u64> {
+        let len = buffers.len();
+        for slice in buffers.into_slices() {
+            fill_random(slice);
+        }
+        Ok(len as u64)
+    }
+
+    /// Discard all bytes, return the whole length
+    fn write(&self, buffers: ByteBuffers) -> Result<u64> {
+        Ok(buffers.len() as u64)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_xorshift() {
+        let mut first = Xorshift::new(0);
+        let mut second = Xorshift::new(0);
+        assert_ne!(first.state, 0);
+        // the same seed generates the same sequence
+        assert_eq!(first.next_u64(), second.next_u64());
+        second.mix(1);
+        assert_ne!(first.next_u64(), second.next_u64());
+        let mut bytes = [0u8; 13];
+        first.fill(&mut bytes);
+        assert!(bytes.iter().any(|byte| *byte != 0));
+    }
+
+    #[test_case]
+    fn test_fill_random() {
+        let mut first = [0u8; 32];
+        let mut second = [0u8; 32];
+        fill_random(&mut first);
+        fill_random(&mut second);
+        assert_ne!(first, second);
+    }
+}
@@ -0,0 +1,225 @@
+// @author:    olinex
+// @time:      2024/07/08
+
+// self mods
+
+// use other mods
+use alloc::vec::Vec;
+use core::any::Any;
+use frontier_lib::constant::charater;
+use spin::Mutex;
+
+// use self mods
+use crate::configs;
+use crate::fs::{File, PollEvents};
+use crate::lang::buffer::ByteBuffers;
+use crate::lang::console::CONSOLE_WRITER;
+use crate::prelude::*;
+use crate::sbi::{SBIApi, SBI};
+use crate::task::{suspend_current_and_run_other_task, PROCESSOR};
+
+const BACKSPACE: u8 = 0x08;
+const DELETE: u8 = 0x7f;
+const LINE_FEED: u8 = b'\n';
+const CARRIAGE_RETURN: u8 = b'\r';
+
+/// The line discipline of the console, which decides how the input bytes will be processed
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub(crate) struct LineDiscipline {
+    /// Write the input bytes back to the console
+    pub(crate) echo: bool,
+    /// Treat backspace and delete as erasing the previous input byte
+    pub(crate) erase: bool,
+    /// Only return when a whole line was read, otherwise return each byte immediately
+    pub(crate) canonical: bool,
+}
+impl LineDiscipline {
+    /// The canonical line mode, which echoes the input bytes and returns only on newline
+    pub(crate) fn canonical() -> Self {
+        Self {
+            echo: true,
+            erase: true,
+            canonical: true,
+        }
+    }
+
+    /// The raw character mode, which returns each byte immediately without echo
+    pub(crate) fn raw() -> Self {
+        Self {
+            echo: false,
+            erase: false,
+            canonical: false,
+        }
+    }
+}
+impl Default for LineDiscipline {
+    /// The default line discipline in the configs
+    fn default() -> Self {
+        Self {
+            echo: configs::CONSOLE_ECHO,
+            erase: configs::CONSOLE_ERASE,
+            canonical: configs::CONSOLE_CANONICAL,
+        }
+    }
+}
+
+/// The serial console device, the bytes are transmitted through the SBI console.
+/// Each process have it's own line discipline, so one process changing the mode doesn't affect another.
+/// The writing shares the console lock with the kernel printing.
+pub(crate) struct Uart {
+    reader: Mutex<()>,
+    /// The byte which was taken from the console by polling but has not been read yet
+    peeked: Mutex<Option<u8>>,
+}
+impl Uart {
+    /// Create a new uart device
+    pub(crate) fn new() -> Self {
+        Self {
+            reader: Mutex::new(()),
+            peeked: Mutex::new(None),
+        }
+    }
+
+    /// Read one byte from the console, the byte taken by polling will be returned first
+    fn getchar(&self) -> Option<u8> {
+        self.peeked.lock().take().or_else(SBI::console_getchar)
+    }
+
+    /// Write one byte into the console, the caller must hold the console lock
+    fn putchar(&self, byte: u8) {
+        SBI::console_putchar(byte);
+    }
+
+    /// Write the input bytes back to the console under the console lock.
+    /// The lock is only held for a few bytes, so it is acquired by spinning.
+    ///
+    /// - Arguments
+    ///     - bytes: the bytes to echo
+    fn echo(&self, bytes: &[u8]) {
+        let lock = CONSOLE_WRITER.lock();
+        for byte in bytes {
+            self.putchar(*byte);
+        }
+        drop(lock);
+    }
+
+    /// Get the line discipline of the current process
+    ///
+    /// - Errors
+    ///     - ProcessHaveNotTask
+    fn discipline(&self) -> Result<LineDiscipline> {
+        let task = PROCESSOR.current_task()?;
+        let process = task.process();
+        let discipline = process.inner_access().console_discipline();
+        Ok(discipline)
+    }
+}
+impl File for Uart {
+    /// Read some bytes from console and write them into buffers.
+    ///
+    /// This method will keep trying to obtain the read lock of the console,
+    /// and when the lock is obtained, the method will not release the lock until the specified number of bytes are read;
+    /// When bytes can no longer be retrieved from the underlying driver layer of the console,
+    /// the current task will be paused and other tasks will be executed,
+    /// and the lock will not be released. Unless the byte read is NULL.
+    /// The input bytes are processed by the line discipline of the current process before they are written into buffers:
+    ///     - canonical mode: return only when a newline was read or buffers are full
+    ///     - raw mode: return immediately once no more byte is available
+    ///
+    /// See [`crate::fs::File`]
+    ///
+    /// - Errors
+    ///     - ProcessHaveNotTask
+    ///     - EOB
+    fn read(&self, buffers: ByteBuffers) -> Result<u64> {
+        let discipline = self.discipline()?;
+        loop {
+            if let Some(lock) = self.reader.try_lock() {
+                let length = buffers.len();
+                let mut line: Vec<u8> = Vec::with_capacity(length);
+                while line.len() < length {
+                    let c = if let Some(c) = self.getchar() {
+                        c
+                    } else if !discipline.canonical && !line.is_empty() {
+                        break;
+                    } else {
+                        suspend_current_and_run_other_task()?;
+                        continue;
+                    };
+                    if c == charater::NULL as u8 {
+                        break;
+                    }
+                    if discipline.erase && (c == BACKSPACE || c == DELETE) {
+                        if line.pop().is_some() && discipline.echo {
+                            self.echo(&[BACKSPACE, b' ', BACKSPACE]);
+                        }
+                        continue;
+                    }
+                    if discipline.echo {
+                        self.echo(&[c]);
+                    }
+                    line.push(c);
+                    if discipline.canonical && (c == LINE_FEED || c == CARRIAGE_RETURN) {
+                        break;
+                    }
+                }
+                drop(lock);
+                let mut iterator = buffers.into_iter();
+                for c in line.iter() {
+                    iterator.next_mut(*c)?;
+                }
+                return Ok(line.len() as u64);
+            } else {
+                suspend_current_and_run_other_task()?;
+            }
+        }
+    }
+
+    /// Read some bytes from buffers and write them into console.
+    ///
+    /// This method will keep trying to obtain the console lock shared with the kernel printing,
+    /// and when the lock is obtained, the method will not release the lock until the specified number of bytes are writed;
+    ///
+    /// See [`crate::fs::File`]
+    ///
+    /// - Errors
+    ///     - ProcessHaveNotTask
+    fn write(&self, buffers: ByteBuffers) -> Result<u64> {
+        loop {
+            if let Some(lock) = CONSOLE_WRITER.try_lock() {
+                let length = buffers.len() as u64;
+                let mut iterator = buffers.into_iter();
+                while let Some(c) = iterator.next() {
+                    self.putchar(c);
+                }
+                drop(lock);
+                return Ok(length);
+            } else {
+                suspend_current_and_run_other_task()?;
+            }
+        }
+    }
+
+    /// The console is readable when there is an input byte available,
+    /// and it is always writable.
+    /// Because the SBI console cannot peek, the polled byte will be kept until the next read.
+    ///
+    /// See [`crate::fs::File`]
+    fn poll_ready(&self, events: PollEvents) -> bool {
+        if events.contains(PollEvents::OUT) {
+            return true;
+        }
+        if !events.contains(PollEvents::IN) {
+            return false;
+        }
+        let mut peeked = self.peeked.lock();
+        if peeked.is_none() {
+            *peeked = SBI::console_getchar();
+        }
+        peeked.is_some()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
@@ -3,7 +3,19 @@
 
 // self mods
 pub(crate) mod blocks;
+pub(crate) mod char;
+pub(crate) mod plic;
 
 // use other mods
 
 // use self mods
+
+/// Claim and handle all the pending external interrupts from the platform interrupt controller
+pub(crate) fn handle_external_interrupts() {
+    while let Some(irq) = plic::claim() {
+        if !blocks::handle_interrupt(irq) {
+            warn!("Unexpected external interrupt from source {}", irq);
+        }
+        plic::complete(irq);
+    }
+}
@@ -0,0 +1,73 @@
+// @author:    olinex
+// @time:      2024/08/14
+
+// self mods
+
+// use other mods
+
+// use self mods
+use crate::configs;
+
+/// The byte offset of the interrupt source priorities, each source takes 4 bytes
+const PRIORITY_OFFSET: usize = 0x0;
+/// The byte offset of the enable bits, each context takes 0x80 bytes
+const ENABLE_OFFSET: usize = 0x2000;
+const ENABLE_STRIDE: usize = 0x80;
+/// The byte offset of the threshold and claim registers, each context takes 0x1000 bytes
+const CONTEXT_OFFSET: usize = 0x20_0000;
+const CONTEXT_STRIDE: usize = 0x1000;
+/// The byte offset of the claim register in the context
+const CLAIM_OFFSET: usize = 0x4;
+
+/// Get the pointer of the 32-bit register in the platform-level interrupt controller
+///
+/// - Arguments
+///     - offset: the byte offset of the register from the base address
+#[inline(always)]
+fn register(offset: usize) -> *mut u32 {
+    (configs::PLIC_BASE_ADDR + offset) as *mut u32
+}
+
+/// Get the byte offset of the threshold register in the supervisor context
+#[inline(always)]
+fn context_offset() -> usize {
+    CONTEXT_OFFSET + configs::PLIC_CONTEXT * CONTEXT_STRIDE
+}
+
+/// Accept all the enabled interrupt sources whose priority is above zero in the supervisor context
+pub(crate) fn init() {
+    unsafe { register(context_offset()).write_volatile(0) };
+}
+
+/// Enable the interrupt source in the supervisor context with the lowest priority
+///
+/// - Arguments
+///     - irq: the id of the interrupt source
+pub(crate) fn enable(irq: usize) {
+    let enable = register(ENABLE_OFFSET + configs::PLIC_CONTEXT * ENABLE_STRIDE + irq / 32 * 4);
+    unsafe {
+        register(PRIORITY_OFFSET + irq * 4).write_volatile(1);
+        enable.write_volatile(enable.read_volatile() | (1 << (irq % 32)));
+    }
+}
+
+/// Claim the pending interrupt with the highest priority,
+/// the source will not be signaled again until the claim is completed.
+///
+/// - Returns
+///     - Some(irq): the id of the claimed interrupt source
+///     - None: there is no pending interrupt
+pub(crate) fn claim() -> Option<usize> {
+    match unsafe { register(context_offset() + CLAIM_OFFSET).read_volatile() } {
+        0 => None,
+        irq => Some(irq as usize),
+    }
+}
+
+/// Complete the claimed interrupt, so that the source can be signaled again
+///
+/// - Arguments
+///     - irq: the id of the claimed interrupt source
+pub(crate) fn complete(irq: usize) {
+    unsafe { register(context_offset() + CLAIM_OFFSET).write_volatile(irq as u32) };
+}
@@ -0,0 +1,58 @@
+// @author:    olinex
+// @time:      2024/07/08
+
+// self mods
+
+// use other mods
+use alloc::collections::BTreeMap;
+use alloc::sync::Arc;
+
+// use self mods
+use super::File;
+use crate::drivers::char::{CharDeviceImpl, Null, Random, Zero};
+
+/// The path of the console device file
+pub(crate) const CONSOLE_PATH: &'static str = "/dev/console";
+/// The path of the null device file
+pub(crate) const NULL_PATH: &'static str = "/dev/null";
+/// The path of the zero device file
+pub(crate) const ZERO_PATH: &'static str = "/dev/zero";
+/// The path of the random device file
+pub(crate) const URANDOM_PATH: &'static str = "/dev/urandom";
+
+lazy_static! {
+    /// Singleton console device
+    pub(crate) static ref CONSOLE: Arc<dyn File> = Arc::new(CharDeviceImpl::new());
+
+    /// The device table, which maps the path of the device file to the device
+    pub(crate) static ref DEVICE_TABLE: BTreeMap<&'static str, Arc<dyn File>> = {
+        let mut table: BTreeMap<&'static str, Arc<dyn File>> = BTreeMap::new();
+        table.insert(CONSOLE_PATH, Arc::clone(&CONSOLE));
+        table.insert(NULL_PATH, Arc::new(Null));
+        table.insert(ZERO_PATH, Arc::new(Zero));
+        table.insert(URANDOM_PATH, Arc::new(Random));
+        table
+    };
+}
+
+/// Find the device file by the path
+///
+/// - Arguments
+///     - path: the path of the device file
+pub(crate) fn find_device(path: &str) -> Option<Arc<dyn File>> {
+    DEVICE_TABLE.get(path).map(|device| Arc::clone(device))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_find_device() {
+        assert!(find_device(CONSOLE_PATH).is_some());
+        assert!(find_device(NULL_PATH).is_some());
+        assert!(find_device(ZERO_PATH).is_some());
+        assert!(find_device(URANDOM_PATH).is_some());
+        assert!(find_device("/dev/unknown").is_none());
+    }
+}
@@ -0,0 +1,251 @@
+// @author:    olinex
+// @time:      2024/08/08
+
+// self mods
+
+// use other mods
+use alloc::collections::VecDeque;
+use alloc::sync::{Arc, Weak};
+use core::any::Any;
+use core::mem::size_of;
+
+// use self mods
+use super::{File, PollEvents, PollWaitQueue};
+use crate::lang::buffer::ByteBuffers;
+use crate::lang::container::UserPromiseRefCell;
+use crate::prelude::*;
+use crate::task::model::TaskControlBlock;
+use crate::task::{block_current_and_run_other_task, PROCESSOR, TASK_SCHEDULER};
+
+/// The max value of the counter, the writer will be blocked if the counter would exceed it
+pub(crate) const EVENTFD_MAX_VALUE: u64 = u64::MAX - 1;
+
+/// The mutable states of the eventfd
+struct EventFdInner {
+    /// The counter of the events which were not read
+    counter: u64,
+    /// The tasks which are blocked because the counter is zero
+    readers: VecDeque<Weak<TaskControlBlock>>,
+    /// The tasks which are blocked because the counter would overflow
+    writers: VecDeque<Weak<TaskControlBlock>>,
+}
+
+/// Wake up at most count alive tasks in the waiting queue
+///
+/// - Arguments
+///     - waiting: the waiting queue of the blocked tasks
+///     - count: the max count of the tasks to wake up
+fn wake_many(waiting: &mut VecDeque<Weak<TaskControlBlock>>, mut count: u64) {
+    while count > 0 {
+        match waiting.pop_front() {
+            Some(task) => {
+                if let Some(task) = task.upgrade() {
+                    task.mark_suspended();
+                    TASK_SCHEDULER.put_read_task(task);
+                    count -= 1;
+                }
+            }
+            None => break,
+        }
+    }
+}
+
+/// The file which keeps a 64-bit counter for the event notification without byte streams.
+/// Each reading and writing must use an 8-byte buffer holding the counter value in native endian.
+/// The writing adds the value to the counter and the reading takes the counter,
+/// in the semaphore mode the reading only decreases the counter by one.
+pub(crate) struct EventFd {
+    /// Whether the reading decreases the counter by one instead of resetting it
+    semaphore: bool,
+    inner: UserPromiseRefCell<EventFdInner>,
+    /// The tasks which are polling the eventfd, they are woken up when the counter changes
+    pollers: PollWaitQueue,
+}
+impl EventFd {
+    /// Create a new eventfd
+    ///
+    /// - Arguments
+    ///     - initval: the initial value of the counter
+    ///     - semaphore: true if the reading decreases the counter by one
+    pub(crate) fn new(initval: u64, semaphore: bool) -> Self {
+        Self {
+            semaphore,
+            inner: unsafe {
+                UserPromiseRefCell::new(EventFdInner {
+                    counter: initval,
+                    readers: VecDeque::new(),
+                    writers: VecDeque::new(),
+                })
+            },
+            pollers: PollWaitQueue::new(),
+        }
+    }
+
+    /// Block the current task in the waiting queue
+    ///
+    /// - Arguments
+    ///     - readers: true if the task is waiting for reading
+    ///
+    /// - Errors
+    ///     - ProcessHaveNotTask
+    fn wait(&self, readers: bool) -> Result<()> {
+        let mut inner = self.inner.exclusive_access();
+        let current_task = PROCESSOR.current_task()?;
+        let waiting = if readers {
+            &mut inner.readers
+        } else {
+            &mut inner.writers
+        };
+        waiting.push_back(Arc::downgrade(&current_task));
+        drop(current_task);
+        drop(inner);
+        block_current_and_run_other_task()
+    }
+}
+impl File for EventFd {
+    /// Take the counter, the current task will be blocked while the counter is zero.
+    ///
+    /// - Errors
+    ///     - ProcessHaveNotTask
+    ///     - InvalidEventFdAccess(len)
+    ///     - EOB
+    fn read(&self, buffers: ByteBuffers) -> Result<u64> {
+        if buffers.len() < size_of::<u64>() {
+            return Err(KernelError::InvalidEventFdAccess(buffers.len()));
+        }
+        let value = loop {
+            let mut inner = self.inner.exclusive_access();
+            if inner.counter > 0 {
+                let value = if self.semaphore { 1 } else { inner.counter };
+                inner.counter -= value;
+                wake_many(&mut inner.writers, u64::MAX);
+                drop(inner);
+                self.pollers.wake_all();
+                break value;
+            }
+            drop(inner);
+            self.wait(true)?;
+        };
+        let mut iterator = buffers.into_iter();
+        for byte in value.to_ne_bytes() {
+            iterator.next_mut(byte)?;
+        }
+        Ok(size_of::<u64>() as u64)
+    }
+
+    /// Add the value to the counter,
+    /// the current task will be blocked while the counter would overflow.
+    /// The blocked readers will be woken up, one reader for each value in the semaphore mode.
+    ///
+    /// - Errors
+    ///     - ProcessHaveNotTask
+    ///     - InvalidEventFdAccess(len)
+    fn write(&self, buffers: ByteBuffers) -> Result<u64> {
+        if buffers.len() < size_of::<u64>() {
+            return Err(KernelError::InvalidEventFdAccess(buffers.len()));
+        }
+        let mut bytes = [0u8; size_of::<u64>()];
+        let mut iterator = buffers.into_iter();
+        for byte in bytes.iter_mut() {
+            *byte = iterator.next().unwrap();
+        }
+        let value = u64::from_ne_bytes(bytes);
+        if value > EVENTFD_MAX_VALUE {
+            return Err(KernelError::InvalidEventFdAccess(size_of::<u64>()));
+        }
+        loop {
+            let mut inner = self.inner.exclusive_access();
+            if inner.counter <= EVENTFD_MAX_VALUE - value {
+                inner.counter += value;
+                if value > 0 {
+                    let count = if self.semaphore { value } else { 1 };
+                    wake_many(&mut inner.readers, count);
+                    drop(inner);
+                    self.pollers.wake_all();
+                }
+                return Ok(size_of::<u64>() as u64);
+            }
+            drop(inner);
+            self.wait(false)?;
+        }
+    }
+
+    /// The eventfd is readable when the counter is not zero,
+    /// and writable when the counter is less than the max value
+    fn poll_ready(&self, events: PollEvents) -> bool {
+        let inner = self.inner.access();
+        (events.contains(PollEvents::IN) && inner.counter > 0)
+            || (events.contains(PollEvents::OUT) && inner.counter < EVENTFD_MAX_VALUE)
+    }
+
+    fn poll_wait_queue(&self) -> Option<&PollWaitQueue> {
+        Some(&self.pollers)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task::model::ForkedProcess;
+    use alloc::vec;
+
+    fn new_buffers(bytes: &mut [u8]) -> ByteBuffers {
+        let len = bytes.len();
+        let slice = unsafe { core::slice::from_raw_parts_mut(bytes.as_mut_ptr(), len) };
+        ByteBuffers::new(vec![slice], len)
+    }
+
+    #[test_case]
+    fn test_eventfd_read_and_write() {
+        let eventfd = EventFd::new(2, false);
+        let mut bytes = [0u8; 8];
+        assert!(eventfd
+            .read(new_buffers(&mut bytes[..4]))
+            .is_err_and(|e| e.is_invalideventfdaccess()));
+        assert!(eventfd.poll_ready(PollEvents::IN));
+        assert_eq!(eventfd.read(new_buffers(&mut bytes)).unwrap(), 8);
+        assert_eq!(u64::from_ne_bytes(bytes), 2);
+        assert!(!eventfd.poll_ready(PollEvents::IN));
+        let mut bytes = u64::MAX.to_ne_bytes();
+        assert!(eventfd
+            .write(new_buffers(&mut bytes))
+            .is_err_and(|e| e.is_invalideventfdaccess()));
+        // the reading only decreases the counter by one in the semaphore mode
+        let eventfd = EventFd::new(0, true);
+        let mut bytes = 3u64.to_ne_bytes();
+        assert_eq!(eventfd.write(new_buffers(&mut bytes)).unwrap(), 8);
+        assert_eq!(eventfd.read(new_buffers(&mut bytes)).unwrap(), 8);
+        assert_eq!(u64::from_ne_bytes(bytes), 1);
+        assert_eq!(eventfd.inner.access().counter, 2);
+    }
+
+    #[test_case]
+    fn test_eventfd_write_wakes_reader_once() {
+        let process = ForkedProcess::new();
+        let task = process.inner_access().root_task();
+        let eventfd = EventFd::new(0, false);
+        task.mark_blocked();
+        eventfd
+            .inner
+            .exclusive_access()
+            .readers
+            .push_back(Arc::downgrade(&task));
+        let mut bytes = 0u64.to_ne_bytes();
+        // the zero value does not wake up the reader
+        assert_eq!(eventfd.write(new_buffers(&mut bytes)).unwrap(), 8);
+        assert!(task.is_blocked());
+        let mut bytes = 1u64.to_ne_bytes();
+        assert_eq!(eventfd.write(new_buffers(&mut bytes)).unwrap(), 8);
+        assert!(!task.is_blocked());
+        assert!(TASK_SCHEDULER.fetch_specific(&task));
+        assert!(Arc::ptr_eq(&TASK_SCHEDULER.pop_ready_task().unwrap(), &task));
+        // the reader was already woken up, it is not put into the ready queue again
+        assert_eq!(eventfd.write(new_buffers(&mut bytes)).unwrap(), 8);
+        assert!(!TASK_SCHEDULER.fetch_specific(&task));
+        assert_eq!(eventfd.inner.access().counter, 2);
+    }
+}
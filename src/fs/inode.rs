@@ -4,26 +4,450 @@
 // self mods
 
 // use other mods
+use alloc::collections::BTreeMap;
 use alloc::string::{String, ToString};
 use alloc::sync::Arc;
 use alloc::vec::Vec;
+use core::any::Any;
+use core::mem::size_of;
+use frontier_fs::configs::BLOCK_BYTE_SIZE;
 use frontier_fs::vfs::{FileSystem, Inode};
 use frontier_fs::OpenFlags;
 use spin::Mutex;
 
 // use self mods
-use super::{File, ROOT_FS};
+use super::device::find_device;
+use super::procfs::find_proc;
+use super::{File, FS_LOCK, MOUNT_TABLE, ROOT_DEVICE_ID};
+use crate::configs;
 use crate::lang::buffer::ByteBuffers;
+use crate::lang::container::UserPromiseRefCell;
+use crate::lang::timer::{self, TimeVal};
+use crate::memory::frame::FRAME_ALLOCATOR;
 use crate::prelude::*;
+use crate::task::model::Credential;
 
 const PATH_SPLITER: &'static str = "/";
+const MOUNT_SPLITER: char = ':';
+/// The name of the hidden file in the root directory of each file system,
+/// which keeps the metadata records of all inodes in the order of the inode bitmap index
+const META_FILE_NAME: &'static str = ".inodes";
+
+/// Split the leading mount identifier from the path, the path like `1:/data/file` will be split into `(1, "/data/file")`.
+/// If the path have no mount identifier, None will be returned.
+///
+/// - Arguments
+///     - path: the path which may start with the mount identifier
+fn split_mount(path: &str) -> Option<(usize, &str)> {
+    let (device_id, rest) = path.split_once(MOUNT_SPLITER)?;
+    if device_id.is_empty() || !device_id.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    Some((device_id.parse().ok()?, rest))
+}
+
+/// The timestamps of the inode in microseconds of the realtime clock.
+/// The on-disk inode has no room for them, so they are only kept in memory
+/// and seeded with the current realtime when the inode is touched first time after booting.
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct InodeTimes {
+    /// The last time the data was read
+    atime_us: usize,
+    /// The last time the data was written
+    mtime_us: usize,
+    /// The last time the data or the timestamps were changed
+    ctime_us: usize,
+}
+impl InodeTimes {
+    /// Create new timestamps which are all the current moment
+    ///
+    /// - Arguments
+    ///     - now_us: the current realtime microseconds
+    fn new(now_us: usize) -> Self {
+        Self {
+            atime_us: now_us,
+            mtime_us: now_us,
+            ctime_us: now_us,
+        }
+    }
+
+    /// Update the access time after reading.
+    /// Like the relatime, the access time is only updated if it is not newer than the modification
+    /// time or it is older than [`configs::FS_ATIME_UPDATE_INTERVAL_US`],
+    /// so the frequent reading is cheap.
+    ///
+    /// - Arguments
+    ///     - now_us: the current realtime microseconds
+    ///
+    /// - Returns
+    ///     - true: the access time was updated
+    ///     - false: the access time is recent enough
+    fn access(&mut self, now_us: usize) -> bool {
+        if self.atime_us <= self.mtime_us
+            || self.atime_us.saturating_add(configs::FS_ATIME_UPDATE_INTERVAL_US) <= now_us
+        {
+            self.atime_us = now_us;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Update the modification and change time after writing
+    ///
+    /// - Arguments
+    ///     - now_us: the current realtime microseconds
+    fn modify(&mut self, now_us: usize) {
+        self.mtime_us = now_us;
+        self.ctime_us = now_us;
+    }
+}
+
+bitflags! {
+    /// The permission bits of the inode, which are grouped into the owner, group and other classes
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub(crate) struct InodeMode: u32 {
+        const OWNER_READ = 0o400;
+        const OWNER_WRITE = 0o200;
+        const OWNER_EXECUTE = 0o100;
+        const GROUP_READ = 0o040;
+        const GROUP_WRITE = 0o020;
+        const GROUP_EXECUTE = 0o010;
+        const OTHER_READ = 0o004;
+        const OTHER_WRITE = 0o002;
+        const OTHER_EXECUTE = 0o001;
+    }
+}
+impl InodeMode {
+    /// The bit offset of the owner class in the mode
+    const OWNER_SHIFT: u32 = 6;
+    /// The bit offset of the group class in the mode
+    const GROUP_SHIFT: u32 = 3;
+    /// The bit offset of the other class in the mode
+    const OTHER_SHIFT: u32 = 0;
+
+    /// Get the default mode of the inode which was never changed by chmod,
+    /// the flags of the inode grant the accesses to all classes except writing,
+    /// which is only granted to the owner. The directories can be searched by all classes.
+    ///
+    /// - Arguments
+    ///     - inode: the inode object return by file system
+    fn default_of(inode: &Inode) -> Self {
+        let flags = inode.flags();
+        let mut mode = Self::empty();
+        mode.set(
+            Self::OWNER_READ | Self::GROUP_READ | Self::OTHER_READ,
+            flags.is_readable(),
+        );
+        mode.set(Self::OWNER_WRITE, flags.is_writable());
+        mode.set(
+            Self::OWNER_EXECUTE | Self::GROUP_EXECUTE | Self::OTHER_EXECUTE,
+            flags.is_executable() || flags.is_dir(),
+        );
+        mode
+    }
+
+    /// Check if the access is permitted to the class
+    ///
+    /// - Arguments
+    ///     - access: the requested access
+    ///     - shift: the bit offset of the class in the mode
+    fn permits(&self, access: Access, shift: u32) -> bool {
+        let bits = (access as u32) << shift;
+        self.bits() & bits == bits
+    }
+}
+
+/// The access which is requested to the inode, as the permission bits of one class
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub(crate) enum Access {
+    Read = 0o4,
+    Write = 0o2,
+    Execute = 0o1,
+}
+
+/// The record of the inode metadata which is persisted in the metadata file of the file system,
+/// because the on-disk inode has no room for it.
+/// The record of the inode which metadata was never written is all zeros.
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+struct InodeRecord {
+    /// The kind of the record, see [`InodeRecord::KIND_NONE`]
+    kind: u32,
+    /// The permission bits
+    mode: u32,
+    /// The user id of the owner
+    uid: u32,
+    /// The group id of the owner
+    gid: u32,
+}
+impl InodeRecord {
+    /// The record was never written, the default metadata of the inode is used
+    const KIND_NONE: u32 = 0;
+    /// The record of the regular file or directory
+    const KIND_FILE: u32 = 1;
+    /// The record of the symbolic link, which data is the target path
+    const KIND_SYMLINK: u32 = 2;
+    /// The byte size of the record in the metadata file
+    const BYTE_SIZE: usize = 16;
+
+    /// Get the byte offset of the record in the metadata file
+    ///
+    /// - Arguments
+    ///     - index: the inode bitmap index
+    fn offset(index: u32) -> u64 {
+        index as u64 * Self::BYTE_SIZE as u64
+    }
+
+    /// Encode the record as the little endian words
+    fn to_bytes(&self) -> [u8; Self::BYTE_SIZE] {
+        let mut bytes = [0u8; Self::BYTE_SIZE];
+        let words = [self.kind, self.mode, self.uid, self.gid];
+        for (chunk, word) in bytes.chunks_exact_mut(size_of::<u32>()).zip(words) {
+            chunk.copy_from_slice(&word.to_le_bytes());
+        }
+        bytes
+    }
+
+    /// Decode the record from the little endian words
+    ///
+    /// - Arguments
+    ///     - bytes: the bytes read from the metadata file
+    fn from_bytes(bytes: &[u8; Self::BYTE_SIZE]) -> Self {
+        let mut words = bytes
+            .chunks_exact(size_of::<u32>())
+            .map(|chunk| u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]));
+        Self {
+            kind: words.next().unwrap(),
+            mode: words.next().unwrap(),
+            uid: words.next().unwrap(),
+            gid: words.next().unwrap(),
+        }
+    }
+
+    /// Load the record of the inode from the metadata file of the device,
+    /// the record which cannot be read is treated as never written.
+    ///
+    /// - Arguments
+    ///     - device_id: the id of the device which contains the inode
+    ///     - index: the inode bitmap index
+    fn load(device_id: usize, index: u32) -> Self {
+        let meta_inode = match META_INODES.get(&device_id) {
+            Some(meta_inode) => meta_inode,
+            None => return Self::default(),
+        };
+        let mut bytes = [0u8; Self::BYTE_SIZE];
+        match meta_inode.read_buffer(&mut bytes, Self::offset(index)) {
+            Ok(read_size) if read_size == Self::BYTE_SIZE => Self::from_bytes(&bytes),
+            Ok(_) => Self::default(),
+            Err(error) => {
+                warn!(
+                    "Load the metadata of inode {} failed cause: {}",
+                    index,
+                    KernelError::from(error)
+                );
+                Self::default()
+            }
+        }
+    }
+
+    /// Save the record of the inode into the metadata file of the device
+    ///
+    /// - Arguments
+    ///     - device_id: the id of the device which contains the inode
+    ///     - index: the inode bitmap index
+    ///
+    /// - Errors
+    ///     - FileSystemError
+    ///         - BitmapExhausted(start_block_id)
+    ///         - DataOutOfBounds
+    ///         - NoDroptableBlockCache
+    ///         - RawDeviceError(error code)
+    fn save(&self, device_id: usize, index: u32) -> Result<()> {
+        if let Some(meta_inode) = META_INODES.get(&device_id) {
+            meta_inode.write_buffer(&self.to_bytes(), Self::offset(index))?;
+        }
+        Ok(())
+    }
+}
+
+/// The metadata of the inode which is not kept by the on-disk inode,
+/// the permission bits and the owner are persisted as [`InodeRecord`],
+/// while the timestamps are only kept in memory.
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct InodeMeta {
+    times: InodeTimes,
+    mode: InodeMode,
+    /// The user id of the owner, the inodes on disk are owned by the privileged user
+    uid: usize,
+    /// The group id of the owner
+    gid: usize,
+    /// Whether the inode is a directory, which can always be searched by the privileged user
+    dir: bool,
+    /// Whether the inode is a symbolic link, the file system only knows it as a regular file
+    symlink: bool,
+}
+impl InodeMeta {
+    /// Create the metadata of the inode which is owned by the credential
+    ///
+    /// - Arguments
+    ///     - inode: the inode object return by file system
+    ///     - cred: the credential of the owner
+    ///     - now_us: the current realtime microseconds
+    fn new(inode: &Inode, cred: Credential, now_us: usize) -> Self {
+        Self {
+            times: InodeTimes::new(now_us),
+            mode: InodeMode::default_of(inode),
+            uid: cred.uid,
+            gid: cred.gid,
+            dir: inode.flags().is_dir(),
+            symlink: false,
+        }
+    }
+
+    /// Load the metadata of the inode from the persisted record,
+    /// the inode without record is owned by the privileged user.
+    ///
+    /// - Arguments
+    ///     - device_id: the id of the device which contains the inode
+    ///     - inode: the inode object return by file system
+    ///     - now_us: the current realtime microseconds
+    fn load(device_id: usize, inode: &Inode, now_us: usize) -> Self {
+        let mut meta = Self::new(inode, Credential::ROOT, now_us);
+        let record = InodeRecord::load(device_id, inode.inode_bitmap_index());
+        if record.kind != InodeRecord::KIND_NONE {
+            meta.mode = InodeMode::from_bits_truncate(record.mode);
+            meta.uid = record.uid as usize;
+            meta.gid = record.gid as usize;
+            meta.symlink = record.kind == InodeRecord::KIND_SYMLINK;
+        }
+        meta
+    }
+
+    /// Get the record of the metadata which will be persisted
+    fn record(&self) -> InodeRecord {
+        let kind = if self.symlink {
+            InodeRecord::KIND_SYMLINK
+        } else {
+            InodeRecord::KIND_FILE
+        };
+        InodeRecord {
+            kind,
+            mode: self.mode.bits(),
+            uid: self.uid as u32,
+            gid: self.gid as u32,
+        }
+    }
+
+    /// Check if the access is permitted to the credential.
+    /// The class of the credential is chosen by comparing the ids with the owner,
+    /// and the privileged user can always read and write and search the directories,
+    /// but can only execute the inode which is executable to any class.
+    ///
+    /// - Arguments
+    ///     - cred: the credential which requests the access
+    ///     - access: the requested access
+    fn permits(&self, cred: Credential, access: Access) -> bool {
+        if cred.is_privileged() {
+            return access != Access::Execute
+                || self.dir
+                || self.mode.intersects(
+                    InodeMode::OWNER_EXECUTE | InodeMode::GROUP_EXECUTE | InodeMode::OTHER_EXECUTE,
+                );
+        }
+        let shift = if cred.uid == self.uid {
+            InodeMode::OWNER_SHIFT
+        } else if cred.gid == self.gid {
+            InodeMode::GROUP_SHIFT
+        } else {
+            InodeMode::OTHER_SHIFT
+        };
+        self.mode.permits(access, shift)
+    }
+}
+
+/// The status of the file shared with the user space
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub(crate) struct Stat {
+    /// The id of the device which contains the file
+    pub(crate) dev: usize,
+    /// The inode bitmap index of the file in the device
+    pub(crate) ino: usize,
+    /// The permission bits of the file, see [`InodeMode`]
+    pub(crate) mode: u32,
+    /// The user id of the owner
+    pub(crate) uid: usize,
+    /// The group id of the owner
+    pub(crate) gid: usize,
+    /// The byte size of the file
+    pub(crate) size: usize,
+    /// The last access time
+    pub(crate) atime: TimeVal,
+    /// The last modification time
+    pub(crate) mtime: TimeVal,
+    /// The last status change time
+    pub(crate) ctime: TimeVal,
+}
 
 /// The inner struct for os inode, which contain the byte offsets have currently readed.
 pub(crate) struct OSInodeInner {
     offset: u64,
     inode: Arc<Inode>,
+    /// The end offset of the last read, used to detect sequential reading
+    last_read_end: Option<u64>,
+    /// The end offset of the data which have been prefetched into the block cache
+    readahead_end: u64,
 }
 impl OSInodeInner {
+    /// Create a new inner os inode which offset is zero
+    ///
+    /// - Arguments
+    ///     - inode: the inode object return by file system
+    fn new(inode: Arc<Inode>) -> Self {
+        Self {
+            offset: 0,
+            inode,
+            last_read_end: None,
+            readahead_end: 0,
+        }
+    }
+
+    /// Prefetch the next blocks after the current offset into the block cache of the file system.
+    /// Readahead only happens when the file is read sequentially,
+    /// random access will disable it so that we don't thrash the block cache,
+    /// and it will also be disabled when the free frames are below the low watermark.
+    /// Prefetching is non-essential, so the caller should ignore the error instead of failing.
+    ///
+    /// - Arguments
+    ///     - start: the offset where the current read started
+    ///
+    /// - Errors
+    ///     - DataOutOfBounds
+    ///     - NoDroptableBlockCache
+    ///     - RawDeviceError(error code)
+    fn readahead(&mut self, start: u64) -> Result<()> {
+        let sequential = self.last_read_end.is_some_and(|end| end == start);
+        self.last_read_end.replace(self.offset);
+        // prefetching is non-essential, so it will be skipped when the free frames are low
+        if !sequential || FRAME_ALLOCATOR.access().is_low() {
+            self.readahead_end = self.offset;
+            return Ok(());
+        }
+        let block_size = BLOCK_BYTE_SIZE as u64;
+        let target_end = self.offset + (configs::FS_READAHEAD_BLOCK_COUNT as u64) * block_size;
+        let mut prefetch_offset = self.readahead_end.max(self.offset);
+        let mut buffer = vec![0u8; BLOCK_BYTE_SIZE];
+        while prefetch_offset < target_end {
+            let read_size = self.inode.read_buffer(&mut buffer, prefetch_offset)?;
+            if read_size == 0 {
+                break;
+            }
+            prefetch_offset += read_size as u64;
+        }
+        self.readahead_end = prefetch_offset;
+        Ok(())
+    }
+
     /// Check the permissions of the file to see if the data is allowed to be read.
     ///
     /// - Errors
@@ -54,6 +478,21 @@ impl OSInodeInner {
         }
     }
 
+    /// Check the type of the file to see if the data is allowed to be truncated.
+    ///
+    /// - Errors
+    ///     - FileMustNotBeDirectory(inode bitmap index)
+    #[inline(always)]
+    fn must_not_be_directory(&self) -> Result<()> {
+        if self.inode.flags().is_dir() {
+            Err(KernelError::FileMustNotBeDirectory(
+                self.inode.inode_bitmap_index(),
+            ))
+        } else {
+            Ok(())
+        }
+    }
+
     /// Check the permissions of the file to see if the code is allowed to be execute.
     ///
     /// - Errors
@@ -72,21 +511,182 @@ impl OSInodeInner {
 }
 
 /// The Inode object for direct read/write by the operating system wraps the read-write inode and read-only permission identifiers
+/// Each operation takes the [`FS_LOCK`] before the spin lock of the inner inode,
+/// so the task can be blocked during the I/O without spinning the other tasks.
 pub(crate) struct OSInode {
-    #[allow(dead_code)]
+    /// The id of the device which contains the inode
+    device_id: usize,
     flags: OpenFlags,
+    /// The credential of the opener, which the following accesses are checked with
+    cred: Credential,
     inner: Mutex<OSInodeInner>,
 }
 impl OSInode {
     /// Create a new operation system inode obejct.
     ///
     /// - Arguments
+    ///     - device_id: the id of the device which contains the inode
     ///     - flags: the permission mode for the operation of the inode
+    ///     - cred: the credential of the opener
     ///     - inode: the inode object return by file system
-    pub(crate) fn new(flags: OpenFlags, inode: Arc<Inode>) -> Self {
+    pub(crate) fn new(
+        device_id: usize,
+        flags: OpenFlags,
+        cred: Credential,
+        inode: Arc<Inode>,
+    ) -> Self {
         Self {
+            device_id,
             flags,
-            inner: Mutex::new(OSInodeInner { offset: 0, inode }),
+            cred,
+            inner: Mutex::new(OSInodeInner::new(inode)),
+        }
+    }
+
+    /// Update the metadata of the inode, it will be loaded if the inode was never touched.
+    ///
+    /// - Arguments
+    ///     - inode: the inode object return by file system
+    ///     - f: the function which updates the metadata with the current realtime
+    fn update_meta<T>(&self, inode: &Inode, f: impl FnOnce(&mut InodeMeta, usize) -> T) -> T {
+        let now_us = timer::realtime_us();
+        let mut table = INODE_METAS.exclusive_access();
+        let meta = table
+            .entry((self.device_id, inode.inode_bitmap_index()))
+            .or_insert_with(|| InodeMeta::load(self.device_id, inode, now_us));
+        f(meta, now_us)
+    }
+
+    /// Reset the metadata of the newly created inode, which is owned by the credential.
+    /// The stale metadata of the deleted inode at the same bitmap index will be replaced.
+    ///
+    /// - Arguments
+    ///     - inode: the newly created inode
+    ///     - cred: the credential of the creator
+    ///     - symlink: whether the inode is a symbolic link
+    ///
+    /// - Errors
+    ///     - FileSystemError
+    ///         - BitmapExhausted(start_block_id)
+    ///         - DataOutOfBounds
+    ///         - NoDroptableBlockCache
+    ///         - RawDeviceError(error code)
+    fn init_meta(&self, inode: &Inode, cred: Credential, symlink: bool) -> Result<()> {
+        let index = inode.inode_bitmap_index();
+        let mut meta = InodeMeta::new(inode, cred, timer::realtime_us());
+        meta.symlink = symlink;
+        meta.record().save(self.device_id, index)?;
+        INODE_METAS
+            .exclusive_access()
+            .insert((self.device_id, index), meta);
+        Ok(())
+    }
+
+    /// Check the mode and the owner to see if the access is permitted to the credential.
+    ///
+    /// - Arguments
+    ///     - inner: the locked inner os inode
+    ///     - cred: the credential which requests the access
+    ///     - access: the requested access
+    ///
+    /// - Errors
+    ///     - FilePermissionDenied(inode bitmap index)
+    fn must_be_permitted(
+        &self,
+        inner: &OSInodeInner,
+        cred: Credential,
+        access: Access,
+    ) -> Result<()> {
+        if self.update_meta(&inner.inode, |meta, _| meta.permits(cred, access)) {
+            Ok(())
+        } else {
+            Err(KernelError::FilePermissionDenied(
+                inner.inode.inode_bitmap_index(),
+            ))
+        }
+    }
+
+    /// Check if the accesses requested by the open flags are permitted to the opener.
+    ///
+    /// - Arguments
+    ///     - flags: the flags which the os inode was opened with
+    ///
+    /// - Errors
+    ///     - FilePermissionDenied(inode bitmap index)
+    fn must_be_openable(&self, flags: OpenFlags) -> Result<()> {
+        let _guard = FS_LOCK.lock();
+        let inner = self.inner.lock();
+        if flags.contains(OpenFlags::READ) {
+            self.must_be_permitted(&inner, self.cred, Access::Read)?;
+        }
+        if flags.contains(OpenFlags::WRITE) {
+            self.must_be_permitted(&inner, self.cred, Access::Write)?;
+        }
+        Ok(())
+    }
+
+    /// Get the status of the file
+    pub(crate) fn stat(&self) -> Stat {
+        let _guard = FS_LOCK.lock();
+        let inner = self.inner.lock();
+        let meta = self.update_meta(&inner.inode, |meta, _| *meta);
+        Stat {
+            dev: self.device_id,
+            ino: inner.inode.inode_bitmap_index() as usize,
+            mode: meta.mode.bits(),
+            uid: meta.uid,
+            gid: meta.gid,
+            size: inner.inode.size() as usize,
+            atime: TimeVal::from_us(meta.times.atime_us),
+            mtime: TimeVal::from_us(meta.times.mtime_us),
+            ctime: TimeVal::from_us(meta.times.ctime_us),
+        }
+    }
+
+    /// Set the access and modification time explicitly, the change time will be the current moment.
+    ///
+    /// - Arguments
+    ///     - atime: the new access time, None means the current moment
+    ///     - mtime: the new modification time, None means the current moment
+    pub(crate) fn set_times(&self, atime: Option<TimeVal>, mtime: Option<TimeVal>) {
+        let _guard = FS_LOCK.lock();
+        let inner = self.inner.lock();
+        self.update_meta(&inner.inode, |meta, now_us| {
+            meta.times.atime_us = atime.map_or(now_us, |atime| atime.as_us());
+            meta.times.mtime_us = mtime.map_or(now_us, |mtime| mtime.as_us());
+            meta.times.ctime_us = now_us;
+        });
+    }
+
+    /// Change the permission bits of the inode, the change time will be the current moment.
+    /// Only the owner and the privileged user can change the permission bits,
+    /// and the new permission bits are persisted in the metadata file.
+    ///
+    /// - Arguments
+    ///     - mode: the new permission bits
+    ///
+    /// - Errors
+    ///     - FilePermissionDenied(inode bitmap index)
+    ///     - FileSystemError
+    ///         - BitmapExhausted(start_block_id)
+    ///         - DataOutOfBounds
+    ///         - NoDroptableBlockCache
+    ///         - RawDeviceError(error code)
+    pub(crate) fn set_mode(&self, mode: InodeMode) -> Result<()> {
+        let _guard = FS_LOCK.lock();
+        let inner = self.inner.lock();
+        let index = inner.inode.inode_bitmap_index();
+        let record = self.update_meta(&inner.inode, |meta, now_us| {
+            if !self.cred.is_privileged() && self.cred.uid != meta.uid {
+                return None;
+            }
+            meta.mode = mode;
+            meta.times.ctime_us = now_us;
+            Some(meta.record())
+        });
+        match record {
+            Some(record) => record.save(self.device_id, index),
+            None => Err(KernelError::FilePermissionDenied(index)),
         }
     }
 
@@ -99,11 +699,14 @@ impl OSInode {
     ///         - NoDroptableBlockCache
     ///         - RawDeviceError(error code)
     ///     - FileMustBeReadable(inode bitmap index)
+    ///     - FilePermissionDenied(inode bitmap index)
     /// )
     #[allow(dead_code)]
     fn ls(&self) -> Result<Vec<String>> {
+        let _guard = FS_LOCK.lock();
         let inner = self.inner.lock();
         inner.must_be_readable()?;
+        self.must_be_permitted(&inner, self.cred, Access::Read)?;
         Ok(inner.inode.list_child_names()?)
     }
 
@@ -112,6 +715,7 @@ impl OSInode {
     /// - Arguments
     ///     - name: the name of child os inode
     ///     - flags: the permission mode for the operation of the inode
+    ///     - cred: the credential of the opener, the created child will be owned by it
     ///
     /// - Errors
     ///     - FileSystemError
@@ -124,27 +728,26 @@ impl OSInode {
     ///         - BitmapIndexDeallocated(bitmap_index)
     ///         - RawDeviceError(error code)
     ///     - FileMustBeReadable(inode bitmap index)
+    ///     - FilePermissionDenied(inode bitmap index)
     ///     - FileDoesNotExists(name)
-    fn get_child(&self, name: &str, flags: OpenFlags) -> Result<Arc<OSInode>> {
+    fn get_child(&self, name: &str, flags: OpenFlags, cred: Credential) -> Result<Arc<OSInode>> {
+        let _guard = FS_LOCK.lock();
         let inner = self.inner.lock();
         inner.must_be_readable()?;
+        // looking up the child only searches the directory without listing it
+        self.must_be_permitted(&inner, cred, Access::Execute)?;
         if let Some(child_inode) = inner.inode.get_child_inode(name)? {
-            Ok(Arc::new(OSInode {
-                flags,
-                inner: Mutex::new(OSInodeInner {
-                    offset: 0,
-                    inode: Arc::new(child_inode),
-                }),
-            }))
+            let child = OSInode::new(self.device_id, flags, cred, Arc::new(child_inode));
+            // the permissions of the link are ignored, the target will be checked when following
+            if !child.is_symlink() {
+                child.must_be_openable(flags)?;
+            }
+            Ok(Arc::new(child))
         } else if flags.is_create() {
+            self.must_be_permitted(&inner, cred, Access::Write)?;
             let child_inode = inner.inode.create_child_inode(name, flags.into())?;
-            Ok(Arc::new(OSInode {
-                flags,
-                inner: Mutex::new(OSInodeInner {
-                    offset: 0,
-                    inode: Arc::new(child_inode),
-                }),
-            }))
+            self.init_meta(&child_inode, cred, false)?;
+            Ok(Arc::new(OSInode::new(self.device_id, flags, cred, Arc::new(child_inode))))
         } else {
             Err(KernelError::FileDoesNotExists(name.to_string()))
         }
@@ -155,6 +758,7 @@ impl OSInode {
     /// - Arguments
     ///     - name: the name of child os inode
     ///     - flags: the permission mode for the operation of the inode
+    ///     - cred: the credential of the creator, the child will be owned by it
     ///
     /// - Errors
     ///     - FileSystemError
@@ -166,18 +770,73 @@ impl OSInode {
     ///         - NoDroptableBlockCache
     ///         - RawDeviceError(error code)
     ///     - FileMustBeWritable(inode bitmap index)
+    ///     - FilePermissionDenied(inode bitmap index)
     #[allow(dead_code)]
-    fn create_child(&self, name: &str, flags: OpenFlags) -> Result<Arc<OSInode>> {
+    fn create_child(&self, name: &str, flags: OpenFlags, cred: Credential) -> Result<Arc<OSInode>> {
+        let _guard = FS_LOCK.lock();
         let inner = self.inner.lock();
         inner.must_be_writable()?;
+        self.must_be_permitted(&inner, cred, Access::Write)?;
         let child_inode = inner.inode.create_child_inode(name, flags.into())?;
-        Ok(Arc::new(OSInode {
-            flags,
-            inner: Mutex::new(OSInodeInner {
-                offset: 0,
-                inode: Arc::new(child_inode),
-            }),
-        }))
+        self.init_meta(&child_inode, cred, false)?;
+        Ok(Arc::new(OSInode::new(self.device_id, flags, cred, Arc::new(child_inode))))
+    }
+
+    /// Create a symbolic link as child into current inode, the target path is kept as the data.
+    /// The link is a regular file for the file system, and it is marked as link by the metadata.
+    ///
+    /// - Arguments
+    ///     - name: the name of the link
+    ///     - target: the path which the link refers
+    ///     - cred: the credential of the creator, the link will be owned by it
+    ///
+    /// - Errors
+    ///     - FileSystemError
+    ///         - InodeMustBeDirectory(bitmap index)
+    ///         - DuplicatedFname(name, inode bitmap index)
+    ///         - BitmapExhausted(start_block_id)
+    ///         - DataOutOfBounds
+    ///         - NoDroptableBlockCache
+    ///         - RawDeviceError(error code)
+    ///     - FileMustBeWritable(inode bitmap index)
+    ///     - FilePermissionDenied(inode bitmap index)
+    fn create_symlink(&self, name: &str, target: &str, cred: Credential) -> Result<Arc<OSInode>> {
+        let _guard = FS_LOCK.lock();
+        let inner = self.inner.lock();
+        inner.must_be_writable()?;
+        self.must_be_permitted(&inner, cred, Access::Write)?;
+        let link_inode = inner
+            .inode
+            .create_child_inode(name, (OpenFlags::READ | OpenFlags::WRITE).into())?;
+        link_inode.write_buffer(target.as_bytes(), 0)?;
+        self.init_meta(&link_inode, cred, true)?;
+        self.update_meta(&inner.inode, |meta, now_us| meta.times.modify(now_us));
+        Ok(Arc::new(OSInode::new(
+            self.device_id,
+            OpenFlags::READ,
+            cred,
+            Arc::new(link_inode),
+        )))
+    }
+
+    /// Check if the inode is a symbolic link
+    pub(crate) fn is_symlink(&self) -> bool {
+        let _guard = FS_LOCK.lock();
+        let inner = self.inner.lock();
+        self.update_meta(&inner.inode, |meta, _| meta.symlink)
+    }
+
+    /// Read the target path of the symbolic link without following it
+    ///
+    /// - Errors
+    ///     - FileSystemError
+    ///         - DataOutOfBounds
+    ///         - NoDroptableBlockCache
+    ///         - RawDeviceError(error code)
+    pub(crate) fn read_link(&self) -> Result<String> {
+        let _guard = FS_LOCK.lock();
+        let bytes = self.inner.lock().inode.read_all()?;
+        Ok(String::from_utf8_lossy(&bytes).into_owned())
     }
 
     /// Remove child os inode from current os inode
@@ -195,11 +854,23 @@ impl OSInode {
     ///         - NoDroptableBlockCache
     ///         - RawDeviceError(error code)
     ///     - FileMustBeWritable(inode bitmap index)
+    ///     - FilePermissionDenied(inode bitmap index)
+    ///     - FileDoesNotExists(name)
     #[allow(dead_code)]
-    fn remove_child(&self, name: &str) -> Result<()> {
+    pub(crate) fn remove_child(&self, name: &str) -> Result<()> {
+        let _guard = FS_LOCK.lock();
         let inner = self.inner.lock();
         inner.must_be_writable()?;
+        self.must_be_permitted(&inner, self.cred, Access::Write)?;
+        let index = match inner.inode.get_child_inode(name)? {
+            Some(child_inode) => child_inode.inode_bitmap_index(),
+            None => return Err(KernelError::FileDoesNotExists(name.to_string())),
+        };
         inner.inode.remove_child_inode(name)?;
+        // the metadata of the freed inode must not be kept or inherited by the next inode
+        INODE_METAS.exclusive_access().remove(&(self.device_id, index));
+        InodeRecord::default().save(self.device_id, index)?;
+        self.update_meta(&inner.inode, |meta, now_us| meta.times.modify(now_us));
         Ok(())
     }
 
@@ -211,9 +882,12 @@ impl OSInode {
     ///         - NoDroptableBlockCache
     ///         - RawDeviceError(error code)
     ///     - FileMustBeReadable(bitmap index)
+    ///     - FilePermissionDenied(inode bitmap index)
     pub(crate) fn read_all(&self) -> Result<Vec<u8>> {
+        let _guard = FS_LOCK.lock();
         let inner = self.inner.lock();
         inner.must_be_readable()?;
+        self.must_be_permitted(&inner, self.cred, Access::Read)?;
         Ok(inner.inode.read_all()?)
     }
 }
@@ -222,12 +896,16 @@ impl File for OSInode {
     ///
     /// - Errors
     ///     - FileMustBeReadable(inode bitmap index)
+    ///     - FilePermissionDenied(inode bitmap index)
     ///     - DataOutOfBounds
     ///     - NoDroptableBlockCache
     ///     - RawDeviceError(error code)
     fn read(&self, buffers: ByteBuffers) -> Result<u64> {
+        let _guard = FS_LOCK.lock();
         let mut inner = self.inner.lock();
         inner.must_be_readable()?;
+        self.must_be_permitted(&inner, self.cred, Access::Read)?;
+        let start = inner.offset;
         let mut total_read_size = 0u64;
         for slice in buffers.into_slices() {
             let read_size = inner.inode.read_buffer(slice, inner.offset)?;
@@ -237,6 +915,13 @@ impl File for OSInode {
             inner.offset += read_size as u64;
             total_read_size += read_size as u64;
         }
+        if total_read_size != 0 {
+            // the data was already read, so the failure of prefetching must not fail the reading
+            if let Err(error) = inner.readahead(start) {
+                warn!("Readahead failed cause: {}", error);
+            }
+            self.update_meta(&inner.inode, |meta, now_us| meta.times.access(now_us));
+        }
         Ok(total_read_size)
     }
 
@@ -244,12 +929,20 @@ impl File for OSInode {
     ///
     /// - Errors
     ///     - FileMustBeWritable(inode bitmap index)
+    ///     - FilePermissionDenied(inode bitmap index)
     ///     - DataOutOfBounds
     ///     - NoDroptableBlockCache
     ///     - RawDeviceError(error code)
     fn write(&self, buffers: ByteBuffers) -> Result<u64> {
+        let _guard = FS_LOCK.lock();
         let mut inner = self.inner.lock();
         inner.must_be_writable()?;
+        self.must_be_permitted(&inner, self.cred, Access::Write)?;
+        // the other writers may have their own offsets, so the end of the file must be fetched before each writing.
+        // The kernel will never be preempted, so no other writer can extend the file between fetching and writing
+        if self.flags.contains(OpenFlags::APPEND) {
+            inner.offset = inner.inode.size() as u64;
+        }
         let mut total_write_size = 0u64;
         for slice in buffers.into_slices() {
             let write_size = inner.inode.write_buffer(slice, inner.offset)?;
@@ -257,23 +950,222 @@ impl File for OSInode {
             inner.offset += write_size as u64;
             total_write_size += write_size as u64;
         }
+        // writing breaks the sequential reading and may change the prefetched blocks
+        inner.last_read_end.take();
+        inner.readahead_end = inner.offset;
+        if total_write_size != 0 {
+            self.update_meta(&inner.inode, |meta, now_us| meta.times.modify(now_us));
+        }
+        Ok(total_write_size)
+    }
+
+    /// Read at the explicit offset, neither the offset nor the readahead state will be changed,
+    /// so that the sequential reader of the same os inode will not be disturbed.
+    ///
+    /// See [`crate::fs::File`]
+    ///
+    /// - Errors
+    ///     - FileMustBeReadable(inode bitmap index)
+    ///     - FilePermissionDenied(inode bitmap index)
+    ///     - DataOutOfBounds
+    ///     - NoDroptableBlockCache
+    ///     - RawDeviceError(error code)
+    fn read_at(&self, buffers: ByteBuffers, offset: u64) -> Result<u64> {
+        let _guard = FS_LOCK.lock();
+        let inner = self.inner.lock();
+        inner.must_be_readable()?;
+        self.must_be_permitted(&inner, self.cred, Access::Read)?;
+        let mut total_read_size = 0u64;
+        for slice in buffers.into_slices() {
+            let read_size = inner.inode.read_buffer(slice, offset + total_read_size)?;
+            if read_size == 0 {
+                break;
+            }
+            total_read_size += read_size as u64;
+        }
+        if total_read_size != 0 {
+            self.update_meta(&inner.inode, |meta, now_us| meta.times.access(now_us));
+        }
+        Ok(total_read_size)
+    }
+
+    /// Write at the explicit offset even if the os inode was opened with append flag, the offset will not be changed.
+    /// The prefetched blocks are still valid because they are shared with the block cache.
+    ///
+    /// See [`crate::fs::File`]
+    ///
+    /// - Errors
+    ///     - FileMustBeWritable(inode bitmap index)
+    ///     - FilePermissionDenied(inode bitmap index)
+    ///     - DataOutOfBounds
+    ///     - NoDroptableBlockCache
+    ///     - RawDeviceError(error code)
+    fn write_at(&self, buffers: ByteBuffers, offset: u64) -> Result<u64> {
+        let _guard = FS_LOCK.lock();
+        let inner = self.inner.lock();
+        inner.must_be_writable()?;
+        self.must_be_permitted(&inner, self.cred, Access::Write)?;
+        let mut total_write_size = 0u64;
+        for slice in buffers.into_slices() {
+            let write_size = inner.inode.write_buffer(slice, offset + total_write_size)?;
+            assert_eq!(write_size, slice.len());
+            total_write_size += write_size as u64;
+        }
+        if total_write_size != 0 {
+            self.update_meta(&inner.inode, |meta, now_us| meta.times.modify(now_us));
+        }
         Ok(total_write_size)
     }
+
+    /// Grow or shrink the data of current os inode to exactly the length, the offset will not be changed.
+    /// The grown region will be read as zeros, the file system decides whether the blocks are allocated for it,
+    /// and the blocks beyond the new length will be freed by the file system.
+    ///
+    /// See [`crate::fs::File`]
+    ///
+    /// - Errors
+    ///     - FileSystemError
+    ///         - BitmapExhausted(start_block_id)
+    ///         - BitmapIndexDeallocated(bitmap_index)
+    ///         - DataOutOfBounds
+    ///         - NoDroptableBlockCache
+    ///         - RawDeviceError(error code)
+    ///     - FileMustBeWritable(bitmap index)
+    ///     - FilePermissionDenied(inode bitmap index)
+    ///     - FileMustNotBeDirectory(bitmap index)
+    fn truncate(&self, len: u64) -> Result<()> {
+        let _guard = FS_LOCK.lock();
+        let mut inner = self.inner.lock();
+        inner.must_be_writable()?;
+        self.must_be_permitted(&inner, self.cred, Access::Write)?;
+        inner.must_not_be_directory()?;
+        inner.inode.truncate(len)?;
+        // the prefetched blocks beyond the new length are no longer part of the file
+        inner.last_read_end.take();
+        inner.readahead_end = inner.readahead_end.min(len);
+        self.update_meta(&inner.inode, |meta, now_us| meta.times.modify(now_us));
+        Ok(())
+    }
+
+    /// Flush the dirty block caches of the file system which contains the inode into the device.
+    /// The block cache cannot be flushed by the inode, so the dirty blocks of the other files
+    /// in the same device are flushed together, while the other devices are not touched.
+    ///
+    /// See [`crate::fs::File`]
+    ///
+    /// - Errors
+    ///     - FileSystemError
+    ///         - RawDeviceError(error code)
+    ///     - DeviceNotMounted(device id)
+    fn sync(&self) -> Result<()> {
+        let _guard = FS_LOCK.lock();
+        let fs = MOUNT_TABLE
+            .get(&self.device_id)
+            .ok_or(KernelError::DeviceNotMounted(self.device_id))?;
+        fs.sync()?;
+        Ok(())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
 }
 
 lazy_static! {
-    /// The static root os inode read from file system
-    pub(crate) static ref ROOT_INODE: Arc<OSInode> = {
-        let root_inode = Arc::new(ROOT_FS.root_inode());
-        Arc::new(OSInode::new(OpenFlags::RWDIR, root_inode))
-    };
+    /// The metadata of the inodes which were touched since booting,
+    /// the device id and the inode bitmap index as key
+    static ref INODE_METAS: UserPromiseRefCell<BTreeMap<(usize, u32), InodeMeta>> =
+        unsafe { UserPromiseRefCell::new(BTreeMap::new()) };
+
+    /// The metadata files of all mounted file systems, the device id as key.
+    /// The metadata file will be created in the root directory if it does not exist.
+    static ref META_INODES: BTreeMap<usize, Arc<Inode>> = MOUNT_TABLE
+        .iter()
+        .map(|(device_id, fs)| {
+            let root_inode = fs.root_inode();
+            let meta_inode = match root_inode.get_child_inode(META_FILE_NAME).unwrap() {
+                Some(meta_inode) => meta_inode,
+                None => root_inode
+                    .create_child_inode(META_FILE_NAME, (OpenFlags::READ | OpenFlags::WRITE).into())
+                    .unwrap(),
+            };
+            (*device_id, Arc::new(meta_inode))
+        })
+        .collect();
+
+    /// The root os inodes of all mounted file systems, the device id as key
+    pub(crate) static ref MOUNTED_INODES: BTreeMap<usize, Arc<OSInode>> = MOUNT_TABLE
+        .iter()
+        .map(|(device_id, fs)| {
+            let root_inode = Arc::new(fs.root_inode());
+            let root = OSInode::new(*device_id, OpenFlags::RWDIR, Credential::ROOT, root_inode);
+            (*device_id, Arc::new(root))
+        })
+        .collect();
+
+    /// The static root os inode read from root file system
+    pub(crate) static ref ROOT_INODE: Arc<OSInode> =
+        Arc::clone(MOUNTED_INODES.get(&ROOT_DEVICE_ID).unwrap());
 }
 impl ROOT_INODE {
+    /// Find the file by the path, the virtual files like the devices and the process files
+    /// are looked up first, the other files are resolved like [`ROOT_INODE::find_inode`].
+    ///
+    /// - Arguments
+    ///     - path: the path of the file, split by "/"
+    ///     - flags: once the file is found, the flags that affects subsequent behavior
+    ///     - cred: the credential which the accesses of the resolution are checked with
+    ///
+    /// - Errors
+    ///     - See [`ROOT_INODE::find_inode`]
+    pub(crate) fn find(
+        &self,
+        path: &str,
+        flags: OpenFlags,
+        cred: Credential,
+    ) -> Result<Arc<dyn File>> {
+        match find_virtual(path) {
+            Some(file) => Ok(file),
+            None => self
+                .find_inode(path, flags, cred)
+                .map(|inode| inode as Arc<dyn File>),
+        }
+    }
+
+    /// Find the file like [`ROOT_INODE::find`], but the last symbolic link is not followed.
+    ///
+    /// - Arguments
+    ///     - path: the path of the file, split by "/"
+    ///     - flags: once the file is found, the flags that affects subsequent behavior
+    ///     - cred: the credential which the accesses of the resolution are checked with
+    ///
+    /// - Errors
+    ///     - See [`ROOT_INODE::find_inode`]
+    pub(crate) fn find_link(
+        &self,
+        path: &str,
+        flags: OpenFlags,
+        cred: Credential,
+    ) -> Result<Arc<dyn File>> {
+        match find_virtual(path) {
+            Some(file) => Ok(file),
+            None => self
+                .find_inode_link(path, flags, cred)
+                .map(|inode| inode as Arc<dyn File>),
+        }
+    }
+
     /// Find the os inode in the file system by the path, and the path is split by "/".
+    /// The virtual files are not looked up, see [`ROOT_INODE::find`].
+    /// If the path starts with a mount identifier like `1:`, the file system of the device will be used,
+    /// otherwise the root file system will be used.
+    /// The symbolic links are followed during the resolution, see [`ROOT_INODE::find_inode_link`]
+    /// for returning the last link itself.
     ///
     /// - Arguments
     ///     - path: the path of the os inode, split by "/"
     ///     - flags: once the os inode is found, the flags that affects subsequent behavior
+    ///     - cred: the credential which the accesses of the resolution are checked with
     ///
     /// - Errors
     ///     - FileSystemError
@@ -286,26 +1178,385 @@ impl ROOT_INODE {
     ///         - BitmapIndexDeallocated(bitmap_index)
     ///         - RawDeviceError(error code)
     ///     - FileMustBeReadable(bitmap index)
+    ///     - FilePermissionDenied(inode bitmap index)
     ///     - FileDoesNotExists(name)
-    pub(crate) fn find(&self, path: &str, flags: OpenFlags) -> Result<Arc<OSInode>> {
-        let mut parent: Arc<OSInode> = Arc::clone(self);
-        let names: Vec<&str> = path.split(PATH_SPLITER).collect();
-        let last = names.len() - 1;
-        let mut first = true;
-        for (index, name) in names.iter().enumerate() {
-            if first {
-                first = false;
-                if name.is_empty() {
-                    continue;
-                }
+    ///     - DeviceNotMounted(device id)
+    ///     - SymlinkLoop(path)
+    pub(crate) fn find_inode(
+        &self,
+        path: &str,
+        flags: OpenFlags,
+        cred: Credential,
+    ) -> Result<Arc<OSInode>> {
+        self.resolve(path, flags, cred, true)
+    }
+
+    /// Find the os inode like [`ROOT_INODE::find_inode`],
+    /// but the last symbolic link is not followed,
+    /// so that the link itself will be returned.
+    ///
+    /// - Arguments
+    ///     - path: the path of the os inode, split by "/"
+    ///     - flags: once the os inode is found, the flags that affects subsequent behavior
+    ///     - cred: the credential which the accesses of the resolution are checked with
+    ///
+    /// - Errors
+    ///     - See [`ROOT_INODE::find_inode`]
+    pub(crate) fn find_inode_link(
+        &self,
+        path: &str,
+        flags: OpenFlags,
+        cred: Credential,
+    ) -> Result<Arc<OSInode>> {
+        self.resolve(path, flags, cred, false)
+    }
+
+    /// Resolve the path name by name and follow the symbolic links,
+    /// at most [`configs::MAX_SYMLINK_HOPS`] links can be followed.
+    ///
+    /// - Arguments
+    ///     - path: the path of the os inode, split by "/"
+    ///     - flags: once the os inode is found, the flags that affects subsequent behavior
+    ///     - cred: the credential which the accesses of the resolution are checked with
+    ///     - follow: whether the last symbolic link will be followed
+    ///
+    /// - Errors
+    ///     - See [`ROOT_INODE::find_inode`]
+    fn resolve(
+        &self,
+        path: &str,
+        flags: OpenFlags,
+        cred: Credential,
+        follow: bool,
+    ) -> Result<Arc<OSInode>> {
+        let (mut parent, rest) = self.split_root(path)?;
+        // the names which were not resolved, in the reversed order
+        let mut names = Vec::new();
+        push_names(&mut names, rest);
+        let mut hops = 0;
+        while let Some(name) = names.pop() {
+            let last = names.is_empty();
+            let flags = if last { flags } else { OpenFlags::RDIR };
+            let child = parent.get_child(&name, flags, cred)?;
+            if !child.is_symlink() || (last && !follow) {
+                parent = child;
+                continue;
+            }
+            hops += 1;
+            if hops > configs::MAX_SYMLINK_HOPS {
+                return Err(KernelError::SymlinkLoop(path.to_string()));
             }
-            let flags = if index == last {
-                flags
+            // the absolute target is resolved from the root, the relative one from the parent
+            let target = child.read_link()?;
+            if target.starts_with(PATH_SPLITER) || split_mount(&target).is_some() {
+                let (root, rest) = self.split_root(&target)?;
+                parent = root;
+                push_names(&mut names, rest);
             } else {
-                OpenFlags::RDIR
-            };
-            parent = parent.get_child(name, flags)?;
+                push_names(&mut names, &target);
+            }
         }
         Ok(parent)
     }
+
+    /// Split the path into the root os inode where the resolution starts and the rest path.
+    ///
+    /// - Arguments
+    ///     - path: the path which may start with the mount identifier
+    ///
+    /// - Errors
+    ///     - DeviceNotMounted(device id)
+    fn split_root<'a>(&self, path: &'a str) -> Result<(Arc<OSInode>, &'a str)> {
+        if let Some((device_id, path)) = split_mount(path) {
+            let root = MOUNTED_INODES
+                .get(&device_id)
+                .ok_or(KernelError::DeviceNotMounted(device_id))?;
+            Ok((Arc::clone(root), path))
+        } else {
+            Ok((Arc::clone(self), path))
+        }
+    }
+
+    /// Create a symbolic link which refers the target path, the target does not need to exist.
+    ///
+    /// - Arguments
+    ///     - target: the path which the link refers
+    ///     - linkpath: the path of the link
+    ///     - cred: the credential of the creator, the link will be owned by it
+    ///
+    /// - Errors
+    ///     - FileSystemError
+    ///         - InodeMustBeDirectory(bitmap index)
+    ///         - DuplicatedFname(name, inode bitmap index)
+    ///         - BitmapExhausted(start_block_id)
+    ///         - DataOutOfBounds
+    ///         - NoDroptableBlockCache
+    ///         - RawDeviceError(error code)
+    ///     - FileMustBeReadable(bitmap index)
+    ///     - FileMustBeWritable(bitmap index)
+    ///     - FilePermissionDenied(inode bitmap index)
+    ///     - FileDoesNotExists(name)
+    ///     - DeviceNotMounted(device id)
+    ///     - SymlinkLoop(path)
+    pub(crate) fn symlink(
+        &self,
+        target: &str,
+        linkpath: &str,
+        cred: Credential,
+    ) -> Result<Arc<OSInode>> {
+        let (parent, name) = match linkpath.rsplit_once(PATH_SPLITER) {
+            Some((parent, name)) => (self.find_inode(parent, OpenFlags::RDIR, cred)?, name),
+            None => (Arc::clone(self), linkpath),
+        };
+        parent.create_symlink(name, target, cred)
+    }
+}
+
+/// Find the virtual file which is not backed by the file system, such as the device files
+/// and the process files, None will be returned if the path is not a virtual file.
+///
+/// - Arguments
+///     - path: the path of the virtual file
+fn find_virtual(path: &str) -> Option<Arc<dyn File>> {
+    find_device(path).or_else(|| find_proc(path))
+}
+
+/// Split the path by "/" and push the names into the stack in the reversed order,
+/// so that the first name will be popped first.
+/// The leading empty name of the absolute path is skipped.
+///
+/// - Arguments
+///     - names: the stack of the names which were not resolved
+///     - path: the path to split
+fn push_names(names: &mut Vec<String>, path: &str) {
+    let mut splited: Vec<&str> = path.split(PATH_SPLITER).collect();
+    if splited.first().is_some_and(|name| name.is_empty()) {
+        splited.remove(0);
+    }
+    names.extend(splited.into_iter().rev().map(String::from));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::device::NULL_PATH;
+    use crate::task::model::{INIT_PROC, ROOT_GID, ROOT_UID, USER_GID, USER_UID};
+    use alloc::format;
+
+    fn new_buffers(bytes: &mut [u8]) -> ByteBuffers {
+        let len = bytes.len();
+        let slice = unsafe { core::slice::from_raw_parts_mut(bytes.as_mut_ptr(), len) };
+        ByteBuffers::new(vec![slice], len)
+    }
+
+    /// Look up the file again from a new root inode of the root file system,
+    /// so that nothing kept by the previous os inodes is reused
+    ///
+    /// - Arguments
+    ///     - name: the name of the file in the root directory
+    fn reopen(name: &str) -> Arc<OSInode> {
+        let fs = MOUNT_TABLE.get(&ROOT_DEVICE_ID).unwrap();
+        let root_inode = Arc::new(fs.root_inode());
+        let root = OSInode::new(ROOT_DEVICE_ID, OpenFlags::RWDIR, Credential::ROOT, root_inode);
+        root.get_child(name, OpenFlags::READ, Credential::ROOT).unwrap()
+    }
+
+    #[test_case]
+    fn test_split_mount() {
+        assert_eq!(split_mount("1:/data/file"), Some((1, "/data/file")));
+        assert_eq!(split_mount("12:file"), Some((12, "file")));
+        assert_eq!(split_mount("/initproc"), None);
+        assert_eq!(split_mount(":/initproc"), None);
+        assert_eq!(split_mount("/a:b"), None);
+    }
+
+    #[test_case]
+    fn test_push_names() {
+        let mut names = Vec::new();
+        push_names(&mut names, "/a/b");
+        assert_eq!(names, vec!["b", "a"]);
+        // the names of the link target are resolved before the rest names
+        push_names(&mut names, "c/d");
+        assert_eq!(names, vec!["b", "a", "d", "c"]);
+        names.clear();
+        push_names(&mut names, "");
+        assert!(names.is_empty());
+    }
+
+    #[test_case]
+    fn test_chmod_denies_open() {
+        let path = "/chmod_scratch";
+        let user = Credential {
+            uid: USER_UID,
+            gid: USER_GID,
+        };
+        let group = Credential {
+            uid: USER_UID,
+            gid: ROOT_GID,
+        };
+        let flags = OpenFlags::READ | OpenFlags::WRITE | OpenFlags::CREATE;
+        let file = ROOT_INODE.find_inode(path, flags, Credential::ROOT).unwrap();
+        let mode = InodeMode::OWNER_READ
+            | InodeMode::OWNER_WRITE
+            | InodeMode::GROUP_READ
+            | InodeMode::OTHER_READ;
+        file.set_mode(mode).unwrap();
+        let stat = file.stat();
+        assert_eq!((stat.uid, stat.gid), (ROOT_UID, ROOT_GID));
+        assert!(ROOT_INODE.find_inode(path, OpenFlags::READ, user).is_ok());
+        file.set_mode(InodeMode::OWNER_WRITE | InodeMode::GROUP_READ).unwrap();
+        // the other class is denied while the group class is permitted
+        assert!(ROOT_INODE
+            .find_inode(path, OpenFlags::READ, user)
+            .is_err_and(|e| e.is_filepermissiondenied()));
+        assert!(ROOT_INODE.find_inode(path, OpenFlags::READ, group).is_ok());
+        // the privileged user can read without the owner read bit
+        assert!(file.read_all().is_ok());
+        // the file can still be found without requesting any access, but not read or chmod
+        let found = ROOT_INODE.find_inode(path, OpenFlags::empty(), user).unwrap();
+        assert!(found
+            .read_all()
+            .is_err_and(|e| e.is_filepermissiondenied()));
+        assert!(found
+            .set_mode(mode)
+            .is_err_and(|e| e.is_filepermissiondenied()));
+        // the mode is loaded from the metadata file after the cached metadata is dropped
+        let key = (ROOT_DEVICE_ID, file.stat().ino as u32);
+        INODE_METAS.exclusive_access().remove(&key);
+        assert_eq!(file.stat().mode, (InodeMode::OWNER_WRITE | InodeMode::GROUP_READ).bits());
+        file.set_mode(mode).unwrap();
+        assert!(ROOT_INODE.find_inode(path, OpenFlags::READ, user).is_ok());
+        drop(found);
+        drop(file);
+        ROOT_INODE.remove_child(&path[1..]).unwrap();
+    }
+
+    #[test_case]
+    fn test_find_virtual_files() {
+        let null = ROOT_INODE.find(NULL_PATH, OpenFlags::READ, Credential::ROOT).unwrap();
+        assert!(null.as_any().downcast_ref::<OSInode>().is_none());
+        assert!(ROOT_INODE
+            .find_link(NULL_PATH, OpenFlags::READ, Credential::ROOT)
+            .is_ok());
+        let path = format!("/proc/{}/status", INIT_PROC.pid());
+        assert!(ROOT_INODE.find(&path, OpenFlags::READ, Credential::ROOT).is_ok());
+        // the virtual files are not in the file system
+        assert!(ROOT_INODE
+            .find_inode(NULL_PATH, OpenFlags::READ, Credential::ROOT)
+            .is_err());
+        let path = configs::INIT_PROCESS_PATH;
+        let file = ROOT_INODE.find(path, OpenFlags::READ, Credential::ROOT).unwrap();
+        assert!(file.as_any().downcast_ref::<OSInode>().is_some());
+    }
+
+    #[test_case]
+    fn test_directory_search_requires_execute() {
+        let user = Credential {
+            uid: USER_UID,
+            gid: USER_GID,
+        };
+        let root = ROOT_INODE.stat();
+        let mode = InodeMode::from_bits(root.mode).unwrap();
+        assert!(mode.permits(Access::Execute, InodeMode::OTHER_SHIFT));
+        let path = configs::INIT_PROCESS_PATH;
+        ROOT_INODE.set_mode(mode - InodeMode::OTHER_EXECUTE).unwrap();
+        // the directory can still be read but not searched by the other class
+        let result = ROOT_INODE.find_inode(path, OpenFlags::empty(), user);
+        ROOT_INODE.set_mode(mode).unwrap();
+        assert!(result.is_err_and(|e| e.is_filepermissiondenied()));
+        assert!(ROOT_INODE.find_inode(path, OpenFlags::empty(), user).is_ok());
+        // the privileged user can always search the directory
+        assert!(ROOT_INODE.find_inode(path, OpenFlags::empty(), Credential::ROOT).is_ok());
+    }
+
+    #[test_case]
+    fn test_symlink_follow_and_loop() {
+        let flags = OpenFlags::READ | OpenFlags::WRITE | OpenFlags::CREATE;
+        let target = ROOT_INODE.find_inode("/symlink_target", flags, Credential::ROOT).unwrap();
+        target.inner.lock().inode.write_buffer(b"frontier", 0).unwrap();
+        let link = ROOT_INODE
+            .symlink("/symlink_target", "/symlink_link", Credential::ROOT)
+            .unwrap();
+        assert!(link.is_symlink());
+        assert!(!target.is_symlink());
+        assert_eq!(link.read_link().unwrap(), "/symlink_target");
+        // the link is followed by default, or returned itself
+        let found = ROOT_INODE
+            .find_inode("/symlink_link", OpenFlags::READ, Credential::ROOT)
+            .unwrap();
+        assert!(!found.is_symlink());
+        assert_eq!(&found.read_all().unwrap()[..8], b"frontier");
+        let itself = ROOT_INODE
+            .find_inode_link("/symlink_link", OpenFlags::READ, Credential::ROOT)
+            .unwrap();
+        assert!(itself.is_symlink());
+        // the kind of the link is loaded from the metadata file after the cache is dropped
+        let key = (ROOT_DEVICE_ID, link.stat().ino as u32);
+        INODE_METAS.exclusive_access().remove(&key);
+        assert!(itself.is_symlink());
+        // the link refers itself, so the resolution stops after the max hops
+        ROOT_INODE
+            .symlink("symlink_loop", "/symlink_loop", Credential::ROOT)
+            .unwrap();
+        assert!(ROOT_INODE
+            .find_inode("/symlink_loop", OpenFlags::READ, Credential::ROOT)
+            .is_err_and(|e| e.is_symlinkloop()));
+        assert!(ROOT_INODE
+            .find_inode_link("/symlink_loop", OpenFlags::READ, Credential::ROOT)
+            .is_ok_and(|file| file.is_symlink()));
+        drop((target, link, found, itself));
+        for name in ["symlink_target", "symlink_link", "symlink_loop"] {
+            ROOT_INODE.remove_child(name).unwrap();
+        }
+    }
+
+    #[test_case]
+    fn test_fsync_read_back() {
+        let flags = OpenFlags::READ | OpenFlags::WRITE | OpenFlags::CREATE;
+        let file = ROOT_INODE.find_inode("/fsync_scratch", flags, Credential::ROOT).unwrap();
+        let mut data = *b"flushed by fsync";
+        assert_eq!(file.write_at(new_buffers(&mut data), 0).unwrap(), data.len() as u64);
+        assert!(file.sync().is_ok());
+        drop(file);
+        let file = reopen("fsync_scratch");
+        let mut buffer = [0u8; 16];
+        assert_eq!(file.read_at(new_buffers(&mut buffer), 0).unwrap(), 16);
+        assert_eq!(&buffer, b"flushed by fsync");
+        drop(file);
+        ROOT_INODE.remove_child("fsync_scratch").unwrap();
+        assert!(ROOT_INODE.sync().is_ok());
+    }
+
+    #[test_case]
+    fn test_sync_all_read_back() {
+        let flags = OpenFlags::READ | OpenFlags::WRITE | OpenFlags::CREATE;
+        let file = ROOT_INODE.find_inode("/sync_scratch", flags, Credential::ROOT).unwrap();
+        let mut data = *b"flushed by sync all";
+        assert_eq!(file.write_at(new_buffers(&mut data), 0).unwrap(), data.len() as u64);
+        drop(file);
+        assert!(crate::fs::sync_all().is_ok());
+        let file = reopen("sync_scratch");
+        let mut buffer = [0u8; 19];
+        assert_eq!(file.read_at(new_buffers(&mut buffer), 0).unwrap(), 19);
+        assert_eq!(&buffer, b"flushed by sync all");
+        drop(file);
+        ROOT_INODE.remove_child("sync_scratch").unwrap();
+        assert!(crate::fs::sync_all().is_ok());
+    }
+
+    #[test_case]
+    fn test_inode_times_relatime() {
+        let mut times = InodeTimes::new(100);
+        // the access time is not newer than the modification time
+        assert!(times.access(200));
+        assert_eq!(times.atime_us, 200);
+        // the following reading will not update the recent access time
+        assert!(!times.access(300));
+        assert_eq!(times.atime_us, 200);
+        times.modify(400);
+        assert_eq!((times.mtime_us, times.ctime_us), (400, 400));
+        assert!(times.access(500));
+        assert!(!times.access(600));
+        assert!(times.access(500 + configs::FS_ATIME_UPDATE_INTERVAL_US));
+    }
 }
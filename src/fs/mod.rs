@@ -2,19 +2,110 @@
 // @time:      2024/01/10
 
 // self mods
+pub(crate) mod device;
+pub(crate) mod eventfd;
 pub(crate) mod inode;
+pub(crate) mod mqueue;
+pub(crate) mod pidfd;
 pub(crate) mod pipe;
+pub(crate) mod procfs;
 pub(crate) mod stdio;
+pub(crate) mod timerfd;
 
 // use other mods
 use alloc::boxed::Box;
+use alloc::collections::{BTreeMap, VecDeque};
+use alloc::sync::{Arc, Weak};
+use alloc::vec::Vec;
+use core::any::Any;
 use frontier_fs::block::BLOCK_DEVICE_REGISTER;
 use frontier_fs::vfs::{FileSystem, FS};
+use spin::mutex::Mutex;
 
 // use self mods
-use crate::drivers::blocks::BlockDeviceImpl;
+use crate::configs;
+use crate::drivers::blocks::{AccountedBlockDevice, BlockDeviceImpl};
 use crate::lang::buffer::ByteBuffers;
+use crate::lang::container::UserPromiseRefCell;
 use crate::prelude::*;
+use crate::sbi::*;
+use crate::task::model::TaskControlBlock;
+use crate::task::{block_current_and_run_other_task, PROCESSOR, TASK_SCHEDULER};
+
+bitflags! {
+    /// The events which can be waited by the poll syscall
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub(crate) struct PollEvents: u16 {
+        /// There are bytes to read, or reading will not block because of end of file
+        const IN = 1 << 0;
+        /// There is room to write, or writing will not block because of the closed reader
+        const OUT = 1 << 2;
+    }
+}
+
+/// The file descriptor and the events to poll, shared with the user space
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct PollFd {
+    /// The file descriptor to poll
+    pub(crate) fd: usize,
+    /// The requested events, see [`PollEvents`]
+    pub(crate) events: u16,
+    /// The returned events which are ready, filled by the kernel
+    pub(crate) revents: u16,
+}
+
+/// The tasks which are sleeping in the poll syscall and waiting for the events of a file.
+/// The file wakes all of them up when it's state changes, and they will check their files again.
+pub(crate) struct PollWaitQueue {
+    waiters: Mutex<Vec<Weak<TaskControlBlock>>>,
+}
+impl PollWaitQueue {
+    /// Create a new empty wait queue
+    pub(crate) fn new() -> Self {
+        Self {
+            waiters: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Register the task which is going to sleep in the poll syscall,
+    /// the task which has already been registered will not be duplicated.
+    ///
+    /// - Arguments
+    ///     - task: the polling task
+    pub(crate) fn register(&self, task: &Arc<TaskControlBlock>) {
+        let mut waiters = self.waiters.lock();
+        waiters.retain(|waiter| waiter.strong_count() > 0);
+        if !waiters.iter().any(|waiter| waiter.as_ptr() == Arc::as_ptr(task)) {
+            waiters.push(Arc::downgrade(task));
+        }
+    }
+
+    /// Unregister the task after it returns from the poll syscall,
+    /// so that the later events will not wake it up from the other sleeping.
+    ///
+    /// - Arguments
+    ///     - task: the polling task
+    pub(crate) fn unregister(&self, task: &Arc<TaskControlBlock>) {
+        self.waiters
+            .lock()
+            .retain(|waiter| waiter.strong_count() > 0 && waiter.as_ptr() != Arc::as_ptr(task));
+    }
+
+    /// Wake all the sleeping tasks up, they are kept registered until they return from polling
+    ///
+    /// - Returns
+    ///     - the count of the tasks which were sleeping and now are ready
+    pub(crate) fn wake_all(&self) -> usize {
+        let tasks: Vec<Arc<TaskControlBlock>> = self
+            .waiters
+            .lock()
+            .iter()
+            .filter_map(|waiter| waiter.upgrade())
+            .collect();
+        TASK_SCHEDULER.wake_sleep_tasks(&tasks)
+    }
+}
 
 /// Core trait, all structs that implement this feature can be read and written as files.
 pub(crate) trait File: Send + Sync {
@@ -28,13 +119,400 @@ pub(crate) trait File: Send + Sync {
     /// - Arguments
     ///     - buffers: a wrapper class for byte slices in the user-mode stack space
     fn write(&self, buffers: ByteBuffers) -> Result<u64>;
+    /// Grow or shrink the file to exactly the length.
+    /// Only the regular files can be truncated, so the other files return an error by default.
+    ///
+    /// - Arguments
+    ///     - len: the new byte length of the file
+    ///
+    /// - Errors
+    ///     - FileNotTruncatable
+    fn truncate(&self, _len: u64) -> Result<()> {
+        Err(KernelError::FileNotTruncatable)
+    }
+    /// Read file at the explicit offset without changing the offset of the file.
+    /// Only the regular files can be accessed at the offset, so the other files return an error by default.
+    ///
+    /// - Arguments
+    ///     - buffers: a wrapper class for byte slices in the user-mode stack space
+    ///     - offset: the byte offset of the file to read from
+    ///
+    /// - Errors
+    ///     - FileNotSeekable
+    fn read_at(&self, _buffers: ByteBuffers, _offset: u64) -> Result<u64> {
+        Err(KernelError::FileNotSeekable)
+    }
+    /// Write file at the explicit offset without changing the offset of the file.
+    /// Only the regular files can be accessed at the offset, so the other files return an error by default.
+    ///
+    /// - Arguments
+    ///     - buffers: a wrapper class for byte slices in the user-mode stack space
+    ///     - offset: the byte offset of the file to write to
+    ///
+    /// - Errors
+    ///     - FileNotSeekable
+    fn write_at(&self, _buffers: ByteBuffers, _offset: u64) -> Result<u64> {
+        Err(KernelError::FileNotSeekable)
+    }
+    /// Flush the dirty blocks of the file into the device.
+    /// Only the regular files are backed by the blocks, so the other files do nothing by default.
+    fn sync(&self) -> Result<()> {
+        Ok(())
+    }
+    /// Check if the file can be read or written without blocking.
+    /// The regular files never block, so they are always ready by default.
+    ///
+    /// - Arguments
+    ///     - events: the events to check, see [`PollEvents`]
+    fn poll_ready(&self, events: PollEvents) -> bool {
+        !events.is_empty()
+    }
+    /// Get the wait queue which is woken up when the file may become ready.
+    /// The files without it are checked again after each poll interval by default.
+    fn poll_wait_queue(&self) -> Option<&PollWaitQueue> {
+        None
+    }
+    /// Get the file as any type, so that we can downcast it to the specific file type
+    fn as_any(&self) -> &dyn Any;
+}
+
+/// Check if the holder of the lock is the task
+///
+/// - Arguments
+///     - holder: the holder of the lock, None if it is held without a current task
+///     - task: the task to check, None if there is no current task
+fn is_holder(
+    holder: &Option<Weak<TaskControlBlock>>,
+    task: &Option<Arc<TaskControlBlock>>,
+) -> bool {
+    match (holder, task) {
+        (Some(holder), Some(task)) => holder.as_ptr() == Arc::as_ptr(task),
+        (None, None) => true,
+        _ => false,
+    }
+}
+
+/// The mutable states of the file system lock
+struct FsLockInner {
+    /// The task which holds the lock, None if it is held without a current task
+    holder: Option<Weak<TaskControlBlock>>,
+    /// The nested count of the holder, zero means the lock is free
+    depth: usize,
+    /// The task which the released lock was handed over to, the others cannot take it
+    next: Option<Weak<TaskControlBlock>>,
+    /// The tasks of each process which are blocked until the lock is handed over to them,
+    /// in the blocking order, the pid as key
+    waiting: BTreeMap<usize, VecDeque<Weak<TaskControlBlock>>>,
+    /// The processes which have blocked tasks in the round-robin order
+    order: VecDeque<usize>,
+}
+impl FsLockInner {
+    /// Queue the task which is going to be blocked into the queue of its process
+    ///
+    /// - Arguments
+    ///     - task: the blocked task
+    fn push_waiting(&mut self, task: &Arc<TaskControlBlock>) {
+        let pid = task.process().pid();
+        let queue = self.waiting.entry(pid).or_default();
+        queue.push_back(Arc::downgrade(task));
+        if queue.len() == 1 {
+            self.order.push_back(pid);
+        }
+    }
+
+    /// Take the first live blocked task of the process in turn,
+    /// the process will be moved to the end of the order if it still has blocked tasks.
+    ///
+    /// - Returns
+    ///     - Some(task): the task which the lock will be handed over to
+    ///     - None: there is no live blocked task
+    fn pop_waiting(&mut self) -> Option<Arc<TaskControlBlock>> {
+        while let Some(pid) = self.order.pop_front() {
+            let queue = self.waiting.get_mut(&pid).unwrap();
+            let task = queue.pop_front().unwrap();
+            if queue.is_empty() {
+                self.waiting.remove(&pid);
+            } else {
+                self.order.push_back(pid);
+            }
+            if let Some(task) = task.upgrade() {
+                return Some(task);
+            }
+        }
+        None
+    }
+}
+
+/// The sleeping lock which serializes all the operations of the file systems.
+/// The file system holds the spin locks of the inodes and the block caches during the I/O,
+/// so the contenders are blocked by this lock before they touch the file system,
+/// and the holder is allowed to be blocked by the block device until the completion interrupt,
+/// see [`FsLock::may_sleep`].
+/// The released lock is handed over to the blocked tasks by round-robin over their processes,
+/// one operation for each process in turn, so one process doing heavy I/O cannot starve others.
+/// The lock is reentrant for its holder, because the operations are nested in the path resolution.
+/// The callers must not hold the borrows of the processes while calling into the file system,
+/// because the other tasks will run while the holder or the contenders are blocked.
+pub(crate) struct FsLock {
+    inner: UserPromiseRefCell<FsLockInner>,
+}
+impl FsLock {
+    /// Create a new free lock
+    fn new() -> Self {
+        Self {
+            inner: unsafe {
+                UserPromiseRefCell::new(FsLockInner {
+                    holder: None,
+                    depth: 0,
+                    next: None,
+                    waiting: BTreeMap::new(),
+                    order: VecDeque::new(),
+                })
+            },
+        }
+    }
+
+    /// Take the lock, the current task will be blocked until the lock is handed over to it.
+    /// Without a current task the lock must be free, which is always true during the booting,
+    /// because the lock is only kept by the holder blocked in the I/O after the scheduler started.
+    ///
+    /// - Returns
+    ///     - the guard which releases the lock when it is dropped
+    pub(crate) fn lock(&self) -> FsGuard<'_> {
+        loop {
+            let current_task = PROCESSOR.try_current_task();
+            let mut inner = self.inner.exclusive_access();
+            if inner.depth > 0 && is_holder(&inner.holder, &current_task) {
+                inner.depth += 1;
+                return FsGuard { lock: self };
+            }
+            let next = inner.next.as_ref().and_then(|next| next.upgrade());
+            let handed = match (next, &current_task) {
+                (None, _) => true,
+                (Some(next), Some(task)) => Arc::ptr_eq(&next, task),
+                (Some(_), None) => false,
+            };
+            if inner.depth == 0 && handed {
+                inner.next.take();
+                inner.holder = current_task.as_ref().map(Arc::downgrade);
+                inner.depth = 1;
+                return FsGuard { lock: self };
+            }
+            let task = current_task.expect("the file system is locked by a blocked task");
+            inner.push_waiting(&task);
+            drop(task);
+            drop(inner);
+            block_current_and_run_other_task().unwrap();
+        }
+    }
+
+    /// Release the lock once, the lock will be handed over to the waiting task of the next process
+    /// when the holder releases all of its nested locks.
+    fn unlock(&self) {
+        let mut inner = self.inner.exclusive_access();
+        inner.depth -= 1;
+        if inner.depth > 0 {
+            return;
+        }
+        inner.holder.take();
+        if let Some(task) = inner.pop_waiting() {
+            inner.next.replace(Arc::downgrade(&task));
+            task.mark_suspended();
+            TASK_SCHEDULER.put_read_task(task);
+        }
+    }
+
+    /// Check whether the current task can be blocked by the block device during the I/O.
+    /// Only the holder can be, the other callers may hold the spin locks of the file system.
+    /// The tasks are never blocked before the scheduler started running them.
+    pub(crate) fn may_sleep(&self) -> bool {
+        if !PROCESSOR.is_scheduling() {
+            return false;
+        }
+        let current_task = PROCESSOR.try_current_task();
+        let inner = self.inner.access();
+        current_task.is_some() && inner.depth > 0 && is_holder(&inner.holder, &current_task)
+    }
+}
+
+/// The guard of the file system lock, the lock is released when it is dropped
+pub(crate) struct FsGuard<'a> {
+    lock: &'a FsLock,
 }
+impl Drop for FsGuard<'_> {
+    fn drop(&mut self) {
+        self.lock.unlock();
+    }
+}
+
+/// The device id of the root file system
+pub(crate) const ROOT_DEVICE_ID: usize = 0;
 
 lazy_static! {
-    /// The root file system, through which all operations on files are invoked by the operating system
-    pub(crate) static ref ROOT_FS: FS = {
-        let device = Box::new(BlockDeviceImpl::new().unwrap());
-        let tracker = BLOCK_DEVICE_REGISTER.lock().mount(device).unwrap();
-        *FS::open(&tracker).unwrap()
+    /// The lock which serializes all the operations of the file systems, see [`FsLock`]
+    pub(crate) static ref FS_LOCK: FsLock = FsLock::new();
+
+    /// All the mounted file systems, the device id as key.
+    /// Each virtio slot in the configs will be probed, and the slot without device will be skipped.
+    /// The device without a valid file system is also skipped instead of stopping the kernel.
+    /// The file system with [`ROOT_DEVICE_ID`] is the root file system,
+    /// through which all operations on files are invoked by the operating system
+    pub(crate) static ref MOUNT_TABLE: BTreeMap<usize, FS> = {
+        let mut table = BTreeMap::new();
+        for (device_id, (base, irq)) in configs::VIRTIO_BLOCK_SLOTS.iter().enumerate() {
+            let device = match BlockDeviceImpl::new(*base, Some(*irq)) {
+                Ok(device) => Box::new(AccountedBlockDevice::new(device)),
+                Err(error) => {
+                    assert_ne!(device_id, ROOT_DEVICE_ID, "root block device is required");
+                    debug!("skip block device {} at {:#x}: {}", device_id, base, error);
+                    continue;
+                }
+            };
+            let tracker = BLOCK_DEVICE_REGISTER.lock().mount(device).unwrap();
+            match FS::open(&tracker) {
+                Ok(fs) => {
+                    table.insert(device_id, *fs);
+                }
+                Err(error) => {
+                    assert_ne!(device_id, ROOT_DEVICE_ID, "root file system is required");
+                    warn!(
+                        "Skip mounting block device {} at {:#x} cause: {:?}",
+                        device_id, base, error
+                    );
+                }
+            }
+        }
+        table
     };
 }
+
+/// Flush the dirty block caches of all mounted file systems into their devices.
+/// It must be called before the kernel shutdown, otherwise the written data may be lost.
+///
+/// - Errors
+///     - FileSystemError
+///         - RawDeviceError(error code)
+pub(crate) fn sync_all() -> Result<()> {
+    let _guard = FS_LOCK.lock();
+    for fs in MOUNT_TABLE.values() {
+        fs.sync()?;
+    }
+    Ok(())
+}
+
+/// Flush all file systems and reset the machine by the mode.
+/// The flushing failure will only be logged, because the machine will be reset anyway.
+///
+/// - Arguments
+///     - mode: the reset mode
+pub(crate) fn sync_and_reset(mode: ResetMode) -> ! {
+    if let Err(error) = sync_all() {
+        error!("Sync file systems before {:?} failed cause: {}", mode, error);
+    }
+    SBI::reset(mode)
+}
+
+/// Flush all file systems and shutdown the kernel, see [`sync_and_reset`]
+pub(crate) fn sync_and_shutdown() -> ! {
+    sync_and_reset(ResetMode::PowerOff)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task::model::{Credential, CurrentTaskGuard, ForkedProcess};
+    use frontier_fs::OpenFlags;
+    use inode::ROOT_INODE;
+
+    #[test_case]
+    fn test_fs_lock_hands_over_to_waiting_task() {
+        let lock = FsLock::new();
+        let guard = lock.lock();
+        // the holder can take the lock again in the nested operations
+        drop(lock.lock());
+        assert_eq!(lock.inner.access().depth, 1);
+        let process = ForkedProcess::new();
+        let task = process.inner_access().root_task();
+        task.mark_blocked();
+        lock.inner.exclusive_access().push_waiting(&task);
+        drop(guard);
+        assert!(!task.is_blocked());
+        assert!(TASK_SCHEDULER.fetch_specific(&task));
+        assert!(Arc::ptr_eq(&TASK_SCHEDULER.pop_ready_task().unwrap(), &task));
+        // the released lock is reserved for the woken task
+        assert_eq!(lock.inner.access().depth, 0);
+        assert!(lock.inner.access().next.is_some());
+        let current = process.enter();
+        let guard = lock.lock();
+        assert!(lock.inner.access().next.is_none());
+        // the holder is never blocked by the device before the scheduler started
+        assert!(!lock.may_sleep());
+        drop(guard);
+        drop(current);
+        assert_eq!(lock.inner.access().depth, 0);
+        assert!(lock.inner.access().holder.is_none());
+    }
+
+    #[test_case]
+    fn test_fs_lock_hands_over_between_processes_in_turn() {
+        let lock = FsLock::new();
+        let guard = lock.lock();
+        let heavy = ForkedProcess::new();
+        let heavy_task = heavy.inner_access().root_task();
+        let heavy_thread = heavy
+            .alloc_task(
+                0,
+                &[],
+                configs::KERNEL_TASK_STACK_BYTE_SIZE,
+                configs::USER_TASK_STACK_BYTE_SIZE,
+            )
+            .unwrap();
+        let light = ForkedProcess::new();
+        let light_task = light.inner_access().root_task();
+        // both tasks of the heavy process were blocked before the light process
+        for task in [&heavy_task, &heavy_thread, &light_task] {
+            task.mark_blocked();
+            lock.inner.exclusive_access().push_waiting(task);
+        }
+        drop(guard);
+        let mut handed = Vec::new();
+        loop {
+            let next = lock.inner.access().next.as_ref().and_then(|next| next.upgrade());
+            let next = match next {
+                Some(next) => next,
+                None => break,
+            };
+            assert!(TASK_SCHEDULER.fetch_specific(&next));
+            assert!(Arc::ptr_eq(&TASK_SCHEDULER.pop_ready_task().unwrap(), &next));
+            let current = CurrentTaskGuard::enter(Arc::clone(&next));
+            drop(lock.lock());
+            drop(current);
+            handed.push(next);
+        }
+        // the light process took the lock before the second operation of the heavy process
+        assert_eq!(handed.len(), 3);
+        assert!(Arc::ptr_eq(&handed[0], &heavy_task));
+        assert!(Arc::ptr_eq(&handed[1], &light_task));
+        assert!(Arc::ptr_eq(&handed[2], &heavy_thread));
+        assert!(lock.inner.access().order.is_empty());
+        heavy_thread.mark_zombie(0);
+    }
+
+    #[test_case]
+    fn test_sync_all() {
+        assert!(sync_all().is_ok());
+        // nothing is dirty after the first flushing
+        assert!(sync_all().is_ok());
+    }
+
+    #[test_case]
+    fn test_file_sync() {
+        let file = ROOT_INODE
+            .find_inode(configs::INIT_PROCESS_PATH, OpenFlags::READ, Credential::ROOT)
+            .unwrap();
+        assert!(file.sync().is_ok());
+        // the pipe is not backed by any block
+        let pipe = pipe::Pipe::new(configs::PIPE_RING_BUFFER_LENGTH);
+        assert!(pipe.sync().is_ok());
+    }
+}
@@ -0,0 +1,301 @@
+// @author:    olinex
+// @time:      2024/08/06
+
+// self mods
+
+// use other mods
+use alloc::collections::{BTreeMap, VecDeque};
+use alloc::string::String;
+use alloc::sync::{Arc, Weak};
+use alloc::vec::Vec;
+use core::any::Any;
+
+// use self mods
+use super::{File, PollEvents};
+use crate::configs;
+use crate::lang::buffer::ByteBuffers;
+use crate::lang::container::UserPromiseRefCell;
+use crate::prelude::*;
+use crate::task::model::TaskControlBlock;
+use crate::task::{block_current_and_run_other_task, PROCESSOR, TASK_SCHEDULER};
+
+/// The mutable states of the message queue
+struct MessageQueueInner {
+    /// The messages which were sent but not received, each message keeps its own boundary
+    messages: VecDeque<Vec<u8>>,
+    /// The tasks which are blocked because the queue is full
+    senders: VecDeque<Weak<TaskControlBlock>>,
+    /// The tasks which are blocked because the queue is empty
+    receivers: VecDeque<Weak<TaskControlBlock>>,
+}
+
+/// Wake up the first alive task in the waiting queue
+///
+/// - Arguments
+///     - waiting: the waiting queue of the blocked tasks
+fn wake_one(waiting: &mut VecDeque<Weak<TaskControlBlock>>) {
+    while let Some(task) = waiting.pop_front() {
+        if let Some(task) = task.upgrade() {
+            task.mark_suspended();
+            TASK_SCHEDULER.put_read_task(task);
+            break;
+        }
+    }
+}
+
+/// The bounded queue of the fixed-size messages.
+/// Unlike the byte-stream pipe, each sending is received as a whole message by one receiving.
+/// The queue is registered by the name in [`MQUEUE_TABLE`],
+/// and it will be released when the last file descriptor referring it was closed.
+pub(crate) struct MessageQueue {
+    /// The name which is used to find the queue
+    name: String,
+    inner: UserPromiseRefCell<MessageQueueInner>,
+}
+impl MessageQueue {
+    /// Create a new empty message queue
+    ///
+    /// - Arguments
+    ///     - name: the name of the queue
+    fn new(name: String) -> Self {
+        Self {
+            name,
+            inner: unsafe {
+                UserPromiseRefCell::new(MessageQueueInner {
+                    messages: VecDeque::new(),
+                    senders: VecDeque::new(),
+                    receivers: VecDeque::new(),
+                })
+            },
+        }
+    }
+
+    /// Send the bytes as one message, the current task will be blocked until the queue is not full.
+    ///
+    /// - Arguments
+    ///     - buffers: the bytes of the message
+    ///
+    /// - Returns
+    ///     - the byte length of the message
+    ///
+    /// - Errors
+    ///     - ProcessHaveNotTask
+    ///     - MessageTooLong(len)
+    pub(crate) fn send(&self, buffers: ByteBuffers) -> Result<u64> {
+        let len = buffers.len();
+        if len > configs::MQUEUE_MESSAGE_BYTE_SIZE {
+            return Err(KernelError::MessageTooLong(len));
+        }
+        let mut message = Vec::with_capacity(len);
+        let mut iterator = buffers.into_iter();
+        while let Some(byte) = iterator.next() {
+            message.push(byte);
+        }
+        loop {
+            let mut inner = self.inner.exclusive_access();
+            if inner.messages.len() < configs::MQUEUE_MAX_MESSAGE_COUNT {
+                inner.messages.push_back(message);
+                wake_one(&mut inner.receivers);
+                return Ok(len as u64);
+            }
+            let current_task = PROCESSOR.current_task()?;
+            inner.senders.push_back(Arc::downgrade(&current_task));
+            drop(current_task);
+            drop(inner);
+            block_current_and_run_other_task()?;
+        }
+    }
+
+    /// Receive the oldest message into the buffers,
+    /// the current task will be blocked until the queue is not empty.
+    /// The message will be kept in the queue if the buffers are too small to hold it.
+    ///
+    /// - Arguments
+    ///     - buffers: the buffers to fill in
+    ///
+    /// - Returns
+    ///     - the byte length of the message
+    ///
+    /// - Errors
+    ///     - ProcessHaveNotTask
+    ///     - MessageTooLong(len)
+    ///     - EOB
+    pub(crate) fn recv(&self, buffers: ByteBuffers) -> Result<u64> {
+        loop {
+            let mut inner = self.inner.exclusive_access();
+            if let Some(message) = inner.messages.front() {
+                if message.len() > buffers.len() {
+                    return Err(KernelError::MessageTooLong(message.len()));
+                }
+                let message = inner.messages.pop_front().unwrap();
+                wake_one(&mut inner.senders);
+                drop(inner);
+                let mut iterator = buffers.into_iter();
+                for byte in message.iter() {
+                    iterator.next_mut(*byte)?;
+                }
+                return Ok(message.len() as u64);
+            }
+            let current_task = PROCESSOR.current_task()?;
+            inner.receivers.push_back(Arc::downgrade(&current_task));
+            drop(current_task);
+            drop(inner);
+            block_current_and_run_other_task()?;
+        }
+    }
+}
+impl File for MessageQueue {
+    /// Receive one message, see [`MessageQueue::recv`]
+    fn read(&self, buffers: ByteBuffers) -> Result<u64> {
+        self.recv(buffers)
+    }
+
+    /// Send one message, see [`MessageQueue::send`]
+    fn write(&self, buffers: ByteBuffers) -> Result<u64> {
+        self.send(buffers)
+    }
+
+    /// The queue is readable when there are messages, and writable when it is not full
+    fn poll_ready(&self, events: PollEvents) -> bool {
+        let inner = self.inner.access();
+        (events.contains(PollEvents::IN) && !inner.messages.is_empty())
+            || (events.contains(PollEvents::OUT)
+                && inner.messages.len() < configs::MQUEUE_MAX_MESSAGE_COUNT)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+impl Drop for MessageQueue {
+    /// Unregister the name of the queue, so the next opening will create a new queue
+    fn drop(&mut self) {
+        let mut table = MQUEUE_TABLE.exclusive_access();
+        if table
+            .get(&self.name)
+            .is_some_and(|queue| queue.strong_count() == 0)
+        {
+            table.remove(&self.name);
+        }
+    }
+}
+
+/// The map of the queue name and the weak reference of the queue
+type MessageQueueTable = BTreeMap<String, Weak<MessageQueue>>;
+
+lazy_static! {
+    /// The global registry of the message queues, the unrelated processes can rendezvous by the name.
+    /// Only the weak references are kept, so the queues are owned by the file descriptors.
+    pub(crate) static ref MQUEUE_TABLE: Arc<UserPromiseRefCell<MessageQueueTable>> =
+        Arc::new(unsafe { UserPromiseRefCell::new(BTreeMap::new()) });
+}
+impl MQUEUE_TABLE {
+    /// Get the message queue by the name, a new queue will be created if the name does not exist.
+    ///
+    /// - Arguments
+    ///     - name: the name of the queue
+    ///
+    /// - Returns
+    ///     - Some(queue): the queue registered by the name
+    ///     - None: the count of the queues reaches the limit
+    pub(crate) fn open(&self, name: &str) -> Option<Arc<MessageQueue>> {
+        let mut table = self.exclusive_access();
+        if let Some(queue) = table.get(name).and_then(|queue| queue.upgrade()) {
+            return Some(queue);
+        }
+        if !table.contains_key(name) && table.len() >= configs::MAX_MQUEUE_COUNT {
+            return None;
+        }
+        let queue = Arc::new(MessageQueue::new(String::from(name)));
+        table.insert(String::from(name), Arc::downgrade(&queue));
+        Some(queue)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task::model::ForkedProcess;
+    use alloc::vec;
+
+    fn new_buffers(bytes: &mut [u8]) -> ByteBuffers {
+        let len = bytes.len();
+        let slice = unsafe { core::slice::from_raw_parts_mut(bytes.as_mut_ptr(), len) };
+        ByteBuffers::new(vec![slice], len)
+    }
+
+    #[test_case]
+    fn test_mqueue_keeps_message_boundaries() {
+        let queue = MQUEUE_TABLE.open("test_mqueue").unwrap();
+        let mut first = *b"hello";
+        let mut second = *b"world!";
+        assert_eq!(queue.send(new_buffers(&mut first)).unwrap(), 5);
+        assert_eq!(queue.send(new_buffers(&mut second)).unwrap(), 6);
+        assert!(queue.poll_ready(PollEvents::IN));
+        let mut bytes = [0u8; 16];
+        assert_eq!(queue.recv(new_buffers(&mut bytes)).unwrap(), 5);
+        assert_eq!(&bytes[..5], b"hello");
+        // the message is kept if the buffer is too small
+        assert!(queue
+            .recv(new_buffers(&mut bytes[..4]))
+            .is_err_and(|e| e.is_messagetoolong()));
+        assert_eq!(queue.recv(new_buffers(&mut bytes)).unwrap(), 6);
+        assert_eq!(&bytes[..6], b"world!");
+        assert!(!queue.poll_ready(PollEvents::IN));
+        let mut large = [0u8; configs::MQUEUE_MESSAGE_BYTE_SIZE + 1];
+        assert!(queue
+            .send(new_buffers(&mut large))
+            .is_err_and(|e| e.is_messagetoolong()));
+    }
+
+    #[test_case]
+    fn test_mqueue_released_with_last_reference() {
+        let queue = MQUEUE_TABLE.open("test_mqueue_release").unwrap();
+        let other = MQUEUE_TABLE.open("test_mqueue_release").unwrap();
+        assert!(Arc::ptr_eq(&queue, &other));
+        drop(queue);
+        assert!(MQUEUE_TABLE.access().contains_key("test_mqueue_release"));
+        drop(other);
+        assert!(!MQUEUE_TABLE.access().contains_key("test_mqueue_release"));
+    }
+
+    #[test_case]
+    fn test_mqueue_blocked_side_woken_by_other_side() {
+        let process = ForkedProcess::new();
+        let task = process.inner_access().root_task();
+        let queue = MQUEUE_TABLE.open("test_mqueue_blocking").unwrap();
+        // simulate the receiver blocking on the empty queue until the sender arrives
+        task.mark_blocked();
+        queue
+            .inner
+            .exclusive_access()
+            .receivers
+            .push_back(Arc::downgrade(&task));
+        let mut message = *b"ping";
+        assert_eq!(queue.send(new_buffers(&mut message)).unwrap(), 4);
+        assert!(!task.is_blocked());
+        assert!(queue.inner.access().receivers.is_empty());
+        assert!(TASK_SCHEDULER.fetch_specific(&task));
+        assert!(Arc::ptr_eq(&TASK_SCHEDULER.pop_ready_task().unwrap(), &task));
+        let mut bytes = [0u8; 4];
+        assert_eq!(queue.recv(new_buffers(&mut bytes)).unwrap(), 4);
+        assert_eq!(&bytes, b"ping");
+        // simulate the sender blocking on the full queue until the receiver arrives
+        for _ in 0..configs::MQUEUE_MAX_MESSAGE_COUNT {
+            assert_eq!(queue.send(new_buffers(&mut message)).unwrap(), 4);
+        }
+        assert!(!queue.poll_ready(PollEvents::OUT));
+        task.mark_blocked();
+        queue
+            .inner
+            .exclusive_access()
+            .senders
+            .push_back(Arc::downgrade(&task));
+        assert_eq!(queue.recv(new_buffers(&mut bytes)).unwrap(), 4);
+        assert!(!task.is_blocked());
+        assert!(queue.inner.access().senders.is_empty());
+        assert!(TASK_SCHEDULER.fetch_specific(&task));
+        assert!(Arc::ptr_eq(&TASK_SCHEDULER.pop_ready_task().unwrap(), &task));
+        assert!(queue.poll_ready(PollEvents::OUT));
+    }
+}
@@ -0,0 +1,91 @@
+// @author:    olinex
+// @time:      2024/07/02
+
+// self mods
+
+// use other mods
+use alloc::sync::{Arc, Weak};
+use core::any::Any;
+
+// use self mods
+use super::{File, PollEvents};
+use crate::lang::buffer::ByteBuffers;
+use crate::prelude::*;
+use crate::task::model::ProcessControlBlock;
+
+/// A file which refers to a specific process.
+/// Because it holds the reference of the process control block instead of the pid,
+/// it cannot be confused with other process even if the pid was recycled and reused.
+pub(crate) struct PidFd {
+    process: Weak<ProcessControlBlock>,
+}
+impl PidFd {
+    /// Create a new pidfd which refers to the process
+    ///
+    /// - Arguments
+    ///     - process: the process control block referred by the pidfd
+    pub(crate) fn new(process: &Arc<ProcessControlBlock>) -> Self {
+        Self {
+            process: Arc::downgrade(process),
+        }
+    }
+
+    /// Get the process referred by the pidfd, return None if the process was recycled
+    pub(crate) fn process(&self) -> Option<Arc<ProcessControlBlock>> {
+        self.process.upgrade()
+    }
+}
+impl File for PidFd {
+    /// pidfd is not readable, always return zero
+    fn read(&self, _: ByteBuffers) -> Result<u64> {
+        Ok(0)
+    }
+
+    /// pidfd is not writable, always return zero
+    fn write(&self, _: ByteBuffers) -> Result<u64> {
+        Ok(0)
+    }
+
+    /// The pidfd is readable once the process has exited, whether or not it was recycled.
+    ///
+    /// See [`crate::fs::File`]
+    fn poll_ready(&self, events: PollEvents) -> bool {
+        events.contains(PollEvents::IN)
+            && self.process().map_or(true, |process| process.is_zombie())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task::model::ForkedProcess;
+
+    #[test_case]
+    fn test_pidfd_recycled_process() {
+        let pidfd = PidFd {
+            process: Weak::new(),
+        };
+        assert!(pidfd.process().is_none());
+        assert!(pidfd.poll_ready(PollEvents::IN));
+        assert!(pidfd.as_any().downcast_ref::<PidFd>().is_some());
+    }
+
+    #[test_case]
+    fn test_pidfd_ready_after_process_exits() {
+        let child = ForkedProcess::new();
+        let pidfd = PidFd::new(&child);
+        assert!(!pidfd.poll_ready(PollEvents::IN));
+        assert!(!pidfd.poll_ready(PollEvents::OUT));
+        child.mark_zombie(0);
+        assert!(pidfd.poll_ready(PollEvents::IN));
+        assert!(!pidfd.poll_ready(PollEvents::OUT));
+        assert!(Arc::ptr_eq(&pidfd.process().unwrap(), &child));
+        drop(child);
+        assert!(pidfd.process().is_none());
+        assert!(pidfd.poll_ready(PollEvents::IN));
+    }
+}
@@ -5,14 +5,15 @@
 
 // use other mods
 use alloc::sync::{Arc, Weak};
+use core::any::Any;
 use enum_group::EnumGroup;
 use spin::mutex::Mutex;
 
 // use self mods
-use super::File;
+use super::{File, PollEvents, PollWaitQueue};
 use crate::lang::buffer::{ByteBuffers, RingBuffer};
 use crate::prelude::*;
-use crate::task::suspend_current_and_run_other_task;
+use crate::task::{current_has_pending_signal, suspend_current_and_run_other_task};
 
 /// A wrapper enumeration class for ringbuffer, only readable or writable.
 /// For the same ringbuffer, it only makes sense to write data to it if it is read.
@@ -24,25 +25,31 @@ use crate::task::suspend_current_and_run_other_task;
 /// Conversely, if there are no readable ends,
 /// the pipe will be automatically recycled,
 /// and weak references on the writable side will no longer be able to be written.
+///
+/// The both sides share the wait queue of the polling tasks,
+/// which are woken up when the bytes are read or written, or when any side is closed.
 #[derive(EnumGroup)]
 pub(crate) enum Pipe {
-    Read(Arc<Mutex<RingBuffer>>),
-    Write(Weak<Mutex<RingBuffer>>),
+    Read(Arc<Mutex<RingBuffer>>, Arc<PollWaitQueue>),
+    Write(Weak<Mutex<RingBuffer>>, Arc<PollWaitQueue>),
 }
 impl Pipe {
     /// Create a new readable pipe
     pub(crate) fn new(capacity: usize) -> Self {
-        Self::Read(Arc::new(Mutex::new(RingBuffer::new(capacity))))
+        Self::Read(
+            Arc::new(Mutex::new(RingBuffer::new(capacity))),
+            Arc::new(PollWaitQueue::new()),
+        )
     }
 
     /// Fork writable pipe, and if the current pipe is readable, it will inevitably return a writable copy of the pipe.
     /// If the current pipe is writable, None will be returned when all readable sides of the pipe have been closed.
     pub(crate) fn writable_fork(&self) -> Option<Self> {
         match self {
-            Self::Read(tap) => Some(Self::Write(Arc::downgrade(tap))),
-            Self::Write(tap) => tap
-                .upgrade()
-                .and_then(|upgrade| Some(Self::Write(Arc::downgrade(&upgrade)))),
+            Self::Read(tap, pollers) => Some(Self::Write(Arc::downgrade(tap), Arc::clone(pollers))),
+            Self::Write(tap, pollers) => tap.upgrade().and_then(|upgrade| {
+                Some(Self::Write(Arc::downgrade(&upgrade), Arc::clone(pollers)))
+            }),
         }
     }
 
@@ -51,8 +58,10 @@ impl Pipe {
     #[allow(dead_code)]
     pub(crate) fn readable_fork(&self) -> Option<Self> {
         match self {
-            Self::Write(tap) => tap.upgrade().and_then(|upgrade| Some(Self::Read(upgrade))),
-            Self::Read(tap) => Some(Self::Read(Arc::clone(tap))),
+            Self::Write(tap, pollers) => tap
+                .upgrade()
+                .and_then(|upgrade| Some(Self::Read(upgrade, Arc::clone(pollers)))),
+            Self::Read(tap, pollers) => Some(Self::Read(Arc::clone(tap), Arc::clone(pollers))),
         }
     }
 
@@ -60,24 +69,24 @@ impl Pipe {
     #[allow(dead_code)]
     pub(crate) fn clone(&self) -> Self {
         match self {
-            Self::Read(tap) => Self::Read(Arc::clone(tap)),
-            Self::Write(tap) => Self::Write(Weak::clone(tap)),
+            Self::Read(tap, pollers) => Self::Read(Arc::clone(tap), Arc::clone(pollers)),
+            Self::Write(tap, pollers) => Self::Write(Weak::clone(tap), Arc::clone(pollers)),
         }
     }
 
     /// Check if the pipe is closed and cannot write anymore byte into it.
     pub(crate) fn all_write_end_closed(&self) -> bool {
         match self {
-            Self::Write(_) => false,
-            Self::Read(buffer) => Arc::weak_count(buffer) == 0,
+            Self::Write(..) => false,
+            Self::Read(buffer, _) => Arc::weak_count(buffer) == 0,
         }
     }
 
     /// Check if the pipe is close and no any other readable tap.
     pub(crate) fn all_read_end_closed(&self) -> bool {
         match self {
-            Self::Read(_) => false,
-            Self::Write(buffer) => buffer.upgrade().is_none(),
+            Self::Read(..) => false,
+            Self::Write(buffer, _) => buffer.upgrade().is_none(),
         }
     }
 }
@@ -96,14 +105,17 @@ impl File for Pipe {
     /// if they do not exist, they will check whether there are still writers on the side, 
     /// and if they do not, they will immediately end the current task.
     /// 
+    /// The reading which has not read any byte will be interrupted by the pending signal.
+    ///
     /// See [`crate::fs::File`]
     /// 
     /// - Errors
     ///     - ProcessHaveNotTask
     ///     - EOB
+    ///     - SyscallInterrupted
     fn read(&self, buffers: ByteBuffers) -> Result<u64> {
-        let tap = if let Self::Read(tap) = self {
-            tap
+        let (tap, pollers) = if let Self::Read(tap, pollers) = self {
+            (tap, pollers)
         } else {
             panic!("reading write only pipe");
         };
@@ -124,6 +136,9 @@ impl File for Pipe {
                     if self.all_write_end_closed() {
                         return Ok(already_readed_size);
                     }
+                    if already_readed_size == 0 && current_has_pending_signal()? {
+                        return Err(KernelError::SyscallInterrupted);
+                    }
                     drop(inner);
                     suspend_current_and_run_other_task()?;
                     continue;
@@ -136,6 +151,8 @@ impl File for Pipe {
                         panic!("cannot read byte from ring buffer")
                     }
                 }
+                drop(inner);
+                pollers.wake_all();
             };
             suspend_current_and_run_other_task()?;
         }
@@ -155,14 +172,17 @@ impl File for Pipe {
     /// if they do not exist, they will check whether there are still readers on the side, 
     /// and if they do not, they will immediately end the current task.
     /// 
+    /// The writing which has not written any byte will be interrupted by the pending signal.
+    ///
     /// See [`crate::fs::File`]
     /// 
     /// - Errors
     ///     - ProcessHaveNotTask
     ///     - EOB
+    ///     - SyscallInterrupted
     fn write(&self, buffers: ByteBuffers) -> Result<u64> {
-        let tap = if let Self::Write(tap) = self {
-            tap
+        let (tap, pollers) = if let Self::Write(tap, pollers) = self {
+            (tap, pollers)
         } else {
             panic!("writing read only pipe");
         };
@@ -186,6 +206,9 @@ impl File for Pipe {
                     if self.all_read_end_closed() {
                         return Ok(already_written_size);
                     }
+                    if already_written_size == 0 && current_has_pending_signal()? {
+                        return Err(KernelError::SyscallInterrupted);
+                    }
                     drop(inner);
                     suspend_current_and_run_other_task()?;
                     continue;
@@ -198,9 +221,125 @@ impl File for Pipe {
                         panic!("no more byte from byte buffers")
                     }
                 }
+                drop(inner);
+                pollers.wake_all();
             };
             suspend_current_and_run_other_task()?;
         }
         Ok(already_written_size)
     }
+
+    /// The readable pipe is ready when there are unread bytes or all writers have been closed,
+    /// the writable pipe is ready when there is room in the buffer or all readers have been closed.
+    ///
+    /// See [`crate::fs::File`]
+    fn poll_ready(&self, events: PollEvents) -> bool {
+        match self {
+            Self::Read(tap, _) => {
+                events.contains(PollEvents::IN)
+                    && (tap.lock().len() > 0 || self.all_write_end_closed())
+            }
+            Self::Write(tap, _) => {
+                events.contains(PollEvents::OUT)
+                    && tap.upgrade().map_or(true, |buffer| {
+                        let inner = buffer.lock();
+                        inner.len() < inner.capacity()
+                    })
+            }
+        }
+    }
+
+    /// See [`crate::fs::File`]
+    fn poll_wait_queue(&self) -> Option<&PollWaitQueue> {
+        match self {
+            Self::Read(_, pollers) | Self::Write(_, pollers) => Some(pollers),
+        }
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+impl Drop for Pipe {
+    /// Wake the polling tasks up, because the other side may see the end of file
+    /// or the closed reader after this side is closed
+    fn drop(&mut self) {
+        match self {
+            Self::Read(_, pollers) | Self::Write(_, pollers) => pollers.wake_all(),
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task::model::ForkedProcess;
+    use crate::task::TASK_SCHEDULER;
+
+    #[test_case]
+    fn test_pipe_poll_ready() {
+        let read_tap = Pipe::new(3);
+        let write_tap = read_tap.writable_fork().unwrap();
+        assert!(!read_tap.poll_ready(PollEvents::IN));
+        assert!(!read_tap.poll_ready(PollEvents::OUT));
+        assert!(write_tap.poll_ready(PollEvents::OUT));
+        if let Pipe::Read(tap, _) = &read_tap {
+            let mut inner = tap.lock();
+            for byte in 0..3 {
+                inner.write_byte(byte).unwrap();
+            }
+        }
+        assert!(read_tap.poll_ready(PollEvents::IN));
+        assert!(!write_tap.poll_ready(PollEvents::OUT));
+        drop(write_tap);
+        assert!(read_tap.poll_ready(PollEvents::IN));
+        let write_tap = read_tap.writable_fork().unwrap();
+        drop(read_tap);
+        assert!(write_tap.poll_ready(PollEvents::OUT));
+    }
+
+    #[test_case]
+    fn test_pipe_close_wakes_pollers() {
+        let process = ForkedProcess::new();
+        let task = process.inner_access().root_task();
+        let read_tap = Pipe::new(3);
+        let write_tap = read_tap.writable_fork().unwrap();
+        let pollers = read_tap.poll_wait_queue().unwrap();
+        pollers.register(&task);
+        pollers.register(&task);
+        task.mark_blocked();
+        TASK_SCHEDULER.put_sleep_task(usize::MAX, Arc::clone(&task));
+        // the polling reader will see the end of file after the writer is closed
+        drop(write_tap);
+        assert!(!task.is_blocked());
+        assert!(read_tap.poll_ready(PollEvents::IN));
+        assert!(TASK_SCHEDULER.fetch_specific(&task));
+        assert!(Arc::ptr_eq(&TASK_SCHEDULER.pop_ready_task().unwrap(), &task));
+        // the unregistered task will not be woken up from the other sleeping
+        pollers.unregister(&task);
+        task.mark_blocked();
+        TASK_SCHEDULER.put_sleep_task(usize::MAX, Arc::clone(&task));
+        assert_eq!(pollers.wake_all(), 0);
+        assert!(task.is_blocked());
+        TASK_SCHEDULER.remove_timer(&task);
+    }
+
+    #[test_case]
+    fn test_pipe_is_not_truncatable() {
+        let read_tap = Pipe::new(3);
+        assert!(read_tap
+            .truncate(0)
+            .is_err_and(|e| e.is_filenottruncatable()));
+    }
+
+    #[test_case]
+    fn test_pipe_is_not_seekable() {
+        let read_tap = Pipe::new(3);
+        assert!(read_tap
+            .read_at(ByteBuffers::new(vec![], 0), 0)
+            .is_err_and(|e| e.is_filenotseekable()));
+        assert!(read_tap
+            .write_at(ByteBuffers::new(vec![], 0), 0)
+            .is_err_and(|e| e.is_filenotseekable()));
+    }
 }
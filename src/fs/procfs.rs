@@ -0,0 +1,168 @@
+// @author:    olinex
+// @time:      2024/08/21
+
+// self mods
+
+// use other mods
+use alloc::format;
+use alloc::string::String;
+use alloc::sync::{Arc, Weak};
+use core::any::Any;
+use spin::Mutex;
+
+// use self mods
+use super::File;
+use crate::lang::buffer::ByteBuffers;
+use crate::prelude::*;
+use crate::task::model::ProcessControlBlock;
+use crate::task::PROCESS_TABLE;
+
+/// The path prefix of the process files, the full path is like `/proc/<pid>/<name>`
+const PROC_PATH_PREFIX: &'static str = "/proc/";
+const PROC_PATH_SPLITER: char = '/';
+
+/// The kinds of the files in each process directory.
+/// To add a new kind of file, add the variant with it's file name and the rendering of it's content.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum ProcFileKind {
+    /// The summary of the process, such as the status, the memory size and the count of the files
+    Status,
+}
+impl ProcFileKind {
+    /// Get the kind of the file by the file name
+    ///
+    /// - Arguments
+    ///     - name: the file name in the process directory
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "status" => Some(Self::Status),
+            _ => None,
+        }
+    }
+
+    /// Render the content of the file from the current state of the process
+    ///
+    /// - Arguments
+    ///     - process: the process which the file belongs to
+    fn render(&self, process: &Arc<ProcessControlBlock>) -> String {
+        match self {
+            Self::Status => {
+                let status = process.status();
+                let ppid = process.ppid();
+                let inner = process.inner_access();
+                format!(
+                    "pid: {}\nppid: {}\nstatus: {:?}\npath: {}\ntasks: {}\nmemory: {}\nfds: {}\n",
+                    process.pid(),
+                    ppid,
+                    status,
+                    inner.path(),
+                    inner.task_count(),
+                    inner.space().byte_size(),
+                    inner.fd_count(),
+                )
+            }
+        }
+    }
+}
+
+/// A read-only file which is not backed by the block device,
+/// it's content will be rendered from the state of the process on each reading.
+pub(crate) struct ProcFile {
+    process: Weak<ProcessControlBlock>,
+    kind: ProcFileKind,
+    /// The byte offset of the rendered content which has been read
+    offset: Mutex<usize>,
+}
+impl ProcFile {
+    /// Create a new process file
+    ///
+    /// - Arguments
+    ///     - process: the process which the file belongs to
+    ///     - kind: the kind of the file
+    pub(crate) fn new(process: &Arc<ProcessControlBlock>, kind: ProcFileKind) -> Self {
+        Self {
+            process: Arc::downgrade(process),
+            kind,
+            offset: Mutex::new(0),
+        }
+    }
+}
+impl File for ProcFile {
+    /// Read the rendered content from the current offset,
+    /// zero will be returned if the process was recycled
+    fn read(&self, buffers: ByteBuffers) -> Result<u64> {
+        let process = match self.process.upgrade() {
+            Some(process) => process,
+            None => return Ok(0),
+        };
+        let content = self.kind.render(&process);
+        let mut offset = self.offset.lock();
+        let mut bytes = content.as_bytes().get(*offset..).unwrap_or(&[]);
+        let mut count = 0;
+        for slice in buffers.into_slices() {
+            if bytes.is_empty() {
+                break;
+            }
+            let len = slice.len().min(bytes.len());
+            slice[..len].copy_from_slice(&bytes[..len]);
+            bytes = &bytes[len..];
+            count += len;
+        }
+        *offset += count;
+        Ok(count as u64)
+    }
+
+    /// process file is not writable, always return zero
+    fn write(&self, _: ByteBuffers) -> Result<u64> {
+        Ok(0)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Find the process file by the path like `/proc/<pid>/<name>`.
+/// None will be returned if the path is not a process file or the process does not exist.
+///
+/// - Arguments
+///     - path: the path of the process file
+pub(crate) fn find_proc(path: &str) -> Option<Arc<dyn File>> {
+    let (pid, name) = path
+        .strip_prefix(PROC_PATH_PREFIX)?
+        .split_once(PROC_PATH_SPLITER)?;
+    let kind = ProcFileKind::from_name(name)?;
+    let process = PROCESS_TABLE.get(pid.parse().ok()?)?;
+    Some(Arc::new(ProcFile::new(&process, kind)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task::model::INIT_PROC;
+    use alloc::vec;
+
+    fn new_buffers(bytes: &mut [u8]) -> ByteBuffers {
+        let len = bytes.len();
+        let slice = unsafe { core::slice::from_raw_parts_mut(bytes.as_mut_ptr(), len) };
+        ByteBuffers::new(vec![slice], len)
+    }
+
+    #[test_case]
+    fn test_find_proc_status() {
+        let pid = INIT_PROC.pid();
+        assert!(find_proc("/proc/status").is_none());
+        assert!(find_proc("/proc/x/status").is_none());
+        assert!(find_proc(&format!("/proc/{}/unknown", pid)).is_none());
+        let file = find_proc(&format!("/proc/{}/status", pid)).unwrap();
+        let mut bytes = [0u8; 256];
+        assert_eq!(file.read(new_buffers(&mut bytes[..4])).unwrap(), 4);
+        assert_eq!(&bytes[..4], b"pid:");
+        // the next reading continues from the offset
+        let count = file.read(new_buffers(&mut bytes[4..])).unwrap() as usize;
+        let content = core::str::from_utf8(&bytes[..4 + count]).unwrap();
+        assert!(content.starts_with(&format!("pid: {}\n", pid)));
+        assert!(content.contains("fds: "));
+        assert_eq!(file.read(new_buffers(&mut bytes)).unwrap(), 0);
+    }
+}
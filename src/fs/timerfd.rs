@@ -0,0 +1,208 @@
+// @author:    olinex
+// @time:      2024/08/09
+
+// self mods
+
+// use other mods
+use core::any::Any;
+use core::mem::size_of;
+
+// use self mods
+use super::{File, PollEvents};
+use crate::configs;
+use crate::lang::buffer::ByteBuffers;
+use crate::lang::container::UserPromiseRefCell;
+use crate::lang::timer::TimeVal;
+use crate::prelude::*;
+use crate::task::{current_has_pending_signal, sleep_current_and_run_other_task, TASK_SCHEDULER};
+
+/// The setting of the timer shared with the user space.
+/// The zero value disarms the timer, and the zero interval makes the timer one-shot.
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub(crate) struct TimerSpec {
+    /// The time until the next expiration
+    pub(crate) value: TimeVal,
+    /// The period of the timer after the first expiration
+    pub(crate) interval: TimeVal,
+}
+
+/// The mutable states of the timerfd
+#[derive(Debug, Default)]
+struct TimerFdInner {
+    /// The microseconds since boot of the next expiration, None if the timer is disarmed
+    expire_us: Option<usize>,
+    /// The period of the timer in microseconds, zero means the timer is one-shot
+    interval_us: usize,
+}
+impl TimerFdInner {
+    /// Get the count of the expirations which were not read.
+    /// The expirations of the periodic timer are coalesced, so none of them will be lost.
+    ///
+    /// - Arguments
+    ///     - now_us: the current microseconds since boot
+    fn expirations(&self, now_us: usize) -> u64 {
+        match self.expire_us {
+            Some(expire_us) if expire_us <= now_us => match self.interval_us {
+                0 => 1,
+                interval_us => ((now_us - expire_us) / interval_us + 1) as u64,
+            },
+            _ => 0,
+        }
+    }
+
+    /// Consume the expirations and arm the periodic timer for the next expiration
+    ///
+    /// - Arguments
+    ///     - count: the count of the consumed expirations
+    fn consume(&mut self, count: u64) {
+        self.expire_us = match (self.expire_us, self.interval_us) {
+            (Some(expire_us), interval_us) if interval_us != 0 => {
+                Some(expire_us.saturating_add(interval_us.saturating_mul(count as usize)))
+            }
+            _ => None,
+        };
+    }
+
+    /// Get the setting of the timer relative to now
+    ///
+    /// - Arguments
+    ///     - now_us: the current microseconds since boot
+    fn spec(&self, now_us: usize) -> TimerSpec {
+        TimerSpec {
+            value: TimeVal::from_us(
+                self.expire_us
+                    .map_or(0, |expire_us| expire_us.saturating_sub(now_us).max(1)),
+            ),
+            interval: TimeVal::from_us(self.interval_us),
+        }
+    }
+}
+
+/// The file which becomes readable when the one-shot or periodic timer expires.
+/// The reading returns the count of the expirations since the last reading
+/// in an 8-byte buffer, and blocks the current task in the sleep heap until the timer expires.
+pub(crate) struct TimerFd {
+    inner: UserPromiseRefCell<TimerFdInner>,
+}
+impl TimerFd {
+    /// Create a new disarmed timerfd
+    pub(crate) fn new() -> Self {
+        Self {
+            inner: unsafe { UserPromiseRefCell::new(TimerFdInner::default()) },
+        }
+    }
+
+    /// Arm or disarm the timer, and return the previous setting.
+    /// The expirations which were not read are dropped.
+    ///
+    /// - Arguments
+    ///     - spec: the new setting of the timer
+    pub(crate) fn settime(&self, spec: TimerSpec) -> TimerSpec {
+        let now_us = TASK_SCHEDULER.now_us();
+        let mut inner = self.inner.exclusive_access();
+        let old = inner.spec(now_us);
+        let value_us = spec.value.as_us();
+        inner.expire_us = (value_us != 0).then(|| now_us.saturating_add(value_us));
+        inner.interval_us = spec.interval.as_us();
+        old
+    }
+}
+impl File for TimerFd {
+    /// Take the count of the expirations,
+    /// the current task will sleep until the timer expires or a signal arrives.
+    /// The sleeping is split by the poll interval, so the new setting of the timer can be noticed.
+    ///
+    /// - Errors
+    ///     - ProcessHaveNotTask
+    ///     - InvalidTimerFdAccess(len)
+    ///     - SyscallInterrupted
+    ///     - EOB
+    fn read(&self, buffers: ByteBuffers) -> Result<u64> {
+        if buffers.len() < size_of::<u64>() {
+            return Err(KernelError::InvalidTimerFdAccess(buffers.len()));
+        }
+        let count = loop {
+            let now_us = TASK_SCHEDULER.now_us();
+            let mut inner = self.inner.exclusive_access();
+            let count = inner.expirations(now_us);
+            if count > 0 {
+                inner.consume(count);
+                break count;
+            }
+            let remain_us = inner
+                .expire_us
+                .map_or(configs::POLL_INTERVAL_US, |expire_us| expire_us - now_us);
+            drop(inner);
+            sleep_current_and_run_other_task(remain_us.min(configs::POLL_INTERVAL_US))?;
+            if current_has_pending_signal()? {
+                return Err(KernelError::SyscallInterrupted);
+            }
+        };
+        let mut iterator = buffers.into_iter();
+        for byte in count.to_ne_bytes() {
+            iterator.next_mut(byte)?;
+        }
+        Ok(size_of::<u64>() as u64)
+    }
+
+    /// timerfd is not writable, always return zero
+    fn write(&self, _: ByteBuffers) -> Result<u64> {
+        Ok(0)
+    }
+
+    /// The timerfd is readable when the timer has expired, and it is never writable
+    fn poll_ready(&self, events: PollEvents) -> bool {
+        events.contains(PollEvents::IN)
+            && self.inner.access().expirations(TASK_SCHEDULER.now_us()) > 0
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_timerfd_periodic_expirations_coalesce() {
+        let mut inner = TimerFdInner {
+            expire_us: Some(100),
+            interval_us: 30,
+        };
+        assert_eq!(inner.expirations(99), 0);
+        assert_eq!(inner.expirations(100), 1);
+        // the timer fired at 100, 130, 160 and 190 without being read
+        assert_eq!(inner.expirations(200), 4);
+        inner.consume(4);
+        assert_eq!(inner.expire_us, Some(220));
+        assert_eq!(inner.expirations(219), 0);
+        assert_eq!(inner.spec(200).value, TimeVal::from_us(20));
+    }
+
+    #[test_case]
+    fn test_timerfd_one_shot_and_disarmed() {
+        let mut inner = TimerFdInner {
+            expire_us: Some(100),
+            interval_us: 0,
+        };
+        assert_eq!(inner.expirations(1000), 1);
+        inner.consume(1);
+        assert!(inner.expire_us.is_none());
+        assert_eq!(inner.expirations(1000), 0);
+        assert_eq!(inner.spec(1000), TimerSpec::default());
+        let timerfd = TimerFd::new();
+        assert!(!timerfd.poll_ready(PollEvents::IN));
+        let spec = TimerSpec {
+            value: TimeVal::from_us(1_000_000),
+            interval: TimeVal::default(),
+        };
+        assert_eq!(timerfd.settime(spec), TimerSpec::default());
+        let old = timerfd.settime(TimerSpec::default());
+        assert!(old.value.as_us() > 0);
+        assert_eq!(old.interval, TimeVal::default());
+        assert_eq!(timerfd.settime(TimerSpec::default()), TimerSpec::default());
+    }
+}
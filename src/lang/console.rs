@@ -5,10 +5,15 @@
 
 // use other mods
 use core::fmt::{self, Write};
+use spin::Mutex;
 
 // use self mods
 use crate::sbi::*;
 
+/// The lock of the console output, which is shared by the kernel printing and the console device,
+/// so that the kernel logs will never be interleaved into the middle of the user outputs.
+pub(crate) static CONSOLE_WRITER: Mutex<()> = Mutex::new(());
+
 struct Stdout;
 impl Write for Stdout {
     fn write_str(&mut self, s: &str) -> fmt::Result {
@@ -21,5 +26,16 @@ impl Write for Stdout {
 
 // impl rust buildin print function
 pub(crate) fn print(args: fmt::Arguments) {
+    let lock = CONSOLE_WRITER.lock();
     Stdout.write_fmt(args).unwrap();
+    drop(lock);
+}
+
+/// Release the console lock which may be held by the panicking code,
+/// so that the panic handler can still print the messages.
+/// It must only be called by the panic handler, because the holder will never release it.
+pub(crate) unsafe fn force_unlock() {
+    if CONSOLE_WRITER.is_locked() {
+        CONSOLE_WRITER.force_unlock();
+    }
 }
@@ -4,13 +4,25 @@
 // self mods
 
 // use other mods
+#[cfg(debug_assertions)]
+use core::cell::Cell;
 use core::cell::{Ref, RefCell, RefMut};
+use core::ops::{Deref, DerefMut};
+use core::panic::Location;
 
 // use self mods
 
 pub(crate) struct UserPromiseRefCell<T> {
     // inner data
     inner: RefCell<T>,
+    // The caller location of the outstanding borrow, only kept in debug builds.
+    // For the shared borrows, it is the location of the first one which is still outstanding,
+    // and it is cleared when all the outstanding borrows are released.
+    #[cfg(debug_assertions)]
+    borrowed_at: Cell<Option<&'static Location<'static>>>,
+    // The count of the outstanding shared borrows, only kept in debug builds.
+    #[cfg(debug_assertions)]
+    shared_count: Cell<usize>,
 }
 // force mark UPSafeCell as a Sync safe struct
 unsafe impl<T> Sync for UserPromiseRefCell<T> {}
@@ -19,16 +31,139 @@ impl<T> UserPromiseRefCell<T> {
     pub(crate) unsafe fn new(value: T) -> Self {
         Self {
             inner: RefCell::new(value),
+            #[cfg(debug_assertions)]
+            borrowed_at: Cell::new(None),
+            #[cfg(debug_assertions)]
+            shared_count: Cell::new(0),
         }
     }
 
-    // Panic if the data has been borrowed.
-    pub(crate) fn exclusive_access(&self) -> RefMut<'_, T> {
-        self.inner.borrow_mut()
+    // Panic if the data has been borrowed, the panic message contains the caller location.
+    #[track_caller]
+    pub(crate) fn exclusive_access(&self) -> UserPromiseRefMut<'_, T> {
+        match self.inner.try_borrow_mut() {
+            Ok(inner) => {
+                #[cfg(debug_assertions)]
+                self.borrowed_at.set(Some(Location::caller()));
+                UserPromiseRefMut { inner, cell: self }
+            }
+            Err(_) => self.conflict("exclusively"),
+        }
+    }
+
+    // Only read borrowed, panic if the data has been exclusively borrowed.
+    #[track_caller]
+    pub(crate) fn access(&self) -> UserPromiseRef<'_, T> {
+        match self.try_access() {
+            Some(inner) => inner,
+            None => self.conflict("shared"),
+        }
+    }
+
+    // Only read borrowed, return None instead of panic if the data has been exclusively borrowed.
+    #[track_caller]
+    pub(crate) fn try_access(&self) -> Option<UserPromiseRef<'_, T>> {
+        let inner = self.inner.try_borrow().ok()?;
+        #[cfg(debug_assertions)]
+        {
+            if self.shared_count.get() == 0 {
+                self.borrowed_at.set(Some(Location::caller()));
+            }
+            self.shared_count.set(self.shared_count.get() + 1);
+        }
+        Some(UserPromiseRef { inner, cell: self })
+    }
+
+    // Panic because of the conflicting borrow at the caller location,
+    // the location of the outstanding borrow is also reported in debug builds.
+    #[cold]
+    #[track_caller]
+    fn conflict(&self, kind: &str) -> ! {
+        #[cfg(debug_assertions)]
+        if let Some(location) = self.borrowed_at.get() {
+            panic!(
+                "Cannot borrow {} at {}, the outstanding borrow was taken at {}",
+                kind,
+                Location::caller(),
+                location
+            );
+        }
+        panic!("Cannot borrow {} at {}", kind, Location::caller())
+    }
+}
+
+// The shared borrow of the user promise ref cell,
+// which releases the recorded location when the last shared borrow is dropped.
+pub(crate) struct UserPromiseRef<'a, T> {
+    inner: Ref<'a, T>,
+    #[cfg_attr(not(debug_assertions), allow(dead_code))]
+    cell: &'a UserPromiseRefCell<T>,
+}
+impl<T> Deref for UserPromiseRef<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner
     }
+}
+impl<T> Drop for UserPromiseRef<'_, T> {
+    fn drop(&mut self) {
+        #[cfg(debug_assertions)]
+        {
+            let count = self.cell.shared_count.get() - 1;
+            self.cell.shared_count.set(count);
+            if count == 0 {
+                self.cell.borrowed_at.set(None);
+            }
+        }
+    }
+}
+
+// The exclusive borrow of the user promise ref cell,
+// which releases the recorded location when it is dropped.
+pub(crate) struct UserPromiseRefMut<'a, T> {
+    inner: RefMut<'a, T>,
+    #[cfg_attr(not(debug_assertions), allow(dead_code))]
+    cell: &'a UserPromiseRefCell<T>,
+}
+impl<T> Deref for UserPromiseRefMut<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+impl<T> DerefMut for UserPromiseRefMut<'_, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.inner
+    }
+}
+impl<T> Drop for UserPromiseRefMut<'_, T> {
+    fn drop(&mut self) {
+        #[cfg(debug_assertions)]
+        self.cell.borrowed_at.set(None);
+    }
+}
+
+#[cfg(all(test, debug_assertions))]
+mod tests {
+    use super::*;
 
-    // Only read borrowed
-    pub(crate) fn access(&self) -> Ref<'_, T> {
-        self.inner.borrow()
+    #[test_case]
+    fn test_borrowed_at_tracks_outstanding_borrow() {
+        let cell = unsafe { UserPromiseRefCell::new(0usize) };
+        *cell.exclusive_access() += 1;
+        assert!(cell.borrowed_at.get().is_none());
+        let first = cell.access();
+        let location = cell.borrowed_at.get().unwrap();
+        let second = cell.access();
+        // the first outstanding shared borrow is still reported after the later ones
+        assert_eq!(cell.borrowed_at.get().unwrap(), location);
+        drop(first);
+        assert!(cell.borrowed_at.get().is_some());
+        drop(second);
+        assert!(cell.borrowed_at.get().is_none());
+        assert_eq!(*cell.access(), 1);
+        assert!(cell.borrowed_at.get().is_none());
     }
 }
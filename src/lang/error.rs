@@ -78,6 +78,18 @@ pub(crate) enum KernelError {
     #[error("VPN {0} was not mapped")]
     VPNNotMapped(usize),
 
+    #[groups(memory, va)]
+    #[error("Virtual address {0:#x} overflow")]
+    VirtualAddressOverflow(usize),
+
+    #[groups(memory, va)]
+    #[error("User range [{0:#x}, +{1:#x}) is not accessible")]
+    UserRangeNotAccessible(usize, usize),
+
+    #[groups(memory, va)]
+    #[error("String is not terminated in {0} bytes")]
+    UnterminatedString(usize),
+
     #[groups(memory, page_table)]
     #[error("Try to allocate new page table entry from a full page mapper {0}")]
     AllocFullPageMapper(usize),
@@ -90,14 +102,50 @@ pub(crate) enum KernelError {
     #[error("Page table get invalid permission flags: {0}")]
     InvaidPageTablePerm(usize),
 
+    #[groups(memory, shm)]
+    #[error("Shared memory segment {0} does not exists")]
+    ShmDoesNotExist(usize),
+
+    #[groups(memory, cgroup)]
+    #[error("Memory cgroup {0} exceeds the frame budget")]
+    CgroupBudgetExceeded(usize),
+
+    #[groups(memory, swap)]
+    #[error("Swap slot {0} read or write failed")]
+    SwapIOFailed(usize),
+
     #[groups(task)]
     #[error("Invalid headless task")]
     InvalidHeadlessTask,
 
+    #[groups(task)]
+    #[error("Invalid kernel stack byte size {0}")]
+    InvalidKernelStackSize(usize),
+
+    #[groups(task)]
+    #[error("Invalid user stack byte size {0}")]
+    InvalidUserStackSize(usize),
+
+    #[groups(task)]
+    #[error("User stack of task {0} overlaps the trap context area")]
+    UserStackOutOfLayout(usize),
+
     #[groups(task)]
     #[error("Unloadable task")]
     UnloadableTask,
 
+    #[groups(task)]
+    #[error("Elf segment [{0:#x}, {1:#x}) is out of the user space range")]
+    ElfSegmentOutOfRange(usize, usize),
+
+    #[groups(task)]
+    #[error("Dynamic linking is unsupported")]
+    DynamicLinkingUnsupported,
+
+    #[groups(task)]
+    #[error("Joining task {0} will cause deadlock")]
+    JoinDeadlock(usize),
+
     #[groups(process)]
     #[error("Process have not task")]
     ProcessHaveNotTask,
@@ -110,6 +158,14 @@ pub(crate) enum KernelError {
     #[error("Exec other code in multi task process {0}")]
     ExecWithMultiTasks(usize),
 
+    #[groups(process)]
+    #[error("Process exceeds the limit of {0} tasks")]
+    TaskLimitExceeded(usize),
+
+    #[groups(process)]
+    #[error("Process exceeds the limit of {0} bytes address space")]
+    SpaceLimitExceeded(usize),
+
     #[groups(fs)]
     #[error("Invalid open flags {0:#x}")]
     InvalidOpenFlags(u32),
@@ -126,6 +182,14 @@ pub(crate) enum KernelError {
     #[error("File {0} does not exists")]
     FileDoesNotExists(String),
 
+    #[groups(fs)]
+    #[error("Device {0} was not mounted")]
+    DeviceNotMounted(usize),
+
+    #[groups(fs)]
+    #[error("Too many symbolic links were followed when resolving {0}")]
+    SymlinkLoop(String),
+
     #[groups(vfs)]
     #[error("Inode {0} must be readable")]
     FileMustBeReadable(u32),
@@ -138,10 +202,46 @@ pub(crate) enum KernelError {
     #[error("Inode {0} must be executable")]
     FileMustBeExecutable(u32),
 
+    #[groups(vfs)]
+    #[error("Inode {0} must not be directory")]
+    FileMustNotBeDirectory(u32),
+
+    #[groups(vfs)]
+    #[error("Inode {0} does not permit the access")]
+    FilePermissionDenied(u32),
+
+    #[groups(vfs)]
+    #[error("File cannot be truncated")]
+    FileNotTruncatable,
+
+    #[groups(vfs)]
+    #[error("File cannot be accessed at the explicit offset")]
+    FileNotSeekable,
+
+    #[groups(vfs)]
+    #[error("Message with {0} bytes is too long")]
+    MessageTooLong(usize),
+
+    #[groups(vfs)]
+    #[error("Eventfd cannot be accessed with {0} bytes buffer or the max value")]
+    InvalidEventFdAccess(usize),
+
+    #[groups(vfs)]
+    #[error("Timerfd cannot be read with {0} bytes buffer")]
+    InvalidTimerFdAccess(usize),
+
     #[groups(signal)]
     #[error("Duplicate signal {0:?} as setting")]
     DuplicateSignal(Signal),
 
+    #[groups(signal)]
+    #[error("The queue of real-time signal {0} is full")]
+    SignalQueueFull(usize),
+
+    #[groups(signal)]
+    #[error("The blocking syscall was interrupted by signal")]
+    SyscallInterrupted,
+
     #[groups(sync)]
     #[error("Double lock mutex")]
     DoubleLockMutex,
@@ -150,6 +250,10 @@ pub(crate) enum KernelError {
     #[error("Double unlock mutex")]
     DoubleUnlockMutex,
 
+    #[groups(sync)]
+    #[error("Deadlock detected")]
+    Deadlock,
+
     #[groups(sync)]
     #[error("Mutex exhausted")]
     MutexExhausted,
@@ -158,6 +262,10 @@ pub(crate) enum KernelError {
     #[error("Mutex {0} does not exists")]
     MutexDoesNotExist(usize),
 
+    #[groups(sync)]
+    #[error("Invalid mutex kind: {0}")]
+    InvalidMutexKind(usize),
+
     #[groups(sync)]
     #[error("Semaphore exhausted")]
     SemaphoreExhausted,
@@ -174,6 +282,14 @@ pub(crate) enum KernelError {
     #[error("Condvar {0} does not exists")]
     CondvarDoesNotExist(usize),
 
+    #[groups(sync)]
+    #[error("Barrier exhausted")]
+    BarrierExhausted,
+
+    #[groups(sync)]
+    #[error("Barrier {0} does not exists")]
+    BarrierDoesNotExist(usize),
+
     #[groups(others, lib)]
     #[error("Lib error: {0}")]
     LibError(#[from] LibError),
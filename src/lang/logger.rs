@@ -4,33 +4,276 @@
 // self mods
 
 // use other mods
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use core::fmt::{self, Write};
+use core::sync::atomic::{AtomicUsize, Ordering};
 use log::{LevelFilter, Metadata, Record};
+use spin::Mutex;
 
 // use self mods
+use super::buffer::RingBuffer;
+use super::timer;
 use crate::configs;
 use crate::println;
 
-pub(crate) struct KernelLogger;
+/// The prefix of the targets of the kernel's log records, which will be stripped before matching
+const KERNEL_TARGET_PREFIX: &'static str = "frontier_kernel::";
+const MODULE_SPLITER: &'static str = "::";
+
+/// Check if the module path is the target itself or one of it's sub modules
+///
+/// - Arguments
+///     - target: the target which was configured
+///     - module: the module path of the log record
+fn is_module_of(target: &str, module: &str) -> bool {
+    match module.strip_prefix(target) {
+        Some(rest) => rest.is_empty() || rest.starts_with(MODULE_SPLITER),
+        None => false,
+    }
+}
+
+/// The log levels of the targets, the default level will be used if no target was matched
+pub(crate) struct LogLevels {
+    default: LevelFilter,
+    targets: BTreeMap<String, LevelFilter>,
+}
+impl LogLevels {
+    /// Create the log levels from the configs
+    fn new() -> Self {
+        Self {
+            default: configs::LOG_LEVEL.to_level_filter(),
+            targets: configs::LOG_TARGET_LEVELS
+                .iter()
+                .map(|(target, level)| (target.to_string(), *level))
+                .collect(),
+        }
+    }
+
+    /// Get the level of the module path by the longest matched target
+    ///
+    /// - Arguments
+    ///     - module: the module path of the log record
+    fn level_of(&self, module: &str) -> LevelFilter {
+        let module = module.strip_prefix(KERNEL_TARGET_PREFIX).unwrap_or(module);
+        self.targets
+            .iter()
+            .filter(|(target, _)| is_module_of(target, module))
+            .max_by_key(|(target, _)| target.len())
+            .map_or(self.default, |(_, level)| *level)
+    }
+
+    /// Set the level of the target, the empty target means the default level
+    ///
+    /// - Arguments
+    ///     - target: the module path without the crate name
+    ///     - level: the new level filter
+    fn set(&mut self, target: &str, level: LevelFilter) {
+        if target.is_empty() {
+            self.default = level;
+        } else {
+            self.targets.insert(target.to_string(), level);
+        }
+    }
+
+    /// Get the most verbose level of all targets,
+    /// the log records above it will be filtered by the log macros before formatting
+    fn max_level(&self) -> LevelFilter {
+        self.targets
+            .values()
+            .copied()
+            .fold(self.default, LevelFilter::max)
+    }
+
+    /// Get the least verbose level of all targets,
+    /// the log records at or below it are enabled whatever their targets are
+    fn min_level(&self) -> LevelFilter {
+        self.targets
+            .values()
+            .copied()
+            .fold(self.default, LevelFilter::min)
+    }
+}
+
+/// The ring buffer which keeps the recent log lines, the oldest bytes will be dropped when it is full.
+/// The buffer was allocated once at the initialization, so that the logging will never allocate on the heap.
+pub(crate) struct LogRing {
+    ring: RingBuffer,
+    /// The count of the bytes which were dropped since the last reading
+    dropped: usize,
+}
+impl LogRing {
+    /// Create a new log ring buffer
+    ///
+    /// - Arguments
+    ///     - capacity: the byte size of the ring buffer
+    fn new(capacity: usize) -> Self {
+        Self {
+            ring: RingBuffer::new(capacity),
+            dropped: 0,
+        }
+    }
+
+    /// Read and consume the oldest bytes into the buffer
+    ///
+    /// - Arguments
+    ///     - buf: the buffer to fill in
+    ///
+    /// - Returns
+    ///     - (count of the bytes read, count of the bytes dropped since the last reading)
+    fn read(&mut self, buf: &mut [u8]) -> (usize, usize) {
+        let mut count = 0;
+        while count < buf.len() {
+            match self.ring.read_byte() {
+                Some(byte) => buf[count] = byte,
+                None => break,
+            }
+            count += 1;
+        }
+        (count, core::mem::take(&mut self.dropped))
+    }
+}
+impl Write for LogRing {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for byte in s.bytes() {
+            if self.ring.write_byte(byte).is_err() {
+                self.ring.read_byte();
+                self.dropped += 1;
+                self.ring.write_byte(byte).map_err(|_| fmt::Error)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+pub(crate) struct KernelLogger {
+    levels: Mutex<Option<LogLevels>>,
+    ring: Mutex<Option<LogRing>>,
+}
 impl log::Log for KernelLogger {
     fn enabled(&self, metadata: &Metadata) -> bool {
-        metadata.level() <= configs::LOG_LEVEL
+        // only the records between the cached least and most verbose levels need the targets
+        let level = metadata.level();
+        if level as usize <= MIN_LEVEL.load(Ordering::Relaxed) {
+            return true;
+        }
+        if level > log::max_level() {
+            return false;
+        }
+        match self.levels.lock().as_ref() {
+            Some(levels) => metadata.level() <= levels.level_of(metadata.target()),
+            None => metadata.level() <= configs::LOG_LEVEL,
+        }
     }
 
     fn log(&self, record: &Record) {
         if self.enabled(record.metadata()) {
             println!("[kernel] {} {}", record.level(), record.args());
+            // never wait for the lock, the record may be logged while the ring buffer is being read
+            if let Some(mut ring) = self.ring.try_lock() {
+                if let Some(ring) = ring.as_mut() {
+                    let _ = writeln!(ring, "[{}] {} {}", timer::now_us(), record.level(), record.args());
+                }
+            }
         }
     }
 
     fn flush(&self) {}
 }
 
-static LOGGER: KernelLogger = KernelLogger;
+/// The cached least verbose level of all targets, see [`LogLevels::min_level`]
+static MIN_LEVEL: AtomicUsize = AtomicUsize::new(configs::LOG_LEVEL as usize);
+
+static LOGGER: KernelLogger = KernelLogger {
+    levels: Mutex::new(None),
+    ring: Mutex::new(None),
+};
+
+/// Read and consume the recent log lines, it will never block.
+/// Nothing will be read before the ring buffer was initialized.
+///
+/// - Arguments
+///     - buf: the buffer to fill in
+///
+/// - Returns
+///     - (count of the bytes read, count of the bytes dropped since the last reading)
+pub(crate) fn read_ring(buf: &mut [u8]) -> (usize, usize) {
+    match LOGGER.ring.lock().as_mut() {
+        Some(ring) => ring.read(buf),
+        None => (0, 0),
+    }
+}
+
+/// Set the log level of the target at runtime.
+///
+/// - Arguments
+///     - target: the module path without the crate name, the empty target means the default level
+///     - level: the new level filter
+pub(crate) fn set_level(target: &str, level: LevelFilter) {
+    let mut levels = LOGGER.levels.lock();
+    let levels = levels.get_or_insert_with(LogLevels::new);
+    levels.set(target, level);
+    MIN_LEVEL.store(levels.min_level() as usize, Ordering::Relaxed);
+    log::set_max_level(levels.max_level());
+}
 
 #[inline(always)]
 pub(crate) fn init() {
     if let Err(error) = log::set_logger(&LOGGER) {
         panic!("Could not set logger cause by {}", error);
     }
-    log::set_max_level(LevelFilter::Trace);
+    // the heap is not ready yet, the levels of the targets and the ring buffer will be set up after memory initialization
+    log::set_max_level(configs::LOG_LEVEL.to_level_filter());
+}
+
+/// Load the log levels of the targets from the configs and allocate the ring buffer,
+/// it must be called after the heap was initialized
+#[inline(always)]
+pub(crate) fn late_init() {
+    let levels = LogLevels::new();
+    MIN_LEVEL.store(levels.min_level() as usize, Ordering::Relaxed);
+    log::set_max_level(levels.max_level());
+    LOGGER.levels.lock().replace(levels);
+    LOGGER
+        .ring
+        .lock()
+        .replace(LogRing::new(configs::LOG_RING_BUFFER_BYTE_SIZE));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_log_levels_longest_target() {
+        let mut levels = LogLevels {
+            default: LevelFilter::Info,
+            targets: BTreeMap::new(),
+        };
+        levels.set("memory", LevelFilter::Debug);
+        levels.set("memory::space", LevelFilter::Warn);
+        assert_eq!(levels.level_of("frontier_kernel::memory::heap"), LevelFilter::Debug);
+        assert_eq!(levels.level_of("frontier_kernel::memory::space"), LevelFilter::Warn);
+        assert_eq!(levels.level_of("frontier_kernel::memoryx"), LevelFilter::Info);
+        assert_eq!(levels.level_of("virtio_drivers::device"), LevelFilter::Info);
+        assert_eq!(levels.max_level(), LevelFilter::Debug);
+        assert_eq!(levels.min_level(), LevelFilter::Warn);
+        levels.set("", LevelFilter::Trace);
+        assert_eq!(levels.level_of("frontier_kernel::task"), LevelFilter::Trace);
+        assert_eq!(levels.max_level(), LevelFilter::Trace);
+        assert_eq!(levels.min_level(), LevelFilter::Warn);
+    }
+
+    #[test_case]
+    fn test_log_ring_drop_oldest_bytes() {
+        let mut ring = LogRing::new(8);
+        let mut buf = [0u8; 8];
+        assert!(write!(ring, "abcdef").is_ok());
+        assert_eq!(ring.read(&mut buf[..4]), (4, 0));
+        assert_eq!(&buf[..4], b"abcd");
+        assert!(write!(ring, "0123456789").is_ok());
+        assert_eq!(ring.read(&mut buf), (8, 4));
+        assert_eq!(&buf, b"23456789");
+        assert_eq!(ring.read(&mut buf), (0, 0));
+    }
 }
@@ -4,15 +4,63 @@
 // self mods
 
 // use other mods
+use core::mem::size_of;
 use core::panic::PanicInfo;
+use core::sync::atomic::{AtomicBool, Ordering};
 
 // use self mods
+use super::console;
+use crate::configs;
 use crate::println;
 use crate::sbi::*;
+use crate::task::PROCESSOR;
+
+/// The max count of the frames which will be printed in the backtrace
+const BACKTRACE_MAX_DEPTH: usize = 16;
+
+/// Set to true once the kernel panic, so the panic in the panic handler will not recurse.
+static PANICKING: AtomicBool = AtomicBool::new(false);
+
+/// Print the return addresses by walking the frame pointers.
+/// In the frame of RISC-V, the return address was saved at `fp - 8` and the previous frame pointer was saved at `fp - 16`.
+/// The walking will stop once the frame pointer is out of the stack window or does not move toward the stack bottom,
+/// so that the corrupted stack will not be dereferenced.
+///
+/// - Arguments
+///     - sp: the current stack pointer
+///     - fp: the current frame pointer
+fn print_backtrace(sp: usize, mut fp: usize) {
+    let window = (configs::_addr_bootstack_end as usize - configs::_addr_bootstack_start as usize)
+        .max(configs::KERNEL_TASK_STACK_MAX_BYTE_SIZE);
+    let limit = sp.saturating_add(window);
+    println!("[kernel] BACKTRACE:");
+    for depth in 0..BACKTRACE_MAX_DEPTH {
+        if fp % size_of::<usize>() != 0 || fp < sp + 2 * size_of::<usize>() || fp > limit {
+            break;
+        }
+        let ra = unsafe { *((fp - size_of::<usize>()) as *const usize) };
+        let prev_fp = unsafe { *((fp - 2 * size_of::<usize>()) as *const usize) };
+        if ra == 0 {
+            break;
+        }
+        println!("[kernel]     #{} ra = {:#x}", depth, ra);
+        if prev_fp <= fp {
+            break;
+        }
+        fp = prev_fp;
+    }
+}
 
 // panic handler must end the process and return noting
 #[panic_handler]
 fn panic(info: &PanicInfo) -> ! {
+    // the panicking code may be writing the console
+    unsafe { console::force_unlock() };
+    if PANICKING.swap(true, Ordering::Relaxed) {
+        println!("[kernel] PANIC AGAIN WHILE PANICKING, give up dumping");
+        SBI::shutdown()
+    }
+    let (sp, ra, fp) = SBI::read_frame_registers();
     match (info.location(), info.message()) {
         (Some(loc), Some(msg)) => {
             println!(
@@ -36,5 +84,18 @@ fn panic(info: &PanicInfo) -> ! {
             println!("[kernel] PANIC AT unknown location, cause by unknown message");
         }
     };
+    // the task may be absent in early boot, or be borrowed by the panicking code
+    match PROCESSOR.try_current_task().and_then(|task| task.try_ids()) {
+        Some((pid, tid)) => println!("[kernel] CURRENT TASK: pid = {}, tid = {}", pid, tid),
+        None => println!("[kernel] CURRENT TASK: unknown"),
+    }
+    println!(
+        "[kernel] REGISTERS: sepc = {:#x}, sp = {:#x}, ra = {:#x}, fp = {:#x}",
+        SBI::read_trap_pc(),
+        sp,
+        ra,
+        fp
+    );
+    print_backtrace(sp, fp);
     SBI::shutdown()
 }
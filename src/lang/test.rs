@@ -9,13 +9,12 @@
 
 #[cfg(test)]
 pub(crate) fn test_runner(tests: &[&dyn Fn()]) -> ! {
-    use crate::sbi::*;
     info!("Running {} tests", tests.len());
     for test in tests {
         test();
     }
     info!("Successfully Finished {} tests", tests.len());
-    SBI::shutdown()
+    crate::fs::sync_and_shutdown()
 }
 
 #[cfg(test)]
@@ -4,25 +4,357 @@
 // self mods
 
 // use other mods
+use core::sync::atomic::{AtomicUsize, Ordering};
+use core::time::Duration;
 
 // use self mods
 use crate::configs;
+use crate::lang::container::UserPromiseRefCell;
 use crate::sbi::*;
 
 const MICRO_PER_SEC: usize = 1_000_000;
+const NANO_PER_SEC: u64 = 1_000_000_000;
+const NANO_PER_MICRO: u64 = 1_000;
 
-/// Set the timer to make cpu can be interrupted
+/// The time value structure shared with the user space,
+/// which is made of seconds and the microseconds less than one second.
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub(crate) struct TimeVal {
+    pub(crate) sec: usize,
+    pub(crate) usec: usize,
+}
+impl TimeVal {
+    /// Create a new time value from microseconds
+    ///
+    /// - Arguments
+    ///     - us: the total microseconds
+    pub(crate) fn from_us(us: usize) -> Self {
+        Self {
+            sec: us / MICRO_PER_SEC,
+            usec: us % MICRO_PER_SEC,
+        }
+    }
+
+    /// Convert current time value to the total microseconds
+    pub(crate) fn as_us(&self) -> usize {
+        self.sec
+            .saturating_mul(MICRO_PER_SEC)
+            .saturating_add(self.usec)
+    }
+}
+
+/// The CPU time consumed by the current task and process in timer ticks,
+/// which is shared with the user space.
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub(crate) struct Times {
+    pub(crate) task_user_time: usize,
+    pub(crate) task_kernel_time: usize,
+    pub(crate) process_user_time: usize,
+    pub(crate) process_kernel_time: usize,
+}
+
+/// The accumulator of the CPU time in timer ticks.
+/// The time elapsed since the last stamp will be attributed to either user time or kernel time.
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub(crate) struct CpuTime {
+    user: usize,
+    kernel: usize,
+    stamp: usize,
+}
+impl CpuTime {
+    /// Restart the accounting from the moment, the time before it will be ignored
+    ///
+    /// - Arguments
+    ///     - now: the current timer ticks
+    pub(crate) fn restart(&mut self, now: usize) {
+        self.stamp = now;
+    }
+
+    /// Accumulate the time elapsed since the last stamp
+    ///
+    /// - Arguments
+    ///     - now: the current timer ticks
+    ///     - user: if true the time is attributed to user time, otherwise kernel time
+    pub(crate) fn account(&mut self, now: usize, user: bool) {
+        let delta = now.saturating_sub(self.stamp);
+        if user {
+            self.user += delta;
+        } else {
+            self.kernel += delta;
+        }
+        self.stamp = now;
+    }
+
+    /// Merge other accumulated time into current one
+    ///
+    /// - Arguments
+    ///     - other: the other accumulator
+    pub(crate) fn merge(&mut self, other: &Self) {
+        self.user += other.user;
+        self.kernel += other.kernel;
+    }
+
+    /// Get the accumulated user time in timer ticks
+    pub(crate) fn user(&self) -> usize {
+        self.user
+    }
+
+    /// Get the accumulated kernel time in timer ticks
+    pub(crate) fn kernel(&self) -> usize {
+        self.kernel
+    }
+}
+
+/// The monotonic clock which extends the fixed-width timer counter.
+/// The rolling over of the counter is detected by comparing with the last read value,
+/// so the clock must be read at least once in each wrap period,
+/// which is always true because [`set_next_trigger`] updates it in each timer tick.
+pub(crate) struct MonotonicClock {
+    /// The count of the values of the counter, the counter rolls over to zero when reaching it
+    modulus: u128,
+    /// The last value read from the counter
+    last: u128,
+    /// The extended ticks when the counter rolled over last time
+    epoch: u128,
+    /// The extended ticks when the kernel was booted
+    boot: u128,
+}
+impl MonotonicClock {
+    /// Create a new clock
+    ///
+    /// - Arguments
+    ///     - bits: the bit width of the timer counter
+    fn new(bits: u32) -> Self {
+        Self {
+            modulus: 1 << bits,
+            last: 0,
+            epoch: 0,
+            boot: 0,
+        }
+    }
+
+    /// Update the clock with the value read from the counter and get the extended ticks
+    ///
+    /// - Arguments
+    ///     - raw: the value of the timer counter
+    fn update(&mut self, raw: usize) -> u128 {
+        let raw = raw as u128 % self.modulus;
+        if raw < self.last {
+            self.epoch += self.modulus;
+        }
+        self.last = raw;
+        self.epoch + raw
+    }
+
+    /// Record the moment when the kernel was booted
+    ///
+    /// - Arguments
+    ///     - raw: the value of the timer counter
+    fn mark_boot(&mut self, raw: usize) {
+        self.boot = self.update(raw);
+    }
+
+    /// Get the ticks elapsed since the kernel was booted
+    ///
+    /// - Arguments
+    ///     - raw: the value of the timer counter
+    fn uptime_ticks(&mut self, raw: usize) -> u128 {
+        self.update(raw) - self.boot
+    }
+}
+
+lazy_static! {
+    /// The clock of the whole timer counter, which is 64 bits on riscv64
+    static ref UPTIME_CLOCK: UserPromiseRefCell<MonotonicClock> =
+        unsafe { UserPromiseRefCell::new(MonotonicClock::new(usize::BITS)) };
+}
+
+/// Record the moment when the kernel finished the initialization
+#[inline(always)]
+pub(crate) fn mark_boot() {
+    UPTIME_CLOCK.exclusive_access().mark_boot(SBI::get_timer());
+}
+
+/// Get the microseconds elapsed since the kernel was booted, the wraps of the counter are counted
+#[inline(always)]
+pub(crate) fn uptime_us() -> usize {
+    let ticks = UPTIME_CLOCK.exclusive_access().uptime_ticks(SBI::get_timer());
+    ticks_to_us_with(ticks, configs::BOARD_CLOCK_FREQ as u64)
+}
+
+/// The realtime microseconds at the boot moment, there is no RTC so it is zero until it is set
+static REALTIME_BOOT_US: AtomicUsize = AtomicUsize::new(0);
+
+/// Get the microseconds of the realtime clock, which is the uptime plus the settable boot moment
+#[inline(always)]
+pub(crate) fn realtime_us() -> usize {
+    REALTIME_BOOT_US.load(Ordering::Relaxed).saturating_add(uptime_us())
+}
+
+/// Set the realtime clock, the uptime clock will not be affected
+///
+/// - Arguments
+///     - us: the microseconds of the realtime clock at the current moment
+#[inline(always)]
+pub(crate) fn set_realtime_us(us: usize) {
+    REALTIME_BOOT_US.store(us.saturating_sub(uptime_us()), Ordering::Relaxed);
+}
+
+/// Set the timer to make cpu can be interrupted,
+/// the uptime clock is updated in each tick, so that the wraps of the counter are never missed
 #[inline(always)]
 pub(crate) fn set_next_trigger() {
-    SBI::set_timer(SBI::get_timer() + (configs::BOARD_CLOCK_FREQ / configs::TICKS_PER_SEC));
+    let now = SBI::get_timer();
+    UPTIME_CLOCK.exclusive_access().update(now);
+    SBI::set_timer(now + (configs::BOARD_CLOCK_FREQ / configs::TICKS_PER_SEC));
 }
 
-/// Get the current timer as microseconds.
-/// Be careful, the timer microseconds isn't the timestamp from 1970-01-01T00:00:00,
-/// it is the timestamp from the moment when cpu was reset:
-///     - 1 seconds = 1000 milliseconds 
+/// Convert the timer ticks to nanoseconds with the clock frequency, the remainder will be truncated.
+///
+/// - Arguments
+///     - ticks: the timer ticks
+///     - freq: the count of the timer ticks in one second
+fn ticks_to_ns_with(ticks: u64, freq: u64) -> u64 {
+    (ticks as u128 * NANO_PER_SEC as u128 / freq as u128) as u64
+}
+
+/// Convert the extended timer ticks to microseconds with the clock frequency,
+/// the remainder will be truncated and the result will be saturated.
+///
+/// - Arguments
+///     - ticks: the extended timer ticks
+///     - freq: the count of the timer ticks in one second
+fn ticks_to_us_with(ticks: u128, freq: u64) -> usize {
+    let us = ticks.saturating_mul(MICRO_PER_SEC as u128) / freq as u128;
+    us.min(usize::MAX as u128) as usize
+}
+
+/// Convert the nanoseconds to timer ticks with the clock frequency,
+/// the ticks will be rounded up so that the waiting will never be shorter than the duration.
+///
+/// - Arguments
+///     - ns: the nanoseconds
+///     - freq: the count of the timer ticks in one second
+fn ns_to_ticks_with(ns: u64, freq: u64) -> u64 {
+    (ns as u128 * freq as u128).div_ceil(NANO_PER_SEC as u128) as u64
+}
+
+/// Convert the timer ticks to the duration
+///
+/// - Arguments
+///     - ticks: the timer ticks
+pub(crate) fn ticks_to_duration(ticks: usize) -> Duration {
+    Duration::from_nanos(ticks_to_ns_with(ticks as u64, configs::BOARD_CLOCK_FREQ as u64))
+}
+
+/// Convert the duration to timer ticks, the ticks will be rounded up
+///
+/// - Arguments
+///     - duration: the duration
+pub(crate) fn duration_to_ticks(duration: Duration) -> usize {
+    let ns = duration.as_nanos().min(u64::MAX as u128) as u64;
+    ns_to_ticks_with(ns, configs::BOARD_CLOCK_FREQ as u64) as usize
+}
+
+/// Get the current timer as nanoseconds.
+/// Be careful, the timer nanoseconds isn't the timestamp from 1970-01-01T00:00:00,
+/// it is the monotonic timestamp from the moment when cpu was reset.
+#[inline(always)]
+pub(crate) fn now_ns() -> u64 {
+    ticks_to_ns_with(SBI::get_timer() as u64, configs::BOARD_CLOCK_FREQ as u64)
+}
+
+/// Get the current timer as microseconds, see [`now_ns`]
+///     - 1 seconds = 1000 milliseconds
 ///     - 1 milliseconds = 1000 microseconds
 #[inline(always)]
-pub(crate) fn get_timer_us() -> usize {
-    SBI::get_timer() * MICRO_PER_SEC / configs::BOARD_CLOCK_FREQ
+pub(crate) fn now_us() -> usize {
+    (now_ns() / NANO_PER_MICRO) as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_time_val_convert() {
+        let tv = TimeVal::from_us(3 * MICRO_PER_SEC + 25);
+        assert_eq!(tv.sec, 3);
+        assert_eq!(tv.usec, 25);
+        assert_eq!(tv.as_us(), 3 * MICRO_PER_SEC + 25);
+        assert_eq!(TimeVal::from_us(0), TimeVal::default());
+    }
+
+    #[test_case]
+    fn test_ticks_convert() {
+        // the board clock, one tick is 80 nanoseconds
+        assert_eq!(ticks_to_ns_with(125, 12_500_000), 10_000);
+        assert_eq!(ns_to_ticks_with(10_000, 12_500_000), 125);
+        assert_eq!(ns_to_ticks_with(10_001, 12_500_000), 126);
+        // one tick is two microseconds
+        assert_eq!(ticks_to_ns_with(3, 500_000), 6_000);
+        assert_eq!(ns_to_ticks_with(5_000, 500_000), 3);
+        assert_eq!(ns_to_ticks_with(1, 500_000), 1);
+        assert_eq!(ns_to_ticks_with(0, 500_000), 0);
+        // no overflow for the large ticks
+        assert_eq!(ticks_to_ns_with(u64::MAX, NANO_PER_SEC), u64::MAX);
+        let ticks = configs::BOARD_CLOCK_FREQ;
+        assert_eq!(ticks_to_duration(ticks), Duration::from_secs(1));
+        assert_eq!(duration_to_ticks(Duration::from_secs(1)), ticks);
+    }
+
+    #[test_case]
+    fn test_monotonic_clock_wraps() {
+        assert_eq!(ticks_to_us_with(125, 12_500_000), 10);
+        assert_eq!(ticks_to_us_with(124, 12_500_000), 9);
+        assert_eq!(ticks_to_us_with(u128::MAX, 1), usize::MAX);
+        // simulate the 8 bits counter which rolls over after 255
+        let mut clock = MonotonicClock::new(8);
+        clock.mark_boot(200);
+        assert_eq!(clock.uptime_ticks(250), 50);
+        assert_eq!(clock.uptime_ticks(10), 66);
+        assert_eq!(clock.uptime_ticks(10), 66);
+        assert_eq!(clock.uptime_ticks(199), 255);
+        assert_eq!(clock.uptime_ticks(5), 317);
+        // the wrap between two reads is counted by the update in the timer tick
+        clock.update(100);
+        clock.update(20);
+        assert_eq!(clock.uptime_ticks(30), 342);
+        // the whole counter never wraps in the tests
+        assert!(uptime_us() <= uptime_us());
+    }
+
+    #[test_case]
+    fn test_realtime_clock() {
+        let epoch_us = 1_700_000_000 * MICRO_PER_SEC;
+        set_realtime_us(epoch_us);
+        let now_us = realtime_us();
+        assert!(now_us >= epoch_us);
+        assert!(now_us - epoch_us < MICRO_PER_SEC);
+        set_realtime_us(0);
+        assert!(realtime_us() <= uptime_us());
+    }
+
+    #[test_case]
+    fn test_cpu_time_account() {
+        let mut time = CpuTime::default();
+        time.restart(10);
+        time.account(15, false);
+        assert_eq!((time.user(), time.kernel()), (0, 5));
+        time.account(25, true);
+        assert_eq!((time.user(), time.kernel()), (10, 5));
+        // the time never goes back
+        time.account(20, true);
+        assert_eq!((time.user(), time.kernel()), (10, 5));
+        // the time between restart and the last stamp will be ignored
+        time.restart(100);
+        time.account(101, false);
+        assert_eq!((time.user(), time.kernel()), (10, 6));
+        let mut total = CpuTime::default();
+        total.merge(&time);
+        total.merge(&time);
+        assert_eq!((total.user(), total.kernel()), (20, 12));
+    }
 }
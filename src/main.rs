@@ -48,6 +48,7 @@ use core::arch::global_asm;
 use core::sync::atomic::{AtomicBool, Ordering};
 
 // use self mods
+use sbi::{SBIApi, SBI};
 
 // re export commonly used modules or functions
 mod prelude {
@@ -84,8 +85,12 @@ fn init() {
     lang::logger::init();
     // init heap/frames/kernel space
     memory::init();
+    // load the log levels and the ring buffer of the logger which need the heap
+    lang::logger::late_init();
     // make process enable
     task::init();
+    // the uptime is counted from the moment when the initialization was finished
+    lang::timer::mark_boot();
     // release initial lock
     release();
 }
@@ -108,7 +113,7 @@ fn main(hartid: usize, _: usize) -> () {
     trap::init();
     cfg_if! {
         if #[cfg(not(test))] {
-            task::run();
+            task::run(hartid);
         } else {
             test_main();
         }
@@ -116,21 +121,29 @@ fn main(hartid: usize, _: usize) -> () {
 }
 
 /// Make current hart to waitting kernel initialzation by hart zero.
+/// The hart will sleep between each checking of the lock instead of busy loop.
+/// Because the global interrupt was still disabled, the pending interrupt only wakes the hart up without trapping,
+/// and the timer will be armed before each sleep, so the hart will never miss the release forever.
 #[inline(always)]
 fn wait() {
+    // acquire ordering pairs with the release ordering in [`release`],
+    // so all the writes of the initialization are visible after the lock was taken
     while INITIALIZED
-        .compare_exchange(false, true, Ordering::Relaxed, Ordering::Relaxed)
+        .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
         .is_err()
     {
-        // TODO: we should make hart sleep a bit instead of busy loop.
-        continue;
+        SBI::set_timer(SBI::get_timer() + configs::BOARD_CLOCK_FREQ / configs::TICKS_PER_SEC);
+        unsafe {
+            SBI::enable_timer_interrupt();
+            SBI::wait_for_interrupt();
+        }
     }
 }
 
 /// Release the global lock of kernel initialzation. Only can by call by hart zero.
 #[inline(always)]
 fn release() {
-    INITIALIZED.store(false, Ordering::Relaxed);
+    INITIALIZED.store(false, Ordering::Release);
 }
 
 /// The global lock of kernel initialization,
@@ -11,6 +11,7 @@ use alloc::collections::BTreeSet;
 use alloc::sync::{Arc, Weak};
 
 // use self mods
+use crate::configs;
 use crate::lang::container::UserPromiseRefCell;
 use crate::lang::error::*;
 
@@ -248,6 +249,46 @@ impl LinkedListPageRangeAllocator {
         pub(crate) fn dealloc(&self, start_vpn: usize, end_vpn: usize) -> Option<()> {
         self.change(start_vpn, end_vpn, false)
     }
+
+    /// Find the first available page interval which is large enough,
+    /// the interval will not be allocated.
+    /// - Arguments
+    ///     - min_vpn: the lowest virtual memory page number of the interval
+    ///     - count: the count of the pages in the interval
+    ///
+    /// - Returns
+    ///     - Some(start_vpn): the first virtual memory page number of the interval
+    ///     - None: there was no available interval
+    pub(crate) fn find_free(&self, min_vpn: usize, count: usize) -> Option<usize> {
+        let mut current_page_node = Arc::clone(&self.root);
+        loop {
+            let current_borrow = current_page_node.access();
+            let next_page_node = Arc::clone(current_borrow.next.as_ref()?);
+            let next_vpn = next_page_node.access().vpn;
+            let start_vpn = current_borrow.vpn.max(min_vpn);
+            if !current_borrow.used && start_vpn.saturating_add(count) <= next_vpn {
+                return Some(start_vpn);
+            }
+            drop(current_borrow);
+            current_page_node = next_page_node;
+        }
+    }
+}
+
+/// The usage statistics of the frame allocator, shared with the user space
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct FrameStats {
+    /// The physical page number which will be allocated when no more recycled frames
+    pub(crate) current_ppn: usize,
+    /// The end physical page number which will not be allocated
+    pub(crate) end_ppn: usize,
+    /// The count of the frames those have been released but not yet allocated
+    pub(crate) recycled: usize,
+    /// The count of the frames which can still be allocated
+    pub(crate) free: usize,
+    /// The count of the free frames below which the memory is treated as low
+    pub(crate) low_watermark: usize,
 }
 
 /// A physical memory frame allocation manager
@@ -261,6 +302,10 @@ pub(crate) struct BTreeSetFrameAllocator {
     end_ppn: usize,
     /// a set of frames those have been released but not yet allocated
     recycled: BTreeSet<usize>,
+    /// the count of the free frames below which the memory is treated as low
+    low_watermark: usize,
+    /// whether the count of the free frames is below the low watermark
+    low: bool,
 }
 impl BTreeSetFrameAllocator {
     /// Create a new BTreeSetFrameAllocator
@@ -269,6 +314,8 @@ impl BTreeSetFrameAllocator {
             current_ppn: 0,
             end_ppn: 0,
             recycled: BTreeSet::new(),
+            low_watermark: configs::FRAME_LOW_WATERMARK_COUNT,
+            low: false,
         }
     }
 
@@ -281,6 +328,48 @@ impl BTreeSetFrameAllocator {
         assert!(current_ppn < end_ppn);
         self.current_ppn = current_ppn;
         self.end_ppn = end_ppn;
+        self.update_watermark();
+    }
+
+    /// Set the low watermark of the free frames
+    ///
+    /// - Arguments
+    ///     - low_watermark: the count of the free frames below which the memory is treated as low
+    pub(crate) fn set_low_watermark(&mut self, low_watermark: usize) {
+        self.low_watermark = low_watermark;
+        self.update_watermark();
+    }
+
+    /// Get the count of the frames which can still be allocated
+    pub(crate) fn free_count(&self) -> usize {
+        self.end_ppn - self.current_ppn + self.recycled.len()
+    }
+
+    /// Check if the count of the free frames is below the low watermark,
+    /// the non-essential allocations should be skipped to keep the frames for the critical ones
+    pub(crate) fn is_low(&self) -> bool {
+        self.low
+    }
+
+    /// Update the low memory state and warn when the count of the free frames crosses the low watermark
+    fn update_watermark(&mut self) {
+        let low = self.free_count() < self.low_watermark;
+        if low != self.low {
+            if low {
+                warn!(
+                    "Free frames {} are below the low watermark {}",
+                    self.free_count(),
+                    self.low_watermark
+                );
+            } else {
+                info!(
+                    "Free frames {} are recovered above the low watermark {}",
+                    self.free_count(),
+                    self.low_watermark
+                );
+            }
+            self.low = low;
+        }
     }
 
     /// Alloc a new frame and return new physical page number
@@ -288,17 +377,52 @@ impl BTreeSetFrameAllocator {
     /// - Errors
     ///     - FrameExhausted
     pub(crate) fn alloc(&mut self) -> Result<usize> {
-        if let Some(ppn) = self.recycled.pop_first() {
-            Ok(ppn)
+        let ppn = if let Some(ppn) = self.recycled.pop_first() {
+            ppn
         } else {
             let ppn = self.current_ppn;
             if ppn >= self.end_ppn {
-                Err(KernelError::FrameExhausted)
-            } else {
-                self.current_ppn += 1;
-                Ok(ppn)
+                return Err(KernelError::FrameExhausted);
+            }
+            self.current_ppn += 1;
+            ppn
+        };
+        self.update_watermark();
+        Ok(ppn)
+    }
+
+    /// Alloc contiguous frames and return the first physical page number which is aligned.
+    /// The recycled frames will be used first, otherwise the frames will be allocated from the never used ones,
+    /// and the skipped frames for alignment will be recycled.
+    ///
+    /// - Arguments
+    ///     - count: the count of the contiguous frames
+    ///     - align: the alignment of the first physical page number
+    ///
+    /// - Errors
+    ///     - FrameExhausted
+    pub(crate) fn alloc_contiguous(&mut self, count: usize, align: usize) -> Result<usize> {
+        let found = self
+            .recycled
+            .iter()
+            .filter(|ppn| *ppn % align == 0)
+            .find(|ppn| self.recycled.range(**ppn..**ppn + count).count() == count)
+            .copied();
+        if let Some(start) = found {
+            for ppn in start..start + count {
+                self.recycled.remove(&ppn);
             }
+            self.update_watermark();
+            return Ok(start);
+        }
+        let start = self.current_ppn.next_multiple_of(align);
+        if start + count > self.end_ppn {
+            return Err(KernelError::FrameExhausted);
         }
+        self.recycled.extend(self.current_ppn..start);
+        self.current_ppn = start + count;
+        self.update_watermark();
+        Ok(start)
     }
 
     /// Dealloc a frame
@@ -309,10 +433,22 @@ impl BTreeSetFrameAllocator {
         if ppn >= self.current_ppn || !self.recycled.insert(ppn) {
             Err(KernelError::FrameNotDeallocable(ppn))
         } else {
+            self.update_watermark();
             Ok(())
         }
     }
 
+    /// Get the usage statistics of the allocator
+    pub(crate) fn stats(&self) -> FrameStats {
+        FrameStats {
+            current_ppn: self.current_ppn,
+            end_ppn: self.end_ppn,
+            recycled: self.recycled.len(),
+            free: self.free_count(),
+            low_watermark: self.low_watermark,
+        }
+    }
+
     /// Readonly field for testing
     #[allow(dead_code)]
     pub(crate) fn current_ppn(&self) -> usize {
@@ -330,6 +466,19 @@ impl BTreeSetFrameAllocator {
 mod tests {
     use super::*;
 
+    #[test_case]
+    fn test_page_range_alloctor_find_free() {
+        let alloctor = LinkedListPageRangeAllocator::new(0, 8);
+        assert_eq!(alloctor.find_free(0, 8), Some(0));
+        assert_eq!(alloctor.find_free(1, 8), None);
+        assert!(alloctor.alloc(2, 4).is_some());
+        assert_eq!(alloctor.find_free(0, 2), Some(0));
+        assert_eq!(alloctor.find_free(0, 3), Some(4));
+        assert_eq!(alloctor.find_free(3, 1), Some(4));
+        assert_eq!(alloctor.find_free(5, 3), Some(5));
+        assert_eq!(alloctor.find_free(5, 4), None);
+    }
+
     #[test_case]
     fn test_page_range_alloctor_alloc_and_dealloc() {
         let alloctor = LinkedListPageRangeAllocator::new(0, 1);
@@ -389,9 +538,53 @@ mod tests {
         assert_eq!(allocator.end_ppn, 1);
         assert!(allocator.alloc().is_err_and(|t| t.is_frameexhausted()));
         assert!(allocator.dealloc(0).is_ok());
+        assert_eq!(
+            allocator.stats(),
+            FrameStats {
+                current_ppn: 1,
+                end_ppn: 1,
+                recycled: 1,
+                free: 1,
+                low_watermark: configs::FRAME_LOW_WATERMARK_COUNT,
+            }
+        );
         assert!(allocator.alloc().is_ok_and(|t| t == 0));
         assert_eq!(allocator.current_ppn, 1);
         assert_eq!(allocator.end_ppn, 1);
         assert!(allocator.alloc().is_err_and(|t| t.is_frameexhausted()));
     }
+
+    #[test_case]
+    fn test_frame_allocator_alloc_contiguous() {
+        let mut allocator = BTreeSetFrameAllocator::new();
+        allocator.init(1, 10);
+        assert!(allocator.alloc_contiguous(4, 4).is_ok_and(|t| t == 4));
+        assert_eq!(allocator.current_ppn, 8);
+        assert!(allocator.alloc_contiguous(4, 4).is_err_and(|t| t.is_frameexhausted()));
+        assert!(allocator.alloc_contiguous(3, 1).is_ok_and(|t| t == 1));
+        for ppn in 4..8 {
+            assert!(allocator.dealloc(ppn).is_ok());
+        }
+        assert!(allocator.alloc_contiguous(4, 4).is_ok_and(|t| t == 4));
+        assert!(allocator.alloc().is_ok_and(|t| t == 8));
+    }
+
+    #[test_case]
+    fn test_frame_allocator_low_watermark() {
+        let mut allocator = BTreeSetFrameAllocator::new();
+        allocator.init(0, 4);
+        allocator.set_low_watermark(2);
+        assert_eq!(allocator.free_count(), 4);
+        assert!(!allocator.is_low());
+        assert!(allocator.alloc().is_ok_and(|t| t == 0));
+        assert!(allocator.alloc().is_ok_and(|t| t == 1));
+        assert_eq!(allocator.free_count(), 2);
+        assert!(!allocator.is_low());
+        assert!(allocator.alloc().is_ok_and(|t| t == 2));
+        assert_eq!(allocator.free_count(), 1);
+        assert!(allocator.is_low());
+        assert!(allocator.dealloc(0).is_ok());
+        assert_eq!(allocator.free_count(), 2);
+        assert!(!allocator.is_low());
+    }
 }
@@ -4,13 +4,16 @@
 // self mods
 
 // use other mods
+use alloc::collections::BTreeMap;
 use alloc::sync::Arc;
 
 // use self mods
 use super::allocator::LinkedListPageRangeAllocator;
-use super::page_table::PageTable;
+use super::page_table::{PageTable, HUGE_PAGE_COUNT};
+use super::swap::SWAP_SPACE;
 use super::{PageBytes, PageTableFlags, PageTableTr};
 use crate::lang::container::UserPromiseRefCell;
+use crate::sync::futex::FUTEX_TABLE;
 use crate::{configs, prelude::*};
 
 /// The type of the area
@@ -24,6 +27,24 @@ pub(crate) enum AreaMapping {
     Framed,
 }
 
+/// The page of the area whose frame was released, it will be mapped again when it is accessed
+#[derive(Copy, Clone, PartialEq, Debug)]
+enum ReleasedPage {
+    /// The page was released by advice and will be zero-filled
+    Discarded,
+    /// The page was evicted to the swap slot and will be read back
+    Swapped(usize),
+}
+
+/// The kind of the fault which mapped the released page again
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub(crate) enum RefaultKind {
+    /// The page was zero-filled without any I/O
+    Minor,
+    /// The page was read back from the swap device
+    Major,
+}
+
 /// The virtual page range tracker which will automatically dealloc when dropping,
 /// Like the frame, virtual page cannot be allocated twice before it is dropped.
 struct PageRangeTracker {
@@ -109,6 +130,9 @@ pub(crate) struct Area {
     page_range_tracker: PageRangeTracker,
     /// The refrence of the page table which contains the area
     page_table: Arc<UserPromiseRefCell<PageTable>>,
+    /// The pages whose frames were released by advice or evicted to the swap device,
+    /// the virtual page number as key, they will be mapped again when they are accessed
+    released: UserPromiseRefCell<BTreeMap<usize, ReleasedPage>>,
 }
 impl Area {
     /// Create a new area
@@ -146,6 +170,7 @@ impl Area {
             flags,
             page_range_tracker: PageRangeTracker::new(start_vpn, end_vpn, allocator),
             page_table: Arc::clone(page_table),
+            released: unsafe { UserPromiseRefCell::new(BTreeMap::new()) },
         };
         area.map()?;
         Ok(area)
@@ -182,13 +207,16 @@ impl Area {
             flags: another.flags,
             page_range_tracker: PageRangeTracker::new(start_vpn, end_vpn, allocator),
             page_table: Arc::clone(page_table),
+            released: unsafe { UserPromiseRefCell::new(BTreeMap::new()) },
         };
         area.map()?;
         area.copy_another(another)?;
         Ok(area)
     }
 
-    /// Copy another area's bytes to current area
+    /// Copy another area's bytes to current area,
+    /// the discarded pages of another area will be copied as zeros,
+    /// and the swapped pages of another area will be read from the swap device
     ///
     /// - Arguments
     ///     - another: another area which is to be copied
@@ -198,6 +226,8 @@ impl Area {
     ///
     /// - Errors
     ///     - VPNNotMapped(vpn)
+    ///     - FrameExhausted
+    ///     - SwapIOFailed(slot)
     pub(crate) fn copy_another(&self, another: &Area) -> Result<usize> {
         let (src_start_vpn, src_end_vpn) = another.range();
         let (dst_start_vpn, dst_end_vpn) = self.range();
@@ -209,9 +239,16 @@ impl Area {
             src_offset
         };
         for offset in 0..count {
-            let src = another.get_byte_array(src_start_vpn + offset)?;
-            let dst = self.get_byte_array(dst_start_vpn + offset)?;
-            dst.copy_from_slice(src);
+            let dst_vpn = dst_start_vpn + offset;
+            let src_vpn = src_start_vpn + offset;
+            self.refault(dst_vpn)?;
+            let dst = self.get_byte_array(dst_vpn)?;
+            let released = another.released.access().get(&src_vpn).copied();
+            match released {
+                Some(ReleasedPage::Discarded) => dst.fill(0),
+                Some(ReleasedPage::Swapped(slot)) => SWAP_SPACE.read(slot, dst)?,
+                None => dst.copy_from_slice(another.get_byte_array(src_vpn)?),
+            }
         }
         Ok(count)
     }
@@ -245,7 +282,7 @@ impl Area {
     ///     - AllocFullPageMapper(ppn)
     ///     - PPNAlreadyMapped(ppn)
     ///     - PPNNotMapped(ppn)
-    fn map_one(&mut self, vpn: usize) -> Result<usize> {
+    fn map_one(&self, vpn: usize) -> Result<usize> {
         self.page_range_tracker.check(vpn)?;
         let ppn = match self.area_mapping {
             AreaMapping::Identical => {
@@ -259,6 +296,38 @@ impl Area {
         Ok(ppn)
     }
 
+    /// Map the aligned run of the pages which starts from the virtual page number as a huge page,
+    /// only if the huge page mapping is enabled and the whole run is in the area.
+    /// If the area mapping is Framed, the contiguous frames will be allocated for the huge page.
+    ///
+    /// - Arguments
+    ///     - vpn: the first virtual page number of the run
+    ///
+    /// - Returns
+    ///     - Ok(true): the run was mapped as a huge page
+    ///     - Ok(false): the run is not eligible or there are no contiguous frames
+    ///
+    /// - Errors
+    ///     - VPNAlreadyMapped(vpn)
+    ///     - InvaidPageTablePerm(flags)
+    ///     - FrameExhausted
+    ///     - AllocFullPageMapper(ppn)
+    ///     - PPNAlreadyMapped(ppn)
+    ///     - PPNNotMapped(ppn)
+    fn map_huge_one(&self, vpn: usize) -> Result<bool> {
+        if !configs::MEMORY_HUGE_PAGE_MAPPING
+            || vpn % HUGE_PAGE_COUNT != 0
+            || vpn + HUGE_PAGE_COUNT > self.page_range_tracker.end_vpn()
+        {
+            return Ok(false);
+        }
+        let mut page_table = self.page_table.exclusive_access();
+        match self.area_mapping {
+            AreaMapping::Identical => page_table.map_huge(vpn, vpn, self.flags).map(|_| true),
+            AreaMapping::Framed => page_table.map_huge_with_alloc(vpn, self.flags),
+        }
+    }
+
     /// Unmap a virtal page number.
     /// If the area mapping is Idential, area will only unmap the virtual page number from page table,
     /// If the area mapping is Framed, area will deallocate memory frame and unmap the virtual page number from page table.
@@ -271,7 +340,7 @@ impl Area {
     ///     - VPNNotMapped(vpn)
     ///     - PPNNotMapped(ppn)
     ///     - DeallocEmptyPageMapper(ppn)
-    fn unmap_one(&mut self, vpn: usize) -> Result<usize> {
+    fn unmap_one(&self, vpn: usize) -> Result<usize> {
         self.page_range_tracker.check(vpn)?;
         let ppn = match self.area_mapping {
             AreaMapping::Identical => {
@@ -285,35 +354,224 @@ impl Area {
         Ok(ppn)
     }
 
-    /// Map all virtual pages
+    /// Map all virtual pages.
+    /// If the mapping fails, the pages which were not mapped will be treated as discarded,
+    /// so that only the mapped pages will be unmapped when the area is dropped.
     ///
     /// - Errors
     ///     - VPNOutOfArea(vpn, start_vpn, end_vpn)
     ///     - VPNAlreadyMapped(vpn)
     ///     - InvaidPageTablePerm(flags)
+    ///     - CgroupBudgetExceeded(id)
     ///     - FrameExhausted
     ///     - AllocFullPageMapper(ppn)
     ///     - PPNAlreadyMapped(ppn)
     ///     - PPNNotMapped(ppn)
     fn map(&mut self) -> Result<()> {
-        for vpn in self.page_range_tracker.page_range() {
-            self.map_one(vpn)?;
+        let (start_vpn, end_vpn) = self.range();
+        let mut vpn = start_vpn;
+        while vpn < end_vpn {
+            let result = self.map_huge_one(vpn).and_then(|huge| {
+                if huge {
+                    Ok(HUGE_PAGE_COUNT)
+                } else {
+                    self.map_one(vpn).map(|_| 1)
+                }
+            });
+            match result {
+                Ok(count) => vpn += count,
+                Err(error) => {
+                    let mut released = self.released.exclusive_access();
+                    for vpn in vpn..end_vpn {
+                        released.insert(vpn, ReleasedPage::Discarded);
+                    }
+                    return Err(error);
+                }
+            }
         }
         Ok(())
     }
 
-    /// Unallocate all virtual pages
+    /// Unallocate all virtual pages, the swap slots of the evicted pages will be released
     ///
     /// - Errors
     ///     - VPNOutOfArea(vpn, start_vpn, end_vpn)
     ///     - VPNNotMapped(vpn)
     ///     - PPNNotMapped(ppn)
     ///     - DeallocEmptyPageMapper(ppn)
+    ///     - IdNotDeallocable(slot)
     fn unmap(&mut self) -> Result<()> {
-        for vpn in self.page_range_tracker.page_range() {
-            self.unmap_one(vpn)?;
+        self.page_table.exclusive_access().begin_tlb_batch();
+        let result = self
+            .page_range_tracker
+            .page_range()
+            .try_for_each(|vpn| {
+                let released = self.released.exclusive_access().remove(&vpn);
+                match released {
+                    Some(ReleasedPage::Discarded) => Ok(()),
+                    Some(ReleasedPage::Swapped(slot)) => SWAP_SPACE.free(slot),
+                    None => self.unmap_one(vpn).map(|_| ()),
+                }
+            });
+        self.page_table.exclusive_access().end_tlb_batch();
+        result
+    }
+
+    /// Check if the area keeps the anonymous data, which frames can be released or evicted
+    fn is_anonymous(&self) -> bool {
+        self.area_mapping == AreaMapping::Framed && self.flags.contains(PageTableFlags::RWU)
+    }
+
+    /// Release the frames of the pages in the range back to the frame allocator,
+    /// the pages will be mapped to the zeroed frames again by [`Area::refault`] when they are accessed.
+    /// Only the anonymous area is eligible.
+    ///
+    /// - Arguments
+    ///     - start_vpn: the start virtual page number of the range
+    ///     - end_vpn: the end virtual page number of the range which is not contained
+    ///
+    /// - Returns
+    ///     - Ok(true): the frames were released
+    ///     - Ok(false): the area is not eligible
+    ///
+    /// - Errors
+    ///     - VPNOutOfArea(vpn, start_vpn, end_vpn)
+    ///     - VPNNotMapped(vpn)
+    ///     - PPNNotMapped(ppn)
+    ///     - DeallocEmptyPageMapper(ppn)
+    ///     - IdNotDeallocable(slot)
+    pub(crate) fn discard_pages(&mut self, start_vpn: usize, end_vpn: usize) -> Result<bool> {
+        if !self.is_anonymous() {
+            return Ok(false);
         }
-        Ok(())
+        self.page_table.exclusive_access().begin_tlb_batch();
+        let result = (start_vpn..end_vpn).try_for_each(|vpn| {
+            let released = self.released.access().get(&vpn).copied();
+            match released {
+                Some(ReleasedPage::Discarded) => return Ok(()),
+                Some(ReleasedPage::Swapped(slot)) => SWAP_SPACE.free(slot)?,
+                None => {
+                    self.unmap_one(vpn)?;
+                }
+            }
+            self.released
+                .exclusive_access()
+                .insert(vpn, ReleasedPage::Discarded);
+            Ok(())
+        });
+        self.page_table.exclusive_access().end_tlb_batch();
+        result.map(|_| true)
+    }
+
+    /// Evict the page to the swap device and release its frame,
+    /// the page will be read back by [`Area::refault`] when it is accessed.
+    /// Only the mapped page of the anonymous area is eligible,
+    /// and the page with the futex waiters is pinned to its frame.
+    ///
+    /// - Arguments
+    ///     - vpn: the virtual page number of the page
+    ///
+    /// - Returns
+    ///     - Ok(true): the page was evicted
+    ///     - Ok(false): the page is not eligible, or there is no free swap slot
+    ///
+    /// - Errors
+    ///     - VPNOutOfArea(vpn, start_vpn, end_vpn)
+    ///     - VPNNotMapped(vpn)
+    ///     - PPNNotMapped(ppn)
+    ///     - DeallocEmptyPageMapper(ppn)
+    ///     - SwapIOFailed(slot)
+    ///     - IdNotDeallocable(slot)
+    pub(crate) fn swap_out_page(&mut self, vpn: usize) -> Result<bool> {
+        self.page_range_tracker.check(vpn)?;
+        if !self.is_anonymous() || self.released.access().contains_key(&vpn) {
+            return Ok(false);
+        }
+        let pinned = self
+            .page_table
+            .access()
+            .translate_ppn_with(vpn)
+            .is_some_and(|ppn| FUTEX_TABLE.has_waiters(ppn));
+        if pinned {
+            return Ok(false);
+        }
+        let slot = match SWAP_SPACE.swap_out(self.get_byte_array(vpn)?)? {
+            Some(slot) => slot,
+            None => return Ok(false),
+        };
+        if let Err(error) = self.unmap_one(vpn) {
+            SWAP_SPACE.free(slot)?;
+            return Err(error);
+        }
+        self.released
+            .exclusive_access()
+            .insert(vpn, ReleasedPage::Swapped(slot));
+        Ok(true)
+    }
+
+    /// Map the released page to a new frame again,
+    /// the discarded page will be zero-filled and the swapped page will be read back from the swap device
+    ///
+    /// - Arguments
+    ///     - vpn: the virtual page number which was accessed
+    ///
+    /// - Returns
+    ///     - Ok(Some(kind)): the page was mapped again
+    ///     - Ok(None): the page was not released
+    ///
+    /// - Errors
+    ///     - VPNOutOfArea(vpn, start_vpn, end_vpn)
+    ///     - VPNAlreadyMapped(vpn)
+    ///     - FrameExhausted
+    ///     - AllocFullPageMapper(ppn)
+    ///     - SwapIOFailed(slot)
+    ///     - IdNotDeallocable(slot)
+    pub(crate) fn refault(&self, vpn: usize) -> Result<Option<RefaultKind>> {
+        let released = match self.released.access().get(&vpn).copied() {
+            Some(released) => released,
+            None => return Ok(None),
+        };
+        self.map_one(vpn)?;
+        if let ReleasedPage::Swapped(slot) = released {
+            let result = self
+                .get_byte_array(vpn)
+                .and_then(|bytes| SWAP_SPACE.swap_in(slot, bytes));
+            if let Err(error) = result {
+                // keep the page released, so that the data in the slot will not be lost
+                self.unmap_one(vpn)?;
+                return Err(error);
+            }
+        }
+        self.released.exclusive_access().remove(&vpn);
+        match released {
+            ReleasedPage::Discarded => Ok(Some(RefaultKind::Minor)),
+            ReleasedPage::Swapped(_) => Ok(Some(RefaultKind::Major)),
+        }
+    }
+
+    /// Promote all the fully-populated and aligned runs of pages in the area into huge pages.
+    /// Only the framed area which is user writable, which means the anonymous data, is eligible.
+    ///
+    /// - Returns
+    ///     - Ok(the count of the promoted huge pages)
+    ///
+    /// - Errors
+    ///     - VPNNotMapped(vpn)
+    pub(crate) fn promote_huge_pages(&self) -> Result<usize> {
+        if !self.is_anonymous() {
+            return Ok(0);
+        }
+        let (start_vpn, end_vpn) = self.range();
+        let mut page_table = self.page_table.exclusive_access();
+        let mut count = 0;
+        let mut vpn = start_vpn.next_multiple_of(HUGE_PAGE_COUNT);
+        while vpn + HUGE_PAGE_COUNT <= end_vpn {
+            if page_table.promote_huge(vpn)? {
+                count += 1;
+            }
+            vpn += HUGE_PAGE_COUNT;
+        }
+        Ok(count)
     }
 
     /// # Unsafe
@@ -381,6 +639,30 @@ impl Area {
         }
         Ok(())
     }
+
+    /// Fill zero to the byte range of the multi continues pages.
+    /// - Arguments
+    ///     - offset: The byte offset from the beginning of the first virtual page
+    ///     - length: The byte length to be filled
+    ///
+    /// - Errors
+    ///     - VPNNotMapped(vpn)
+    pub(crate) fn zero_multi_pages(&mut self, offset: usize, length: usize) -> Result<()> {
+        assert_eq!(self.area_mapping, AreaMapping::Framed);
+        let linear_end = length + offset;
+        assert!(linear_end <= self.page_range_tracker.byte_size());
+        let vpn_offset = self.page_range_tracker.start_vpn();
+        let mut linear_start = offset;
+        while linear_start < linear_end {
+            let vpn = linear_start / configs::MEMORY_PAGE_BYTE_SIZE;
+            let end = linear_end.min((vpn + 1) * configs::MEMORY_PAGE_BYTE_SIZE);
+            let per_page_offset = linear_start % configs::MEMORY_PAGE_BYTE_SIZE;
+            let dst = self.get_byte_array(vpn + vpn_offset)?;
+            dst[per_page_offset..per_page_offset + end - linear_start].fill(0);
+            linear_start = end;
+        }
+        Ok(())
+    }
 }
 impl Drop for Area {
     fn drop(&mut self) {
@@ -391,6 +673,8 @@ impl Drop for Area {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::task::model::ForkedProcess;
+    use crate::task::TASK_SCHEDULER;
 
     #[test_case]
     fn test_area_from_another() {
@@ -519,4 +803,183 @@ mod tests {
         assert_eq!(&area.get_byte_array(1).unwrap()[0..2], &[1, 1][0..2]);
         assert_eq!(&area.get_byte_array(2).unwrap()[0..3], &[0, 1, 1][0..3]);
     }
+
+    #[test_case]
+    fn test_area_zero_multi_pages() {
+        let page_table = Arc::new(unsafe { UserPromiseRefCell::new(*PageTable::new(0).unwrap()) });
+        let page_range_allocator = Arc::new(LinkedListPageRangeAllocator::new(
+            0,
+            *super::super::MAX_VIRTUAL_PAGE_NUMBER + 1,
+        ));
+        let mut area = Area::new(
+            0,
+            3,
+            PageTableFlags::R,
+            AreaMapping::Framed,
+            &page_range_allocator,
+            &page_table,
+        )
+        .unwrap();
+        for vpn in 0..3 {
+            area.get_byte_array(vpn).unwrap().fill(1);
+        }
+        let offset = configs::MEMORY_PAGE_BYTE_SIZE - 2;
+        assert!(area
+            .zero_multi_pages(offset, configs::MEMORY_PAGE_BYTE_SIZE + 4)
+            .is_ok());
+        assert_eq!(&area.get_byte_array(0).unwrap()[offset - 1..], &[1, 0, 0]);
+        assert_eq!(
+            area.get_byte_array(1).unwrap(),
+            &[0; configs::MEMORY_PAGE_BYTE_SIZE]
+        );
+        assert_eq!(&area.get_byte_array(2).unwrap()[0..3], &[0, 0, 1]);
+    }
+
+    #[test_case]
+    fn test_area_discard_pages_and_refault() {
+        let page_table = Arc::new(unsafe { UserPromiseRefCell::new(*PageTable::new(0).unwrap()) });
+        let page_range_allocator = Arc::new(LinkedListPageRangeAllocator::new(
+            0,
+            *super::super::MAX_VIRTUAL_PAGE_NUMBER + 1,
+        ));
+        let mut area = Area::new(
+            0,
+            3,
+            PageTableFlags::RWU,
+            AreaMapping::Framed,
+            &page_range_allocator,
+            &page_table,
+        )
+        .unwrap();
+        for vpn in 0..3 {
+            area.get_byte_array(vpn).unwrap().fill(1);
+        }
+        assert!(area.discard_pages(1, 3).unwrap());
+        assert!(area.discard_pages(1, 2).unwrap());
+        assert!(area.get_byte_array(1).is_err_and(|e| e.is_vpnnotmapped()));
+        assert!(page_table.access().translate_ppn_with(2).is_none());
+        assert!(area.refault(0).unwrap().is_none());
+        assert_eq!(area.refault(1).unwrap(), Some(RefaultKind::Minor));
+        assert_eq!(
+            area.get_byte_array(1).unwrap(),
+            &[0; configs::MEMORY_PAGE_BYTE_SIZE]
+        );
+        assert_eq!(&area.get_byte_array(0).unwrap()[0..2], &[1, 1]);
+        let mut kernel_area = Area::new(
+            3,
+            4,
+            PageTableFlags::RW,
+            AreaMapping::Framed,
+            &page_range_allocator,
+            &page_table,
+        )
+        .unwrap();
+        assert!(!kernel_area.discard_pages(3, 4).unwrap());
+        // the released page which is never accessed again will be skipped when dropping
+        drop(area);
+        assert!(page_table.access().translate_ppn_with(1).is_none());
+    }
+
+    #[test_case]
+    fn test_area_swap_out_page_and_refault() {
+        let page_table = Arc::new(unsafe { UserPromiseRefCell::new(*PageTable::new(0).unwrap()) });
+        let page_range_allocator = Arc::new(LinkedListPageRangeAllocator::new(
+            0,
+            *super::super::MAX_VIRTUAL_PAGE_NUMBER + 1,
+        ));
+        let mut area = Area::new(
+            0,
+            2,
+            PageTableFlags::RWU,
+            AreaMapping::Framed,
+            &page_range_allocator,
+            &page_table,
+        )
+        .unwrap();
+        for (index, byte) in area.get_byte_array(0).unwrap().iter_mut().enumerate() {
+            *byte = index as u8;
+        }
+        assert!(area.swap_out_page(0).unwrap());
+        assert!(!area.swap_out_page(0).unwrap());
+        assert!(page_table.access().translate_ppn_with(0).is_none());
+        // the copied area reads the swapped page without releasing the slot
+        let other_page_table =
+            Arc::new(unsafe { UserPromiseRefCell::new(*PageTable::new(1).unwrap()) });
+        let other_allocator = Arc::new(LinkedListPageRangeAllocator::new(
+            0,
+            *super::super::MAX_VIRTUAL_PAGE_NUMBER + 1,
+        ));
+        let other = Area::from_another(&area, &other_allocator, &other_page_table).unwrap();
+        assert_eq!(area.refault(0).unwrap(), Some(RefaultKind::Major));
+        for bytes in [area.get_byte_array(0).unwrap(), other.get_byte_array(0).unwrap()] {
+            assert!(bytes.iter().enumerate().all(|(index, byte)| *byte == index as u8));
+        }
+        // the slot of the evicted page will be released when dropping
+        assert!(area.swap_out_page(1).unwrap());
+        drop(area);
+    }
+
+    #[test_case]
+    fn test_area_swap_out_page_pinned_by_futex_waiter() {
+        let page_table = Arc::new(unsafe { UserPromiseRefCell::new(*PageTable::new(0).unwrap()) });
+        let page_range_allocator = Arc::new(LinkedListPageRangeAllocator::new(
+            0,
+            *super::super::MAX_VIRTUAL_PAGE_NUMBER + 1,
+        ));
+        let mut area = Area::new(
+            0,
+            1,
+            PageTableFlags::RWU,
+            AreaMapping::Framed,
+            &page_range_allocator,
+            &page_table,
+        )
+        .unwrap();
+        let pa = page_table.access().translate_ppn_with(0).unwrap()
+            * configs::MEMORY_PAGE_BYTE_SIZE;
+        let process = ForkedProcess::new();
+        let task = process.inner_access().root_task();
+        task.mark_blocked();
+        FUTEX_TABLE.enqueue(pa, Arc::downgrade(&task));
+        assert!(!area.swap_out_page(0).unwrap());
+        // the page can be evicted after the waiter was woken up
+        assert_eq!(FUTEX_TABLE.wake(pa, 1), 1);
+        assert!(area.swap_out_page(0).unwrap());
+        assert!(TASK_SCHEDULER.fetch_specific(&task));
+        assert!(Arc::ptr_eq(&TASK_SCHEDULER.pop_ready_task().unwrap(), &task));
+    }
+
+    #[test_case]
+    fn test_area_map_huge_pages() {
+        let page_table = Arc::new(unsafe { UserPromiseRefCell::new(*PageTable::new(0).unwrap()) });
+        let page_range_allocator = Arc::new(LinkedListPageRangeAllocator::new(
+            0,
+            *super::super::MAX_VIRTUAL_PAGE_NUMBER + 1,
+        ));
+        // only the aligned run in the middle of the area is large enough for the huge page
+        let start_vpn = HUGE_PAGE_COUNT - 1;
+        let end_vpn = HUGE_PAGE_COUNT * 2 + 1;
+        let area = Area::new(
+            start_vpn,
+            end_vpn,
+            PageTableFlags::RW,
+            AreaMapping::Identical,
+            &page_range_allocator,
+            &page_table,
+        )
+        .unwrap();
+        assert_eq!(
+            page_table.access().is_huge(HUGE_PAGE_COUNT),
+            configs::MEMORY_HUGE_PAGE_MAPPING
+        );
+        assert!(!page_table.access().is_huge(start_vpn));
+        assert!(!page_table.access().is_huge(end_vpn - 1));
+        for vpn in start_vpn..end_vpn {
+            assert_eq!(page_table.access().translate_ppn_with(vpn), Some(vpn));
+        }
+        drop(area);
+        for vpn in start_vpn..end_vpn {
+            assert!(page_table.access().translate_ppn_with(vpn).is_none());
+        }
+    }
 }
@@ -0,0 +1,163 @@
+// @author:    olinex
+// @time:      2024/08/12
+
+// self mods
+
+// use other mods
+use alloc::collections::BTreeMap;
+use alloc::sync::Arc;
+
+// use self mods
+use crate::configs;
+use crate::lang::container::UserPromiseRefCell;
+use crate::prelude::*;
+use crate::task::allocator::BTreeIdAllocator;
+
+/// The statistics of the memory cgroup, which is shared with the user space
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub(crate) struct CgroupStat {
+    /// The max count of the frames which can be charged to the group
+    pub(crate) budget: usize,
+    /// The count of the frames which are charged to the group now
+    pub(crate) usage: usize,
+    /// The max count of the frames which were charged to the group at the same time
+    pub(crate) peak: usize,
+    /// The count of the charges which failed because of the budget
+    pub(crate) failcnt: usize,
+}
+
+/// The group of the processes which share one budget of the framed pages.
+/// The frames mapped by the `Framed` areas in the spaces of the member processes
+/// are charged to the group, and the charging fails when the budget is used up
+/// even if the global frames remain.
+pub(crate) struct MemCgroup {
+    /// The unique id of the group
+    id: usize,
+    stat: UserPromiseRefCell<CgroupStat>,
+}
+impl MemCgroup {
+    /// Create a new empty group
+    ///
+    /// - Arguments
+    ///     - id: the unique id of the group
+    ///     - budget: the max count of the frames which can be charged
+    fn new(id: usize, budget: usize) -> Self {
+        Self {
+            id,
+            stat: unsafe {
+                UserPromiseRefCell::new(CgroupStat {
+                    budget,
+                    ..CgroupStat::default()
+                })
+            },
+        }
+    }
+
+    /// Get the unique id of the group
+    pub(crate) fn id(&self) -> usize {
+        self.id
+    }
+
+    /// Get the statistics of the group
+    pub(crate) fn stat(&self) -> CgroupStat {
+        *self.stat.access()
+    }
+
+    /// Charge the frames to the group before they are allocated
+    ///
+    /// - Arguments
+    ///     - count: the count of the frames
+    ///
+    /// - Errors
+    ///     - CgroupBudgetExceeded(id)
+    pub(crate) fn charge(&self, count: usize) -> Result<()> {
+        let mut stat = self.stat.exclusive_access();
+        if stat.usage.saturating_add(count) > stat.budget {
+            stat.failcnt += 1;
+            return Err(KernelError::CgroupBudgetExceeded(self.id));
+        }
+        stat.usage += count;
+        stat.peak = stat.peak.max(stat.usage);
+        Ok(())
+    }
+
+    /// Credit the frames back to the group after they are deallocated
+    ///
+    /// - Arguments
+    ///     - count: the count of the frames
+    pub(crate) fn uncharge(&self, count: usize) {
+        let mut stat = self.stat.exclusive_access();
+        assert!(stat.usage >= count);
+        stat.usage -= count;
+    }
+}
+
+/// The table of all memory cgroups
+pub(crate) struct CgroupTable {
+    /// The map of the group id and the group, the groups are never destroyed
+    groups: BTreeMap<usize, Arc<MemCgroup>>,
+    /// The allocator of the group id
+    id_allocator: BTreeIdAllocator,
+}
+impl CgroupTable {
+    /// Create a new empty cgroup table
+    fn new() -> Self {
+        Self {
+            groups: BTreeMap::new(),
+            id_allocator: BTreeIdAllocator::new(configs::MAX_CGROUP_COUNT),
+        }
+    }
+}
+
+lazy_static! {
+    /// The global memory cgroup table, any process can join the group by the id
+    pub(crate) static ref CGROUP_TABLE: Arc<UserPromiseRefCell<CgroupTable>> =
+        Arc::new(unsafe { UserPromiseRefCell::new(CgroupTable::new()) });
+}
+impl CGROUP_TABLE {
+    /// Create a new memory cgroup and return its id
+    ///
+    /// - Arguments
+    ///     - budget: the max count of the frames which can be charged to the group
+    ///
+    /// - Errors
+    ///     - IdExhausted
+    pub(crate) fn create(&self, budget: usize) -> Result<usize> {
+        let mut table = self.exclusive_access();
+        let id = table.id_allocator.alloc()?;
+        table.groups.insert(id, Arc::new(MemCgroup::new(id, budget)));
+        Ok(id)
+    }
+
+    /// Get the memory cgroup by the id
+    ///
+    /// - Arguments
+    ///     - id: the id of the group
+    pub(crate) fn get(&self, id: usize) -> Option<Arc<MemCgroup>> {
+        self.access().groups.get(&id).cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_mem_cgroup_charge_and_uncharge() {
+        let id = CGROUP_TABLE.create(4).unwrap();
+        let cgroup = CGROUP_TABLE.get(id).unwrap();
+        assert_eq!(cgroup.id(), id);
+        assert!(cgroup.charge(3).is_ok());
+        assert!(cgroup
+            .charge(2)
+            .is_err_and(|e| e.is_cgroupbudgetexceeded()));
+        assert!(cgroup.charge(1).is_ok());
+        cgroup.uncharge(4);
+        let stat = cgroup.stat();
+        assert_eq!(stat.budget, 4);
+        assert_eq!(stat.usage, 0);
+        assert_eq!(stat.peak, 4);
+        assert_eq!(stat.failcnt, 1);
+    }
+}
@@ -5,6 +5,7 @@
 
 // use other mods
 use alloc::sync::Arc;
+use alloc::vec::Vec;
 use core::mem;
 
 use super::PageBytes;
@@ -14,6 +15,7 @@ use super::{page_table::PageTable, PageTableTr};
 use crate::configs;
 use crate::lang::container;
 use crate::prelude::*;
+use crate::task::PROCESS_TABLE;
 
 /// A tracker wrapper for physical memory frame
 /// Which will automatically dealloc frame for others can reuse it
@@ -25,11 +27,19 @@ impl FrameTracker {
     /// - Arguments
     ///     - frame: The physical page number
     pub(crate) fn new(ppn: usize) -> Self {
-        let tracker = Self { ppn };
+        let tracker = Self::new_uninit(ppn);
         tracker.clear();
         tracker
     }
 
+    /// Create a new FrameTracker but keep the stale data in frame,
+    /// the caller must overwrite the whole frame before it can be seen by others
+    /// - Arguments
+    ///     - frame: The physical page number
+    fn new_uninit(ppn: usize) -> Self {
+        Self { ppn }
+    }
+
     /// Get the physical page number
         pub(crate) fn ppn(&self) -> usize {
         self.ppn
@@ -63,10 +73,7 @@ impl FrameTracker {
 
     /// Set all byte to zero in frame
     pub(crate) fn clear(&self) {
-        let array = self.get_byte_array();
-        for i in array {
-            *i = 0;
-        }
+        self.get_byte_array().fill(0);
     }
 }
 impl Drop for FrameTracker {
@@ -85,8 +92,9 @@ lazy_static! {
 }
 impl FRAME_ALLOCATOR {
 
-    /// Alloc a new frame an return the tracker.
+    /// Alloc a new zeroed frame an return the tracker.
     /// If the tracker is dropped, the frame will automatic dealloc.
+    /// The frame may be recycled from other process, so it must be zeroed before it can be mapped into user space.
     /// 
     /// - Errors
     ///     - FrameExhausted
@@ -95,6 +103,55 @@ impl FRAME_ALLOCATOR {
         Ok(FrameTracker::new(ppn))
     }
 
+    /// Alloc a new frame without zeroing and return the tracker.
+    /// Only used by the kernel when the whole frame will be overwritten immediately.
+    ///
+    /// - Errors
+    ///     - FrameExhausted
+    pub(crate) fn alloc_uninit(&self) -> Result<FrameTracker> {
+        let ppn = self.exclusive_access().alloc()?;
+        Ok(FrameTracker::new_uninit(ppn))
+    }
+
+    /// Alloc contiguous frames and return the trackers in order of the physical page number.
+    ///
+    /// - Arguments
+    ///     - count: the count of the contiguous frames
+    ///     - align: the alignment of the first physical page number
+    ///     - zeroed: whether the frames will be zeroed, skip it only if the frames will be overwritten immediately
+    ///
+    /// - Errors
+    ///     - FrameExhausted
+    pub(crate) fn alloc_contiguous(
+        &self,
+        count: usize,
+        align: usize,
+        zeroed: bool,
+    ) -> Result<Vec<FrameTracker>> {
+        let start = self.exclusive_access().alloc_contiguous(count, align)?;
+        let new = if zeroed {
+            FrameTracker::new
+        } else {
+            FrameTracker::new_uninit
+        };
+        Ok((start..start + count).map(new).collect())
+    }
+
+    /// Evict at most [`configs::SWAP_RECLAIM_PAGE_COUNT`] cold pages of the processes
+    /// to the swap device if the free frames are below the low watermark,
+    /// see [`PROCESS_TABLE::reclaim_cold_pages`].
+    /// It is only called in the idle loop of the processor,
+    /// where no task is running and no process or swap space is being borrowed.
+    ///
+    /// - Returns
+    ///     - the count of the evicted pages
+    pub(crate) fn reclaim(&self) -> usize {
+        if !self.access().is_low() {
+            return 0;
+        }
+        PROCESS_TABLE.reclaim_cold_pages(configs::SWAP_RECLAIM_PAGE_COUNT)
+    }
+
     /// Dealloc a old frame.
     /// This method will be call by frame tracker when it was dropping,
     /// So they was not necessary to call by yourself.
@@ -128,6 +185,7 @@ pub(crate) fn init_frame_allocator() {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::task::model::ForkedProcess;
 
     #[test_case]
     fn test_global_frame_allocator_alloc_and_dealloc() {
@@ -156,4 +214,35 @@ mod tests {
         assert_eq!(array[0], 0);
         assert!((&array[0] as *const u8) as usize == tracker.pa());
     }
+
+    #[test_case]
+    fn test_global_frame_allocator_alloc_uninit() {
+        let tracker = FRAME_ALLOCATOR.alloc_uninit().unwrap();
+        let frame = tracker.ppn();
+        tracker.get_byte_array()[0] = 1;
+        drop(tracker);
+        let tracker = FRAME_ALLOCATOR.alloc_uninit().unwrap();
+        assert_eq!(frame, tracker.ppn());
+        assert_eq!(tracker.get_byte_array()[0], 1);
+        drop(tracker);
+        let tracker = FRAME_ALLOCATOR.alloc().unwrap();
+        assert_eq!(frame, tracker.ppn());
+        assert_eq!(tracker.get_byte_array()[0], 0);
+    }
+
+    #[test_case]
+    fn test_reclaim_below_low_watermark() {
+        let process = ForkedProcess::new();
+        let resident = process.inner_access().space().resident_frames();
+        // nothing will be evicted while the free frames are enough
+        assert_eq!(FRAME_ALLOCATOR.reclaim(), 0);
+        assert_eq!(process.inner_access().space().resident_frames(), resident);
+        let free = FRAME_ALLOCATOR.access().free_count();
+        FRAME_ALLOCATOR.exclusive_access().set_low_watermark(free + 1);
+        assert!(FRAME_ALLOCATOR.reclaim() > 0);
+        FRAME_ALLOCATOR
+            .exclusive_access()
+            .set_low_watermark(configs::FRAME_LOW_WATERMARK_COUNT);
+        assert!(process.inner_access().space().resident_frames() < resident);
+    }
 }
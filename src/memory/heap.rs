@@ -5,20 +5,77 @@
 
 // use other mods
 use buddy_system_allocator as allocator;
-use core::alloc::Layout;
+use core::alloc::{GlobalAlloc, Layout};
+use core::sync::atomic::{AtomicUsize, Ordering};
 
 // use self mods
 use crate::configs;
+use crate::memory::frame::FRAME_ALLOCATOR;
 
 // malloc memory in bss section, which will be used as kernel heap space
 static mut KERNEL_HEAP_SPACE: [u8; configs::KERNEL_HEAP_BYTE_SIZE] =
     [0; configs::KERNEL_HEAP_BYTE_SIZE];
 
+/// The usage statistics of the kernel heap, shared with the user space
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct HeapStats {
+    /// The bytes which have been allocated, including the internal fragments of the allocator
+    pub(crate) allocated: usize,
+    /// The bytes which can still be allocated
+    pub(crate) free: usize,
+    /// The maximum bytes which have ever been allocated at the same time
+    pub(crate) peak: usize,
+}
+
+/// The buddy system heap which also records the peak usage
+struct TrackedHeap {
+    heap: allocator::LockedHeap<32>,
+    peak: AtomicUsize,
+}
+unsafe impl GlobalAlloc for TrackedHeap {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = self.heap.alloc(layout);
+        if !ptr.is_null() {
+            let allocated = self.heap.lock().stats_alloc_actual();
+            self.peak.fetch_max(allocated, Ordering::Relaxed);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        self.heap.dealloc(ptr, layout)
+    }
+}
+
 #[global_allocator]
-static HEAP_ALLOCATOR: allocator::LockedHeap<32> = allocator::LockedHeap::<32>::empty();
+static HEAP_ALLOCATOR: TrackedHeap = TrackedHeap {
+    heap: allocator::LockedHeap::<32>::empty(),
+    peak: AtomicUsize::new(0),
+};
+
+/// Get the usage statistics of the kernel heap
+pub(crate) fn stats() -> HeapStats {
+    let heap = HEAP_ALLOCATOR.heap.lock();
+    let allocated = heap.stats_alloc_actual();
+    HeapStats {
+        allocated,
+        free: heap.stats_total_bytes() - allocated,
+        peak: HEAP_ALLOCATOR.peak.load(Ordering::Relaxed),
+    }
+}
 
+// Log the requested layout and the memory usage before panicking,
+// so that we can tell whether the heap is exhausted or just too fragmented.
+// The frame allocator may be borrowed when the allocation failed, so its statistics are optional
 #[alloc_error_handler]
 pub(crate) fn handle_alloc_error(layout: Layout) -> ! {
+    error!(
+        "Heap allocation error, layout = {:?}, heap = {:?}, frame = {:?}",
+        layout,
+        stats(),
+        FRAME_ALLOCATOR.try_access().map(|allocator| allocator.stats())
+    );
     panic!("Heap allocation error, layout = {:?}", layout);
 }
 
@@ -31,6 +88,7 @@ pub(crate) fn init_heap() {
     debug!("[{:#018x}, {:#018x}): Heap physical memory address initialized", start_addr, end_addr);
     unsafe {
         HEAP_ALLOCATOR
+            .heap
             .lock()
             .init(start_addr, configs::KERNEL_HEAP_BYTE_SIZE);
     }
@@ -51,6 +109,19 @@ mod tests {
         assert!(bss_range.contains(&(a.as_ref() as *const _ as usize)));
     }
 
+    #[test_case]
+    fn test_heap_stats() {
+        use alloc::boxed::Box;
+        let before = super::stats();
+        let a = Box::new([0u8; 1024]);
+        let after = super::stats();
+        assert!(after.allocated >= before.allocated + a.len());
+        assert_eq!(after.allocated + after.free, before.allocated + before.free);
+        assert!(after.peak >= after.allocated);
+        drop(a);
+        assert_eq!(super::stats().allocated, before.allocated);
+    }
+
     #[test_case]
     fn test_vector() {
         let mut v = vec![];
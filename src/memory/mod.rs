@@ -4,9 +4,13 @@
 // self mods
 pub(crate) mod allocator;
 pub(crate) mod area;
+pub(crate) mod cgroup;
 pub(crate) mod frame;
 pub(crate) mod heap;
+pub(crate) mod shm;
 pub(crate) mod space;
+pub(crate) mod swap;
+pub(crate) mod tlb;
 
 cfg_if! {
     if #[cfg(all(any(target_arch = "riscv32", target_arch = "riscv64")))] {
@@ -23,6 +27,14 @@ use crate::configs;
 use crate::prelude::*;
 use page_table::PageTable;
 
+/// The memory usage of the kernel heap and the frame allocator, shared with the user space
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct MemInfo {
+    pub(crate) heap: heap::HeapStats,
+    pub(crate) frame: allocator::FrameStats,
+}
+
 /// Alias of the page bytes array
 pub(crate) type PageBytes = [u8; configs::MEMORY_PAGE_BYTE_SIZE];
 
@@ -51,6 +63,7 @@ bitflags! {
         const U = 1 << 4;
         const RX = Self::R.bits() | Self::X.bits();
         const RW = Self::R.bits() | Self::W.bits();
+        const RU = Self::R.bits() | Self::U.bits();
         const RXU = Self::RX.bits() | Self::U.bits();
         const RWU = Self::RW.bits() | Self::U.bits();
     }
@@ -156,6 +169,17 @@ pub(crate) trait PageTableTr {
     ///     - None
     fn translate_ppn_with(&self, vpn: usize) -> Option<usize>;
 
+    /// Get the permission flags of the page which the virtual page number is pointing to.
+    /// If the virtual page number is not mapped, this function will return None
+    ///
+    /// - Arguments
+    ///     - vpn: virtual page number
+    ///
+    /// - Returns
+    ///     - Some(flags)
+    ///     - None
+    fn translate_flags_with(&self, vpn: usize) -> Option<PageTableFlags>;
+
     /// Get the reference of the frame tracker by the virtual page number
     ///
     /// - Arguments
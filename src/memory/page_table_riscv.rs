@@ -6,15 +6,20 @@
 // use other mods
 use alloc::boxed::Box;
 use alloc::collections::BTreeMap;
+use alloc::sync::Arc;
 use bit_field::BitField;
 use core::ops::{AddAssign, Range, SubAssign};
 use riscv::register::satp::Mode;
 
 // use self mods
+use super::cgroup::MemCgroup;
+use super::tlb::TlbFlushBatch;
 use super::{frame, PageTableFlags, PageTableTr};
 use crate::configs;
 use crate::lang::container::UserPromiseRefCell;
 use crate::prelude::*;
+use crate::sbi::{SBIApi, SBI};
+use crate::task::allocator::{AutoRecycledIdAllocator, IdTracker};
 
 /// The offset of the vpn/ppn
 const OFFSET_RANGE: Range<usize> = 0..12;
@@ -35,6 +40,10 @@ const PTE_COUNT: usize = configs::MEMORY_PAGE_BYTE_SIZE / PTE_BYTE_SIZE;
 const PTE_FLAGS_RANGE: Range<usize> = 0..8;
 const PTE_PPN_RANGE: Range<usize> = 10..54;
 
+/// The count of the pages in a huge page,
+/// which is mapped by a leaf PTE in the second last level page mapper
+pub(crate) const HUGE_PAGE_COUNT: usize = PTE_COUNT;
+
 /// Memory manager unit binary composition structure
 /// ```
 /// ----------------------------------------------------------------
@@ -48,6 +57,13 @@ const MMU_MODE_RANGE: Range<usize> = 60..64;
 
 pub(crate) const MAX_TASK_ID: usize = (1 << (MMU_ASID_RANGE.end - MMU_ASID_RANGE.start)) - 1;
 
+lazy_static! {
+    /// The allocator of the address space ids of the user page tables,
+    /// the max id is reserved for the kernel page table
+    static ref ASID_ALLOCATOR: AutoRecycledIdAllocator =
+        AutoRecycledIdAllocator::new(MAX_TASK_ID);
+}
+
 cfg_if! {
     if #[cfg(all(feature = "mmu_sv39", target_arch = "riscv64"))] {
         const MMU_MODE: Mode = Mode::Sv39;
@@ -136,6 +152,20 @@ impl PageTableEntry {
         pub(crate) fn is_valid(&self) -> bool {
         self.flags().contains(PTEFlags::V)
     }
+
+    /// Clear the flag bits of the PTE
+    ///
+    /// - Arguments
+    ///     - flags: the flag bits which will be cleared
+    pub(crate) fn remove_flags(&mut self, flags: PTEFlags) {
+        let bits = self.flags().difference(flags).bits() as usize;
+        self.bits.set_bits(PTE_FLAGS_RANGE, bits);
+    }
+
+    /// Check if the PTE is a leaf which refers to the physical page instead of the next page mapper
+    pub(crate) fn is_leaf(&self) -> bool {
+        self.is_valid() && self.flags().intersects(PTEFlags::R | PTEFlags::W | PTEFlags::X)
+    }
 }
 
 /// The array of the PTE of the total page
@@ -224,8 +254,469 @@ pub(crate) struct PageTable {
     mappers: BTreeMap<usize, PageMapper>,
     /// The trackers of the frames, mapped vpn as key
     trackers: BTreeMap<usize, frame::FrameTracker>,
+    /// The accumulator of the TLB flush operations
+    tlb_batch: TlbFlushBatch,
+    /// The tracker of the address space id, which will be recycled when the page table is dropped
+    asid_tracker: Option<IdTracker>,
+    /// The memory cgroup which the frames tracked by the page table are charged to
+    cgroup: Option<Arc<MemCgroup>>,
 }
 impl PageTable {
+    /// Create a new page table with the address space id allocated from the global allocator.
+    /// The recycled asid may still be tagged on the TLB entries of the dropped page table,
+    /// so the entries of the asid will be flushed before the new page table use it.
+    ///
+    /// - Errors
+    ///     - IdExhausted
+    ///     - FrameExhausted
+    pub(crate) fn new_with_recycled_asid() -> Result<Box<Self>> {
+        let tracker = ASID_ALLOCATOR.alloc()?;
+        let mut page_table = Self::new(tracker.id())?;
+        unsafe { SBI::sync_tlb_asid(tracker.id()) };
+        page_table.asid_tracker = Some(tracker);
+        Ok(page_table)
+    }
+
+    /// Check if the page table is the one currently used by the memory manager unit
+    pub(crate) fn is_active(&self) -> bool {
+        SBI::read_mmu_token() == self.mmu_token()
+    }
+
+    /// Get the count of the frames which are allocated and tracked by the page table
+    pub(crate) fn frame_count(&self) -> usize {
+        self.trackers.len()
+    }
+
+    /// Get the memory cgroup which the frames are charged to
+    pub(crate) fn cgroup(&self) -> Option<Arc<MemCgroup>> {
+        self.cgroup.clone()
+    }
+
+    /// Move the charge of the tracked frames from the current memory cgroup to the new one.
+    ///
+    /// - Arguments
+    ///     - cgroup: the new memory cgroup, None means the frames will not be charged
+    ///
+    /// - Errors
+    ///     - CgroupBudgetExceeded(id)
+    pub(crate) fn set_cgroup(&mut self, cgroup: Option<Arc<MemCgroup>>) -> Result<()> {
+        let count = self.trackers.len();
+        match (&self.cgroup, &cgroup) {
+            (Some(old), Some(new)) if Arc::ptr_eq(old, new) => return Ok(()),
+            _ => {}
+        }
+        if let Some(new) = &cgroup {
+            new.charge(count)?;
+        }
+        if let Some(old) = self.cgroup.take() {
+            old.uncharge(count);
+        }
+        self.cgroup = cgroup;
+        Ok(())
+    }
+
+    /// Charge the frames to the memory cgroup before they are allocated
+    ///
+    /// - Arguments
+    ///     - count: the count of the frames
+    ///
+    /// - Errors
+    ///     - CgroupBudgetExceeded(id)
+    fn charge(&self, count: usize) -> Result<()> {
+        match &self.cgroup {
+            Some(cgroup) => cgroup.charge(count),
+            None => Ok(()),
+        }
+    }
+
+    /// Credit the frames back to the memory cgroup after they are deallocated
+    ///
+    /// - Arguments
+    ///     - count: the count of the frames
+    fn uncharge(&self, count: usize) {
+        if let Some(cgroup) = &self.cgroup {
+            cgroup.uncharge(count);
+        }
+    }
+
+    /// Begin to accumulate the TLB flush operations when unmapping pages in bulk
+    pub(crate) fn begin_tlb_batch(&mut self) {
+        self.tlb_batch.begin();
+    }
+
+    /// End the accumulation and issue a single whole TLB flush if any entry was modified,
+    /// no matter whether the page table is active
+    pub(crate) fn end_tlb_batch(&mut self) {
+        if self.tlb_batch.end() {
+            unsafe { SBI::sync_tlb() };
+        }
+    }
+
+    /// Get the count of the TLB flush operations which have been issued for the page table
+    #[cfg(test)]
+    pub(crate) fn tlb_flushes(&self) -> usize {
+        self.tlb_batch.flushes()
+    }
+
+    /// Flush the TLB entry of the virtual page number which mapping was removed.
+    /// Inactive page table have no need to flush outside the batch,
+    /// because it will be flushed when it is switched to.
+    ///
+    /// - Arguments
+    ///     - vpn: the virtual page number which was unmapped
+    fn flush_tlb(&mut self, vpn: usize) {
+        let active = self.is_active();
+        if self.tlb_batch.record(active) {
+            unsafe { SBI::sync_tlb_va(Self::cal_base_va_with(vpn)) };
+        }
+    }
+
+    /// Flush all the TLB entries if the page table is active,
+    /// which is used when the structure of the page mappers was changed.
+    fn flush_tlb_all(&mut self) {
+        let active = self.is_active();
+        if self.tlb_batch.record(active) {
+            unsafe { SBI::sync_tlb() };
+        }
+    }
+
+    /// Get the second last level page mapper which covers the virtual page number,
+    /// the PTE of the virtual page number in it is either pointing to the last level page mapper or a huge page leaf.
+    ///
+    /// - Arguments
+    ///     - vpn: the virtual page number
+    fn get_huge_mapper(&self, vpn: usize) -> Option<&PageMapper> {
+        let indexes = Self::page_indexes(vpn);
+        let mut mapper = &self.root;
+        for i in 0..PAGE_LEVEL - 2 {
+            let entry = &mapper.get_pte_array()[indexes[i]];
+            if !entry.is_valid() || entry.is_leaf() {
+                return None;
+            }
+            mapper = self.mappers.get(&entry.ppn())?;
+        }
+        Some(mapper)
+    }
+
+    /// Convert the permission flags into the PTE flags which is forced to be valid
+    ///
+    /// - Arguments
+    ///     - flags: the permission flags
+    ///
+    /// - Errors
+    ///     - InvaidPageTablePerm(flags)
+    fn convert_flags(flags: PageTableFlags) -> Result<PTEFlags> {
+        let bits = flags.bits() | PTEFlags::V.bits();
+        PTEFlags::from_bits(bits).ok_or(KernelError::InvaidPageTablePerm(bits as usize))
+    }
+
+    /// Find the page mapper in the level which covers the virtual page number,
+    /// the missing page mappers on the path will be created,
+    /// and the counter of the used PTE in the parent page mapper will be increased.
+    ///
+    /// - Arguments
+    ///     - vpn: the virtual page number
+    ///     - level: the level of the page mapper, the root page mapper is in level 0
+    ///
+    /// - Errors
+    ///     - VPNAlreadyMapped(vpn)
+    ///     - FrameExhausted
+    ///     - AllocFullPageMapper(ppn)
+    ///     - PPNAlreadyMapped(ppn)
+    ///     - PPNNotMapped(ppn)
+    fn get_or_create_mapper(&mut self, vpn: usize, level: usize) -> Result<&PageMapper> {
+        let indexes = Self::page_indexes(vpn);
+        // the physical page number of the current page mapper, the root page mapper is None
+        let mut mapper_ppn = None;
+        // find or create a next page mapper
+        for i in 0..level {
+            let mapper = match mapper_ppn {
+                Some(ppn) => self.mappers.get(&ppn).ok_or(KernelError::PPNNotMapped(ppn))?,
+                None => &self.root,
+            };
+            let entries = mapper.get_pte_array();
+            let entry = &mut entries[indexes[i]];
+            // the virtual page number is already covered by a huge page
+            if entry.is_leaf() {
+                return Err(KernelError::VPNAlreadyMapped(vpn));
+            }
+            // check if the entry is valid
+            // if entry is invalid, we should create a new page mapper
+            // and write the entry to the parent page mapper
+            // which entry is referenced to the currently created page mapper
+            let child_ppn = if !entry.is_valid() {
+                let tracker = frame::FRAME_ALLOCATOR.alloc()?;
+                let child_ppn = tracker.ppn();
+                *entry = PageTableEntry::new(child_ppn, PTEFlags::V);
+                // increase the parent page mapper valid entries count
+                let child_mapper = PageMapper::new(Some(mapper.ppn()), tracker);
+                mapper.incr()?;
+                if let Some(_) = self.mappers.insert(child_ppn, child_mapper) {
+                    return Err(KernelError::PPNAlreadyMapped(child_ppn));
+                }
+                child_ppn
+            } else {
+                entry.ppn()
+            };
+            // when entry is valid, the next page mapper is created,
+            // so we only need to find the next page mapper by index.
+            mapper_ppn = Some(child_ppn);
+        }
+        match mapper_ppn {
+            Some(ppn) => self.mappers.get(&ppn).ok_or(KernelError::PPNNotMapped(ppn)),
+            None => Ok(&self.root),
+        }
+    }
+
+    /// Establish the mapping of a huge page by a leaf PTE in the second last level page mapper,
+    /// so that the whole run of the pages only takes one PTE and one TLB entry.
+    /// Both of the virtual and the physical page number must be aligned to [`HUGE_PAGE_COUNT`],
+    /// and this method will not alloc frames, see [`PageTable::map_huge_with_alloc`].
+    ///
+    /// - Arguments
+    ///     - vpn: the first virtual page number of the huge page
+    ///     - ppn: the first physical page number of the huge page
+    ///     - flags: the flags of the PTE
+    ///
+    /// - Errors
+    ///     - InvaidPageTablePerm(flags)
+    ///     - VPNAlreadyMapped(vpn)
+    ///     - FrameExhausted
+    ///     - AllocFullPageMapper(ppn)
+    ///     - PPNAlreadyMapped(ppn)
+    ///     - PPNNotMapped(ppn)
+    pub(crate) fn map_huge(&mut self, vpn: usize, ppn: usize, flags: PageTableFlags) -> Result<()> {
+        assert_eq!(vpn % HUGE_PAGE_COUNT, 0);
+        assert_eq!(ppn % HUGE_PAGE_COUNT, 0);
+        let flags = Self::convert_flags(flags)?;
+        let index = Self::page_indexes(vpn)[PAGE_LEVEL - 2];
+        let mapper = self.get_or_create_mapper(vpn, PAGE_LEVEL - 2)?;
+        let entry = &mut mapper.get_pte_array()[index];
+        // the valid entry is either a huge page or pointing to the last level page mapper,
+        // which means some of the pages were mapped
+        if entry.is_valid() {
+            return Err(KernelError::VPNAlreadyMapped(vpn));
+        }
+        *entry = PageTableEntry::new(ppn, flags);
+        mapper.incr()?;
+        Ok(())
+    }
+
+    /// Allocate the contiguous and aligned frames and map them as a huge page, see [`PageTable::map_huge`]
+    ///
+    /// - Arguments
+    ///     - vpn: the first virtual page number of the huge page
+    ///     - flags: the flags of the PTE
+    ///
+    /// - Returns
+    ///     - Ok(true): the huge page was mapped
+    ///     - Ok(false): there are no contiguous frames
+    ///
+    /// - Errors
+    ///     - InvaidPageTablePerm(flags)
+    ///     - VPNAlreadyMapped(vpn)
+    ///     - CgroupBudgetExceeded(id)
+    ///     - FrameExhausted
+    ///     - AllocFullPageMapper(ppn)
+    ///     - PPNAlreadyMapped(ppn)
+    ///     - PPNNotMapped(ppn)
+    pub(crate) fn map_huge_with_alloc(
+        &mut self,
+        vpn: usize,
+        flags: PageTableFlags,
+    ) -> Result<bool> {
+        self.charge(HUGE_PAGE_COUNT)?;
+        let trackers = match frame::FRAME_ALLOCATOR.alloc_contiguous(
+            HUGE_PAGE_COUNT,
+            HUGE_PAGE_COUNT,
+            true,
+        ) {
+            Ok(trackers) => trackers,
+            Err(error) => {
+                self.uncharge(HUGE_PAGE_COUNT);
+                return if error.is_frameexhausted() {
+                    Ok(false)
+                } else {
+                    Err(error)
+                };
+            }
+        };
+        if let Err(error) = self.map_huge(vpn, trackers[0].ppn(), flags) {
+            self.uncharge(HUGE_PAGE_COUNT);
+            return Err(error);
+        }
+        for (offset, tracker) in trackers.into_iter().enumerate() {
+            self.trackers.insert(vpn + offset, tracker);
+        }
+        Ok(true)
+    }
+
+    /// Read and clear the flag bit of the leaf entry which the virtual page number is pointing to,
+    /// the TLB entry will be flushed so that the hardware will set the bit again on the next access.
+    ///
+    /// - Arguments
+    ///     - vpn: the virtual page number
+    ///     - flag: the accessed or dirty flag bit
+    ///
+    /// - Returns
+    ///     - true: the bit was set and now cleared
+    ///     - false: the bit was not set or the virtual page number is not mapped
+    fn take_flag(&mut self, vpn: usize, flag: PTEFlags) -> bool {
+        let taken = match self.find_leaf_entry(vpn) {
+            Some((entry, _)) if entry.flags().contains(flag) => {
+                entry.remove_flags(flag);
+                true
+            }
+            _ => false,
+        };
+        if taken {
+            self.flush_tlb(vpn);
+        }
+        taken
+    }
+
+    /// Read and clear the accessed bit of the page,
+    /// which means whether the page was read, written or executed since the last taking
+    ///
+    /// - Arguments
+    ///     - vpn: the virtual page number
+    pub(crate) fn take_accessed(&mut self, vpn: usize) -> bool {
+        self.take_flag(vpn, PTEFlags::A)
+    }
+
+    /// Read and clear the dirty bit of the page,
+    /// which means whether the page was written since the last taking
+    ///
+    /// - Arguments
+    ///     - vpn: the virtual page number
+    pub(crate) fn take_dirty(&mut self, vpn: usize) -> bool {
+        self.take_flag(vpn, PTEFlags::D)
+    }
+
+    /// Check if the virtual page number is mapped by a huge page
+    ///
+    /// - Arguments
+    ///     - vpn: the virtual page number
+    pub(crate) fn is_huge(&self, vpn: usize) -> bool {
+        let index = Self::page_indexes(vpn)[PAGE_LEVEL - 2];
+        self.get_huge_mapper(vpn)
+            .is_some_and(|mapper| mapper.get_pte_array()[index].is_leaf())
+    }
+
+    /// Promote the fully-populated and aligned run of pages which contains the virtual page number into a huge page.
+    /// The data will be copied into the contiguous frames, and the last level page mapper will be removed,
+    /// so that only one TLB entry is needed for the whole run.
+    /// Only the pages with the same flags and whose frames are exclusively owned by the page table are eligible,
+    /// the shared or identical mapped pages will be left as-is.
+    ///
+    /// - Arguments
+    ///     - vpn: any virtual page number in the run
+    ///
+    /// - Returns
+    ///     - Ok(true): the run was promoted
+    ///     - Ok(false): the run is not eligible or there are no contiguous frames
+    ///
+    /// - Errors
+    ///     - VPNNotMapped(vpn)
+    pub(crate) fn promote_huge(&mut self, vpn: usize) -> Result<bool> {
+        let start_vpn = vpn - vpn % HUGE_PAGE_COUNT;
+        let index = Self::page_indexes(start_vpn)[PAGE_LEVEL - 2];
+        let entry = match self.get_huge_mapper(start_vpn) {
+            Some(mapper) => mapper.get_pte_array()[index],
+            None => return Ok(false),
+        };
+        if !entry.is_valid() || entry.is_leaf() {
+            return Ok(false);
+        }
+        let child_ppn = entry.ppn();
+        let child = self
+            .mappers
+            .get(&child_ppn)
+            .ok_or(KernelError::PPNNotMapped(child_ppn))?;
+        if !child.is_full() {
+            return Ok(false);
+        }
+        let entries = child.get_pte_array();
+        let ignored = PTEFlags::A | PTEFlags::D;
+        let flags = entries[0].flags().difference(ignored);
+        let eligible = entries.iter().enumerate().all(|(offset, entry)| {
+            entry.flags().difference(ignored) == flags
+                && self.trackers.contains_key(&(start_vpn + offset))
+        });
+        if !eligible {
+            return Ok(false);
+        }
+        // each frame will be overwritten by the data of the old page
+        let trackers = match frame::FRAME_ALLOCATOR.alloc_contiguous(
+            HUGE_PAGE_COUNT,
+            HUGE_PAGE_COUNT,
+            false,
+        ) {
+            Ok(trackers) => trackers,
+            Err(error) if error.is_frameexhausted() => return Ok(false),
+            Err(error) => return Err(error),
+        };
+        let ppn = trackers[0].ppn();
+        for (offset, tracker) in trackers.into_iter().enumerate() {
+            let vpn = start_vpn + offset;
+            tracker
+                .get_byte_array()
+                .copy_from_slice(self.get_byte_array(vpn)?);
+            // the old frame will be deallocated when the old tracker is dropped
+            self.trackers.insert(vpn, tracker);
+        }
+        let mapper = self
+            .get_huge_mapper(start_vpn)
+            .ok_or(KernelError::VPNNotMapped(start_vpn))?;
+        mapper.get_pte_array()[index] = PageTableEntry::new(ppn, flags);
+        self.mappers.remove(&child_ppn);
+        self.flush_tlb_all();
+        Ok(true)
+    }
+
+    /// Demote the huge page which contains the virtual page number back into the normal pages,
+    /// so that each page can be unmapped or changed separately.
+    /// The frames will not be moved, only a new last level page mapper will be created.
+    ///
+    /// - Arguments
+    ///     - vpn: any virtual page number in the huge page
+    ///
+    /// - Returns
+    ///     - Ok(true): the huge page was demoted
+    ///     - Ok(false): the virtual page number is not mapped by huge page
+    ///
+    /// - Errors
+    ///     - FrameExhausted
+    ///     - AllocFullPageMapper(ppn)
+    ///     - PPNAlreadyMapped(ppn)
+    pub(crate) fn demote_huge(&mut self, vpn: usize) -> Result<bool> {
+        let index = Self::page_indexes(vpn)[PAGE_LEVEL - 2];
+        let (parent_ppn, entry) = match self.get_huge_mapper(vpn) {
+            Some(mapper) => (mapper.ppn(), mapper.get_pte_array()[index]),
+            None => return Ok(false),
+        };
+        if !entry.is_leaf() {
+            return Ok(false);
+        }
+        // all of the entries in the child page mapper will be overwritten
+        let tracker = frame::FRAME_ALLOCATOR.alloc_uninit()?;
+        let child_ppn = tracker.ppn();
+        let child = PageMapper::new(Some(parent_ppn), tracker);
+        let entries = child.get_pte_array();
+        for offset in 0..HUGE_PAGE_COUNT {
+            entries[offset] = PageTableEntry::new(entry.ppn() + offset, entry.flags());
+            child.incr()?;
+        }
+        if let Some(_) = self.mappers.insert(child_ppn, child) {
+            return Err(KernelError::PPNAlreadyMapped(child_ppn));
+        }
+        if let Some(mapper) = self.get_huge_mapper(vpn) {
+            mapper.get_pte_array()[index] = PageTableEntry::new(child_ppn, PTEFlags::V);
+        }
+        self.flush_tlb_all();
+        Ok(true)
+    }
+
     /// Get the indexes of the PTE in the page mapper.
     ///
     /// - Arguments
@@ -246,6 +737,37 @@ impl PageTable {
         }
         indexes
     }
+
+    /// Find the valid leaf page table entry which the virtual page number is pointing to
+    ///
+    /// - Arguments
+    ///     - vpn: the virtual page number
+    ///
+    /// - Returns
+    ///     - Some((leaf entry, the count of the lower vpn bits covered by the entry))
+    ///     - None
+    fn find_leaf_entry(&self, vpn: usize) -> Option<(&mut PageTableEntry, usize)> {
+        let indexes = Self::page_indexes(vpn);
+        let last = PAGE_LEVEL - 1;
+        let mut mapper = &self.root;
+        for i in 0..last {
+            let entry = mapper.get_pte_array()[indexes[i]];
+            if !entry.is_valid() {
+                return None;
+            }
+            if entry.is_leaf() {
+                let bits = PTE_OFFSET_BIT_SIZE * (last - i);
+                return Some((&mut mapper.get_pte_array()[indexes[i]], bits));
+            }
+            mapper = self.mappers.get(&entry.ppn())?;
+        }
+        let entry = &mut mapper.get_pte_array()[indexes[last]];
+        if entry.is_valid() {
+            Some((entry, 0))
+        } else {
+            None
+        }
+    }
 }
 impl PageTableTr for PageTable {
     /// Help function to get physical page number with physical address
@@ -299,6 +821,9 @@ impl PageTableTr for PageTable {
             root: PageMapper::new(None, tracker),
             mappers: BTreeMap::new(),
             trackers: BTreeMap::new(),
+            tlb_batch: TlbFlushBatch::new(),
+            asid_tracker: None,
+            cgroup: None,
         }))
     }
 
@@ -340,46 +865,10 @@ impl PageTableTr for PageTable {
     ///     - PPNAlreadyMapped(ppn)
     ///     - PPNNotMapped(ppn)
     fn map_without_alloc(&mut self, vpn: usize, ppn: usize, flags: PageTableFlags) -> Result<()> {
-        // force mark the flag if `valid` to be ture
-        let bits = flags.bits() | PTEFlags::V.bits();
-        // convert PagetableFlags to PTEFlags
-        let flags =
-            PTEFlags::from_bits(bits).ok_or(KernelError::InvaidPageTablePerm(bits as usize))?;
-        // get the physical page number indexes
-        let indexes = Self::page_indexes(vpn);
-        let last = PAGE_LEVEL - 1;
-        let mut mapper = &self.root;
-        // find or create a next page mapper
-        for i in 0..last {
-            let entries = mapper.get_pte_array();
-            let entry = &mut entries[indexes[i]];
-            // check if the entry is valid
-            // if entry is invalid, we should create a new page mapper
-            // and write the entry to the parent page mapper
-            // which entry is referenced to the currently created page mapper
-            let child_ppn = if !entry.is_valid() {
-                let tracker = frame::FRAME_ALLOCATOR.alloc()?;
-                let child_ppn = tracker.ppn();
-                *entry = PageTableEntry::new(child_ppn, PTEFlags::V);
-                // increase the parent page mapper valid entries count
-                let child_mapper = PageMapper::new(Some(mapper.ppn()), tracker);
-                mapper.incr()?;
-                if let Some(_) = self.mappers.insert(child_ppn, child_mapper) {
-                    return Err(KernelError::PPNAlreadyMapped(child_ppn));
-                }
-                child_ppn
-            } else {
-                entry.ppn()
-            };
-            // when entry is valid, the next page mapper is created,
-            // so we only need to find the next page mapper by index.
-            mapper = self
-                .mappers
-                .get(&child_ppn)
-                .ok_or(KernelError::PPNNotMapped(child_ppn))?;
-        }
-        let entries = mapper.get_pte_array();
-        let entry = &mut entries[indexes[last]];
+        let flags = Self::convert_flags(flags)?;
+        let index = Self::page_indexes(vpn)[PAGE_LEVEL - 1];
+        let mapper = self.get_or_create_mapper(vpn, PAGE_LEVEL - 1)?;
+        let entry = &mut mapper.get_pte_array()[index];
         // create a new entry to the page mapper which is referenced to the ppn
         if !entry.is_valid() {
             *entry = PageTableEntry::new(ppn, flags);
@@ -403,7 +892,12 @@ impl PageTableTr for PageTable {
     ///     - VPNNotMapped(vpn)
     ///     - PPNNotMapped(ppn)
     ///     - DeallocEmptyPageMapper(ppn)
+    ///     - FrameExhausted
+    ///     - AllocFullPageMapper(ppn)
+    ///     - PPNAlreadyMapped(ppn)
     fn unmap_without_dealloc(&mut self, vpn: usize) -> Result<usize> {
+        // the huge page must be split before unmapping one of it's pages
+        self.demote_huge(vpn)?;
         // get the physical page number indexes
         let indexes = Self::page_indexes(vpn);
         let last = PAGE_LEVEL - 1;
@@ -470,6 +964,7 @@ impl PageTableTr for PageTable {
             }
             remove_ppn = mapper.ppn();
         }
+        self.flush_tlb(vpn);
         Ok(return_ppn)
     }
 
@@ -482,19 +977,27 @@ impl PageTableTr for PageTable {
     /// - Errors
     ///     - VPNAlreadyMapped(vpn)
     ///     - InvaidPageTablePerm(flags) 
+    ///     - CgroupBudgetExceeded(id)
     ///     - FrameExhausted 
     ///     - AllocFullPageMapper(ppn) 
     ///     - PPNAlreadyMapped(ppn) 
     ///     - PPNNotMapped(ppn)
     fn map(&mut self, vpn: usize, flags: PageTableFlags) -> Result<usize> {
         if !flags.is_empty() {
-            let tracker = frame::FRAME_ALLOCATOR.alloc()?;
-            let ppn = tracker.ppn();
-            self.map_without_alloc(vpn, ppn, flags)?;
-            match self.trackers.insert(vpn, tracker) {
-                Some(_) => Err(KernelError::VPNAlreadyMapped(vpn)),
-                None => Ok(ppn),
+            // the frame is charged first, and credited back if the mapping fails
+            self.charge(1)?;
+            let result = frame::FRAME_ALLOCATOR.alloc().and_then(|tracker| {
+                let ppn = tracker.ppn();
+                self.map_without_alloc(vpn, ppn, flags)?;
+                match self.trackers.insert(vpn, tracker) {
+                    Some(_) => Err(KernelError::VPNAlreadyMapped(vpn)),
+                    None => Ok(ppn),
+                }
+            });
+            if result.is_err() {
+                self.uncharge(1);
             }
+            result
         } else {
             Err(KernelError::InvaidPageTablePerm(flags.bits() as usize))
         }
@@ -513,7 +1016,10 @@ impl PageTableTr for PageTable {
         let ppn = self.unmap_without_dealloc(vpn)?;
         match self.trackers.remove(&vpn) {
             None => Err(KernelError::VPNNotMapped(vpn)),
-            Some(_) => Ok(ppn),
+            Some(_) => {
+                self.uncharge(1);
+                Ok(ppn)
+            }
         }
     }
 
@@ -527,23 +1033,22 @@ impl PageTableTr for PageTable {
     ///     - Some(ppn)
     ///     - None
     fn translate_ppn_with(&self, vpn: usize) -> Option<usize> {
-        let indexes = Self::page_indexes(vpn);
-        let last = PAGE_LEVEL - 1;
-        let mut entries = self.root.get_pte_array();
-        for i in 0..last {
-            let entry = &entries[indexes[i]];
-            if !entry.is_valid() {
-                return None;
-            }
-            let mapper = self.mappers.get(&entry.ppn())?;
-            entries = mapper.get_pte_array();
-        }
-        let entry = &entries[indexes[last]];
-        if entry.is_valid() {
-            Some(entry.ppn())
-        } else {
-            None
-        }
+        let (entry, bits) = self.find_leaf_entry(vpn)?;
+        // the leaf in the upper level page mapper covers the lower bits of the virtual page number
+        Some(entry.ppn() + vpn.get_bits(0..bits))
+    }
+
+    /// Get the permission flags of the leaf page table entry by virtual page number
+    ///
+    /// - Arguments
+    ///     - vpn: the virtual page number
+    ///
+    /// - Returns
+    ///     - Some(flags)
+    ///     - None
+    fn translate_flags_with(&self, vpn: usize) -> Option<PageTableFlags> {
+        let (entry, _) = self.find_leaf_entry(vpn)?;
+        Some(PageTableFlags::from_bits_truncate(entry.flags().bits()))
     }
 
     /// Get the frame tracker by virtual page number
@@ -588,6 +1093,13 @@ impl PageTableTr for PageTable {
     }
 }
 
+impl Drop for PageTable {
+    /// Credit the frames which are still tracked back to the memory cgroup
+    fn drop(&mut self) {
+        self.uncharge(self.trackers.len());
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::super::*;
@@ -633,6 +1145,14 @@ mod tests {
         );
     }
 
+    #[test_case]
+    fn test_pte_remove_flags() {
+        let mut entry = PageTableEntry::new(1, PTEFlags::V | PTEFlags::A | PTEFlags::D);
+        entry.remove_flags(PTEFlags::A);
+        assert!(entry.flags() == PTEFlags::V | PTEFlags::D);
+        assert_eq!(entry.ppn(), 1);
+    }
+
     #[test_case]
     fn test_pagetable_page_indexes() {
         assert_eq!(PageTable::page_indexes(0), [0; 3]);
@@ -657,6 +1177,28 @@ mod tests {
         assert!(PageTable::new(2).is_ok_and(|f| f.asid() == 2));
     }
 
+    #[test_case]
+    fn test_pagetable_new_with_recycled_asid() {
+        let page_table = PageTable::new_with_recycled_asid().unwrap();
+        let asid = page_table.asid();
+        assert!(asid < MAX_TASK_ID);
+        let other = PageTable::new_with_recycled_asid().unwrap();
+        assert_ne!(other.asid(), asid);
+        drop(page_table);
+        // the recycled asid must be reused instead of exhausting the asid space
+        for _ in 0..=MAX_TASK_ID {
+            let page_table = PageTable::new_with_recycled_asid().unwrap();
+            assert_ne!(page_table.asid(), other.asid());
+            assert!(page_table.asid() < MAX_TASK_ID);
+        }
+        let mut page_table = PageTable::new_with_recycled_asid().unwrap();
+        let ppn = page_table.ppn();
+        page_table.map_without_alloc(1, ppn, PageTableFlags::R).unwrap();
+        assert_eq!(page_table.translate_ppn_with(1), Some(ppn));
+        assert!(other.translate_ppn_with(1).is_none());
+        page_table.unmap_without_dealloc(1).unwrap();
+    }
+
     #[test_case]
     fn test_pagetable_cal_base_va_with() {
         assert_eq!(PageTable::cal_base_va_with(0), 0);
@@ -732,6 +1274,18 @@ mod tests {
             .get_tracker_with(0)
             .is_err_and(|e| e.is_vpnnotmapped()));
         assert!(page_table.map(0, PageTableFlags::R).is_ok());
+        // the inactive page table will be flushed when it is switched to
+        assert!(!page_table.is_active());
+        assert_eq!(page_table.tlb_flushes(), 0);
+        // but the batch always ends with a real flush
+        page_table.begin_tlb_batch();
+        assert!(page_table.unmap(0).is_ok());
+        page_table.end_tlb_batch();
+        assert_eq!(page_table.tlb_flushes(), 1);
+        // nothing was modified in the empty batch
+        page_table.begin_tlb_batch();
+        page_table.end_tlb_batch();
+        assert_eq!(page_table.tlb_flushes(), 1);
     }
 
     #[test_case]
@@ -756,4 +1310,123 @@ mod tests {
         assert_eq!(bytes[0], 0u8);
         assert_eq!(bytes[configs::MEMORY_PAGE_BYTE_SIZE - 1], 0u8);
     }
+
+    #[test_case]
+    fn test_pagetable_promote_and_demote_huge() {
+        let mut page_table = PageTable::new(0).unwrap();
+        let start_vpn = HUGE_PAGE_COUNT;
+        let last_vpn = start_vpn + HUGE_PAGE_COUNT - 1;
+        for vpn in start_vpn..last_vpn {
+            assert!(page_table.map(vpn, PageTableFlags::RWU).is_ok());
+            page_table.get_byte_array(vpn).unwrap()[0] = vpn as u8;
+        }
+        // the run which is not fully populated cannot be promoted
+        assert!(page_table.promote_huge(start_vpn).is_ok_and(|promoted| !promoted));
+        assert!(page_table.map(last_vpn, PageTableFlags::RWU).is_ok());
+        page_table.get_byte_array(last_vpn).unwrap()[0] = last_vpn as u8;
+        let mapper_count = page_table.mappers.len();
+        assert!(page_table.promote_huge(last_vpn).is_ok_and(|promoted| promoted));
+        assert!(page_table.is_huge(start_vpn));
+        assert_eq!(page_table.mappers.len(), mapper_count - 1);
+        let ppn = page_table.translate_ppn_with(start_vpn).unwrap();
+        assert_eq!(ppn % HUGE_PAGE_COUNT, 0);
+        for offset in 0..HUGE_PAGE_COUNT {
+            let vpn = start_vpn + offset;
+            assert_eq!(page_table.translate_ppn_with(vpn), Some(ppn + offset));
+            assert!(page_table
+                .get_tracker_with(vpn)
+                .is_ok_and(|tracker| tracker.ppn() == ppn + offset));
+            assert_eq!(page_table.get_byte_array(vpn).unwrap()[0], vpn as u8);
+        }
+        assert!(page_table
+            .map(start_vpn, PageTableFlags::RWU)
+            .is_err_and(|e| e.is_vpnalreadymapped()));
+        // unmapping one of the pages will demote the huge page
+        assert!(page_table.unmap(start_vpn).is_ok());
+        assert!(!page_table.is_huge(start_vpn));
+        assert_eq!(page_table.mappers.len(), mapper_count);
+        assert!(page_table.translate_ppn_with(start_vpn).is_none());
+        assert_eq!(
+            page_table.translate_ppn_with(last_vpn),
+            Some(ppn + HUGE_PAGE_COUNT - 1)
+        );
+        assert_eq!(page_table.get_byte_array(last_vpn).unwrap()[0], last_vpn as u8);
+        for vpn in start_vpn + 1..=last_vpn {
+            assert!(page_table.unmap(vpn).is_ok());
+        }
+    }
+
+    #[test_case]
+    fn test_pagetable_promote_huge_not_owned() {
+        let mut page_table = PageTable::new(0).unwrap();
+        let start_vpn = HUGE_PAGE_COUNT;
+        for vpn in start_vpn..start_vpn + HUGE_PAGE_COUNT {
+            assert!(page_table
+                .map_without_alloc(vpn, vpn, PageTableFlags::RWU)
+                .is_ok());
+        }
+        // the frames which are not owned by the page table may be shared with others
+        assert!(page_table.promote_huge(start_vpn).is_ok_and(|promoted| !promoted));
+        assert!(!page_table.is_huge(start_vpn));
+        for vpn in start_vpn..start_vpn + HUGE_PAGE_COUNT {
+            assert!(page_table.unmap_without_dealloc(vpn).is_ok_and(|ppn| ppn == vpn));
+        }
+    }
+
+    #[test_case]
+    fn test_pagetable_map_huge() {
+        let mut page_table = PageTable::new(0).unwrap();
+        let vpn = HUGE_PAGE_COUNT;
+        let ppn = HUGE_PAGE_COUNT * 3;
+        let mapper_count = page_table.mappers.len();
+        assert!(page_table.map_huge(vpn, ppn, PageTableFlags::RW).is_ok());
+        assert!(page_table.is_huge(vpn));
+        // the huge page only needs the mappers above the second last level
+        assert_eq!(page_table.mappers.len(), mapper_count + 1);
+        assert_eq!(page_table.translate_ppn_with(vpn), Some(ppn));
+        assert_eq!(
+            page_table.translate_ppn_with(vpn + HUGE_PAGE_COUNT - 1),
+            Some(ppn + HUGE_PAGE_COUNT - 1)
+        );
+        assert!(page_table.translate_ppn_with(vpn + HUGE_PAGE_COUNT).is_none());
+        assert!(page_table
+            .translate_flags_with(vpn + 1)
+            .is_some_and(|flags| flags.contains(PageTableFlags::RW)));
+        assert!(page_table
+            .map_without_alloc(vpn + 1, ppn, PageTableFlags::RW)
+            .is_err_and(|e| e.is_vpnalreadymapped()));
+        assert!(page_table
+            .map_huge(vpn, ppn, PageTableFlags::RW)
+            .is_err_and(|e| e.is_vpnalreadymapped()));
+        // unmapping one of the pages will demote the huge page
+        assert!(page_table
+            .unmap_without_dealloc(vpn + 1)
+            .is_ok_and(|unmapped| unmapped == ppn + 1));
+        assert!(!page_table.is_huge(vpn));
+        assert!(page_table.translate_ppn_with(vpn + 1).is_none());
+        assert_eq!(page_table.translate_ppn_with(vpn + 2), Some(ppn + 2));
+        assert!(page_table
+            .map_huge(vpn, ppn, PageTableFlags::RW)
+            .is_err_and(|e| e.is_vpnalreadymapped()));
+    }
+
+    #[test_case]
+    fn test_pagetable_map_huge_with_alloc() {
+        let mut page_table = PageTable::new(0).unwrap();
+        let vpn = HUGE_PAGE_COUNT;
+        assert!(page_table
+            .map_huge_with_alloc(vpn, PageTableFlags::RWU)
+            .is_ok_and(|mapped| mapped));
+        let ppn = page_table.translate_ppn_with(vpn).unwrap();
+        assert_eq!(ppn % HUGE_PAGE_COUNT, 0);
+        for offset in [0, HUGE_PAGE_COUNT - 1] {
+            assert!(page_table
+                .get_tracker_with(vpn + offset)
+                .is_ok_and(|tracker| tracker.ppn() == ppn + offset));
+            assert_eq!(page_table.get_byte_array(vpn + offset).unwrap()[0], 0);
+        }
+        for offset in 0..HUGE_PAGE_COUNT {
+            assert!(page_table.unmap(vpn + offset).is_ok());
+        }
+    }
 }
@@ -0,0 +1,171 @@
+// @author:    olinex
+// @time:      2024/08/02
+
+// self mods
+
+// use other mods
+use alloc::collections::BTreeMap;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+// use self mods
+use super::frame::{FrameTracker, FRAME_ALLOCATOR};
+use crate::configs;
+use crate::lang::container::UserPromiseRefCell;
+use crate::prelude::*;
+use crate::task::allocator::BTreeIdAllocator;
+
+/// The shared memory segment, which frames can be mapped into the spaces of different processes.
+/// There is no synchronization between the processes which attach the same segment,
+/// they must coordinate the access by themselves, such as using the futex word in the segment.
+struct ShmSegment {
+    /// The key which is used to find the segment
+    key: usize,
+    /// The frames of the segment, they will be deallocated when the segment is dropped
+    frames: Vec<FrameTracker>,
+    /// The count of the attachments in all spaces
+    refcount: usize,
+}
+
+/// The table of all shared memory segments
+pub(crate) struct ShmTable {
+    /// The map of the key and the segment id
+    keys: BTreeMap<usize, usize>,
+    /// The map of the segment id and the segment
+    segments: BTreeMap<usize, ShmSegment>,
+    /// The allocator of the segment id
+    id_allocator: BTreeIdAllocator,
+}
+impl ShmTable {
+    /// Create a new empty shared memory table
+    fn new() -> Self {
+        Self {
+            keys: BTreeMap::new(),
+            segments: BTreeMap::new(),
+            id_allocator: BTreeIdAllocator::new(configs::MAX_SHM_COUNT),
+        }
+    }
+}
+
+lazy_static! {
+    /// The global shared memory table, the segments can be attached by any process
+    pub(crate) static ref SHM_TABLE: Arc<UserPromiseRefCell<ShmTable>> =
+        Arc::new(unsafe { UserPromiseRefCell::new(ShmTable::new()) });
+}
+impl SHM_TABLE {
+    /// Get the id of the segment by the key, if the segment does not exist, a new one will be created.
+    /// The size of the new segment will be rounded up to the page size.
+    ///
+    /// - Arguments
+    ///     - key: the key of the segment
+    ///     - size: the byte size of the segment
+    ///
+    /// - Returns
+    ///     - Ok(Some(id)): the id of the segment
+    ///     - Ok(None): the size is zero or greater than the existing segment
+    ///
+    /// - Errors
+    ///     - IdExhausted
+    ///     - FrameExhausted
+    pub(crate) fn get(&self, key: usize, size: usize) -> Result<Option<usize>> {
+        let count = size.div_ceil(configs::MEMORY_PAGE_BYTE_SIZE);
+        let mut table = self.exclusive_access();
+        if let Some(id) = table.keys.get(&key).copied() {
+            return Ok((count <= table.segments[&id].frames.len()).then_some(id));
+        }
+        if count == 0 {
+            return Ok(None);
+        }
+        let mut frames = Vec::with_capacity(count);
+        for _ in 0..count {
+            frames.push(FRAME_ALLOCATOR.alloc()?);
+        }
+        let id = table.id_allocator.alloc()?;
+        table.keys.insert(key, id);
+        table.segments.insert(
+            id,
+            ShmSegment {
+                key,
+                frames,
+                refcount: 0,
+            },
+        );
+        Ok(Some(id))
+    }
+
+    /// Get the byte size of the segment, which is rounded up to the page size
+    ///
+    /// - Arguments
+    ///     - id: the id of the segment
+    pub(crate) fn byte_size(&self, id: usize) -> Option<usize> {
+        self.access()
+            .segments
+            .get(&id)
+            .map(|segment| segment.frames.len() * configs::MEMORY_PAGE_BYTE_SIZE)
+    }
+
+    /// Get the physical page numbers of the segment and increase the reference count
+    ///
+    /// - Arguments
+    ///     - id: the id of the segment
+    ///
+    /// - Errors
+    ///     - ShmDoesNotExist(id)
+    pub(crate) fn attach(&self, id: usize) -> Result<Vec<usize>> {
+        let mut table = self.exclusive_access();
+        let segment = table
+            .segments
+            .get_mut(&id)
+            .ok_or(KernelError::ShmDoesNotExist(id))?;
+        segment.refcount += 1;
+        Ok(segment.frames.iter().map(|frame| frame.ppn()).collect())
+    }
+
+    /// Decrease the reference count of the segment,
+    /// the segment and its frames will be released when the reference count reaches zero.
+    ///
+    /// - Arguments
+    ///     - id: the id of the segment
+    ///
+    /// - Errors
+    ///     - ShmDoesNotExist(id)
+    ///     - IdNotDeallocable(id)
+    pub(crate) fn detach(&self, id: usize) -> Result<()> {
+        let mut table = self.exclusive_access();
+        let segment = table
+            .segments
+            .get_mut(&id)
+            .ok_or(KernelError::ShmDoesNotExist(id))?;
+        segment.refcount -= 1;
+        if segment.refcount == 0 {
+            let segment = table.segments.remove(&id).unwrap();
+            table.keys.remove(&segment.key);
+            table.id_allocator.dealloc(id)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_shm_table_get_attach_and_detach() {
+        let key = usize::MAX;
+        assert!(SHM_TABLE.get(key, 0).unwrap().is_none());
+        let id = SHM_TABLE.get(key, 1).unwrap().unwrap();
+        assert_eq!(SHM_TABLE.get(key, configs::MEMORY_PAGE_BYTE_SIZE).unwrap(), Some(id));
+        assert!(SHM_TABLE
+            .get(key, configs::MEMORY_PAGE_BYTE_SIZE + 1)
+            .unwrap()
+            .is_none());
+        let ppns = SHM_TABLE.attach(id).unwrap();
+        assert_eq!(ppns.len(), 1);
+        assert_eq!(SHM_TABLE.attach(id).unwrap(), ppns);
+        assert!(SHM_TABLE.detach(id).is_ok());
+        assert_eq!(SHM_TABLE.get(key, 1).unwrap(), Some(id));
+        assert!(SHM_TABLE.detach(id).is_ok());
+        assert!(SHM_TABLE.attach(id).is_err_and(|e| e.is_shmdoesnotexist()));
+    }
+}
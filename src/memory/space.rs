@@ -4,10 +4,12 @@
 // self mods
 
 // use other mods
+use alloc::boxed::Box;
 use alloc::collections::{BTreeMap, BTreeSet};
 use alloc::string::String;
 use alloc::sync::Arc;
 use alloc::vec::Vec;
+use core::mem::{size_of, MaybeUninit};
 use elf::abi;
 use elf::endian::AnyEndian;
 use elf::segment::ProgramHeader;
@@ -16,14 +18,28 @@ use frontier_lib::constant::charater;
 
 // use self mods
 use super::allocator::LinkedListPageRangeAllocator;
-use super::area::{Area, AreaMapping};
+use super::area::{Area, AreaMapping, RefaultKind};
+use super::cgroup::MemCgroup;
 use super::page_table::{PageTable, MAX_TASK_ID};
+use super::shm::SHM_TABLE;
+use super::swap::SWAP_SPACE;
 use super::{PageTableFlags, PageTableTr};
 use crate::lang::buffer::ByteBuffers;
 use crate::lang::container::UserPromiseRefCell;
 use crate::sbi::{self, SBIApi};
 use crate::{configs, prelude::*};
 
+/// The kind of the memory fault, which helps to tell the cause of the fault
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub(crate) enum FaultKind {
+    /// The address falls in the guard page below the user stack of the task, the value is the task id
+    StackOverflow(usize),
+    /// The address is mapped but the access is not permitted
+    Protection,
+    /// The address is not mapped at all, such as a wild pointer dereference
+    Unmapped,
+}
+
 /// The abstract structure which represents the virtual memory address space
 pub(crate) struct Space {
     /// Areas of the virtual page range, which keys are the the start and end virtual page number
@@ -33,8 +49,46 @@ pub(crate) struct Space {
     /// The allocator used to manage the areas page range,
     /// each area will alloc a range of the virtual page numbers and each range cannot have overlapping parts
     page_range_allocator: Arc<LinkedListPageRangeAllocator>,
+    /// The guard page ranges below the user stacks, which values are the task id of the stacks
+    guard_ranges: BTreeMap<(usize, usize), usize>,
+    /// The page ranges of the attached shared memory segments, which values are the segment id
+    shm_ranges: BTreeMap<(usize, usize), usize>,
 }
 impl Space {
+    /// Get the total byte size of all areas in the space
+    pub(crate) fn byte_size(&self) -> usize {
+        self.area_set
+            .keys()
+            .map(|(start_vpn, end_vpn)| (end_vpn - start_vpn) * configs::MEMORY_PAGE_BYTE_SIZE)
+            .sum()
+    }
+
+    /// Get the total byte size of all areas and attached shared memory segments in the space
+    pub(crate) fn mapped_byte_size(&self) -> usize {
+        let shm_byte_size: usize = self
+            .shm_ranges
+            .keys()
+            .map(|(start_vpn, end_vpn)| (end_vpn - start_vpn) * configs::MEMORY_PAGE_BYTE_SIZE)
+            .sum();
+        self.byte_size() + shm_byte_size
+    }
+
+    /// Get the memory cgroup which the framed pages of the space are charged to
+    pub(crate) fn cgroup(&self) -> Option<Arc<MemCgroup>> {
+        self.page_table.access().cgroup()
+    }
+
+    /// Join the space into the memory cgroup, the charge of the mapped frames will be moved to it.
+    ///
+    /// - Arguments
+    ///     - cgroup: the new memory cgroup, None means the frames will not be charged
+    ///
+    /// - Errors
+    ///     - CgroupBudgetExceeded(id)
+    pub(crate) fn set_cgroup(&mut self, cgroup: Option<Arc<MemCgroup>>) -> Result<()> {
+        self.page_table.exclusive_access().set_cgroup(cgroup)
+    }
+
     /// Get the range of the kernel stack's virtual page number in the kernel address space,
     /// which kernel stack is belong to the task according to the kernel stack's id.
     /// The kernel stack is allocated in the upper half space of the kernel address space.
@@ -56,20 +110,51 @@ impl Space {
     /// |          guard page           |
     /// ---------------------------------
     /// ```
+    /// Each kernel stack takes a slot of the max stack size,
+    /// the unmapped part below the stack bottom in the slot also acts as the guard pages.
     ///
     /// - Arguments
     ///     - kid: the kernel stack unique id
+    ///     - byte_size: the byte size of the kernel stack, which cannot be greater than the max size
     ///
     /// - Returns
     ///     - (start virtual page number, end virtual page number)
-    pub(crate) fn get_kernel_task_stack_vpn_range(kid: usize) -> (usize, usize) {
+    pub(crate) fn get_kernel_task_stack_vpn_range(kid: usize, byte_size: usize) -> (usize, usize) {
         let max_vpn = *super::TRAMPOLINE_VIRTUAL_PAGE_NUMBER;
-        let stack_page_count = Self::vpn_ceil(configs::KERNEL_TASK_STACK_BYTE_SIZE);
-        let end_vpn = max_vpn - (stack_page_count + configs::KERNEL_GUARD_PAGE_COUNT) * kid;
+        let slot_page_count = configs::KERNEL_TASK_STACK_MAX_BYTE_SIZE
+            .div_ceil(configs::MEMORY_PAGE_BYTE_SIZE);
+        let stack_page_count = byte_size
+            .div_ceil(configs::MEMORY_PAGE_BYTE_SIZE)
+            .min(slot_page_count);
+        let end_vpn = max_vpn - (slot_page_count + configs::KERNEL_GUARD_PAGE_COUNT) * kid;
         let start_vpn = end_vpn - stack_page_count;
         (start_vpn, end_vpn)
     }
 
+    /// Get the range of the guard virtual addresses below the task's kernel stack,
+    /// which contains the unmapped part of the slot and the guard pages.
+    /// The kernel stack was overflowed if the kernel faults at the address in this range.
+    ///
+    /// - Arguments
+    ///     - kid: the kernel stack unique id
+    ///     - byte_size: the byte size of the kernel stack
+    ///
+    /// - Returns
+    ///     - (start virtual address, end virtual address)
+    pub(crate) fn get_kernel_task_stack_guard_va_range(
+        kid: usize,
+        byte_size: usize,
+    ) -> (usize, usize) {
+        let slot_page_count = configs::KERNEL_TASK_STACK_MAX_BYTE_SIZE
+            .div_ceil(configs::MEMORY_PAGE_BYTE_SIZE);
+        let (start_vpn, end_vpn) = Self::get_kernel_task_stack_vpn_range(kid, byte_size);
+        let guard_start_vpn = end_vpn - slot_page_count - configs::KERNEL_GUARD_PAGE_COUNT;
+        (
+            PageTable::cal_base_va_with(guard_start_vpn),
+            PageTable::cal_base_va_with(start_vpn),
+        )
+    }
+
     /// Get the top virtual address of the task's kernel stack
     ///
     /// - Arguments
@@ -78,7 +163,8 @@ impl Space {
     /// - Returns
     ///     - kernel stack's top virtual address
     pub(crate) fn get_kernel_task_stack_top_va(kid: usize) -> usize {
-        let (_, end_vpn) = Self::get_kernel_task_stack_vpn_range(kid);
+        // the top of the kernel stack does not depend on the byte size
+        let (_, end_vpn) = Self::get_kernel_task_stack_vpn_range(kid, 0);
         PageTable::cal_base_va_with(end_vpn)
     }
 
@@ -89,10 +175,16 @@ impl Space {
     /// ```
     /// IN TASK SPACE:
     /// --------------------------------- <- stack top virtual address
+    /// |    task1's user stack top     |
+    /// |             ...               | <- task1
+    /// |   task1's uer stack bottom    |
+    /// --------------------------------- <- stack bottom virtual address
+    /// |          guard page           |
+    /// ---------------------------------
     /// |    task0's user stack top     |
-    /// |             ...               | <- task
+    /// |             ...               | <- task0
     /// |   task0's uer stack bottom    |
-    /// --------------------------------- <- stack bottom virtual address
+    /// ---------------------------------
     /// |          guard page           |
     /// ---------------------------------
     /// |           task data           |
@@ -102,20 +194,42 @@ impl Space {
     /// |              ...              |
     /// --------------------------------- <- MIN virtual address
     /// ```
+    /// Each user stack takes a slot of the max stack size,
+    /// the unmapped part below the stack bottom in the slot also acts as the guard pages,
+    /// so the stacks of different sizes never overlap each other.
+    /// The slot must be below the trap contexts of all the tasks.
     ///
     /// - Arguments
     ///     - end_va: the virtual address of the last code or data of task
     ///     - tid: the unique id of the task
+    ///     - byte_size: the byte size of the user stack, which cannot be greater than the max size
     ///
     /// - Returns
     ///     - (start virtual page number, end virtual page number)
-    pub(crate) fn get_user_task_stack_vpn_range(end_va: usize, tid: usize) -> (usize, usize) {
-        let guard_size =
-            (tid + 1) * configs::KERNEL_GUARD_PAGE_COUNT * configs::MEMORY_PAGE_BYTE_SIZE;
-        let stack_size = tid * configs::USER_TASK_STACK_BYTE_SIZE;
-        let start_va = end_va + guard_size + stack_size;
-        let end_va = start_va + configs::USER_TASK_STACK_BYTE_SIZE;
-        (Self::vpn_ceil(start_va), Self::vpn_ceil(end_va))
+    ///
+    /// - Errors
+    ///     - VirtualAddressOverflow(va)
+    ///     - UserStackOutOfLayout(tid)
+    pub(crate) fn get_user_task_stack_vpn_range(
+        end_va: usize,
+        tid: usize,
+        byte_size: usize,
+    ) -> Result<(usize, usize)> {
+        let slot_size = configs::KERNEL_GUARD_PAGE_COUNT * configs::MEMORY_PAGE_BYTE_SIZE
+            + configs::USER_TASK_STACK_MAX_BYTE_SIZE;
+        let stack_size = byte_size
+            .div_ceil(configs::MEMORY_PAGE_BYTE_SIZE)
+            .saturating_mul(configs::MEMORY_PAGE_BYTE_SIZE)
+            .min(configs::USER_TASK_STACK_MAX_BYTE_SIZE);
+        let end_va = (tid + 1)
+            .checked_mul(slot_size)
+            .and_then(|offset| end_va.checked_add(offset))
+            .ok_or(KernelError::VirtualAddressOverflow(end_va))?;
+        if end_va > Self::get_task_trap_ctx_bottom_va(configs::MAX_TID_COUNT - 1) {
+            return Err(KernelError::UserStackOutOfLayout(tid));
+        }
+        let start_va = end_va - stack_size;
+        Ok((Self::vpn_ceil(start_va)?, Self::vpn_ceil(end_va)?))
     }
 
     /// Get the top virtual address of the task's user stack
@@ -126,9 +240,14 @@ impl Space {
     ///
     /// - Returns
     ///     - user stack's top virtual address
-    pub(crate) fn get_user_task_stack_top_va(end_va: usize, tid: usize) -> usize {
-        let (_, end_vpn) = Self::get_user_task_stack_vpn_range(end_va, tid);
-        PageTable::cal_base_va_with(end_vpn)
+    ///
+    /// - Errors
+    ///     - VirtualAddressOverflow(va)
+    ///     - UserStackOutOfLayout(tid)
+    pub(crate) fn get_user_task_stack_top_va(end_va: usize, tid: usize) -> Result<usize> {
+        // the top of the user stack does not depend on the byte size
+        let (_, end_vpn) = Self::get_user_task_stack_vpn_range(end_va, tid, 0)?;
+        Ok(PageTable::cal_base_va_with(end_vpn))
     }
 
     /// Get the range of the task's trap context page number in the user address space,
@@ -156,8 +275,9 @@ impl Space {
     pub(crate) fn get_task_trap_ctx_vpn_range(tid: usize) -> (usize, usize) {
         let offset = configs::MEMORY_PAGE_BYTE_SIZE * tid;
         let start_va = configs::TRAP_CTX_VIRTUAL_BASE_ADDR - offset;
-        let end_va = start_va + configs::MEMORY_PAGE_BYTE_SIZE;
-        (Self::vpn_ceil(start_va), Self::vpn_ceil(end_va))
+        // the trap context base address is page aligned and only takes one page
+        let start_vpn = Self::vpn_floor(start_va);
+        (start_vpn, start_vpn + 1)
     }
 
     /// Get the bottom virtual address of the trap context
@@ -172,16 +292,23 @@ impl Space {
         PageTable::cal_base_va_with(start_vpn)
     }
 
-    /// Get the virtual page number which is calculated by ceil divide the virtual address
+    /// Get the virtual page number which is calculated by ceil divide the virtual address.
+    /// The address in the last page cannot be rounded up, because the next page number
+    /// is out of the virtual address space and will be wrapped to a small one.
     ///
     /// - Arguments
     ///     - va: virtual address
-    fn vpn_ceil(va: usize) -> usize {
-        // TODO: pa may too big and overflow
-        PageTable::get_vpn_with(va + configs::MEMORY_PAGE_BYTE_SIZE - 1)
+    ///
+    /// - Errors
+    ///     - VirtualAddressOverflow(va)
+    fn vpn_ceil(va: usize) -> Result<usize> {
+        va.checked_add(configs::MEMORY_PAGE_BYTE_SIZE - 1)
+            .map(PageTable::get_vpn_with)
+            .ok_or(KernelError::VirtualAddressOverflow(va))
     }
 
-    /// Get the virtual page number which is calculated by floor divide the virtual address
+    /// Get the virtual page number which is calculated by floor divide the virtual address,
+    /// it never overflows because the address is only truncated.
     ///
     /// - Arguments
     ///     - va: virtual address
@@ -194,24 +321,66 @@ impl Space {
         self.page_table.access().mmu_token()
     }
 
-    /// Make current address space activate by wirtting the mmu token to the register
+    /// Make current address space activate by wirtting the mmu token to the register.
+    ///
+    /// The TLB never keeps the stale entries of a space, because of these invariants:
+    /// - switching the address space always flushes the whole TLB after writing the token
+    /// - changing the mappings of the active page table flushes the changed pages at once,
+    ///   or flushes the whole TLB only once at the end of the batch which wraps the changes
+    /// - the inactive page table has no entry in the TLB, so it has no need to flush
+    /// - the recycled asid is flushed before it is tagged on a new page table
     fn activate(&self) {
         unsafe { sbi::SBI::write_mmu_token(self.mmu_token()) };
     }
 
+    /// Begin to accumulate the TLB flush operations of the space's page table,
+    /// so that changing a lot of mappings in one system call only issues one fence.
+    /// The batch must be ended before the task yields the processor,
+    /// otherwise the changes of the other tasks sharing the space will not be flushed in time.
+    pub(crate) fn begin_tlb_batch(&self) {
+        self.page_table.exclusive_access().begin_tlb_batch();
+    }
+
+    /// End the accumulation and issue the fence if any mapping was changed in the batch
+    pub(crate) fn end_tlb_batch(&self) {
+        self.page_table.exclusive_access().end_tlb_batch();
+    }
+
     /// Create a new space without any area and frame except the root page mapper frame
     ///
+    /// - Arguments
+    ///     - asid: the address space unique id
+    ///
     /// - Errors
     ///     - FrameExhausted
     fn new_bare(asid: usize) -> Result<Self> {
-        let page_table = PageTable::new(asid)?;
+        Ok(Self::with_page_table(PageTable::new(asid)?))
+    }
+
+    /// Create a new bare user space, whose asid is allocated from the recycled asids,
+    /// so that the spaces created over time will never exhaust the asids
+    ///
+    /// - Errors
+    ///     - IdExhausted
+    ///     - FrameExhausted
+    fn new_user_bare() -> Result<Self> {
+        Ok(Self::with_page_table(PageTable::new_with_recycled_asid()?))
+    }
+
+    /// Wrap the page table into a space without any area
+    ///
+    /// - Arguments
+    ///     - page_table: the empty page table of the space
+    fn with_page_table(page_table: Box<PageTable>) -> Self {
         let page_range_allocator =
             LinkedListPageRangeAllocator::new(0, *super::MAX_VIRTUAL_PAGE_NUMBER + 1);
-        Ok(Self {
+        Self {
             page_table: Arc::new(unsafe { UserPromiseRefCell::new(*page_table) }),
             area_set: BTreeMap::new(),
             page_range_allocator: Arc::new(page_range_allocator),
-        })
+            guard_ranges: BTreeMap::new(),
+            shm_ranges: BTreeMap::new(),
+        }
     }
 
     /// Push area into space and write data to the area,
@@ -267,9 +436,119 @@ impl Space {
             .ok_or(KernelError::AreaNotExists(start_vpn, end_vpn))
     }
 
+    /// Check that the range of the user pointer can be accessed by the user mode,
+    /// each page in the range must be mapped with the user flag and the needed permission.
+    /// The trampoline and the trap context pages are never accessible,
+    /// so that the kernel will not leak or overwrite them on behalf of the user.
+    /// The released pages in the range will be mapped again before they are checked.
+    ///
+    /// - Arguments
+    ///     - ptr: the pointer of the first byte in the range
+    ///     - len: the byte length of the range
+    ///     - need_write: whether the range must be writable
+    ///
+    /// - Errors
+    ///     - UserRangeNotAccessible(va, len)
+    ///     - FrameExhausted
+    ///     - SwapIOFailed(slot)
+    pub(crate) fn check_user_range<T>(
+        &self,
+        ptr: *const T,
+        len: usize,
+        need_write: bool,
+    ) -> Result<()> {
+        let start_va = ptr as usize;
+        let error = || KernelError::UserRangeNotAccessible(start_va, len);
+        if len == 0 {
+            return Ok(());
+        }
+        let last_va = start_va.checked_add(len - 1).ok_or_else(error)?;
+        let start_vpn = Self::vpn_floor(start_va);
+        let last_vpn = Self::vpn_floor(last_va);
+        // reject the non-canonical address, which will be truncated to another page
+        let is_canonical = |va: usize, vpn: usize| {
+            PageTable::cal_base_va_with(vpn) + PageTable::get_va_offset(va) == va
+        };
+        if !is_canonical(start_va, start_vpn)
+            || !is_canonical(last_va, last_vpn)
+            || last_vpn >= *super::TRAMPOLINE_VIRTUAL_PAGE_NUMBER
+        {
+            return Err(error());
+        }
+        let needed = if need_write {
+            PageTableFlags::RWU
+        } else {
+            PageTableFlags::RU
+        };
+        for vpn in start_vpn..=last_vpn {
+            let mut flags = self.page_table.access().translate_flags_with(vpn);
+            // the released page is mapped again, just like it is accessed by the user mode
+            if flags.is_none() && self.refault(PageTable::cal_base_va_with(vpn))?.is_some() {
+                flags = self.page_table.access().translate_flags_with(vpn);
+            }
+            match flags {
+                Some(flags) if flags.contains(needed) => continue,
+                _ => return Err(error()),
+            }
+        }
+        Ok(())
+    }
+
+    /// Walk the physical memory of the virtual address range with the page table borrowed only once,
+    /// the physically contiguous pages will be merged into one run, so that they can be copied in the largest chunks.
+    /// The visitor is called with the physical address of the run, the byte offset of the run in the range and the byte length of the run.
+    /// If some page is not mapped, the runs before it may have been visited.
+    ///
+    /// - Arguments
+    ///     - va: the virtual address of the first byte
+    ///     - len: the byte length of the range
+    ///     - visitor: the function which will be called for each physically contiguous run
+    ///
+    /// - Errors
+    ///     - VPNNotMapped(vpn)
+    fn walk_physical_runs(
+        &self,
+        va: usize,
+        len: usize,
+        mut visitor: impl FnMut(usize, usize, usize),
+    ) -> Result<()> {
+        let page_table = self.page_table.access();
+        let end_va = va + len;
+        let mut current_va = va;
+        // the physical address, the byte offset in the range and the byte length of the current run
+        let mut run: Option<(usize, usize, usize)> = None;
+        while current_va < end_va {
+            let vpn = Self::vpn_floor(current_va);
+            let ppn = page_table
+                .translate_ppn_with(vpn)
+                .ok_or(KernelError::VPNNotMapped(vpn))?;
+            let offset = PageTable::get_va_offset(current_va);
+            let size = (configs::MEMORY_PAGE_BYTE_SIZE - offset).min(end_va - current_va);
+            let pa = ppn * configs::MEMORY_PAGE_BYTE_SIZE + offset;
+            let merged = match run.as_mut() {
+                Some((run_pa, _, run_len)) if *run_pa + *run_len == pa => {
+                    *run_len += size;
+                    true
+                }
+                _ => false,
+            };
+            if !merged {
+                if let Some((run_pa, run_offset, run_len)) = run.replace((pa, current_va - va, size))
+                {
+                    visitor(run_pa, run_offset, run_len);
+                }
+            }
+            current_va += size;
+        }
+        if let Some((run_pa, run_offset, run_len)) = run {
+            visitor(run_pa, run_offset, run_len);
+        }
+        Ok(())
+    }
+
     /// Translate byte buffers from current space to the current stack.
     /// Only kernel space allow to access all of the physical frame in memory.
-    /// To reduce memory copies, each byte buffers in different frame will be load as bytes slice pointer.
+    /// To reduce memory copies, each byte buffers in different physically contiguous run will be load as bytes slice pointer.
     /// Please be carefully!!! This method does not guarantee the lifetime of the returned byte buffers.
     ///
     /// - Arguments
@@ -283,28 +562,81 @@ impl Space {
         ptr: *const u8,
         len: usize,
     ) -> Result<ByteBuffers> {
-        let mut start_va = ptr as usize;
-        let end_va = start_va + len;
         let mut buffers = vec![];
-        let page_table = self.page_table.access();
-        while start_va < end_va {
-            let tmp_start_va = start_va;
-            let tmp_start_offset = PageTable::get_va_offset(tmp_start_va);
-            let tmp_byte_length = configs::MEMORY_PAGE_BYTE_SIZE - tmp_start_offset;
-            let tmp_end_va = end_va.min(tmp_start_va + tmp_byte_length);
-            let tmp_end_offset = match PageTable::get_va_offset(tmp_end_va) {
-                0 => configs::MEMORY_PAGE_BYTE_SIZE,
-                a => a,
-            };
-            let buffer = page_table.get_byte_array(Self::vpn_floor(tmp_start_va))?;
-            buffers.push(&mut buffer[tmp_start_offset..tmp_end_offset]);
-            start_va = tmp_end_va;
-        }
+        self.walk_physical_runs(ptr as usize, len, |pa, _, run_len| {
+            buffers.push(unsafe { core::slice::from_raw_parts_mut(pa as *mut u8, run_len) });
+        })?;
         Ok(ByteBuffers::new(buffers, len))
     }
 
+    /// Copy the bytes from the kernel into current space,
+    /// the bytes will be copied in the largest physically contiguous chunks.
+    ///
+    /// - Arguments
+    ///     - dst_ptr: the pointer of the first byte in current space
+    ///     - src: the bytes to copy
+    ///
+    /// - Errors
+    ///     - VPNNotMapped(vpn)
+    pub(crate) fn copy_to_user(&self, dst_ptr: *mut u8, src: &[u8]) -> Result<()> {
+        self.walk_physical_runs(dst_ptr as usize, src.len(), |pa, offset, len| {
+            let dst = unsafe { core::slice::from_raw_parts_mut(pa as *mut u8, len) };
+            dst.copy_from_slice(&src[offset..offset + len]);
+        })
+    }
+
+    /// Copy the bytes from current space into the kernel,
+    /// the bytes will be copied in the largest physically contiguous chunks.
+    ///
+    /// - Arguments
+    ///     - dst: the buffer to fill
+    ///     - src_ptr: the pointer of the first byte in current space
+    ///
+    /// - Errors
+    ///     - VPNNotMapped(vpn)
+    pub(crate) fn copy_from_user(&self, dst: &mut [u8], src_ptr: *const u8) -> Result<()> {
+        self.walk_physical_runs(src_ptr as usize, dst.len(), |pa, offset, len| {
+            let src = unsafe { core::slice::from_raw_parts(pa as *const u8, len) };
+            dst[offset..offset + len].copy_from_slice(src);
+        })
+    }
+
+    /// Copy the value from the kernel into current space like [`Space::copy_to_user`],
+    /// so that the value straddling the page boundary is never written into the wrong frame.
+    ///
+    /// - Arguments
+    ///     - dst_ptr: the pointer of the value in current space
+    ///     - value: the value to copy
+    ///
+    /// - Errors
+    ///     - VPNNotMapped(vpn)
+    pub(crate) fn copy_value_to_user<T: Copy>(&self, dst_ptr: *mut T, value: &T) -> Result<()> {
+        let src = unsafe {
+            core::slice::from_raw_parts(value as *const T as *const u8, size_of::<T>())
+        };
+        self.copy_to_user(dst_ptr as *mut u8, src)
+    }
+
+    /// Copy the value from current space into the kernel like [`Space::copy_from_user`].
+    /// The value must be plain old data, which is valid for any bit pattern written by the user.
+    ///
+    /// - Arguments
+    ///     - src_ptr: the pointer of the value in current space
+    ///
+    /// - Errors
+    ///     - VPNNotMapped(vpn)
+    pub(crate) fn copy_value_from_user<T: Copy>(&self, src_ptr: *const T) -> Result<T> {
+        let mut value = MaybeUninit::<T>::uninit();
+        let dst = unsafe {
+            core::slice::from_raw_parts_mut(value.as_mut_ptr() as *mut u8, size_of::<T>())
+        };
+        self.copy_from_user(dst, src_ptr as *const u8)?;
+        Ok(unsafe { value.assume_init() })
+    }
+
     /// Translate a byte pointer into the String from current space to the current stack,
     /// it will extract each char until reach the NULL(\0) char.
+    /// Each page of the string will be checked to be user readable before scanning.
     /// Only kernel sapce allow to access all of the physical frame in memory.
     /// To reduce memory copies, each byte buffers in different frame will be load as bytes slice pointer.
     /// Please be carefully!!! This method does not guarantee the lifetime of the returned byte buffers.
@@ -314,8 +646,30 @@ impl Space {
     ///
     /// - Errors
     ///     - VPNNotMapped(vpn)
+    ///     - UserRangeNotAccessible(va, len)
     pub(crate) fn translated_string(&self, ptr: *const u8) -> Result<String> {
+        self.translated_string_bounded(ptr, usize::MAX)
+    }
+
+    /// Translate a byte pointer into the String like [`Space::translated_string`],
+    /// but the scan will stop after `max_len` bytes,
+    /// so the corrupt pointer without the NULL(\0) char cannot make the kernel walk through the whole space.
+    ///
+    /// - Arguments
+    ///     - ptr: the pointer of the string
+    ///     - max_len: the max byte length of the string, including the NULL char
+    ///
+    /// - Errors
+    ///     - VPNNotMapped(vpn)
+    ///     - UserRangeNotAccessible(va, len)
+    ///     - UnterminatedString(max_len)
+    pub(crate) fn translated_string_bounded(
+        &self,
+        ptr: *const u8,
+        max_len: usize,
+    ) -> Result<String> {
         let mut start_va = ptr as usize;
+        let mut remain_len = max_len;
         let mut string = String::new();
         let page_table = self.page_table.access();
         'outer: loop {
@@ -323,8 +677,13 @@ impl Space {
             let tmp_start_offset = PageTable::get_va_offset(tmp_start_va);
             let tmp_byte_length = configs::MEMORY_PAGE_BYTE_SIZE - tmp_start_offset;
             let tmp_end_va = tmp_start_va + tmp_byte_length;
+            self.check_user_range(tmp_start_va as *const u8, tmp_byte_length, false)?;
             let buffer = page_table.get_byte_array(Self::vpn_floor(tmp_start_va))?;
             for offset in tmp_start_offset..configs::MEMORY_PAGE_BYTE_SIZE {
+                if remain_len == 0 {
+                    return Err(KernelError::UnterminatedString(max_len));
+                }
+                remain_len -= 1;
                 let byte = buffer[offset];
                 if byte == charater::NULL as u8 {
                     break 'outer;
@@ -369,6 +728,98 @@ impl Space {
         Some(ppn * configs::MEMORY_PAGE_BYTE_SIZE + offset)
     }
 
+    /// Classify the memory fault by the faulting virtual address,
+    /// so that the stack overflow can be distinguished from the wild pointer dereference.
+    ///
+    /// - Arguments
+    ///     - va: the faulting virtual address
+    pub(crate) fn classify_fault(&self, va: usize) -> FaultKind {
+        let vpn = Self::vpn_floor(va);
+        let guard = self
+            .guard_ranges
+            .iter()
+            .find(|((start_vpn, end_vpn), _)| *start_vpn <= vpn && vpn < *end_vpn);
+        if let Some((_, tid)) = guard {
+            FaultKind::StackOverflow(*tid)
+        } else if self.page_table.access().translate_ppn_with(vpn).is_some() {
+            FaultKind::Protection
+        } else {
+            FaultKind::Unmapped
+        }
+    }
+
+    /// Release the frames of the anonymous pages in the range back to the frame allocator,
+    /// the pages will be mapped to the zeroed frames again when they are accessed.
+    ///
+    /// - Arguments
+    ///     - va: the page aligned virtual address of the first byte
+    ///     - len: the byte length of the range, it will be rounded up to the page size
+    ///
+    /// - Returns
+    ///     - Ok(true): the frames were released
+    ///     - Ok(false): the address is not aligned, or the range is not covered by one anonymous area
+    ///
+    /// - Errors
+    ///     - VPNOutOfArea(vpn, start_vpn, end_vpn)
+    ///     - VPNNotMapped(vpn)
+    ///     - PPNNotMapped(ppn)
+    ///     - DeallocEmptyPageMapper(ppn)
+    ///     - IdNotDeallocable(slot)
+    pub(crate) fn discard_user_pages(&mut self, va: usize, len: usize) -> Result<bool> {
+        let start_vpn = Self::vpn_floor(va);
+        if PageTable::cal_base_va_with(start_vpn) != va {
+            return Ok(false);
+        }
+        let end_vpn = match va.checked_add(len).map(Self::vpn_ceil) {
+            Some(Ok(end_vpn)) => end_vpn,
+            _ => return Ok(false),
+        };
+        let area = self
+            .area_set
+            .iter_mut()
+            .find(|((area_start_vpn, area_end_vpn), _)| {
+                *area_start_vpn <= start_vpn && end_vpn <= *area_end_vpn
+            });
+        match area {
+            Some((_, area)) => area.discard_pages(start_vpn, end_vpn),
+            None => Ok(false),
+        }
+    }
+
+    /// Map the released page which contains the faulting virtual address to a new frame again,
+    /// the page was either discarded by [`Space::discard_user_pages`]
+    /// or evicted by [`Space::swap_out_cold_pages`].
+    ///
+    /// - Arguments
+    ///     - va: the faulting virtual address
+    ///
+    /// - Returns
+    ///     - Ok(Some(kind)): the page was mapped again, the faulting instruction can be executed
+    ///     - Ok(None): the page was not released
+    ///
+    /// - Errors
+    ///     - VPNAlreadyMapped(vpn)
+    ///     - FrameExhausted
+    ///     - AllocFullPageMapper(ppn)
+    ///     - SwapIOFailed(slot)
+    ///     - IdNotDeallocable(slot)
+    pub(crate) fn refault(&self, va: usize) -> Result<Option<RefaultKind>> {
+        let vpn = Self::vpn_floor(va);
+        let area = self
+            .area_set
+            .iter()
+            .find(|((start_vpn, end_vpn), _)| *start_vpn <= vpn && vpn < *end_vpn);
+        match area {
+            Some((_, area)) => area.refault(vpn),
+            None => Ok(None),
+        }
+    }
+
+    /// Get the count of the frames which are mapped in the space's page table
+    pub(crate) fn resident_frames(&self) -> usize {
+        self.page_table.access().frame_count()
+    }
+
     /// Map trampoline frame to the current address space's max page.
     /// By default, we assume that all code in trampoline page are addressed relative to registers,
     /// so all address spaces can share the same trampoline of kernel space by registering page table entry only.
@@ -454,6 +905,7 @@ impl Space {
     /// - Arguments
     ///     - end_va: the virtual address of the last code or data of task
     ///     - tid: the unique id of the task
+    ///     - byte_size: the byte size of the user stack
     ///
     /// - Errors
     ///     - AreaAllocFailed(start_vpn, end_vpn)
@@ -464,8 +916,13 @@ impl Space {
     ///     - AllocFullPageMapper(ppn)
     ///     - PPNAlreadyMapped(ppn)
     ///     - PPNNotMapped(ppn)
-    pub(crate) fn alloc_user_task_stack(&mut self, end_va: usize, tid: usize) -> Result<()> {
-        let (start_vpn, end_vpn) = Space::get_user_task_stack_vpn_range(end_va, tid);
+    pub(crate) fn alloc_user_task_stack(
+        &mut self,
+        end_va: usize,
+        tid: usize,
+        byte_size: usize,
+    ) -> Result<()> {
+        let (start_vpn, end_vpn) = Space::get_user_task_stack_vpn_range(end_va, tid, byte_size)?;
         // Map user stack with User Mode flag
         let area = Area::new(
             start_vpn,
@@ -476,6 +933,8 @@ impl Space {
             &self.page_table,
         )?;
         self.push(area, 0, None)?;
+        let guard_start_vpn = start_vpn.saturating_sub(configs::KERNEL_GUARD_PAGE_COUNT);
+        self.guard_ranges.insert((guard_start_vpn, start_vpn), tid);
         debug!(
             "[{:#018x}, {:#018x}): mapped task {}'s user stack segment address range",
             PageTable::cal_base_va_with(start_vpn),
@@ -490,12 +949,20 @@ impl Space {
     /// - Arguments
     ///     - end_va: the virtual address of the last code or data of task
     ///     - tid: the unique id of the task
+    ///     - byte_size: the byte size of the user stack
     ///
     /// - Errors
     ///     - AreaDeallocFailed(start vpn, end vpn)
-    pub(crate) fn dealloc_user_task_stack(&mut self, end_va: usize, tid: usize) -> Result<()> {
-        let (start_vpn, end_vpn) = Space::get_user_task_stack_vpn_range(end_va, tid);
+    pub(crate) fn dealloc_user_task_stack(
+        &mut self,
+        end_va: usize,
+        tid: usize,
+        byte_size: usize,
+    ) -> Result<()> {
+        let (start_vpn, end_vpn) = Space::get_user_task_stack_vpn_range(end_va, tid, byte_size)?;
         self.pop(start_vpn, end_vpn)?;
+        self.guard_ranges
+            .retain(|_, guard_tid| *guard_tid != tid);
         debug!(
             "[{:#018x}, {:#018x}): unmapped task {}'s user stack segment address range",
             PageTable::cal_base_va_with(start_vpn),
@@ -591,6 +1058,44 @@ impl Space {
         Ok(())
     }
 
+    /// Copy the bytes from another space into the current space,
+    /// the ranges can start and end at any byte, and span the page boundaries.
+    /// If the another space is the current space, the two ranges must not overlap.
+    ///
+    /// - Arguments
+    ///     - another: another space which the bytes will be copied from
+    ///     - src_va: the virtual address of the first byte in another space
+    ///     - dst_va: the virtual address of the first byte in current space
+    ///     - len: the count of the bytes to copy
+    ///
+    /// - Errors
+    ///     - VPNNotMapped(vpn)
+    pub(crate) fn copy_bytes_between(
+        &self,
+        another: &Self,
+        src_va: usize,
+        dst_va: usize,
+        len: usize,
+    ) -> Result<()> {
+        let src_buffers = another.translated_byte_buffers(src_va as *const u8, len)?;
+        let dst_buffers = self.translated_byte_buffers(dst_va as *const u8, len)?;
+        let mut src_slices = src_buffers.into_slices().into_iter();
+        let mut src: &[u8] = &[];
+        for dst in dst_buffers.into_slices() {
+            let mut offset = 0;
+            while offset < dst.len() {
+                if src.is_empty() {
+                    src = src_slices.next().unwrap();
+                }
+                let size = src.len().min(dst.len() - offset);
+                dst[offset..offset + size].copy_from_slice(&src[..size]);
+                src = &src[size..];
+                offset += size;
+            }
+        }
+        Ok(())
+    }
+
     /// Copy area from self space according to the vpn range
     ///
     /// - Arguments
@@ -669,7 +1174,7 @@ impl Space {
         let mut space = Self::new_bare(MAX_TASK_ID)?;
         for (start_va, end_va) in configs::MMIO {
             let start_vpn = Self::vpn_floor(*start_va);
-            let end_vpn = Self::vpn_ceil(*end_va);
+            let end_vpn = Self::vpn_ceil(*end_va)?;
             let area = Area::new(
                 start_vpn,
                 end_vpn,
@@ -686,7 +1191,7 @@ impl Space {
             );
         }
         let start_vpn = Self::vpn_floor(configs::_addr_text_start as usize);
-        let end_vpn = Self::vpn_ceil(configs::_addr_text_end as usize);
+        let end_vpn = Self::vpn_ceil(configs::_addr_text_end as usize)?;
         // map code segement as area
         let area = Area::new(
             start_vpn,
@@ -704,7 +1209,7 @@ impl Space {
         );
         // map read only data segement as area
         let start_vpn = Self::vpn_floor(configs::_addr_rodata_start as usize);
-        let end_vpn = Self::vpn_ceil(configs::_addr_rodata_end as usize);
+        let end_vpn = Self::vpn_ceil(configs::_addr_rodata_end as usize)?;
         let area = Area::new(
             start_vpn,
             end_vpn,
@@ -721,7 +1226,7 @@ impl Space {
         );
         // map read write data segment as area
         let start_vpn = Self::vpn_floor(configs::_addr_data_start as usize);
-        let end_vpn = Self::vpn_ceil(configs::_addr_data_end as usize);
+        let end_vpn = Self::vpn_ceil(configs::_addr_data_end as usize)?;
         // A read/write data segment may be empty
         if start_vpn != end_vpn {
             let area = Area::new(
@@ -747,7 +1252,7 @@ impl Space {
         }
         // map read write bootstack segment as area
         let start_vpn = Self::vpn_floor(configs::_addr_bootstack_start as usize);
-        let end_vpn = Self::vpn_ceil(configs::_addr_bootstack_end as usize);
+        let end_vpn = Self::vpn_ceil(configs::_addr_bootstack_end as usize)?;
         if start_vpn != end_vpn {
             let area = Area::new(
                 start_vpn,
@@ -772,7 +1277,7 @@ impl Space {
         }
         // map block started by symbol segment as area
         let start_vpn = Self::vpn_floor(configs::_addr_bss_start as usize);
-        let end_vpn = Self::vpn_ceil(configs::_addr_bss_end as usize);
+        let end_vpn = Self::vpn_ceil(configs::_addr_bss_end as usize)?;
         if start_vpn != end_vpn {
             let area = Area::new(
                 start_vpn,
@@ -818,9 +1323,204 @@ impl Space {
         Ok(space)
     }
 
-    /// Clear all pages
+    /// Promote the eligible anonymous areas into huge pages,
+    /// the areas which cannot be promoted will be left as-is.
+    ///
+    /// - Returns
+    ///     - Ok(the count of the promoted huge pages)
+    ///
+    /// - Errors
+    ///     - VPNNotMapped(vpn)
+    pub(crate) fn promote_huge_pages(&self) -> Result<usize> {
+        let mut count = 0;
+        for area in self.area_set.values() {
+            count += area.promote_huge_pages()?;
+        }
+        Ok(count)
+    }
+
+    /// Evict the cold anonymous pages which were not accessed since the last scan to the swap device,
+    /// which is used to release the frames when the free frames are below the low watermark.
+    /// The TLB will be flushed only once after all pages were evicted.
+    ///
+    /// - Arguments
+    ///     - max_count: the max count of the pages to evict
+    ///
+    /// - Returns
+    ///     - Ok(the count of the evicted pages)
+    ///
+    /// - Errors
+    ///     - VPNNotMapped(vpn)
+    ///     - PPNNotMapped(ppn)
+    ///     - DeallocEmptyPageMapper(ppn)
+    ///     - SwapIOFailed(slot)
+    ///     - IdNotDeallocable(slot)
+    pub(crate) fn swap_out_cold_pages(&mut self, max_count: usize) -> Result<usize> {
+        if !SWAP_SPACE.is_enabled() {
+            return Ok(0);
+        }
+        let touched: BTreeSet<usize> = self.scan_working_set().into_iter().collect();
+        self.page_table.exclusive_access().begin_tlb_batch();
+        let mut count = 0;
+        let mut result = Ok(());
+        'areas: for ((start_vpn, end_vpn), area) in self.area_set.iter_mut() {
+            for vpn in *start_vpn..*end_vpn {
+                if count >= max_count {
+                    break 'areas;
+                }
+                if touched.contains(&vpn) {
+                    continue;
+                }
+                match area.swap_out_page(vpn) {
+                    Ok(true) => count += 1,
+                    Ok(false) => (),
+                    Err(error) => {
+                        result = Err(error);
+                        break 'areas;
+                    }
+                }
+            }
+        }
+        self.page_table.exclusive_access().end_tlb_batch();
+        result.map(|_| count)
+    }
+
+    /// Scan the pages of all areas and report the pages which were accessed since the last scan,
+    /// the accessed bits will be cleared and the TLB will be flushed only once after the scan.
+    ///
+    /// - Returns
+    ///     - the virtual page numbers of the accessed pages in ascending order
+    pub(crate) fn scan_working_set(&self) -> Vec<usize> {
+        let mut page_table = self.page_table.exclusive_access();
+        page_table.begin_tlb_batch();
+        let touched = self
+            .area_set
+            .keys()
+            .flat_map(|(start_vpn, end_vpn)| *start_vpn..*end_vpn)
+            .filter(|vpn| page_table.take_accessed(*vpn))
+            .collect();
+        page_table.end_tlb_batch();
+        touched
+    }
+
+    /// Clear all pages, the TLB will be flushed only once after all areas were unmapped,
+    /// even if the space is not active
     pub(crate) fn recycle_data_pages(&mut self) {
+        self.page_table.exclusive_access().begin_tlb_batch();
         self.area_set.clear();
+        self.detach_all_shm();
+        self.page_table.exclusive_access().end_tlb_batch();
+    }
+
+    /// Attach the shared memory segment into the first free page range above [`configs::SHM_VIRTUAL_BASE_ADDR`],
+    /// the pages are user readable and writable, and return the base virtual address.
+    /// The attachments will not be inherited by the forked space.
+    ///
+    /// - Arguments
+    ///     - id: the id of the shared memory segment
+    ///
+    /// - Errors
+    ///     - ShmDoesNotExist(id)
+    ///     - AreaAllocFailed(start_vpn, end_vpn)
+    ///     - VPNAlreadyMapped(vpn)
+    ///     - FrameExhausted
+    ///     - AllocFullPageMapper(ppn)
+    pub(crate) fn attach_shm(&mut self, id: usize) -> Result<usize> {
+        let ppns = SHM_TABLE.attach(id)?;
+        let count = ppns.len();
+        let min_vpn = Self::vpn_floor(configs::SHM_VIRTUAL_BASE_ADDR);
+        let range = self
+            .page_range_allocator
+            .find_free(min_vpn, count)
+            .and_then(|start_vpn| {
+                self.page_range_allocator
+                    .alloc(start_vpn, start_vpn + count)
+                    .map(|_| (start_vpn, start_vpn + count))
+            });
+        let (start_vpn, end_vpn) = match range {
+            Some(range) => range,
+            None => {
+                SHM_TABLE.detach(id)?;
+                return Err(KernelError::AreaAllocFailed(min_vpn, min_vpn + count));
+            }
+        };
+        // record the range first, so that the segment will be detached even if the mapping fails
+        self.shm_ranges.insert((start_vpn, end_vpn), id);
+        let mut page_table = self.page_table.exclusive_access();
+        for (offset, ppn) in ppns.into_iter().enumerate() {
+            page_table.map_without_alloc(start_vpn + offset, ppn, PageTableFlags::RWU)?;
+        }
+        Ok(PageTable::cal_base_va_with(start_vpn))
+    }
+
+    /// Detach the shared memory segment which was attached at the virtual address
+    ///
+    /// - Arguments
+    ///     - va: the base virtual address returned by [`Space::attach_shm`]
+    ///
+    /// - Returns
+    ///     - Ok(true): the segment was detached
+    ///     - Ok(false): there was no segment attached at the virtual address
+    ///
+    /// - Errors
+    ///     - ShmDoesNotExist(id)
+    ///     - IdNotDeallocable(id)
+    pub(crate) fn detach_shm(&mut self, va: usize) -> Result<bool> {
+        let start_vpn = Self::vpn_floor(va);
+        let range = self
+            .shm_ranges
+            .keys()
+            .find(|(shm_start_vpn, _)| *shm_start_vpn == start_vpn)
+            .copied();
+        match range {
+            Some(range) => {
+                self.detach_shm_range(range)?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Unmap the pages of the shared memory segment and decrease its reference count
+    ///
+    /// - Arguments
+    ///     - range: the page range of the attached segment
+    ///
+    /// - Errors
+    ///     - ShmDoesNotExist(id)
+    ///     - IdNotDeallocable(id)
+    fn detach_shm_range(&mut self, range: (usize, usize)) -> Result<()> {
+        let id = match self.shm_ranges.remove(&range) {
+            Some(id) => id,
+            None => return Ok(()),
+        };
+        let (start_vpn, end_vpn) = range;
+        let mut page_table = self.page_table.exclusive_access();
+        page_table.begin_tlb_batch();
+        for vpn in start_vpn..end_vpn {
+            // the pages may be partially mapped if the attachment failed
+            let _ = page_table.unmap_without_dealloc(vpn);
+        }
+        page_table.end_tlb_batch();
+        drop(page_table);
+        self.page_range_allocator.dealloc(start_vpn, end_vpn);
+        SHM_TABLE.detach(id)
+    }
+
+    /// Detach all of the shared memory segments in the space
+    fn detach_all_shm(&mut self) {
+        let ranges: Vec<(usize, usize)> = self.shm_ranges.keys().copied().collect();
+        for range in ranges {
+            if let Err(error) = self.detach_shm_range(range) {
+                warn!("Failed to detach shared memory at {:?}: {}", range, error);
+            }
+        }
+    }
+}
+impl Drop for Space {
+    /// Release the attached shared memory segments, the frames of the areas will be released by themselves
+    fn drop(&mut self) {
+        self.detach_all_shm();
     }
 }
 
@@ -885,7 +1585,6 @@ impl KERNEL_SPACE {
     /// ```
     ///
     /// - Arguments
-    ///     - asid: the unique id of the address space
     ///     - data: the elf binary byte data sclice
     ///
     /// - Returns
@@ -895,8 +1594,11 @@ impl KERNEL_SPACE {
     ///
     /// - Errors
     ///     - ParseElfError
+    ///     - IdExhausted
     ///     - InvalidHeadlessTask
     ///     - UnloadableTask
+    ///     - DynamicLinkingUnsupported
+    ///     - ElfSegmentOutOfRange(start_va, end_va)
     ///     - FrameExhausted
     ///     - AreaAllocFailed(start_vpn, end_vpn)
     ///     - VPNOutOfArea(vpn, start_vpn, end_vpn)
@@ -906,11 +1608,18 @@ impl KERNEL_SPACE {
     ///     - AllocFullPageMapper(ppn)
     ///     - PPNAlreadyMapped(ppn)
     ///     - PPNNotMapped(ppn)
-    pub(crate) fn new_user_from_elf(asid: usize, data: &[u8]) -> Result<(Space, usize, usize)> {
+    pub(crate) fn new_user_from_elf(data: &[u8]) -> Result<(Space, usize, usize)> {
         let elf_bytes = ElfBytes::<AnyEndian>::minimal_parse(data)?;
-        let program_headers: Vec<ProgramHeader> = elf_bytes
+        let segments = elf_bytes
             .segments()
-            .ok_or(KernelError::InvalidHeadlessTask)?
+            .ok_or(KernelError::InvalidHeadlessTask)?;
+        // The dynamically linked executable cannot run without its interpreter.
+        // An in-kernel loader could map the interpreter named by the segment here,
+        // and jump to the interpreter's entry point with the auxiliary vector instead.
+        if segments.iter().any(|phdr| phdr.p_type == abi::PT_INTERP) {
+            return Err(KernelError::DynamicLinkingUnsupported);
+        }
+        let program_headers: Vec<ProgramHeader> = segments
             .iter()
             // This means we only accept elf loadable segments
             .filter(|phdr| phdr.p_type == abi::PT_LOAD)
@@ -918,17 +1627,28 @@ impl KERNEL_SPACE {
         if program_headers.len() == 0 {
             return Err(KernelError::UnloadableTask);
         }
-        let mut space = Space::new_bare(asid)?;
+        let mut space = Space::new_user_bare()?;
         let mut max_end_va: usize = 0;
+        // the segments must not overlap the trap contexts and the trampoline in the top of the user space
+        let limit_va = Space::get_task_trap_ctx_bottom_va(configs::MAX_TID_COUNT - 1);
         for (index, phdr) in program_headers.iter().enumerate() {
             let start_va = phdr.p_vaddr;
-            let end_va = phdr.p_vaddr + phdr.p_memsz;
+            let end_va = phdr
+                .p_vaddr
+                .checked_add(phdr.p_memsz)
+                .ok_or(KernelError::VirtualAddressOverflow(start_va as usize))?;
+            if end_va as usize > limit_va {
+                return Err(KernelError::ElfSegmentOutOfRange(
+                    start_va as usize,
+                    end_va as usize,
+                ));
+            }
             let start_vpn = Space::vpn_floor(start_va as usize);
-            let end_vpn = Space::vpn_ceil(end_va as usize);
+            let end_vpn = Space::vpn_ceil(end_va as usize)?;
             // Task code and data was restricted as User Mode flags
             let flags = Self::convert_flags(phdr.p_flags);
             max_end_va = end_va as usize;
-            let area = Area::new(
+            let mut area = Area::new(
                 start_vpn,
                 end_vpn,
                 flags | PageTableFlags::U,
@@ -937,6 +1657,9 @@ impl KERNEL_SPACE {
                 &space.page_table,
             )?;
             let segment = elf_bytes.segment_data(&phdr)?;
+            // the bss tail of the segment is not contained in the file, it must be zeroed explicitly
+            let bss_size = (phdr.p_memsz as usize).saturating_sub(segment.len());
+            area.zero_multi_pages(segment.len(), bss_size)?;
             space.push(area, 0, Some(segment))?;
             debug!(
                 "[{:#018x}, {:#018x}): mapped {} segment address range",
@@ -952,12 +1675,14 @@ impl KERNEL_SPACE {
     /// Create a new user space according to other user space,
     /// which will copy all of the bytes from other user space areas into new user space.
     /// Because if we want to fork a new task from origin, we need to copy all of the memory from it.
+    /// The new space joins the memory cgroup of the other space before copying.
     ///
     /// - Arguments
-    ///     - asid: the address space unique id
     ///     - another: the reference to the other user space
     ///
     /// - Errors
+    ///     - IdExhausted
+    ///     - CgroupBudgetExceeded(id)
     ///     - AreaAllocFailed(start_vpn, end_vpn)
     ///     - VPNOutOfArea(vpn, start_vpn, end_vpn)
     ///     - VPNAlreadyMapped(vpn)
@@ -968,11 +1693,11 @@ impl KERNEL_SPACE {
     ///     - PPNNotMapped(ppn)
     ///     - VPNNotMapped(vpn)
     pub(crate) fn new_user_from_another(
-        asid: usize,
         another: &Space,
         exclude_ranges: Option<BTreeSet<(usize, usize)>>,
     ) -> Result<Space> {
-        let mut space = Space::new_bare(asid)?;
+        let mut space = Space::new_user_bare()?;
+        space.set_cgroup(another.cgroup())?;
         for (range, another_area) in another.area_set.iter() {
             if exclude_ranges.as_ref().is_none()
                 || exclude_ranges
@@ -995,6 +1720,7 @@ impl KERNEL_SPACE {
     ///
     /// - Arguments
     ///     - kid: kernel stack's unique id
+    ///     - byte_size: the byte size of the kernel stack
     ///
     /// - Errors
     ///     - AreaAllocFailed(start_vpn, end_vpn)
@@ -1006,9 +1732,9 @@ impl KERNEL_SPACE {
     ///     - PPNAlreadyMapped(ppn)
     ///     - PPNNotMapped(ppn)
     ///     - VPNNotMapped(vpn)
-    pub(crate) fn map_kernel_task_stack(&self, kid: usize) -> Result<usize> {
+    pub(crate) fn map_kernel_task_stack(&self, kid: usize, byte_size: usize) -> Result<usize> {
         let (kernel_stack_bottom_vpn, kernel_stack_top_vpn) =
-            Space::get_kernel_task_stack_vpn_range(kid);
+            Space::get_kernel_task_stack_vpn_range(kid, byte_size);
         let mut kernel_space = self.exclusive_access();
         // Map task's kernel stack area in space
         // It must be drop by task
@@ -1034,11 +1760,12 @@ impl KERNEL_SPACE {
     ///
     /// - Arguments
     ///     - kid: kernel stack unique id
+    ///     - byte_size: the byte size of the kernel stack which was mapped
     ///
     /// - Errors
     ///     - AreaDeallocFailed(start vpn, end vpn)
-    pub(crate) fn unmap_kernel_task_stack(&self, kid: usize) -> Result<()> {
-        let (start_vpn, end_vpn) = Space::get_kernel_task_stack_vpn_range(kid);
+    pub(crate) fn unmap_kernel_task_stack(&self, kid: usize, byte_size: usize) -> Result<()> {
+        let (start_vpn, end_vpn) = Space::get_kernel_task_stack_vpn_range(kid, byte_size);
         self.exclusive_access().pop(start_vpn, end_vpn)?;
         debug!(
             "[{:#018x}, {:#018x}): unmapped kernel stack {} segment address range",
@@ -1060,41 +1787,459 @@ pub(crate) fn init_kernel_space() {
 
 #[cfg(test)]
 mod tests {
+    use super::super::cgroup::CGROUP_TABLE;
     use super::super::*;
     use super::*;
 
+    const STACK_SIZE: usize = configs::USER_TASK_STACK_BYTE_SIZE;
+
+    #[test_case]
+    fn test_cal_user_task_stack_va_range() {
+        let end_va = configs::MEMORY_PAGE_BYTE_SIZE * 16;
+        let max_size = configs::USER_TASK_STACK_MAX_BYTE_SIZE;
+        let (start_vpn, end_vpn) = Space::get_user_task_stack_vpn_range(end_va, 0, STACK_SIZE).unwrap();
+        assert_eq!(end_vpn - start_vpn, STACK_SIZE / configs::MEMORY_PAGE_BYTE_SIZE);
+        // the stacks of different sizes share the same top address
+        let (max_start_vpn, max_end_vpn) =
+            Space::get_user_task_stack_vpn_range(end_va, 0, max_size * 2).unwrap();
+        assert_eq!(max_end_vpn, end_vpn);
+        assert_eq!(max_end_vpn - max_start_vpn, max_size / configs::MEMORY_PAGE_BYTE_SIZE);
+        // the largest stack does not overlap the stack of the previous task
+        let (next_start_vpn, _) = Space::get_user_task_stack_vpn_range(end_va, 1, max_size).unwrap();
+        assert_eq!(next_start_vpn, end_vpn + configs::KERNEL_GUARD_PAGE_COUNT);
+        assert_eq!(
+            Space::get_user_task_stack_top_va(end_va, 1).unwrap(),
+            PageTable::cal_base_va_with(
+                end_vpn + configs::KERNEL_GUARD_PAGE_COUNT + max_size / configs::MEMORY_PAGE_BYTE_SIZE
+            )
+        );
+        // the stack of the last valid task is just below the trap contexts
+        let slot_size = configs::KERNEL_GUARD_PAGE_COUNT * configs::MEMORY_PAGE_BYTE_SIZE + max_size;
+        let limit_va = Space::get_task_trap_ctx_bottom_va(configs::MAX_TID_COUNT - 1);
+        let last_tid = (limit_va - end_va) / slot_size - 1;
+        let last_top_va = Space::get_user_task_stack_top_va(end_va, last_tid).unwrap();
+        assert!(last_top_va <= limit_va);
+        assert!(limit_va - last_top_va < slot_size);
+        assert!(Space::get_user_task_stack_vpn_range(end_va, last_tid + 1, STACK_SIZE)
+            .is_err_and(|e| e.is_userstackoutoflayout()));
+    }
+
+    #[test_case]
+    fn test_cal_kernel_task_stack_guard_va_range() {
+        let size = configs::KERNEL_TASK_STACK_BYTE_SIZE;
+        let max_size = configs::KERNEL_TASK_STACK_MAX_BYTE_SIZE;
+        let (start_vpn, _) = Space::get_kernel_task_stack_vpn_range(0, size);
+        let (guard_start_va, guard_end_va) = Space::get_kernel_task_stack_guard_va_range(0, size);
+        // the guard range is just below the stack bottom and above the top of the next slot
+        assert_eq!(guard_end_va, PageTable::cal_base_va_with(start_vpn));
+        assert_eq!(guard_start_va, Space::get_kernel_task_stack_top_va(1));
+        assert_eq!(
+            guard_end_va - guard_start_va,
+            max_size - size + configs::KERNEL_GUARD_PAGE_COUNT * configs::MEMORY_PAGE_BYTE_SIZE
+        );
+        let (guard_start_va, guard_end_va) =
+            Space::get_kernel_task_stack_guard_va_range(1, max_size);
+        assert_eq!(guard_start_va, Space::get_kernel_task_stack_top_va(2));
+        assert_eq!(
+            guard_end_va - guard_start_va,
+            configs::KERNEL_GUARD_PAGE_COUNT * configs::MEMORY_PAGE_BYTE_SIZE
+        );
+    }
+
     #[test_case]
     fn test_cal_kernel_task_stack_va_range() {
+        let size = configs::KERNEL_TASK_STACK_BYTE_SIZE;
+        let max_size = configs::KERNEL_TASK_STACK_MAX_BYTE_SIZE;
+        let page_count = size / configs::MEMORY_PAGE_BYTE_SIZE;
+        let slot_page_count = max_size / configs::MEMORY_PAGE_BYTE_SIZE;
         assert_eq!(
-            Space::get_kernel_task_stack_vpn_range(0).1,
+            Space::get_kernel_task_stack_vpn_range(0, size).1,
             *TRAMPOLINE_VIRTUAL_PAGE_NUMBER
         );
         assert_eq!(
-            Space::get_kernel_task_stack_vpn_range(0).0,
-            *TRAMPOLINE_VIRTUAL_PAGE_NUMBER
-                - configs::KERNEL_TASK_STACK_BYTE_SIZE / configs::MEMORY_PAGE_BYTE_SIZE
+            Space::get_kernel_task_stack_vpn_range(0, size).0,
+            *TRAMPOLINE_VIRTUAL_PAGE_NUMBER - page_count
         );
         assert_eq!(
-            Space::get_kernel_task_stack_vpn_range(1).1,
-            *TRAMPOLINE_VIRTUAL_PAGE_NUMBER
-                - configs::KERNEL_TASK_STACK_BYTE_SIZE / configs::MEMORY_PAGE_BYTE_SIZE
-                - configs::KERNEL_GUARD_PAGE_COUNT
+            Space::get_kernel_task_stack_vpn_range(1, size).1,
+            *TRAMPOLINE_VIRTUAL_PAGE_NUMBER - slot_page_count - configs::KERNEL_GUARD_PAGE_COUNT
         );
         assert_eq!(
-            Space::get_kernel_task_stack_vpn_range(1).0,
+            Space::get_kernel_task_stack_vpn_range(1, size).0,
             *TRAMPOLINE_VIRTUAL_PAGE_NUMBER
-                - configs::KERNEL_TASK_STACK_BYTE_SIZE / configs::MEMORY_PAGE_BYTE_SIZE * 2
+                - slot_page_count
+                - page_count
                 - configs::KERNEL_GUARD_PAGE_COUNT
         );
+        // the size is limited by the max size and the top is always the same
+        assert_eq!(
+            Space::get_kernel_task_stack_vpn_range(1, max_size * 2),
+            Space::get_kernel_task_stack_vpn_range(1, max_size)
+        );
+        assert_eq!(
+            Space::get_kernel_task_stack_vpn_range(1, max_size).1,
+            Space::get_kernel_task_stack_vpn_range(1, size).1
+        );
+    }
+
+    #[test_case]
+    fn test_space_classify_fault() {
+        let mut space = Space::new_bare(1).unwrap();
+        let end_va = configs::MEMORY_PAGE_BYTE_SIZE * 16;
+        assert!(space.alloc_user_task_stack(end_va, 1, STACK_SIZE).is_ok());
+        let (start_vpn, end_vpn) = Space::get_user_task_stack_vpn_range(end_va, 1, STACK_SIZE).unwrap();
+        let guard_va = PageTable::cal_base_va_with(start_vpn) - 1;
+        let stack_va = PageTable::cal_base_va_with(end_vpn) - 1;
+        assert_eq!(space.classify_fault(guard_va), FaultKind::StackOverflow(1));
+        assert_eq!(space.classify_fault(stack_va), FaultKind::Protection);
+        assert_eq!(space.classify_fault(0), FaultKind::Unmapped);
+        assert!(space.dealloc_user_task_stack(end_va, 1, STACK_SIZE).is_ok());
+        assert_eq!(space.classify_fault(guard_va), FaultKind::Unmapped);
+        assert_eq!(space.classify_fault(stack_va), FaultKind::Unmapped);
+    }
+
+    #[test_case]
+    fn test_space_discard_user_pages() {
+        let mut space = Space::new_bare(1).unwrap();
+        let end_va = configs::MEMORY_PAGE_BYTE_SIZE * 16;
+        assert!(space.alloc_user_task_stack(end_va, 1, STACK_SIZE).is_ok());
+        let (start_vpn, _) = Space::get_user_task_stack_vpn_range(end_va, 1, STACK_SIZE).unwrap();
+        let va = PageTable::cal_base_va_with(start_vpn);
+        space.page_table.access().get_byte_array(start_vpn).unwrap().fill(0xff);
+        assert!(!space.discard_user_pages(va + 1, 1).unwrap());
+        assert!(!space.discard_user_pages(0, 1).unwrap());
+        assert!(space.discard_user_pages(va, 1).unwrap());
+        assert_eq!(space.classify_fault(va), FaultKind::Unmapped);
+        assert_eq!(space.refault(va + 1).unwrap(), Some(RefaultKind::Minor));
+        assert!(space.refault(va).unwrap().is_none());
+        let bytes = space.page_table.access().get_byte_array(start_vpn).unwrap();
+        assert!(bytes.iter().all(|byte| *byte == 0));
+        assert!(space.dealloc_user_task_stack(end_va, 1, STACK_SIZE).is_ok());
+    }
+
+    #[test_case]
+    fn test_space_swap_out_cold_pages() {
+        let mut space = Space::new_bare(1).unwrap();
+        let end_va = configs::MEMORY_PAGE_BYTE_SIZE * 16;
+        assert!(space.alloc_user_task_stack(end_va, 1, STACK_SIZE).is_ok());
+        let (start_vpn, _) = Space::get_user_task_stack_vpn_range(end_va, 1, STACK_SIZE).unwrap();
+        let va = PageTable::cal_base_va_with(start_vpn);
+        space.page_table.access().get_byte_array(start_vpn).unwrap().fill(0x5a);
+        // the pages were never accessed by the user mode, so they are all cold
+        assert_eq!(space.swap_out_cold_pages(1).unwrap(), 1);
+        assert!(space.page_table.access().translate_ppn_with(start_vpn).is_none());
+        // the kernel maps the evicted page back before accessing it on behalf of the user
+        assert!(space.check_user_range(va as *const u8, 1, true).is_ok());
+        let bytes = space.page_table.access().get_byte_array(start_vpn).unwrap();
+        assert!(bytes.iter().all(|byte| *byte == 0x5a));
+        assert!(space.dealloc_user_task_stack(end_va, 1, STACK_SIZE).is_ok());
+    }
+
+    #[test_case]
+    fn test_space_batched_operations_flush_once() {
+        let mut space = Space::new_bare(1).unwrap();
+        let end_va = configs::MEMORY_PAGE_BYTE_SIZE * 16;
+        assert!(space.alloc_user_task_stack(end_va, 1, STACK_SIZE).is_ok());
+        let flushes = space.page_table.access().tlb_flushes();
+        // nothing was accessed, so no entry was modified by the scanning
+        assert!(space.scan_working_set().is_empty());
+        assert_eq!(space.page_table.access().tlb_flushes(), flushes);
+        // the inactive space is flushed once after evicting the pages
+        assert!(!space.page_table.access().is_active());
+        assert_eq!(space.swap_out_cold_pages(2).unwrap(), 2);
+        assert_eq!(space.page_table.access().tlb_flushes(), flushes + 1);
+        space.recycle_data_pages();
+        assert_eq!(space.page_table.access().tlb_flushes(), flushes + 2);
+    }
+
+    #[test_case]
+    fn test_space_charge_mem_cgroup() {
+        let page_count = STACK_SIZE / configs::MEMORY_PAGE_BYTE_SIZE;
+        let id = CGROUP_TABLE.create(page_count * 2 - 1).unwrap();
+        let cgroup = CGROUP_TABLE.get(id).unwrap();
+        let mut space = Space::new_bare(1).unwrap();
+        let end_va = configs::MEMORY_PAGE_BYTE_SIZE * 16;
+        assert!(space.alloc_user_task_stack(end_va, 1, STACK_SIZE).is_ok());
+        // the frames which were mapped before joining are moved into the group
+        assert!(space.set_cgroup(Some(Arc::clone(&cgroup))).is_ok());
+        assert_eq!(cgroup.stat().usage, page_count);
+        // the second stack exceeds the budget even if the global frames remain
+        assert!(space
+            .alloc_user_task_stack(end_va, 2, STACK_SIZE)
+            .is_err_and(|e| e.is_cgroupbudgetexceeded()));
+        assert_eq!(cgroup.stat().usage, page_count);
+        assert_eq!(cgroup.stat().failcnt, 1);
+        // the forked space joins the same group
+        let another = KERNEL_SPACE::new_user_from_another(&space, Some(BTreeSet::new()));
+        assert!(another.is_err_and(|e| e.is_cgroupbudgetexceeded()));
+        assert_eq!(cgroup.stat().usage, page_count);
+        assert!(space.dealloc_user_task_stack(end_va, 1, STACK_SIZE).is_ok());
+        assert_eq!(cgroup.stat().usage, 0);
+        assert!(space.alloc_user_task_stack(end_va, 1, STACK_SIZE).is_ok());
+        drop(space);
+        assert_eq!(cgroup.stat().usage, 0);
+        assert_eq!(cgroup.stat().peak, page_count * 2 - 1);
+    }
+
+    #[test_case]
+    fn test_space_check_user_range() {
+        let mut space = Space::new_bare(1).unwrap();
+        let end_va = configs::MEMORY_PAGE_BYTE_SIZE * 16;
+        assert!(space.alloc_user_task_stack(end_va, 0, STACK_SIZE).is_ok());
+        assert!(space.alloc_task_trap_ctx(0).is_ok());
+        let (start_vpn, end_vpn) = Space::get_user_task_stack_vpn_range(end_va, 0, STACK_SIZE).unwrap();
+        let start_va = PageTable::cal_base_va_with(start_vpn) as *const u8;
+        let stack_size = (end_vpn - start_vpn) * configs::MEMORY_PAGE_BYTE_SIZE;
+        assert!(space.check_user_range(start_va, stack_size, true).is_ok());
+        assert!(space.check_user_range(start_va, stack_size, false).is_ok());
+        assert!(space
+            .check_user_range(start_va, stack_size + 1, false)
+            .is_err_and(|e| e.is_userrangenotaccessible()));
+        assert!(space.check_user_range(0 as *const u8, 0, false).is_ok());
+        assert!(space.check_user_range(0 as *const u8, 1, false).is_err());
+        assert!(space.check_user_range(usize::MAX as *const u8, 2, false).is_err());
+        let trap_ctx_va = Space::get_task_trap_ctx_bottom_va(0) as *const u8;
+        assert!(space.check_user_range(trap_ctx_va, 1, false).is_err());
+        let trampoline_va = configs::TRAMPOLINE_VIRTUAL_BASE_ADDR as *const u8;
+        assert!(space.check_user_range(trampoline_va, 1, false).is_err());
+    }
+
+    #[test_case]
+    fn test_space_translated_string_bounded() {
+        let mut space = Space::new_bare(1).unwrap();
+        let end_va = configs::MEMORY_PAGE_BYTE_SIZE * 16;
+        assert!(space.alloc_user_task_stack(end_va, 0, STACK_SIZE).is_ok());
+        let (start_vpn, _) = Space::get_user_task_stack_vpn_range(end_va, 0, STACK_SIZE).unwrap();
+        // let the string cross the page boundary
+        let ptr = (PageTable::cal_base_va_with(start_vpn + 1) - 2) as *const u8;
+        let bytes = space.translated_refmut(ptr as *const [u8; 2]).unwrap();
+        *bytes = [b'/', b'a'];
+        let bytes = space.translated_refmut((ptr as usize + 2) as *const [u8; 2]).unwrap();
+        *bytes = [b'b', 0];
+        assert_eq!(space.translated_string(ptr).unwrap(), "/ab");
+        assert_eq!(space.translated_string_bounded(ptr, 4).unwrap(), "/ab");
+        assert!(space
+            .translated_string_bounded(ptr, 3)
+            .is_err_and(|e| e.is_unterminatedstring()));
+    }
+
+    #[test_case]
+    fn test_space_copy_bytes_between() {
+        let end_va = configs::MEMORY_PAGE_BYTE_SIZE * 16;
+        let (start_vpn, _) = Space::get_user_task_stack_vpn_range(end_va, 0, STACK_SIZE).unwrap();
+        let base_va = PageTable::cal_base_va_with(start_vpn);
+        let mut src_space = Space::new_bare(1).unwrap();
+        let mut dst_space = Space::new_bare(2).unwrap();
+        assert!(src_space.alloc_user_task_stack(end_va, 0, STACK_SIZE).is_ok());
+        assert!(dst_space.alloc_user_task_stack(end_va, 0, STACK_SIZE).is_ok());
+        // starts mid-page in the source and ends mid-page in the destination
+        let src_va = base_va + 100;
+        let dst_va = base_va + configs::MEMORY_PAGE_BYTE_SIZE - 300;
+        let len = configs::MEMORY_PAGE_BYTE_SIZE + 200;
+        let mut iterator = src_space
+            .translated_byte_buffers(src_va as *const u8, len)
+            .unwrap()
+            .into_iter();
+        for i in 0..len {
+            iterator.next_mut(i as u8).unwrap();
+        }
+        assert!(dst_space
+            .copy_bytes_between(&src_space, src_va, dst_va, len)
+            .is_ok());
+        let mut iterator = dst_space
+            .translated_byte_buffers((dst_va - 1) as *const u8, len + 2)
+            .unwrap()
+            .into_iter();
+        assert_eq!(iterator.next(), Some(0));
+        for i in 0..len {
+            assert_eq!(iterator.next(), Some(i as u8));
+        }
+        assert_eq!(iterator.next(), Some(0));
+        assert!(dst_space
+            .copy_bytes_between(&src_space, src_va, 0, 1)
+            .is_err_and(|e| e.is_vpnnotmapped()));
+    }
+
+    #[test_case]
+    fn test_space_copy_to_and_from_user_benchmark() {
+        use crate::lang::timer::now_us;
+        // both buffers and the mapped frames must fit in the kernel heap and frame allocator
+        let byte_size = 128 * 1024;
+        let rounds = 16;
+        let start_vpn = 16;
+        let end_vpn = start_vpn + byte_size / configs::MEMORY_PAGE_BYTE_SIZE;
+        let mut space = Space::new_bare(1).unwrap();
+        let area = Area::new(
+            start_vpn,
+            end_vpn,
+            PageTableFlags::RWU,
+            AreaMapping::Framed,
+            &space.page_range_allocator,
+            &space.page_table,
+        )
+        .unwrap();
+        assert!(space.push(area, 0, None).is_ok());
+        // starts and ends in the middle of the pages
+        let ptr = (PageTable::cal_base_va_with(start_vpn) + 7) as *mut u8;
+        let len = byte_size - 14;
+        let mut src: Vec<u8> = (0..len).map(|i| i as u8).collect();
+        let mut dst = vec![0u8; len];
+
+        let start_us = now_us();
+        for round in 0..rounds {
+            src[0] = round as u8;
+            let mut iterator = space.translated_byte_buffers(ptr, len).unwrap().into_iter();
+            for byte in src.iter() {
+                iterator.next_mut(*byte).unwrap();
+            }
+            let mut iterator = space.translated_byte_buffers(ptr, len).unwrap().into_iter();
+            for byte in dst.iter_mut() {
+                *byte = iterator.next().unwrap();
+            }
+            assert!(src == dst);
+        }
+        let iterator_us = now_us() - start_us;
+
+        let start_us = now_us();
+        for round in 0..rounds {
+            src[0] = round as u8;
+            dst.fill(0);
+            assert!(space.copy_to_user(ptr, &src).is_ok());
+            assert!(space.copy_from_user(&mut dst, ptr).is_ok());
+            assert!(src == dst);
+        }
+        let bulk_us = now_us() - start_us;
+        info!(
+            "copy {} bytes into and out of user space {} times: byte iterator {}us, bulk copy {}us",
+            len, rounds, iterator_us, bulk_us
+        );
+        assert!(space
+            .copy_to_user(0 as *mut u8, &src[..1])
+            .is_err_and(|e| e.is_vpnnotmapped()));
+        // the value straddling the page boundary is copied as a whole
+        let ptr = (PageTable::cal_base_va_with(start_vpn + 1) - 4) as *mut [u32; 2];
+        assert!(space.copy_value_to_user(ptr, &[1, 2]).is_ok());
+        assert_eq!(space.copy_value_from_user(ptr).unwrap(), [1, 2]);
+    }
+
+    #[test_case]
+    fn test_space_recycled_frame_is_zeroed() {
+        let end_va = configs::MEMORY_PAGE_BYTE_SIZE * 16;
+        let (start_vpn, end_vpn) = Space::get_user_task_stack_vpn_range(end_va, 0, STACK_SIZE).unwrap();
+        let mut space = Space::new_bare(1).unwrap();
+        assert!(space.alloc_user_task_stack(end_va, 0, STACK_SIZE).is_ok());
+        let mut ppns = BTreeSet::new();
+        for vpn in start_vpn..end_vpn {
+            space.page_table.access().get_byte_array(vpn).unwrap().fill(0xff);
+            ppns.insert(space.page_table.access().translate_ppn_with(vpn).unwrap());
+        }
+        drop(space);
+        let mut space = Space::new_bare(2).unwrap();
+        assert!(space.alloc_user_task_stack(end_va, 0, STACK_SIZE).is_ok());
+        let mut recycled = false;
+        for vpn in start_vpn..end_vpn {
+            let ppn = space.page_table.access().translate_ppn_with(vpn).unwrap();
+            recycled |= ppns.contains(&ppn);
+            let bytes = space.page_table.access().get_byte_array(vpn).unwrap();
+            assert!(bytes.iter().all(|byte| *byte == 0));
+        }
+        assert!(recycled);
     }
 
     #[test_case]
     fn test_space_vpn_ceil() {
-        assert_eq!(Space::vpn_ceil(0), 0);
-        assert_eq!(Space::vpn_ceil(1), 1);
-        assert_eq!(Space::vpn_ceil(configs::MEMORY_PAGE_BYTE_SIZE - 1), 1);
-        assert_eq!(Space::vpn_ceil(configs::MEMORY_PAGE_BYTE_SIZE), 1);
-        assert_eq!(Space::vpn_ceil(configs::MEMORY_PAGE_BYTE_SIZE + 1), 2);
+        assert_eq!(Space::vpn_ceil(0).unwrap(), 0);
+        assert_eq!(Space::vpn_ceil(1).unwrap(), 1);
+        assert_eq!(Space::vpn_ceil(configs::MEMORY_PAGE_BYTE_SIZE - 1).unwrap(), 1);
+        assert_eq!(Space::vpn_ceil(configs::MEMORY_PAGE_BYTE_SIZE).unwrap(), 1);
+        assert_eq!(Space::vpn_ceil(configs::MEMORY_PAGE_BYTE_SIZE + 1).unwrap(), 2);
+        assert_eq!(
+            Space::vpn_ceil(configs::TRAMPOLINE_VIRTUAL_BASE_ADDR).unwrap(),
+            *MAX_VIRTUAL_PAGE_NUMBER
+        );
+        assert!(Space::vpn_ceil(configs::TRAMPOLINE_VIRTUAL_BASE_ADDR + 1)
+            .is_err_and(|e| e.is_virtualaddressoverflow()));
+        assert!(Space::vpn_ceil(usize::MAX).is_err_and(|e| e.is_virtualaddressoverflow()));
+        assert!(Space::get_user_task_stack_vpn_range(usize::MAX, 0, STACK_SIZE).is_err());
+        assert!(
+            Space::get_user_task_stack_vpn_range(configs::TRAMPOLINE_VIRTUAL_BASE_ADDR, 0, STACK_SIZE)
+                .is_err()
+        );
+    }
+
+    /// Build a minimal riscv64 elf executable,
+    /// all the program headers of the types share the same payload and bss tail at the virtual address
+    fn build_elf(types: &[u32], vaddr: u64, payload: &[u8], bss_size: usize) -> Vec<u8> {
+        const EHDR_SIZE: usize = 64;
+        const PHDR_SIZE: usize = 56;
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&[0x7f, b'E', b'L', b'F', 2, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        bytes.extend_from_slice(&abi::ET_EXEC.to_le_bytes());
+        bytes.extend_from_slice(&abi::EM_RISCV.to_le_bytes());
+        bytes.extend_from_slice(&1u32.to_le_bytes());
+        bytes.extend_from_slice(&vaddr.to_le_bytes());
+        bytes.extend_from_slice(&(EHDR_SIZE as u64).to_le_bytes());
+        bytes.extend_from_slice(&0u64.to_le_bytes());
+        bytes.extend_from_slice(&0u32.to_le_bytes());
+        bytes.extend_from_slice(&(EHDR_SIZE as u16).to_le_bytes());
+        bytes.extend_from_slice(&(PHDR_SIZE as u16).to_le_bytes());
+        bytes.extend_from_slice(&(types.len() as u16).to_le_bytes());
+        bytes.extend_from_slice(&[0; 6]);
+        let offset = EHDR_SIZE + PHDR_SIZE * types.len();
+        for p_type in types {
+            bytes.extend_from_slice(&p_type.to_le_bytes());
+            bytes.extend_from_slice(&(abi::PF_R | abi::PF_X).to_le_bytes());
+            bytes.extend_from_slice(&(offset as u64).to_le_bytes());
+            bytes.extend_from_slice(&vaddr.to_le_bytes());
+            bytes.extend_from_slice(&vaddr.to_le_bytes());
+            bytes.extend_from_slice(&(payload.len() as u64).to_le_bytes());
+            bytes.extend_from_slice(&((payload.len() + bss_size) as u64).to_le_bytes());
+            bytes.extend_from_slice(&(configs::MEMORY_PAGE_BYTE_SIZE as u64).to_le_bytes());
+        }
+        bytes.extend_from_slice(payload);
+        bytes
+    }
+
+    #[test_case]
+    fn test_new_user_from_elf_segment_range() {
+        let payload = [0x13u8, 0, 0, 0];
+        let data = build_elf(&[abi::PT_LOAD], 0x10000, &payload, 0);
+        let (space, base_size, entry_point) = KERNEL_SPACE::new_user_from_elf(&data).unwrap();
+        assert_eq!(base_size, 0x10000 + payload.len());
+        assert_eq!(entry_point, 0x10000);
+        drop(space);
+        let trap_ctx_va = configs::TRAP_CTX_VIRTUAL_BASE_ADDR as u64;
+        let data = build_elf(&[abi::PT_LOAD], trap_ctx_va, &payload, 0);
+        assert!(KERNEL_SPACE::new_user_from_elf(&data)
+            .is_err_and(|e| e.is_elfsegmentoutofrange()));
+        let limit_va = Space::get_task_trap_ctx_bottom_va(configs::MAX_TID_COUNT - 1);
+        let data = build_elf(&[abi::PT_LOAD], (limit_va - 2) as u64, &payload, 0);
+        assert!(KERNEL_SPACE::new_user_from_elf(&data)
+            .is_err_and(|e| e.is_elfsegmentoutofrange()));
+        let data = build_elf(&[abi::PT_LOAD], 1 << 60, &payload, 0);
+        assert!(KERNEL_SPACE::new_user_from_elf(&data)
+            .is_err_and(|e| e.is_elfsegmentoutofrange()));
+    }
+
+    #[test_case]
+    fn test_new_user_from_elf_zero_bss() {
+        let payload = [0x13u8; 16];
+        let bss_size = configs::MEMORY_PAGE_BYTE_SIZE * 2;
+        let data = build_elf(&[abi::PT_LOAD], 0x10000, &payload, bss_size);
+        let (space, base_size, _) = KERNEL_SPACE::new_user_from_elf(&data).unwrap();
+        assert_eq!(base_size, 0x10000 + payload.len() + bss_size);
+        let mut bytes = vec![0xffu8; payload.len() + bss_size];
+        assert!(space.copy_from_user(&mut bytes, 0x10000 as *const u8).is_ok());
+        assert_eq!(&bytes[..payload.len()], &payload);
+        assert!(bytes[payload.len()..].iter().all(|byte| *byte == 0));
+    }
+
+    #[test_case]
+    fn test_new_user_from_elf_with_interpreter() {
+        let payload = b"/lib/ld-linux-riscv64-lp64d.so.1\0";
+        let data = build_elf(&[abi::PT_INTERP, abi::PT_LOAD], 0x10000, payload, 0);
+        assert!(KERNEL_SPACE::new_user_from_elf(&data)
+            .is_err_and(|e| e.is_dynamiclinkingunsupported()));
     }
 
     #[test_case]
@@ -1104,6 +2249,7 @@ mod tests {
         assert_eq!(Space::vpn_floor(configs::MEMORY_PAGE_BYTE_SIZE - 1), 0);
         assert_eq!(Space::vpn_floor(configs::MEMORY_PAGE_BYTE_SIZE), 1);
         assert_eq!(Space::vpn_floor(configs::MEMORY_PAGE_BYTE_SIZE + 1), 1);
+        assert_eq!(Space::vpn_floor(usize::MAX), *MAX_VIRTUAL_PAGE_NUMBER);
     }
 
     #[test_case]
@@ -1149,17 +2295,78 @@ mod tests {
         )
     }
 
+    #[test_case]
+    fn test_kernel_space_scan_working_set() {
+        let size = configs::MEMORY_PAGE_BYTE_SIZE * 2;
+        let end_vpn = KERNEL_SPACE.map_kernel_task_stack(3, size).unwrap();
+        let start_vpn = end_vpn - 2;
+        KERNEL_SPACE.access().scan_working_set();
+        let touched = KERNEL_SPACE.access().scan_working_set();
+        assert!(!touched.contains(&start_vpn));
+        // the hardware sets the accessed and dirty bits again after the TLB entry was flushed
+        let va = PageTable::cal_base_va_with(start_vpn);
+        unsafe { core::ptr::write_volatile(va as *mut u8, 1) };
+        let touched = KERNEL_SPACE.access().scan_working_set();
+        assert!(touched.contains(&start_vpn));
+        assert!(!touched.contains(&(start_vpn + 1)));
+        let kernel_space = KERNEL_SPACE.access();
+        let mut page_table = kernel_space.page_table.exclusive_access();
+        assert!(page_table.take_dirty(start_vpn));
+        assert!(!page_table.take_dirty(start_vpn));
+        assert!(!page_table.take_accessed(start_vpn));
+        drop(page_table);
+        drop(kernel_space);
+        assert!(KERNEL_SPACE.unmap_kernel_task_stack(3, size).is_ok());
+    }
+
+    #[test_case]
+    fn test_kernel_space_unmapped_page_is_flushed() {
+        let (vpn, _) = Space::get_kernel_task_stack_vpn_range(3, configs::MEMORY_PAGE_BYTE_SIZE);
+        let va = PageTable::cal_base_va_with(vpn);
+        let first = frame::FRAME_ALLOCATOR.alloc().unwrap();
+        let second = frame::FRAME_ALLOCATOR.alloc().unwrap();
+        unsafe { core::ptr::write_volatile(first.pa() as *mut u8, 1) };
+        unsafe { core::ptr::write_volatile(second.pa() as *mut u8, 2) };
+        let kernel_space = KERNEL_SPACE.access();
+        let mut page_table = kernel_space.page_table.exclusive_access();
+        assert!(page_table.is_active());
+        page_table.map_without_alloc(vpn, first.ppn(), PageTableFlags::RW).unwrap();
+        // load the translation of the first frame into the TLB
+        assert_eq!(unsafe { core::ptr::read_volatile(va as *const u8) }, 1);
+        let flushes = page_table.tlb_flushes();
+        assert_eq!(page_table.unmap_without_dealloc(vpn).unwrap(), first.ppn());
+        assert!(page_table.translate_ppn_with(vpn).is_none());
+        assert_eq!(page_table.tlb_flushes(), flushes + 1);
+        // the stale translation would still read the first frame if it was not flushed
+        page_table.map_without_alloc(vpn, second.ppn(), PageTableFlags::RW).unwrap();
+        assert_eq!(unsafe { core::ptr::read_volatile(va as *const u8) }, 2);
+        page_table.begin_tlb_batch();
+        page_table.unmap_without_dealloc(vpn).unwrap();
+        assert_eq!(page_table.tlb_flushes(), flushes + 1);
+        page_table.end_tlb_batch();
+        assert_eq!(page_table.tlb_flushes(), flushes + 2);
+        page_table.map_without_alloc(vpn, first.ppn(), PageTableFlags::RW).unwrap();
+        assert_eq!(unsafe { core::ptr::read_volatile(va as *const u8) }, 1);
+        page_table.unmap_without_dealloc(vpn).unwrap();
+    }
+
     #[test_case]
     fn test_kernel_space_map_and_unmap_kernel_task_stack() {
+        let size = configs::KERNEL_TASK_STACK_BYTE_SIZE;
         // try create task 3's kernel stack
-        assert!(KERNEL_SPACE.map_kernel_task_stack(3).is_ok());
-        assert!(KERNEL_SPACE.unmap_kernel_task_stack(3).is_ok());
+        assert!(KERNEL_SPACE.map_kernel_task_stack(3, size).is_ok());
+        assert!(KERNEL_SPACE.unmap_kernel_task_stack(3, size).is_ok());
         // try to duplicate create task kernel stack
-        assert!(KERNEL_SPACE.map_kernel_task_stack(3).is_ok_and(|vpn| vpn
+        assert!(KERNEL_SPACE.map_kernel_task_stack(3, size).is_ok_and(|vpn| vpn
             == *TRAMPOLINE_VIRTUAL_PAGE_NUMBER
                 - 3 * (configs::KERNEL_GUARD_PAGE_COUNT
-                    + (configs::KERNEL_TASK_STACK_BYTE_SIZE / configs::MEMORY_PAGE_BYTE_SIZE))));
-        assert!(KERNEL_SPACE.map_kernel_task_stack(3).is_err());
-        assert!(KERNEL_SPACE.unmap_kernel_task_stack(3).is_ok());
+                    + (configs::KERNEL_TASK_STACK_MAX_BYTE_SIZE / configs::MEMORY_PAGE_BYTE_SIZE))));
+        assert!(KERNEL_SPACE.map_kernel_task_stack(3, size).is_err());
+        assert!(KERNEL_SPACE.unmap_kernel_task_stack(3, size).is_ok());
+        // the large kernel stack is mapped at the same top
+        let max_size = configs::KERNEL_TASK_STACK_MAX_BYTE_SIZE;
+        assert!(KERNEL_SPACE.map_kernel_task_stack(3, max_size).is_ok());
+        assert!(KERNEL_SPACE.unmap_kernel_task_stack(3, size).is_err());
+        assert!(KERNEL_SPACE.unmap_kernel_task_stack(3, max_size).is_ok());
     }
 }
@@ -0,0 +1,187 @@
+// @author:    olinex
+// @time:      2024/08/20
+
+// self mods
+
+// use other mods
+use alloc::sync::Arc;
+use frontier_fs::block::BlockDevice;
+use frontier_fs::configs::BLOCK_BYTE_SIZE;
+
+// use self mods
+use super::PageBytes;
+use crate::configs;
+use crate::drivers::blocks::BlockDeviceImpl;
+use crate::lang::container::UserPromiseRefCell;
+use crate::prelude::*;
+use crate::task::allocator::BTreeIdAllocator;
+
+/// The count of the device blocks which are used by each swap slot
+const SLOT_BLOCK_COUNT: usize = configs::MEMORY_PAGE_BYTE_SIZE / BLOCK_BYTE_SIZE;
+
+/// The swap space on the raw block device, which keeps the evicted anonymous pages.
+/// The device is not formatted, each page will be written into a slot of the contiguous blocks.
+pub(crate) struct SwapSpace {
+    /// The raw block device, the swapping will be disabled if there is no device
+    device: Option<BlockDeviceImpl>,
+    /// The allocator of the swap slots
+    slot_allocator: BTreeIdAllocator,
+}
+impl SwapSpace {
+    /// Create a new swap space by probing the swap device slot
+    fn new() -> Self {
+        // the swap device is always polled, because the faulting space is borrowed during swapping
+        let device = match BlockDeviceImpl::new(configs::VIRTIO_SWAP_SLOT, None) {
+            Ok(device) => Some(device),
+            Err(error) => {
+                debug!(
+                    "skip swap device at {:#x}: {}",
+                    configs::VIRTIO_SWAP_SLOT,
+                    error
+                );
+                None
+            }
+        };
+        Self {
+            device,
+            slot_allocator: BTreeIdAllocator::new(configs::SWAP_SLOT_COUNT),
+        }
+    }
+
+    /// Read the page from the swap slot
+    ///
+    /// - Arguments
+    ///     - slot: the swap slot
+    ///     - bytes: the page bytes which will be overwritten
+    ///
+    /// - Errors
+    ///     - SwapIOFailed(slot)
+    fn read_slot(&self, slot: usize, bytes: &mut PageBytes) -> Result<()> {
+        let device = self.device.as_ref().ok_or(KernelError::SwapIOFailed(slot))?;
+        for (index, block) in bytes.chunks_mut(BLOCK_BYTE_SIZE).enumerate() {
+            if device.read_block(slot * SLOT_BLOCK_COUNT + index, block).is_some() {
+                return Err(KernelError::SwapIOFailed(slot));
+            }
+        }
+        Ok(())
+    }
+
+    /// Write the page into the swap slot
+    ///
+    /// - Arguments
+    ///     - slot: the swap slot
+    ///     - bytes: the page bytes which will be written
+    ///
+    /// - Errors
+    ///     - SwapIOFailed(slot)
+    fn write_slot(&self, slot: usize, bytes: &PageBytes) -> Result<()> {
+        let device = self.device.as_ref().ok_or(KernelError::SwapIOFailed(slot))?;
+        for (index, block) in bytes.chunks(BLOCK_BYTE_SIZE).enumerate() {
+            if device.write_block(slot * SLOT_BLOCK_COUNT + index, block).is_some() {
+                return Err(KernelError::SwapIOFailed(slot));
+            }
+        }
+        Ok(())
+    }
+}
+
+lazy_static! {
+    /// The global swap space, the swap device will be probed when it is used at the first time
+    pub(crate) static ref SWAP_SPACE: Arc<UserPromiseRefCell<SwapSpace>> =
+        Arc::new(unsafe { UserPromiseRefCell::new(SwapSpace::new()) });
+}
+impl SWAP_SPACE {
+    /// Check if there is a swap device to evict the pages
+    pub(crate) fn is_enabled(&self) -> bool {
+        self.access().device.is_some()
+    }
+
+    /// Write the page into a new swap slot
+    ///
+    /// - Arguments
+    ///     - bytes: the page bytes which will be evicted
+    ///
+    /// - Returns
+    ///     - Ok(Some(slot)): the page was written into the slot
+    ///     - Ok(None): there is no swap device or no free slot
+    ///
+    /// - Errors
+    ///     - SwapIOFailed(slot)
+    ///     - IdNotDeallocable(slot)
+    pub(crate) fn swap_out(&self, bytes: &PageBytes) -> Result<Option<usize>> {
+        let mut swap = self.exclusive_access();
+        if swap.device.is_none() {
+            return Ok(None);
+        }
+        let slot = match swap.slot_allocator.alloc() {
+            Ok(slot) => slot,
+            Err(error) if error.is_idexhausted() => return Ok(None),
+            Err(error) => return Err(error),
+        };
+        if let Err(error) = swap.write_slot(slot, bytes) {
+            swap.slot_allocator.dealloc(slot)?;
+            return Err(error);
+        }
+        Ok(Some(slot))
+    }
+
+    /// Read the page from the swap slot and keep the slot, which is used when copying the space
+    ///
+    /// - Arguments
+    ///     - slot: the swap slot
+    ///     - bytes: the page bytes which will be overwritten
+    ///
+    /// - Errors
+    ///     - SwapIOFailed(slot)
+    pub(crate) fn read(&self, slot: usize, bytes: &mut PageBytes) -> Result<()> {
+        self.access().read_slot(slot, bytes)
+    }
+
+    /// Read the page from the swap slot and release the slot
+    ///
+    /// - Arguments
+    ///     - slot: the swap slot
+    ///     - bytes: the page bytes which will be overwritten
+    ///
+    /// - Errors
+    ///     - SwapIOFailed(slot)
+    ///     - IdNotDeallocable(slot)
+    pub(crate) fn swap_in(&self, slot: usize, bytes: &mut PageBytes) -> Result<()> {
+        let mut swap = self.exclusive_access();
+        swap.read_slot(slot, bytes)?;
+        swap.slot_allocator.dealloc(slot)
+    }
+
+    /// Release the swap slot without reading, which is used when the evicted page is dropped
+    ///
+    /// - Arguments
+    ///     - slot: the swap slot
+    ///
+    /// - Errors
+    ///     - IdNotDeallocable(slot)
+    pub(crate) fn free(&self, slot: usize) -> Result<()> {
+        self.exclusive_access().slot_allocator.dealloc(slot)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_swap_space_swap_out_and_in() {
+        assert!(SWAP_SPACE.is_enabled());
+        let mut bytes = [0u8; configs::MEMORY_PAGE_BYTE_SIZE];
+        for (index, byte) in bytes.iter_mut().enumerate() {
+            *byte = index as u8;
+        }
+        let slot = SWAP_SPACE.swap_out(&bytes).unwrap().unwrap();
+        let mut copied = [0u8; configs::MEMORY_PAGE_BYTE_SIZE];
+        assert!(SWAP_SPACE.read(slot, &mut copied).is_ok());
+        assert_eq!(copied, bytes);
+        copied.fill(0);
+        assert!(SWAP_SPACE.swap_in(slot, &mut copied).is_ok());
+        assert_eq!(copied, bytes);
+        assert!(SWAP_SPACE.free(slot).is_err_and(|e| e.is_idnotdeallocable()));
+    }
+}
@@ -0,0 +1,124 @@
+// @author:    olinex
+// @time:      2024/07/03
+
+// self mods
+
+// use other mods
+
+// use self mods
+
+/// The accumulator of the TLB flush operations.
+/// When unmapping a lot of pages in bulk, flushing TLB for each page is very slow,
+/// so we accumulate the flush requests in batch and issue a single whole flush at the end.
+/// Outside the batch, only the active page table is flushed, which is always the kernel space
+/// while the kernel is running, because the whole TLB is flushed by the trampoline
+/// whenever the user space is switched to, see [`crate::memory::page_table::PageTable::is_active`].
+/// But the batch always ends with one real flush whenever any entry was modified in it,
+/// so the bulk operations on the user spaces never depend on the flushing of the trampoline.
+#[derive(Debug)]
+pub(crate) struct TlbFlushBatch {
+    /// The nested depth of the batch, zero means no batch is running
+    depth: usize,
+    /// The count of the pages waiting for flushing
+    pending: usize,
+    /// The count of the flush operations which have been issued
+    flushes: usize,
+}
+impl TlbFlushBatch {
+    /// Create a new empty batch
+    pub(crate) fn new() -> Self {
+        Self {
+            depth: 0,
+            pending: 0,
+            flushes: 0,
+        }
+    }
+
+    /// Begin a batch, batches can be nested and only the outermost batch will flush
+    pub(crate) fn begin(&mut self) {
+        self.depth += 1;
+    }
+
+    /// Record a page which need to be flushed.
+    ///
+    /// - Arguments
+    ///     - active: whether the page table is active
+    ///
+    /// - Returns
+    ///     - true: no batch is running and the page table is active, flush the page immediately
+    ///     - false: the page was accumulated into the batch, or the page table is inactive
+    pub(crate) fn record(&mut self, active: bool) -> bool {
+        if self.depth > 0 {
+            self.pending += 1;
+            false
+        } else if active {
+            self.flushes += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// End a batch.
+    ///
+    /// - Returns
+    ///     - true: the outermost batch was ended and a whole flush should be issued
+    ///     - false: no need to flush
+    pub(crate) fn end(&mut self) -> bool {
+        assert!(self.depth > 0);
+        self.depth -= 1;
+        if self.depth == 0 && self.pending > 0 {
+            self.pending = 0;
+            self.flushes += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Get the count of the flush operations which have been issued
+    #[cfg(test)]
+    pub(crate) fn flushes(&self) -> usize {
+        self.flushes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_tlb_flush_batch_per_page() {
+        let mut batch = TlbFlushBatch::new();
+        for _ in 0..1024 {
+            assert!(batch.record(true));
+        }
+        assert_eq!(batch.flushes(), 1024);
+        // the inactive page table is flushed when it is switched to
+        assert!(!batch.record(false));
+        assert_eq!(batch.flushes(), 1024);
+    }
+
+    #[test_case]
+    fn test_tlb_flush_batch_bulk() {
+        let mut batch = TlbFlushBatch::new();
+        batch.begin();
+        for _ in 0..16 {
+            batch.begin();
+            for active in [true, false].into_iter().cycle().take(64) {
+                assert!(!batch.record(active));
+            }
+            assert!(!batch.end());
+        }
+        assert!(batch.end());
+        assert_eq!(batch.flushes(), 1);
+        batch.begin();
+        assert!(!batch.end());
+        assert_eq!(batch.flushes(), 1);
+        // the batch of the inactive page table is flushed too
+        batch.begin();
+        assert!(!batch.record(false));
+        assert!(batch.end());
+        assert_eq!(batch.flushes(), 2);
+    }
+}
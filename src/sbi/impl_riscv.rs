@@ -5,11 +5,12 @@
 
 // use other mods
 use core::arch::asm;
-use riscv::register::{satp, sie, stvec, time};
+use riscv::register::{cycle, instret, satp, sepc, sie, sip, stvec, time};
 use sbi::legacy;
+use sbi::system_reset::{system_reset, ResetReason, ResetType};
 
 // use self mods
-use super::{SBIApi, SBI};
+use super::{ResetMode, SBIApi, SBI};
 
 impl SBIApi for SBI {
 
@@ -18,11 +19,34 @@ impl SBIApi for SBI {
         legacy::shutdown()
     }
 
+    #[inline(always)]
+    fn reset(mode: ResetMode) -> ! {
+        match mode {
+            ResetMode::Halt => loop {
+                unsafe { Self::wait_for_interrupt() };
+            },
+            ResetMode::PowerOff => Self::shutdown(),
+            ResetMode::Reset => {
+                // the system reset extension may not be implemented by the firmware,
+                // in that case the machine will be powered off instead
+                if let Err(error) = system_reset(ResetType::ColdReboot, ResetReason::NoReason) {
+                    error!("System reset failed cause: {:?}", error);
+                }
+                Self::shutdown()
+            }
+        }
+    }
+
     #[inline(always)]
     unsafe fn sync_icache() {
         asm!("fence.i");
     }
 
+    #[inline(always)]
+    unsafe fn wait_for_interrupt() {
+        asm!("wfi");
+    }
+
     #[inline(always)]
     unsafe fn set_direct_trap_vector(addr: usize) {
         stvec::write(addr, stvec::TrapMode::Direct)
@@ -38,11 +62,42 @@ impl SBIApi for SBI {
         legacy::console_getchar()
     }
 
+    #[inline(always)]
+    fn read_trap_pc() -> usize {
+        sepc::read()
+    }
+
+    #[inline(always)]
+    fn read_frame_registers() -> (usize, usize, usize) {
+        let (sp, ra, fp): (usize, usize, usize);
+        unsafe {
+            asm!(
+                "mv {sp}, sp",
+                "mv {ra}, ra",
+                "mv {fp}, s0",
+                sp = out(reg) sp,
+                ra = out(reg) ra,
+                fp = out(reg) fp,
+            );
+        }
+        (sp, ra, fp)
+    }
+
     #[inline(always)]
     fn get_timer() -> usize {
         time::read()
     }
 
+    #[inline(always)]
+    fn get_cycle() -> usize {
+        cycle::read()
+    }
+
+    #[inline(always)]
+    fn get_instret() -> usize {
+        instret::read()
+    }
+
     #[inline(always)]
     fn set_timer(timer: usize) {
         legacy::set_timer(timer as u64);
@@ -53,6 +108,36 @@ impl SBIApi for SBI {
         sie::set_stimer();
     }
 
+    #[inline(always)]
+    fn is_timer_interrupt_pending() -> bool {
+        sip::read().stimer()
+    }
+
+    #[inline(always)]
+    fn send_ipi(hart_mask: usize) {
+        legacy::send_ipi(&hart_mask as *const usize)
+    }
+
+    #[inline(always)]
+    unsafe fn enable_software_interrupt() {
+        sie::set_ssoft();
+    }
+
+    #[inline(always)]
+    unsafe fn clear_software_interrupt() {
+        sip::clear_ssoft();
+    }
+
+    #[inline(always)]
+    unsafe fn enable_external_interrupt() {
+        sie::set_sext();
+    }
+
+    #[inline(always)]
+    fn is_external_interrupt_pending() -> bool {
+        sip::read().sext()
+    }
+
     #[inline(always)]
     fn read_mmu_token() -> usize {
         satp::read().bits()
@@ -68,4 +153,14 @@ impl SBIApi for SBI {
     unsafe fn sync_tlb() {
         asm!("sfence.vma");
     }
+
+    #[inline(always)]
+    unsafe fn sync_tlb_va(va: usize) {
+        asm!("sfence.vma {va}, zero", va = in(reg) va);
+    }
+
+    #[inline(always)]
+    unsafe fn sync_tlb_asid(asid: usize) {
+        asm!("sfence.vma zero, {asid}", asid = in(reg) asid);
+    }
 }
@@ -7,13 +7,49 @@
 
 // use self mods
 
+/// The modes of resetting the machine
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ResetMode {
+    /// Stop all the harts but keep the power on
+    Halt = 0,
+    /// Power off the machine
+    PowerOff = 1,
+    /// Restart the machine
+    Reset = 2,
+}
+impl ResetMode {
+    /// Parse the reset mode from the raw value, None will be returned if the value is unknown
+    ///
+    /// - Arguments
+    ///     - mode: the raw value of the reset mode
+    pub(crate) fn from_raw(mode: usize) -> Option<Self> {
+        match mode {
+            0 => Some(Self::Halt),
+            1 => Some(Self::PowerOff),
+            2 => Some(Self::Reset),
+            _ => None,
+        }
+    }
+}
+
 pub(crate) trait SBIApi {
     /// Shutdown the kernel
     fn shutdown() -> !;
 
+    /// Reset the machine by the mode, the kernel will never return
+    ///
+    /// - Arguments
+    ///     - mode: the reset mode
+    fn reset(mode: ResetMode) -> !;
+
     /// This function is used to ensure that a subsequent instruction fetch will see any previous data stores already visible in the same hart
     unsafe fn sync_icache();
 
+    /// Make current hart to sleep until an interrupt become pending.
+    /// The hart will be woken up by the pending interrupt which was enabled in the interrupt enable register,
+    /// even if the global interrupt switch was closed, and in that case the trap will not be taken.
+    unsafe fn wait_for_interrupt();
+
     /// Set the trap handler's entry point address to cpu in direct mode
     ///
     /// - Arguments
@@ -29,11 +65,27 @@ pub(crate) trait SBIApi {
     /// Get a single character from console and return
     fn console_getchar() -> Option<u8>;
 
+    /// Read the program counter where the last trap happened
+    fn read_trap_pc() -> usize;
+
+    /// Read the stack pointer, the return address and the frame pointer of the caller.
+    /// It must be inlined into the caller, otherwise the registers of itself will be read.
+    ///
+    /// - Returns
+    ///     - (sp, ra, fp)
+    fn read_frame_registers() -> (usize, usize, usize);
+
     /// Get the current time counter since the cpu have been reset previously.
     /// The counter value will increase in a fix frequency, so the frequence of the time counter is relative to the board of the SoC.
     /// If the function return 1, it don't means it return 1 second or 1 millisecond.
     fn get_timer() -> usize;
 
+    /// Get the count of the cpu cycles since the cpu have been reset previously
+    fn get_cycle() -> usize;
+
+    /// Get the count of the retired instructions since the cpu have been reset previously
+    fn get_instret() -> usize;
+
     /// Set the time counter for cpu to interrupt in the next time
     ///
     /// - Arguments
@@ -43,6 +95,27 @@ pub(crate) trait SBIApi {
     /// Set cpu timer interrupt enabled
     unsafe fn enable_timer_interrupt();
 
+    /// Check if the timer interrupt is pending but not taken
+    fn is_timer_interrupt_pending() -> bool;
+
+    /// Send the inter-processor interrupt to other harts, which will be received as supervisor software interrupt
+    ///
+    /// - Arguments
+    ///     - hart_mask: the bit mask of the harts to be interrupted, the bit `n` represent the hart `n`
+    fn send_ipi(hart_mask: usize);
+
+    /// Set cpu software interrupt enabled, so that the hart can receive the inter-processor interrupt
+    unsafe fn enable_software_interrupt();
+
+    /// Clear the pending software interrupt, which means the inter-processor interrupt was acknowledged
+    unsafe fn clear_software_interrupt();
+
+    /// Set cpu external interrupt enabled, so that the hart can receive the interrupts of the devices
+    unsafe fn enable_external_interrupt();
+
+    /// Check if the external interrupt is pending but not taken
+    fn is_external_interrupt_pending() -> bool;
+
     /// Read the memory manager unit's token which is represent to the page table
     fn read_mmu_token() -> usize;
 
@@ -54,6 +127,19 @@ pub(crate) trait SBIApi {
 
     /// This function is used to make sure that translation lookup buffer is synchronized with the page table forcefully
     unsafe fn sync_tlb();
+
+    /// Synchronize the translation lookup buffer with the page table for only one virtual address
+    ///
+    /// - Arguments
+    ///     - va: the virtual address which mapping was changed
+    unsafe fn sync_tlb_va(va: usize);
+
+    /// Synchronize the translation lookup buffer with the page table for only one address space,
+    /// the global mappings are not flushed.
+    ///
+    /// - Arguments
+    ///     - asid: the address space id whose mappings were changed or reused
+    unsafe fn sync_tlb_asid(asid: usize);
 }
 
 pub(crate) struct SBI;
@@ -0,0 +1,105 @@
+// @author:    olinex
+// @time:      2024/08/23
+
+// self mods
+
+// use other mods
+
+// use self mods
+
+// The memory ordering of the helpers:
+// - the read-modify-write operations use both acquire and release (`aqrl` in RISC-V),
+//   because they are used as the synchronization points between the waiter and the waker,
+//   which must not be reordered in either direction
+
+cfg_if! {
+    if #[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))] {
+        use core::arch::asm;
+
+        /// Compare the word with the current value and exchange it with the new value if they are equal,
+        /// by the load-reserved and store-conditional instructions.
+        /// The store-conditional will fail if any other hart wrote the word after the load-reserved,
+        /// so the comparing and the exchanging are atomic as a whole.
+        ///
+        /// - Arguments
+        ///     - word: the kernel pointer of the word, which must be aligned to 4 bytes
+        ///     - current: the expected value of the word
+        ///     - new: the value to store if the word is the expected value
+        ///
+        /// - Returns
+        ///     - Ok(previous): the word was exchanged
+        ///     - Err(previous): the word was not the expected value
+        #[inline(always)]
+        pub(crate) fn compare_exchange_u32(word: *mut u32, current: u32, new: u32) -> Result<u32, u32> {
+            let previous: u32;
+            unsafe {
+                asm!(
+                    "1:",
+                    "lr.w.aqrl {previous}, ({word})",
+                    "bne {previous}, {current}, 2f",
+                    "sc.w.aqrl {failed}, {new}, ({word})",
+                    "bnez {failed}, 1b",
+                    "2:",
+                    word = in(reg) word,
+                    // the word was sign extended by the load-reserved in rv64
+                    current = in(reg) current as i32 as isize,
+                    new = in(reg) new,
+                    previous = out(reg) previous,
+                    failed = out(reg) _,
+                );
+            }
+            if previous == current {
+                Ok(previous)
+            } else {
+                Err(previous)
+            }
+        }
+    } else {
+        compile_error!("Unknown target_arch to implying atomic helpers");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_compare_exchange() {
+        let mut word = 1u32;
+        assert_eq!(compare_exchange_u32(&mut word, 2, 3), Err(1));
+        assert_eq!(compare_exchange_u32(&mut word, 1, 3), Ok(1));
+        assert_eq!(word, 3);
+        // the high bit must not be confused by the sign extension
+        word = u32::MAX;
+        assert_eq!(compare_exchange_u32(&mut word, u32::MAX, 0), Ok(u32::MAX));
+        assert_eq!(word, 0);
+    }
+
+    /// Interleave two compare-and-swap increasing loops step by step on the single hart,
+    /// the exchange with the value loaded before the other loop's store must fail and retry,
+    /// so that no increment is lost
+    #[test_case]
+    fn test_compare_exchange_rejects_stale_value() {
+        let mut counter = 0u32;
+        let mut loaded = [None; 2];
+        let mut done = [0; 2];
+        let mut retries = 0;
+        let mut step = 0;
+        while done.iter().any(|count| *count < 100) {
+            let turn = step % 2;
+            step += 1;
+            if done[turn] == 100 {
+                continue;
+            }
+            match loaded[turn].take() {
+                None => loaded[turn] = Some(counter),
+                Some(previous) => match compare_exchange_u32(&mut counter, previous, previous + 1) {
+                    Ok(_) => done[turn] += 1,
+                    Err(_) => retries += 1,
+                },
+            }
+        }
+        assert_eq!(counter, 200);
+        assert!(retries > 0);
+    }
+}
@@ -0,0 +1,148 @@
+// @author:    olinex
+// @time:      2024/08/14
+
+// self mods
+
+// use other mods
+use alloc::collections::VecDeque;
+use alloc::sync::{Arc, Weak};
+
+// use self mods
+use crate::lang::container::UserPromiseRefCell;
+use crate::prelude::*;
+use crate::task::model::TaskControlBlock;
+use crate::task::{block_current_and_run_other_task, PROCESSOR, TASK_SCHEDULER};
+
+struct BarrierInner {
+    /// The count of the tasks which must arrive before all of them are released
+    count: usize,
+    /// The count of the tasks which have arrived in the current round
+    arrived: usize,
+    /// The round of the barrier, increased each time the waiting tasks are released
+    generation: usize,
+    waiting: VecDeque<Weak<TaskControlBlock>>,
+}
+
+/// The barrier blocks the tasks until the configured count of them have arrived,
+/// then the last arrival releases all of them, and the barrier can be reused for the next round.
+pub(crate) struct Barrier {
+    inner: UserPromiseRefCell<BarrierInner>,
+}
+impl Barrier {
+    /// Create a new barrier
+    ///
+    /// - Arguments
+    ///     - count: the count of the tasks in each round, it must be greater than zero
+    pub(crate) fn new(count: usize) -> Self {
+        assert!(count > 0);
+        Self {
+            inner: unsafe {
+                UserPromiseRefCell::new(BarrierInner {
+                    count,
+                    arrived: 0,
+                    generation: 0,
+                    waiting: VecDeque::new(),
+                })
+            },
+        }
+    }
+
+    /// Wait until all the tasks of the current round have arrived.
+    /// The task may be woken up by others, such as signals, so it will block again until the round is finished.
+    ///
+    /// - Returns
+    ///     - true: current task is the last arrival which released the others
+    ///     - false: current task was released by the last arrival
+    ///
+    /// - Errors
+    ///     - ProcessHaveNotTask
+    pub(crate) fn wait(&self) -> Result<bool> {
+        let mut inner = self.inner.exclusive_access();
+        inner.arrived += 1;
+        if inner.arrived >= inner.count {
+            inner.arrived = 0;
+            inner.generation = inner.generation.wrapping_add(1);
+            while let Some(other) = inner.waiting.pop_front() {
+                if let Some(other) = other.upgrade() {
+                    other.mark_suspended();
+                    TASK_SCHEDULER.put_read_task(other);
+                }
+            }
+            return Ok(true);
+        }
+        let generation = inner.generation;
+        loop {
+            let current_task = PROCESSOR.current_task()?;
+            inner.waiting.push_back(Arc::downgrade(&current_task));
+            drop(inner);
+            drop(current_task);
+            block_current_and_run_other_task()?;
+            inner = self.inner.exclusive_access();
+            if inner.generation != generation {
+                return Ok(false);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::configs;
+    use crate::task::model::ForkedProcess;
+    use alloc::vec::Vec;
+
+    #[test_case]
+    fn test_barrier_reused_for_next_round() {
+        let barrier = Barrier::new(1);
+        for round in 1..=2 {
+            assert!(barrier.wait().is_ok_and(|leader| leader));
+            assert_eq!(barrier.inner.access().generation, round);
+            assert_eq!(barrier.inner.access().arrived, 0);
+        }
+    }
+
+    #[test_case]
+    fn test_barrier_last_arrival_releases_threads() {
+        let process = ForkedProcess::new();
+        let threads: Vec<_> = (0..2)
+            .map(|_| {
+                process
+                    .alloc_task(
+                        0,
+                        &[],
+                        configs::KERNEL_TASK_STACK_BYTE_SIZE,
+                        configs::USER_TASK_STACK_BYTE_SIZE,
+                    )
+                    .unwrap()
+            })
+            .collect();
+        let barrier = Barrier::new(3);
+        // the blocking side cannot switch away in the tests,
+        // so the earlier arrivals are recorded just like waiting before blocking
+        for thread in threads.iter() {
+            let mut inner = barrier.inner.exclusive_access();
+            inner.arrived += 1;
+            inner.waiting.push_back(Arc::downgrade(thread));
+            drop(inner);
+            thread.mark_blocked();
+        }
+        let current = process.enter();
+        // the last arrival releases all the blocked threads and never blocks itself
+        assert!(barrier.wait().is_ok_and(|leader| leader));
+        drop(current);
+        for thread in threads.iter() {
+            assert!(!thread.is_blocked());
+            assert!(TASK_SCHEDULER.fetch_specific(thread));
+            assert!(Arc::ptr_eq(&TASK_SCHEDULER.pop_ready_task().unwrap(), thread));
+        }
+        let inner = barrier.inner.access();
+        assert_eq!(inner.generation, 1);
+        assert_eq!(inner.arrived, 0);
+        assert!(inner.waiting.is_empty());
+        drop(inner);
+        for thread in threads.iter() {
+            thread.mark_zombie(0);
+        }
+    }
+}
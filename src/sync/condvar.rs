@@ -11,18 +11,43 @@ use alloc::sync::{Arc, Weak};
 use crate::lang::container::UserPromiseRefCell;
 use crate::prelude::*;
 use crate::task::model::TaskControlBlock;
-use crate::task::{block_current_and_run_other_task, PROCESSOR, TASK_SCHEDULER};
+use crate::task::{
+    block_current_and_run_other_task, sleep_current_and_run_other_task, PROCESSOR, TASK_SCHEDULER,
+};
 
 use super::mutex::Mutex;
 
 pub(crate) trait Condvar: Sync + Send {
     fn signal(&self) -> Result<()>;
     fn wait(&self, mutex: Arc<dyn Mutex>) -> Result<()>;
+    /// Wait until the condvar was signaled or the timeout elapsed,
+    /// the mutex will be re-acquired in both cases.
+    ///
+    /// - Returns
+    ///     - true: the condvar was signaled
+    ///     - false: the timeout elapsed
+    fn wait_timeout(&self, mutex: Arc<dyn Mutex>, us: usize) -> Result<bool>;
 }
 
 struct CondvarBlockingInner {
     waiting: VecDeque<Weak<TaskControlBlock>>,
 }
+impl CondvarBlockingInner {
+    /// Remove the task from the waiting queue
+    ///
+    /// - Arguments
+    ///     - task: the task which was woken up
+    ///
+    /// - Returns
+    ///     - true: the task was still waiting, so it was not woken up by signal
+    ///     - false: the task has been removed by signal
+    fn remove(&mut self, task: &Arc<TaskControlBlock>) -> bool {
+        let length = self.waiting.len();
+        self.waiting
+            .retain(|waiter| waiter.as_ptr() != Arc::as_ptr(task));
+        self.waiting.len() != length
+    }
+}
 
 pub(crate) struct CondvarBlocking {
     inner: UserPromiseRefCell<CondvarBlockingInner>,
@@ -39,13 +64,24 @@ impl CondvarBlocking {
     }
 }
 impl Condvar for CondvarBlocking {
+    /// Wake up the first task which is still blocked in the waiting queue.
+    /// The waiter which has timed out is ready but not yet removed itself from the queue,
+    /// so it must be skipped, or it will be put into the ready queue twice.
     fn signal(&self) -> Result<()> {
         let mut inner = self.inner.exclusive_access();
-        while let Some(prev) = inner.waiting.pop_front() {
-            if let Some(prev) = prev.upgrade() {
-                prev.mark_suspended();
-                TASK_SCHEDULER.put_read_task(prev);
-                break;
+        inner.waiting.retain(|waiter| waiter.strong_count() > 0);
+        let index = inner
+            .waiting
+            .iter()
+            .position(|waiter| waiter.upgrade().is_some_and(|task| task.is_blocked()));
+        if let Some(task) = index
+            .and_then(|index| inner.waiting.remove(index))
+            .and_then(|waiter| waiter.upgrade())
+        {
+            // the timed waiter is sleeping in the timer heap and must be removed from it
+            if !TASK_SCHEDULER.wake_sleep_task(&task) {
+                task.mark_suspended();
+                TASK_SCHEDULER.put_read_task(task);
             }
         }
         Ok(())
@@ -61,4 +97,45 @@ impl Condvar for CondvarBlocking {
         block_current_and_run_other_task()?;
         mutex.lock()
     }
+
+    fn wait_timeout(&self, mutex: Arc<dyn Mutex>, us: usize) -> Result<bool> {
+        mutex.unlock()?;
+        let task = PROCESSOR.current_task()?;
+        let mut inner = self.inner.exclusive_access();
+        inner.waiting.push_back(Arc::downgrade(&task));
+        drop(inner);
+        drop(task);
+        sleep_current_and_run_other_task(us)?;
+        // the signaled waiter has been removed from the queue by the signal,
+        // otherwise the waiter was woken up by the timer and must remove itself
+        let task = PROCESSOR.current_task()?;
+        let timeout = self.inner.exclusive_access().remove(&task);
+        drop(task);
+        mutex.lock()?;
+        Ok(!timeout)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task::model::ForkedProcess;
+
+    #[test_case]
+    fn test_condvar_signal_skips_timed_out_waiter() {
+        let process = ForkedProcess::new();
+        let task = process.inner_access().root_task();
+        let condvar = CondvarBlocking::new();
+        condvar
+            .inner
+            .exclusive_access()
+            .waiting
+            .push_back(Arc::downgrade(&task));
+        // the waiter was woken up by the timer, so it is ready and must not be signaled
+        assert!(!task.is_blocked());
+        assert!(condvar.signal().is_ok());
+        assert_eq!(condvar.inner.access().waiting.len(), 1);
+        assert!(condvar.inner.exclusive_access().remove(&task));
+        assert!(!condvar.inner.exclusive_access().remove(&task));
+    }
 }
@@ -0,0 +1,170 @@
+// @author:    olinex
+// @time:      2024/08/12
+
+// self mods
+
+// use other mods
+use alloc::collections::BTreeMap;
+use alloc::sync::{Arc, Weak};
+
+// use self mods
+use crate::configs;
+use crate::lang::container::UserPromiseRefCell;
+use crate::prelude::*;
+use crate::task::model::TaskControlBlock;
+
+/// The wait-for graph between the blocked tasks and the owners of the resources they are waiting for.
+/// Only the resources which have an owner, such as the mutexes, can close a cycle.
+/// The semaphores and the condition variables have no owner, so waiting for them adds no edge.
+pub(crate) struct WaitForGraph {
+    /// The owner task of each resource, the address of the resource as key
+    owners: BTreeMap<usize, Weak<TaskControlBlock>>,
+    /// The resource which each blocked task is waiting for, the address of the task as key
+    waiting: BTreeMap<usize, usize>,
+}
+impl WaitForGraph {
+    /// Create a new empty wait-for graph
+    fn new() -> Self {
+        Self {
+            owners: BTreeMap::new(),
+            waiting: BTreeMap::new(),
+        }
+    }
+
+    /// Record the task as the owner of the resource, and the task no longer waits for any resource.
+    ///
+    /// - Arguments
+    ///     - resource: the address of the resource
+    ///     - task: the task which owns the resource now
+    pub(crate) fn acquire(&mut self, resource: usize, task: &Arc<TaskControlBlock>) {
+        self.waiting.remove(&(Arc::as_ptr(task) as usize));
+        self.owners.insert(resource, Arc::downgrade(task));
+    }
+
+    /// Remove the owner of the resource
+    ///
+    /// - Arguments
+    ///     - resource: the address of the resource
+    pub(crate) fn release(&mut self, resource: usize) {
+        self.owners.remove(&resource);
+    }
+
+    /// Check if the task waiting for the resource will close a cycle in the graph.
+    /// The path is followed from the owner of the resource to the owner of the resource it is waiting for,
+    /// until the path reaches the task or a running owner.
+    ///
+    /// - Arguments
+    ///     - resource: the address of the resource
+    ///     - task: the task which is going to wait for the resource
+    pub(crate) fn would_deadlock(&self, resource: usize, task: &Arc<TaskControlBlock>) -> bool {
+        let task = Arc::as_ptr(task) as usize;
+        let mut resource = resource;
+        // each task waits for one resource at most, so the path cannot be longer than the waiting tasks
+        for _ in 0..=self.waiting.len() {
+            let owner = match self.owners.get(&resource).and_then(|owner| owner.upgrade()) {
+                Some(owner) => Arc::as_ptr(&owner) as usize,
+                None => return false,
+            };
+            if owner == task {
+                return true;
+            }
+            resource = match self.waiting.get(&owner) {
+                Some(resource) => *resource,
+                None => return false,
+            };
+        }
+        false
+    }
+
+    /// Add the edge from the task to the resource before the task is blocked.
+    ///
+    /// - Arguments
+    ///     - resource: the address of the resource
+    ///     - task: the task which is going to wait for the resource
+    ///
+    /// - Errors
+    ///     - Deadlock
+    pub(crate) fn block(&mut self, resource: usize, task: &Arc<TaskControlBlock>) -> Result<()> {
+        if self.would_deadlock(resource, task) {
+            return Err(KernelError::Deadlock);
+        }
+        self.waiting.insert(Arc::as_ptr(task) as usize, resource);
+        Ok(())
+    }
+
+    /// Remove the edge from the task after the task was woken up
+    ///
+    /// - Arguments
+    ///     - task: the task which was woken up
+    pub(crate) fn unblock(&mut self, task: &Arc<TaskControlBlock>) {
+        self.waiting.remove(&(Arc::as_ptr(task) as usize));
+    }
+}
+
+lazy_static! {
+    /// The global wait-for graph, only used when [`configs::ENABLE_DEADLOCK_DETECTION`] is true
+    pub(crate) static ref WAIT_FOR_GRAPH: UserPromiseRefCell<WaitForGraph> =
+        unsafe { UserPromiseRefCell::new(WaitForGraph::new()) };
+}
+impl WAIT_FOR_GRAPH {
+    /// See [`WaitForGraph::acquire`], do nothing if the deadlock detection was disabled
+    pub(crate) fn acquire(&self, resource: usize, task: &Arc<TaskControlBlock>) {
+        if configs::ENABLE_DEADLOCK_DETECTION {
+            self.exclusive_access().acquire(resource, task);
+        }
+    }
+
+    /// See [`WaitForGraph::release`], do nothing if the deadlock detection was disabled
+    pub(crate) fn release(&self, resource: usize) {
+        if configs::ENABLE_DEADLOCK_DETECTION {
+            self.exclusive_access().release(resource);
+        }
+    }
+
+    /// See [`WaitForGraph::block`], do nothing if the deadlock detection was disabled
+    ///
+    /// - Errors
+    ///     - Deadlock
+    pub(crate) fn block(&self, resource: usize, task: &Arc<TaskControlBlock>) -> Result<()> {
+        if configs::ENABLE_DEADLOCK_DETECTION {
+            self.exclusive_access().block(resource, task)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// See [`WaitForGraph::unblock`], do nothing if the deadlock detection was disabled
+    pub(crate) fn unblock(&self, task: &Arc<TaskControlBlock>) {
+        if configs::ENABLE_DEADLOCK_DETECTION {
+            self.exclusive_access().unblock(task);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task::model::{ForkedProcess, INIT_PROC};
+
+    #[test_case]
+    fn test_wait_for_graph_two_lock_inversion() {
+        let first_task = INIT_PROC.inner_access().root_task();
+        let process = ForkedProcess::fork(&first_task);
+        let second_task = process.inner_access().root_task();
+        let (first_lock, second_lock) = (1, 2);
+        let mut graph = WaitForGraph::new();
+        graph.acquire(first_lock, &first_task);
+        graph.acquire(second_lock, &second_task);
+        assert!(graph.block(second_lock, &first_task).is_ok());
+        assert!(graph
+            .block(first_lock, &second_task)
+            .is_err_and(|e| e.is_deadlock()));
+        // the cycle is broken once the first task gives up waiting
+        graph.unblock(&first_task);
+        assert!(graph.block(first_lock, &second_task).is_ok());
+        assert!(graph.would_deadlock(second_lock, &first_task));
+        graph.release(first_lock);
+        graph.acquire(first_lock, &second_task);
+        assert!(!graph.would_deadlock(first_lock, &first_task));
+    }
+}
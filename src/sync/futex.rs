@@ -0,0 +1,205 @@
+// @author:    olinex
+// @time:      2024/07/05
+
+// self mods
+
+// use other mods
+use alloc::collections::VecDeque;
+use alloc::sync::{Arc, Weak};
+use alloc::vec::Vec;
+use spin::mutex::TicketMutex;
+
+// use self mods
+use super::atomic;
+use crate::configs;
+use crate::prelude::*;
+use crate::task::model::TaskControlBlock;
+use crate::task::{block_current_and_run_other_task, PROCESSOR, TASK_SCHEDULER};
+
+/// The multiplier of the fibonacci hashing, which is 2^64 divided by the golden ratio
+const FIBONACCI_HASH_MULTIPLIER: u64 = 0x9E37_79B9_7F4A_7C15;
+
+/// The waiting queue in one bucket of the futex hash table,
+/// each waiting task is keyed by the physical address of the futex word.
+struct FutexBucket {
+    waiting: VecDeque<(usize, Weak<TaskControlBlock>)>,
+}
+
+/// The futex hash table, which is keyed by the physical address of the futex word,
+/// so that the processes which share the same memory can wait on the same futex.
+/// The count of the buckets is fixed when it is initialized to avoid resizing,
+/// and each bucket is protected by a ticket lock which is fair for all waiters.
+pub(crate) struct FutexTable {
+    bits: u32,
+    buckets: Vec<TicketMutex<FutexBucket>>,
+}
+impl FutexTable {
+    /// Create a new futex hash table
+    ///
+    /// - Arguments
+    ///     - count: the count of the buckets, it will be rounded up to the power of two
+    pub(crate) fn new(count: usize) -> Self {
+        let count = count.max(2).next_power_of_two();
+        let mut buckets = Vec::with_capacity(count);
+        for _ in 0..count {
+            buckets.push(TicketMutex::new(FutexBucket {
+                waiting: VecDeque::new(),
+            }));
+        }
+        Self {
+            bits: count.trailing_zeros(),
+            buckets,
+        }
+    }
+
+    /// Get the index of the bucket by the physical address.
+    /// Futex words are aligned to 4 bytes, so the lowest bits are dropped before hashing,
+    /// and the fibonacci hashing will spread the neighbour addresses into different buckets.
+    ///
+    /// - Arguments
+    ///     - pa: the physical address of the futex word
+    fn hash(&self, pa: usize) -> usize {
+        let key = (pa as u64) >> 2;
+        (key.wrapping_mul(FIBONACCI_HASH_MULTIPLIER) >> (u64::BITS - self.bits)) as usize
+    }
+
+    /// Block current task if the value of the futex word is still the expected one.
+    /// The value is checked with the bucket lock held, so that no wake up will be lost.
+    /// The checking is an atomic compare-and-swap with the same value,
+    /// so the checking will not see a torn or stale value written by other harts.
+    ///
+    /// - Arguments
+    ///     - pa: the physical address of the futex word
+    ///     - word: the kernel pointer of the futex word
+    ///     - expected: the value which the futex word should be
+    ///
+    /// - Returns
+    ///     - Ok(0): current task was woken up
+    ///     - Ok(-1): the value of the futex word is not the expected one
+    ///
+    /// - Errors
+    ///     - ProcessHaveNotTask
+    pub(crate) fn wait(&self, pa: usize, word: *const u32, expected: u32) -> Result<isize> {
+        let task = PROCESSOR.current_task()?;
+        let mut bucket = self.buckets[self.hash(pa)].lock();
+        if atomic::compare_exchange_u32(word as *mut u32, expected, expected).is_err() {
+            return Ok(-1);
+        }
+        bucket.waiting.push_back((pa, Arc::downgrade(&task)));
+        drop(bucket);
+        drop(task);
+        block_current_and_run_other_task()?;
+        Ok(0)
+    }
+
+    /// Wake up the tasks which are waiting on the futex word.
+    ///
+    /// - Arguments
+    ///     - pa: the physical address of the futex word
+    ///     - count: the max count of the tasks will be woken up
+    ///
+    /// - Returns
+    ///     - the count of the tasks were woken up
+    pub(crate) fn wake(&self, pa: usize, count: usize) -> usize {
+        let mut bucket = self.buckets[self.hash(pa)].lock();
+        let mut woken = 0;
+        let mut index = 0;
+        while woken < count && index < bucket.waiting.len() {
+            if bucket.waiting[index].0 != pa {
+                index += 1;
+                continue;
+            }
+            let (_, task) = bucket.waiting.remove(index).unwrap();
+            if let Some(task) = task.upgrade() {
+                task.mark_suspended();
+                TASK_SCHEDULER.put_read_task(task);
+                woken += 1;
+            }
+        }
+        woken
+    }
+
+    /// Whether any live task is waiting on the futex words in the physical page.
+    /// The waiting tasks are keyed by the physical address,
+    /// so the page must keep its frame until they are woken up,
+    /// otherwise waking by the physical address of the new frame will never find them.
+    ///
+    /// - Arguments
+    ///     - ppn: the physical page number
+    pub(crate) fn has_waiters(&self, ppn: usize) -> bool {
+        let start = ppn * configs::MEMORY_PAGE_BYTE_SIZE;
+        let end = start + configs::MEMORY_PAGE_BYTE_SIZE;
+        self.buckets.iter().any(|bucket| {
+            bucket
+                .lock()
+                .waiting
+                .iter()
+                .any(|(pa, task)| (start..end).contains(pa) && task.strong_count() > 0)
+        })
+    }
+
+    /// Queue the task as waiting on the futex word without blocking it,
+    /// so that the waiting can be simulated in the tests.
+    ///
+    /// - Arguments
+    ///     - pa: the physical address of the futex word
+    ///     - task: the waiting task
+    #[cfg(test)]
+    pub(crate) fn enqueue(&self, pa: usize, task: Weak<TaskControlBlock>) {
+        self.buckets[self.hash(pa)].lock().waiting.push_back((pa, task));
+    }
+}
+
+lazy_static! {
+    /// The global futex hash table shared by all processes
+    pub(crate) static ref FUTEX_TABLE: FutexTable = FutexTable::new(configs::FUTEX_HASH_BUCKET_COUNT);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task::model::INIT_PROC;
+
+    #[test_case]
+    fn test_futex_table_size() {
+        assert_eq!(FutexTable::new(0).buckets.len(), 2);
+        assert_eq!(FutexTable::new(100).buckets.len(), 128);
+        assert_eq!(FutexTable::new(256).buckets.len(), 256);
+    }
+
+    #[test_case]
+    fn test_futex_table_hash_spread() {
+        let table = FutexTable::new(64);
+        let mut counts = [0usize; 64];
+        // many distinct futex words in the continuous memory
+        for i in 0..4096 {
+            let index = table.hash(0x8040_0000 + i * 4);
+            assert!(index < 64);
+            counts[index] += 1;
+        }
+        let max = counts.iter().max().unwrap();
+        let min = counts.iter().min().unwrap();
+        assert!(*max <= 2 * 4096 / 64);
+        assert!(*min >= 4096 / 64 / 2);
+    }
+
+    #[test_case]
+    fn test_futex_table_wake_without_waiter() {
+        let table = FutexTable::new(16);
+        assert_eq!(table.wake(0x8040_0000, 1), 0);
+    }
+
+    #[test_case]
+    fn test_futex_table_has_waiters_in_page() {
+        let table = FutexTable::new(16);
+        let ppn = 0x80400;
+        let pa = ppn * configs::MEMORY_PAGE_BYTE_SIZE + 0x10;
+        let task = INIT_PROC.inner_access().root_task();
+        table.enqueue(pa, Arc::downgrade(&task));
+        assert!(table.has_waiters(ppn));
+        assert!(!table.has_waiters(ppn + 1));
+        // the exited waiters will never pin the page
+        table.enqueue(pa + configs::MEMORY_PAGE_BYTE_SIZE, Weak::new());
+        assert!(!table.has_waiters(ppn + 1));
+    }
+}
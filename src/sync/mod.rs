@@ -2,9 +2,13 @@
 // @time:      2024/06/04
 
 // self mods
+pub(crate) mod atomic;
+pub(crate) mod barrier;
 pub(crate) mod mutex;
 pub(crate) mod semaphore;
 pub(crate) mod condvar;
+pub(crate) mod deadlock;
+pub(crate) mod futex;
 
 // use other mods
 
@@ -6,8 +6,11 @@
 // use other mods
 use alloc::collections::VecDeque;
 use alloc::sync::{Arc, Weak};
+use enum_group::EnumGroup;
 
 // use self mods
+use super::deadlock::WAIT_FOR_GRAPH;
+use crate::configs;
 use crate::lang::container::UserPromiseRefCell;
 use crate::prelude::*;
 use crate::task::model::TaskControlBlock;
@@ -18,6 +21,61 @@ use crate::task::{
 pub(crate) trait Mutex: Sync + Send {
     fn lock(&self) -> Result<()>;
     fn unlock(&self) -> Result<()>;
+    /// Try to acquire the mutex without blocking or spinning,
+    /// current task will never be put into the waiting queue.
+    ///
+    /// - Returns
+    ///     - true: the mutex was acquired
+    ///     - false: the mutex is held by any task, including current task
+    ///
+    /// - Errors
+    ///     - ProcessHaveNotTask
+    fn try_lock(&self) -> Result<bool> {
+        let current_task = PROCESSOR.current_task()?;
+        Ok(self.try_lock_with(&current_task))
+    }
+    /// Try to acquire the mutex for the task without blocking or spinning
+    ///
+    /// - Arguments
+    ///     - task: the task which will hold the mutex
+    fn try_lock_with(&self, task: &Arc<TaskControlBlock>) -> bool;
+}
+
+/// The kind of the mutex which was passed by the user through the create syscall.
+#[derive(EnumGroup, Debug, Copy, Clone, PartialEq)]
+pub(crate) enum MutexKind {
+    Spin,
+    Blocking,
+    Adaptive,
+    Recursive,
+}
+impl MutexKind {
+    /// Decode the mutex kind from the syscall argument.
+    ///
+    /// - Arguments
+    ///     - kind: 0 means spin, 1 means blocking, 2 means adaptive and 3 means recursive
+    ///
+    /// - Errors
+    ///     - InvalidMutexKind(kind)
+    pub(crate) fn from_usize(kind: usize) -> Result<Self> {
+        match kind {
+            0 => Ok(Self::Spin),
+            1 => Ok(Self::Blocking),
+            2 => Ok(Self::Adaptive),
+            3 => Ok(Self::Recursive),
+            _ => Err(KernelError::InvalidMutexKind(kind)),
+        }
+    }
+
+    /// Create a new mutex object of current kind
+    pub(crate) fn create(&self) -> Arc<dyn Mutex> {
+        match self {
+            Self::Spin => Arc::new(MutexSpin::new()),
+            Self::Blocking => Arc::new(MutexBlocking::new()),
+            Self::Adaptive => Arc::new(MutexAdaptive::new()),
+            Self::Recursive => Arc::new(MutexRecursive::new()),
+        }
+    }
 }
 
 struct MutexSpinInner {
@@ -65,6 +123,15 @@ impl Mutex for MutexSpin {
         inner.locked.take();
         Ok(())
     }
+
+    fn try_lock_with(&self, task: &Arc<TaskControlBlock>) -> bool {
+        let mut inner = self.inner.exclusive_access();
+        if inner.locked.as_ref().and_then(|prev| prev.upgrade()).is_some() {
+            return false;
+        }
+        inner.locked.replace(Arc::downgrade(task));
+        true
+    }
 }
 
 struct MutexBlockingInner {
@@ -88,6 +155,24 @@ impl MutexBlocking {
             },
         }
     }
+
+    /// Get the address of the mutex as the resource in the wait-for graph
+    fn resource(&self) -> usize {
+        self as *const Self as usize
+    }
+
+    /// Check if the mutex is holding by the task
+    ///
+    /// - Arguments
+    ///     - task: the task which may hold the mutex
+    fn is_held_by(&self, task: &Arc<TaskControlBlock>) -> bool {
+        self.inner
+            .access()
+            .locked
+            .as_ref()
+            .and_then(|prev| prev.upgrade())
+            .is_some_and(|prev| Arc::as_ptr(&prev) == Arc::as_ptr(task))
+    }
 }
 impl Mutex for MutexBlocking {
     fn lock(&self) -> Result<()> {
@@ -109,15 +194,18 @@ impl Mutex for MutexBlocking {
                     suspend_current_and_run_other_task()?;
                     continue;
                 }
+                WAIT_FOR_GRAPH.block(self.resource(), &current_task)?;
                 inner.waiting.push_back(Arc::downgrade(&current_task));
                 drop(prev);
                 drop(inner);
                 drop(current_task);
                 block_current_and_run_other_task()?;
+                WAIT_FOR_GRAPH.unblock(&PROCESSOR.current_task()?);
                 continue;
             }
             inner.next.take();
             inner.locked.replace(Arc::downgrade(&current_task));
+            WAIT_FOR_GRAPH.acquire(self.resource(), &current_task);
             return Ok(());
         }
     }
@@ -146,6 +234,403 @@ impl Mutex for MutexBlocking {
             }
         }
         inner.locked.take();
+        WAIT_FOR_GRAPH.release(self.resource());
         Ok(())
     }
+
+    /// The mutex which was handed over to the next waiting task can only be acquired by that task
+    fn try_lock_with(&self, task: &Arc<TaskControlBlock>) -> bool {
+        let mut inner = self.inner.exclusive_access();
+        if inner.locked.as_ref().and_then(|prev| prev.upgrade()).is_some()
+            || inner
+                .next
+                .as_ref()
+                .and_then(|next| next.upgrade())
+                .is_some_and(|next| Arc::as_ptr(&next) != Arc::as_ptr(task))
+        {
+            return false;
+        }
+        inner.next.take();
+        inner.locked.replace(Arc::downgrade(task));
+        WAIT_FOR_GRAPH.acquire(self.resource(), task);
+        true
+    }
+}
+
+impl Drop for MutexBlocking {
+    /// The address may be reused by another mutex, so the owner must be removed from the wait-for graph
+    fn drop(&mut self) {
+        WAIT_FOR_GRAPH.release(self.resource());
+    }
+}
+
+struct MutexAdaptiveInner {
+    locked: Option<Weak<TaskControlBlock>>,
+    waiting: VecDeque<Weak<TaskControlBlock>>,
+}
+
+/// The adaptive mutex will spin for a bounded number of iterations while the holder is still running,
+/// and fall back to block current task if the mutex has not been released in time.
+/// If the holder is not running, spinning is pointless and current task will be blocked immediately.
+/// The max iterations of spinning is [`configs::MUTEX_ADAPTIVE_SPIN_LIMIT`].
+pub(crate) struct MutexAdaptive {
+    inner: UserPromiseRefCell<MutexAdaptiveInner>,
+}
+impl MutexAdaptive {
+    pub(crate) fn new() -> Self {
+        Self {
+            inner: unsafe {
+                UserPromiseRefCell::new(MutexAdaptiveInner {
+                    locked: None,
+                    waiting: VecDeque::new(),
+                })
+            },
+        }
+    }
+
+    /// Get the address of the mutex as the resource in the wait-for graph
+    fn resource(&self) -> usize {
+        self as *const Self as usize
+    }
+
+    /// Check if the mutex is holding by any alive task
+    fn is_locked(&self) -> bool {
+        self.inner
+            .access()
+            .locked
+            .as_ref()
+            .and_then(|prev| prev.upgrade())
+            .is_some()
+    }
+
+    /// Spin until the mutex was released or the holder stop running.
+    ///
+    /// - Arguments
+    ///     - holder: the task which is holding the mutex
+    ///     - spin: the waiting in each spinning iteration
+    ///
+    /// - Returns
+    ///     - true: the mutex was released during spinning
+    ///     - false: the spin limit was reached or the holder is not running
+    fn spin_until_released(
+        &self,
+        holder: &Arc<TaskControlBlock>,
+        spin: &mut impl FnMut(&Self),
+    ) -> bool {
+        for _ in 0..configs::MUTEX_ADAPTIVE_SPIN_LIMIT {
+            if let Some(released) = self.spin_once(holder, spin) {
+                return released;
+            }
+        }
+        !self.is_locked()
+    }
+
+    /// Spin one iteration while the mutex is holding by the holder,
+    /// the mutex is only borrowed to read, so spinning never conflicts with the holder.
+    ///
+    /// - Arguments
+    ///     - holder: the task which is holding the mutex
+    ///     - spin: the waiting in the spinning iteration
+    ///
+    /// - Returns
+    ///     - Some(true): the mutex was released
+    ///     - Some(false): the holder is not running, so spinning is pointless
+    ///     - None: the mutex is still held by the running holder
+    fn spin_once(
+        &self,
+        holder: &Arc<TaskControlBlock>,
+        spin: &mut impl FnMut(&Self),
+    ) -> Option<bool> {
+        if !self.is_locked() {
+            return Some(true);
+        }
+        if !holder.is_running() {
+            return Some(false);
+        }
+        spin(self);
+        None
+    }
+
+    /// Acquire the mutex, spin while the running holder keeps it and block if spinning failed.
+    ///
+    /// - Arguments
+    ///     - spin: the waiting in each spinning iteration, which is the hint of the processor,
+    ///         the tests release the mutex in it just like the holder running on the other hart
+    ///
+    /// - Errors
+    ///     - ProcessHaveNotTask
+    ///     - DoubleLockMutex
+    ///     - Deadlock
+    fn lock_spinning(&self, mut spin: impl FnMut(&Self)) -> Result<()> {
+        loop {
+            let current_task = PROCESSOR.current_task()?;
+            let mut inner = self.inner.exclusive_access();
+            if let Some(prev) = inner.locked.as_ref().and_then(|prev| prev.upgrade()) {
+                if Arc::as_ptr(&prev) == Arc::as_ptr(&current_task) {
+                    return Err(KernelError::DoubleLockMutex);
+                }
+                drop(inner);
+                if self.spin_until_released(&prev, &mut spin) {
+                    continue;
+                }
+                let mut inner = self.inner.exclusive_access();
+                // the mutex may be released after the last spinning iteration,
+                // so it must be checked again under the borrow which enqueues current task,
+                // otherwise the waking up in the unlocking is lost and current task sleeps forever
+                if inner.locked.as_ref().and_then(|prev| prev.upgrade()).is_none() {
+                    continue;
+                }
+                WAIT_FOR_GRAPH.block(self.resource(), &current_task)?;
+                inner.waiting.push_back(Arc::downgrade(&current_task));
+                drop(prev);
+                drop(inner);
+                drop(current_task);
+                block_current_and_run_other_task()?;
+                WAIT_FOR_GRAPH.unblock(&PROCESSOR.current_task()?);
+                continue;
+            }
+            inner.locked.replace(Arc::downgrade(&current_task));
+            WAIT_FOR_GRAPH.acquire(self.resource(), &current_task);
+            return Ok(());
+        }
+    }
+}
+impl Mutex for MutexAdaptive {
+    fn lock(&self) -> Result<()> {
+        self.lock_spinning(|_| core::hint::spin_loop())
+    }
+
+    fn unlock(&self) -> Result<()> {
+        let current_task = PROCESSOR.current_task()?;
+        let mut inner = self.inner.exclusive_access();
+        if let Some(prev) = inner.locked.as_ref().and_then(|prev| prev.upgrade()) {
+            if Arc::as_ptr(&prev) != Arc::as_ptr(&current_task) {
+                return Err(KernelError::DoubleUnlockMutex);
+            }
+        }
+        while let Some(other) = inner.waiting.pop_front() {
+            if let Some(other) = other.upgrade() {
+                other.mark_suspended();
+                TASK_SCHEDULER.put_read_task(other);
+                break;
+            }
+        }
+        inner.locked.take();
+        WAIT_FOR_GRAPH.release(self.resource());
+        Ok(())
+    }
+
+    fn try_lock_with(&self, task: &Arc<TaskControlBlock>) -> bool {
+        let mut inner = self.inner.exclusive_access();
+        if inner.locked.as_ref().and_then(|prev| prev.upgrade()).is_some() {
+            return false;
+        }
+        inner.locked.replace(Arc::downgrade(task));
+        WAIT_FOR_GRAPH.acquire(self.resource(), task);
+        true
+    }
+}
+
+impl Drop for MutexAdaptive {
+    /// The address may be reused by another mutex, so the owner must be removed from the wait-for graph
+    fn drop(&mut self) {
+        WAIT_FOR_GRAPH.release(self.resource());
+    }
+}
+
+/// The recursive mutex can be locked by the holder multiple times without deadlocking itself,
+/// and it is released only when the holder unlocks it the same times.
+/// Other tasks will be blocked by the underlying blocking mutex until the mutex was released.
+pub(crate) struct MutexRecursive {
+    mutex: MutexBlocking,
+    /// The count of the locking by the holder which were not unlocked
+    count: UserPromiseRefCell<usize>,
+}
+impl MutexRecursive {
+    pub(crate) fn new() -> Self {
+        Self {
+            mutex: MutexBlocking::new(),
+            count: unsafe { UserPromiseRefCell::new(0) },
+        }
+    }
+}
+impl Mutex for MutexRecursive {
+    fn lock(&self) -> Result<()> {
+        let current_task = PROCESSOR.current_task()?;
+        if self.mutex.is_held_by(&current_task) {
+            *self.count.exclusive_access() += 1;
+            return Ok(());
+        }
+        drop(current_task);
+        self.mutex.lock()?;
+        *self.count.exclusive_access() = 1;
+        Ok(())
+    }
+
+    /// Decrease the locking count of the holder, the mutex is released when the count is zero.
+    ///
+    /// - Errors
+    ///     - ProcessHaveNotTask
+    ///     - DoubleUnlockMutex
+    fn unlock(&self) -> Result<()> {
+        let current_task = PROCESSOR.current_task()?;
+        if !self.mutex.is_held_by(&current_task) {
+            return Err(KernelError::DoubleUnlockMutex);
+        }
+        let mut count = self.count.exclusive_access();
+        *count -= 1;
+        if *count > 0 {
+            return Ok(());
+        }
+        drop(count);
+        drop(current_task);
+        self.mutex.unlock()
+    }
+
+    /// The holder can acquire the mutex again, which also needs a matching unlocking
+    fn try_lock_with(&self, task: &Arc<TaskControlBlock>) -> bool {
+        if self.mutex.is_held_by(task) {
+            *self.count.exclusive_access() += 1;
+            return true;
+        }
+        if !self.mutex.try_lock_with(task) {
+            return false;
+        }
+        *self.count.exclusive_access() = 1;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task::model::{CurrentTaskGuard, ForkedProcess, INIT_PROC};
+
+    #[test_case]
+    fn test_mutex_kind_from_usize() {
+        assert!(MutexKind::from_usize(0).is_ok_and(|kind| kind.is_spin()));
+        assert!(MutexKind::from_usize(1).is_ok_and(|kind| kind.is_blocking()));
+        assert!(MutexKind::from_usize(2).is_ok_and(|kind| kind.is_adaptive()));
+        assert!(MutexKind::from_usize(3).is_ok_and(|kind| kind.is_recursive()));
+        assert!(MutexKind::from_usize(4).is_err_and(|err| err.is_invalidmutexkind()));
+    }
+
+    #[test_case]
+    fn test_mutex_blocking_failed_try_lock() {
+        let root_task = INIT_PROC.inner_access().root_task();
+        let process = ForkedProcess::fork(&root_task);
+        let task = process.inner_access().root_task();
+        let mutex = MutexBlocking::new();
+        assert!(mutex.try_lock_with(&root_task));
+        let status = task.status();
+        assert!(!mutex.try_lock_with(&task));
+        assert!(!mutex.try_lock_with(&root_task));
+        // the failed acquire neither changes the holder nor enqueues the task
+        let inner = mutex.inner.access();
+        assert!(inner
+            .locked
+            .as_ref()
+            .and_then(|prev| prev.upgrade())
+            .is_some_and(|prev| Arc::as_ptr(&prev) == Arc::as_ptr(&root_task)));
+        assert!(inner.waiting.is_empty());
+        assert!(inner.next.is_none());
+        drop(inner);
+        assert_eq!(task.status(), status);
+    }
+
+    #[test_case]
+    fn test_mutex_adaptive_unlocked_without_holder() {
+        let mutex = MutexAdaptive::new();
+        assert!(!mutex.is_locked());
+    }
+
+    #[test_case]
+    fn test_mutex_adaptive_short_sections_never_block() {
+        // each critical section is held for a few iterations, which is far less than the spin limit
+        let (sections, hold) = (16, 4);
+        let holder = ForkedProcess::new();
+        let holder_task = holder.inner_access().root_task();
+        let waiter = ForkedProcess::new();
+        let waiter_task = waiter.inner_access().root_task();
+        holder_task.mark_running();
+        let switches = waiter_task.rusage().voluntary_switches;
+        let current = waiter.enter();
+        let mutex = MutexAdaptive::new();
+        let mut total_spins = 0;
+        for _ in 0..sections {
+            assert!(mutex.try_lock_with(&holder_task));
+            let mut spins = 0;
+            // the holder running on the other hart releases the mutex during the spinning
+            mutex
+                .lock_spinning(|mutex| {
+                    spins += 1;
+                    if spins == hold {
+                        mutex.inner.exclusive_access().locked.take();
+                    }
+                })
+                .unwrap();
+            total_spins += spins;
+            assert!(mutex.inner.access().waiting.is_empty());
+            mutex.unlock().unwrap();
+        }
+        drop(current);
+        // the waiting task took the spin path for all sections without any context switch,
+        // while the blocking mutex would switch once and the spin mutex would yield each time
+        assert_eq!(total_spins, sections * hold);
+        assert_eq!(waiter_task.rusage().voluntary_switches, switches);
+        assert!(!waiter_task.is_blocked());
+        holder_task.mark_suspended();
+    }
+
+    #[test_case]
+    fn test_mutex_adaptive_long_section_spins_bounded() {
+        let root_task = INIT_PROC.inner_access().root_task();
+        let process = ForkedProcess::fork(&root_task);
+        let task = process.inner_access().root_task();
+        let mutex = MutexAdaptive::new();
+        let mut spins = 0;
+        let mut count = |_: &MutexAdaptive| spins += 1;
+        assert!(mutex.try_lock_with(&task));
+        // the holder which is not running will never release the mutex, so no spinning at all
+        assert!(!task.is_running());
+        assert!(!mutex.spin_until_released(&task, &mut count));
+        // the long held section only wastes the bounded iterations before blocking,
+        // and the spinning only reads the mutex while the holder may be borrowing it
+        task.mark_running();
+        let holding = mutex.inner.access();
+        assert!(!mutex.spin_until_released(&task, &mut count));
+        drop(holding);
+        // the released mutex is acquired without spinning
+        mutex.inner.exclusive_access().locked.take();
+        assert!(mutex.spin_until_released(&task, &mut count));
+        assert_eq!(spins, configs::MUTEX_ADAPTIVE_SPIN_LIMIT);
+        task.mark_suspended();
+    }
+
+    #[test_case]
+    fn test_mutex_recursive_nested_lock_and_unlock() {
+        let root_task = INIT_PROC.inner_access().root_task();
+        let process = ForkedProcess::fork(&root_task);
+        let task = process.inner_access().root_task();
+        let mutex = MutexRecursive::new();
+        let _current = process.enter();
+        mutex.lock().unwrap();
+        mutex.lock().unwrap();
+        assert!(mutex.try_lock().unwrap());
+        assert_eq!(*mutex.count.access(), 3);
+        // other task can neither acquire nor release the mutex
+        assert!(!mutex.try_lock_with(&root_task));
+        let root_current = CurrentTaskGuard::enter(Arc::clone(&root_task));
+        assert!(mutex.unlock().is_err_and(|err| err.is_doubleunlockmutex()));
+        drop(root_current);
+        mutex.unlock().unwrap();
+        mutex.unlock().unwrap();
+        // the mutex is still held until the count reaches zero
+        assert!(!mutex.try_lock_with(&root_task));
+        mutex.unlock().unwrap();
+        assert!(!mutex.mutex.is_held_by(&task));
+        assert!(mutex.unlock().is_err_and(|err| err.is_doubleunlockmutex()));
+        assert!(mutex.try_lock_with(&root_task));
+        assert!(!mutex.try_lock_with(&task));
+    }
 }
@@ -18,6 +18,13 @@ use crate::task::{
 pub(crate) trait Semaphore: Sync + Send {
     fn up(&self) -> Result<isize>;
     fn down(&self) -> Result<isize>;
+    /// Try to take one source without blocking or spinning,
+    /// the count will not be changed if there is no source.
+    ///
+    /// - Returns
+    ///     - true: one source was taken
+    ///     - false: there is no source
+    fn try_down(&self) -> bool;
 }
 
 struct SemaphoreSpinInner {
@@ -53,6 +60,15 @@ impl Semaphore for SemaphoreSpin {
         inner.count += 1;
         return Ok(inner.count);
     }
+
+    fn try_down(&self) -> bool {
+        let mut inner = self.inner.exclusive_access();
+        if inner.count <= 0 {
+            return false;
+        }
+        inner.count -= 1;
+        true
+    }
 }
 
 struct SemaphoreBlockingInner {
@@ -105,4 +121,35 @@ impl Semaphore for SemaphoreBlocking {
             Ok(inner.count)
         }
     }
+
+    /// The count is not decreased if there is no source,
+    /// so that the waiting task will not be woken up by the source for current task
+    fn try_down(&self) -> bool {
+        let mut inner = self.inner.exclusive_access();
+        if inner.count <= 0 {
+            return false;
+        }
+        inner.count -= 1;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_semaphore_try_down() {
+        let spin = SemaphoreSpin::new(1);
+        let blocking = SemaphoreBlocking::new(1);
+        let semaphores: [&dyn Semaphore; 2] = [&spin, &blocking];
+        for semaphore in semaphores {
+            assert!(semaphore.try_down());
+            assert!(!semaphore.try_down());
+            assert!(semaphore.up().is_ok_and(|count| count == 1));
+            assert!(semaphore.try_down());
+        }
+        assert_eq!(blocking.inner.access().count, 0);
+        assert!(blocking.inner.access().waiting.is_empty());
+    }
 }
@@ -5,30 +5,48 @@
 
 // use other mods
 use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::mem::size_of;
 use frontier_fs::OpenFlags;
 
 // use self mods
-use crate::configs::PIPE_RING_BUFFER_LENGTH;
-use crate::fs::inode::ROOT_INODE;
+use crate::configs::{MAX_PATH_LEN, PIPE_RING_BUFFER_LENGTH, POLL_INTERVAL_US};
+use crate::drivers::char::{CharDeviceImpl, LineDiscipline};
+use crate::fs::eventfd::{EventFd, EVENTFD_MAX_VALUE};
+use crate::fs::inode::{InodeMode, OSInode, Stat, ROOT_INODE};
+use crate::fs::mqueue::{MessageQueue, MQUEUE_TABLE};
 use crate::fs::pipe::Pipe;
+use crate::fs::timerfd::{TimerFd, TimerSpec};
+use crate::fs::{sync_all, File, PollEvents, PollFd};
+use crate::lang::buffer::ByteBuffers;
+use crate::lang::timer::TimeVal;
 use crate::prelude::*;
 use crate::task::*;
 
+/// The open flag which returns the symbolic link itself instead of following it,
+/// the bit is not used by the open flags of the file system and will be removed before parsing
+const O_NOFOLLOW: u32 = 1 << 31;
+
 /// Open a file and return the file descriptor.
+/// If the path is a device file in the device table, the device will be opened directly.
+/// If the path is a process file like `/proc/<pid>/status`, the process file will be opened directly.
 /// If the descriptor is less than zero, it means there was an error
 ///
 /// - Arguments
 ///     - path_ptr: The pointer address that path to the file, it must end with \0 char
-///     - flags: the unsigned value of the open flags
+///     - flags: the unsigned value of the open flags, see also [`O_NOFOLLOW`]
 ///
 /// - Returns
 ///     -  > -1: file descriptor
-///     - -1: file does not exists
+///     - -1: file does not exists, the path is too long, too many symbolic links were followed,
+///         the access is not permitted, the file cannot be truncated
+///         or the file descriptors are exhausted
 ///
 /// - Errors
 ///     - InvalidOpenFlags(flags)
 ///     - ProcessHaveNotTask
 ///     - VPNNotMapped(vpn)
+///     - UserRangeNotAccessible(va, len)
 ///     - FileSystemError
 ///         - InodeMustBeDirectory(bitmap index)
 ///         - DataOutOfBounds
@@ -39,21 +57,387 @@ use crate::task::*;
 ///         - BitmapIndexDeallocated(bitmap_index)
 ///         - RawDeviceError(error code)
 ///         - FileMustBeReadable(bitmap index)
+///     - FileDescriptorDoesNotExist(fd)
 #[inline(always)]
 pub(crate) fn sys_open(path_ptr: *const u8, flags: u32) -> Result<isize> {
-    let flags = OpenFlags::from_bits(flags).ok_or(KernelError::InvalidOpenFlags(flags))?;
+    let follow = flags & O_NOFOLLOW == 0;
+    let flags = OpenFlags::from_bits(flags & !O_NOFOLLOW)
+        .ok_or(KernelError::InvalidOpenFlags(flags))?;
     let task = PROCESSOR.current_task()?;
     let process = task.process();
-    let mut inner = process.inner_exclusive_access();
-    let current_space = inner.space();
-    let path = current_space.translated_string(path_ptr)?;
-    match ROOT_INODE.find(&path, flags) {
-        Ok(file) => Ok(inner.alloc_fd(file)? as isize),
-        Err(KernelError::FileDoesNotExists(_)) => Ok(-1),
+    let inner = process.inner_access();
+    let path = match inner.space().translated_string_bounded(path_ptr, MAX_PATH_LEN) {
+        Ok(path) => path,
+        Err(KernelError::UnterminatedString(_)) => return Ok(-1),
+        Err(other) => return Err(other),
+    };
+    let cred = inner.credential();
+    // the task may be blocked in the file system, the process must not be borrowed
+    drop(inner);
+    let result = if follow {
+        ROOT_INODE.find(&path, flags, cred)
+    } else {
+        ROOT_INODE.find_link(&path, flags, cred)
+    };
+    let file = match result {
+        Ok(file) => file,
+        Err(KernelError::FileDoesNotExists(_))
+        | Err(KernelError::SymlinkLoop(_))
+        | Err(KernelError::FilePermissionDenied(_)) => return Ok(-1),
+        Err(other) => return Err(other),
+    };
+    // the file descriptor is allocated first, so that the failed opening never truncates the file
+    let fd = match process.inner_exclusive_access().alloc_fd(Arc::clone(&file)) {
+        Ok(fd) => fd,
+        Err(KernelError::FileDescriptorExhausted) => return Ok(-1),
+        Err(other) => return Err(other),
+    };
+    if flags.contains(OpenFlags::TRUNC) {
+        let failure = match file.truncate(0) {
+            // the virtual files like the devices ignore the truncation
+            Ok(()) | Err(KernelError::FileNotTruncatable) => None,
+            Err(KernelError::FileMustNotBeDirectory(_))
+            | Err(KernelError::FileMustBeWritable(_))
+            | Err(KernelError::FilePermissionDenied(_)) => Some(Ok(-1)),
+            Err(other) => Some(Err(other)),
+        };
+        if let Some(failure) = failure {
+            process.inner_exclusive_access().dealloc_fd(fd)?;
+            return failure;
+        }
+    }
+    Ok(fd as isize)
+}
+
+/// Grow or shrink the file to exactly the length.
+/// The grown region will be read as zeros, and the blocks beyond the new length will be freed.
+///
+/// - Arguments
+///     - fd: the file descriptor
+///     - len: the new byte length of the file
+///
+/// - Returns
+///     - 0: success
+///     - -1: file descriptor does not exists, or the file cannot be truncated
+///
+/// - Errors
+///     - ProcessHaveNotTask
+///     - FileSystemError
+///         - BitmapExhausted(start_block_id)
+///         - BitmapIndexDeallocated(bitmap_index)
+///         - DataOutOfBounds
+///         - NoDroptableBlockCache
+///         - RawDeviceError(error code)
+#[inline(always)]
+pub(crate) fn sys_ftruncate(fd: usize, len: usize) -> Result<isize> {
+    let task = PROCESSOR.current_task()?;
+    let process = task.process();
+    let inner = process.inner_access();
+    let file = match inner.get_file(fd) {
+        Some(file) => Arc::clone(file),
+        None => return Ok(-1),
+    };
+    drop(inner);
+    match file.truncate(len as u64) {
+        Ok(()) => Ok(0),
+        Err(KernelError::FileNotTruncatable)
+        | Err(KernelError::FileMustNotBeDirectory(_))
+        | Err(KernelError::FileMustBeWritable(_))
+        | Err(KernelError::FilePermissionDenied(_)) => Ok(-1),
+        Err(other) => Err(other),
+    }
+}
+
+/// Flush the dirty block caches of all mounted file systems into their devices.
+///
+/// - Returns
+///     - 0: success
+///
+/// - Errors
+///     - FileSystemError
+///         - RawDeviceError(error code)
+#[inline(always)]
+pub(crate) fn sys_sync() -> Result<isize> {
+    sync_all()?;
+    Ok(0)
+}
+
+/// Flush the dirty blocks of the file into the device,
+/// the files which are not backed by the blocks do nothing and succeed.
+/// The dirty blocks of the other files in the same device may be flushed together.
+///
+/// - Arguments
+///     - fd: the file descriptor
+///
+/// - Returns
+///     - 0: success
+///     - -1: file descriptor does not exists
+///
+/// - Errors
+///     - ProcessHaveNotTask
+///     - FileSystemError
+///         - RawDeviceError(error code)
+///     - DeviceNotMounted(device id)
+#[inline(always)]
+pub(crate) fn sys_fsync(fd: usize) -> Result<isize> {
+    let task = PROCESSOR.current_task()?;
+    let process = task.process();
+    let inner = process.inner_access();
+    let file = match inner.get_file(fd) {
+        Some(file) => Arc::clone(file),
+        None => return Ok(-1),
+    };
+    drop(inner);
+    file.sync()?;
+    Ok(0)
+}
+
+/// Get the status of the file, including the size and the timestamps.
+///
+/// - Arguments
+///     - fd: the file descriptor
+///     - stat: the pointer of the status structure which will be written
+///
+/// - Returns
+///     - 0: success
+///     - -1: file descriptor does not exists, or the file is not a regular file or directory
+///
+/// - Errors
+///     - ProcessHaveNotTask
+///     - VPNNotMapped(vpn)
+///     - UserRangeNotAccessible(va, len)
+#[inline(always)]
+pub(crate) fn sys_fstat(fd: usize, stat: *mut Stat) -> Result<isize> {
+    let task = PROCESSOR.current_task()?;
+    let process = task.process();
+    let inner = process.inner_access();
+    let file = match inner.get_file(fd) {
+        Some(file) => Arc::clone(file),
+        None => return Ok(-1),
+    };
+    drop(inner);
+    let value = match file.as_any().downcast_ref::<OSInode>() {
+        Some(inode) => inode.stat(),
+        None => return Ok(-1),
+    };
+    let inner = process.inner_access();
+    inner.space().check_user_range(stat, size_of::<Stat>(), true)?;
+    inner.space().copy_value_to_user(stat, &value)?;
+    Ok(0)
+}
+
+/// Set the access and modification time of the file by the path.
+///
+/// - Arguments
+///     - path_ptr: the pointer of the path string
+///     - times: the pointer of the access time and the modification time,
+///         both will be the current moment if it is null
+///
+/// - Returns
+///     - 0: success
+///     - -1: the path is unterminated or the file does not exist
+///
+/// - Errors
+///     - ProcessHaveNotTask
+///     - VPNNotMapped(vpn)
+///     - UserRangeNotAccessible(va, len)
+///     - FileSystemError
+///         - InodeMustBeDirectory(bitmap index)
+///         - DataOutOfBounds
+///         - NoDroptableBlockCache
+///         - RawDeviceError(error code)
+///     - FileMustBeReadable(bitmap index)
+///     - DeviceNotMounted(device id)
+#[inline(always)]
+pub(crate) fn sys_utimensat(path_ptr: *const u8, times: *const [TimeVal; 2]) -> Result<isize> {
+    let task = PROCESSOR.current_task()?;
+    let process = task.process();
+    let inner = process.inner_access();
+    let path = match inner.space().translated_string_bounded(path_ptr, MAX_PATH_LEN) {
+        Ok(path) => path,
+        Err(KernelError::UnterminatedString(_)) => return Ok(-1),
+        Err(other) => return Err(other),
+    };
+    let [atime, mtime] = if times.is_null() {
+        [None, None]
+    } else {
+        inner.space().check_user_range(times, size_of::<[TimeVal; 2]>(), false)?;
+        let [atime, mtime] = inner.space().copy_value_from_user(times)?;
+        [Some(atime), Some(mtime)]
+    };
+    let cred = inner.credential();
+    drop(inner);
+    let file = match ROOT_INODE.find_inode(&path, OpenFlags::empty(), cred) {
+        Ok(file) => file,
+        Err(KernelError::FileDoesNotExists(_))
+        | Err(KernelError::SymlinkLoop(_))
+        | Err(KernelError::FilePermissionDenied(_)) => return Ok(-1),
+        Err(other) => return Err(other),
+    };
+    file.set_times(atime, mtime);
+    Ok(0)
+}
+
+/// Change the permission bits of the file by the path, the symbolic links are followed.
+///
+/// - Arguments
+///     - path_ptr: the pointer of the path string
+///     - mode: the new permission bits, see [`InodeMode`]
+///
+/// - Returns
+///     - 0: success
+///     - -1: the path is unterminated, the mode is invalid, the file does not exist
+///         or the current process is neither the owner nor privileged
+///
+/// - Errors
+///     - ProcessHaveNotTask
+///     - VPNNotMapped(vpn)
+///     - FileSystemError
+///         - InodeMustBeDirectory(bitmap index)
+///         - DataOutOfBounds
+///         - NoDroptableBlockCache
+///         - RawDeviceError(error code)
+///     - FileMustBeReadable(bitmap index)
+///     - DeviceNotMounted(device id)
+#[inline(always)]
+pub(crate) fn sys_chmod(path_ptr: *const u8, mode: u32) -> Result<isize> {
+    let mode = match InodeMode::from_bits(mode) {
+        Some(mode) => mode,
+        None => return Ok(-1),
+    };
+    let task = PROCESSOR.current_task()?;
+    let process = task.process();
+    let inner = process.inner_access();
+    let path = match inner.space().translated_string_bounded(path_ptr, MAX_PATH_LEN) {
+        Ok(path) => path,
+        Err(KernelError::UnterminatedString(_)) => return Ok(-1),
+        Err(other) => return Err(other),
+    };
+    let cred = inner.credential();
+    drop(inner);
+    // no access is requested to the file itself, only the directories must be searchable
+    let result = ROOT_INODE
+        .find_inode(&path, OpenFlags::empty(), cred)
+        .and_then(|file| file.set_mode(mode));
+    match result {
+        Ok(()) => Ok(0),
+        Err(KernelError::FileDoesNotExists(_))
+        | Err(KernelError::SymlinkLoop(_))
+        | Err(KernelError::FilePermissionDenied(_)) => Ok(-1),
         Err(other) => Err(other),
     }
 }
 
+/// Create a symbolic link which refers the target path, the target does not need to exist.
+///
+/// - Arguments
+///     - target_ptr: the pointer of the target path string
+///     - linkpath_ptr: the pointer of the link path string
+///
+/// - Returns
+///     - 0: success
+///     - -1: the paths are unterminated, or the parent directory of the link does not exist
+///
+/// - Errors
+///     - ProcessHaveNotTask
+///     - VPNNotMapped(vpn)
+///     - FileSystemError
+///         - InodeMustBeDirectory(bitmap index)
+///         - DuplicatedFname(name, inode bitmap index)
+///         - BitmapExhausted(start_block_id)
+///         - DataOutOfBounds
+///         - NoDroptableBlockCache
+///         - RawDeviceError(error code)
+///     - FileMustBeReadable(bitmap index)
+///     - FileMustBeWritable(bitmap index)
+///     - DeviceNotMounted(device id)
+#[inline(always)]
+pub(crate) fn sys_symlink(target_ptr: *const u8, linkpath_ptr: *const u8) -> Result<isize> {
+    let task = PROCESSOR.current_task()?;
+    let process = task.process();
+    let inner = process.inner_access();
+    let space = inner.space();
+    let paths = space
+        .translated_string_bounded(target_ptr, MAX_PATH_LEN)
+        .and_then(|target| {
+            let linkpath = space.translated_string_bounded(linkpath_ptr, MAX_PATH_LEN)?;
+            Ok((target, linkpath))
+        });
+    let (target, linkpath) = match paths {
+        Ok(paths) => paths,
+        Err(KernelError::UnterminatedString(_)) => return Ok(-1),
+        Err(other) => return Err(other),
+    };
+    let cred = inner.credential();
+    drop(inner);
+    match ROOT_INODE.symlink(&target, &linkpath, cred) {
+        Ok(_) => Ok(0),
+        Err(KernelError::FileDoesNotExists(_))
+        | Err(KernelError::SymlinkLoop(_))
+        | Err(KernelError::FilePermissionDenied(_)) => Ok(-1),
+        Err(other) => Err(other),
+    }
+}
+
+/// Read the target path of the symbolic link without following it,
+/// the target will be truncated if the buffer is too small and no NULL terminator is appended.
+///
+/// - Arguments
+///     - path_ptr: the pointer of the link path string
+///     - buffer_ptr: the pointer of the buffer which will be written
+///     - len: the byte length of the buffer
+///
+/// - Returns
+///     - the byte length of the target written into the buffer
+///     - -1: the path is unterminated, or the link does not exist, or the path is not a link
+///
+/// - Errors
+///     - ProcessHaveNotTask
+///     - VPNNotMapped(vpn)
+///     - UserRangeNotAccessible(va, len)
+///     - FileSystemError
+///         - InodeMustBeDirectory(bitmap index)
+///         - DataOutOfBounds
+///         - NoDroptableBlockCache
+///         - RawDeviceError(error code)
+///     - FileMustBeReadable(bitmap index)
+///     - DeviceNotMounted(device id)
+///     - EOB
+#[inline(always)]
+pub(crate) fn sys_readlink(path_ptr: *const u8, buffer_ptr: *mut u8, len: usize) -> Result<isize> {
+    let task = PROCESSOR.current_task()?;
+    let process = task.process();
+    let inner = process.inner_access();
+    let path = match inner.space().translated_string_bounded(path_ptr, MAX_PATH_LEN) {
+        Ok(path) => path,
+        Err(KernelError::UnterminatedString(_)) => return Ok(-1),
+        Err(other) => return Err(other),
+    };
+    let cred = inner.credential();
+    drop(inner);
+    let file = match ROOT_INODE.find_inode_link(&path, OpenFlags::READ, cred) {
+        Ok(file) if file.is_symlink() => file,
+        Ok(_)
+        | Err(KernelError::FileDoesNotExists(_))
+        | Err(KernelError::SymlinkLoop(_))
+        | Err(KernelError::FilePermissionDenied(_)) => return Ok(-1),
+        Err(other) => return Err(other),
+    };
+    let target = file.read_link()?;
+    let size = target.len().min(len);
+    if size == 0 {
+        return Ok(0);
+    }
+    let inner = process.inner_access();
+    inner.space().check_user_range(buffer_ptr, size, true)?;
+    let buffers = inner.space().translated_byte_buffers(buffer_ptr, size)?;
+    let mut iterator = buffers.into_iter();
+    for byte in target.bytes().take(size) {
+        iterator.next_mut(byte)?;
+    }
+    Ok(size as isize)
+}
+
 /// Close a file and return the status code.
 ///
 /// - Arguments
@@ -87,6 +471,8 @@ pub(crate) fn sys_close(fd: usize) -> Result<isize> {
 ///
 /// - Returns
 ///     - writed length
+///     - -1: the writing was interrupted by a signal before writing any byte,
+///           or the eventfd was written with an invalid buffer
 /// 
 /// - Errors
 ///     - ProcessHaveNotTask
@@ -99,6 +485,7 @@ pub(crate) fn sys_write(fd: usize, buffer_ptr: *const u8, len: usize) -> Result<
     let process = task.process();
     let inner = process.inner_access();
     let current_space = inner.space();
+    current_space.check_user_range(buffer_ptr, len, false)?;
     let buffers = current_space.translated_byte_buffers(buffer_ptr, len)?;
     let file = inner
         .get_file(fd)
@@ -107,7 +494,13 @@ pub(crate) fn sys_write(fd: usize, buffer_ptr: *const u8, len: usize) -> Result<
     drop(inner);
     drop(process);
     drop(task);
-    Ok(file.write(buffers)? as isize)
+    match file.write(buffers) {
+        Ok(size) => Ok(size as isize),
+        Err(KernelError::InvalidEventFdAccess(_))
+        | Err(KernelError::FilePermissionDenied(_)) => Ok(-1),
+        Err(KernelError::SyscallInterrupted) => interrupt_current_syscall(),
+        Err(other) => Err(other),
+    }
 }
 
 /// Read a &str from the IO device and save it to the buffer.
@@ -120,6 +513,8 @@ pub(crate) fn sys_write(fd: usize, buffer_ptr: *const u8, len: usize) -> Result<
 /// 
 /// - Returns
 ///     - readed length
+///     - -1: the reading was interrupted by a signal before reading any byte,
+///           or the eventfd or timerfd was read with an invalid buffer
 ///
 /// - Errors
 ///     - ProcessHaveNotTask
@@ -131,13 +526,105 @@ pub(crate) fn sys_read(fd: usize, buffer_ptr: *mut u8, len: usize) -> Result<isi
     let process = task.process();
     let inner = process.inner_access();
     let current_space = inner.space();
+    current_space.check_user_range(buffer_ptr, len, true)?;
     let buffers = current_space.translated_byte_buffers(buffer_ptr, len)?;
     let file = inner
         .get_file(fd)
         .ok_or(KernelError::FileDescriptorDoesNotExist(fd))?;
     let file = Arc::clone(file);
     drop(inner);
-    Ok(file.read(buffers)? as isize)
+    drop(process);
+    drop(task);
+    match file.read(buffers) {
+        Ok(size) => Ok(size as isize),
+        Err(KernelError::InvalidEventFdAccess(_))
+        | Err(KernelError::InvalidTimerFdAccess(_))
+        | Err(KernelError::FilePermissionDenied(_)) => Ok(-1),
+        Err(KernelError::SyscallInterrupted) => interrupt_current_syscall(),
+        Err(other) => Err(other),
+    }
+}
+
+/// Read from the file at the explicit offset, the offset of the file will not be changed.
+///
+/// - Arguments
+///     - fd: the file descriptor
+///     - buffer_ptr: the pointer of the buffer to fill in
+///     - len: the byte length of the buffer
+///     - offset: the byte offset of the file to read from
+///
+/// - Returns
+///     - the count of the bytes read
+///     - -1: file descriptor does not exists, or the file cannot be accessed at the offset
+///
+/// - Errors
+///     - ProcessHaveNotTask
+///     - VPNNotMapped(vpn)
+///     - UserRangeNotAccessible(va, len)
+///     - FileMustBeReadable(inode bitmap index)
+///     - FileSystemError
+///         - DataOutOfBounds
+///         - NoDroptableBlockCache
+///         - RawDeviceError(error code)
+#[inline(always)]
+pub(crate) fn sys_pread(fd: usize, buffer_ptr: *mut u8, len: usize, offset: usize) -> Result<isize> {
+    let task = PROCESSOR.current_task()?;
+    let process = task.process();
+    let inner = process.inner_access();
+    let current_space = inner.space();
+    current_space.check_user_range(buffer_ptr, len, true)?;
+    let buffers = current_space.translated_byte_buffers(buffer_ptr, len)?;
+    let file = match inner.get_file(fd) {
+        Some(file) => Arc::clone(file),
+        None => return Ok(-1),
+    };
+    drop(inner);
+    match file.read_at(buffers, offset as u64) {
+        Ok(size) => Ok(size as isize),
+        Err(KernelError::FileNotSeekable) | Err(KernelError::FilePermissionDenied(_)) => Ok(-1),
+        Err(other) => Err(other),
+    }
+}
+
+/// Write into the file at the explicit offset, the offset of the file will not be changed.
+///
+/// - Arguments
+///     - fd: the file descriptor
+///     - buffer_ptr: the pointer of the bytes to write
+///     - len: the byte length of the bytes
+///     - offset: the byte offset of the file to write to
+///
+/// - Returns
+///     - the count of the bytes written
+///     - -1: file descriptor does not exists, or the file cannot be accessed at the offset
+///
+/// - Errors
+///     - ProcessHaveNotTask
+///     - VPNNotMapped(vpn)
+///     - UserRangeNotAccessible(va, len)
+///     - FileMustBeWritable(inode bitmap index)
+///     - FileSystemError
+///         - DataOutOfBounds
+///         - NoDroptableBlockCache
+///         - RawDeviceError(error code)
+#[inline(always)]
+pub(crate) fn sys_pwrite(fd: usize, buffer_ptr: *const u8, len: usize, offset: usize) -> Result<isize> {
+    let task = PROCESSOR.current_task()?;
+    let process = task.process();
+    let inner = process.inner_access();
+    let current_space = inner.space();
+    current_space.check_user_range(buffer_ptr, len, false)?;
+    let buffers = current_space.translated_byte_buffers(buffer_ptr, len)?;
+    let file = match inner.get_file(fd) {
+        Some(file) => Arc::clone(file),
+        None => return Ok(-1),
+    };
+    drop(inner);
+    match file.write_at(buffers, offset as u64) {
+        Ok(size) => Ok(size as isize),
+        Err(KernelError::FileNotSeekable) | Err(KernelError::FilePermissionDenied(_)) => Ok(-1),
+        Err(other) => Err(other),
+    }
 }
 
 /// Create a pipe `file` in the current task, return readable file descriptor and writable file descriptor.
@@ -149,28 +636,274 @@ pub(crate) fn sys_read(fd: usize, buffer_ptr: *mut u8, len: usize) -> Result<isi
 ///
 /// - Returns
 ///     - 0: success
+///     - -1: the file descriptors are exhausted, no file descriptor is allocated
 /// 
 /// - Errors
 ///     - ProcessHaveNotTask
-///     - FileDescriptorExhausted
+///     - FileDescriptorDoesNotExist(fd)
 ///     - VPNNotMapped(vpn)
+///     - UserRangeNotAccessible(va, len)
 #[inline(always)]
 pub(crate) fn sys_pipe(read_tap_fd_ptr: *mut usize, write_tap_fd_ptr: *mut usize) -> Result<isize> {
     let task = PROCESSOR.current_task()?;
     let process = task.process();
     let mut inner = process.inner_exclusive_access();
+    inner.space().check_user_range(read_tap_fd_ptr, size_of::<usize>(), true)?;
+    inner.space().check_user_range(write_tap_fd_ptr, size_of::<usize>(), true)?;
     let read_tap = Pipe::new(PIPE_RING_BUFFER_LENGTH);
     let write_tap = read_tap.writable_fork().unwrap();
-    let read_fd = inner.alloc_fd(Arc::new(read_tap))?;
-    let write_fd = inner.alloc_fd(Arc::new(write_tap))?;
+    let read_fd = match inner.alloc_fd(Arc::new(read_tap)) {
+        Ok(fd) => fd,
+        Err(KernelError::FileDescriptorExhausted) => return Ok(-1),
+        Err(other) => return Err(other),
+    };
+    let write_fd = match inner.alloc_fd(Arc::new(write_tap)) {
+        Ok(fd) => fd,
+        Err(KernelError::FileDescriptorExhausted) => {
+            inner.dealloc_fd(read_fd)?;
+            return Ok(-1);
+        }
+        Err(other) => return Err(other),
+    };
     let current_space = inner.space();
-    let read_tap_fd = current_space.translated_refmut(read_tap_fd_ptr)?;
-    let write_tap_fd = current_space.translated_refmut(write_tap_fd_ptr)?;
-    *read_tap_fd = read_fd;
-    *write_tap_fd = write_fd;
+    current_space.copy_value_to_user(read_tap_fd_ptr, &read_fd)?;
+    current_space.copy_value_to_user(write_tap_fd_ptr, &write_fd)?;
+    Ok(0)
+}
+
+/// The flag of the eventfd which makes the reading decrease the counter by one
+const EFD_SEMAPHORE: usize = 1 << 0;
+
+/// Create an eventfd in the current process, return its file descriptor.
+/// The eventfd is inherited by the forked process like other files.
+///
+/// - Arguments
+///     - initval: the initial value of the counter
+///     - flags: the flags of the eventfd, only `EFD_SEMAPHORE` is supported now
+///
+/// - Returns
+///     - the file descriptor of the eventfd
+///     - -1: the flags are unknown, the initial value exceeds the max value of the counter
+///         or the file descriptors are exhausted
+///
+/// - Errors
+///     - ProcessHaveNotTask
+#[inline(always)]
+pub(crate) fn sys_eventfd(initval: usize, flags: usize) -> Result<isize> {
+    if flags & !EFD_SEMAPHORE != 0 || initval as u64 > EVENTFD_MAX_VALUE {
+        return Ok(-1);
+    }
+    let eventfd = EventFd::new(initval as u64, flags & EFD_SEMAPHORE != 0);
+    let task = PROCESSOR.current_task()?;
+    let process = task.process();
+    let mut inner = process.inner_exclusive_access();
+    match inner.alloc_fd(Arc::new(eventfd)) {
+        Ok(fd) => Ok(fd as isize),
+        Err(KernelError::FileDescriptorExhausted) => Ok(-1),
+        Err(other) => Err(other),
+    }
+}
+
+/// Create a disarmed timerfd in the current process, return its file descriptor.
+///
+/// - Arguments
+///     - flags: the flags of the timerfd, there is no flag supported now
+///
+/// - Returns
+///     - the file descriptor of the timerfd
+///     - -1: the flags are unknown or the file descriptors are exhausted
+///
+/// - Errors
+///     - ProcessHaveNotTask
+#[inline(always)]
+pub(crate) fn sys_timerfd_create(flags: usize) -> Result<isize> {
+    if flags != 0 {
+        return Ok(-1);
+    }
+    let task = PROCESSOR.current_task()?;
+    let process = task.process();
+    let mut inner = process.inner_exclusive_access();
+    match inner.alloc_fd(Arc::new(TimerFd::new())) {
+        Ok(fd) => Ok(fd as isize),
+        Err(KernelError::FileDescriptorExhausted) => Ok(-1),
+        Err(other) => Err(other),
+    }
+}
+
+/// Arm or disarm the timer of the timerfd.
+/// The zero value disarms the timer, and the zero interval makes the timer one-shot.
+///
+/// - Arguments
+///     - fd: the file descriptor of the timerfd
+///     - new_spec: the pointer of the new setting
+///     - old_spec: the pointer of the previous setting which will be written, ignored if it is null
+///
+/// - Returns
+///     - 0: success
+///     - -1: the file descriptor is not a timerfd
+///
+/// - Errors
+///     - ProcessHaveNotTask
+///     - VPNNotMapped(vpn)
+///     - UserRangeNotAccessible(va, len)
+#[inline(always)]
+pub(crate) fn sys_timerfd_settime(
+    fd: usize,
+    new_spec: *const TimerSpec,
+    old_spec: *mut TimerSpec,
+) -> Result<isize> {
+    let task = PROCESSOR.current_task()?;
+    let process = task.process();
+    let inner = process.inner_access();
+    let file = match inner.get_file(fd) {
+        Some(file) => Arc::clone(file),
+        None => return Ok(-1),
+    };
+    let timerfd = match file.as_any().downcast_ref::<TimerFd>() {
+        Some(timerfd) => timerfd,
+        None => return Ok(-1),
+    };
+    inner.space().check_user_range(new_spec, size_of::<TimerSpec>(), false)?;
+    if !old_spec.is_null() {
+        inner.space().check_user_range(old_spec, size_of::<TimerSpec>(), true)?;
+    }
+    let spec = inner.space().copy_value_from_user(new_spec)?;
+    let old = timerfd.settime(spec);
+    if !old_spec.is_null() {
+        inner.space().copy_value_to_user(old_spec, &old)?;
+    }
     Ok(0)
 }
 
+/// Open the message queue by the name and return the file descriptor referring it,
+/// a new queue will be created if the name does not exist.
+/// The queue will be released when all the file descriptors referring it were closed.
+///
+/// - Arguments
+///     - name_ptr: the pointer of the name string end with NULL
+///
+/// - Returns
+///     - the file descriptor of the message queue
+///     - -1: the name is too long, the count of the queues reaches the limit
+///         or the file descriptors are exhausted
+///
+/// - Errors
+///     - ProcessHaveNotTask
+///     - VPNNotMapped(vpn)
+///     - UserRangeNotAccessible(va, len)
+#[inline(always)]
+pub(crate) fn sys_mq_open(name_ptr: *const u8) -> Result<isize> {
+    let task = PROCESSOR.current_task()?;
+    let process = task.process();
+    let mut inner = process.inner_exclusive_access();
+    let name = match inner.space().translated_string_bounded(name_ptr, MAX_PATH_LEN) {
+        Ok(name) => name,
+        Err(KernelError::UnterminatedString(_)) => return Ok(-1),
+        Err(other) => return Err(other),
+    };
+    let queue = match MQUEUE_TABLE.open(&name) {
+        Some(queue) => queue,
+        None => return Ok(-1),
+    };
+    match inner.alloc_fd(queue) {
+        Ok(fd) => Ok(fd as isize),
+        Err(KernelError::FileDescriptorExhausted) => Ok(-1),
+        Err(other) => Err(other),
+    }
+}
+
+/// Get the message queue and the byte buffers of the current process
+///
+/// - Arguments
+///     - fd: the file descriptor of the message queue
+///     - buffer_ptr: the pointer of the buffer
+///     - len: the byte length of the buffer
+///     - writable: true if the buffer will be written by the kernel
+///
+/// - Returns
+///     - Some((queue, buffers))
+///     - None: the file descriptor does not exist or it is not a message queue
+///
+/// - Errors
+///     - ProcessHaveNotTask
+///     - VPNNotMapped(vpn)
+///     - UserRangeNotAccessible(va, len)
+fn mq_buffers(
+    fd: usize,
+    buffer_ptr: *const u8,
+    len: usize,
+    writable: bool,
+) -> Result<Option<(Arc<dyn File>, ByteBuffers)>> {
+    let task = PROCESSOR.current_task()?;
+    let process = task.process();
+    let inner = process.inner_access();
+    let file = match inner.get_file(fd) {
+        Some(file) if file.as_any().is::<MessageQueue>() => Arc::clone(file),
+        _ => return Ok(None),
+    };
+    inner.space().check_user_range(buffer_ptr, len, writable)?;
+    let buffers = inner.space().translated_byte_buffers(buffer_ptr, len)?;
+    Ok(Some((file, buffers)))
+}
+
+/// Send the bytes in the buffer as one message to the message queue,
+/// the current task will be blocked until the queue is not full.
+///
+/// - Arguments
+///     - fd: the file descriptor of the message queue
+///     - buffer_ptr: the pointer of the message
+///     - len: the byte length of the message
+///
+/// - Returns
+///     - the byte length of the sent message
+///     - -1: the file descriptor is not a message queue or the message is too long
+///
+/// - Errors
+///     - ProcessHaveNotTask
+///     - VPNNotMapped(vpn)
+///     - UserRangeNotAccessible(va, len)
+#[inline(always)]
+pub(crate) fn sys_mq_send(fd: usize, buffer_ptr: *const u8, len: usize) -> Result<isize> {
+    let (queue, buffers) = match mq_buffers(fd, buffer_ptr, len, false)? {
+        Some(pair) => pair,
+        None => return Ok(-1),
+    };
+    match queue.write(buffers) {
+        Ok(size) => Ok(size as isize),
+        Err(KernelError::MessageTooLong(_)) => Ok(-1),
+        Err(other) => Err(other),
+    }
+}
+
+/// Receive the oldest message of the message queue into the buffer,
+/// the current task will be blocked until the queue is not empty.
+///
+/// - Arguments
+///     - fd: the file descriptor of the message queue
+///     - buffer_ptr: the pointer of the buffer to fill in
+///     - len: the byte length of the buffer
+///
+/// - Returns
+///     - the byte length of the received message
+///     - -1: the file descriptor is not a message queue or the buffer is too small for the message
+///
+/// - Errors
+///     - ProcessHaveNotTask
+///     - VPNNotMapped(vpn)
+///     - UserRangeNotAccessible(va, len)
+#[inline(always)]
+pub(crate) fn sys_mq_recv(fd: usize, buffer_ptr: *mut u8, len: usize) -> Result<isize> {
+    let (queue, buffers) = match mq_buffers(fd, buffer_ptr, len, true)? {
+        Some(pair) => pair,
+        None => return Ok(-1),
+    };
+    match queue.read(buffers) {
+        Ok(size) => Ok(size as isize),
+        Err(KernelError::MessageTooLong(_)) => Ok(-1),
+        Err(other) => Err(other),
+    }
+}
+
 /// Based on the incoming file descriptor, the specified file is copied and saved to the context of the current task.
 /// Returns a new file descriptor, pointing to a copy of the file.
 ///
@@ -179,11 +912,11 @@ pub(crate) fn sys_pipe(read_tap_fd_ptr: *mut usize, write_tap_fd_ptr: *mut usize
 ///
 /// - Returns
 ///     - new file descriptor
+///     - -1: the file descriptors are exhausted
 /// 
 /// - Errors
 ///     - ProcessHaveNotTask
 ///     - FileDescriptorDoesNotExist(fd)
-///     - FileDescriptorExhausted
 #[inline(always)]
 pub(crate) fn sys_dup(fd: usize) -> Result<isize> {
     let task = PROCESSOR.current_task()?;
@@ -193,6 +926,404 @@ pub(crate) fn sys_dup(fd: usize) -> Result<isize> {
         .get_file(fd)
         .ok_or(KernelError::FileDescriptorDoesNotExist(fd))?;
     let file = Arc::clone(file);
-    let fd = inner.alloc_fd(file)?;
-    Ok(fd as isize)
+    match inner.alloc_fd(file) {
+        Ok(fd) => Ok(fd as isize),
+        Err(KernelError::FileDescriptorExhausted) => Ok(-1),
+        Err(other) => Err(other),
+    }
+}
+
+/// Switch the console between canonical line mode and raw character mode for current process.
+/// Each process have it's own mode, so one process changing the mode doesn't affect another.
+///
+/// - Arguments
+///     - fd: the file descriptor which must refer to the console
+///     - raw: if true switch to raw character mode, otherwise switch to canonical line mode
+///
+/// - Returns
+///     - 0: success
+///     - -1: the file descriptor is not the console
+///
+/// - Errors
+///     - ProcessHaveNotTask
+#[inline(always)]
+pub(crate) fn sys_console_mode(fd: usize, raw: bool) -> Result<isize> {
+    let task = PROCESSOR.current_task()?;
+    let process = task.process();
+    let mut inner = process.inner_exclusive_access();
+    if !inner
+        .get_file(fd)
+        .is_some_and(|file| file.as_any().is::<CharDeviceImpl>())
+    {
+        return Ok(-1);
+    }
+    let discipline = if raw {
+        LineDiscipline::raw()
+    } else {
+        LineDiscipline::canonical()
+    };
+    inner.set_console_discipline(discipline);
+    Ok(0)
+}
+
+/// Check the polled file descriptors once and fill the returned events.
+/// The file descriptor which does not exist will be treated as not ready.
+///
+/// - Arguments
+///     - fds_ptr: the pointer to the array of the polled file descriptors
+///     - n: the length of the array
+///
+/// - Returns
+///     - the count of the ready file descriptors
+///
+/// - Errors
+///     - ProcessHaveNotTask
+///     - VPNNotMapped(vpn)
+fn poll_once(fds_ptr: *mut PollFd, n: usize) -> Result<usize> {
+    let task = PROCESSOR.current_task()?;
+    let process = task.process();
+    let inner = process.inner_access();
+    let mut count = 0;
+    for i in 0..n {
+        let poll_fd_ptr = fds_ptr.wrapping_add(i);
+        let mut poll_fd = inner.space().copy_value_from_user(poll_fd_ptr)?;
+        let events = PollEvents::from_bits_truncate(poll_fd.events);
+        let revents = match inner.get_file(poll_fd.fd) {
+            Some(file) => events
+                .iter()
+                .filter(|event| file.poll_ready(*event))
+                .collect(),
+            None => PollEvents::empty(),
+        };
+        poll_fd.revents = revents.bits();
+        inner.space().copy_value_to_user(poll_fd_ptr, &poll_fd)?;
+        if !revents.is_empty() {
+            count += 1;
+        }
+    }
+    Ok(count)
+}
+
+/// Register the current task in the wait queues of the polled files before it goes to sleep.
+/// The file descriptor which does not exist will be skipped.
+///
+/// - Arguments
+///     - fds_ptr: the pointer to the array of the polled file descriptors
+///     - n: the length of the array
+///
+/// - Returns
+///     - the registered files, the task must be unregistered from them after sleeping
+///     - true if all the polled files have the wait queues and will wake the task up
+///
+/// - Errors
+///     - ProcessHaveNotTask
+///     - VPNNotMapped(vpn)
+fn register_pollers(fds_ptr: *mut PollFd, n: usize) -> Result<(Vec<Arc<dyn File>>, bool)> {
+    let task = PROCESSOR.current_task()?;
+    let process = task.process();
+    let inner = process.inner_access();
+    let mut files = Vec::new();
+    let mut wakeable = true;
+    for i in 0..n {
+        let poll_fd = inner.space().copy_value_from_user(fds_ptr.wrapping_add(i))?;
+        let file = match inner.get_file(poll_fd.fd) {
+            Some(file) => Arc::clone(file),
+            None => continue,
+        };
+        match file.poll_wait_queue() {
+            Some(pollers) => {
+                pollers.register(&task);
+                files.push(file);
+            }
+            None => wakeable = false,
+        }
+    }
+    Ok((files, wakeable))
+}
+
+/// Wait until at least one of the file descriptors is ready to read or write, or the timeout elapses.
+/// The current task sleeps in the wait queues of the polled files until they wake it up,
+/// the files without the wait queues are checked again after each poll interval.
+/// The returned events will be written into the array.
+///
+/// - Arguments
+///     - fds_ptr: the pointer to the array of the polled file descriptors
+///     - n: the length of the array
+///     - timeout_us: the timeout in microseconds, 0 means returning immediately and `usize::MAX` means waiting forever
+///
+/// - Returns
+///     - the count of the ready file descriptors
+///     - 0: the timeout elapsed and none of the file descriptors is ready
+///     - -1: the array is invalid or the waiting was interrupted by a signal
+///
+/// - Errors
+///     - ProcessHaveNotTask
+///     - VPNNotMapped(vpn)
+#[inline(always)]
+pub(crate) fn sys_poll(fds_ptr: *mut PollFd, n: usize, timeout_us: usize) -> Result<isize> {
+    let task = PROCESSOR.current_task()?;
+    let process = task.process();
+    let byte_size = match n.checked_mul(size_of::<PollFd>()) {
+        Some(byte_size) => byte_size,
+        None => return Ok(-1),
+    };
+    if n == 0
+        || process
+            .inner_access()
+            .space()
+            .check_user_range(fds_ptr, byte_size, true)
+            .is_err()
+    {
+        return Ok(-1);
+    }
+    drop(process);
+    drop(task);
+    let expire_us = TASK_SCHEDULER.now_us().saturating_add(timeout_us);
+    loop {
+        let count = poll_once(fds_ptr, n)?;
+        if count > 0 {
+            return Ok(count as isize);
+        }
+        let remain_us = expire_us.saturating_sub(TASK_SCHEDULER.now_us());
+        if remain_us == 0 {
+            return Ok(0);
+        }
+        let (files, wakeable) = register_pollers(fds_ptr, n)?;
+        let sleep_us = if wakeable {
+            remain_us
+        } else {
+            remain_us.min(POLL_INTERVAL_US)
+        };
+        sleep_current_and_run_other_task(sleep_us)?;
+        let task = PROCESSOR.current_task()?;
+        for file in files.iter() {
+            file.poll_wait_queue().unwrap().unregister(&task);
+        }
+        drop(task);
+        drop(files);
+        if current_has_pending_signal()? {
+            return interrupt_current_syscall();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::ROOT_DEVICE_ID;
+    use crate::task::model::{Credential, ForkedProcess, RLimit};
+    use alloc::vec::Vec;
+
+    /// Copy the path with the NULL terminator into the first half of the scratch memory
+    fn user_path(process: &ForkedProcess, path: &str) -> *const u8 {
+        let ptr = process.user_scratch_va() as *mut u8;
+        let inner = process.inner_access();
+        inner.space().copy_to_user(ptr, path.as_bytes()).unwrap();
+        inner.space().copy_to_user(ptr.wrapping_add(path.len()), &[0]).unwrap();
+        ptr
+    }
+
+    /// Copy the data into the second half of the scratch memory
+    fn user_data(process: &ForkedProcess, data: &[u8]) -> *mut u8 {
+        let ptr = (process.user_scratch_va() + 128) as *mut u8;
+        process.inner_access().space().copy_to_user(ptr, data).unwrap();
+        ptr
+    }
+
+    /// Read the file from the offset by pread into the second half of the scratch memory
+    fn read_back(process: &ForkedProcess, fd: usize, len: usize, offset: usize) -> Vec<u8> {
+        let ptr = (process.user_scratch_va() + 128) as *mut u8;
+        let size = sys_pread(fd, ptr, len, offset).unwrap() as usize;
+        let mut bytes = vec![0u8; size];
+        process.inner_access().space().copy_from_user(&mut bytes, ptr).unwrap();
+        bytes
+    }
+
+    /// Get the byte size of the opened regular file
+    fn file_size(process: &ForkedProcess, fd: usize) -> usize {
+        let file = Arc::clone(process.inner_access().get_file(fd).unwrap());
+        file.as_any().downcast_ref::<OSInode>().unwrap().stat().size
+    }
+
+    #[test_case]
+    fn test_fstat_and_utimensat() {
+        let process = ForkedProcess::new();
+        let current = process.enter();
+        let flags = OpenFlags::READ | OpenFlags::WRITE | OpenFlags::CREATE;
+        let file = ROOT_INODE.find_inode("/times_scratch", flags, Credential::ROOT).unwrap();
+        let ino = file.stat().ino;
+        let fd = process.inner_exclusive_access().alloc_fd(file).unwrap();
+        // both the status and the times straddle the page boundary
+        let scratch = process.user_scratch_va();
+        let stat_ptr = (scratch + 64) as *mut Stat;
+        let times_ptr = (scratch + 112) as *mut [TimeVal; 2];
+        let path_ptr = (scratch + 192) as *mut u8;
+        assert_eq!(sys_fstat(fd, stat_ptr).unwrap(), 0);
+        let stat = process.inner_access().space().copy_value_from_user(stat_ptr).unwrap();
+        assert_eq!((stat.dev, stat.ino), (ROOT_DEVICE_ID, ino));
+        let times = [TimeVal::from_us(1_000_001), TimeVal::from_us(2_000_002)];
+        let inner = process.inner_access();
+        inner.space().copy_value_to_user(times_ptr, &times).unwrap();
+        inner.space().copy_to_user(path_ptr, b"/times_scratch\0").unwrap();
+        drop(inner);
+        assert_eq!(sys_utimensat(path_ptr, times_ptr).unwrap(), 0);
+        assert_eq!(sys_fstat(fd, stat_ptr).unwrap(), 0);
+        let stat = process.inner_access().space().copy_value_from_user(stat_ptr).unwrap();
+        assert_eq!((stat.atime, stat.mtime), (times[0], times[1]));
+        // the closed file descriptor has no status
+        assert_eq!(sys_close(fd).unwrap(), 0);
+        assert_eq!(sys_fstat(fd, stat_ptr).unwrap(), -1);
+        drop(current);
+        ROOT_INODE.remove_child("times_scratch").unwrap();
+    }
+
+    #[test_case]
+    fn test_open_append_and_trunc() {
+        let process = ForkedProcess::new();
+        let current = process.enter();
+        let path = user_path(&process, "/open_scratch");
+        let create = (OpenFlags::READ | OpenFlags::WRITE | OpenFlags::CREATE).bits();
+        let append = (OpenFlags::WRITE | OpenFlags::APPEND).bits();
+        let fd = sys_open(path, create).unwrap() as usize;
+        let first = sys_open(path, append).unwrap() as usize;
+        let second = sys_open(path, append).unwrap() as usize;
+        // the appending writers have their own offsets, but never overwrite each other
+        let data = user_data(&process, b"onetwothree");
+        assert_eq!(sys_write(first, data, 3).unwrap(), 3);
+        assert_eq!(sys_write(second, data.wrapping_add(3), 3).unwrap(), 3);
+        assert_eq!(sys_write(first, data.wrapping_add(6), 5).unwrap(), 5);
+        assert_eq!(read_back(&process, fd, 16, 0), b"onetwothree");
+        // the existing data is dropped by opening with the truncation
+        let trunc = (OpenFlags::WRITE | OpenFlags::TRUNC).bits();
+        let truncated = sys_open(path, trunc).unwrap() as usize;
+        assert_eq!(file_size(&process, fd), 0);
+        // the directory can be opened but cannot be truncated
+        let root = user_path(&process, "0:");
+        assert_eq!(sys_open(root, trunc).unwrap(), -1);
+        let dir = sys_open(root, OpenFlags::RDIR.bits()).unwrap();
+        assert!(dir >= 0);
+        for fd in [fd, first, second, truncated, dir as usize] {
+            assert_eq!(sys_close(fd).unwrap(), 0);
+        }
+        drop(current);
+        ROOT_INODE.remove_child("open_scratch").unwrap();
+    }
+
+    #[test_case]
+    fn test_file_descriptors_exhausted() {
+        let process = ForkedProcess::new();
+        let current = process.enter();
+        let path = user_path(&process, "/nofile_scratch");
+        let create = (OpenFlags::READ | OpenFlags::WRITE | OpenFlags::CREATE).bits();
+        let fd = sys_open(path, create).unwrap() as usize;
+        let data = user_data(&process, b"frontier");
+        assert_eq!(sys_write(fd, data, 8).unwrap(), 8);
+        // the lowest free file descriptor was allocated, so no one is free below the limit
+        let limit = process.inner_access().nofile_limit();
+        let exhausted = RLimit {
+            soft: fd + 1,
+            hard: limit.hard,
+        };
+        assert!(process.inner_exclusive_access().set_nofile_limit(exhausted));
+        // the syscalls fail softly, and the failed opening never truncates the file
+        let trunc = (OpenFlags::WRITE | OpenFlags::TRUNC).bits();
+        assert_eq!(sys_open(path, trunc).unwrap(), -1);
+        assert_eq!(file_size(&process, fd), 8);
+        assert_eq!(sys_dup(fd).unwrap(), -1);
+        assert_eq!(sys_eventfd(0, 0).unwrap(), -1);
+        assert_eq!(sys_timerfd_create(0).unwrap(), -1);
+        assert_eq!(super::super::signal::sys_pidfd_open(process.pid()).unwrap(), -1);
+        let fds_ptr = process.user_scratch_va() as *mut usize;
+        assert_eq!(sys_pipe(fds_ptr, fds_ptr.wrapping_add(1)).unwrap(), -1);
+        // the pipe needs two file descriptors, the first one is released after the failure
+        let one_left = RLimit {
+            soft: fd + 2,
+            hard: limit.hard,
+        };
+        assert!(process.inner_exclusive_access().set_nofile_limit(one_left));
+        assert_eq!(sys_pipe(fds_ptr, fds_ptr.wrapping_add(1)).unwrap(), -1);
+        assert_eq!(sys_dup(fd).unwrap(), fd as isize + 1);
+        assert_eq!(sys_close(fd + 1).unwrap(), 0);
+        assert!(process.inner_exclusive_access().set_nofile_limit(limit));
+        assert_eq!(sys_close(fd).unwrap(), 0);
+        drop(current);
+        ROOT_INODE.remove_child("nofile_scratch").unwrap();
+    }
+
+    #[test_case]
+    fn test_ftruncate_grow_and_shrink() {
+        let process = ForkedProcess::new();
+        let current = process.enter();
+        let path = user_path(&process, "/truncate_scratch");
+        let create = OpenFlags::READ | OpenFlags::WRITE | OpenFlags::CREATE | OpenFlags::TRUNC;
+        let fd = sys_open(path, create.bits()).unwrap() as usize;
+        let data = user_data(&process, b"frontier");
+        assert_eq!(sys_write(fd, data, 8).unwrap(), 8);
+        let stat_ptr = (process.user_scratch_va() + 64) as *mut Stat;
+        let fstat_size = || {
+            assert_eq!(sys_fstat(fd, stat_ptr).unwrap(), 0);
+            process.inner_access().space().copy_value_from_user(stat_ptr).unwrap().size
+        };
+        // the grown region is read as zeros
+        assert_eq!(sys_ftruncate(fd, 100).unwrap(), 0);
+        assert_eq!(fstat_size(), 100);
+        let bytes = read_back(&process, fd, 100, 0);
+        assert_eq!(&bytes[..8], b"frontier");
+        assert!(bytes[8..].iter().all(|byte| *byte == 0));
+        // the shrunk data cannot be read any more, and the regrown region is zeros again
+        assert_eq!(sys_ftruncate(fd, 4).unwrap(), 0);
+        assert_eq!(fstat_size(), 4);
+        assert_eq!(read_back(&process, fd, 100, 0), b"fron");
+        assert_eq!(sys_ftruncate(fd, 8).unwrap(), 0);
+        assert_eq!(read_back(&process, fd, 100, 0), b"fron\0\0\0\0");
+        // the unknown file descriptor cannot be truncated
+        assert_eq!(sys_ftruncate(usize::MAX, 0).unwrap(), -1);
+        assert_eq!(sys_close(fd).unwrap(), 0);
+        drop(current);
+        ROOT_INODE.remove_child("truncate_scratch").unwrap();
+    }
+
+    #[test_case]
+    fn test_pread_and_pwrite_keep_offset() {
+        let process = ForkedProcess::new();
+        let current = process.enter();
+        let path = user_path(&process, "/pread_scratch");
+        let create = OpenFlags::READ | OpenFlags::WRITE | OpenFlags::CREATE | OpenFlags::TRUNC;
+        let writer = sys_open(path, create.bits()).unwrap() as usize;
+        let reader = sys_open(path, OpenFlags::READ.bits()).unwrap() as usize;
+        let data = user_data(&process, b"abcdef");
+        assert_eq!(sys_write(writer, data, 6).unwrap(), 6);
+        // the positional writing does not move the offset of the sequential writer
+        let data = user_data(&process, b"XY");
+        assert_eq!(sys_pwrite(writer, data, 2, 0).unwrap(), 2);
+        let data = user_data(&process, b"gh");
+        assert_eq!(sys_write(writer, data, 2).unwrap(), 2);
+        assert_eq!(read_back(&process, reader, 16, 0), b"XYcdefgh");
+        // the positional reading does not disturb the sequential reader
+        let buffer = (process.user_scratch_va() + 200) as *mut u8;
+        let mut bytes = [0u8; 2];
+        assert_eq!(sys_read(reader, buffer, 2).unwrap(), 2);
+        process.inner_access().space().copy_from_user(&mut bytes, buffer).unwrap();
+        assert_eq!(&bytes, b"XY");
+        assert_eq!(read_back(&process, reader, 2, 6), b"gh");
+        assert_eq!(sys_read(reader, buffer, 2).unwrap(), 2);
+        process.inner_access().space().copy_from_user(&mut bytes, buffer).unwrap();
+        assert_eq!(&bytes, b"cd");
+        for fd in [writer, reader] {
+            assert_eq!(sys_close(fd).unwrap(), 0);
+        }
+        drop(current);
+        ROOT_INODE.remove_child("pread_scratch").unwrap();
+    }
+
+    #[test_case]
+    fn test_eventfd_rejects_invalid_initval() {
+        let process = ForkedProcess::new();
+        let _current = process.enter();
+        assert_eq!(sys_eventfd(u64::MAX as usize, 0).unwrap(), -1);
+        assert_eq!(sys_eventfd(0, 1 << 1).unwrap(), -1);
+        let fd = sys_eventfd(EVENTFD_MAX_VALUE as usize, EFD_SEMAPHORE).unwrap();
+        assert!(fd >= 0);
+        assert_eq!(sys_close(fd as usize).unwrap(), 0);
+    }
 }
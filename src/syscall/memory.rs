@@ -0,0 +1,245 @@
+// @author:    olinex
+// @time:      2024/08/02
+
+// self mods
+
+// use other mods
+use alloc::sync::Arc;
+use core::mem::size_of;
+
+// use self mods
+use crate::memory::cgroup::{CgroupStat, CGROUP_TABLE};
+use crate::memory::frame::FRAME_ALLOCATOR;
+use crate::memory::shm::SHM_TABLE;
+use crate::memory::{heap, MemInfo};
+use crate::prelude::*;
+use crate::task::PROCESSOR;
+
+/// The advice which tells the kernel that the pages will not be accessed in the near future,
+/// their frames can be released and they will be zero-filled when they are accessed again
+const MADV_DONTNEED: usize = 4;
+
+/// Get the shared memory segment by the key, a new segment will be created if the key does not exist.
+/// The processes which share the segment must synchronize by themselves, such as using the futex.
+///
+/// - Arguments
+///     - key: the key of the shared memory segment
+///     - size: the byte size of the segment, it will be rounded up to the page size
+///
+/// - Returns
+///     - the id of the segment
+///     - -1: the size is zero or greater than the size of the existing segment
+///
+/// - Errors
+///     - IdExhausted
+///     - FrameExhausted
+#[inline(always)]
+pub(crate) fn sys_shm_get(key: usize, size: usize) -> Result<isize> {
+    match SHM_TABLE.get(key, size)? {
+        Some(id) => Ok(id as isize),
+        None => Ok(-1),
+    }
+}
+
+/// Attach the shared memory segment into the current process's space with read and write permission.
+///
+/// - Arguments
+///     - id: the id of the shared memory segment
+///
+/// - Returns
+///     - the base virtual address of the attached segment
+///     - -1: the segment does not exist, there is no free page range,
+///           or the address space would exceed the limit
+///
+/// - Errors
+///     - ProcessHaveNotTask
+///     - VPNAlreadyMapped(vpn)
+///     - FrameExhausted
+///     - AllocFullPageMapper(ppn)
+#[inline(always)]
+pub(crate) fn sys_shm_attach(id: usize) -> Result<isize> {
+    let task = PROCESSOR.current_task()?;
+    let process = task.process();
+    let mut inner = process.inner_exclusive_access();
+    match inner.attach_shm(id) {
+        Ok(va) => Ok(va as isize),
+        Err(KernelError::ShmDoesNotExist(_))
+        | Err(KernelError::AreaAllocFailed(..))
+        | Err(KernelError::SpaceLimitExceeded(_)) => Ok(-1),
+        Err(other) => Err(other),
+    }
+}
+
+/// Detach the shared memory segment from the current process's space,
+/// the segment will be released when it was detached by all processes.
+///
+/// - Arguments
+///     - va: the base virtual address of the attached segment
+///
+/// - Returns
+///     - 0: success
+///     - -1: there is no segment attached at the address
+///
+/// - Errors
+///     - ProcessHaveNotTask
+///     - ShmDoesNotExist(id)
+///     - IdNotDeallocable(id)
+#[inline(always)]
+pub(crate) fn sys_shm_detach(va: usize) -> Result<isize> {
+    let task = PROCESSOR.current_task()?;
+    let process = task.process();
+    let mut inner = process.inner_exclusive_access();
+    if inner.space_mut().detach_shm(va)? {
+        Ok(0)
+    } else {
+        Ok(-1)
+    }
+}
+
+/// Give advice about the use of the memory in the range of the current process's space,
+/// only the `MADV_DONTNEED` advice is supported now.
+///
+/// - Arguments
+///     - addr: the page aligned virtual address of the range
+///     - len: the byte length of the range, it will be rounded up to the page size
+///     - advice: the advice of the range
+///
+/// - Returns
+///     - 0: success
+///     - -1: the advice is not supported, the address is not aligned,
+///           or the range is not covered by one anonymous area
+///
+/// - Errors
+///     - ProcessHaveNotTask
+///     - VPNNotMapped(vpn)
+///     - PPNNotMapped(ppn)
+///     - DeallocEmptyPageMapper(ppn)
+///     - IdNotDeallocable(slot)
+#[inline(always)]
+pub(crate) fn sys_madvise(addr: usize, len: usize, advice: usize) -> Result<isize> {
+    if advice != MADV_DONTNEED {
+        return Ok(-1);
+    }
+    let task = PROCESSOR.current_task()?;
+    let process = task.process();
+    let mut inner = process.inner_exclusive_access();
+    if inner.space_mut().discard_user_pages(addr, len)? {
+        Ok(0)
+    } else {
+        Ok(-1)
+    }
+}
+
+/// Get the memory usage of the kernel heap and the frame allocator.
+///
+/// - Arguments
+///     - info: the pointer of the memory info structure which will be written
+///
+/// - Returns
+///     - 0: success
+///
+/// - Errors
+///     - ProcessHaveNotTask
+///     - VPNNotMapped(vpn)
+///     - UserRangeNotAccessible(va, len)
+#[inline(always)]
+pub(crate) fn sys_meminfo(info: *mut MemInfo) -> Result<isize> {
+    // collect the statistics before borrowing the process, so the allocating in the borrowing is not counted
+    let stats = MemInfo {
+        heap: heap::stats(),
+        frame: FRAME_ALLOCATOR.access().stats(),
+    };
+    let task = PROCESSOR.current_task()?;
+    let process = task.process();
+    let inner = process.inner_access();
+    inner.space().check_user_range(info, size_of::<MemInfo>(), true)?;
+    inner.space().copy_value_to_user(info, &stats)?;
+    Ok(0)
+}
+
+/// Create a new memory cgroup with the frame budget,
+/// the processes can join the group by [`sys_cgroup_attach`].
+///
+/// - Arguments
+///     - budget: the max count of the frames which can be charged to the group
+///
+/// - Returns
+///     - the id of the group
+///
+/// - Errors
+///     - IdExhausted
+#[inline(always)]
+pub(crate) fn sys_cgroup_create(budget: usize) -> Result<isize> {
+    Ok(CGROUP_TABLE.create(budget)? as isize)
+}
+
+/// Move the current process or one of it's child processes into the memory cgroup,
+/// only the privileged process can move the processes between the groups.
+/// The frames which are already mapped will be charged to the new group,
+/// and the processes forked from the target later will join the same group.
+///
+/// - Arguments
+///     - id: the id of the memory cgroup
+///     - pid: the id of the target process, 0 means the current process
+///
+/// - Returns
+///     - 0: success
+///     - -1: the group does not exist, the target process is neither the current process
+///         nor it's child, or the mapped frames exceed the budget of the group
+///     - -2: the current process is not privileged
+///
+/// - Errors
+///     - ProcessHaveNotTask
+#[inline(always)]
+pub(crate) fn sys_cgroup_attach(id: usize, pid: usize) -> Result<isize> {
+    let cgroup = match CGROUP_TABLE.get(id) {
+        Some(cgroup) => cgroup,
+        None => return Ok(-1),
+    };
+    let task = PROCESSOR.current_task()?;
+    let process = task.process();
+    if !process.inner_access().is_privileged() {
+        return Ok(-2);
+    }
+    let target = if pid == 0 || pid == process.pid() {
+        Arc::clone(&process)
+    } else if let Some(child) = process.inner_access().get_child(pid) {
+        Arc::clone(child)
+    } else {
+        return Ok(-1);
+    };
+    let mut inner = target.inner_exclusive_access();
+    match inner.space_mut().set_cgroup(Some(cgroup)) {
+        Ok(_) => Ok(0),
+        Err(KernelError::CgroupBudgetExceeded(_)) => Ok(-1),
+        Err(other) => Err(other),
+    }
+}
+
+/// Get the statistics of the memory cgroup
+///
+/// - Arguments
+///     - id: the id of the memory cgroup
+///     - stat: the pointer of the statistics structure which will be written
+///
+/// - Returns
+///     - 0: success
+///     - -1: the group does not exist
+///
+/// - Errors
+///     - ProcessHaveNotTask
+///     - VPNNotMapped(vpn)
+///     - UserRangeNotAccessible(va, len)
+#[inline(always)]
+pub(crate) fn sys_cgroup_stat(id: usize, stat: *mut CgroupStat) -> Result<isize> {
+    let value = match CGROUP_TABLE.get(id) {
+        Some(cgroup) => cgroup.stat(),
+        None => return Ok(-1),
+    };
+    let task = PROCESSOR.current_task()?;
+    let process = task.process();
+    let inner = process.inner_access();
+    inner.space().check_user_range(stat, size_of::<CgroupStat>(), true)?;
+    inner.space().copy_value_to_user(stat, &value)?;
+    Ok(0)
+}
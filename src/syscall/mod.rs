@@ -3,56 +3,149 @@
 
 // self mods
 mod fs;
+mod memory;
 mod process;
 mod signal;
 mod sync;
+mod system;
 mod task;
 mod time;
 
 // use other mods
-use frontier_lib::{constant::sysid, model::signal::SignalAction};
+use frontier_lib::constant::sysid;
+use frontier_lib::model::signal::SignalFlags;
 
 // use self mods
+use self::system::UtsName;
+use crate::fs::inode::Stat;
+use crate::fs::timerfd::TimerSpec;
+use crate::fs::PollFd;
+use crate::lang::timer::{TimeVal, Times};
+use crate::memory::cgroup::CgroupStat;
+use crate::memory::MemInfo;
 use crate::prelude::*;
+use crate::task::clone::ThreadAttr;
+use crate::task::model::{ProcessInfo, RLimit, RUsage};
+use crate::task::signal::{SignalStack, UserSignalAction};
+use crate::task::SchedStats;
 
 // handle syscall exception with `syscall_id` and other arguments
 #[inline(always)]
-pub(crate) fn syscall(syscall_id: usize, arg1: usize, arg2: usize, arg3: usize) -> Result<isize> {
+pub(crate) fn syscall(
+    syscall_id: usize,
+    arg1: usize,
+    arg2: usize,
+    arg3: usize,
+    arg4: usize,
+) -> Result<isize> {
     match syscall_id {
         sysid::DUP => fs::sys_dup(arg1 as usize),
+        sysid::CONSOLE_MODE => fs::sys_console_mode(arg1, arg2 != 0),
         sysid::OPEN => fs::sys_open(arg1 as *const u8, arg2 as u32),
         sysid::CLOSE => fs::sys_close(arg1),
+        sysid::FTRUNCATE => fs::sys_ftruncate(arg1, arg2),
+        sysid::SYNC => fs::sys_sync(),
+        sysid::FSYNC => fs::sys_fsync(arg1),
+        sysid::FSTAT => fs::sys_fstat(arg1, arg2 as *mut Stat),
+        sysid::CHMOD => fs::sys_chmod(arg1 as *const u8, arg2 as u32),
+        sysid::SYMLINK => fs::sys_symlink(arg1 as *const u8, arg2 as *const u8),
+        sysid::READLINK => fs::sys_readlink(arg1 as *const u8, arg2 as *mut u8, arg3),
+        sysid::UTIMENSAT => fs::sys_utimensat(arg1 as *const u8, arg2 as *const [TimeVal; 2]),
         sysid::PIPE => fs::sys_pipe(arg1 as *mut usize, arg2 as *mut usize),
+        sysid::EVENTFD => fs::sys_eventfd(arg1, arg2),
+        sysid::TIMERFD_CREATE => fs::sys_timerfd_create(arg1),
+        sysid::TIMERFD_SETTIME => fs::sys_timerfd_settime(
+            arg1,
+            arg2 as *const TimerSpec,
+            arg3 as *mut TimerSpec,
+        ),
+        sysid::MQ_OPEN => fs::sys_mq_open(arg1 as *const u8),
+        sysid::MQ_SEND => fs::sys_mq_send(arg1, arg2 as *const u8, arg3),
+        sysid::MQ_RECV => fs::sys_mq_recv(arg1, arg2 as *mut u8, arg3),
         sysid::READ => fs::sys_read(arg1, arg2 as *mut u8, arg3),
         sysid::WRITE => fs::sys_write(arg1, arg2 as *const u8, arg3),
+        sysid::PREAD => fs::sys_pread(arg1, arg2 as *mut u8, arg3, arg4),
+        sysid::PWRITE => fs::sys_pwrite(arg1, arg2 as *const u8, arg3, arg4),
+        sysid::POLL => fs::sys_poll(arg1 as *mut PollFd, arg2, arg3),
         sysid::EXIT => process::sys_exit(arg1 as i32),
+        sysid::REBOOT => process::sys_reboot(arg1),
         sysid::SLEEP => time::sys_sleep(arg1 as usize),
+        sysid::NANOSLEEP => time::sys_nanosleep(arg1 as *const TimeVal, arg2 as *mut TimeVal),
         sysid::YIELD => task::sys_yield(),
-        sysid::KILL => signal::sys_kill(arg1 as isize, arg2 as usize),
+        sysid::YIELD_TO => task::sys_yield_to(arg1),
+        sysid::SCHED_YIELD_WITH_HINT => task::sys_sched_yield_with_hint(),
+        sysid::SCHED_STATS => task::sys_sched_stats(arg1 as *mut SchedStats),
+        sysid::KILL => signal::sys_kill(arg1 as isize, arg2, arg3),
+        sysid::TKILL => signal::sys_tkill(arg1, arg2),
+        sysid::PIDFD_OPEN => signal::sys_pidfd_open(arg1),
+        sysid::PIDFD_SEND_SIGNAL => signal::sys_pidfd_send_signal(arg1, arg2, arg3),
         sysid::SIG_ACTION => signal::sys_sig_action(
             arg1 as usize,
-            arg2 as *const SignalAction,
-            arg3 as *mut SignalAction,
+            arg2 as *const UserSignalAction,
+            arg3 as *mut UserSignalAction,
         ),
         sysid::SIG_PROC_MASK => signal::sys_sig_proc_mask(arg1 as u32),
         sysid::SIG_RETURN => signal::sys_sig_return(),
+        sysid::SIGALTSTACK => signal::sys_sigaltstack(
+            arg1 as *const SignalStack,
+            arg2 as *mut SignalStack,
+        ),
+        sysid::SIG_PENDING => signal::sys_sig_pending(arg1 as *mut SignalFlags),
+        sysid::SIG_SUSPEND => signal::sys_sig_suspend(arg1 as u32),
         sysid::GET_TIME => time::sys_get_time(),
+        sysid::UPTIME => time::sys_uptime(),
+        sysid::GETTIMEOFDAY => time::sys_gettimeofday(arg1 as *mut TimeVal),
+        sysid::SETTIMEOFDAY => time::sys_settimeofday(arg1 as *const TimeVal),
+        sysid::TIMES => time::sys_times(arg1 as *mut Times),
         sysid::GET_PID => process::sys_get_pid(),
+        sysid::GET_PPID => process::sys_get_ppid(),
+        sysid::SETPGID => process::sys_setpgid(arg1, arg2),
+        sysid::GETUID => process::sys_getuid(),
+        sysid::SETUID => process::sys_setuid(arg1),
+        sysid::GETGID => process::sys_getgid(),
+        sysid::SETGID => process::sys_setgid(arg1),
+        sysid::PTRACE => process::sys_ptrace(arg1, arg2, arg3, arg4),
+        sysid::LIST_PROCESSES => process::sys_list_processes(arg1 as *mut ProcessInfo, arg2),
+        sysid::GETRUSAGE => process::sys_getrusage(arg1 as isize, arg2 as *mut RUsage),
+        sysid::GETRLIMIT => process::sys_getrlimit(arg1, arg2 as *mut RLimit),
+        sysid::SETRLIMIT => process::sys_setrlimit(arg1, arg2 as *const RLimit),
         sysid::FORK => process::sys_fork(),
         sysid::EXEC => process::sys_exec(arg1 as *const u8, arg2 as *const u8),
-        sysid::WAIT_PID => process::sys_wait_pid(arg1 as isize, arg2 as *mut i32),
-        sysid::THREAD_CREATE => task::sys_thread_create(arg1, arg2),
+        sysid::WAIT_PID => process::sys_wait_pid(arg1 as isize, arg2 as *mut i32, arg3),
+        sysid::THREAD_CREATE => task::sys_thread_create(arg1, arg2, arg3, arg4 as *const ThreadAttr),
+        sysid::CLONE3 => task::sys_clone3(arg1 as *const u8, arg2),
+        sysid::SET_TLS => task::sys_set_tls(arg1),
         sysid::GET_TID => task::sys_get_tid(),
         sysid::WAIT_TID => task::sys_wait_tid(arg1 as isize, arg2 as *mut i32),
-        sysid::MUTEX_CREATE => sync::sys_create_mutex(arg1 != 0),
+        sysid::THREAD_JOIN => task::sys_thread_join(arg1, arg2 as *mut i32),
+        sysid::MUTEX_CREATE => sync::sys_create_mutex(arg1),
         sysid::MUTEX_LOCK => sync::sys_lock_mutex(arg1 as usize),
+        sysid::MUTEX_TRY_LOCK => sync::sys_try_lock_mutex(arg1),
         sysid::MUTEX_UNLOCK => sync::sys_unlock_mutex(arg1 as usize),
         sysid::SEMAPHORE_CREATE => sync::sys_create_semaphore(arg1 != 0, arg2 as isize),
         sysid::SEMAPHORE_UP => sync::sys_up_semaphore(arg1 as usize),
         sysid::SEMAPHORE_DOWN => sync::sys_down_semaphore(arg1 as usize),
+        sysid::SEMAPHORE_TRY_DOWN => sync::sys_try_down_semaphore(arg1),
         sysid::CONDVAR_CREATE => sync::sys_create_condvar(),
         sysid::CONDVAR_SIGNAL => sync::sys_signal_condvar(arg1 as usize),
         sysid::CONDVAR_WAIT => sync::sys_wait_condvar(arg1 as usize, arg2 as usize),
+        sysid::CONDVAR_WAIT_TIMEOUT => sync::sys_wait_condvar_timeout(arg1, arg2, arg3),
+        sysid::BARRIER_CREATE => sync::sys_create_barrier(arg1),
+        sysid::BARRIER_WAIT => sync::sys_barrier_wait(arg1),
+        sysid::FUTEX_WAIT => sync::sys_futex_wait(arg1 as *const u32, arg2 as u32),
+        sysid::FUTEX_WAKE => sync::sys_futex_wake(arg1 as *const u32, arg2),
+        sysid::SHM_GET => memory::sys_shm_get(arg1, arg2),
+        sysid::SHM_ATTACH => memory::sys_shm_attach(arg1),
+        sysid::SHM_DETACH => memory::sys_shm_detach(arg1),
+        sysid::MEMINFO => memory::sys_meminfo(arg1 as *mut MemInfo),
+        sysid::MADVISE => memory::sys_madvise(arg1, arg2, arg3),
+        sysid::CGROUP_CREATE => memory::sys_cgroup_create(arg1),
+        sysid::CGROUP_ATTACH => memory::sys_cgroup_attach(arg1, arg2),
+        sysid::CGROUP_STAT => memory::sys_cgroup_stat(arg1, arg2 as *mut CgroupStat),
+        sysid::SET_LOG_LEVEL => system::sys_set_log_level(arg1 as *const u8, arg2),
+        sysid::DMESG => system::sys_dmesg(arg1 as *mut u8, arg2, arg3 as *mut usize),
+        sysid::GETRANDOM => system::sys_getrandom(arg1 as *mut u8, arg2),
+        sysid::UNAME => system::sys_uname(arg1 as *mut UtsName),
         _ => Err(KernelError::InvaidSyscallId(syscall_id)),
     }
 }
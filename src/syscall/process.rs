@@ -6,12 +6,53 @@
 // use other mods
 
 use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::mem::size_of;
 use frontier_fs::OpenFlags;
 
 // use self mods
+use crate::configs;
 use crate::fs::inode::ROOT_INODE;
+use crate::fs::sync_and_reset;
 use crate::prelude::*;
-use crate::task::{exit_current_and_run_other_task, PROCESSOR, TASK_SCHEDULER};
+use crate::sbi::ResetMode;
+use crate::task::model::{ProcessInfo, RLimit, RUsage, ROOT_PID};
+use crate::trap::context::UserRegs;
+use crate::task::{
+    current_has_pending_signal, exit_current_and_run_other_task, interrupt_current_syscall,
+    suspend_current_and_run_other_task, PROCESSOR, PROCESS_TABLE, TASK_SCHEDULER,
+};
+
+/// Get the resource usage of all the tasks in the current process
+const RUSAGE_SELF: isize = 0;
+/// Get the resource usage of the children which have exited and been waited
+const RUSAGE_CHILDREN: isize = -1;
+/// Get the resource usage of the current task only
+const RUSAGE_THREAD: isize = 1;
+/// The limit of the tasks in the current process, which also limits the living child processes
+const RLIMIT_NPROC: usize = 6;
+/// The limit of the file descriptors in the current process
+const RLIMIT_NOFILE: usize = 7;
+/// The limit of the bytes mapped in the address space of the current process
+const RLIMIT_AS: usize = 9;
+/// Read a word in the space of the traced process
+const PTRACE_PEEKDATA: usize = 2;
+/// Write a word in the space of the traced process
+const PTRACE_POKEDATA: usize = 5;
+/// Continue the traced process
+const PTRACE_CONT: usize = 7;
+/// Continue the traced process to execute one instruction, then stop it by the TRAP signal
+const PTRACE_SINGLESTEP: usize = 9;
+/// Read the registers of the traced process
+const PTRACE_GETREGS: usize = 12;
+/// Write the registers of the traced process
+const PTRACE_SETREGS: usize = 13;
+/// Attach to the process and stop it
+const PTRACE_ATTACH: usize = 16;
+/// Detach from the traced process and continue it
+const PTRACE_DETACH: usize = 17;
+/// Return immediately if the waited child process is still alive
+const WNOHANG: usize = 1 << 0;
 
 /// Task exits and submit an exit code
 ///
@@ -24,6 +65,38 @@ pub(crate) fn sys_exit(exit_code: i32) -> ! {
     unreachable!();
 }
 
+/// Reset the machine by the mode, only the initial process run by the privileged user can call it,
+/// the other processes are refused even if they are privileged.
+/// All the file systems will be flushed before resetting.
+///
+/// - Arguments
+///     - mode: the reset mode
+///         - 0: halt
+///         - 1: power off
+///         - 2: reboot
+///
+/// - Returns
+///     - never returns if success
+///     - -1: the current process is not the initial process or it is not privileged
+///     - -2: the mode is unknown
+///
+/// - Errors
+///     - ProcessHaveNotTask
+#[inline(always)]
+pub(crate) fn sys_reboot(mode: usize) -> Result<isize> {
+    let task = PROCESSOR.current_task()?;
+    let process = task.process();
+    if process.pid() != ROOT_PID || !process.inner_access().is_privileged() {
+        return Ok(-1);
+    }
+    drop(process);
+    drop(task);
+    match ResetMode::from_raw(mode) {
+        Some(mode) => sync_and_reset(mode),
+        None => Ok(-2),
+    }
+}
+
 /// Get the current task's process unique id
 #[inline(always)]
 pub(crate) fn sys_get_pid() -> Result<isize> {
@@ -31,6 +104,349 @@ pub(crate) fn sys_get_pid() -> Result<isize> {
     Ok(current_task.process().pid() as isize)
 }
 
+/// Get the parent process unique id of the current task's process.
+/// If the parent process has exited, the process was reparented to `initproc`,
+/// and the pid of `initproc` will be returned.
+///
+/// - Errors
+///     - ProcessHaveNotTask
+#[inline(always)]
+pub(crate) fn sys_get_ppid() -> Result<isize> {
+    let current_task = PROCESSOR.current_task()?;
+    Ok(current_task.process().ppid() as isize)
+}
+
+/// List all the live processes into the buffer, each process will be written as [`ProcessInfo`],
+/// which contains the pid, ppid, status and path of the process.
+///
+/// - Arguments
+///     - buffer_ptr: the pointer of the process information array
+///     - len: the max count of the process information the array can hold
+///
+/// - Returns
+///     - the count of the process information which was written
+///
+/// - Errors
+///     - ProcessHaveNotTask
+///     - VPNNotMapped(vpn)
+///     - UserRangeNotAccessible(va, len)
+#[inline(always)]
+pub(crate) fn sys_list_processes(buffer_ptr: *mut ProcessInfo, len: usize) -> Result<isize> {
+    let processes = PROCESS_TABLE.list();
+    let count = processes.len().min(len);
+    if count == 0 {
+        return Ok(0);
+    }
+    // collect the infos before borrowing the current process, which may also be listed
+    let infos: Vec<ProcessInfo> = processes
+        .iter()
+        .take(count)
+        .map(|process| process.info())
+        .collect();
+    let task = PROCESSOR.current_task()?;
+    let process = task.process();
+    let inner = process.inner_access();
+    let byte_size = count * size_of::<ProcessInfo>();
+    inner.space().check_user_range(buffer_ptr, byte_size, true)?;
+    for (index, info) in infos.iter().enumerate() {
+        let ptr = buffer_ptr.wrapping_add(index) as *mut u8;
+        inner.space().copy_to_user(ptr, info.as_bytes())?;
+    }
+    Ok(count as isize)
+}
+
+/// Get the resource usage, which contains the page fault and context switch counts.
+///
+/// - Arguments
+///     - who: the target whose usage will be got
+///         - 0: all the tasks in the current process
+///         - -1: the children which have exited and been waited
+///         - 1: the current task
+///     - usage: the pointer of the usage structure which will be written
+///
+/// - Returns
+///     - 0: success
+///     - -1: the target is unknown
+///
+/// - Errors
+///     - ProcessHaveNotTask
+///     - VPNNotMapped(vpn)
+///     - UserRangeNotAccessible(va, len)
+#[inline(always)]
+pub(crate) fn sys_getrusage(who: isize, usage: *mut RUsage) -> Result<isize> {
+    let task = PROCESSOR.current_task()?;
+    let process = task.process();
+    // the resident frames are sampled again, so the usage is up to date
+    task.account_resident_frames(process.inner_access().space().resident_frames());
+    let rusage = match who {
+        RUSAGE_SELF => process.rusage(),
+        RUSAGE_CHILDREN => process.children_rusage(),
+        RUSAGE_THREAD => task.rusage(),
+        _ => return Ok(-1),
+    };
+    let inner = process.inner_access();
+    inner.space().check_user_range(usage, size_of::<RUsage>(), true)?;
+    inner.space().copy_value_to_user(usage, &rusage)?;
+    Ok(0)
+}
+
+/// Get the soft and hard limits of the resource in the current process.
+///
+/// - Arguments
+///     - resource: the resource whose limit will be got
+///         - 6: the tasks and the living child processes
+///         - 7: the file descriptors
+///         - 9: the bytes mapped in the address space
+///     - limit: the pointer of the limit structure which will be written
+///
+/// - Returns
+///     - 0: success
+///     - -1: the resource is unknown
+///
+/// - Errors
+///     - ProcessHaveNotTask
+///     - VPNNotMapped(vpn)
+///     - UserRangeNotAccessible(va, len)
+#[inline(always)]
+pub(crate) fn sys_getrlimit(resource: usize, limit: *mut RLimit) -> Result<isize> {
+    let task = PROCESSOR.current_task()?;
+    let process = task.process();
+    let inner = process.inner_access();
+    let value = match resource {
+        RLIMIT_NPROC => inner.task_limit(),
+        RLIMIT_NOFILE => inner.nofile_limit(),
+        RLIMIT_AS => inner.space_limit(),
+        _ => return Ok(-1),
+    };
+    inner.space().check_user_range(limit, size_of::<RLimit>(), true)?;
+    inner.space().copy_value_to_user(limit, &value)?;
+    Ok(0)
+}
+
+/// Set the soft and hard limits of the resource in the current process,
+/// the limits will be inherited by the forked children.
+/// The hard limit can only be lowered, and the soft limit must not exceed the hard limit.
+///
+/// - Arguments
+///     - resource: the resource whose limit will be set
+///         - 6: the tasks and the living child processes
+///         - 7: the file descriptors
+///         - 9: the bytes mapped in the address space
+///     - limit: the pointer of the new limit structure
+///
+/// - Returns
+///     - 0: success
+///     - -1: the resource is unknown or the limit is invalid
+///
+/// - Errors
+///     - ProcessHaveNotTask
+///     - VPNNotMapped(vpn)
+///     - UserRangeNotAccessible(va, len)
+#[inline(always)]
+pub(crate) fn sys_setrlimit(resource: usize, limit: *const RLimit) -> Result<isize> {
+    let task = PROCESSOR.current_task()?;
+    let process = task.process();
+    let mut inner = process.inner_exclusive_access();
+    inner.space().check_user_range(limit, size_of::<RLimit>(), false)?;
+    let value = inner.space().copy_value_from_user(limit)?;
+    let success = match resource {
+        RLIMIT_NPROC => inner.set_task_limit(value),
+        RLIMIT_NOFILE => inner.set_nofile_limit(value),
+        RLIMIT_AS => inner.set_space_limit(value),
+        _ => return Ok(-1),
+    };
+    Ok(if success { 0 } else { -1 })
+}
+
+/// Set the process group id of the current process or one of it's child processes.
+///
+/// - Arguments
+///     - pid: the id of the target process, 0 means the current process
+///     - pgid: the new process group id, 0 means using the pid of the target process
+///
+/// - Returns
+///     - 0: success
+///     - -1: the target process is neither the current process nor it's child
+///     - -2: the group is neither led by the target process nor existing in the same session
+///
+/// - Errors
+///     - ProcessHaveNotTask
+#[inline(always)]
+pub(crate) fn sys_setpgid(pid: usize, pgid: usize) -> Result<isize> {
+    let current_task = PROCESSOR.current_task()?;
+    let process = current_task.process();
+    let target = if pid == 0 || pid == process.pid() {
+        Arc::clone(&process)
+    } else if let Some(child) = process.inner_access().get_child(pid) {
+        Arc::clone(child)
+    } else {
+        return Ok(-1);
+    };
+    let pgid = if pgid == 0 { target.pid() } else { pgid };
+    if target.join_group(pgid) {
+        Ok(0)
+    } else {
+        Ok(-2)
+    }
+}
+
+/// Get the user id of the current process
+///
+/// - Errors
+///     - ProcessHaveNotTask
+#[inline(always)]
+pub(crate) fn sys_getuid() -> Result<isize> {
+    let current_task = PROCESSOR.current_task()?;
+    let uid = current_task.process().inner_access().uid();
+    Ok(uid as isize)
+}
+
+/// Set the user id of the current process,
+/// the unprivileged process can only set the user id to itself.
+///
+/// - Arguments
+///     - uid: the new user id
+///
+/// - Returns
+///     - 0: success
+///     - -1: the user id is unknown or the current process is not privileged
+///
+/// - Errors
+///     - ProcessHaveNotTask
+#[inline(always)]
+pub(crate) fn sys_setuid(uid: usize) -> Result<isize> {
+    let current_task = PROCESSOR.current_task()?;
+    let success = current_task.process().inner_exclusive_access().set_uid(uid);
+    Ok(if success { 0 } else { -1 })
+}
+
+/// Get the group id of the current process
+///
+/// - Errors
+///     - ProcessHaveNotTask
+#[inline(always)]
+pub(crate) fn sys_getgid() -> Result<isize> {
+    let current_task = PROCESSOR.current_task()?;
+    let gid = current_task.process().inner_access().gid();
+    Ok(gid as isize)
+}
+
+/// Set the group id of the current process,
+/// the unprivileged process can only set the group id to itself.
+///
+/// - Arguments
+///     - gid: the new group id
+///
+/// - Returns
+///     - 0: success
+///     - -1: the group id is unknown or the current process is not privileged
+///
+/// - Errors
+///     - ProcessHaveNotTask
+#[inline(always)]
+pub(crate) fn sys_setgid(gid: usize) -> Result<isize> {
+    let current_task = PROCESSOR.current_task()?;
+    let success = current_task.process().inner_exclusive_access().set_gid(gid);
+    Ok(if success { 0 } else { -1 })
+}
+
+/// Trace another process like a debugger.
+/// The process must be attached before it is traced,
+/// and it must be stopped by the STOP signal before it's memory and registers are accessed.
+/// The registers are the ones of the root task in the traced process.
+///
+/// - Arguments
+///     - request: the kind of the tracing
+///         - 2: read the word at the address into the pointer of the data
+///         - 5: write the data as the word at the address
+///         - 7: continue the process
+///         - 9: continue the process to execute one instruction of the root task,
+///             then stop it by the TRAP signal
+///         - 12: read the registers into the pointer of the data, see [`UserRegs`]
+///         - 13: write the registers from the pointer of the data, see [`UserRegs`]
+///         - 16: attach to the process and stop it,
+///             the parent run by the same user and the privileged process can attach
+///         - 17: detach from the process and continue it
+///     - pid: the id of the traced process
+///     - addr: the virtual address in the space of the traced process
+///     - data: the value or the pointer in the space of the current process
+///
+/// - Returns
+///     - 0: success
+///     - -1: the process does not exist, the current process is not authorized,
+///         the traced process is not stopped, the address is not accessible
+///         or the request is unknown
+///
+/// - Errors
+///     - ProcessHaveNotTask
+///     - UserRangeNotAccessible(va, len)
+///     - AreaNotExists(start_vpn, end_vpn)
+///     - VPNNotMapped(vpn)
+///     - FrameExhausted
+///     - SwapIOFailed(slot)
+#[inline(always)]
+pub(crate) fn sys_ptrace(request: usize, pid: usize, addr: usize, data: usize) -> Result<isize> {
+    let current_task = PROCESSOR.current_task()?;
+    let process = current_task.process();
+    let target = match PROCESS_TABLE.get(pid) {
+        Some(target) => target,
+        None => return Ok(-1),
+    };
+    match request {
+        PTRACE_ATTACH => return Ok(if target.attach(&process) { 0 } else { -1 }),
+        PTRACE_DETACH => match target.detach(&process) {
+            Ok(true) => return Ok(0),
+            Ok(false) | Err(KernelError::UserRangeNotAccessible(_, _)) => return Ok(-1),
+            Err(other) => return Err(other),
+        },
+        _ => {}
+    }
+    if !target.is_traced_by(&process) || !target.is_stopped() {
+        return Ok(-1);
+    }
+    match request {
+        PTRACE_PEEKDATA => {
+            let value = match target.peek_word(addr) {
+                Ok(value) => value,
+                Err(KernelError::UserRangeNotAccessible(_, _)) => return Ok(-1),
+                Err(other) => return Err(other),
+            };
+            let inner = process.inner_access();
+            let ptr = data as *mut usize;
+            inner.space().check_user_range(ptr, size_of::<usize>(), true)?;
+            inner.space().copy_value_to_user(ptr, &value)?;
+        }
+        PTRACE_POKEDATA => match target.poke_word(addr, data) {
+            Ok(()) => {}
+            Err(KernelError::UserRangeNotAccessible(_, _)) => return Ok(-1),
+            Err(other) => return Err(other),
+        },
+        PTRACE_CONT => target.resume()?,
+        PTRACE_SINGLESTEP => match target.step() {
+            Ok(()) => {}
+            Err(KernelError::UserRangeNotAccessible(_, _)) => return Ok(-1),
+            Err(other) => return Err(other),
+        },
+        PTRACE_GETREGS => {
+            let regs = target.user_regs()?;
+            let inner = process.inner_access();
+            let ptr = data as *mut UserRegs;
+            inner.space().check_user_range(ptr, size_of::<UserRegs>(), true)?;
+            inner.space().copy_value_to_user(ptr, &regs)?;
+        }
+        PTRACE_SETREGS => {
+            let inner = process.inner_access();
+            let ptr = data as *const UserRegs;
+            inner.space().check_user_range(ptr, size_of::<UserRegs>(), false)?;
+            let regs = inner.space().copy_value_from_user(ptr)?;
+            drop(inner);
+            target.set_user_regs(&regs)?;
+        }
+        _ => return Ok(-1),
+    }
+    Ok(0)
+}
+
 /// Fork a new children process from the parent process,
 /// the children process will also at the very moment after have called the fork;
 /// So it seems very likely the children process and the parent process are both fork a new process
@@ -39,6 +455,11 @@ pub(crate) fn sys_get_pid() -> Result<isize> {
 /// If the return value is 0, it means that the process is the new process;
 /// If the return value is other than 0, it means that the process is the parent process and the return value is the pid of the new process
 ///
+/// - Returns
+///     - the pid of the new process
+///     - -1: the current process would exceed the limit of the child processes,
+///         or the memory cgroup would exceed the frame budget
+///
 /// - Errors
 ///     - ProcessHaveNotTask
 ///     - ForkWithNoRootTask(tid)
@@ -57,7 +478,13 @@ pub(crate) fn sys_get_pid() -> Result<isize> {
 pub(crate) fn sys_fork() -> Result<isize> {
     let current_task = PROCESSOR.current_task()?;
     current_task.forkable()?;
-    let new_process = current_task.fork_process()?;
+    let new_process = match current_task.fork_process() {
+        Ok(process) => process,
+        Err(KernelError::TaskLimitExceeded(_)) | Err(KernelError::CgroupBudgetExceeded(_)) => {
+            return Ok(-1)
+        }
+        Err(other) => return Err(other),
+    };
     let new_process_inner = new_process.inner_access();
     let new_root_task = new_process_inner.root_task();
     let new_task_inner = new_root_task.inner_access();
@@ -79,7 +506,7 @@ pub(crate) fn sys_fork() -> Result<isize> {
 /// In some cases, we should not consider them errors,
 /// but instead we should return an error code for the caller to decide how to proceed with it.
 ///
-/// If the task isn't exists, it will return error code(-1)
+/// If the new program cannot be charged to the memory cgroup, it will return error code(-1)
 /// or this function will always return the count of return back string pointer in user stack(2)
 ///
 /// - Arguments
@@ -89,6 +516,8 @@ pub(crate) fn sys_fork() -> Result<isize> {
 /// - Errors
 ///     - ProcessHaveNotTask
 ///     - VPNNotMapped(vpn)
+///     - UserRangeNotAccessible(va, len)
+///     - UnterminatedString(max_len)
 ///     - FileSystemError
 ///         - InodeMustBeDirectory(bitmap index)
 ///         - DataOutOfBounds
@@ -107,12 +536,16 @@ pub(crate) fn sys_exec(path_ptr: *const u8, args_ptr: *const u8) -> Result<isize
     let process = task.process();
     let process_inner = process.inner_access();
     let current_space = process_inner.space();
-    let path = current_space.translated_string(path_ptr)?;
+    let path = current_space.translated_string_bounded(path_ptr, configs::MAX_PATH_LEN)?;
     if path.is_empty() {
         return Err(KernelError::FileDoesNotExists(path));
     }
     let args = current_space.translated_string(args_ptr)?;
-    let file = ROOT_INODE.find(&path, OpenFlags::READ)?;
+    let cred = process_inner.credential();
+    // the task may be blocked in the file system, the process must not be borrowed
+    drop(process_inner);
+    drop(process);
+    let file = ROOT_INODE.find_inode(&path, OpenFlags::READ, cred)?;
     let data = file.read_all()?;
     debug!(
         "task {}({} bytes) was loaded successfully",
@@ -120,30 +553,114 @@ pub(crate) fn sys_exec(path_ptr: *const u8, args_ptr: *const u8) -> Result<isize
         data.len()
     );
     drop(file);
-    drop(process_inner);
-    drop(process);
-    Ok(task.exec(path, &data, args)? as isize)
+    match task.exec(path, &data, args) {
+        Ok(argc) => Ok(argc as isize),
+        Err(KernelError::CgroupBudgetExceeded(_)) => Ok(-1),
+        Err(other) => Err(other),
+    }
 }
 
 /// Wait children process becomes a zombie process, reclaim all its resources, and collect its return value
 ///
+/// The current task is blocked until the child process exits, unless the `WNOHANG` option is set.
+/// The blocking waiting will be interrupted by the pending signal,
+/// and it will be executed again if the handler of the signal was registered with `SA_RESTART`.
+///
 /// - Arguments
 ///     - pid: the id of the process which we are waiting for
 ///     - exit_code_ptr: The pointer address that represents the return value of the child process,
 ///         the child process needs to write the return value by itself.
 ///         If this address is 0, it means that it does not need to be saved
+///     - options: the options of the waiting, only `WNOHANG` is supported now
 ///
 /// - Returns
-///     - -1: child process does not exist
-///     - -2: child process is still alive
+///     - -1: child process does not exist, the options are unknown or the waiting was interrupted
+///     - -2: child process is still alive and the `WNOHANG` option is set
 ///
 /// - Errors
 ///     - ProcessHaveNotTask
 #[inline(always)]
-pub(crate) fn sys_wait_pid(pid: isize, exit_code_ptr: *mut i32) -> Result<isize> {
-    let task = PROCESSOR.current_task()?;
-    let process = task.process();
-    process.wait_pid(pid, exit_code_ptr)
+pub(crate) fn sys_wait_pid(pid: isize, exit_code_ptr: *mut i32, options: usize) -> Result<isize> {
+    if options & !WNOHANG != 0 {
+        return Ok(-1);
+    }
+    loop {
+        let task = PROCESSOR.current_task()?;
+        let process = task.process();
+        match process.wait_pid(pid, exit_code_ptr)? {
+            -2 if options & WNOHANG == 0 => {
+                drop(process);
+                drop(task);
+                if current_has_pending_signal()? {
+                    return interrupt_current_syscall();
+                }
+                suspend_current_and_run_other_task()?;
+            }
+            result => return Ok(result),
+        }
+    }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task::model::{CurrentTaskGuard, ForkedProcess, INIT_PROC, USER_UID};
+    use frontier_lib::model::signal::Signal;
+
+    #[test_case]
+    fn test_reboot_only_by_privileged_init_process() {
+        // the unknown mode is rejected without resetting the machine
+        let init_task = INIT_PROC.inner_access().root_task();
+        let current = CurrentTaskGuard::enter(init_task);
+        assert_eq!(sys_reboot(usize::MAX).unwrap(), -2);
+        drop(current);
+        let process = ForkedProcess::new();
+        let _current = process.enter();
+        // the forked process is refused even if it is run by the privileged user
+        assert!(process.inner_access().is_privileged());
+        assert_eq!(sys_reboot(usize::MAX).unwrap(), -1);
+        assert_eq!(sys_reboot(ResetMode::Reset as usize).unwrap(), -1);
+        assert_eq!(sys_setuid(USER_UID).unwrap(), 0);
+        assert_eq!(sys_getuid().unwrap(), USER_UID as isize);
+        assert_eq!(sys_reboot(ResetMode::Reset as usize).unwrap(), -1);
+        assert_eq!(sys_setuid(0).unwrap(), -1);
+    }
+
+    #[test_case]
+    fn test_setpgid_only_joins_existing_group() {
+        let process = ForkedProcess::new();
+        let task = process.inner_access().root_task();
+        let _current = process.enter();
+        let pid = process.pid();
+        // the group which does not exist cannot be joined
+        assert_eq!(sys_setpgid(0, usize::MAX).unwrap(), -2);
+        assert_ne!(process.inner_access().pgid(), usize::MAX);
+        // the process can always lead it's own group
+        assert_eq!(sys_setpgid(0, 0).unwrap(), 0);
+        assert_eq!(process.inner_access().pgid(), pid);
+        let child = ForkedProcess::fork(&task);
+        assert_eq!(child.inner_access().pgid(), pid);
+        assert_eq!(sys_setpgid(child.pid(), child.pid()).unwrap(), 0);
+        // the child can go back to the existing group of it's parent
+        assert_eq!(sys_setpgid(child.pid(), pid).unwrap(), 0);
+        assert_eq!(child.inner_access().pgid(), pid);
+        drop(child);
+    }
 
+    #[test_case]
+    fn test_wait_pid_is_interrupted_by_signal() {
+        let process = ForkedProcess::new();
+        let task = process.inner_access().root_task();
+        let _current = process.enter();
+        let child = ForkedProcess::fork(&task);
+        let pid = child.pid() as isize;
+        let exit_code_ptr = process.user_scratch_va() as *mut i32;
+        assert_eq!(sys_wait_pid(pid, exit_code_ptr, WNOHANG << 1).unwrap(), -1);
+        assert_eq!(sys_wait_pid(pid, exit_code_ptr, WNOHANG).unwrap(), -2);
+        // the blocking waiting returns the interrupted result and can be restarted by the handler
+        task.kill(Signal::USR1).unwrap();
+        assert_eq!(sys_wait_pid(pid, exit_code_ptr, 0).unwrap(), -1);
+        task.clear_interrupted_syscall();
+        drop(child);
+    }
+}
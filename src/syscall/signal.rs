@@ -4,47 +4,116 @@
 // self mods
 
 // use other mods
-use frontier_lib::model::signal::{Signal, SignalAction, SignalFlags};
+use alloc::sync::Arc;
+use core::mem::size_of;
+use frontier_lib::model::signal::{Signal, SignalFlags};
 
 // use self mods
+use crate::fs::pidfd::PidFd;
 use crate::prelude::*;
-use crate::task::{PROCESSOR, TASK_SCHEDULER};
+use crate::task::model::ProcessControlBlock;
+use crate::task::signal::{is_rt_signum, SignalStack, UserSignalAction, SA_RESTART};
+use crate::task::{sleep_current_and_run_other_task, PROCESSOR, PROCESS_TABLE};
 
 /// Send a signal to other(but also self) process.
+/// If the pid is negative, the signal will be sent to every process in the process group |pid|.
+/// The standard signals will be collapsed if they are already pending,
+/// but each real-time signal will be queued with the value.
 ///
 /// - Arguments
-///     - pid: the id of the process which we want to send signal
+///     - pid: the id of the process or the negative id of the process group which we want to send signal
 ///     - signum: the value of the signal
+///     - value: the value passed to the handler of the real-time signal, it is ignored by the standard signals
+///
+/// - Returns
+///     - 0: the signal was sent to at least one process
+///     - -1: the signal is duplicated, the signal queue is full or there is no process in the process group
 ///
 /// - Errors
 ///     - ProcessHaveNotTask
 ///     - UnknownSignum(isize)
 #[inline(always)]
-pub(crate) fn sys_kill(pid: isize, signum: usize) -> Result<isize> {
-    let signal: Signal = signum.try_into()?;
-    if let Some(process) = TASK_SCHEDULER.get_process(pid) {
-        debug!("Try to kill process {} with signal {:?}", process.pid(), signal);
-        if let Err(_) = process.kill(signal) {
-            Ok(-1)
-        } else {
+pub(crate) fn sys_kill(pid: isize, signum: usize, value: usize) -> Result<isize> {
+    let signal: Option<Signal> = if is_rt_signum(signum) {
+        None
+    } else {
+        Some(signum.try_into()?)
+    };
+    let send = |process: &Arc<ProcessControlBlock>| match signal {
+        Some(signal) => process.kill(signal).is_ok(),
+        None => process.kill_rt(signum, value).is_ok(),
+    };
+    if pid < 0 {
+        let pgid = pid.unsigned_abs();
+        let mut sent = false;
+        for process in PROCESS_TABLE.group(pgid) {
+            debug!("Try to kill process {} in group {} with signal {}", process.pid(), pgid, signum);
+            sent |= send(&process);
+        }
+        return if sent { Ok(0) } else { Ok(-1) };
+    }
+    if let Some(process) = PROCESS_TABLE.get(pid as usize) {
+        debug!("Try to kill process {} with signal {}", process.pid(), signum);
+        if send(&process) {
             Ok(0)
+        } else {
+            Ok(-1)
         }
     } else {
         Err(KernelError::ProcessHaveNotTask)
     }
 }
 
+/// Send a signal to the task in the current process specifically,
+/// the handler of the signal will run in the task's own trap context.
+/// The signal without custom handler will take the default action of the process.
+///
+/// - Arguments
+///     - tid: the id of the task in the current process
+///     - signum: the value of the signal, the real-time signals are not supported
+///
+/// - Returns
+///     - 0: success
+///     - -1: the task does not exist or has exited, the signal is real-time or duplicated
+///
+/// - Errors
+///     - ProcessHaveNotTask
+///     - UnknownSignum(isize)
+#[inline(always)]
+pub(crate) fn sys_tkill(tid: usize, signum: usize) -> Result<isize> {
+    if is_rt_signum(signum) {
+        return Ok(-1);
+    }
+    let signal: Signal = signum.try_into()?;
+    let process = PROCESSOR.current_task()?.process();
+    let target = match process.inner_access().task(tid) {
+        Some(task) if !task.is_zombie() => task,
+        _ => return Ok(-1),
+    };
+    debug!("Try to kill task {} in process {} with signal {}", tid, process.pid(), signum);
+    if target.kill(signal).is_ok() {
+        Ok(0)
+    } else {
+        Ok(-1)
+    }
+}
+
 /// Registers a user-mode function as a handler for a signal
 /// 
 /// - Arguments
 ///     - signum: the number of the signal
 ///     - new_action: 
-///         the pointer of the [`frontier_lib::model::signal::SignalAction`] immutable reference,
-///         which contains the function user-mode virtual memory address and signal mask settings.
+///         the pointer of the [`UserSignalAction`] immutable reference,
+///         which contains the function user-mode virtual memory address, signal mask settings
+///         and the flags of the action, only `SA_RESTART` is supported now.
 ///     - old_action: 
-///         the pointer of the [`frontier_lib::model::signal::SignalAction`] mutable reference,
+///         the pointer of the [`UserSignalAction`] mutable reference,
 ///         which will be writen the value of previous action.
 /// 
+/// - Returns
+///     - 0: success
+///     - -1: the signal cannot be handled, the action pointers are null or the flags are unknown
+///
 /// - Errors
 ///     - LibError::InvalidSignalNumber(signum)
 ///     - ProcessHaveNotTask
@@ -52,9 +121,12 @@ pub(crate) fn sys_kill(pid: isize, signum: usize) -> Result<isize> {
 #[inline(always)]
 pub(crate) fn sys_sig_action(
     signum: usize,
-    new_action: *const SignalAction,
-    old_action: *mut SignalAction,
+    new_action: *const UserSignalAction,
+    old_action: *mut UserSignalAction,
 ) -> Result<isize> {
+    if is_rt_signum(signum) {
+        return sys_rt_sig_action(signum, new_action, old_action);
+    }
     let signal: Signal = signum.try_into()?;
     let flag: SignalFlags = signal.into();
     if flag.is_empty()
@@ -69,15 +141,100 @@ pub(crate) fn sys_sig_action(
     let process = task.process();
     let mut process_inner = process.inner_exclusive_access();
     let space = process_inner.space();
-    let new_action = space.translated_refmut(new_action)?.clone();
-    let old_action = space.translated_refmut(old_action)?;
+    space.check_user_range(new_action, size_of::<UserSignalAction>(), false)?;
+    space.check_user_range(old_action, size_of::<UserSignalAction>(), true)?;
+    let new_action = space.copy_value_from_user(new_action)?;
+    if new_action.flags & !SA_RESTART != 0 {
+        return Ok(-1);
+    }
+    let previous = UserSignalAction {
+        action: process_inner.get_signal_action(signal),
+        flags: if process_inner.is_signal_restart(signal) { SA_RESTART } else { 0 },
+    };
+    space.copy_value_to_user(old_action, &previous)?;
     let process = task.process();
-    *old_action = process_inner.get_signal_action(signal);
-    process_inner.set_signal_action(signal, new_action);
+    process_inner.set_signal_action(signal, new_action.action);
+    process_inner.set_signal_restart(signal, new_action.flags & SA_RESTART != 0);
     debug!("Set action {:?} in process {} with signal {:?}", new_action, process.pid(), signal);
     Ok(0)
 }
 
+/// Registers a user-mode function as a handler for a real-time signal,
+/// the flags of the action are ignored, see [`sys_sig_action`]
+#[inline(always)]
+fn sys_rt_sig_action(
+    signum: usize,
+    new_action: *const UserSignalAction,
+    old_action: *mut UserSignalAction,
+) -> Result<isize> {
+    if new_action.is_null() || old_action.is_null() {
+        return Ok(-1);
+    }
+    let task = PROCESSOR.current_task()?;
+    let process = task.process();
+    let mut process_inner = process.inner_exclusive_access();
+    let space = process_inner.space();
+    space.check_user_range(new_action, size_of::<UserSignalAction>(), false)?;
+    space.check_user_range(old_action, size_of::<UserSignalAction>(), true)?;
+    let new_action = space.copy_value_from_user(new_action)?;
+    let previous = UserSignalAction {
+        action: process_inner.get_rt_signal_action(signum),
+        flags: 0,
+    };
+    space.copy_value_to_user(old_action, &previous)?;
+    process_inner.set_rt_signal_action(signum, new_action.action);
+    debug!("Set action {:?} in process {} with real-time signal {}", new_action, process.pid(), signum);
+    Ok(0)
+}
+
+/// Set up the alternate stack for the signal handlers, and return the previous alternate stack.
+///
+/// - Arguments
+///     - new_stack: the pointer of the new alternate stack, null means keeping the current one
+///     - old_stack: the pointer which will be written the previous alternate stack, it can be null
+///
+/// - Returns
+///     - 0: success
+///     - -1: the new stack region is not mapped and writable or the onstack flags are invalid
+///
+/// - Errors
+///     - ProcessHaveNotTask
+///     - UserRangeNotAccessible(start_va, end_va)
+///     - VPNNotMapped(vpn)
+#[inline(always)]
+pub(crate) fn sys_sigaltstack(
+    new_stack: *const SignalStack,
+    old_stack: *mut SignalStack,
+) -> Result<isize> {
+    let task = PROCESSOR.current_task()?;
+    let process = task.process();
+    let mut process_inner = process.inner_exclusive_access();
+    let space = process_inner.space();
+    let new_stack = if new_stack.is_null() {
+        None
+    } else {
+        space.check_user_range(new_stack, size_of::<SignalStack>(), false)?;
+        let stack = space.copy_value_from_user(new_stack)?;
+        if SignalFlags::from_bits(stack.onstack).is_none()
+            || (stack.size != 0
+                && space
+                    .check_user_range(stack.base as *const u8, stack.size, true)
+                    .is_err())
+        {
+            return Ok(-1);
+        }
+        Some(stack)
+    };
+    if !old_stack.is_null() {
+        space.check_user_range(old_stack, size_of::<SignalStack>(), true)?;
+        space.copy_value_to_user(old_stack, &process_inner.signal_alt_stack())?;
+    }
+    if let Some(stack) = new_stack {
+        process_inner.exchange_signal_alt_stack(stack);
+    }
+    Ok(0)
+}
+
 /// Set up signal masking for the current task, return previous signal masking.
 /// 
 /// - Arguments
@@ -101,6 +258,57 @@ pub(crate) fn sys_sig_proc_mask(mask: u32) -> Result<isize> {
     }
 }
 
+/// Get the signals which were sent to the current process but blocked by the signal masking
+///
+/// - Arguments
+///     - pending: the pointer of the signal flags which will be written
+///
+/// - Returns
+///     - Ok(0)
+///
+/// - Errors
+///     - ProcessHaveNotTask
+///     - UserRangeNotAccessible(start_va, end_va)
+///     - VPNNotMapped(vpn)
+#[inline(always)]
+pub(crate) fn sys_sig_pending(pending: *mut SignalFlags) -> Result<isize> {
+    let task = PROCESSOR.current_task()?;
+    let process = task.process();
+    let process_inner = process.inner_access();
+    let space = process_inner.space();
+    space.check_user_range(pending, size_of::<SignalFlags>(), true)?;
+    space.copy_value_to_user(pending, &process_inner.blocked_signals())?;
+    Ok(0)
+}
+
+/// Replace the signal masking with a temporary one and block the current task until any signal is pending.
+/// The previous signal masking will be restored after the signal was handled.
+/// Because the kernel will never be preempted, there is no gap between installing the masking and blocking,
+/// the signal sent in the meantime will always wake up the task.
+///
+/// - Arguments
+///     - mask: the bitmap of temporary signal masking
+///
+/// - Returns
+///     - -1: the task was interrupted by a signal or the mask is invalid
+///
+/// - Errors
+///     - ProcessHaveNotTask
+#[inline(always)]
+pub(crate) fn sys_sig_suspend(mask: u32) -> Result<isize> {
+    let mask = match SignalFlags::from_bits(mask) {
+        Some(mask) => mask,
+        None => return Ok(-1),
+    };
+    let process = PROCESSOR.current_task()?.process();
+    process.inner_exclusive_access().suspend_signal_mask(mask);
+    // the process will be woken up by the kill, but it may also be woken up by other events
+    while !process.inner_access().has_pending_signal() {
+        sleep_current_and_run_other_task(usize::MAX)?;
+    }
+    Ok(-1)
+}
+
 /// Make current task return to normal trap context after handling signal
 /// 
 /// - Errors
@@ -108,7 +316,151 @@ pub(crate) fn sys_sig_proc_mask(mask: u32) -> Result<isize> {
 #[inline(always)]
 pub(crate) fn sys_sig_return() -> Result<isize> {
     let task = PROCESSOR.current_task()?;
+    // the signal sent to the task specifically is always the latest one being handled
+    if let Some(signum) = task.signal_return()? {
+        return Ok(signum);
+    }
     let process = task.process();
     let mut process_inner = process.inner_exclusive_access();
     process_inner.signal_return()
 }
+
+/// Open a pidfd which refers to the process, the process must be current process or it's child.
+///
+/// - Arguments
+///     - pid: the id of the process
+///
+/// - Returns
+///     - > -1: the file descriptor of the pidfd
+///     - -1: process does not exist, or the file descriptors are exhausted
+///
+/// - Errors
+///     - ProcessHaveNotTask
+#[inline(always)]
+pub(crate) fn sys_pidfd_open(pid: usize) -> Result<isize> {
+    let task = PROCESSOR.current_task()?;
+    let process = task.process();
+    let mut inner = process.inner_exclusive_access();
+    let target = if process.pid() == pid {
+        Some(Arc::clone(&process))
+    } else {
+        inner.get_child(pid).map(|child| Arc::clone(child))
+    };
+    let target = match target {
+        Some(target) => target,
+        None => return Ok(-1),
+    };
+    match inner.alloc_fd(Arc::new(PidFd::new(&target))) {
+        Ok(fd) => Ok(fd as isize),
+        Err(KernelError::FileDescriptorExhausted) => Ok(-1),
+        Err(other) => Err(other),
+    }
+}
+
+/// Send a signal to the process referred by the pidfd.
+///
+/// - Arguments
+///     - pidfd: the file descriptor of the pidfd
+///     - signum: the value of the signal
+///     - info: reserved for the signal information, it is ignored now
+///
+/// - Returns
+///     - 0: success
+///     - -1: the file descriptor is not a valid pidfd
+///     - -2: the process has exited
+///     - -3: the signal is already pending
+///
+/// - Errors
+///     - ProcessHaveNotTask
+///     - UnknownSignum(isize)
+#[inline(always)]
+pub(crate) fn sys_pidfd_send_signal(pidfd: usize, signum: usize, _info: usize) -> Result<isize> {
+    let signal: Signal = signum.try_into()?;
+    let task = PROCESSOR.current_task()?;
+    let process = task.process();
+    let inner = process.inner_access();
+    let target = inner
+        .get_file(pidfd)
+        .and_then(|file| file.as_any().downcast_ref::<PidFd>())
+        .map(|pidfd| pidfd.process());
+    drop(inner);
+    drop(process);
+    drop(task);
+    match target {
+        None => Ok(-1),
+        Some(None) => Ok(-2),
+        Some(Some(target)) if target.is_zombie() => Ok(-2),
+        Some(Some(target)) => {
+            debug!("Try to kill process {} with signal {:?} by pidfd", target.pid(), signal);
+            if let Err(_) = target.kill(signal) {
+                Ok(-3)
+            } else {
+                Ok(0)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task::model::ForkedProcess;
+    use crate::task::signal::custom_action;
+
+    #[test_case]
+    fn test_sig_action_carries_restart_flag() {
+        let process = ForkedProcess::new();
+        let _current = process.enter();
+        let new_ptr = process.user_scratch_va() as *mut UserSignalAction;
+        let old_ptr = (process.user_scratch_va() + 128) as *mut UserSignalAction;
+        let write = |flags: usize| {
+            let action = UserSignalAction {
+                action: custom_action(0x1000),
+                flags,
+            };
+            process.inner_access().space().copy_value_to_user(new_ptr, &action).unwrap();
+        };
+        let read_old = || process.inner_access().space().copy_value_from_user(old_ptr).unwrap();
+        let signum = Signal::USR1 as usize;
+        write(SA_RESTART);
+        assert_eq!(sys_sig_action(signum, new_ptr, old_ptr).unwrap(), 0);
+        assert_eq!(read_old().flags, 0);
+        assert!(process.inner_access().is_signal_restart(Signal::USR1));
+        // the restart flag of the previous action is given back
+        write(0);
+        assert_eq!(sys_sig_action(signum, new_ptr, old_ptr).unwrap(), 0);
+        assert_eq!(read_old().flags, SA_RESTART);
+        assert_eq!(read_old().action.handler() as usize, 0x1000);
+        assert!(!process.inner_access().is_signal_restart(Signal::USR1));
+        // the unknown flags are rejected without changing the action
+        write(SA_RESTART << 1);
+        assert_eq!(sys_sig_action(signum, new_ptr, old_ptr).unwrap(), -1);
+        assert!(!process.inner_access().is_signal_restart(Signal::USR1));
+    }
+
+    #[test_case]
+    fn test_pidfd_send_signal_to_referred_process() {
+        let process = ForkedProcess::new();
+        let task = process.inner_access().root_task();
+        let _current = process.enter();
+        let child = ForkedProcess::fork(&task);
+        let sibling = ForkedProcess::fork(&task);
+        let pidfd = sys_pidfd_open(child.pid()).unwrap();
+        assert!(pidfd >= 0);
+        let pidfd = pidfd as usize;
+        assert_eq!(sys_pidfd_send_signal(usize::MAX, Signal::USR1 as usize, 0).unwrap(), -1);
+        assert_eq!(sys_pidfd_send_signal(pidfd, Signal::USR1 as usize, 0).unwrap(), 0);
+        assert!(child.inner_access().has_pending_signal());
+        assert!(!sibling.inner_access().has_pending_signal());
+        assert!(!process.inner_access().has_pending_signal());
+        // the exited process cannot be signaled, even if the pid is reused by another process
+        child.mark_zombie(0);
+        assert_eq!(sys_pidfd_send_signal(pidfd, Signal::USR2 as usize, 0).unwrap(), -2);
+        drop(child);
+        let reused = ForkedProcess::fork(&task);
+        assert_eq!(sys_pidfd_send_signal(pidfd, Signal::USR2 as usize, 0).unwrap(), -2);
+        assert!(!reused.inner_access().has_pending_signal());
+        drop(reused);
+        drop(sibling);
+    }
+}
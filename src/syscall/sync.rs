@@ -6,17 +6,21 @@
 // use other mods
 
 use alloc::sync::Arc;
+use core::mem::size_of;
 
 // use self mods
 use crate::prelude::*;
+use crate::sync::futex::FUTEX_TABLE;
+use crate::sync::mutex::MutexKind;
 use crate::task::PROCESSOR;
 
 #[inline(always)]
-pub(crate) fn sys_create_mutex(blocking: bool) -> Result<isize> {
+pub(crate) fn sys_create_mutex(kind: usize) -> Result<isize> {
+    let kind = MutexKind::from_usize(kind)?;
     let task = PROCESSOR.current_task()?;
     let process = task.process();
     let mut inner = process.inner_exclusive_access();
-    Ok(inner.alloc_mutex(blocking)? as isize)
+    Ok(inner.alloc_mutex(kind)? as isize)
 }
 
 #[allow(dead_code)]
@@ -42,10 +46,53 @@ pub(crate) fn sys_lock_mutex(id: usize) -> Result<isize> {
     drop(inner);
     drop(process);
     drop(task);
-    mutex.lock()?;
-    Ok(0)
+    match mutex.lock() {
+        Ok(()) => Ok(0),
+        Err(KernelError::Deadlock) => Ok(-1),
+        Err(other) => Err(other),
+    }
+}
+
+/// Try to acquire the mutex without blocking, current task is never put into the waiting queue.
+///
+/// - Arguments
+///     - id: the id of the mutex
+///
+/// - Returns
+///     - 1: the mutex was acquired
+///     - 0: the mutex is held by any task
+///
+/// - Errors
+///     - ProcessHaveNotTask
+///     - MutexDoesNotExist(id)
+#[inline(always)]
+pub(crate) fn sys_try_lock_mutex(id: usize) -> Result<isize> {
+    let task = PROCESSOR.current_task()?;
+    let process = task.process();
+    let inner = process.inner_access();
+    let mutex = Arc::clone(
+        inner
+            .get_mutex(id)
+            .ok_or(KernelError::MutexDoesNotExist(id))?,
+    );
+    drop(inner);
+    drop(process);
+    drop(task);
+    Ok(mutex.try_lock()? as isize)
 }
 
+/// Release the mutex held by the current task.
+///
+/// - Arguments
+///     - id: the id of the mutex
+///
+/// - Returns
+///     - 0: the mutex was released
+///     - -1: the mutex is held by another task
+///
+/// - Errors
+///     - ProcessHaveNotTask
+///     - MutexDoesNotExist(id)
 #[inline(always)]
 pub(crate) fn sys_unlock_mutex(id: usize) -> Result<isize> {
     let task = PROCESSOR.current_task()?;
@@ -59,8 +106,11 @@ pub(crate) fn sys_unlock_mutex(id: usize) -> Result<isize> {
     drop(inner);
     drop(process);
     drop(task);
-    mutex.unlock()?;
-    Ok(0)
+    match mutex.unlock() {
+        Ok(()) => Ok(0),
+        Err(KernelError::DoubleUnlockMutex) => Ok(-1),
+        Err(other) => Err(other),
+    }
 }
 
 #[inline(always)]
@@ -115,6 +165,39 @@ pub(crate) fn sys_down_semaphore(id: usize) -> Result<isize> {
     Ok(0)
 }
 
+/// Try to take one source of the semaphore without blocking,
+/// current task is never put into the waiting queue.
+///
+/// - Arguments
+///     - id: the id of the semaphore
+///
+/// - Returns
+///     - 0: one source was taken
+///     - -1: there is no source
+///
+/// - Errors
+///     - ProcessHaveNotTask
+///     - SemaphoreDoesNotExist(id)
+#[inline(always)]
+pub(crate) fn sys_try_down_semaphore(id: usize) -> Result<isize> {
+    let task = PROCESSOR.current_task()?;
+    let process = task.process();
+    let inner = process.inner_access();
+    let sp = Arc::clone(
+        inner
+            .get_semaphore(id)
+            .ok_or(KernelError::SemaphoreDoesNotExist(id))?,
+    );
+    drop(inner);
+    drop(process);
+    drop(task);
+    if sp.try_down() {
+        Ok(0)
+    } else {
+        Ok(-1)
+    }
+}
+
 #[inline(always)]
 pub(crate) fn sys_create_condvar() -> Result<isize> {
     let task = PROCESSOR.current_task()?;
@@ -162,6 +245,246 @@ pub(crate) fn sys_wait_condvar(id: usize, mutex_id: usize) -> Result<isize> {
     drop(inner);
     drop(process);
     drop(task);
-    condvar.wait(mutex)?;
-    Ok(0)
+    match condvar.wait(mutex) {
+        Ok(()) => Ok(0),
+        Err(KernelError::Deadlock) => Ok(-1),
+        Err(other) => Err(other),
+    }
+}
+
+/// Block current task until the condition variable was signaled or the timeout elapsed,
+/// the mutex will be released during waiting and re-acquired before returning in both cases.
+///
+/// - Arguments
+///     - id: the id of the condition variable
+///     - mutex_id: the id of the mutex which is held by current task
+///     - us: the timeout in microseconds
+///
+/// - Returns
+///     - 1: the condition variable was signaled
+///     - 0: the timeout elapsed
+///     - -1: re-acquiring the mutex would deadlock
+///
+/// - Errors
+///     - ProcessHaveNotTask
+///     - MutexDoesNotExist(mutex_id)
+///     - CondvarDoesNotExist(id)
+///     - DoubleUnlockMutex
+#[inline(always)]
+pub(crate) fn sys_wait_condvar_timeout(id: usize, mutex_id: usize, us: usize) -> Result<isize> {
+    let task = PROCESSOR.current_task()?;
+    let process = task.process();
+    let inner = process.inner_access();
+    let mutex = Arc::clone(
+        inner
+            .get_mutex(mutex_id)
+            .ok_or(KernelError::MutexDoesNotExist(mutex_id))?,
+    );
+    let condvar = Arc::clone(
+        inner
+            .get_condvar(id)
+            .ok_or(KernelError::CondvarDoesNotExist(id))?,
+    );
+    drop(inner);
+    drop(process);
+    drop(task);
+    match condvar.wait_timeout(mutex, us) {
+        Ok(signaled) => Ok(signaled as isize),
+        Err(KernelError::Deadlock) => Ok(-1),
+        Err(other) => Err(other),
+    }
+}
+
+/// Create a barrier which releases the tasks each time the count of them have arrived.
+///
+/// - Arguments
+///     - count: the count of the tasks in each round
+///
+/// - Returns
+///     - the id of the barrier
+///     - -1: the count is zero
+///
+/// - Errors
+///     - ProcessHaveNotTask
+///     - BarrierExhausted
+#[inline(always)]
+pub(crate) fn sys_create_barrier(count: usize) -> Result<isize> {
+    if count == 0 {
+        return Ok(-1);
+    }
+    let task = PROCESSOR.current_task()?;
+    let process = task.process();
+    let mut inner = process.inner_exclusive_access();
+    Ok(inner.alloc_barrier(count)? as isize)
+}
+
+/// Block current task until all the tasks of the current round have arrived at the barrier.
+///
+/// - Arguments
+///     - id: the id of the barrier
+///
+/// - Returns
+///     - 1: current task is the last arrival which released the others
+///     - 0: current task was released by the last arrival
+///
+/// - Errors
+///     - ProcessHaveNotTask
+///     - BarrierDoesNotExist(id)
+#[inline(always)]
+pub(crate) fn sys_barrier_wait(id: usize) -> Result<isize> {
+    let task = PROCESSOR.current_task()?;
+    let process = task.process();
+    let inner = process.inner_access();
+    let barrier = Arc::clone(
+        inner
+            .get_barrier(id)
+            .ok_or(KernelError::BarrierDoesNotExist(id))?,
+    );
+    drop(inner);
+    drop(process);
+    drop(task);
+    Ok(barrier.wait()? as isize)
+}
+
+/// Check the futex word is aligned, the unaligned word may cross the page boundary
+/// and it cannot be accessed atomically.
+///
+/// - Arguments
+///     - uaddr: the virtual address of the futex word in the user space
+fn is_futex_aligned(uaddr: *const u32) -> bool {
+    uaddr as usize % size_of::<u32>() == 0
+}
+
+/// Block current task until other task wake up the futex word,
+/// if the value of the futex word is not the expected one, return immediately.
+///
+/// - Arguments
+///     - uaddr: the virtual address of the futex word in the user space
+///     - val: the expected value of the futex word
+///
+/// - Returns
+///     - 0: current task was woken up
+///     - -1: the value of the futex word is not the expected one
+///     - -2: the futex word is not aligned
+///
+/// - Errors
+///     - ProcessHaveNotTask
+///     - VPNNotMapped(vpn)
+#[inline(always)]
+pub(crate) fn sys_futex_wait(uaddr: *const u32, val: u32) -> Result<isize> {
+    if !is_futex_aligned(uaddr) {
+        return Ok(-2);
+    }
+    let task = PROCESSOR.current_task()?;
+    let process = task.process();
+    let inner = process.inner_access();
+    let space = inner.space();
+    space.check_user_range(uaddr, size_of::<u32>(), false)?;
+    let pa = space
+        .translate_pa(uaddr as usize)
+        .ok_or(KernelError::VPNNotMapped(uaddr as usize))?;
+    let word = space.translated_refmut(uaddr)? as *const u32;
+    drop(inner);
+    drop(process);
+    drop(task);
+    FUTEX_TABLE.wait(pa, word, val)
+}
+
+/// Wake up the tasks which are waiting on the futex word.
+///
+/// - Arguments
+///     - uaddr: the virtual address of the futex word in the user space
+///     - count: the max count of the tasks will be woken up
+///
+/// - Returns
+///     - the count of the tasks were woken up
+///     - -1: the futex word is not aligned
+///
+/// - Errors
+///     - ProcessHaveNotTask
+///     - VPNNotMapped(vpn)
+#[inline(always)]
+pub(crate) fn sys_futex_wake(uaddr: *const u32, count: usize) -> Result<isize> {
+    if !is_futex_aligned(uaddr) {
+        return Ok(-1);
+    }
+    let task = PROCESSOR.current_task()?;
+    let process = task.process();
+    let inner = process.inner_access();
+    inner.space().check_user_range(uaddr, size_of::<u32>(), false)?;
+    let pa = inner
+        .space()
+        .translate_pa(uaddr as usize)
+        .ok_or(KernelError::VPNNotMapped(uaddr as usize))?;
+    drop(inner);
+    drop(process);
+    drop(task);
+    Ok(FUTEX_TABLE.wake(pa, count) as isize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::configs;
+    use crate::task::model::{CurrentTaskGuard, ForkedProcess};
+
+    #[test_case]
+    fn test_futex_rejects_unaligned_word() {
+        assert!(is_futex_aligned(0x1000 as *const u32));
+        assert!(!is_futex_aligned(0x1001 as *const u32));
+        // the unaligned word is rejected before it is translated
+        assert_eq!(sys_futex_wait(0x1001 as *const u32, 0).unwrap(), -2);
+        assert_eq!(sys_futex_wake(0x1002 as *const u32, 1).unwrap(), -1);
+    }
+
+    #[test_case]
+    fn test_unlock_mutex_by_other_task_fails_softly() {
+        let process = ForkedProcess::new();
+        let thread = process
+            .alloc_task(
+                0,
+                &[],
+                configs::KERNEL_TASK_STACK_BYTE_SIZE,
+                configs::USER_TASK_STACK_BYTE_SIZE,
+            )
+            .unwrap();
+        let current = process.enter();
+        let id = sys_create_mutex(3).unwrap() as usize;
+        assert_eq!(sys_lock_mutex(id).unwrap(), 0);
+        // the task which does not hold the mutex is not killed by the mistaken unlocking
+        let thread_current = CurrentTaskGuard::enter(Arc::clone(&thread));
+        assert_eq!(sys_unlock_mutex(id).unwrap(), -1);
+        assert_eq!(sys_try_lock_mutex(id).unwrap(), 0);
+        drop(thread_current);
+        assert_eq!(sys_unlock_mutex(id).unwrap(), 0);
+        drop(current);
+        thread.mark_zombie(0);
+    }
+
+    #[test_case]
+    fn test_try_down_semaphore_contended() {
+        let process = ForkedProcess::new();
+        let thread = process
+            .alloc_task(
+                0,
+                &[],
+                configs::KERNEL_TASK_STACK_BYTE_SIZE,
+                configs::USER_TASK_STACK_BYTE_SIZE,
+            )
+            .unwrap();
+        let current = process.enter();
+        let id = sys_create_semaphore(true, 1).unwrap() as usize;
+        assert_eq!(sys_try_down_semaphore(id).unwrap(), 0);
+        // the other task neither takes the source nor blocks while the source is taken
+        let thread_current = CurrentTaskGuard::enter(Arc::clone(&thread));
+        assert_eq!(sys_try_down_semaphore(id).unwrap(), -1);
+        assert!(!thread.is_blocked());
+        assert!(PROCESSOR.try_current_task().is_some_and(|task| Arc::ptr_eq(&task, &thread)));
+        drop(thread_current);
+        assert_eq!(sys_up_semaphore(id).unwrap(), 0);
+        assert_eq!(sys_try_down_semaphore(id).unwrap(), 0);
+        assert!(sys_try_down_semaphore(usize::MAX).is_err_and(|e| e.is_semaphoredoesnotexist()));
+        drop(current);
+        thread.mark_zombie(0);
+    }
 }
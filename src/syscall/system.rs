@@ -0,0 +1,219 @@
+// @author:    olinex
+// @time:      2024/08/20
+
+// self mods
+
+// use other mods
+use alloc::vec;
+use core::mem::size_of;
+use log::LevelFilter;
+
+// use self mods
+use crate::configs::{self, LOG_RING_BUFFER_BYTE_SIZE, MAX_PATH_LEN};
+use crate::drivers::char::fill_random;
+use crate::lang::logger;
+use crate::prelude::*;
+use crate::task::PROCESSOR;
+
+cfg_if! {
+    if #[cfg(target_arch = "riscv64")] {
+        const MACHINE: &'static str = "riscv64";
+    } else if #[cfg(target_arch = "riscv32")] {
+        const MACHINE: &'static str = "riscv32";
+    } else {
+        compile_error!("Unknown target_arch to identify the machine");
+    }
+}
+
+/// The identity of the kernel and the architecture shared with the user space,
+/// each field is a fixed-size string padded with NULL
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub(crate) struct UtsName {
+    pub(crate) sysname: [u8; configs::UTS_NAME_FIELD_BYTE_SIZE],
+    pub(crate) release: [u8; configs::UTS_NAME_FIELD_BYTE_SIZE],
+    pub(crate) machine: [u8; configs::UTS_NAME_FIELD_BYTE_SIZE],
+}
+impl UtsName {
+    /// Convert the string into the fixed-size field, the string will be truncated to keep the NULL terminator
+    ///
+    /// - Arguments
+    ///     - value: the string of the field
+    fn field(value: &str) -> [u8; configs::UTS_NAME_FIELD_BYTE_SIZE] {
+        let mut bytes = [0; configs::UTS_NAME_FIELD_BYTE_SIZE];
+        let length = value.len().min(configs::UTS_NAME_FIELD_BYTE_SIZE - 1);
+        bytes[..length].copy_from_slice(&value.as_bytes()[..length]);
+        bytes
+    }
+
+    /// Create the identity of the current kernel
+    pub(crate) fn current() -> Self {
+        Self {
+            sysname: Self::field(configs::KERNEL_NAME),
+            release: Self::field(configs::KERNEL_RELEASE),
+            machine: Self::field(MACHINE),
+        }
+    }
+}
+
+/// Get the identity of the kernel and the architecture.
+///
+/// - Arguments
+///     - name: the pointer of the identity structure which will be written
+///
+/// - Returns
+///     - 0: success
+///
+/// - Errors
+///     - ProcessHaveNotTask
+///     - VPNNotMapped(vpn)
+///     - UserRangeNotAccessible(va, len)
+#[inline(always)]
+pub(crate) fn sys_uname(name: *mut UtsName) -> Result<isize> {
+    let task = PROCESSOR.current_task()?;
+    let process = task.process();
+    let inner = process.inner_access();
+    inner.space().check_user_range(name, size_of::<UtsName>(), true)?;
+    inner.space().copy_value_to_user(name, &UtsName::current())?;
+    Ok(0)
+}
+
+/// Set the log level of the target at runtime.
+///
+/// - Arguments
+///     - target_ptr: the pointer of the target string, which is the module path without the crate name,
+///       such as `memory::space`, the empty target means the default level of all targets
+///     - level: the new level
+///         - 0: off
+///         - 1: error
+///         - 2: warn
+///         - 3: info
+///         - 4: debug
+///         - 5: trace
+///
+/// - Returns
+///     - 0: success
+///     - -1: the target string is unterminated or the level is unknown
+///     - -2: the current process is not privileged
+///
+/// - Errors
+///     - ProcessHaveNotTask
+///     - VPNNotMapped(vpn)
+#[inline(always)]
+pub(crate) fn sys_set_log_level(target_ptr: *const u8, level: usize) -> Result<isize> {
+    let level = match LevelFilter::iter().nth(level) {
+        Some(level) => level,
+        None => return Ok(-1),
+    };
+    let task = PROCESSOR.current_task()?;
+    let process = task.process();
+    let inner = process.inner_access();
+    if !inner.is_privileged() {
+        return Ok(-2);
+    }
+    let target = match inner
+        .space()
+        .translated_string_bounded(target_ptr, MAX_PATH_LEN)
+    {
+        Ok(target) => target,
+        Err(KernelError::UnterminatedString(_)) => return Ok(-1),
+        Err(other) => return Err(other),
+    };
+    logger::set_level(&target, level);
+    Ok(0)
+}
+
+/// Read and consume the recent kernel log lines, it will never block.
+///
+/// - Arguments
+///     - buffer_ptr: the pointer of the buffer to fill in
+///     - len: the byte length of the buffer
+///     - dropped_ptr: the pointer to write the count of the bytes which were dropped because of the overflow
+///       since the last reading, it will be ignored if it is null
+///
+/// - Returns
+///     - the count of the bytes read
+///
+/// - Errors
+///     - ProcessHaveNotTask
+///     - VPNNotMapped(vpn)
+///     - UserRangeNotAccessible(va, len)
+#[inline(always)]
+pub(crate) fn sys_dmesg(buffer_ptr: *mut u8, len: usize, dropped_ptr: *mut usize) -> Result<isize> {
+    let task = PROCESSOR.current_task()?;
+    let process = task.process();
+    let inner = process.inner_access();
+    let space = inner.space();
+    let len = len.min(LOG_RING_BUFFER_BYTE_SIZE);
+    space.check_user_range(buffer_ptr, len, true)?;
+    if !dropped_ptr.is_null() {
+        space.check_user_range(dropped_ptr, size_of::<usize>(), true)?;
+    }
+    let mut buffer = vec![0u8; len];
+    let (count, dropped) = logger::read_ring(&mut buffer);
+    space.copy_to_user(buffer_ptr, &buffer[..count])?;
+    if !dropped_ptr.is_null() {
+        space.copy_value_to_user(dropped_ptr, &dropped)?;
+    }
+    Ok(count as isize)
+}
+
+/// Fill the buffer with random bytes, it will never block.
+///
+/// - Arguments
+///     - buffer_ptr: the pointer of the buffer to fill in
+///     - len: the byte length of the buffer
+///
+/// - Returns
+///     - the count of the bytes filled, which is always the whole length
+///
+/// - Errors
+///     - ProcessHaveNotTask
+///     - VPNNotMapped(vpn)
+///     - UserRangeNotAccessible(va, len)
+#[inline(always)]
+pub(crate) fn sys_getrandom(buffer_ptr: *mut u8, len: usize) -> Result<isize> {
+    let task = PROCESSOR.current_task()?;
+    let process = task.process();
+    let inner = process.inner_access();
+    let space = inner.space();
+    space.check_user_range(buffer_ptr, len, true)?;
+    let buffers = space.translated_byte_buffers(buffer_ptr, len)?;
+    for slice in buffers.into_slices() {
+        fill_random(slice);
+    }
+    Ok(len as isize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::cgroup::CGROUP_TABLE;
+    use crate::syscall::memory::sys_cgroup_attach;
+    use crate::task::model::{ForkedProcess, USER_UID};
+
+    #[test_case]
+    fn test_uts_name_null_padded() {
+        let name = UtsName::current();
+        assert_eq!(&name.sysname[..configs::KERNEL_NAME.len()], configs::KERNEL_NAME.as_bytes());
+        assert!(name.sysname[configs::KERNEL_NAME.len()..].iter().all(|byte| *byte == 0));
+        assert_eq!(&name.machine[..MACHINE.len()], MACHINE.as_bytes());
+        let long = UtsName::field(&"x".repeat(configs::UTS_NAME_FIELD_BYTE_SIZE * 2));
+        assert_eq!(long[configs::UTS_NAME_FIELD_BYTE_SIZE - 1], 0);
+    }
+
+    #[test_case]
+    fn test_unprivileged_admin_calls_denied() {
+        let process = ForkedProcess::new();
+        let _current = process.enter();
+        assert!(process.inner_exclusive_access().set_uid(USER_UID));
+        let target = process.user_scratch_va() as *mut u8;
+        process.inner_access().space().copy_to_user(target, b"\0").unwrap();
+        assert_eq!(sys_set_log_level(target, 0).unwrap(), -2);
+        let joined = || process.inner_access().space().cgroup().map(|cgroup| cgroup.id());
+        let before = joined();
+        let id = CGROUP_TABLE.create(usize::MAX).unwrap();
+        assert_eq!(sys_cgroup_attach(id, 0).unwrap(), -2);
+        assert_eq!(joined(), before);
+    }
+}
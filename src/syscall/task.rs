@@ -4,10 +4,17 @@
 // self mods
 
 // use other mods
+use alloc::sync::Arc;
+use core::mem::size_of;
 
 // use self mods
+use crate::configs;
 use crate::prelude::*;
-use crate::task::{suspend_current_and_run_other_task, PROCESSOR, TASK_SCHEDULER};
+use crate::task::clone::{CloneArgs, CloneFlags, ThreadAttr};
+use crate::task::{
+    join_current_and_run_other_task, lower_current_and_run_other_task,
+    suspend_current_and_run_other_task, SchedStats, PROCESSOR, TASK_SCHEDULER,
+};
 
 /// Yield to other task, current task will be suspended
 ///
@@ -19,14 +26,90 @@ pub(crate) fn sys_yield() -> Result<isize> {
     Ok(0)
 }
 
+/// Yield to other task politely, such as in the spin-wait loops.
+/// Current task will be suspended and run after the other tasks in it's level for one round,
+/// it's level is not changed, so the lowered priority is restored automatically.
+///
+/// - Errors
+///     - ProcessHaveNotTask
+#[inline(always)]
+pub(crate) fn sys_sched_yield_with_hint() -> Result<isize> {
+    lower_current_and_run_other_task()?;
+    Ok(0)
+}
+
+/// Yield to the specified task in the current process, which will be run next instead of the normal pick.
+/// If the target task is not ready or the count of consecutive directed yields reached the limit,
+/// it will fall back to a normal yield.
+///
+/// - Arguments
+///     - tid: the id of the target task in the current process
+///
+/// - Returns
+///     - 0: the target task was run next
+///     - 1: fall back to a normal yield
+///
+/// - Errors
+///     - ProcessHaveNotTask
+#[inline(always)]
+pub(crate) fn sys_yield_to(tid: usize) -> Result<isize> {
+    let current_task = PROCESSOR.current_task()?;
+    let target = current_task.process().inner_access().task(tid);
+    let directed = match target {
+        Some(target) if !Arc::ptr_eq(&target, &current_task) && target.status().is_ready() => {
+            TASK_SCHEDULER.fetch_specific(&target)
+        }
+        _ => false,
+    };
+    drop(current_task);
+    suspend_current_and_run_other_task()?;
+    Ok(if directed { 0 } else { 1 })
+}
+
+/// Get the statistics of the scheduler
+///
+/// - Arguments
+///     - stats: the pointer of the statistics structure which will be written
+///
+/// - Returns
+///     - Ok(0)
+///
+/// - Errors
+///     - ProcessHaveNotTask
+///     - UserRangeNotAccessible(start_va, end_va)
+///     - VPNNotMapped(vpn)
+#[inline(always)]
+pub(crate) fn sys_sched_stats(stats: *mut SchedStats) -> Result<isize> {
+    let task = PROCESSOR.current_task()?;
+    // collect the statistics before borrowing the process, it will walk through all processes
+    let current_stats = TASK_SCHEDULER.stats();
+    let process = task.process();
+    let inner = process.inner_access();
+    inner.space().check_user_range(stats, size_of::<SchedStats>(), true)?;
+    inner.space().copy_value_to_user(stats, &current_stats)?;
+    Ok(0)
+}
+
 /// Create a new thread in the current task's process
 /// 
 /// - Arguments
 ///     - entry_point: the virtual address of entry point in user space 
 ///     - arg: argument pass from user mode which will be store in a10 register
+///     - kernel_stack_size: the byte size of the new thread's kernel stack, zero means the default size
+///     - attr_ptr: the pointer of the [`crate::task::clone::ThreadAttr`] in user space,
+///         which contains the user stack size and the extra arguments, null means using the defaults
+///
+/// - Returns
+///     - the id of the new thread
+///     - -1: the kernel stack size or the user stack size is greater than the max size,
+///         or the user stack of the new thread cannot be placed below the trap context area,
+///         or the process would exceed the limit of the tasks or the address space,
+///         or the memory cgroup would exceed the frame budget
 /// 
 /// - Errors
 ///     - ProcessHaveNotTask
+///     - VPNNotMapped(vpn)
+///     - UserRangeNotAccessible(va, len)
 ///     - ForkWithNoRootTask(tid)
 ///     - AreaAllocFailed(start_vpn, end_vpn)
 ///     - VPNOutOfArea(vpn, start_vpn, end_vpn)
@@ -37,11 +120,50 @@ pub(crate) fn sys_yield() -> Result<isize> {
 ///     - PPNAlreadyMapped(ppn)
 ///     - PPNNotMapped(ppn)
 #[inline(always)]
-pub(crate) fn sys_thread_create(entry_point: usize, arg: usize) -> Result<isize> {
+pub(crate) fn sys_thread_create(
+    entry_point: usize,
+    arg: usize,
+    kernel_stack_size: usize,
+    attr_ptr: *const ThreadAttr,
+) -> Result<isize> {
     let current_task = PROCESSOR.current_task()?;
     current_task.forkable()?;
     let process = current_task.process();
-    let new_task = process.alloc_task(entry_point, Some(arg))?;
+    let kernel_stack_size = if kernel_stack_size == 0 {
+        configs::KERNEL_TASK_STACK_BYTE_SIZE
+    } else {
+        kernel_stack_size
+    };
+    let mut attr = ThreadAttr::default();
+    if !attr_ptr.is_null() {
+        let process_inner = process.inner_access();
+        let space = process_inner.space();
+        space.check_user_range(attr_ptr, size_of::<ThreadAttr>(), false)?;
+        let bytes = unsafe {
+            core::slice::from_raw_parts_mut(
+                &mut attr as *mut ThreadAttr as *mut u8,
+                size_of::<ThreadAttr>(),
+            )
+        };
+        space.copy_from_user(bytes, attr_ptr as *const u8)?;
+    }
+    let args = [
+        arg,
+        attr.args[0] as usize,
+        attr.args[1] as usize,
+        attr.args[2] as usize,
+    ];
+    let new_task =
+        match process.alloc_task(entry_point, &args, kernel_stack_size, attr.stack_size()) {
+            Ok(task) => task,
+            Err(KernelError::InvalidKernelStackSize(_))
+            | Err(KernelError::InvalidUserStackSize(_))
+            | Err(KernelError::UserStackOutOfLayout(_))
+            | Err(KernelError::TaskLimitExceeded(_))
+            | Err(KernelError::SpaceLimitExceeded(_))
+            | Err(KernelError::CgroupBudgetExceeded(_)) => return Ok(-1),
+            Err(other) => return Err(other),
+        };
     let tid = new_task.tid();
     TASK_SCHEDULER.put_read_task(new_task);
     debug!(
@@ -51,6 +173,31 @@ pub(crate) fn sys_thread_create(entry_point: usize, arg: usize) -> Result<isize>
     Ok(tid as isize)
 }
 
+/// Set the thread pointer (tp) of the current task, which usually points to the thread local storage.
+/// The value will be restored to the register when the current task returns to the user mode.
+///
+/// - Arguments
+///     - ptr: the new value of the thread pointer
+///
+/// - Returns
+///     - 0: success
+///
+/// - Errors
+///     - ProcessHaveNotTask
+///     - AreaNotExists(start_vpn, end_vpn)
+#[inline(always)]
+pub(crate) fn sys_set_tls(ptr: usize) -> Result<isize> {
+    let current_task = PROCESSOR.current_task()?;
+    let process = current_task.process();
+    let process_inner = process.inner_access();
+    current_task
+        .inner_access()
+        .modify_trap_ctx(process_inner.space(), |trap_ctx| {
+            trap_ctx.set_tp(ptr);
+            Ok(0)
+        })
+}
+
 /// Get current task's unique id
 /// 
 /// - Errors
@@ -79,3 +226,251 @@ pub(crate) fn sys_wait_tid(tid: isize, exit_code_ptr: *mut i32) -> Result<isize>
     let current_task = PROCESSOR.current_task()?;
     current_task.wait_tid(tid, exit_code_ptr)
 }
+
+/// Block the current task until the thread in the same process exits, and collect its exit code.
+/// If the thread has already exited, it will return immediately.
+///
+/// - Arguments
+///     - tid: the id of the thread which we are joining
+///     - exit_code_ptr: The pointer address that represents the return value of the joined thread.
+///         If this address is 0, it means that it does not need to be saved
+///
+/// - Returns
+///     - the id of the joined thread
+///     - -1: the thread does not exist
+///     - -3: the thread is the current thread, or it is joining the current thread directly or indirectly
+///
+/// - Errors
+///     - ProcessHaveNotTask
+#[inline(always)]
+pub(crate) fn sys_thread_join(tid: usize, exit_code_ptr: *mut i32) -> Result<isize> {
+    if (tid as isize) < 0 {
+        return Ok(-1);
+    }
+    loop {
+        let current_task = PROCESSOR.current_task()?;
+        match current_task.wait_tid(tid as isize, exit_code_ptr)? {
+            -2 => {
+                let target = current_task.process().inner_access().task(tid);
+                let target = match target {
+                    Some(target) => target,
+                    None => return Ok(-1),
+                };
+                match current_task.check_join(&target) {
+                    Ok(()) => {}
+                    Err(KernelError::JoinDeadlock(_)) => return Ok(-3),
+                    Err(other) => return Err(other),
+                }
+                drop(current_task);
+                join_current_and_run_other_task(&target)?;
+            }
+            result => return Ok(result),
+        }
+    }
+}
+
+/// Clone a new thread or a new process according to the arguments structure in user space.
+/// The new task will continue running at the very moment after the syscall and get 0 as return value.
+///
+/// - Arguments
+///     - args_ptr: the pointer of the [`crate::task::clone::CloneArgs`] in user space
+///     - size: the byte size of the arguments structure in user space
+///
+/// - Returns
+///     - > 0: the id of the new thread or the new process
+///     - -1: the arguments are invalid or the exit signal is not zero,
+///         or there is no room for the user stack of the new thread,
+///         or the process would exceed the limit of the tasks or the address space,
+///         or the memory cgroup would exceed the frame budget
+///
+/// - Errors
+///     - ProcessHaveNotTask
+///     - ForkWithNoRootTask(tid)
+///     - IdExhausted
+///     - AreaAllocFailed(start_vpn, end_vpn)
+///     - VPNOutOfArea(vpn, start_vpn, end_vpn)
+///     - VPNAlreadyMapped(vpn)
+///     - InvaidPageTablePerm(flags)
+///     - FrameExhausted
+///     - AllocFullPageMapper(ppn)
+///     - PPNAlreadyMapped(ppn)
+///     - PPNNotMapped(ppn)
+///     - AreaNotExists(start_vpn, end_vpn)
+///     - VPNNotMapped(vpn)
+#[inline(always)]
+pub(crate) fn sys_clone3(args_ptr: *const u8, size: usize) -> Result<isize> {
+    let current_task = PROCESSOR.current_task()?;
+    let process = current_task.process();
+    let process_inner = process.inner_access();
+    let space = process_inner.space();
+    space.check_user_range(args_ptr, size, false)?;
+    let args = match CloneArgs::from_buffers(space.translated_byte_buffers(args_ptr, size)?) {
+        Some(args) => args,
+        None => return Ok(-1),
+    };
+    let flags = args.flags();
+    // validate all of the user pointers before creating anything
+    if args.stack != 0
+        && (args.stack_size == 0
+            || args.stack.checked_add(args.stack_size).is_none()
+            || space
+                .check_user_range(args.stack as *const u8, args.stack_size as usize, true)
+                .is_err())
+    {
+        return Ok(-1);
+    }
+    let is_invalid_tid_ptr = |ptr: u64| {
+        space
+            .check_user_range(ptr as *const u32, size_of::<u32>(), true)
+            .is_err()
+    };
+    if (flags.contains(CloneFlags::SETTID) && is_invalid_tid_ptr(args.set_tid))
+        || (flags.contains(CloneFlags::CLEARTID)
+            && (!flags.contains(CloneFlags::THREAD) || is_invalid_tid_ptr(args.clear_tid)))
+    {
+        return Ok(-1);
+    }
+    // the parent is never notified by the exit signal now, so only the default zero is accepted
+    if args.exit_signal != 0 {
+        return Ok(-1);
+    }
+    let parent_trap_ctx = current_task
+        .inner_access()
+        .modify_trap_ctx(space, |trap_ctx| Ok(*trap_ctx))?;
+    drop(process_inner);
+    // the new task will be created and the trap context will be copied from current task
+    let (new_task, id) = if flags.contains(CloneFlags::THREAD) {
+        let new_task = match process.alloc_task(
+            parent_trap_ctx.sepc,
+            &[],
+            current_task.kernel_stack_size(),
+            configs::USER_TASK_STACK_BYTE_SIZE,
+        ) {
+            Ok(task) => task,
+            Err(KernelError::UserStackOutOfLayout(_))
+            | Err(KernelError::TaskLimitExceeded(_))
+            | Err(KernelError::SpaceLimitExceeded(_))
+            | Err(KernelError::CgroupBudgetExceeded(_)) => return Ok(-1),
+            Err(other) => return Err(other),
+        };
+        let tid = new_task.tid();
+        let process_inner = process.inner_access();
+        new_task
+            .inner_access()
+            .modify_trap_ctx(process_inner.space(), |trap_ctx| {
+                // each thread have their own kernel stack and default user stack
+                let kernel_sp_va = trap_ctx.kernel_sp_va;
+                let user_sp_va = trap_ctx.x[2];
+                *trap_ctx = parent_trap_ctx;
+                trap_ctx.kernel_sp_va = kernel_sp_va;
+                trap_ctx.set_sp(user_sp_va);
+                Ok(())
+            })?;
+        drop(process_inner);
+        if flags.contains(CloneFlags::CLEARTID) {
+            new_task
+                .inner_exclusive_access()
+                .set_clear_tid(args.clear_tid as usize);
+        }
+        (new_task, tid)
+    } else {
+        current_task.forkable()?;
+        let new_process = match current_task.fork_process() {
+            Ok(process) => process,
+            Err(KernelError::TaskLimitExceeded(_)) | Err(KernelError::CgroupBudgetExceeded(_)) => {
+                return Ok(-1)
+            }
+            Err(other) => return Err(other),
+        };
+        let new_root_task = new_process.inner_access().root_task();
+        (new_root_task, new_process.pid())
+    };
+    let new_process = new_task.process();
+    let new_process_inner = new_process.inner_access();
+    new_task
+        .inner_access()
+        .modify_trap_ctx(new_process_inner.space(), |trap_ctx| {
+            // for the new task, clone returns 0
+            trap_ctx.set_arg(0, 0);
+            if let Some(stack_top) = args.stack_top() {
+                trap_ctx.set_sp(stack_top);
+            }
+            if flags.contains(CloneFlags::SETTLS) {
+                trap_ctx.set_tp(args.tls as usize);
+            }
+            Ok(())
+        })?;
+    drop(new_process_inner);
+    drop(new_process);
+    if flags.contains(CloneFlags::SETTID) {
+        let process_inner = process.inner_access();
+        process_inner
+            .space()
+            .copy_value_to_user(args.set_tid as *mut u32, &(id as u32))?;
+    }
+    debug!("clone a new task {} with flags: {:?}", id, flags);
+    TASK_SCHEDULER.put_read_task(new_task);
+    Ok(id as isize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task::model::ForkedProcess;
+
+    #[test_case]
+    fn test_thread_join_exited_thread() {
+        let process = ForkedProcess::new();
+        let task = process.inner_access().root_task();
+        let _current = process.enter();
+        let exit_code_ptr = process.user_scratch_va() as *mut i32;
+        let thread = process
+            .alloc_task(
+                0,
+                &[],
+                configs::KERNEL_TASK_STACK_BYTE_SIZE,
+                configs::USER_TASK_STACK_BYTE_SIZE,
+            )
+            .unwrap();
+        let tid = thread.tid();
+        thread.mark_zombie(3);
+        drop(thread);
+        // the exited thread is joined immediately and reaped
+        assert_eq!(sys_thread_join(tid, exit_code_ptr).unwrap(), tid as isize);
+        let exit_code = process.inner_access().space().copy_value_from_user(exit_code_ptr);
+        assert_eq!(exit_code.unwrap(), 3);
+        assert!(process.inner_access().task(tid).is_none());
+        assert_eq!(sys_thread_join(tid, exit_code_ptr).unwrap(), -1);
+    }
+
+    #[test_case]
+    fn test_thread_join_running_thread() {
+        let process = ForkedProcess::new();
+        let task = process.inner_access().root_task();
+        let _current = process.enter();
+        let exit_code_ptr = process.user_scratch_va() as *mut i32;
+        let thread = process
+            .alloc_task(
+                0,
+                &[],
+                configs::KERNEL_TASK_STACK_BYTE_SIZE,
+                configs::USER_TASK_STACK_BYTE_SIZE,
+            )
+            .unwrap();
+        let tid = thread.tid();
+        assert_eq!(task.wait_tid(tid as isize, exit_code_ptr).unwrap(), -2);
+        assert!(task.check_join(&thread).is_ok());
+        // simulate the root task blocking in the join until the thread exits
+        thread.add_joiner(&task);
+        task.mark_blocked();
+        thread.mark_zombie(5);
+        drop(thread);
+        assert!(!task.is_blocked());
+        assert!(TASK_SCHEDULER.fetch_specific(&task));
+        assert!(Arc::ptr_eq(&TASK_SCHEDULER.pop_ready_task().unwrap(), &task));
+        // the woken root task collects the exit code of the thread
+        assert_eq!(sys_thread_join(tid, exit_code_ptr).unwrap(), tid as isize);
+        let exit_code = process.inner_access().space().copy_value_from_user(exit_code_ptr);
+        assert_eq!(exit_code.unwrap(), 5);
+    }
+}
@@ -4,21 +4,224 @@
 // self mods
 
 // use other mods
+use core::mem::size_of;
 
 // use self mods
-use crate::lang::timer;
+use crate::lang::timer::{self, TimeVal, Times};
 use crate::prelude::*;
-use crate::task::sleep_current_and_run_other_task;
+use crate::task::{
+    current_has_deliverable_signal, interrupt_current_syscall, sleep_current_and_run_other_task,
+    PROCESSOR, TASK_SCHEDULER,
+};
 
 /// Get the current timer value as microseconds,
 /// which is the time duration from the moment when cpu reset to the current moment
 #[inline(always)]
 pub(crate) fn sys_get_time() -> Result<isize> {
-    Ok(timer::get_timer_us() as isize)
+    Ok(timer::now_us() as isize)
+}
+
+/// Get the microseconds elapsed since the kernel was booted,
+/// the rolling over of the timer counter will not make it go back.
+#[inline(always)]
+pub(crate) fn sys_uptime() -> Result<isize> {
+    Ok(timer::uptime_us() as isize)
+}
+
+/// Get the realtime clock, which is the uptime plus the moment set by [`sys_settimeofday`].
+///
+/// - Arguments
+///     - tv: the pointer of the time value which will be written
+///
+/// - Returns
+///     - Ok(0)
+///
+/// - Errors
+///     - ProcessHaveNotTask
+///     - VPNNotMapped(vpn)
+///     - UserRangeNotAccessible(va, len)
+#[inline(always)]
+pub(crate) fn sys_gettimeofday(tv: *mut TimeVal) -> Result<isize> {
+    let task = PROCESSOR.current_task()?;
+    let process = task.process();
+    let inner = process.inner_access();
+    inner.space().check_user_range(tv, size_of::<TimeVal>(), true)?;
+    inner.space().copy_value_to_user(tv, &TimeVal::from_us(timer::realtime_us()))?;
+    Ok(0)
+}
+
+/// Set the realtime clock, there is no RTC so the clock starts from zero at booting.
+/// Only the privileged process can set the clock.
+///
+/// - Arguments
+///     - tv: the pointer of the time value of the current moment
+///
+/// - Returns
+///     - Ok(0)
+///     - Ok(-1): the current process is not privileged
+///
+/// - Errors
+///     - ProcessHaveNotTask
+///     - VPNNotMapped(vpn)
+///     - UserRangeNotAccessible(va, len)
+#[inline(always)]
+pub(crate) fn sys_settimeofday(tv: *const TimeVal) -> Result<isize> {
+    let task = PROCESSOR.current_task()?;
+    let process = task.process();
+    let inner = process.inner_access();
+    if !inner.is_privileged() {
+        return Ok(-1);
+    }
+    inner.space().check_user_range(tv, size_of::<TimeVal>(), false)?;
+    timer::set_realtime_us(inner.space().copy_value_from_user(tv)?.as_us());
+    Ok(0)
+}
+
+/// Get the CPU time consumed by the current task and it's process in timer ticks.
+///
+/// - Arguments
+///     - times: the pointer of the times structure which will be written
+///
+/// - Returns
+///     - Ok(0)
+///
+/// - Errors
+///     - ProcessHaveNotTask
+///     - VPNNotMapped(vpn)
+#[inline(always)]
+pub(crate) fn sys_times(times: *mut Times) -> Result<isize> {
+    let task = PROCESSOR.current_task()?;
+    // the time elapsed in the current syscall is also counted
+    task.account_cpu_time(false);
+    let task_time = task.cpu_time();
+    let process = task.process();
+    let process_time = process.cpu_time();
+    let inner = process.inner_access();
+    inner.space().check_user_range(times, size_of::<Times>(), true)?;
+    let value = Times {
+        task_user_time: task_time.user(),
+        task_kernel_time: task_time.kernel(),
+        process_user_time: process_time.user(),
+        process_kernel_time: process_time.kernel(),
+    };
+    inner.space().copy_value_to_user(times, &value)?;
+    Ok(0)
 }
 
 #[inline(always)]
 pub(crate) fn sys_sleep(us: usize) -> Result<isize> {
     sleep_current_and_run_other_task(us)?;
     Ok(0)
-}
\ No newline at end of file
+}
+
+/// Sleep current task for the requested duration.
+/// The task only gives up sleeping early when a signal which will take effect is pending,
+/// otherwise it keeps sleeping for the remaining duration after being woken up,
+/// so the signals which are masked or ignored never interrupt the sleeping.
+/// The remaining duration of the interrupted sleeping will be written into `rem`,
+/// which is also the requested duration if the sleeping is restarted by the signal handler.
+///
+/// - Arguments
+///     - req: the pointer of the requested duration
+///     - rem: the pointer of the remaining duration, ignored if it is null
+///
+/// - Returns
+///     - Ok(0): the full duration has elapsed
+///     - Ok(-1): the sleep was interrupted by a signal
+///
+/// - Errors
+///     - ProcessHaveNotTask
+///     - VPNNotMapped(vpn)
+#[inline(always)]
+pub(crate) fn sys_nanosleep(req: *const TimeVal, rem: *mut TimeVal) -> Result<isize> {
+    let task = PROCESSOR.current_task()?;
+    let process = task.process();
+    let inner = process.inner_access();
+    inner.space().check_user_range(req, size_of::<TimeVal>(), false)?;
+    if !rem.is_null() {
+        inner.space().check_user_range(rem, size_of::<TimeVal>(), true)?;
+    }
+    let us = inner.space().copy_value_from_user(req)?.as_us();
+    drop(inner);
+    drop(process);
+    drop(task);
+    let expire_us = TASK_SCHEDULER.now_us().saturating_add(us);
+    loop {
+        let remain_us = expire_us.saturating_sub(TASK_SCHEDULER.now_us());
+        if remain_us == 0 {
+            return Ok(0);
+        }
+        if current_has_deliverable_signal()? {
+            let result = interrupt_current_syscall()?;
+            if !rem.is_null() {
+                let task = PROCESSOR.current_task()?;
+                let process = task.process();
+                let inner = process.inner_access();
+                inner.space().copy_value_to_user(rem, &TimeVal::from_us(remain_us))?;
+                // the restarted sleeping only sleeps for the remaining duration
+                task.set_interrupted_syscall_arg(rem as usize);
+            }
+            return Ok(result);
+        }
+        sleep_current_and_run_other_task(remain_us)?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task::model::{ForkedProcess, USER_UID};
+    use crate::task::signal::custom_action;
+    use frontier_lib::model::signal::{Signal, SignalFlags};
+
+    #[test_case]
+    fn test_settimeofday_moves_realtime() {
+        let process = ForkedProcess::new();
+        let _current = process.enter();
+        let before = timer::realtime_us();
+        // the time value straddles the page boundary
+        let tv = (process.user_scratch_va() + 120) as *mut TimeVal;
+        let moment = TimeVal::from_us(before + 3_600 * 1_000_000);
+        process.inner_access().space().copy_value_to_user(tv, &moment).unwrap();
+        assert_eq!(sys_settimeofday(tv).unwrap(), 0);
+        assert_eq!(sys_gettimeofday(tv).unwrap(), 0);
+        let now = process.inner_access().space().copy_value_from_user(tv).unwrap();
+        assert!(now.as_us() >= moment.as_us());
+        timer::set_realtime_us(before);
+        // the unprivileged process cannot move the clock
+        assert!(process.inner_exclusive_access().set_uid(USER_UID));
+        assert_eq!(sys_settimeofday(tv).unwrap(), -1);
+        assert!(timer::realtime_us() < moment.as_us());
+    }
+
+    #[test_case]
+    fn test_nanosleep_interrupted_by_deliverable_signal_only() {
+        let process = ForkedProcess::new();
+        let task = process.inner_access().root_task();
+        let _current = process.enter();
+        let req = process.user_scratch_va() as *mut TimeVal;
+        let rem = (process.user_scratch_va() + 64) as *mut TimeVal;
+        let duration = TimeVal::from_us(3_600 * 1_000_000);
+        process.inner_access().space().copy_value_to_user(req, &duration).unwrap();
+        // the signal with the default ignoring action and the masked signal take no effect
+        let mut inner = process.inner_exclusive_access();
+        inner.set_signal_action(Signal::USR1, custom_action(0x1000));
+        let mask = inner.exchange_singal_mask(SignalFlags::USR1);
+        drop(inner);
+        process.kill(Signal::USR1).unwrap();
+        task.kill(Signal::USR2).unwrap();
+        assert!(!task.has_deliverable_signal());
+        // the elapsed sleeping is never interrupted even if signals are pending
+        let zero = (process.user_scratch_va() + 128) as *mut TimeVal;
+        process.inner_access().space().copy_value_to_user(zero, &TimeVal::from_us(0)).unwrap();
+        assert_eq!(sys_nanosleep(zero, rem).unwrap(), 0);
+        // the unmasked signal with the custom handler interrupts the sleeping at once
+        process.inner_exclusive_access().exchange_singal_mask(mask);
+        assert!(task.has_deliverable_signal());
+        assert_eq!(sys_nanosleep(req, rem).unwrap(), -1);
+        let remain = process.inner_access().space().copy_value_from_user(rem).unwrap();
+        assert!(remain.as_us() > 0 && remain.as_us() <= duration.as_us());
+        assert_eq!(task.interrupted_syscall_arg(), Some(rem as usize));
+        task.clear_interrupted_syscall();
+    }
+}
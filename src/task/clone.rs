@@ -0,0 +1,172 @@
+// @author:    olinex
+// @time:      2024/07/10
+
+// self mods
+
+// use other mods
+use core::mem::size_of;
+
+// use self mods
+use crate::configs;
+use crate::lang::buffer::ByteBuffers;
+
+/// The minimal byte size of the clone arguments which user must pass, only contains the flags
+pub(crate) const CLONE_ARGS_SIZE_MIN: usize = size_of::<u64>();
+
+bitflags! {
+    /// The flags which decide how to clone the task
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub(crate) struct CloneFlags: u64 {
+        /// Create a new thread in the same process instead of forking a new process
+        const THREAD = 1 << 16;
+        /// Set the thread pointer register of the new task
+        const SETTLS = 1 << 19;
+        /// Write the id of the new task into the `set_tid` address
+        const SETTID = 1 << 20;
+        /// Clear the `clear_tid` address and wake up the futex on it when the new thread exits
+        const CLEARTID = 1 << 21;
+    }
+}
+
+/// The arguments of the clone3 syscall, which is read from user space.
+/// New fields must always be appended to the tail of the structure,
+/// so that the structures in different versions are compatible with each other.
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub(crate) struct CloneArgs {
+    /// The bits of the [`CloneFlags`]
+    pub(crate) flags: u64,
+    /// The lowest address of the stack for the new task, zero means using the default stack
+    pub(crate) stack: u64,
+    /// The byte size of the stack
+    pub(crate) stack_size: u64,
+    /// The value of the thread pointer register, only used with [`CloneFlags::SETTLS`]
+    pub(crate) tls: u64,
+    /// The address where the id of the new task will be written, only used with [`CloneFlags::SETTID`]
+    pub(crate) set_tid: u64,
+    /// The address will be cleared when the new thread exits, only used with [`CloneFlags::CLEARTID`]
+    pub(crate) clear_tid: u64,
+    /// The signal will be sent to the parent when the new process exits,
+    /// it must be zero because the exit signal is not supported yet
+    pub(crate) exit_signal: u64,
+}
+impl CloneArgs {
+    /// Read the clone arguments from the user byte buffers.
+    /// If user's structure is shorter than the kernel's, the missing fields will be zero;
+    /// If user's structure is longer than the kernel's, the unknown fields must be zero.
+    ///
+    /// - Arguments
+    ///     - buffers: the byte buffers of the user structure
+    ///
+    /// - Returns
+    ///     - Some(arguments)
+    ///     - None: the structure is too short or contains unknown non-zero fields
+    pub(crate) fn from_buffers(buffers: ByteBuffers) -> Option<Self> {
+        if buffers.len() < CLONE_ARGS_SIZE_MIN {
+            return None;
+        }
+        let mut args = Self::default();
+        let bytes = unsafe {
+            core::slice::from_raw_parts_mut(&mut args as *mut Self as *mut u8, size_of::<Self>())
+        };
+        let mut iterator = buffers.into_iter();
+        let mut index = 0;
+        while let Some(byte) = iterator.next() {
+            if index < bytes.len() {
+                bytes[index] = byte;
+            } else if byte != 0 {
+                return None;
+            }
+            index += 1;
+        }
+        Some(args)
+    }
+
+    /// Get the clone flags, unknown bits will be truncated
+    pub(crate) fn flags(&self) -> CloneFlags {
+        CloneFlags::from_bits_truncate(self.flags)
+    }
+
+    /// Get the top address of the stack
+    ///
+    /// - Returns
+    ///     - Some(top address)
+    ///     - None: no stack was provided or the top address overflows
+    pub(crate) fn stack_top(&self) -> Option<usize> {
+        if self.stack == 0 {
+            None
+        } else {
+            self.stack.checked_add(self.stack_size).map(|top| top as usize)
+        }
+    }
+}
+
+/// The optional attributes of the new thread created by the thread_create syscall, which is read from user space.
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub(crate) struct ThreadAttr {
+    /// The byte size of the user stack, zero means using the default size
+    pub(crate) stack_size: u64,
+    /// The extra arguments which will be passed to the a1 ~ a3 registers
+    pub(crate) args: [u64; 3],
+}
+impl ThreadAttr {
+    /// Get the byte size of the user stack, the default size will be used if it is zero
+    pub(crate) fn stack_size(&self) -> usize {
+        if self.stack_size == 0 {
+            configs::USER_TASK_STACK_BYTE_SIZE
+        } else {
+            self.stack_size as usize
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_buffers(bytes: &'static mut [u8]) -> ByteBuffers {
+        let len = bytes.len();
+        ByteBuffers::new(vec![bytes], len)
+    }
+
+    #[test_case]
+    fn test_clone_args_from_buffers() {
+        let mut args = CloneArgs::default();
+        args.flags = (CloneFlags::THREAD | CloneFlags::SETTLS).bits();
+        args.stack = 0x1000;
+        args.stack_size = 0x2000;
+        args.tls = 0x4000;
+        let bytes = vec![0u8; size_of::<CloneArgs>() + 8].leak();
+        unsafe {
+            core::ptr::copy_nonoverlapping(
+                &args as *const CloneArgs as *const u8,
+                bytes.as_mut_ptr(),
+                size_of::<CloneArgs>(),
+            )
+        };
+        let parsed = CloneArgs::from_buffers(new_buffers(bytes)).unwrap();
+        assert_eq!(parsed, args);
+        assert!(parsed.flags().contains(CloneFlags::THREAD));
+        assert_eq!(parsed.stack_top(), Some(0x3000));
+        // the overflowing stack has no top address
+        args.stack = u64::MAX;
+        assert_eq!(args.stack_top(), None);
+    }
+
+    #[test_case]
+    fn test_clone_args_compatible_size() {
+        // older structure which only contains flags
+        let bytes = vec![1u8, 0, 1, 0, 0, 0, 0, 0].leak();
+        let parsed = CloneArgs::from_buffers(new_buffers(bytes)).unwrap();
+        assert_eq!(parsed.flags, 0x10001);
+        assert_eq!(parsed.stack_top(), None);
+        // too short structure
+        let bytes = vec![0u8; CLONE_ARGS_SIZE_MIN - 1].leak();
+        assert!(CloneArgs::from_buffers(new_buffers(bytes)).is_none());
+        // newer structure with unknown non-zero fields
+        let bytes = vec![0u8; size_of::<CloneArgs>() + 8].leak();
+        bytes[size_of::<CloneArgs>()] = 1;
+        assert!(CloneArgs::from_buffers(new_buffers(bytes)).is_none());
+    }
+}
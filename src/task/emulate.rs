@@ -0,0 +1,105 @@
+// @author:    olinex
+// @time:      2024/08/17
+
+// self mods
+
+// use other mods
+
+// use self mods
+use super::step::bits;
+use crate::sbi::*;
+use crate::trap::context::UserRegs;
+
+/// The opcode of the system instructions, including the csr instructions
+const OPCODE_SYSTEM: u32 = 0x73;
+/// The funct3 of the atomic read and set bits in csr instruction
+const FUNCT3_CSRRS: u32 = 0b010;
+/// The csr number of the cycle counter
+const CSR_CYCLE: u32 = 0xc00;
+/// The csr number of the time counter
+const CSR_TIME: u32 = 0xc01;
+/// The csr number of the retired instruction counter
+const CSR_INSTRET: u32 = 0xc02;
+
+/// Decode the counter reading instruction which can be emulated by the kernel,
+/// only the `csrrs rd, csr, zero` forms of rdcycle, rdtime and rdinstret are accepted.
+///
+/// - Arguments
+///     - inst: the illegal instruction
+///
+/// - Returns
+///     - Some(rd, csr): the destination register and the csr number
+///     - None: the instruction cannot be emulated
+fn decode_counter_read(inst: u32) -> Option<(usize, u32)> {
+    if bits(inst, 6, 0) != OPCODE_SYSTEM
+        || bits(inst, 14, 12) != FUNCT3_CSRRS
+        || bits(inst, 19, 15) != 0
+    {
+        return None;
+    }
+    match bits(inst, 31, 20) {
+        csr @ (CSR_CYCLE | CSR_TIME | CSR_INSTRET) => Some((bits(inst, 11, 7) as usize, csr)),
+        _ => None,
+    }
+}
+
+/// Emulate the counter reading instruction which is not permitted in the user mode,
+/// the value of the counter is written into the destination register
+/// and the program counter is moved to the following instruction.
+///
+/// - Arguments
+///     - inst: the illegal instruction at the program counter
+///     - regs: the registers of the task which executed the instruction
+///
+/// - Returns
+///     - true: the instruction was emulated
+///     - false: the instruction is not in the whitelist and the registers are not changed
+pub(crate) fn emulate_counter_read(inst: u32, regs: &mut UserRegs) -> bool {
+    let (rd, csr) = match decode_counter_read(inst) {
+        Some(decoded) => decoded,
+        None => return false,
+    };
+    let value = match csr {
+        CSR_CYCLE => SBI::get_cycle(),
+        CSR_TIME => SBI::get_timer(),
+        _ => SBI::get_instret(),
+    };
+    // the zero register is hardwired, so the reading is just discarded
+    if rd != 0 {
+        regs.x[rd] = value;
+    }
+    regs.pc += 4;
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_emulate_rdtime_increases() {
+        let mut regs = UserRegs::default();
+        regs.pc = 0x1000;
+        // rdtime a0
+        assert!(emulate_counter_read(0xc0102573, &mut regs));
+        assert_eq!(regs.pc, 0x1004);
+        let first = regs.x[10];
+        // rdtime a1
+        assert!(emulate_counter_read(0xc01025f3, &mut regs));
+        assert_eq!(regs.pc, 0x1008);
+        assert!(regs.x[11] >= first);
+        assert!(first > 0);
+    }
+
+    #[test_case]
+    fn test_emulate_rejects_other_instructions() {
+        let mut regs = UserRegs::default();
+        // csrrw a1, time, a1 is not a reading
+        assert!(!emulate_counter_read(0xc01595f3, &mut regs));
+        // csrrs a0, sstatus, zero is not a counter
+        assert!(!emulate_counter_read(0x10002573, &mut regs));
+        // addi a0, a0, 1
+        assert!(!emulate_counter_read(0x00150513, &mut regs));
+        assert_eq!(regs, UserRegs::default());
+    }
+}
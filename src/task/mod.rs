@@ -34,30 +34,38 @@
 // @time:      2023/09/01
 
 // self mods
-mod allocator;
+pub(crate) mod allocator;
+pub(crate) mod clone;
 pub(crate) mod context;
+mod emulate;
 pub(crate) mod model;
 mod process;
 mod scheduler;
-mod signal;
+pub(crate) mod signal;
+pub(crate) mod step;
 mod switch;
 
 // use other mods
+use alloc::sync::Arc;
 use frontier_lib::model::signal::Signal;
 
 // use self mods
 use crate::prelude::*;
 
 // reexports
+pub(crate) use model::PROCESS_TABLE;
 pub(crate) use process::PROCESSOR;
-pub(crate) use scheduler::TASK_SCHEDULER;
+pub(crate) use scheduler::{SchedStats, TASK_SCHEDULER};
 
 /// This method allows the multitasking system to start really running,
 /// which is the engine ignition switch
+///
+/// - Arguments
+///     - hart_id: the id of the current hart
 #[allow(dead_code)]
 #[inline(always)]
-pub(crate) fn run() -> ! {
-    process::PROCESSOR.schedule()
+pub(crate) fn run(hart_id: usize) -> ! {
+    process::PROCESSOR.schedule(hart_id)
 }
 
 /// See [`crate::task::process::PROCESSOR::suspend_current_and_run_other_task`]
@@ -66,6 +74,33 @@ pub(crate) fn suspend_current_and_run_other_task() -> Result<()> {
     process::PROCESSOR.suspend_current_and_run_other_task()
 }
 
+/// See [`crate::task::process::PROCESSOR::lower_current_and_run_other_task`]
+#[inline(always)]
+pub(crate) fn lower_current_and_run_other_task() -> Result<()> {
+    process::PROCESSOR.lower_current_and_run_other_task()
+}
+
+/// See [`crate::task::process::PROCESSOR::preempt_current_and_run_other_task`]
+#[inline(always)]
+pub(crate) fn preempt_current_and_run_other_task() -> Result<()> {
+    process::PROCESSOR.preempt_current_and_run_other_task()
+}
+
+/// Count the syscall of the current task, which is the preemption point of the deterministic mode.
+/// The current task will be preempted at the syscall-count boundaries,
+/// after the sleeping tasks whose virtual timers were timeout become ready.
+///
+/// - Errors
+///     - ProcessHaveNotTask
+#[inline(always)]
+pub(crate) fn tick_current_syscall() -> Result<()> {
+    if TASK_SCHEDULER.tick_syscall() {
+        TASK_SCHEDULER.check_timers();
+        process::PROCESSOR.preempt_current_and_run_other_task()?;
+    }
+    Ok(())
+}
+
 /// See [`crate::task::process::PROCESSOR::exit_current_and_run_other_task`]
 #[inline(always)]
 pub(crate) fn exit_current_and_run_other_task(exit_code: i32) -> Result<()> {
@@ -78,18 +113,74 @@ pub(crate) fn send_current_task_signal(signal: Signal) -> Result<()> {
     process::PROCESSOR.send_current_task_signal(signal)
 }
 
+/// See [`crate::task::process::PROCESSOR::send_current_task_fault_signal`]
+#[inline(always)]
+pub(crate) fn send_current_task_fault_signal(signal: Signal, addr: usize) -> Result<()> {
+    process::PROCESSOR.send_current_task_fault_signal(signal, addr)
+}
+
 /// See [`crate::task::process::PROCESSOR::handle_current_task_signals`]
 #[inline(always)]
 pub(crate) fn handle_current_task_signals() -> Result<Option<Signal>> {
     process::PROCESSOR.handle_current_task_signals()
 }
 
+/// Check whether the current task has any pending signal,
+/// including the process-wide signals and the signals sent to the task specifically
+///
+/// - Errors
+///     - ProcessHaveNotTask
+#[inline(always)]
+pub(crate) fn current_has_pending_signal() -> Result<bool> {
+    let task = process::PROCESSOR.current_task()?;
+    Ok(task.process().inner_access().has_pending_signal() || task.has_pending_signal())
+}
+
+/// Check whether the current task has any pending signal which will take effect once it is handled,
+/// the signals blocked by the mask or ignored by the default actions are not counted
+///
+/// - Errors
+///     - ProcessHaveNotTask
+#[inline(always)]
+pub(crate) fn current_has_deliverable_signal() -> Result<bool> {
+    Ok(process::PROCESSOR.current_task()?.has_deliverable_signal())
+}
+
+/// Mark the syscall of the current task as interrupted by a signal and return the result.
+/// The syscall will be executed again with the same arguments
+/// if the handler of the signal was registered with the restart flag.
+///
+/// - Returns
+///     - Ok(-1): the interrupted result of the syscall
+///
+/// - Errors
+///     - ProcessHaveNotTask
+///     - AreaNotExists(start_vpn, end_vpn)
+///     - VPNNotMapped(vpn)
+#[inline(always)]
+pub(crate) fn interrupt_current_syscall() -> Result<isize> {
+    process::PROCESSOR.current_task()?.mark_syscall_interrupted()?;
+    Ok(-1)
+}
+
 /// Just block current task and run other task
 #[inline(always)]
 pub(crate) fn block_current_and_run_other_task() -> Result<()> {
     process::PROCESSOR.block_current_and_run_other_task(|_| Ok(()))
 }
 
+/// Block current task until the target task exits
+///
+/// - Arguments
+///     - target: the task which the current task is joining
+#[inline(always)]
+pub(crate) fn join_current_and_run_other_task(target: &Arc<model::TaskControlBlock>) -> Result<()> {
+    process::PROCESSOR.block_current_and_run_other_task(|task| {
+        target.add_joiner(&task);
+        Ok(())
+    })
+}
+
 /// Block current task and put it into sleep task heap
 #[inline(always)]
 pub(crate) fn sleep_current_and_run_other_task(us: usize) -> Result<()> {
@@ -99,6 +190,100 @@ pub(crate) fn sleep_current_and_run_other_task(us: usize) -> Result<()> {
     })
 }
 
+/// Promote the eligible anonymous areas of the current process into huge pages
+///
+/// - Errors
+///     - ProcessHaveNotTask
+///     - VPNNotMapped(vpn)
+#[inline(always)]
+pub(crate) fn promote_current_huge_pages() -> Result<usize> {
+    let task = process::PROCESSOR.current_task()?;
+    let process = task.process();
+    let inner = process.inner_access();
+    inner.space().promote_huge_pages()
+}
+
+/// Sample the count of the frames resident in the space of the current process,
+/// so that the max resident frames of the current task can be reported
+///
+/// - Errors
+///     - ProcessHaveNotTask
+#[inline(always)]
+pub(crate) fn account_current_resident_frames() -> Result<()> {
+    let task = process::PROCESSOR.current_task()?;
+    let frames = task.process().inner_access().space().resident_frames();
+    task.account_resident_frames(frames);
+    Ok(())
+}
+
+/// Handle the breakpoint reached by the current task.
+/// The breakpoint inserted by the single-step completes the step of the root task,
+/// the other tasks yield and retry the original instruction after the step is completed,
+/// otherwise the ebreak is handled by the TRAP signal,
+/// see [`model::ProcessControlBlock::reach_breakpoint`].
+///
+/// - Errors
+///     - ProcessHaveNotTask
+///     - AreaNotExists(start_vpn, end_vpn)
+///     - VPNNotMapped(vpn)
+///     - UserRangeNotAccessible(va, len)
+///     - FrameExhausted
+///     - SwapIOFailed(slot)
+#[inline(always)]
+pub(crate) fn handle_current_breakpoint() -> Result<()> {
+    let task = process::PROCESSOR.current_task()?;
+    let process = task.process();
+    let pc = {
+        let inner = process.inner_access();
+        let task_inner = task.inner_access();
+        task_inner.modify_trap_ctx(inner.space(), |trap_ctx| Ok(trap_ctx.sepc))?
+    };
+    if !process.complete_step(&task, pc)? {
+        process.reach_breakpoint(&task, pc)?;
+    } else if task.tid() != model::ROOT_TID {
+        drop(process);
+        drop(task);
+        process::PROCESSOR.suspend_current_and_run_other_task()?;
+    }
+    Ok(())
+}
+
+/// Emulate the illegal instruction of the current task if it reads the counters,
+/// which are not permitted in the user mode, see [`emulate::emulate_counter_read`].
+///
+/// - Returns
+///     - Ok(true): the instruction was emulated and the task will continue after it
+///     - Ok(false): the instruction cannot be emulated
+///
+/// - Errors
+///     - ProcessHaveNotTask
+///     - AreaNotExists(start_vpn, end_vpn)
+///     - VPNNotMapped(vpn)
+///     - UserRangeNotAccessible(va, len)
+///     - FrameExhausted
+///     - SwapIOFailed(slot)
+#[inline(always)]
+pub(crate) fn emulate_current_instruction() -> Result<bool> {
+    let task = process::PROCESSOR.current_task()?;
+    let process = task.process();
+    let pc = {
+        let inner = process.inner_access();
+        let task_inner = task.inner_access();
+        task_inner.modify_trap_ctx(inner.space(), |trap_ctx| Ok(trap_ctx.sepc))?
+    };
+    let inst = step::read_instruction(&process, pc)?;
+    let inner = process.inner_access();
+    let task_inner = task.inner_access();
+    task_inner.modify_trap_ctx(inner.space(), |trap_ctx| {
+        let mut regs = trap_ctx.user_regs();
+        let emulated = emulate::emulate_counter_read(inst, &mut regs);
+        if emulated {
+            trap_ctx.set_user_regs(&regs);
+        }
+        Ok(emulated)
+    })
+}
+
 #[inline(always)]
 pub(crate) fn init() {
     process::add_init_proc();
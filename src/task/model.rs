@@ -8,7 +8,6 @@ use alloc::collections::{BTreeMap, BTreeSet};
 use alloc::string::String;
 use alloc::sync::{Arc, Weak};
 use alloc::vec::Vec;
-use core::cell::{Ref, RefMut};
 use core::str::FromStr as _;
 use enum_group::EnumGroup;
 use frontier_fs::OpenFlags;
@@ -17,43 +16,103 @@ use frontier_lib::model::signal::{Signal, SignalAction, SignalFlags};
 // use self mods
 use super::allocator::{AutoRecycledIdAllocator, IdTracker};
 use super::context::TaskContext;
-use super::signal::SignalControlBlock;
+use super::scheduler::TASK_SCHEDULER;
+use super::signal::{SignalControlBlock, SignalStack, TaskSignalBlock};
+use super::step;
 use crate::configs;
+use crate::drivers::char::LineDiscipline;
 use crate::fs::inode::ROOT_INODE;
 use crate::fs::stdio::{STDIN, STDOUT};
 use crate::fs::File;
-use crate::lang::container::UserPromiseRefCell;
+use crate::lang::container::{UserPromiseRef, UserPromiseRefCell, UserPromiseRefMut};
+use crate::lang::timer::CpuTime;
+use crate::memory::area::RefaultKind;
+use crate::memory::shm::SHM_TABLE;
 use crate::memory::space::{Space, KERNEL_SPACE};
 use crate::prelude::*;
+use crate::sbi::{SBIApi, SBI};
+use crate::sync::barrier::Barrier;
 use crate::sync::condvar::{Condvar, CondvarBlocking};
-use crate::sync::mutex::{Mutex, MutexBlocking, MutexSpin};
+use crate::sync::futex::FUTEX_TABLE;
+use crate::sync::mutex::{Mutex, MutexKind};
 use crate::sync::semaphore::{Semaphore, SemaphoreBlocking, SemaphoreSpin};
-use crate::trap::context::TrapContext;
+use crate::trap::context::{TrapContext, UserRegs};
 
 pub(crate) const ROOT_TID: usize = 0;
 pub(crate) const ROOT_PID: usize = 0;
+/// The user id of the privileged user
+pub(crate) const ROOT_UID: usize = 0;
+/// The group id of the privileged user
+pub(crate) const ROOT_GID: usize = 0;
+/// The user id of the only unprivileged user
+pub(crate) const USER_UID: usize = 1000;
+/// The group id of the only unprivileged user
+pub(crate) const USER_GID: usize = 1000;
+
+/// The user and group ids which the accesses to the files are checked with
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub(crate) struct Credential {
+    pub(crate) uid: usize,
+    pub(crate) gid: usize,
+}
+impl Credential {
+    /// The credential of the privileged user, which is also used by the kernel itself
+    pub(crate) const ROOT: Self = Self {
+        uid: ROOT_UID,
+        gid: ROOT_GID,
+    };
+
+    /// Check if the credential belongs to the privileged user
+    pub(crate) fn is_privileged(&self) -> bool {
+        self.uid == ROOT_UID
+    }
+}
 
 /// The tracker of kernel stack,
 /// each time the tracker is creating, we will map kernel stack to the kernel space.
 /// When the tracker is dropping, the kernel stack will be unmaped from kernel space.
-pub(crate) struct KernelStack(IdTracker);
+pub(crate) struct KernelStack {
+    /// The id tracker of the kernel stack
+    tracker: IdTracker,
+    /// The byte size of the mapped kernel stack
+    byte_size: usize,
+}
 impl KernelStack {
     /// Create a new kernel stack id tracker and map a new kernel stack pages
-    pub(crate) fn new() -> Result<Self> {
+    ///
+    /// - Arguments
+    ///     - byte_size: the byte size of the kernel stack, which cannot be greater than the max size
+    ///
+    /// - Errors
+    ///     - InvalidKernelStackSize(byte_size)
+    ///     - IdExhausted
+    ///     - AreaAllocFailed(start_vpn, end_vpn)
+    ///     - FrameExhausted
+    pub(crate) fn new(byte_size: usize) -> Result<Self> {
+        if byte_size == 0 || byte_size > configs::KERNEL_TASK_STACK_MAX_BYTE_SIZE {
+            return Err(KernelError::InvalidKernelStackSize(byte_size));
+        }
         let tracker = KERNEL_STACK_ALLOCATOR.alloc()?;
-        KERNEL_SPACE.map_kernel_task_stack(tracker.id())?;
-        Ok(Self(tracker))
+        KERNEL_SPACE.map_kernel_task_stack(tracker.id(), byte_size)?;
+        Ok(Self { tracker, byte_size })
     }
 
     /// Get the id of the kernel stack 
     pub(crate) fn id(&self) -> usize {
-        self.0.id()
+        self.tracker.id()
+    }
+
+    /// Get the byte size of the kernel stack
+    pub(crate) fn byte_size(&self) -> usize {
+        self.byte_size
     }
 }
 impl Drop for KernelStack {
     /// Drop kernel stack id tracker and unmap kernel stack pages
     fn drop(&mut self) {
-        KERNEL_SPACE.unmap_kernel_task_stack(self.id()).unwrap();
+        KERNEL_SPACE
+            .unmap_kernel_task_stack(self.id(), self.byte_size)
+            .unwrap();
     }
 }
 
@@ -74,6 +133,8 @@ pub(crate) struct TaskUserResource {
     tracker: IdTracker,
     /// The weak reference to the process for resource recycling
     process: Weak<ProcessControlBlock>,
+    /// The byte size of the mapped user stack
+    stack_size: usize,
 }
 impl TaskUserResource {
     /// Create a task resource manager, alloc trap context and task stack in user space
@@ -81,8 +142,11 @@ impl TaskUserResource {
     /// - Arguments
     ///     - tracker: task id tracker created by process control block
     ///     - process: the process control block reference
+    ///     - stack_size: the byte size of the user stack, which cannot be greater than the max size
     ///
     /// - Errors
+    ///     - InvalidUserStackSize(byte_size)
+    ///     - UserStackOutOfLayout(tid)
     ///     - AreaAllocFailed(start_vpn, end_vpn)
     ///     - VPNOutOfArea(vpn, start_vpn, end_vpn)
     ///     - VPNAlreadyMapped(vpn)
@@ -91,13 +155,23 @@ impl TaskUserResource {
     ///     - AllocFullPageMapper(ppn)
     ///     - PPNAlreadyMapped(ppn)
     ///     - PPNNotMapped(ppn)
-    fn new(tracker: IdTracker, process: &Arc<ProcessControlBlock>) -> Result<Self> {
+    fn new(
+        tracker: IdTracker,
+        process: &Arc<ProcessControlBlock>,
+        stack_size: usize,
+    ) -> Result<Self> {
+        if stack_size == 0 || stack_size > configs::USER_TASK_STACK_MAX_BYTE_SIZE {
+            return Err(KernelError::InvalidUserStackSize(stack_size));
+        }
+        // validate the layout before creating the resource, which will be deallocated when dropping
+        let base_size = process.inner_access().base_size;
+        Space::get_user_task_stack_vpn_range(base_size, tracker.id(), stack_size)?;
         let resource = Self {
             tracker,
             process: Arc::downgrade(process),
+            stack_size,
         };
         let mut process_inner = process.inner_exclusive_access();
-        let base_size = process_inner.base_size;
         resource.alloc(&mut process_inner.space, base_size)?;
         Ok(resource)
     }
@@ -109,6 +183,7 @@ impl TaskUserResource {
     ///     - base_size: the byte size of the executable code and data
     ///
     /// - Errors
+    ///     - UserStackOutOfLayout(tid)
     ///     - AreaAllocFailed(start_vpn, end_vpn)
     ///     - VPNOutOfArea(vpn, start_vpn, end_vpn)
     ///     - VPNAlreadyMapped(vpn)
@@ -119,7 +194,8 @@ impl TaskUserResource {
     ///     - PPNNotMapped(ppn)
     fn alloc(&self, space: &mut Space, base_size: usize) -> Result<()> {
         let tid = self.tracker.id();
-        space.alloc_user_task_stack(base_size, tid)?;
+        // the user stack must be allocated first, so nothing is allocated when it is out of the layout
+        space.alloc_user_task_stack(base_size, tid, self.stack_size)?;
         space.alloc_task_trap_ctx(tid)?;
         Ok(())
     }
@@ -134,9 +210,13 @@ impl TaskUserResource {
     ///     - AreaDeallocFailed(start vpn, end vpn)
     fn dealloc(&self, space: &mut Space, base_size: usize) -> Result<()> {
         let tid = self.tracker.id();
-        space.dealloc_task_trap_ctx(tid)?;
-        space.dealloc_user_task_stack(base_size, tid)?;
-        Ok(())
+        // both areas are unmapped with only one fence
+        space.begin_tlb_batch();
+        let result = space
+            .dealloc_task_trap_ctx(tid)
+            .and_then(|_| space.dealloc_user_task_stack(base_size, tid, self.stack_size));
+        space.end_tlb_batch();
+        result
     }
 
     /// Help function for getting the trap context from task's virtual address space
@@ -164,6 +244,83 @@ impl Drop for TaskUserResource {
     }
 }
 
+/// The resource usage of the tasks, which is shared with the user space.
+/// The counters are reset when forking and kept when executing the new program.
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub(crate) struct RUsage {
+    /// The count of the page faults which were served without any I/O
+    pub(crate) minor_faults: usize,
+    /// The count of the page faults which were served by reading the swap device
+    pub(crate) major_faults: usize,
+    /// The count of the context switches because the task yielded or blocked
+    pub(crate) voluntary_switches: usize,
+    /// The count of the context switches because the time slice was used up
+    pub(crate) involuntary_switches: usize,
+    /// The max count of the frames which were resident in the space
+    pub(crate) max_resident_frames: usize,
+    /// The count of the blocks read from the block devices
+    pub(crate) input_blocks: usize,
+    /// The count of the blocks written into the block devices
+    pub(crate) output_blocks: usize,
+}
+impl RUsage {
+    /// Merge the usage of other tasks into the current usage
+    ///
+    /// - Arguments
+    ///     - other: the usage which will be merged
+    pub(crate) fn merge(&mut self, other: &Self) {
+        self.minor_faults += other.minor_faults;
+        self.major_faults += other.major_faults;
+        self.voluntary_switches += other.voluntary_switches;
+        self.involuntary_switches += other.involuntary_switches;
+        self.max_resident_frames = self.max_resident_frames.max(other.max_resident_frames);
+        self.input_blocks += other.input_blocks;
+        self.output_blocks += other.output_blocks;
+    }
+}
+
+/// The soft and hard limits of a resource, which is shared with the user space.
+/// The soft limit is enforced by the kernel and the hard limit is the ceiling of the soft limit.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub(crate) struct RLimit {
+    /// The limit which is enforced by the kernel
+    pub(crate) soft: usize,
+    /// The max value of the soft limit, it can be lowered but never be raised
+    pub(crate) hard: usize,
+}
+impl RLimit {
+    /// Create a new limit whose soft and hard limits are both the value
+    ///
+    /// - Arguments
+    ///     - value: the value of the limits
+    pub(crate) const fn new(value: usize) -> Self {
+        Self {
+            soft: value,
+            hard: value,
+        }
+    }
+
+    /// Replace the current limit by the new limit,
+    /// the soft limit must not exceed the hard limit and the hard limit must not be raised.
+    ///
+    /// - Arguments
+    ///     - new: the new limit
+    ///
+    /// - Returns
+    ///     - true: the limit was replaced
+    ///     - false: the new limit is invalid
+    pub(crate) fn replace(&mut self, new: Self) -> bool {
+        if new.soft <= new.hard && new.hard <= self.hard {
+            *self = new;
+            true
+        } else {
+            false
+        }
+    }
+}
+
 /// The inner task control block contains all mutable task data.
 pub(crate) struct TaskControlBlockInner {
     /// The running status of the task
@@ -174,6 +331,23 @@ pub(crate) struct TaskControlBlockInner {
     exit_code: Option<usize>,
     /// user mode resource
     user_resource: Option<TaskUserResource>,
+    /// The virtual address of the word which will be cleared when the task exits
+    clear_tid: Option<usize>,
+    /// The CPU time consumed by the task
+    cpu_time: CpuTime,
+    /// The counters of the page faults and the context switches of the task
+    rusage: RUsage,
+    /// The level of the multi-level feedback queues, zero is the highest level
+    sched_level: usize,
+    /// The tasks which are blocked to join the current task, they will be woken up when the current task exits
+    joiners: Vec<Weak<TaskControlBlock>>,
+    /// The task which the current task is blocked to join
+    joining: Option<Weak<TaskControlBlock>>,
+    /// The signals sent to the task specifically, which are handled in the task's own trap context
+    signal: TaskSignalBlock,
+    /// The first argument of the syscall interrupted by a signal,
+    /// it will be restored when the syscall is restarted after the handler returns
+    interrupted_syscall: Option<usize>,
 }
 impl TaskControlBlockInner {
     /// Create a new inner task control block and alloc task user resource
@@ -181,8 +355,11 @@ impl TaskControlBlockInner {
     /// - Arguments
     ///     - tracker: task id tracker created by process control block
     ///     - process: the process control block reference
+    ///     - user_stack_size: the byte size of the task's user stack
     ///
     /// - Errors
+    ///     - InvalidUserStackSize(byte_size)
+    ///     - UserStackOutOfLayout(tid)
     ///     - AreaAllocFailed(start_vpn, end_vpn)
     ///     - VPNOutOfArea(vpn, start_vpn, end_vpn)
     ///     - VPNAlreadyMapped(vpn)
@@ -191,29 +368,61 @@ impl TaskControlBlockInner {
     ///     - AllocFullPageMapper(ppn)
     ///     - PPNAlreadyMapped(ppn)
     ///     - PPNNotMapped(ppn)
-    fn new(tracker: IdTracker, process: &Arc<ProcessControlBlock>) -> Result<Self> {
-        let resource = TaskUserResource::new(tracker, process)?;
+    fn new(
+        tracker: IdTracker,
+        process: &Arc<ProcessControlBlock>,
+        user_stack_size: usize,
+    ) -> Result<Self> {
+        let resource = TaskUserResource::new(tracker, process, user_stack_size)?;
         Ok(Self {
             status: TaskStatus::Ready,
             task_ctx: TaskContext::empty(),
             exit_code: None,
             user_resource: Some(resource),
+            clear_tid: None,
+            cpu_time: CpuTime::default(),
+            rusage: RUsage::default(),
+            sched_level: 0,
+            joiners: Vec::new(),
+            joining: None,
+            signal: TaskSignalBlock::new(),
+            interrupted_syscall: None,
         })
     }
 
     /// Release user resource and mark current task as zombie task,
     /// keep task exit code, cause other task in the same process may wait for it.
+    /// The exit code of the task which has already exited will not be overwritten.
     ///
     /// - Arguments
     ///     - exit_code: the exit code of current task
     fn release_user_resource(&mut self, exit_code: usize) {
         let resource = self.user_resource.take();
-        self.status = TaskStatus::Zombie;
-        self.exit_code.replace(exit_code);
+        self.mark_exited(exit_code);
         self.user_resource = None;
         resource.unwrap();
     }
 
+    /// Mark current task as zombie task without releasing the user resource
+    ///
+    /// - Arguments
+    ///     - exit_code: the exit code of current task
+    fn mark_exited(&mut self, exit_code: usize) {
+        self.set_status(TaskStatus::Zombie);
+        self.exit_code.get_or_insert(exit_code);
+    }
+
+    /// Change the status of the task, and keep the count of the blocked tasks in step with it
+    ///
+    /// - Arguments
+    ///     - status: the new status of the task
+    fn set_status(&mut self, status: TaskStatus) {
+        if self.status.is_blocked() != status.is_blocked() {
+            TASK_SCHEDULER.account_blocked_task(status.is_blocked());
+        }
+        self.status = status;
+    }
+
     /// Modify the trap context through closures to avoid complex borrowing lifecycles.
     ///
     /// - Arguments
@@ -233,6 +442,31 @@ impl TaskControlBlockInner {
         f(trap_ctx)
     }
 
+    /// Make the backup trap context to execute the interrupted syscall again
+    /// if the handler of the signal requires restarting,
+    /// otherwise the syscall keeps the interrupted result.
+    /// The interrupted syscall is forgotten in both cases.
+    ///
+    /// - Arguments
+    ///     - restart: true if the handler of the delivered signal requires restarting
+    ///     - trap_ctx_backup: the trap context which will be resumed after the handler returns
+    fn restart_interrupted_syscall(&mut self, restart: bool, trap_ctx_backup: &mut TrapContext) {
+        if let Some(arg) = self.interrupted_syscall.take() {
+            if restart {
+                trap_ctx_backup.sepc_to_prev_instruction();
+                trap_ctx_backup.set_arg(0, arg);
+            }
+        }
+    }
+
+    /// Set the virtual address of the word which will be cleared when the task exits
+    ///
+    /// - Arguments
+    ///     - clear_tid: the virtual address of the word in user space
+    pub(crate) fn set_clear_tid(&mut self, clear_tid: usize) {
+        self.clear_tid.replace(clear_tid);
+    }
+
     /// Modify the task context through closures to avoid complex borrowing lifecycles.
     ///
     /// - Arguments
@@ -247,6 +481,12 @@ impl TaskControlBlockInner {
         f(&mut self.task_ctx)
     }
 }
+impl Drop for TaskControlBlockInner {
+    /// The task which is dropped while it is blocked is no longer counted as blocked
+    fn drop(&mut self) {
+        self.set_status(TaskStatus::Zombie);
+    }
+}
 
 /// The task control block contains all task data
 pub(crate) struct TaskControlBlock {
@@ -263,8 +503,13 @@ impl TaskControlBlock {
     /// - Arguments
     ///     - tracker: task id tracker created by process control block
     ///     - process: the process control block reference
+    ///     - kernel_stack_size: the byte size of the task's kernel stack
+    ///     - user_stack_size: the byte size of the task's user stack
     ///
     /// - Errors
+    ///     - InvalidKernelStackSize(byte_size)
+    ///     - InvalidUserStackSize(byte_size)
+    ///     - UserStackOutOfLayout(tid)
     ///     - AreaAllocFailed(start_vpn, end_vpn)
     ///     - VPNOutOfArea(vpn, start_vpn, end_vpn)
     ///     - VPNAlreadyMapped(vpn)
@@ -273,9 +518,14 @@ impl TaskControlBlock {
     ///     - AllocFullPageMapper(ppn)
     ///     - PPNAlreadyMapped(ppn)
     ///     - PPNNotMapped(ppn)
-    pub(crate) fn new(tracker: IdTracker, process: &Arc<ProcessControlBlock>) -> Result<Self> {
-        let kernel_stack = KernelStack::new()?;
-        let inner = TaskControlBlockInner::new(tracker, process)?;
+    pub(crate) fn new(
+        tracker: IdTracker,
+        process: &Arc<ProcessControlBlock>,
+        kernel_stack_size: usize,
+        user_stack_size: usize,
+    ) -> Result<Self> {
+        let kernel_stack = KernelStack::new(kernel_stack_size)?;
+        let inner = TaskControlBlockInner::new(tracker, process, user_stack_size)?;
         Ok(Self {
             kernel_stack,
             process: Arc::downgrade(process),
@@ -287,6 +537,7 @@ impl TaskControlBlock {
     /// Only the root task is allow to call this method.
     ///
     /// - Errors
+    ///     - TaskLimitExceeded(limit)
     ///     - IdExhausted
     ///     - AreaAllocFailed(start_vpn, end_vpn)
     ///     - VPNOutOfArea(vpn, start_vpn, end_vpn)
@@ -304,12 +555,21 @@ impl TaskControlBlock {
         let tracker = new_process.tid_allocator.alloc()?;
         let new_tid = tracker.id();
         assert_eq!(new_tid, ROOT_TID);
-        let new_task = Arc::new(Self::new(tracker, &new_process)?);
+        let user_stack_size = self.user_stack_size().unwrap();
+        let new_task = Arc::new(Self::new(
+            tracker,
+            &new_process,
+            self.kernel_stack.byte_size(),
+            user_stack_size,
+        )?);
         let mut process_inner = process.inner_exclusive_access();
         let mut new_process_inner = new_process.inner_exclusive_access();
         // Copy user stack's bytes data from current task's space to new task's space
-        let (user_stack_start_vpn, user_stack_end_vpn) =
-            Space::get_user_task_stack_vpn_range(process_inner.base_size, ROOT_TID);
+        let (user_stack_start_vpn, user_stack_end_vpn) = Space::get_user_task_stack_vpn_range(
+            process_inner.base_size,
+            ROOT_TID,
+            user_stack_size,
+        )?;
         new_process_inner.space.copy_area_from_another(
             &process_inner.space,
             user_stack_start_vpn,
@@ -363,6 +623,7 @@ impl TaskControlBlock {
     ///     - ParseElfError
     ///     - InvalidHeadlessTask
     ///     - UnloadableTask
+    ///     - CgroupBudgetExceeded(id)
     ///     - FrameExhausted
     ///     - AreaAllocFailed(start_vpn, end_vpn)
     ///     - VPNOutOfArea(vpn, start_vpn, end_vpn)
@@ -388,7 +649,15 @@ impl TaskControlBlock {
         if configs::COMMAND_LINE_ARGUMENTS_BYTE_SIZE < path_slice.len() + args_slice.len() + 2 {
             return Err(KernelError::OversizeArgs);
         }
-        let (mut space, base_size, entry_point) = KERNEL_SPACE::new_user_from_elf(pid, data)?;
+        let (mut space, base_size, entry_point) = KERNEL_SPACE::new_user_from_elf(data)?;
+        // the new program is still charged to the memory cgroup of the process,
+        // the previous program is uncharged first, so they are never charged together
+        let cgroup = process_inner.space.cgroup();
+        process_inner.space.set_cgroup(None)?;
+        if let Err(error) = space.set_cgroup(cgroup.clone()) {
+            process_inner.space.set_cgroup(cgroup)?;
+            return Err(error);
+        }
         let inner = self.inner_exclusive_access();
         let resource = inner.user_resource.as_ref().unwrap();
         let prev_base_size = process_inner.base_size;
@@ -397,27 +666,22 @@ impl TaskControlBlock {
         let kid = self.kernel_stack.id();
         let tid = resource.tracker.id();
         let kernel_stack_top_va = Space::get_kernel_task_stack_top_va(kid);
-        let mut user_stack_top_va = Space::get_user_task_stack_top_va(base_size, tid);
+        let mut user_stack_top_va = Space::get_user_task_stack_top_va(base_size, tid)?;
         // push arguments into user stack as byte slice
-        for value in args_slice.iter().rev() {
-            user_stack_top_va -= 1;
-            let byte = space.translated_refmut(user_stack_top_va as *const u8)?;
-            *byte = *value;
-        }
+        user_stack_top_va -= args_slice.len();
+        space.copy_to_user(user_stack_top_va as *mut u8, args_slice)?;
         user_stack_top_va -= core::mem::size_of::<usize>();
-        let length = space.translated_refmut(user_stack_top_va as *const usize)?;
-        *length = args.len();
+        space.copy_value_to_user(user_stack_top_va as *mut usize, &args.len())?;
         // push path into user stack as byte slice
-        for value in path_slice.iter().rev() {
-            user_stack_top_va -= 1;
-            let byte = space.translated_refmut(user_stack_top_va as *const u8)?;
-            *byte = *value;
-        }
+        user_stack_top_va -= path_slice.len();
+        space.copy_to_user(user_stack_top_va as *mut u8, path_slice)?;
         user_stack_top_va -= core::mem::size_of::<usize>();
-        let length = space.translated_refmut(user_stack_top_va as *const usize)?;
-        *length = path.len();
+        space.copy_value_to_user(user_stack_top_va as *mut usize, &path.len())?;
         process_inner.path = path;
         process_inner.space = space;
+        // the breakpoints were inserted in the previous space
+        process_inner.step_breakpoints.clear();
+        process_inner.reached_breakpoint.take();
         process_inner.entry_point = entry_point;
         process_inner.base_size = base_size;
         inner.modify_trap_ctx(&process_inner.space, |trap_ctx| {
@@ -442,7 +706,7 @@ impl TaskControlBlock {
     /// - Returns
     ///     - Ok(-1): task does not exist
     ///     - Ok(-2): task is still running
-    ///     - Ok(task id) 
+    ///     - Ok(task id): the exited root task will not be recycled until the process exits
     /// 
     /// - Errors
     ///     - VPNNotMapped(vpn)
@@ -453,12 +717,27 @@ impl TaskControlBlock {
         for child_tid in child_tids {
             let task = process_inner.tasks.get(&child_tid).unwrap();
             match (task.is_zombie(), tid as usize == child_tid, tid) {
+                // the exited root task is kept until the process exits, it can be joined repeatedly
+                (true, true, _) if child_tid == ROOT_TID => {
+                    let exit_code = task.inner_access().exit_code.unwrap();
+                    process_inner
+                        .space
+                        .copy_value_to_user(exit_code_ptr, &(exit_code as i32))?;
+                    return Ok(ROOT_TID as isize);
+                }
+                (true, _, -1) if child_tid == ROOT_TID => continue,
                 (true, _, -1) | (true, true, _) => {
                     let task = process_inner.tasks.remove(&child_tid).unwrap();
                     assert_eq!(Arc::strong_count(&task), 1);
+                    // keep the CPU time of the exited task in the process
+                    let cpu_time = task.cpu_time();
+                    process_inner.exited_cpu_time.merge(&cpu_time);
+                    let rusage = task.rusage();
+                    process_inner.exited_rusage.merge(&rusage);
                     let exit_code = task.inner_access().exit_code.unwrap();
-                    let real_exit_code = process_inner.space.translated_refmut(exit_code_ptr)?;
-                    *real_exit_code = exit_code as i32;
+                    process_inner
+                        .space
+                        .copy_value_to_user(exit_code_ptr, &(exit_code as i32))?;
                     return Ok(child_tid as isize);
                 }
                 (false, true, _) => return Ok(-2),
@@ -472,6 +751,75 @@ impl TaskControlBlock {
         }
     }
 
+    /// Clear the word at the `clear_tid` address and wake up one task waiting on it,
+    /// so that other threads can join the exited thread by futex.
+    fn clear_child_tid(&self) {
+        let clear_tid = if let Some(clear_tid) = self.inner_exclusive_access().clear_tid.take() {
+            clear_tid
+        } else {
+            return;
+        };
+        let process = self.process();
+        let process_inner = process.inner_access();
+        // the word which is not mapped any more is just ignored
+        let _ = process_inner
+            .space
+            .copy_value_to_user(clear_tid as *mut u32, &0);
+        let pa = process_inner.space.translate_pa(clear_tid);
+        drop(process_inner);
+        if let Some(pa) = pa {
+            FUTEX_TABLE.wake(pa, 1);
+        }
+    }
+
+    /// Register the task which is blocked to join the current task
+    ///
+    /// - Arguments
+    ///     - joiner: the task which is joining the current task
+    pub(crate) fn add_joiner(self: &Arc<Self>, joiner: &Arc<TaskControlBlock>) {
+        self.inner_exclusive_access()
+            .joiners
+            .push(Arc::downgrade(joiner));
+        joiner.inner_exclusive_access().joining = Some(Arc::downgrade(self));
+    }
+
+    /// Check if the current task joining the target task will cause deadlock,
+    /// which means the target task is the current task itself,
+    /// or the target task is joining the current task directly or indirectly.
+    ///
+    /// - Arguments
+    ///     - target: the task which the current task will join
+    ///
+    /// - Errors
+    ///     - JoinDeadlock(tid)
+    pub(crate) fn check_join(&self, target: &Arc<TaskControlBlock>) -> Result<()> {
+        let mut next = Some(Arc::clone(target));
+        while let Some(task) = next {
+            if core::ptr::eq(Arc::as_ptr(&task), self) {
+                return Err(KernelError::JoinDeadlock(target.tid()));
+            }
+            next = task
+                .inner_access()
+                .joining
+                .as_ref()
+                .and_then(|joining| joining.upgrade());
+        }
+        Ok(())
+    }
+
+    /// Wake up all the blocked tasks which are joining the current task,
+    /// they will collect the exit code by themselves after being woken up.
+    fn wake_joiners(&self) {
+        let joiners = core::mem::take(&mut self.inner_exclusive_access().joiners);
+        for joiner in joiners.iter().filter_map(|joiner| joiner.upgrade()) {
+            joiner.inner_exclusive_access().joining = None;
+            if joiner.is_blocked() {
+                joiner.mark_suspended();
+                TASK_SCHEDULER.put_read_task(joiner);
+            }
+        }
+    }
+
     /// See [`TaskControlBlockInner::release_user_resource`]
     fn release_user_resource(&self, exit_code: usize) {
         self.inner_exclusive_access()
@@ -480,13 +828,15 @@ impl TaskControlBlock {
 
     /// Get the inmutable inner structure
     #[inline(always)]
-    pub(crate) fn inner_access(&self) -> Ref<'_, TaskControlBlockInner> {
+    #[track_caller]
+    pub(crate) fn inner_access(&self) -> UserPromiseRef<'_, TaskControlBlockInner> {
         self.inner.access()
     }
 
     /// Get the mutable inner structure
     #[inline(always)]
-    pub(crate) fn inner_exclusive_access(&self) -> RefMut<'_, TaskControlBlockInner> {
+    #[track_caller]
+    pub(crate) fn inner_exclusive_access(&self) -> UserPromiseRefMut<'_, TaskControlBlockInner> {
         self.inner.exclusive_access()
     }
 
@@ -501,6 +851,45 @@ impl TaskControlBlock {
         self.process.upgrade().unwrap()
     }
 
+    /// Get the byte size of the current task's kernel stack
+    pub(crate) fn kernel_stack_size(&self) -> usize {
+        self.kernel_stack.byte_size()
+    }
+
+    /// Check whether the virtual address is in the guard pages below the task's kernel stack.
+    /// It never borrows the task, because it is used by the fatal trap handler.
+    ///
+    /// - Arguments
+    ///     - va: the faulting virtual address
+    pub(crate) fn is_kernel_stack_guard(&self, va: usize) -> bool {
+        let (start_va, end_va) = Space::get_kernel_task_stack_guard_va_range(
+            self.kernel_stack.id(),
+            self.kernel_stack.byte_size(),
+        );
+        (start_va..end_va).contains(&va)
+    }
+
+    /// Get the byte size of the current task's user stack,
+    /// None will be returned if the user resource of the task was released
+    pub(crate) fn user_stack_size(&self) -> Option<usize> {
+        self.inner_access()
+            .user_resource
+            .as_ref()
+            .map(|resource| resource.stack_size)
+    }
+
+    /// Get the unique ids of the task's process and itself without panic,
+    /// None will be returned if the process was dropped or the task was borrowed exclusively.
+    /// It is used by the panic handler, so it must never panic again.
+    ///
+    /// - Returns
+    ///     - Some((pid, tid))
+    pub(crate) fn try_ids(&self) -> Option<(usize, usize)> {
+        let pid = self.process.upgrade()?.pid();
+        let tid = self.inner.try_access()?.user_resource.as_ref()?.tracker.id();
+        Some((pid, tid))
+    }
+
     /// Get the current task's unique id
     pub(crate) fn tid(&self) -> usize {
         self.inner_access()
@@ -524,44 +913,311 @@ impl TaskControlBlock {
         }
     }
 
-    /// Check current task if is zombie status
+    /// Check current task if is zombie status,
+    /// the exited root task may still keep its user resource
     pub(crate) fn is_zombie(&self) -> bool {
-        let inner = self.inner_access();
-        inner.user_resource.is_none() && inner.status.is_zombie()
+        self.inner_access().status.is_zombie()
+    }
+
+    /// Check current task if is running status
+    pub(crate) fn is_running(&self) -> bool {
+        self.inner_access().status.is_running()
+    }
+
+    /// Check current task if is blocked status, the sleeping tasks are also blocked
+    pub(crate) fn is_blocked(&self) -> bool {
+        self.inner_access().status.is_blocked()
+    }
+
+    /// Get the execution status of current task
+    pub(crate) fn status(&self) -> TaskStatus {
+        self.inner_access().status
     }
 
     /// Mark current task as suspended task
     pub(crate) fn mark_suspended(&self) {
-        let mut inner = self.inner_exclusive_access();
-        inner.status = TaskStatus::Ready;
+        self.inner_exclusive_access().set_status(TaskStatus::Ready);
     }
 
-    /// Mark current task as running task
+    /// Mark current task as running task, and restart the accounting of the CPU time,
+    /// so that the time when the task was not running will not be attributed to it
     pub(crate) fn mark_running(&self) {
         let mut inner = self.inner_exclusive_access();
-        inner.status = TaskStatus::Running;
+        inner.set_status(TaskStatus::Running);
+        inner.cpu_time.restart(SBI::get_timer());
+    }
+
+    /// Accumulate the CPU time elapsed since the last accounting
+    ///
+    /// - Arguments
+    ///     - user: if true the time is attributed to user time, otherwise kernel time
+    pub(crate) fn account_cpu_time(&self, user: bool) {
+        self.inner_exclusive_access()
+            .cpu_time
+            .account(SBI::get_timer(), user);
+    }
+
+    /// Get the CPU time consumed by the task
+    pub(crate) fn cpu_time(&self) -> CpuTime {
+        self.inner_access().cpu_time
+    }
+
+    /// Count a context switch of the task
+    ///
+    /// - Arguments
+    ///     - voluntary: if true the task yielded or blocked, otherwise it was preempted
+    pub(crate) fn account_switch(&self, voluntary: bool) {
+        let mut inner = self.inner_exclusive_access();
+        if voluntary {
+            inner.rusage.voluntary_switches += 1;
+        } else {
+            inner.rusage.involuntary_switches += 1;
+        }
+    }
+
+    /// Update the max count of the frames resident in the space of the task
+    ///
+    /// - Arguments
+    ///     - frames: the current count of the resident frames
+    pub(crate) fn account_resident_frames(&self, frames: usize) {
+        let mut inner = self.inner_exclusive_access();
+        inner.rusage.max_resident_frames = inner.rusage.max_resident_frames.max(frames);
+    }
+
+    /// Count a block serviced by the block device for the task
+    ///
+    /// - Arguments
+    ///     - write: if true the block was written, otherwise it was read
+    pub(crate) fn account_block_io(&self, write: bool) {
+        let mut inner = self.inner_exclusive_access();
+        if write {
+            inner.rusage.output_blocks += 1;
+        } else {
+            inner.rusage.input_blocks += 1;
+        }
+    }
+
+    /// Get the resource usage of the task
+    pub(crate) fn rusage(&self) -> RUsage {
+        self.inner_access().rusage
+    }
+
+    /// Map the released page which contains the faulting virtual address again,
+    /// and count the page fault by its kind.
+    ///
+    /// - Arguments
+    ///     - va: the faulting virtual address
+    ///
+    /// - Returns
+    ///     - Ok(true): the page was mapped again, the faulting instruction can be executed
+    ///     - Ok(false): the page was not released
+    ///
+    /// - Errors
+    ///     - VPNAlreadyMapped(vpn)
+    ///     - FrameExhausted
+    ///     - AllocFullPageMapper(ppn)
+    ///     - SwapIOFailed(slot)
+    ///     - IdNotDeallocable(slot)
+    pub(crate) fn refault(&self, va: usize) -> Result<bool> {
+        let process = self.process();
+        let process_inner = process.inner_access();
+        let kind = match process_inner.space.refault(va)? {
+            Some(kind) => kind,
+            None => return Ok(false),
+        };
+        let frames = process_inner.space.resident_frames();
+        let mut inner = self.inner_exclusive_access();
+        match kind {
+            RefaultKind::Minor => inner.rusage.minor_faults += 1,
+            RefaultKind::Major => inner.rusage.major_faults += 1,
+        }
+        inner.rusage.max_resident_frames = inner.rusage.max_resident_frames.max(frames);
+        Ok(true)
+    }
+
+    /// Get the level of the task in the multi-level feedback queues
+    pub(crate) fn sched_level(&self) -> usize {
+        self.inner_access().sched_level
+    }
+
+    /// Set the level of the task in the multi-level feedback queues
+    ///
+    /// - Arguments
+    ///     - level: the new level, zero is the highest level
+    pub(crate) fn set_sched_level(&self, level: usize) {
+        self.inner_exclusive_access().sched_level = level;
+    }
+
+    /// Send the signal to the task specifically, the sleeping task will be woken up,
+    /// so that it can be interrupted by the signal.
+    ///
+    /// - Arguments
+    ///     - signal: the value of the signal send from user mode
+    ///
+    /// - Errors
+    ///     - DuplicateSignal(signal)
+    pub(crate) fn kill(self: &Arc<Self>, signal: Signal) -> Result<()> {
+        self.inner_exclusive_access().signal.try_kill(signal)?;
+        TASK_SCHEDULER.wake_sleep_task(self);
+        Ok(())
+    }
+
+    /// Check whether the task has pending signals sent to it specifically,
+    /// the signals blocked by the mask of the process are not counted
+    pub(crate) fn has_pending_signal(&self) -> bool {
+        let masked = self.process().inner_access().signal.masked();
+        self.inner_access().signal.has_pending_signal(masked)
+    }
+
+    /// Check whether the task has pending signals which will take effect once they are handled,
+    /// including the process-wide signals and the signals sent to the task specifically,
+    /// see [`SignalControlBlock::has_deliverable_signal`]
+    pub(crate) fn has_deliverable_signal(&self) -> bool {
+        let process = self.process();
+        let process_inner = process.inner_access();
+        let sent = self.inner_access().signal.pending_signals(process_inner.signal.masked());
+        process_inner.signal.has_deliverable_signal(sent)
+    }
+
+    /// Mark the syscall of the task as interrupted by a signal,
+    /// the first argument of the syscall is kept because the return value will overwrite it.
+    /// It must be called before the syscall returns.
+    ///
+    /// - Errors
+    ///     - AreaNotExists(start_vpn, end_vpn)
+    ///     - VPNNotMapped(vpn)
+    pub(crate) fn mark_syscall_interrupted(&self) -> Result<()> {
+        let process = self.process();
+        let process_inner = process.inner_access();
+        let mut inner = self.inner_exclusive_access();
+        let arg = inner.modify_trap_ctx(&process_inner.space, |trap_ctx| Ok(trap_ctx.get_arg(0)))?;
+        inner.interrupted_syscall.replace(arg);
+        Ok(())
+    }
+
+    /// Replace the first argument which the interrupted syscall will be executed again with,
+    /// so that the restarted syscall continues with its progress instead of starting over.
+    /// It must be called after [`TaskControlBlock::mark_syscall_interrupted`].
+    ///
+    /// - Arguments
+    ///     - arg: the first argument of the restarted syscall
+    pub(crate) fn set_interrupted_syscall_arg(&self, arg: usize) {
+        if let Some(interrupted) = self.inner_exclusive_access().interrupted_syscall.as_mut() {
+            *interrupted = arg;
+        }
+    }
+
+    /// Get the first argument which the interrupted syscall will be executed again with
+    #[cfg(test)]
+    pub(crate) fn interrupted_syscall_arg(&self) -> Option<usize> {
+        self.inner_access().interrupted_syscall
+    }
+
+    /// Forget the interrupted syscall which was not restarted by any signal handler
+    pub(crate) fn clear_interrupted_syscall(&self) {
+        self.inner_exclusive_access().interrupted_syscall.take();
+    }
+
+    /// Handle the signals sent to the task specifically.
+    /// The signal with custom handler will be delivered in the task's own trap context,
+    /// and the signal with default action will be forwarded to the process,
+    /// so the default actions are still taken by the task handling the process-wide signals.
+    /// That task will not handle its own signal while it is handling a process-wide signal,
+    /// because they share the same trap context.
+    ///
+    /// - Errors
+    ///     - AreaNotExists(start_vpn, end_vpn)
+    ///     - VPNNotMapped(vpn)
+    pub(crate) fn handle_own_signals(&self) -> Result<()> {
+        let process = self.process();
+        let process_inner = process.inner_access();
+        if self.tid() == process_inner.signal_tid && process_inner.signal.is_handling() {
+            return Ok(());
+        }
+        let masked = process_inner.signal.masked();
+        let signal = match self.inner_exclusive_access().signal.pop_signal(masked) {
+            Some(signal) => signal,
+            None => return Ok(()),
+        };
+        let handler = process_inner.signal.get_action(signal).handler();
+        if handler.is_null() {
+            drop(process_inner);
+            debug!("Forward task signal {:?} with default action to process", signal);
+            // the same signal may be already pending in the process, they will be collapsed
+            return match process.kill(signal) {
+                Err(KernelError::DuplicateSignal(_)) => Ok(()),
+                other => other,
+            };
+        }
+        debug!("Handle task signal {:?} with custom action: {}", signal, handler as usize);
+        let sp = process_inner.signal.alt_stack_top(signal);
+        let restart = process_inner.signal.is_restart(signal);
+        let mut inner = self.inner_exclusive_access();
+        let mut trap_ctx_backup = inner.modify_trap_ctx(&process_inner.space, |trap_ctx| {
+            let trap_ctx_backup = trap_ctx.clone();
+            trap_ctx.sepc = handler as usize;
+            trap_ctx.set_arg(0, signal as usize);
+            if let Some(sp) = sp {
+                trap_ctx.set_sp(sp);
+            }
+            Ok(trap_ctx_backup)
+        })?;
+        inner.restart_interrupted_syscall(restart, &mut trap_ctx_backup);
+        inner.signal.backup(signal, trap_ctx_backup);
+        Ok(())
+    }
+
+    /// Clear the signal being processed by the task itself, and resume its normal trap context,
+    /// see [`ProcessControlBlockInner::signal_return`]
+    ///
+    /// - Returns
+    ///     - Some(isize): the signal value
+    ///     - None: the task is not handling its own signal
+    ///
+    /// - Errors
+    ///     - AreaNotExists(start_vpn, end_vpn)
+    ///     - VPNNotMapped(vpn)
+    pub(crate) fn signal_return(&self) -> Result<Option<isize>> {
+        let process = self.process();
+        let process_inner = process.inner_access();
+        let mut inner = self.inner_exclusive_access();
+        match inner.signal.rollback() {
+            Some(trap_ctx_backup) => inner.modify_trap_ctx(&process_inner.space, |trap_ctx| {
+                *trap_ctx = trap_ctx_backup;
+                Ok(Some(trap_ctx.get_arg(0) as isize))
+            }),
+            None => Ok(None),
+        }
     }
 
     /// Mark current task as bloced task
     pub(crate) fn mark_blocked(&self) {
-        let mut inner = self.inner_exclusive_access();
-        inner.status = TaskStatus::Blocked;
+        self.inner_exclusive_access().set_status(TaskStatus::Blocked);
     }
 
     /// Mark current task as zombie task.
-    /// If current task is the root task of the process,
-    /// all other tasks in the same process will be change to zombie status as well.
+    /// If current task is the last live task of the process, no matter it is the root task or not,
+    /// the whole process will be change to zombie status with the exit code of current task.
+    /// The exited root task keeps its user resource until the process exits,
+    /// the process-wide signals will be delivered to the other live tasks after it exits.
     ///
     /// - Arguments
     ///     - exit_code: the exit code of current task
     pub(crate) fn mark_zombie(&self, exit_code: i32) {
+        let process = self.process();
+        if process.inner_access().live_task_count() <= 1 {
+            process.mark_zombie(exit_code);
+            assert!(process.is_zombie());
+            return;
+        }
+        self.clear_child_tid();
         if self.tid() != ROOT_TID {
             self.release_user_resource(exit_code as usize);
         } else {
-            let process = self.process();
-            process.mark_zombie(exit_code);
-            assert!(process.is_zombie());
+            self.inner_exclusive_access()
+                .mark_exited(exit_code as usize);
         }
+        self.wake_joiners();
     }
 }
 
@@ -579,6 +1235,9 @@ pub(crate) struct ProcessControlBlockInner {
     parent: Option<Weak<ProcessControlBlock>>,
     /// Child process of the current process
     childrens: BTreeMap<usize, Arc<ProcessControlBlock>>,
+    /// The pids of the children which were adopted from the exited processes,
+    /// they will be reaped by the kernel instead of waiting by the current process
+    orphans: BTreeSet<usize>,
     /// Store the exit code which define when task exiting
     exit_code: Option<usize>,
     /// The table of the files which is using by process
@@ -589,10 +1248,43 @@ pub(crate) struct ProcessControlBlockInner {
     semaphore_table: Vec<Option<Arc<dyn Semaphore>>>,
     /// The condition variable resource of which is using by process
     condvar_table: Vec<Option<Arc<dyn Condvar>>>,
+    /// The barrier resource of which is using by process
+    barrier_table: Vec<Option<Arc<Barrier>>>,
     /// The block information all about signal
     signal: SignalControlBlock,
+    /// The id of the task which the latest process-wide signal was delivered to
+    signal_tid: usize,
     /// All the tasks belongs to the current process
     tasks: BTreeMap<usize, Arc<TaskControlBlock>>,
+    /// The line discipline used when the process reading from console
+    console_discipline: LineDiscipline,
+    /// The process group id, signals can be sent to all processes in the same group
+    pgid: usize,
+    /// The session id, processes can only join the process groups in the same session
+    sid: usize,
+    /// The CPU time consumed by the tasks which have exited and been removed
+    exited_cpu_time: CpuTime,
+    /// The resource usage of the tasks which have exited and been removed
+    exited_rusage: RUsage,
+    /// The resource usage of the children which have exited and been waited
+    children_rusage: RUsage,
+    /// The limit of the file descriptors, the new descriptor must be less than the soft limit
+    nofile_limit: RLimit,
+    /// The limit of the bytes mapped in the address space
+    space_limit: RLimit,
+    /// The limit of the tasks in the process, which also limits the living child processes
+    task_limit: RLimit,
+    /// The user id of the process, which is inherited on fork and preserved on exec
+    uid: usize,
+    /// The group id of the process, which is inherited on fork and preserved on exec
+    gid: usize,
+    /// The process which is tracing the current process, the tracer is never inherited on fork
+    tracer: Option<Weak<ProcessControlBlock>>,
+    /// The breakpoints inserted by the single-step and the original half words they replaced
+    step_breakpoints: Vec<(usize, u16)>,
+    /// The address and the byte length of the ebreak instruction which stopped the root task,
+    /// it will be skipped when the traced process is continued
+    reached_breakpoint: Option<(usize, usize)>,
 }
 impl ProcessControlBlockInner {
     /// Create a new inner process control block.
@@ -617,13 +1309,30 @@ impl ProcessControlBlockInner {
             space,
             parent: None,
             childrens: BTreeMap::new(),
+            orphans: BTreeSet::new(),
             exit_code: None,
             fd_table,
             mutex_table: Vec::new(),
             semaphore_table: Vec::new(),
             condvar_table: Vec::new(),
+            barrier_table: Vec::new(),
             signal: SignalControlBlock::new(),
+            signal_tid: ROOT_TID,
             tasks: BTreeMap::new(),
+            console_discipline: LineDiscipline::default(),
+            pgid: ROOT_PID,
+            sid: ROOT_PID,
+            exited_cpu_time: CpuTime::default(),
+            exited_rusage: RUsage::default(),
+            children_rusage: RUsage::default(),
+            nofile_limit: RLimit::new(configs::MAX_FD_COUNT),
+            space_limit: RLimit::new(usize::MAX),
+            task_limit: RLimit::new(configs::MAX_TID_COUNT),
+            uid: ROOT_UID,
+            gid: ROOT_GID,
+            tracer: None,
+            step_breakpoints: Vec::new(),
+            reached_breakpoint: None,
         }
     }
 
@@ -647,21 +1356,75 @@ impl ProcessControlBlockInner {
         self.exit_code.replace(exit_code);
     }
 
+    /// Get the path of the process code data in file system
+    pub(crate) fn path(&self) -> &str {
+        &self.path
+    }
+
     /// Get the root task of process
     pub(crate) fn root_task(&self) -> Arc<TaskControlBlock> {
         Arc::clone(self.tasks.get(&ROOT_TID).unwrap())
     }
 
+    /// Get the task which the process-wide signals are delivered to.
+    /// While a process-wide signal is being handled, the task handling it is kept,
+    /// so that the nested signals and the signal return use the same trap context.
+    /// Otherwise the root task is chosen if it has not exited,
+    /// or the live task with the smallest id.
+    pub(crate) fn signal_task(&self) -> Arc<TaskControlBlock> {
+        if self.signal.is_handling() {
+            if let Some(task) = self.tasks.get(&self.signal_tid) {
+                return Arc::clone(task);
+            }
+        }
+        let task = self
+            .tasks
+            .get(&ROOT_TID)
+            .filter(|task| !task.is_zombie())
+            .or_else(|| self.tasks.values().find(|task| !task.is_zombie()))
+            .unwrap_or_else(|| self.tasks.get(&ROOT_TID).unwrap());
+        Arc::clone(task)
+    }
+
+    /// Get the task of process by the task id
+    ///
+    /// - Arguments
+    ///     - tid: the task id in the process
+    pub(crate) fn task(&self, tid: usize) -> Option<Arc<TaskControlBlock>> {
+        self.tasks.get(&tid).map(|task| Arc::clone(task))
+    }
+
     /// Get the space of process
     pub(crate) fn space(&self) -> &Space {
         &self.space
     }
 
+    /// Get the mutable space of process
+    pub(crate) fn space_mut(&mut self) -> &mut Space {
+        &mut self.space
+    }
+
     /// Set the signal masking and return previous version masking
     pub(crate) fn exchange_singal_mask(&mut self, mask: SignalFlags) -> SignalFlags {
         self.signal.mask(mask)
     }
 
+    /// Install a temporary signal masking until the signal was handled,
+    /// see [`SignalControlBlock::suspend`]
+    pub(crate) fn suspend_signal_mask(&mut self, mask: SignalFlags) {
+        self.signal.suspend(mask)
+    }
+
+    /// Check whether any signal is pending
+    pub(crate) fn has_pending_signal(&self) -> bool {
+        self.signal.has_pending_signal()
+    }
+
+    /// Get the signals which were setted but blocked by the masking
+    pub(crate) fn blocked_signals(&self) -> SignalFlags {
+        self.signal.blocked_signals()
+    }
+
     /// Get the action according to signal
     pub(crate) fn get_signal_action(&self, signal: Signal) -> SignalAction {
         self.signal.get_action(signal)
@@ -672,14 +1435,47 @@ impl ProcessControlBlockInner {
         self.signal.set_action(signal, action)
     }
 
+    /// Set whether the syscall interrupted by the signal will be restarted,
+    /// see [`SignalControlBlock::set_restart`]
+    pub(crate) fn set_signal_restart(&mut self, signal: Signal, restart: bool) {
+        self.signal.set_restart(signal, restart)
+    }
+
+    /// Check whether the syscall interrupted by the signal will be restarted,
+    /// see [`SignalControlBlock::is_restart`]
+    pub(crate) fn is_signal_restart(&self, signal: Signal) -> bool {
+        self.signal.is_restart(signal)
+    }
+
+    /// Exchange the alternate stack of the signal handlers and return the previous one
+    pub(crate) fn exchange_signal_alt_stack(&mut self, stack: SignalStack) -> SignalStack {
+        self.signal.exchange_alt_stack(stack)
+    }
+
+    /// Get the alternate stack of the signal handlers
+    pub(crate) fn signal_alt_stack(&self) -> SignalStack {
+        self.signal.alt_stack()
+    }
+
+    /// Get the action according to real-time signal number
+    pub(crate) fn get_rt_signal_action(&self, signum: usize) -> SignalAction {
+        self.signal.get_rt_action(signum)
+    }
+
+    /// Set a new action by real-time signal number
+    pub(crate) fn set_rt_signal_action(&mut self, signum: usize, action: SignalAction) {
+        self.signal.set_rt_action(signum, action)
+    }
+
     /// Clear the signal being processed, and resume the normal trap context.
     /// We use the value of a0 in trap_ctx as the return value of the system call instead of using a specific value like 0,
     /// otherwise when the user-mode recovery trap context is returned,
     /// the a0 register in the original process context will be overwritten by these specific values,
     /// making it impossible for the process to resume normal execution after the signal processing is complete.
     ///
-    /// Be careful, we will use the root task to handler signal,
-    /// so the trap context will also be resumed to root task's trap context.
+    /// Be careful, the process-wide signal is handled by the task chosen by [`Self::signal_task`],
+    /// so the trap context will also be resumed to that task's trap context.
+    /// The signals sent to a task specifically are resumed by [`TaskControlBlock::signal_return`].
     ///
     /// - Returns
     ///     - isize: the signal value
@@ -688,9 +1484,9 @@ impl ProcessControlBlockInner {
     ///     - AreaNotExists(start_vpn, end_vpn)
     ///     - VPNNotMapped(vpn)
     pub(crate) fn signal_return(&mut self) -> Result<isize> {
-        let root_task = self.root_task();
-        if let Some((_, trap_ctx_backup)) = self.signal.rollback() {
-            let task_inner = root_task.inner_access();
+        let signal_task = self.signal_task();
+        if let Some(trap_ctx_backup) = self.signal.rollback() {
+            let task_inner = signal_task.inner_access();
             task_inner.modify_trap_ctx(&self.space, |trap_ctx| {
                 *trap_ctx = trap_ctx_backup;
                 Ok(trap_ctx.get_arg(0) as isize)
@@ -762,6 +1558,59 @@ impl ProcessControlBlockInner {
             .and_then(|wrapper| wrapper.as_ref())
     }
 
+    /// Allocate a barrier which releases the tasks each time the count of them have arrived.
+    ///
+    /// - Arguments
+    ///     - count: the count of the tasks in each round, it must be greater than zero
+    ///
+    /// - Errors
+    ///     - BarrierExhausted
+    pub(crate) fn alloc_barrier(&mut self, count: usize) -> Result<usize> {
+        let barrier = Arc::new(Barrier::new(count));
+        for (id, wrapper) in self.barrier_table.iter_mut().enumerate() {
+            if wrapper.is_none() {
+                (*wrapper).replace(Arc::clone(&barrier));
+                return Ok(id);
+            }
+        }
+        let id = self.barrier_table.len();
+        if id >= configs::MAX_BARRIER_COUNT {
+            Err(KernelError::BarrierExhausted)
+        } else {
+            self.barrier_table.push(Some(Arc::clone(&barrier)));
+            Ok(id)
+        }
+    }
+
+    /// Deallocate a barrier and try to dealloc heap resource in task control context.
+    ///
+    /// - Arguments
+    ///     - id: the id of the barrier
+    ///
+    /// - Errors
+    ///     - BarrierDoesNotExist
+    pub(crate) fn dealloc_barrier(&mut self, id: usize) -> Result<()> {
+        let wrapper = self
+            .barrier_table
+            .get_mut(id)
+            .ok_or(KernelError::BarrierDoesNotExist(id))?;
+        wrapper.take();
+        while self.barrier_table.last().is_some_and(|wrapper| wrapper.is_none()) {
+            self.barrier_table.pop();
+        }
+        Ok(())
+    }
+
+    /// Get the barrier immutable reference from task control context
+    ///
+    /// - Arguments
+    ///     - id: the id the barrier
+    pub(crate) fn get_barrier(&self, id: usize) -> Option<&Arc<Barrier>> {
+        self.barrier_table
+            .get(id)
+            .and_then(|wrapper| wrapper.as_ref())
+    }
+
     /// Allocate a semaphore and set initial source count by count argument.
     /// 
     /// - Arguemnts
@@ -836,16 +1685,12 @@ impl ProcessControlBlockInner {
     /// Allocate a mutex.
     /// 
     /// - Arguemnts
-    ///     - blocking: if the mutex is blocking type
+    ///     - kind: the kind of the mutex
     ///
     /// - Errors
     ///     - MutexExhausted
-    pub(crate) fn alloc_mutex(&mut self, blocking: bool) -> Result<usize> {
-        let mutex: Arc<dyn Mutex> = if blocking {
-            Arc::new(MutexBlocking::new())
-        } else {
-            Arc::new(MutexSpin::new())
-        };
+    pub(crate) fn alloc_mutex(&mut self, kind: MutexKind) -> Result<usize> {
+        let mutex = kind.create();
         for (id, wrapper) in self.mutex_table.iter_mut().enumerate() {
             if wrapper.is_none() {
                 (*wrapper).replace(Arc::clone(&mutex));
@@ -899,6 +1744,7 @@ impl ProcessControlBlockInner {
     }
 
     /// Allocate a file descriptor and set the file object into task control block context.
+    /// The new file descriptor must be less than the soft limit of the file descriptors.
     ///
     /// - Arguments
     ///     - file: the object which impl File trait
@@ -906,14 +1752,15 @@ impl ProcessControlBlockInner {
     /// - Errors
     ///     - FileDescriptorExhausted
     pub(crate) fn alloc_fd(&mut self, file: Arc<dyn File>) -> Result<usize> {
-        for (fd, wrapper) in self.fd_table.iter_mut().enumerate() {
+        let limit = self.nofile_limit.soft;
+        for (fd, wrapper) in self.fd_table.iter_mut().enumerate().take(limit) {
             if wrapper.is_none() {
                 (*wrapper).replace(file);
                 return Ok(fd);
             }
         }
         let fd = self.fd_table.len();
-        if fd >= configs::MAX_FD_COUNT {
+        if fd >= limit {
             Err(KernelError::FileDescriptorExhausted)
         } else {
             self.fd_table.push(Some(file));
@@ -948,6 +1795,128 @@ impl ProcessControlBlockInner {
         Ok(())
     }
 
+    /// Get the limit of the file descriptors
+    pub(crate) fn nofile_limit(&self) -> RLimit {
+        self.nofile_limit
+    }
+
+    /// Set the limit of the file descriptors.
+    /// Lowering the soft limit below the opened file descriptors is allowed,
+    /// the opened files are kept but no more file descriptors can be allocated.
+    ///
+    /// - Arguments
+    ///     - limit: the new limit, the hard limit must not be raised
+    ///
+    /// - Returns
+    ///     - true: the limit was set
+    ///     - false: the limit is invalid
+    pub(crate) fn set_nofile_limit(&mut self, limit: RLimit) -> bool {
+        self.nofile_limit.replace(limit)
+    }
+
+    /// Get the limit of the bytes mapped in the address space
+    pub(crate) fn space_limit(&self) -> RLimit {
+        self.space_limit
+    }
+
+    /// Set the limit of the bytes mapped in the address space,
+    /// the mapped areas are kept even if they exceed the new soft limit.
+    ///
+    /// - Arguments
+    ///     - limit: the new limit, the hard limit must not be raised
+    ///
+    /// - Returns
+    ///     - true: the limit was set
+    ///     - false: the limit is invalid
+    pub(crate) fn set_space_limit(&mut self, limit: RLimit) -> bool {
+        self.space_limit.replace(limit)
+    }
+
+    /// Get the limit of the tasks in the process
+    pub(crate) fn task_limit(&self) -> RLimit {
+        self.task_limit
+    }
+
+    /// Set the limit of the tasks in the process,
+    /// the living tasks and child processes are kept even if they exceed the new soft limit.
+    ///
+    /// - Arguments
+    ///     - limit: the new limit, the hard limit must not be raised
+    ///
+    /// - Returns
+    ///     - true: the limit was set
+    ///     - false: the limit is invalid
+    pub(crate) fn set_task_limit(&mut self, limit: RLimit) -> bool {
+        self.task_limit.replace(limit)
+    }
+
+    /// Check if the bytes can be mapped into the address space without exceeding the soft limit
+    ///
+    /// - Arguments
+    ///     - byte_size: the byte size which will be mapped
+    ///
+    /// - Errors
+    ///     - SpaceLimitExceeded(limit)
+    fn check_space_limit(&self, byte_size: usize) -> Result<()> {
+        let limit = self.space_limit.soft;
+        if self.space.mapped_byte_size().saturating_add(byte_size) > limit {
+            Err(KernelError::SpaceLimitExceeded(limit))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Check if one more task or child process can be created without exceeding the soft limit
+    ///
+    /// - Arguments
+    ///     - count: the count of the living tasks or child processes
+    ///
+    /// - Errors
+    ///     - TaskLimitExceeded(limit)
+    fn check_task_limit(&self, count: usize) -> Result<()> {
+        let limit = self.task_limit.soft;
+        if count >= limit {
+            Err(KernelError::TaskLimitExceeded(limit))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Attach the shared memory segment into the address space, see [`Space::attach_shm`]
+    ///
+    /// - Arguments
+    ///     - id: the id of the shared memory segment
+    ///
+    /// - Errors
+    ///     - ShmDoesNotExist(id)
+    ///     - SpaceLimitExceeded(limit)
+    ///     - AreaAllocFailed(start_vpn, end_vpn)
+    ///     - VPNAlreadyMapped(vpn)
+    ///     - FrameExhausted
+    ///     - AllocFullPageMapper(ppn)
+    pub(crate) fn attach_shm(&mut self, id: usize) -> Result<usize> {
+        let byte_size = SHM_TABLE
+            .byte_size(id)
+            .ok_or(KernelError::ShmDoesNotExist(id))?;
+        self.check_space_limit(byte_size)?;
+        self.space.attach_shm(id)
+    }
+
+    /// Get the count of the opened files
+    pub(crate) fn fd_count(&self) -> usize {
+        self.fd_table.iter().filter(|wrapper| wrapper.is_some()).count()
+    }
+
+    /// Get the count of the tasks which have not been recycled
+    pub(crate) fn task_count(&self) -> usize {
+        self.tasks.len()
+    }
+
+    /// Get the count of the tasks which have not exited
+    pub(crate) fn live_task_count(&self) -> usize {
+        self.tasks.values().filter(|task| !task.is_zombie()).count()
+    }
+
     /// Get the reference of the file object by file descriptor
     ///
     /// - Arguments
@@ -955,6 +1924,174 @@ impl ProcessControlBlockInner {
     pub(crate) fn get_file(&self, fd: usize) -> Option<&Arc<dyn File>> {
         self.fd_table.get(fd).and_then(|wrapper| wrapper.as_ref())
     }
+
+    /// Get the line discipline used when the process reading from console
+    pub(crate) fn console_discipline(&self) -> LineDiscipline {
+        self.console_discipline
+    }
+
+    /// Set the line discipline used when the process reading from console
+    ///
+    /// - Arguments
+    ///     - discipline: the new line discipline
+    pub(crate) fn set_console_discipline(&mut self, discipline: LineDiscipline) {
+        self.console_discipline = discipline;
+    }
+
+    /// Get the reference of the child process by process id
+    ///
+    /// - Arguments
+    ///     - pid: the id of the child process
+    pub(crate) fn get_child(&self, pid: usize) -> Option<&Arc<ProcessControlBlock>> {
+        self.childrens.get(&pid)
+    }
+
+    /// Get the process group id
+    pub(crate) fn pgid(&self) -> usize {
+        self.pgid
+    }
+
+    /// Set the process group id
+    ///
+    /// - Arguments
+    ///     - pgid: the new process group id
+    fn set_pgid(&mut self, pgid: usize) {
+        self.pgid = pgid;
+    }
+
+    /// Get the session id
+    pub(crate) fn sid(&self) -> usize {
+        self.sid
+    }
+
+    /// Get the user id of the process
+    pub(crate) fn uid(&self) -> usize {
+        self.uid
+    }
+
+    /// Get the group id of the process
+    pub(crate) fn gid(&self) -> usize {
+        self.gid
+    }
+
+    /// Check if the process is traced by an alive tracer
+    fn is_traced(&self) -> bool {
+        self.tracer
+            .as_ref()
+            .is_some_and(|tracer| tracer.strong_count() > 0)
+    }
+
+    /// Get the credential which the accesses of the process are checked with
+    pub(crate) fn credential(&self) -> Credential {
+        Credential {
+            uid: self.uid,
+            gid: self.gid,
+        }
+    }
+
+    /// Check if the process is run by the privileged user
+    pub(crate) fn is_privileged(&self) -> bool {
+        self.credential().is_privileged()
+    }
+
+    /// Set the user id of the process.
+    /// Only the privileged process can change to another user,
+    /// and it will lose the privilege after changing to the unprivileged user.
+    ///
+    /// - Arguments
+    ///     - uid: the new user id, must be the root uid or the unprivileged uid
+    ///
+    /// - Returns
+    ///     - true: the user id was set
+    ///     - false: the user id is unknown or the process is not privileged
+    pub(crate) fn set_uid(&mut self, uid: usize) -> bool {
+        if !matches!(uid, ROOT_UID | USER_UID) || (uid != self.uid && !self.is_privileged()) {
+            return false;
+        }
+        self.uid = uid;
+        true
+    }
+
+    /// Set the group id of the process, only the privileged process can change to another group
+    ///
+    /// - Arguments
+    ///     - gid: the new group id, must be the root gid or the unprivileged gid
+    ///
+    /// - Returns
+    ///     - true: the group id was set
+    ///     - false: the group id is unknown or the process is not privileged
+    pub(crate) fn set_gid(&mut self, gid: usize) -> bool {
+        if !matches!(gid, ROOT_GID | USER_GID) || (gid != self.gid && !self.is_privileged()) {
+            return false;
+        }
+        self.gid = gid;
+        true
+    }
+
+    /// Get the parent process.
+    /// Return None if the current process is the `initproc` or the parent process was dropped.
+    pub(crate) fn parent(&self) -> Option<Arc<ProcessControlBlock>> {
+        self.parent.as_ref().and_then(|parent| parent.upgrade())
+    }
+}
+
+/// The signal which will be delivered to the custom handler,
+/// see [`ProcessControlBlock::handle_all_signals`]
+struct SignalDelivery {
+    /// The standard signal, or None if it is a real-time signal
+    signal: Option<Signal>,
+    /// The number of the signal which will be passed to the handler as the first argument
+    signum: usize,
+    /// The virtual address of the handler
+    handler: usize,
+    /// The value which will be passed to the handler as the second argument
+    value: Option<usize>,
+    /// The stack pointer of the alternate stack
+    sp: Option<usize>,
+    /// Whether the syscall interrupted by the signal will be restarted
+    restart: bool,
+}
+
+/// The information of the process shared with the user space,
+/// the path will be truncated and always end with NULL.
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct ProcessInfo {
+    pub(crate) pid: usize,
+    pub(crate) ppid: usize,
+    /// The value of the [`TaskStatus`]
+    pub(crate) status: usize,
+    pub(crate) path: [u8; configs::PROCESS_INFO_PATH_BYTE_SIZE],
+}
+impl ProcessInfo {
+    /// Create a new process information
+    ///
+    /// - Arguments
+    ///     - pid: the process unique id
+    ///     - ppid: the parent process unique id
+    ///     - status: the execution status of the process
+    ///     - path: the path of the process code data in file system
+    pub(crate) fn new(pid: usize, ppid: usize, status: TaskStatus, path: &str) -> Self {
+        let mut bytes = [0; configs::PROCESS_INFO_PATH_BYTE_SIZE];
+        let length = path.len().min(configs::PROCESS_INFO_PATH_BYTE_SIZE - 1);
+        bytes[..length].copy_from_slice(&path.as_bytes()[..length]);
+        Self {
+            pid,
+            ppid,
+            status: status as usize,
+            path: bytes,
+        }
+    }
+
+    /// Get the bytes of the process information
+    pub(crate) fn as_bytes(&self) -> &[u8] {
+        unsafe {
+            core::slice::from_raw_parts(
+                self as *const Self as *const u8,
+                core::mem::size_of::<Self>(),
+            )
+        }
+    }
 }
 
 pub(crate) struct ProcessControlBlock {
@@ -994,14 +2131,17 @@ impl ProcessControlBlock {
     fn new(path: String, data: &[u8], parent: Option<Arc<Self>>) -> Result<Arc<Self>> {
         let tracker = PID_ALLOCATOR.alloc()?;
         let pid = tracker.id();
-        let (space, base_size, entry_point) = KERNEL_SPACE::new_user_from_elf(pid, data)?;
+        let (space, base_size, entry_point) = KERNEL_SPACE::new_user_from_elf(data)?;
         let fd_table = vec![
             Some(Arc::clone(&STDIN)),
             Some(Arc::clone(&STDOUT)),
             Some(Arc::clone(&STDOUT)),
         ];
-        let inner =
+        let mut inner =
             ProcessControlBlockInner::new(path.clone(), space, entry_point, base_size, fd_table);
+        // the new process is the leader of it's own process group and session
+        inner.pgid = pid;
+        inner.sid = pid;
         debug!(
             "load process {} with pid: {}, base_size: {:#x}, entry_point: {:#x}",
             path, pid, base_size, entry_point
@@ -1022,7 +2162,13 @@ impl ProcessControlBlock {
                 .childrens
                 .insert(pid, Arc::clone(&child));
         };
-        child.alloc_task(entry_point, None)?;
+        child.alloc_task(
+            entry_point,
+            &[],
+            configs::KERNEL_TASK_STACK_BYTE_SIZE,
+            configs::USER_TASK_STACK_BYTE_SIZE,
+        )?;
+        PROCESS_TABLE.register(&child);
         Ok(child)
     }
 
@@ -1031,6 +2177,7 @@ impl ProcessControlBlock {
     /// New process must be the child process of the source process.
     ///
     /// - Errors
+    ///     - TaskLimitExceeded(limit)
     ///     - IdExhausted
     ///     - AreaAllocFailed(start_vpn, end_vpn)
     ///     - VPNOutOfArea(vpn, start_vpn, end_vpn)
@@ -1043,20 +2190,25 @@ impl ProcessControlBlock {
     ///     - VPNNotMapped(vpn)
     fn fork(self: &Arc<Self>) -> Result<Arc<Self>> {
         let mut parent_inner = self.inner_exclusive_access();
+        // the zombie children are not counted, they only wait for being reaped
+        let living = parent_inner.childrens.values().filter(|c| !c.is_zombie()).count();
+        parent_inner.check_task_limit(living)?;
         let path = parent_inner.path.clone();
         let tracker = PID_ALLOCATOR.alloc()?;
         let pid = tracker.id();
         let mut exclude_ranges = BTreeSet::new();
         // exclude all of the tasks trap context and user stack
-        for prev_tid in parent_inner.tasks.keys() {
-            exclude_ranges.insert(Space::get_user_task_stack_vpn_range(
-                parent_inner.base_size,
-                *prev_tid,
-            ));
+        for (prev_tid, prev_task) in parent_inner.tasks.iter() {
+            if let Some(stack_size) = prev_task.user_stack_size() {
+                exclude_ranges.insert(Space::get_user_task_stack_vpn_range(
+                    parent_inner.base_size,
+                    *prev_tid,
+                    stack_size,
+                )?);
+            }
             exclude_ranges.insert(Space::get_task_trap_ctx_vpn_range(*prev_tid));
         }
-        let space =
-            KERNEL_SPACE::new_user_from_another(pid, &parent_inner.space, Some(exclude_ranges))?;
+        let space = KERNEL_SPACE::new_user_from_another(&parent_inner.space, Some(exclude_ranges))?;
         // copy all file descriptors to new process
         let mut fd_table = Vec::new();
         for wrapper in parent_inner.fd_table.iter() {
@@ -1066,13 +2218,21 @@ impl ProcessControlBlock {
                 fd_table.push(None)
             }
         }
-        let inner = ProcessControlBlockInner::new(
+        let mut inner = ProcessControlBlockInner::new(
             path.clone(),
             space,
             parent_inner.entry_point,
             parent_inner.base_size,
             fd_table,
         );
+        inner.console_discipline = parent_inner.console_discipline;
+        inner.pgid = parent_inner.pgid;
+        inner.sid = parent_inner.sid;
+        inner.nofile_limit = parent_inner.nofile_limit;
+        inner.space_limit = parent_inner.space_limit;
+        inner.task_limit = parent_inner.task_limit;
+        inner.uid = parent_inner.uid;
+        inner.gid = parent_inner.gid;
         debug!(
             "fork process {} with pid: {}, base size: {:#x}",
             path, pid, parent_inner.base_size,
@@ -1087,6 +2247,7 @@ impl ProcessControlBlock {
         parent_inner.childrens.insert(pid, Arc::clone(&child));
         drop(child_inner);
         drop(parent_inner);
+        PROCESS_TABLE.register(&child);
         Ok(child)
     }
 
@@ -1116,10 +2277,14 @@ impl ProcessControlBlock {
                 (true, _, -1) | (true, true, _) => {
                     debug!("drop child process {} from parent {}", child_pid, parent_id);
                     let child = inner.childrens.remove(&child_pid).unwrap();
+                    inner.orphans.remove(&child_pid);
                     assert_eq!(Arc::strong_count(&child), 1);
+                    // the usage of the waited child includes the usage of its own waited children
+                    let rusage = child.rusage();
+                    inner.children_rusage.merge(&rusage);
+                    inner.children_rusage.merge(&child.children_rusage());
                     let exit_code = child.inner_access().get_exit_code().unwrap();
-                    let real_exit_code = inner.space.translated_refmut(exit_code_ptr)?;
-                    *real_exit_code = exit_code as i32;
+                    inner.space.copy_value_to_user(exit_code_ptr, &(exit_code as i32))?;
                     return Ok(child_pid as isize);
                 }
                 (false, true, _) => return Ok(-2),
@@ -1133,7 +2298,34 @@ impl ProcessControlBlock {
         }
     }
 
+    /// Reap the zombie children which were adopted from the exited processes,
+    /// nobody will wait for them, so they must be released by the kernel.
+    /// The children created by the current process itself are still left to the wait_pid syscall.
+    ///
+    /// - Returns
+    ///     - the count of the reaped children
+    pub(crate) fn reap_zombie_children(&self) -> usize {
+        let mut inner = self.inner_exclusive_access();
+        let orphan_pids: Vec<usize> = inner.orphans.iter().copied().collect();
+        let mut count = 0;
+        for pid in orphan_pids {
+            match inner.childrens.get(&pid) {
+                Some(child) if !child.is_zombie() => continue,
+                Some(_) => {
+                    debug!("reap orphan process {} from parent {}", pid, self.pid());
+                    inner.childrens.remove(&pid);
+                    count += 1;
+                }
+                None => {}
+            }
+            inner.orphans.remove(&pid);
+        }
+        count
+    }
+
     /// Kill the current process.
+    /// If the signal is pending, all sleeping tasks of the process will be woken up,
+    /// so that they can be interrupted by the signal.
     ///
     /// - Arguments
     ///     - signal: the value of the signal send from user mode
@@ -1141,92 +2333,519 @@ impl ProcessControlBlock {
     /// - Errors
     ///     - DuplicateSignal(signal)
     pub(crate) fn kill(&self, signal: Signal) -> Result<()> {
-        self.inner_exclusive_access().signal.try_kill(signal)
+        let mut inner = self.inner_exclusive_access();
+        inner.signal.try_kill(signal)?;
+        if !inner.signal.is_pending_signal(signal) {
+            return Ok(());
+        }
+        drop(inner);
+        self.wake_sleep_tasks();
+        Ok(())
     }
 
-    /// Create the initial process control block
+    /// Kill the current process with the signal caused by the memory fault,
+    /// the faulting address will be passed to the handler of the SEGV signal.
     ///
-    /// - Returns
-    ///     - Ok(Arc<Self>)
+    /// - Arguments
+    ///     - signal: the value of the signal
+    ///     - addr: the faulting virtual address
     ///
     /// - Errors
-    ///     - FileSystemError
-    ///         - InodeMustBeDirectory(bitmap index)
-    ///         - DataOutOfBounds
-    ///         - NoDroptableBlockCache
-    ///         - RawDeviceError(error code)
-    ///         - DuplicatedFname(name, inode bitmap index)
-    ///         - BitmapExhausted(start_block_id)
-    ///         - BitmapIndexDeallocated(bitmap_index)
-    ///         - RawDeviceError(error code)
-    ///     - FileMustBeReadable(bitmap index)
-    ///     - FileDoesNotExists(name)
-    ///     - ParseStringError
-    ///     - IdExhausted
-    ///     - ParseElfError
-    ///     - InvalidHeadlessTask
-    ///     - UnloadableTask
-    ///     - FrameExhausted
-    ///     - AreaAllocFailed(start_vpn, end_vpn)
-    ///     - VPNOutOfArea(vpn, start_vpn, end_vpn)
-    ///     - VPNAlreadyMapped(vpn)
-    ///     - InvaidPageTablePerm(flags)
-    ///     - FrameExhausted
-    ///     - AllocFullPageMapper(ppn)
-    ///     - PPNAlreadyMapped(ppn)
-    ///     - PPNNotMapped(ppn)
-    pub(crate) fn new_init_proc() -> Result<Arc<Self>> {
-        let file = ROOT_INODE.find(&configs::INIT_PROCESS_PATH, OpenFlags::READ)?;
-        let data = file.read_all()?;
-        let name = String::from_str(configs::INIT_PROCESS_PATH)?;
-        Ok(Self::new(name, &data, None)?)
-    }
-
-    /// Get the inmutable inner structure
-    pub(crate) fn inner_access(&self) -> Ref<'_, ProcessControlBlockInner> {
-        self.inner.access()
+    ///     - DuplicateSignal(signal)
+    pub(crate) fn kill_with_fault_addr(&self, signal: Signal, addr: usize) -> Result<()> {
+        self.kill(signal)?;
+        if matches!(signal, Signal::SEGV) {
+            self.inner_exclusive_access().signal.set_fault_addr(addr);
+        }
+        Ok(())
     }
 
-    /// Get the mutable inner structure
-    pub(crate) fn inner_exclusive_access(&self) -> RefMut<'_, ProcessControlBlockInner> {
-        self.inner.exclusive_access()
+    /// Queue a real-time signal to the current process,
+    /// each instance of the same signal will be handled one by one.
+    ///
+    /// - Arguments
+    ///     - signum: the number of the real-time signal
+    ///     - value: the value which will be passed to the handler as the second argument
+    ///
+    /// - Errors
+    ///     - SignalQueueFull(signum)
+    pub(crate) fn kill_rt(&self, signum: usize, value: usize) -> Result<()> {
+        let mut inner = self.inner_exclusive_access();
+        inner.signal.try_kill_rt(signum, value)?;
+        if !inner.signal.is_pending_rt_signal() {
+            return Ok(());
+        }
+        drop(inner);
+        self.wake_sleep_tasks();
+        Ok(())
     }
 
-    /// Get the process unique id
-    pub(crate) fn pid(&self) -> usize {
-        self.tracker.id()
+    /// Wake up the sleeping tasks of the process, so that they can be interrupted by the signal.
+    /// The sleeping tasks of other processes are not disturbed.
+    fn wake_sleep_tasks(&self) {
+        let tasks: Vec<Arc<TaskControlBlock>> = self
+            .inner_access()
+            .tasks
+            .values()
+            .map(|task| Arc::clone(task))
+            .collect();
+        TASK_SCHEDULER.wake_sleep_tasks(&tasks);
     }
 
-    /// Get the mmu token from space
-    pub(crate) fn user_token(&self) -> usize {
-        self.inner_access().space.mmu_token()
+    /// Attach the tracer to the current process and stop it by the STOP signal.
+    /// The tracer must be the parent run by the same user, or any other privileged process.
+    /// The initial process and the process which is already traced can never be attached.
+    ///
+    /// - Arguments
+    ///     - tracer: the process which will trace the current process
+    ///
+    /// - Returns
+    ///     - true: the tracer was attached
+    ///     - false: the tracer is not authorized
+    pub(crate) fn attach(&self, tracer: &Arc<ProcessControlBlock>) -> bool {
+        if self.pid() == ROOT_PID || self.pid() == tracer.pid() || self.tracer().is_some() {
+            return false;
+        }
+        let tracer_inner = tracer.inner_access();
+        let mut inner = self.inner_exclusive_access();
+        let is_parent = inner
+            .parent()
+            .is_some_and(|parent| Arc::ptr_eq(&parent, tracer));
+        if !tracer_inner.is_privileged() && !(is_parent && tracer_inner.uid() == inner.uid()) {
+            return false;
+        }
+        inner.tracer.replace(Arc::downgrade(tracer));
+        drop(inner);
+        drop(tracer_inner);
+        // the process may have been stopped already
+        let _ = self.kill(Signal::STOP);
+        true
     }
 
-    /// Allocate new task control block.
-    /// This method could be called by Arc<Self>.
+    /// Detach the tracer from the current process and continue it by the CONT signal,
+    /// the breakpoints of the unfinished single-step are removed.
     ///
     /// - Arguments
-    ///     - entry_point: the virtual address to the first instruction will be run in the memory space
+    ///     - tracer: the process which is tracing the current process
+    ///
+    /// - Returns
+    ///     - true: the tracer was detached
+    ///     - false: the current process is not traced by the tracer
     ///
     /// - Errors
-    ///     - AreaAllocFailed(start_vpn, end_vpn)
-    ///     - VPNOutOfArea(vpn, start_vpn, end_vpn)
-    ///     - VPNAlreadyMapped(vpn)
-    ///     - InvaidPageTablePerm(flags)
+    ///     - UserRangeNotAccessible(va, len)
     ///     - FrameExhausted
-    ///     - AllocFullPageMapper(ppn)
-    ///     - PPNAlreadyMapped(ppn)
-    ///     - PPNNotMapped(ppn)
-    pub(crate) fn alloc_task(
-        self: &Arc<ProcessControlBlock>,
-        entry_point: usize,
-        arg: Option<usize>,
+    ///     - SwapIOFailed(slot)
+    ///     - AreaNotExists(start_vpn, end_vpn)
+    ///     - VPNNotMapped(vpn)
+    pub(crate) fn detach(&self, tracer: &Arc<ProcessControlBlock>) -> Result<bool> {
+        if !self.is_traced_by(tracer) {
+            return Ok(false);
+        }
+        self.remove_step_breakpoints()?;
+        self.inner_exclusive_access().tracer.take();
+        self.resume()?;
+        Ok(true)
+    }
+
+    /// Continue the stopped process by the CONT signal,
+    /// the ebreak instruction which stopped the root task will be skipped.
+    ///
+    /// - Errors
+    ///     - AreaNotExists(start_vpn, end_vpn)
+    ///     - VPNNotMapped(vpn)
+    pub(crate) fn resume(&self) -> Result<()> {
+        self.skip_reached_breakpoint()?;
+        // the same signal may be still pending
+        let _ = self.kill(Signal::CONT);
+        Ok(())
+    }
+
+    /// Move the program counter of the root task past the ebreak instruction which stopped it,
+    /// unless the tracer has changed the program counter.
+    ///
+    /// - Errors
+    ///     - AreaNotExists(start_vpn, end_vpn)
+    ///     - VPNNotMapped(vpn)
+    fn skip_reached_breakpoint(&self) -> Result<()> {
+        let reached = self.inner_exclusive_access().reached_breakpoint.take();
+        if let Some((pc, len)) = reached {
+            let mut regs = self.user_regs()?;
+            if regs.pc == pc {
+                regs.pc += len;
+                self.set_user_regs(&regs)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Handle the ebreak instruction which was not inserted by the single-step.
+    /// The traced process is stopped by the TRAP signal at the ebreak of the root task,
+    /// which will be skipped when the tracer continues it,
+    /// otherwise the program counter is moved past the ebreak immediately,
+    /// so the handler of the TRAP signal returns to the following instruction.
+    /// The process without the handler will be terminated by the TRAP signal.
+    ///
+    /// - Arguments
+    ///     - task: the task which reached the ebreak
+    ///     - pc: the program counter of the ebreak
+    ///
+    /// - Errors
+    ///     - UserRangeNotAccessible(va, len)
+    ///     - FrameExhausted
+    ///     - SwapIOFailed(slot)
+    ///     - AreaNotExists(start_vpn, end_vpn)
+    ///     - VPNNotMapped(vpn)
+    pub(crate) fn reach_breakpoint(&self, task: &TaskControlBlock, pc: usize) -> Result<()> {
+        let len = step::instruction_len(step::read_half(self, pc)? as u32);
+        let mut inner = self.inner_exclusive_access();
+        if inner.is_traced() && task.tid() == ROOT_TID {
+            inner.reached_breakpoint.replace((pc, len));
+        } else {
+            task.inner_access().modify_trap_ctx(&inner.space, |trap_ctx| {
+                trap_ctx.sepc += len;
+                Ok(())
+            })?;
+        }
+        drop(inner);
+        // the same signal may be still pending
+        let _ = self.kill(Signal::TRAP);
+        Ok(())
+    }
+
+    /// Continue the stopped process to execute one instruction, then stop it by the TRAP signal.
+    /// There is no hardware single-step in the supervisor mode,
+    /// so the breakpoints are inserted at all the instructions which may be executed next,
+    /// and all of them are removed as soon as any one is reached.
+    /// The unmapped next instruction is not breakpointed, because reaching it will fault anyway.
+    ///
+    /// - Errors
+    ///     - AreaNotExists(start_vpn, end_vpn)
+    ///     - VPNNotMapped(vpn)
+    ///     - UserRangeNotAccessible(va, len)
+    ///     - FrameExhausted
+    ///     - SwapIOFailed(slot)
+    pub(crate) fn step(&self) -> Result<()> {
+        self.remove_step_breakpoints()?;
+        self.skip_reached_breakpoint()?;
+        let regs = self.user_regs()?;
+        let inst = step::read_instruction(self, regs.pc)?;
+        let mut pcs = step::next_pcs(inst, regs.pc, &regs);
+        pcs.sort_unstable();
+        pcs.dedup();
+        for pc in pcs {
+            let original = match step::read_half(self, pc) {
+                Ok(original) => original,
+                Err(KernelError::UserRangeNotAccessible(_, _)) => continue,
+                Err(other) => return Err(other),
+            };
+            step::write_half(self, pc, step::C_EBREAK)?;
+            self.inner_exclusive_access()
+                .step_breakpoints
+                .push((pc, original));
+        }
+        self.resume()
+    }
+
+    /// Complete the single-step if the breakpoint inserted by it was reached by the root task,
+    /// the breakpoints are removed and the process will be stopped by the TRAP signal.
+    /// The program counter is kept at the breakpoint, which is the next instruction to execute.
+    /// Only the root task is stepped, the breakpoints reached by the other tasks are kept,
+    /// and those tasks will execute the original instructions after the breakpoints are removed.
+    ///
+    /// - Arguments
+    ///     - task: the task which reached the breakpoint
+    ///     - pc: the program counter of the reached breakpoint
+    ///
+    /// - Returns
+    ///     - true: the breakpoint was inserted by the single-step
+    ///     - false: the breakpoint was not inserted by the single-step
+    ///
+    /// - Errors
+    ///     - UserRangeNotAccessible(va, len)
+    ///     - FrameExhausted
+    ///     - SwapIOFailed(slot)
+    ///     - VPNNotMapped(vpn)
+    pub(crate) fn complete_step(&self, task: &TaskControlBlock, pc: usize) -> Result<bool> {
+        let inserted = self
+            .inner_access()
+            .step_breakpoints
+            .iter()
+            .any(|(addr, _)| *addr == pc);
+        if !inserted || task.tid() != ROOT_TID {
+            return Ok(inserted);
+        }
+        self.remove_step_breakpoints()?;
+        // the same signal may be still pending
+        let _ = self.kill(Signal::TRAP);
+        Ok(true)
+    }
+
+    /// Restore the half words replaced by the breakpoints of the single-step
+    ///
+    /// - Errors
+    ///     - UserRangeNotAccessible(va, len)
+    ///     - FrameExhausted
+    ///     - SwapIOFailed(slot)
+    ///     - VPNNotMapped(vpn)
+    fn remove_step_breakpoints(&self) -> Result<()> {
+        let breakpoints = core::mem::take(&mut self.inner_exclusive_access().step_breakpoints);
+        for (pc, original) in breakpoints {
+            step::write_half(self, pc, original)?;
+        }
+        Ok(())
+    }
+
+    /// Get the alive process which is tracing the current process
+    pub(crate) fn tracer(&self) -> Option<Arc<ProcessControlBlock>> {
+        self.inner_access()
+            .tracer
+            .as_ref()
+            .and_then(|tracer| tracer.upgrade())
+    }
+
+    /// Check if the current process is traced by the tracer
+    ///
+    /// - Arguments
+    ///     - tracer: the process which may trace the current process
+    pub(crate) fn is_traced_by(&self, tracer: &Arc<ProcessControlBlock>) -> bool {
+        self.tracer()
+            .is_some_and(|current| Arc::ptr_eq(&current, tracer))
+    }
+
+    /// Check if the current process was stopped by the STOP signal
+    pub(crate) fn is_stopped(&self) -> bool {
+        self.inner_access().signal.is_frozen()
+    }
+
+    /// Read a word in the space of the current process through it's page table,
+    /// the unmapped or unaligned address will be rejected instead of causing a fault.
+    ///
+    /// - Arguments
+    ///     - addr: the virtual address of the word, aligned to the word size
+    ///
+    /// - Errors
+    ///     - UserRangeNotAccessible(va, len)
+    ///     - FrameExhausted
+    ///     - SwapIOFailed(slot)
+    ///     - VPNNotMapped(vpn)
+    pub(crate) fn peek_word(&self, addr: usize) -> Result<usize> {
+        let ptr = Self::word_ptr(addr)?;
+        let inner = self.inner_access();
+        inner.space().check_user_range(ptr, core::mem::size_of::<usize>(), false)?;
+        Ok(*inner.space().translated_refmut(ptr)?)
+    }
+
+    /// Write a word in the space of the current process through it's page table.
+    /// The readable page which is not writable to the user mode, such as the code,
+    /// can also be written, so that the debugger can insert the breakpoints.
+    ///
+    /// - Arguments
+    ///     - addr: the virtual address of the word, aligned to the word size
+    ///     - value: the value of the word
+    ///
+    /// - Errors
+    ///     - UserRangeNotAccessible(va, len)
+    ///     - FrameExhausted
+    ///     - SwapIOFailed(slot)
+    ///     - VPNNotMapped(vpn)
+    pub(crate) fn poke_word(&self, addr: usize, value: usize) -> Result<()> {
+        let ptr = Self::word_ptr(addr)?;
+        let inner = self.inner_access();
+        inner.space().check_user_range(ptr, core::mem::size_of::<usize>(), false)?;
+        *inner.space().translated_refmut(ptr)? = value;
+        // the written word may be the instruction which will be fetched later
+        unsafe { SBI::sync_icache() };
+        Ok(())
+    }
+
+    /// Check the word address is aligned, so the word never crosses the page boundary
+    ///
+    /// - Arguments
+    ///     - addr: the virtual address of the word
+    ///
+    /// - Errors
+    ///     - UserRangeNotAccessible(va, len)
+    fn word_ptr(addr: usize) -> Result<*const usize> {
+        if addr % core::mem::size_of::<usize>() != 0 {
+            let len = core::mem::size_of::<usize>();
+            return Err(KernelError::UserRangeNotAccessible(addr, len));
+        }
+        Ok(addr as *const usize)
+    }
+
+    /// Get the registers of the root task saved in it's trap context
+    ///
+    /// - Errors
+    ///     - AreaNotExists(start_vpn, end_vpn)
+    ///     - VPNNotMapped(vpn)
+    pub(crate) fn user_regs(&self) -> Result<UserRegs> {
+        let inner = self.inner_access();
+        let root_task = inner.root_task();
+        let task_inner = root_task.inner_access();
+        task_inner.modify_trap_ctx(&inner.space, |trap_ctx| Ok(trap_ctx.user_regs()))
+    }
+
+    /// Replace the registers of the root task saved in it's trap context,
+    /// they will be restored when the root task returns to the user mode.
+    ///
+    /// - Arguments
+    ///     - regs: the new registers
+    ///
+    /// - Errors
+    ///     - AreaNotExists(start_vpn, end_vpn)
+    ///     - VPNNotMapped(vpn)
+    pub(crate) fn set_user_regs(&self, regs: &UserRegs) -> Result<()> {
+        let inner = self.inner_access();
+        let root_task = inner.root_task();
+        let task_inner = root_task.inner_access();
+        task_inner.modify_trap_ctx(&inner.space, |trap_ctx| {
+            trap_ctx.set_user_regs(regs);
+            Ok(())
+        })
+    }
+
+    /// Create the initial process control block
+    ///
+    /// - Returns
+    ///     - Ok(Arc<Self>)
+    ///
+    /// - Errors
+    ///     - FileSystemError
+    ///         - InodeMustBeDirectory(bitmap index)
+    ///         - DataOutOfBounds
+    ///         - NoDroptableBlockCache
+    ///         - RawDeviceError(error code)
+    ///         - DuplicatedFname(name, inode bitmap index)
+    ///         - BitmapExhausted(start_block_id)
+    ///         - BitmapIndexDeallocated(bitmap_index)
+    ///         - RawDeviceError(error code)
+    ///     - FileMustBeReadable(bitmap index)
+    ///     - FileDoesNotExists(name)
+    ///     - ParseStringError
+    ///     - IdExhausted
+    ///     - ParseElfError
+    ///     - InvalidHeadlessTask
+    ///     - UnloadableTask
+    ///     - FrameExhausted
+    ///     - AreaAllocFailed(start_vpn, end_vpn)
+    ///     - VPNOutOfArea(vpn, start_vpn, end_vpn)
+    ///     - VPNAlreadyMapped(vpn)
+    ///     - InvaidPageTablePerm(flags)
+    ///     - FrameExhausted
+    ///     - AllocFullPageMapper(ppn)
+    ///     - PPNAlreadyMapped(ppn)
+    ///     - PPNNotMapped(ppn)
+    pub(crate) fn new_init_proc() -> Result<Arc<Self>> {
+        let path = configs::INIT_PROCESS_PATH;
+        let file = ROOT_INODE.find_inode(path, OpenFlags::READ, Credential::ROOT)?;
+        let data = file.read_all()?;
+        let name = String::from_str(configs::INIT_PROCESS_PATH)?;
+        Ok(Self::new(name, &data, None)?)
+    }
+
+    /// Get the inmutable inner structure
+    #[track_caller]
+    pub(crate) fn inner_access(&self) -> UserPromiseRef<'_, ProcessControlBlockInner> {
+        self.inner.access()
+    }
+
+    /// Get the mutable inner structure
+    #[track_caller]
+    pub(crate) fn inner_exclusive_access(&self) -> UserPromiseRefMut<'_, ProcessControlBlockInner> {
+        self.inner.exclusive_access()
+    }
+
+    /// Get the process unique id
+    pub(crate) fn pid(&self) -> usize {
+        self.tracker.id()
+    }
+
+    /// Move the process into the process group,
+    /// the group must be led by the process itself or already exist in the same session.
+    ///
+    /// - Arguments
+    ///     - pgid: the process group id
+    ///
+    /// - Returns
+    ///     - true: the process joined the group
+    ///     - false: the group cannot be joined and the process group id is not changed
+    pub(crate) fn join_group(&self, pgid: usize) -> bool {
+        if pgid != self.pid() {
+            let sid = self.inner_access().sid();
+            let joinable = PROCESS_TABLE
+                .group(pgid)
+                .iter()
+                .any(|process| process.inner_access().sid() == sid);
+            if !joinable {
+                return false;
+            }
+        }
+        self.inner_exclusive_access().set_pgid(pgid);
+        true
+    }
+
+    /// Get the parent process unique id.
+    /// After the parent process exited, the current process will be reparented to `initproc`,
+    /// so the [`ROOT_PID`] will be returned if the parent process cannot be found.
+    pub(crate) fn ppid(&self) -> usize {
+        self.inner_access()
+            .parent()
+            .map_or(ROOT_PID, |parent| parent.pid())
+    }
+
+    /// Get the mmu token from space
+    pub(crate) fn user_token(&self) -> usize {
+        self.inner_access().space.mmu_token()
+    }
+
+    /// Allocate new task control block.
+    /// This method could be called by Arc<Self>.
+    ///
+    /// - Arguments
+    ///     - entry_point: the virtual address to the first instruction will be run in the memory space
+    ///     - args: the arguments which will be passed to the argument registers in order, at most four
+    ///     - kernel_stack_size: the byte size of the task's kernel stack
+    ///     - user_stack_size: the byte size of the task's user stack
+    ///
+    /// - Errors
+    ///     - TaskLimitExceeded(limit)
+    ///     - SpaceLimitExceeded(limit)
+    ///     - InvalidKernelStackSize(byte_size)
+    ///     - InvalidUserStackSize(byte_size)
+    ///     - UserStackOutOfLayout(tid)
+    ///     - AreaAllocFailed(start_vpn, end_vpn)
+    ///     - VPNOutOfArea(vpn, start_vpn, end_vpn)
+    ///     - VPNAlreadyMapped(vpn)
+    ///     - InvaidPageTablePerm(flags)
+    ///     - FrameExhausted
+    ///     - AllocFullPageMapper(ppn)
+    ///     - PPNAlreadyMapped(ppn)
+    ///     - PPNNotMapped(ppn)
+    pub(crate) fn alloc_task(
+        self: &Arc<ProcessControlBlock>,
+        entry_point: usize,
+        args: &[usize],
+        kernel_stack_size: usize,
+        user_stack_size: usize,
     ) -> Result<Arc<TaskControlBlock>> {
+        assert!(args.len() <= 4);
+        let process_inner = self.inner_access();
+        let living = process_inner.tasks.values().filter(|t| !t.is_zombie()).count();
+        process_inner.check_task_limit(living)?;
+        // each task maps its own user stack and one page of the trap context
+        process_inner.check_space_limit(user_stack_size + configs::MEMORY_PAGE_BYTE_SIZE)?;
+        drop(process_inner);
         let tracker = self.tid_allocator.alloc()?;
         let tid = tracker.id();
-        let task = Arc::new(TaskControlBlock::new(tracker, self)?);
+        let task = Arc::new(TaskControlBlock::new(
+            tracker,
+            self,
+            kernel_stack_size,
+            user_stack_size,
+        )?);
         let mut process_inner = self.inner_exclusive_access();
-        let user_stack_top_va = Space::get_user_task_stack_top_va(process_inner.base_size, tid);
+        let user_stack_top_va = Space::get_user_task_stack_top_va(process_inner.base_size, tid)?;
         let kernel_stack_top_va = Space::get_kernel_task_stack_top_va(task.kernel_stack.id());
         let mut task_inner = task.inner_exclusive_access();
         task_inner.modify_trap_ctx(&process_inner.space, |trap_ctx| {
@@ -1235,8 +2854,11 @@ impl ProcessControlBlock {
                 user_stack_top_va,
                 kernel_stack_top_va,
             );
-            if let Some(arg) = arg {
-                trap_ctx.set_arg(0, arg);
+            // the thread pointer points to the top of the user stack by default,
+            // the user runtime can reserve the thread local storage there or reset it by itself
+            trap_ctx.set_tp(user_stack_top_va);
+            for (index, arg) in args.iter().enumerate() {
+                trap_ctx.set_arg(index, *arg);
             }
             Ok(())
         })?;
@@ -1256,15 +2878,17 @@ impl ProcessControlBlock {
         inner.signal.get_bad_signal()
     }
 
-    /// Make current process to handle all signals.
+    /// Update the signal states of the process and pick the signal which should be delivered to the custom handler.
+    /// The process is only borrowed in this function, so the trap context can be modified after releasing it.
     ///
-    /// - Returns
-    ///     - Ok(killed, frozen)
+    /// - Arguments
+    ///     - deliverable: false if the root task is handling its own signal,
+    ///       the signals with custom handlers will be kept pending until it returns
     ///
-    /// - Errors
-    ///     - AreaNotExists(start_vpn, end_vpn)
-    ///     - VPNNotMapped(vpn)
-    pub(crate) fn handle_all_signals(&self) -> Result<(bool, bool)> {
+    /// - Returns
+    ///     - Some(delivery): the signal which will be delivered to the custom handler
+    ///     - None: no signal should be delivered to the custom handler
+    fn pick_signal_delivery(&self, deliverable: bool) -> Option<SignalDelivery> {
         let mut inner = self.inner_exclusive_access();
         for signal in Signal::iter() {
             if !inner.signal.is_pending_signal(signal) {
@@ -1281,6 +2905,10 @@ impl ProcessControlBlock {
                 Signal::KILL | Signal::DEF => {
                     inner.signal.kill();
                 }
+                // the traced process is stopped by the TRAP signal, so the tracer can inspect it
+                Signal::TRAP if inner.is_traced() => {
+                    inner.signal.freeze_by(Signal::TRAP);
+                }
                 other => {
                     // Get the signal handle action, all of the action handle fuction is pointed to 0
                     let action = inner.signal.get_action(signal);
@@ -1293,27 +2921,103 @@ impl ProcessControlBlock {
                         );
                         continue;
                     }
+                    if !deliverable {
+                        continue;
+                    }
                     debug!(
                         "Handle signal {:?} with custom action: {}",
                         signal, handler as usize,
                     );
-                    // Only root task is able to accept signal
-                    let root_task = inner.root_task();
-                    let task_inner = root_task.inner_access();
-                    // Copy root task's trap context
-                    let trap_ctx_backup = task_inner.modify_trap_ctx(&inner.space, |trap_ctx| {
-                        let trap_ctx_backup = trap_ctx.clone();
-                        trap_ctx.sepc = handler as usize;
-                        trap_ctx.set_arg(0, signal as usize);
-                        Ok(trap_ctx_backup)
-                    })?;
-                    // Backup trap context to the process control block
-                    inner.signal.backup(other, trap_ctx_backup);
-                    return Ok((inner.signal.is_killed(), inner.signal.is_frozen()));
+                    // the faulting address is only meaningful for the SEGV signal
+                    let value = match other {
+                        Signal::SEGV => inner.signal.fault_addr(),
+                        _ => None,
+                    };
+                    return Some(SignalDelivery {
+                        signal: Some(other),
+                        signum: other as usize,
+                        handler: handler as usize,
+                        value,
+                        sp: inner.signal.alt_stack_top(other),
+                        restart: inner.signal.is_restart(other),
+                    });
                 }
             }
         }
-        return Ok((inner.signal.is_killed(), inner.signal.is_frozen()));
+        if !deliverable {
+            return None;
+        }
+        // the real-time signals are handled one instance at a time, so that none of them will be lost
+        while let Some((signum, value)) = inner.signal.pop_rt_signal() {
+            let handler = inner.signal.get_rt_action(signum).handler();
+            if handler.is_null() {
+                debug!("Handle real-time signal {} with default action: ignore it", signum);
+                continue;
+            }
+            debug!(
+                "Handle real-time signal {} with custom action: {}",
+                signum, handler as usize,
+            );
+            return Some(SignalDelivery {
+                signal: None,
+                signum,
+                handler: handler as usize,
+                value: Some(value),
+                sp: None,
+                restart: false,
+            });
+        }
+        // the temporary masking of sigsuspend is no longer needed when no signal is being handled
+        inner.signal.restore_suspended_mask();
+        None
+    }
+
+    /// Make current process to handle all signals.
+    /// The borrows of the process are never nested:
+    /// the signal states are updated with an exclusive borrow which is released before the trap context is modified,
+    /// and the trap context is modified with a shared borrow of the process
+    /// and an exclusive borrow of the task chosen by [`ProcessControlBlockInner::signal_task`].
+    ///
+    /// - Returns
+    ///     - Ok(killed, frozen)
+    ///
+    /// - Errors
+    ///     - AreaNotExists(start_vpn, end_vpn)
+    ///     - VPNNotMapped(vpn)
+    pub(crate) fn handle_all_signals(&self) -> Result<(bool, bool)> {
+        let deliverable = !self.inner_access().signal_task().inner_access().signal.is_handling();
+        if let Some(delivery) = self.pick_signal_delivery(deliverable) {
+            let inner = self.inner_access();
+            // the exited root task will never return to the user mode to run the handler
+            let signal_task = inner.signal_task();
+            let mut task_inner = signal_task.inner_exclusive_access();
+            // Copy the signal task's trap context
+            let mut trap_ctx_backup = task_inner.modify_trap_ctx(&inner.space, |trap_ctx| {
+                let trap_ctx_backup = trap_ctx.clone();
+                trap_ctx.sepc = delivery.handler;
+                trap_ctx.set_arg(0, delivery.signum);
+                if let Some(value) = delivery.value {
+                    trap_ctx.set_arg(1, value);
+                }
+                // the original stack pointer is kept in the backup and will be restored by sig_return
+                if let Some(sp) = delivery.sp {
+                    trap_ctx.set_sp(sp);
+                }
+                Ok(trap_ctx_backup)
+            })?;
+            task_inner.restart_interrupted_syscall(delivery.restart, &mut trap_ctx_backup);
+            drop(task_inner);
+            drop(inner);
+            // Backup trap context to the process control block
+            let mut inner = self.inner_exclusive_access();
+            inner.signal_tid = signal_task.tid();
+            match delivery.signal {
+                Some(signal) => inner.signal.backup(signal, trap_ctx_backup),
+                None => inner.signal.backup_rt(delivery.signum, trap_ctx_backup),
+            }
+        }
+        let inner = self.inner_access();
+        Ok((inner.signal.is_killed(), inner.signal.is_frozen()))
     }
 
     /// Check if the current process is zombie status
@@ -1321,39 +3025,125 @@ impl ProcessControlBlock {
         self.inner_access().is_zombie()
     }
 
+    /// Get the execution status of the process summarized from all of it's tasks:
+    ///     - Running: any task is running
+    ///     - Ready: any task is waiting for running
+    ///     - Zombie: all tasks are zombie
+    ///     - Blocked: otherwise
+    pub(crate) fn status(&self) -> TaskStatus {
+        let inner = self.inner_access();
+        if inner.is_zombie() {
+            return TaskStatus::Zombie;
+        }
+        let statuses: Vec<TaskStatus> = inner.tasks.values().map(|task| task.status()).collect();
+        if statuses.contains(&TaskStatus::Running) {
+            TaskStatus::Running
+        } else if statuses.contains(&TaskStatus::Ready) {
+            TaskStatus::Ready
+        } else {
+            TaskStatus::Blocked
+        }
+    }
+
+    /// Get the count of the process's tasks which are in the specified status
+    ///
+    /// - Arguments
+    ///     - status: the execution status of the tasks
+    pub(crate) fn count_tasks(&self, status: TaskStatus) -> usize {
+        self.inner_access()
+            .tasks
+            .values()
+            .filter(|task| task.status() == status)
+            .count()
+    }
+
+    /// Get the CPU time consumed by all the tasks of the process, including the exited tasks
+    pub(crate) fn cpu_time(&self) -> CpuTime {
+        let inner = self.inner_access();
+        let mut cpu_time = inner.exited_cpu_time;
+        for task in inner.tasks.values() {
+            cpu_time.merge(&task.cpu_time());
+        }
+        cpu_time
+    }
+
+    /// Get the resource usage of all the tasks of the process, including the exited tasks
+    pub(crate) fn rusage(&self) -> RUsage {
+        let inner = self.inner_access();
+        let mut rusage = inner.exited_rusage;
+        for task in inner.tasks.values() {
+            rusage.merge(&task.rusage());
+        }
+        rusage
+    }
+
+    /// Get the resource usage of the children which have exited and been waited
+    pub(crate) fn children_rusage(&self) -> RUsage {
+        self.inner_access().children_rusage
+    }
+
+    /// Get the information of the process which will be shared with the user space
+    pub(crate) fn info(&self) -> ProcessInfo {
+        let status = self.status();
+        let ppid = self.ppid();
+        ProcessInfo::new(self.pid(), ppid, status, self.inner_access().path())
+    }
+
+    /// Detach the current process from all the processes it is tracing and continue them,
+    /// otherwise the stopped tracees would never be continued after the current process exits.
+    fn detach_tracees(self: &Arc<Self>) {
+        for tracee in PROCESS_TABLE.list() {
+            if !tracee.is_traced_by(self) {
+                continue;
+            }
+            if let Err(error) = tracee.detach(self) {
+                warn!("Failed to detach process {} cause: {}", tracee.pid(), error);
+                tracee.inner_exclusive_access().tracer.take();
+                let _ = tracee.kill(Signal::CONT);
+            }
+        }
+    }
+
     /// Mark current process as zombie process.
     /// Only Arc<Self> is able to call this function.
-    /// All of the tasks in the current process will be clear immediately.
+    /// All of the tasks in the current process will be clear immediately,
+    /// and the processes traced by the current process will be detached.
     ///
     /// - Arguments
     ///     - exit_code: the exit code of current process
     pub(crate) fn mark_zombie(self: &Arc<Self>, exit_code: i32) {
+        self.detach_tracees();
         let inner = self.inner_access();
         let tasks: Vec<Arc<TaskControlBlock>> =
             inner.tasks.values().map(|task| Arc::clone(task)).collect();
         // because we will also use inner process control block when releasing task's user resource
         drop(inner);
         for task in tasks {
-            if task.is_zombie() {
+            if task.user_stack_size().is_none() {
                 continue;
             }
             task.release_user_resource(exit_code as usize);
-            assert_eq!(Arc::strong_count(&task), 3);
+            assert!(task.is_zombie());
         }
         let mut inner = self.inner_exclusive_access();
         for child in inner.childrens.values() {
             child.mark_zombie(exit_code);
             let mut child_inner = child.inner_exclusive_access();
             child_inner.parent.replace(Arc::downgrade(&INIT_PROC));
-            INIT_PROC
-                .inner_exclusive_access()
-                .childrens
-                .insert(child.pid(), Arc::clone(child));
+            let mut init_inner = INIT_PROC.inner_exclusive_access();
+            init_inner.childrens.insert(child.pid(), Arc::clone(child));
+            init_inner.orphans.insert(child.pid());
         }
         inner.tasks.clear();
         inner.childrens.clear();
+        // close all files immediately instead of waiting for the parent to reap the process,
+        // so that the other end of the pipes will see EOF as soon as the process exits
+        inner.fd_table.clear();
+        // the waiting tasks are all in current process, so there is no one to wake up
+        inner.barrier_table.clear();
         inner.space.recycle_data_pages();
         inner.set_exit_code(exit_code as usize);
+        PROCESS_TABLE.unregister(self.pid());
     }
 }
 
@@ -1369,22 +3159,871 @@ lazy_static! {
     /// The initial process which will be created when operation system is started
     pub(crate) static ref INIT_PROC: Arc<ProcessControlBlock> =
         ProcessControlBlock::new_init_proc().unwrap();
+
+    /// The global registry of all live processes keyed by pid.
+    /// The weak references never keep the dead processes alive,
+    /// and the processes will be removed from the table when they become zombie.
+    pub(crate) static ref PROCESS_TABLE: Arc<UserPromiseRefCell<BTreeMap<usize, Weak<ProcessControlBlock>>>> =
+        Arc::new(unsafe { UserPromiseRefCell::new(BTreeMap::new()) });
+}
+impl PROCESS_TABLE {
+    /// Register a new process into the table
+    ///
+    /// - Arguments
+    ///     - process: the new process
+    fn register(&self, process: &Arc<ProcessControlBlock>) {
+        self.exclusive_access()
+            .insert(process.pid(), Arc::downgrade(process));
+    }
+
+    /// Remove the process from the table
+    ///
+    /// - Arguments
+    ///     - pid: the unique id of the process
+    fn unregister(&self, pid: usize) {
+        self.exclusive_access().remove(&pid);
+    }
+
+    /// Get the live process by process id
+    ///
+    /// - Arguments
+    ///     - pid: the unique id of the process
+    pub(crate) fn get(&self, pid: usize) -> Option<Arc<ProcessControlBlock>> {
+        self.access().get(&pid).and_then(|process| process.upgrade())
+    }
+
+    /// Get all the live processes ordered by the process id
+    pub(crate) fn list(&self) -> Vec<Arc<ProcessControlBlock>> {
+        self.access()
+            .values()
+            .filter_map(|process| process.upgrade())
+            .collect()
+    }
+
+    /// Evict the cold anonymous pages of the live processes to the swap device.
+    /// It must be called when no task is running, such as in the idle loop of the processor,
+    /// so that none of the process spaces is being borrowed or modified.
+    ///
+    /// - Arguments
+    ///     - max_count: the max count of the pages to evict
+    ///
+    /// - Returns
+    ///     - the count of the evicted pages
+    pub(crate) fn reclaim_cold_pages(&self, max_count: usize) -> usize {
+        let processes: Vec<Arc<ProcessControlBlock>> = self
+            .access()
+            .values()
+            .filter_map(|process| process.upgrade())
+            .collect();
+        let mut count = 0;
+        for process in processes {
+            if count >= max_count {
+                break;
+            }
+            if process.is_zombie() {
+                continue;
+            }
+            let mut inner = process.inner_exclusive_access();
+            match inner.space.swap_out_cold_pages(max_count - count) {
+                Ok(evicted) => count += evicted,
+                Err(error) => warn!(
+                    "Swap out cold pages of process {} failed cause: {}",
+                    process.pid(),
+                    error
+                ),
+            }
+        }
+        count
+    }
+
+    /// Get all the live processes in the process group
+    ///
+    /// - Arguments
+    ///     - pgid: the process group id
+    pub(crate) fn group(&self, pgid: usize) -> Vec<Arc<ProcessControlBlock>> {
+        self.access()
+            .values()
+            .filter_map(|process| process.upgrade())
+            .filter(|process| process.inner_access().pgid() == pgid)
+            .collect()
+    }
+}
+
+/// The child process forked for the tests.
+/// It will be marked as zombie if it is still alive and reaped by it's parent when it is dropped,
+/// so that the tests never leak the zombie children.
+#[cfg(test)]
+pub(crate) struct ForkedProcess(Arc<ProcessControlBlock>);
+#[cfg(test)]
+impl ForkedProcess {
+    /// Fork a child process from the root task of the init process
+    pub(crate) fn new() -> Self {
+        let root_task = INIT_PROC.inner_access().root_task();
+        Self::fork(&root_task)
+    }
+
+    /// Fork a child process from the task
+    ///
+    /// - Arguments
+    ///     - task: the task of the parent process
+    pub(crate) fn fork(task: &Arc<TaskControlBlock>) -> Self {
+        Self(task.fork_process().unwrap())
+    }
+
+    /// Run the root task as the current task until the returned guard is dropped,
+    /// so that the syscalls can be run on behalf of the process
+    pub(crate) fn enter(&self) -> CurrentTaskGuard {
+        CurrentTaskGuard::enter(self.0.inner_access().root_task())
+    }
+
+    /// Get the virtual address of the scratch memory in the user stack of the root task,
+    /// which can be used as the user buffers of the syscalls.
+    /// The scratch memory has 256 bytes and the page boundary is right at the middle of it,
+    /// so the values in it must be copied across the pages.
+    pub(crate) fn user_scratch_va(&self) -> usize {
+        let base_size = self.0.inner_access().base_size;
+        let top_va = Space::get_user_task_stack_top_va(base_size, ROOT_TID).unwrap();
+        top_va - configs::MEMORY_PAGE_BYTE_SIZE * 2 - 128
+    }
+}
+#[cfg(test)]
+impl core::ops::Deref for ForkedProcess {
+    type Target = Arc<ProcessControlBlock>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+#[cfg(test)]
+impl Drop for ForkedProcess {
+    fn drop(&mut self) {
+        if !self.0.is_zombie() {
+            self.0.mark_zombie(0);
+        }
+        let parent = self.0.inner_access().parent.as_ref().and_then(|p| p.upgrade());
+        if let Some(parent) = parent {
+            let mut parent_inner = parent.inner_exclusive_access();
+            parent_inner.childrens.remove(&self.0.pid());
+            parent_inner.orphans.remove(&self.0.pid());
+        }
+    }
+}
+
+/// The guard which keeps the previous current task of the processor,
+/// it will be restored as the current task when the guard is dropped
+#[cfg(test)]
+pub(crate) struct CurrentTaskGuard(Option<Arc<TaskControlBlock>>);
+#[cfg(test)]
+impl CurrentTaskGuard {
+    /// Run the task as the current task until the guard is dropped
+    ///
+    /// - Arguments
+    ///     - task: the new current task
+    pub(crate) fn enter(task: Arc<TaskControlBlock>) -> Self {
+        Self(super::PROCESSOR.replace_current_task(Some(task)))
+    }
+}
+#[cfg(test)]
+impl Drop for CurrentTaskGuard {
+    fn drop(&mut self) {
+        super::PROCESSOR.replace_current_task(self.0.take());
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::fs::pipe::Pipe;
+    use crate::task::signal::custom_action;
+    use crate::task::handle_current_breakpoint;
+    use crate::memory::cgroup::CGROUP_TABLE;
+    use crate::memory::frame::FRAME_ALLOCATOR;
 
     #[test_case]
     fn test_kernel_stack() {
-        let stack = KernelStack::new();
+        let stack = KernelStack::new(configs::KERNEL_TASK_STACK_BYTE_SIZE);
         assert!(stack.is_ok());
         let stack = stack.unwrap();
         let id = stack.id();
         drop(stack);
-        let stack = KernelStack::new();
+        let stack = KernelStack::new(configs::KERNEL_TASK_STACK_MAX_BYTE_SIZE);
         assert!(stack.is_ok());
         let stack = stack.unwrap();
         assert_eq!(stack.id(), id);
+        assert_eq!(stack.byte_size(), configs::KERNEL_TASK_STACK_MAX_BYTE_SIZE);
+        assert!(KernelStack::new(0).is_err_and(|e| e.is_invalidkernelstacksize()));
+        assert!(KernelStack::new(configs::KERNEL_TASK_STACK_MAX_BYTE_SIZE + 1)
+            .is_err_and(|e| e.is_invalidkernelstacksize()));
+    }
+
+    #[test_case]
+    fn test_reap_orphan_zombie_children() {
+        let root_task = INIT_PROC.inner_access().root_task();
+        let mut max_orphan_pid = 0;
+        for _ in 0..16 {
+            let parent = root_task.fork_process().unwrap();
+            let parent_task = parent.inner_access().root_task();
+            let orphan = parent_task.fork_process().unwrap();
+            let orphan_task = orphan.inner_access().root_task();
+            let orphan_pid = orphan.pid();
+            max_orphan_pid = max_orphan_pid.max(orphan_pid);
+            parent.mark_zombie(0);
+            assert!(INIT_PROC.inner_access().get_child(orphan_pid).is_some());
+            // the parent is not an orphan, it is left to the wait_pid syscall
+            INIT_PROC.inner_exclusive_access().childrens.remove(&parent.pid());
+            drop(orphan_task);
+            drop(orphan);
+            drop(parent_task);
+            drop(parent);
+            assert_eq!(INIT_PROC.reap_zombie_children(), 1);
+            assert!(INIT_PROC.inner_access().get_child(orphan_pid).is_none());
+        }
+        // the pids of the reaped orphans are recycled
+        let process = ForkedProcess::new();
+        assert!(process.pid() <= max_orphan_pid);
+        drop(process);
+        assert_eq!(INIT_PROC.reap_zombie_children(), 0);
+    }
+
+    #[test_case]
+    fn test_handle_all_signals_with_task_borrowed() {
+        let process = ForkedProcess::new();
+        let task = process.inner_access().root_task();
+        // the root task is borrowed when handling signals, no exclusive borrow should be required
+        let task_inner = task.inner_access();
+        assert!(process.kill(Signal::STOP).is_ok());
+        assert_eq!(process.handle_all_signals().unwrap(), (false, true));
+        assert!(process.kill(Signal::CONT).is_ok());
+        assert_eq!(process.handle_all_signals().unwrap(), (false, false));
+        assert!(process.kill_rt(configs::SIGNAL_RT_MIN, 0).is_ok());
+        assert_eq!(process.handle_all_signals().unwrap(), (false, false));
+        assert!(!process.inner_access().has_pending_signal());
+        drop(task_inner);
+    }
+
+    #[test_case]
+    fn test_handle_all_signals_with_custom_handler() {
+        let process = ForkedProcess::new();
+        let task = process.inner_access().root_task();
+        let trap_ctx = || {
+            let inner = process.inner_access();
+            let task_inner = task.inner_access();
+            task_inner
+                .modify_trap_ctx(&inner.space, |trap_ctx| Ok(trap_ctx.clone()))
+                .unwrap()
+        };
+        let handler = 0x1000;
+        process
+            .inner_exclusive_access()
+            .set_signal_action(Signal::USR1, custom_action(handler));
+        let before = trap_ctx();
+        assert!(process.kill(Signal::USR1).is_ok());
+        assert_eq!(process.handle_all_signals().unwrap(), (false, false));
+        // the root task will run the handler with the signal number instead of the interrupted code
+        let handling = trap_ctx();
+        assert_eq!(handling.sepc, handler);
+        assert_eq!(handling.get_arg(0), Signal::USR1 as usize);
+        assert!(!process.inner_access().has_pending_signal());
+        // the interrupted trap context is resumed after the handler returns
+        let result = process.inner_exclusive_access().signal_return().unwrap();
+        assert_eq!(result, before.get_arg(0) as isize);
+        assert_eq!(trap_ctx().sepc, before.sepc);
+    }
+
+    #[test_case]
+    fn test_handle_all_signals_after_root_task_exited() {
+        let process = ForkedProcess::new();
+        let task = process.inner_access().root_task();
+        let thread = process
+            .alloc_task(
+                0,
+                &[],
+                configs::KERNEL_TASK_STACK_BYTE_SIZE,
+                configs::USER_TASK_STACK_BYTE_SIZE,
+            )
+            .unwrap();
+        let sepc_of = |task: &Arc<TaskControlBlock>| {
+            let inner = process.inner_access();
+            let task_inner = task.inner_access();
+            task_inner
+                .modify_trap_ctx(&inner.space, |trap_ctx| Ok(trap_ctx.sepc))
+                .unwrap()
+        };
+        assert!(Arc::ptr_eq(&process.inner_access().signal_task(), &task));
+        task.mark_zombie(0);
+        assert!(!process.is_zombie());
+        // the process-wide signal is delivered to the live thread instead of the exited root task
+        assert!(Arc::ptr_eq(&process.inner_access().signal_task(), &thread));
+        let handler = 0x1000;
+        process
+            .inner_exclusive_access()
+            .set_signal_action(Signal::USR1, custom_action(handler));
+        let root_sepc = sepc_of(&task);
+        let thread_sepc = sepc_of(&thread);
+        assert!(process.kill(Signal::USR1).is_ok());
+        assert_eq!(process.handle_all_signals().unwrap(), (false, false));
+        assert_eq!(sepc_of(&thread), handler);
+        assert_eq!(sepc_of(&task), root_sepc);
+        assert!(process.inner_exclusive_access().signal_return().is_ok());
+        assert_eq!(sepc_of(&thread), thread_sepc);
+        thread.mark_zombie(0);
+        assert!(process.is_zombie());
+        drop(thread);
+        drop(task);
+    }
+
+    #[test_case]
+    fn test_mark_zombie_closes_files() {
+        let process = ForkedProcess::new();
+        let task = process.inner_access().root_task();
+        let read_tap = Pipe::new(1);
+        let write_tap = read_tap.writable_fork().unwrap();
+        let fd = process
+            .inner_exclusive_access()
+            .alloc_fd(Arc::new(write_tap))
+            .unwrap();
+        assert!(process.inner_access().get_file(fd).is_some());
+        assert!(!read_tap.all_write_end_closed());
+        process.mark_zombie(0);
+        // the reader will see EOF before the parent reaps the writer
+        assert!(INIT_PROC.inner_access().get_child(process.pid()).is_some());
+        assert!(read_tap.all_write_end_closed());
+        assert!(process.inner_access().get_file(fd).is_none());
+        drop(task);
+    }
+
+    #[test_case]
+    fn test_nofile_limit() {
+        let process = ForkedProcess::new();
+        let mut inner = process.inner_exclusive_access();
+        let read_tap = Arc::new(Pipe::new(1));
+        let opened = inner.fd_table.len();
+        // the hard limit must not be raised and the soft limit must not exceed it
+        assert!(!inner.set_nofile_limit(RLimit::new(configs::MAX_FD_COUNT + 1)));
+        assert!(!inner.set_nofile_limit(RLimit { soft: 2, hard: 1 }));
+        assert!(inner.set_nofile_limit(RLimit {
+            soft: opened + 1,
+            hard: opened + 2
+        }));
+        let fd = inner.alloc_fd(read_tap.clone()).unwrap();
+        assert!(inner
+            .alloc_fd(read_tap.clone())
+            .is_err_and(|e| e.is_filedescriptorexhausted()));
+        // lowering the soft limit keeps the opened files
+        assert!(inner.set_nofile_limit(RLimit::new(1)));
+        assert!(inner.get_file(fd).is_some());
+        inner.dealloc_fd(fd).unwrap();
+        assert!(inner
+            .alloc_fd(read_tap.clone())
+            .is_err_and(|e| e.is_filedescriptorexhausted()));
+        assert!(!inner.set_nofile_limit(RLimit::new(opened + 2)));
+        drop(inner);
+    }
+
+    #[test_case]
+    fn test_set_uid_and_gid() {
+        let process = ForkedProcess::new();
+        let task = process.inner_access().root_task();
+        let mut inner = process.inner_exclusive_access();
+        assert!(inner.is_privileged());
+        assert!(!inner.set_uid(42));
+        assert!(inner.set_gid(USER_GID));
+        assert!(inner.set_uid(USER_UID));
+        assert!(!inner.is_privileged());
+        // the unprivileged process can not get the privilege back
+        assert!(!inner.set_uid(ROOT_UID));
+        assert!(!inner.set_gid(ROOT_GID));
+        assert!(inner.set_uid(USER_UID));
+        drop(inner);
+        let child = ForkedProcess::fork(&task);
+        assert_eq!(child.inner_access().uid(), USER_UID);
+        assert_eq!(child.inner_access().gid(), USER_GID);
+    }
+
+    #[test_case]
+    fn test_ptrace_attach_and_peek() {
+        let process = ForkedProcess::new();
+        let sibling = ForkedProcess::new();
+        assert!(sibling.inner_exclusive_access().set_uid(USER_UID));
+        // only the parent or the privileged process can attach, and only once
+        assert!(!process.attach(&sibling));
+        assert!(!INIT_PROC.attach(&process));
+        assert!(process.attach(&INIT_PROC));
+        assert!(process.is_traced_by(&INIT_PROC));
+        assert!(!process.attach(&INIT_PROC));
+        let mut regs = process.user_regs().unwrap();
+        let sp = regs.x[2];
+        // the unmapped or unaligned address is rejected instead of faulting
+        assert!(process
+            .peek_word(0)
+            .is_err_and(|e| e.is_userrangenotaccessible()));
+        assert!(process
+            .peek_word(sp + 1)
+            .is_err_and(|e| e.is_userrangenotaccessible()));
+        let word = process.peek_word(sp).unwrap();
+        process.poke_word(sp, !word).unwrap();
+        assert_eq!(process.peek_word(sp).unwrap(), !word);
+        regs.x[0] = 1;
+        regs.pc += 4;
+        process.set_user_regs(&regs).unwrap();
+        let changed = process.user_regs().unwrap();
+        assert_eq!(changed.x[0], 0);
+        assert_eq!(changed.pc, regs.pc);
+        assert!(!process.detach(&sibling).unwrap());
+        assert!(process.detach(&INIT_PROC).unwrap());
+        assert!(process.tracer().is_none());
+    }
+
+    #[test_case]
+    fn test_ptrace_single_step_breakpoints() {
+        let process = ForkedProcess::new();
+        let task = process.inner_access().root_task();
+        assert!(process.attach(&INIT_PROC));
+        let pc = process.user_regs().unwrap().pc;
+        let inst = step::read_instruction(&process, pc).unwrap();
+        process.step().unwrap();
+        let breakpoints = process.inner_access().step_breakpoints.clone();
+        assert!(!breakpoints.is_empty());
+        for (addr, _) in breakpoints.iter() {
+            assert_eq!(step::read_half(&process, *addr).unwrap(), step::C_EBREAK);
+        }
+        // the breakpoint which was not inserted by the single-step is left to the signal
+        assert!(!process.complete_step(&task, pc + 1).unwrap());
+        assert!(process.complete_step(&task, breakpoints[0].0).unwrap());
+        assert!(process.inner_access().step_breakpoints.is_empty());
+        for (addr, original) in breakpoints {
+            assert_eq!(step::read_half(&process, addr).unwrap(), original);
+        }
+        assert_eq!(step::read_instruction(&process, pc).unwrap(), inst);
+        assert!(process.detach(&INIT_PROC).unwrap());
+    }
+
+    #[test_case]
+    fn test_ptrace_tracer_exit_continues_tracee() {
+        let tracee = ForkedProcess::new();
+        let tracer = ForkedProcess::new();
+        assert!(tracee.attach(&tracer));
+        assert_eq!(tracee.handle_all_signals().unwrap(), (false, true));
+        assert!(tracee.is_stopped());
+        // the tracee is detached and continued as soon as the tracer exits
+        drop(tracer);
+        assert!(tracee.tracer().is_none());
+        assert_eq!(tracee.handle_all_signals().unwrap(), (false, false));
+        assert!(!tracee.is_stopped());
+    }
+
+    #[test_case]
+    fn test_ptrace_single_step_completed_by_root_task() {
+        let process = ForkedProcess::new();
+        let thread = process
+            .alloc_task(
+                0,
+                &[],
+                configs::KERNEL_TASK_STACK_BYTE_SIZE,
+                configs::USER_TASK_STACK_BYTE_SIZE,
+            )
+            .unwrap();
+        assert!(process.attach(&INIT_PROC));
+        assert_eq!(process.handle_all_signals().unwrap(), (false, true));
+        let mut regs = process.user_regs().unwrap();
+        let pc = regs.pc;
+        let inst = step::read_instruction(&process, pc).unwrap();
+        process.step().unwrap();
+        assert_eq!(process.handle_all_signals().unwrap(), (false, false));
+        let target = process.inner_access().step_breakpoints[0].0;
+        // the other task reaching the breakpoint neither completes nor cancels the step
+        assert!(process.complete_step(&thread, target).unwrap());
+        assert!(!process.inner_access().step_breakpoints.is_empty());
+        assert_eq!(process.handle_all_signals().unwrap(), (false, false));
+        // the root task executed the instruction and trapped at the breakpoint
+        regs.pc = target;
+        process.set_user_regs(&regs).unwrap();
+        let current = process.enter();
+        handle_current_breakpoint().unwrap();
+        drop(current);
+        assert!(process.inner_access().step_breakpoints.is_empty());
+        assert_eq!(process.handle_all_signals().unwrap(), (false, true));
+        assert!(process.is_stopped());
+        assert_eq!(process.user_regs().unwrap().pc, target);
+        assert_eq!(step::read_instruction(&process, pc).unwrap(), inst);
+        assert!(process.detach(&INIT_PROC).unwrap());
+        thread.mark_zombie(0);
+        drop(thread);
+    }
+
+    #[test_case]
+    fn test_ebreak_traps_untraced_and_traced() {
+        // the untraced process moves past the ebreak and will be terminated by the signal
+        {
+            let process = ForkedProcess::new();
+            let task = process.inner_access().root_task();
+            let pc = process.user_regs().unwrap().pc;
+            step::write_half(&process, pc, step::C_EBREAK).unwrap();
+            process.reach_breakpoint(&task, pc).unwrap();
+            assert_eq!(process.user_regs().unwrap().pc, pc + 2);
+            assert_eq!(process.check_bad_signals(), Some(Signal::TRAP));
+        }
+        // the traced process is stopped at the ebreak until it is continued
+        let process = ForkedProcess::new();
+        let task = process.inner_access().root_task();
+        assert!(process.attach(&INIT_PROC));
+        let pc = process.user_regs().unwrap().pc;
+        // the full ebreak instruction
+        step::write_half(&process, pc, 0x0073).unwrap();
+        step::write_half(&process, pc + 2, 0x0010).unwrap();
+        process.reach_breakpoint(&task, pc).unwrap();
+        assert_eq!(process.user_regs().unwrap().pc, pc);
+        assert_eq!(process.handle_all_signals().unwrap(), (false, true));
+        assert!(process.is_stopped());
+        assert_eq!(process.check_bad_signals(), None);
+        process.resume().unwrap();
+        assert_eq!(process.user_regs().unwrap().pc, pc + 4);
+        assert!(process.detach(&INIT_PROC).unwrap());
+    }
+
+    #[test_case]
+    fn test_space_and_task_limits() {
+        let process = ForkedProcess::new();
+        let task = process.inner_access().root_task();
+        let kernel_stack_size = configs::KERNEL_TASK_STACK_BYTE_SIZE;
+        let user_stack_size = configs::USER_TASK_STACK_BYTE_SIZE;
+        let id = SHM_TABLE
+            .get(usize::MAX, configs::MEMORY_PAGE_BYTE_SIZE)
+            .unwrap()
+            .unwrap();
+        let free_frames = FRAME_ALLOCATOR.access().free_count();
+        let mapped = process.inner_access().space().mapped_byte_size();
+        assert!(process
+            .inner_exclusive_access()
+            .set_space_limit(RLimit::new(mapped)));
+        // the new mappings are rejected before any frame is allocated
+        assert!(process
+            .alloc_task(0, &[], kernel_stack_size, user_stack_size)
+            .is_err_and(|e| e.is_spacelimitexceeded()));
+        assert!(process
+            .inner_exclusive_access()
+            .attach_shm(id)
+            .is_err_and(|e| e.is_spacelimitexceeded()));
+        assert_eq!(FRAME_ALLOCATOR.access().free_count(), free_frames);
+        assert_eq!(process.inner_access().space().mapped_byte_size(), mapped);
+        // the forked child inherits the limits
+        let child = ForkedProcess::fork(&task);
+        assert_eq!(child.inner_access().space_limit(), RLimit::new(mapped));
+        assert!(process.inner_exclusive_access().set_task_limit(RLimit::new(1)));
+        assert!(process
+            .alloc_task(0, &[], kernel_stack_size, user_stack_size)
+            .is_err_and(|e| e.is_tasklimitexceeded()));
+        assert!(task.fork_process().is_err_and(|e| e.is_tasklimitexceeded()));
+        // the zombie child is not counted into the limit any more
+        let child_task = child.inner_access().root_task();
+        child.mark_zombie(0);
+        drop(child_task);
+        let other = ForkedProcess::fork(&task);
+        assert!(!other.is_zombie());
+        assert!(task.fork_process().is_err_and(|e| e.is_tasklimitexceeded()));
+        // release the segment which was never attached
+        SHM_TABLE.attach(id).unwrap();
+        SHM_TABLE.detach(id).unwrap();
+        assert!(SHM_TABLE.byte_size(id).is_none());
+    }
+
+    #[test_case]
+    fn test_barrier_table() {
+        let process = ForkedProcess::new();
+        let task = process.inner_access().root_task();
+        let mut inner = process.inner_exclusive_access();
+        let first = inner.alloc_barrier(2).unwrap();
+        let second = inner.alloc_barrier(2).unwrap();
+        assert_ne!(first, second);
+        assert!(inner.dealloc_barrier(first).is_ok());
+        assert!(inner.get_barrier(first).is_none());
+        assert_eq!(inner.alloc_barrier(2).unwrap(), first);
+        assert!(inner.dealloc_barrier(second + 1).is_err_and(|e| e.is_barrierdoesnotexist()));
+        let barrier = Arc::downgrade(inner.get_barrier(second).unwrap());
+        drop(inner);
+        process.mark_zombie(0);
+        // the barriers are freed as soon as the process exits
+        assert!(barrier.upgrade().is_none());
+        drop(task);
+    }
+
+    #[test_case]
+    fn test_joiners_cleared_on_exit() {
+        let process = ForkedProcess::new();
+        let task = process.inner_access().root_task();
+        let thread = process
+            .alloc_task(
+                0,
+                &[],
+                configs::KERNEL_TASK_STACK_BYTE_SIZE,
+                configs::USER_TASK_STACK_BYTE_SIZE,
+            )
+            .unwrap();
+        assert!(process.inner_access().task(thread.tid()).is_some());
+        thread.add_joiner(&task);
+        assert_eq!(thread.inner_access().joiners.len(), 1);
+        thread.mark_zombie(7);
+        // the joiner which is not blocked will not be put into the ready queue
+        assert!(thread.is_zombie());
+        assert!(thread.inner_access().joiners.is_empty());
+        assert_eq!(thread.inner_access().exit_code, Some(7));
+        process.mark_zombie(0);
+        drop(thread);
+        drop(task);
+    }
+
+    #[test_case]
+    fn test_last_live_task_exit_process() {
+        let process = ForkedProcess::new();
+        let task = process.inner_access().root_task();
+        let worker = process
+            .alloc_task(
+                0,
+                &[],
+                configs::KERNEL_TASK_STACK_BYTE_SIZE,
+                configs::USER_TASK_STACK_BYTE_SIZE,
+            )
+            .unwrap();
+        assert_eq!(process.inner_access().live_task_count(), 2);
+        // the root task exits first, the process is still alive
+        task.mark_zombie(1);
+        assert!(task.is_zombie());
+        assert!(!process.is_zombie());
+        assert!(task.user_stack_size().is_some());
+        assert_eq!(process.inner_access().live_task_count(), 1);
+        // the worker exits last and tears down the process
+        worker.mark_zombie(2);
+        assert!(process.is_zombie());
+        assert_eq!(process.inner_access().get_exit_code(), Some(2));
+        assert!(task.user_stack_size().is_none());
+        assert_eq!(task.inner_access().exit_code, Some(1));
+        drop(worker);
+        drop(task);
+    }
+
+    #[test_case]
+    fn test_alloc_task_init_registers() {
+        let process = ForkedProcess::new();
+        let task = process.inner_access().root_task();
+        let user_stack_size = configs::USER_TASK_STACK_BYTE_SIZE * 2;
+        let thread = process
+            .alloc_task(
+                0,
+                &[1, 2, 3, 4],
+                configs::KERNEL_TASK_STACK_BYTE_SIZE,
+                user_stack_size,
+            )
+            .unwrap();
+        assert_eq!(thread.user_stack_size(), Some(user_stack_size));
+        assert!(process
+            .alloc_task(
+                0,
+                &[],
+                configs::KERNEL_TASK_STACK_BYTE_SIZE,
+                configs::USER_TASK_STACK_MAX_BYTE_SIZE + 1,
+            )
+            .is_err_and(|e| e.is_invaliduserstacksize()));
+        let process_inner = process.inner_access();
+        let user_stack_top_va =
+            Space::get_user_task_stack_top_va(process_inner.base_size, thread.tid()).unwrap();
+        thread
+            .inner_access()
+            .modify_trap_ctx(process_inner.space(), |trap_ctx| {
+                assert_eq!(trap_ctx.x[4], user_stack_top_va);
+                for index in 0..4 {
+                    assert_eq!(trap_ctx.get_arg(index), index + 1);
+                }
+                Ok(())
+            })
+            .unwrap();
+        let user_stack_bottom_va = user_stack_top_va - user_stack_size;
+        assert!(process_inner
+            .space()
+            .check_user_range(user_stack_bottom_va as *const u8, user_stack_size, true)
+            .is_ok());
+        drop(process_inner);
+        process.mark_zombie(0);
+        drop(thread);
+        drop(task);
+    }
+
+    #[test_case]
+    fn test_check_join_deadlock() {
+        let process = ForkedProcess::new();
+        let task = process.inner_access().root_task();
+        let first = process
+            .alloc_task(
+                0,
+                &[],
+                configs::KERNEL_TASK_STACK_BYTE_SIZE,
+                configs::USER_TASK_STACK_BYTE_SIZE,
+            )
+            .unwrap();
+        let second = process
+            .alloc_task(
+                0,
+                &[],
+                configs::KERNEL_TASK_STACK_BYTE_SIZE,
+                configs::USER_TASK_STACK_BYTE_SIZE,
+            )
+            .unwrap();
+        assert!(first
+            .check_join(&first)
+            .is_err_and(|e| e.is_joindeadlock()));
+        // the first thread is joining the second, and the root task is joining the first
+        second.add_joiner(&first);
+        first.add_joiner(&task);
+        assert!(task.check_join(&second).is_ok());
+        assert!(second
+            .check_join(&first)
+            .is_err_and(|e| e.is_joindeadlock()));
+        assert!(second
+            .check_join(&task)
+            .is_err_and(|e| e.is_joindeadlock()));
+        second.mark_zombie(0);
+        assert!(first.inner_access().joining.is_none());
+        first.mark_zombie(0);
+        assert!(task.inner_access().joining.is_none());
+        process.mark_zombie(0);
+        drop(first);
+        drop(second);
+        drop(task);
+    }
+
+    #[test_case]
+    fn test_kill_wakes_only_sleeping_tasks_of_target() {
+        let target = ForkedProcess::new();
+        let target_task = target.inner_access().root_task();
+        let other = ForkedProcess::new();
+        let other_task = other.inner_access().root_task();
+        TASK_SCHEDULER.put_sleep_task(usize::MAX, Arc::clone(&target_task));
+        TASK_SCHEDULER.put_sleep_task(usize::MAX, Arc::clone(&other_task));
+        assert!(target.kill(Signal::USR1).is_ok());
+        // the target is ready to handle the signal, but the other task is still sleeping
+        assert!(TASK_SCHEDULER.fetch_specific(&target_task));
+        assert!(Arc::ptr_eq(&TASK_SCHEDULER.pop_ready_task().unwrap(), &target_task));
+        assert!(!TASK_SCHEDULER.fetch_specific(&other_task));
+        assert!(TASK_SCHEDULER.wake_sleep_task(&other_task));
+        assert!(TASK_SCHEDULER.fetch_specific(&other_task));
+        assert!(Arc::ptr_eq(&TASK_SCHEDULER.pop_ready_task().unwrap(), &other_task));
+    }
+
+    #[test_case]
+    fn test_task_kill_is_pending_per_task() {
+        let process = ForkedProcess::new();
+        let task = process.inner_access().root_task();
+        let other = process
+            .alloc_task(
+                0,
+                &[],
+                configs::KERNEL_TASK_STACK_BYTE_SIZE,
+                configs::USER_TASK_STACK_BYTE_SIZE,
+            )
+            .unwrap();
+        assert!(other.kill(Signal::USR1).is_ok());
+        assert!(other
+            .kill(Signal::USR1)
+            .is_err_and(|e| e.is_duplicatesignal()));
+        assert!(other.has_pending_signal());
+        assert!(!task.has_pending_signal());
+        assert!(!process.inner_access().signal.is_pending_signal(Signal::USR1));
+        // the signal without custom handler is forwarded to the process
+        assert!(other.handle_own_signals().is_ok());
+        assert!(!other.has_pending_signal());
+        assert!(process.inner_access().signal.is_pending_signal(Signal::USR1));
+        assert!(other.signal_return().unwrap().is_none());
+        other.mark_zombie(0);
+        process.mark_zombie(0);
+        drop(other);
+        drop(task);
+    }
+
+    #[test_case]
+    fn test_task_restart_interrupted_syscall() {
+        let process = ForkedProcess::new();
+        let task = process.inner_access().root_task();
+        task.inner_access()
+            .modify_trap_ctx(&process.inner_access().space, |trap_ctx| {
+                trap_ctx.set_arg(0, 7);
+                Ok(())
+            })
+            .unwrap();
+        let mut trap_ctx = TrapContext::default();
+        trap_ctx.sepc = 0x1004;
+        trap_ctx.set_arg(0, usize::MAX);
+        // the syscall keeps its interrupted result without the restart flag
+        assert!(task.mark_syscall_interrupted().is_ok());
+        task.inner_exclusive_access()
+            .restart_interrupted_syscall(false, &mut trap_ctx);
+        assert_eq!(trap_ctx.sepc, 0x1004);
+        assert_eq!(trap_ctx.get_arg(0), usize::MAX);
+        // the forgotten syscall will not be restarted
+        task.inner_exclusive_access()
+            .restart_interrupted_syscall(true, &mut trap_ctx);
+        assert_eq!(trap_ctx.sepc, 0x1004);
+        assert!(task.mark_syscall_interrupted().is_ok());
+        task.inner_exclusive_access()
+            .restart_interrupted_syscall(true, &mut trap_ctx);
+        assert_eq!(trap_ctx.sepc, 0x1000);
+        assert_eq!(trap_ctx.get_arg(0), 7);
+    }
+
+    #[test_case]
+    fn test_task_refault_counts_minor_faults() {
+        let process = ForkedProcess::new();
+        let task = process.inner_access().root_task();
+        // the counters of the forked task start from zero
+        assert_eq!(task.rusage(), RUsage::default());
+        let base_size = process.inner_access().base_size;
+        let top_va = Space::get_user_task_stack_top_va(base_size, ROOT_TID).unwrap();
+        let va = top_va - configs::MEMORY_PAGE_BYTE_SIZE;
+        assert!(!task.refault(va).unwrap());
+        for count in 1..=4 {
+            assert!(process
+                .inner_exclusive_access()
+                .space_mut()
+                .discard_user_pages(va, configs::MEMORY_PAGE_BYTE_SIZE)
+                .unwrap());
+            assert!(task.refault(va).unwrap());
+            assert_eq!(task.rusage().minor_faults, count);
+        }
+        assert_eq!(task.rusage().major_faults, 0);
+        assert!(task.rusage().max_resident_frames > 0);
+        assert_eq!(process.rusage().minor_faults, 4);
+    }
+
+    #[test_case]
+    fn test_process_info_path() {
+        let info = ProcessInfo::new(1, 0, TaskStatus::Ready, "/initproc");
+        assert_eq!(info.status, TaskStatus::Ready as usize);
+        assert_eq!(&info.path[..10], b"/initproc\0");
+        let path = "a".repeat(configs::PROCESS_INFO_PATH_BYTE_SIZE * 2);
+        let info = ProcessInfo::new(1, 0, TaskStatus::Running, &path);
+        assert_eq!(info.path[configs::PROCESS_INFO_PATH_BYTE_SIZE - 1], 0);
+        assert_eq!(info.path[configs::PROCESS_INFO_PATH_BYTE_SIZE - 2], b'a');
+        assert_eq!(info.as_bytes().len(), core::mem::size_of::<ProcessInfo>());
+    }
+
+    #[test_case]
+    fn test_exec_keeps_mem_cgroup_charge() {
+
+        let process = ForkedProcess::new();
+        let task = process.inner_access().root_task();
+        let usage = process.inner_access().space().resident_frames();
+        assert!(usage > 0);
+        // the process uses more than half of the budget of the new group
+        let id = CGROUP_TABLE.create(usage * 2 - 1).unwrap();
+        let cgroup = CGROUP_TABLE.get(id).unwrap();
+        let joined = Some(Arc::clone(&cgroup));
+        assert!(process.inner_exclusive_access().space.set_cgroup(joined).is_ok());
+        assert_eq!(cgroup.stat().usage, usage);
+        let path = configs::INIT_PROCESS_PATH;
+        let file = ROOT_INODE.find_inode(path, OpenFlags::READ, Credential::ROOT).unwrap();
+        let data = file.read_all().unwrap();
+        assert!(task.exec(String::from(path), &data, String::new()).is_ok());
+        let usage = cgroup.stat().usage;
+        assert!(usage > 0);
+        assert_eq!(usage, process.inner_access().space().resident_frames());
+        assert_eq!(cgroup.stat().failcnt, 0);
     }
 }
@@ -12,13 +12,31 @@ use super::model::{TaskControlBlock, INIT_PROC};
 use super::scheduler::TASK_SCHEDULER;
 use super::{switch, Signal};
 use crate::lang::container::UserPromiseRefCell;
+use crate::memory::frame::FRAME_ALLOCATOR;
+use crate::sbi::*;
+use crate::trap;
 use crate::{configs, prelude::*};
 
+/// The reason why the current task gives up the processor, which decides the queue it is put back
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Suspension {
+    /// The task yields and stays in it's level
+    Yielded,
+    /// The task used up it's time slice and drops to the lower level
+    Preempted,
+    /// The task yields and is put into the lower level for one round only
+    Lowered,
+}
+
 /// Keep the current running task the processor structure
 pub(crate) struct Processor {
     current: Option<Arc<TaskControlBlock>>,
     empty_task_ctx: TaskContext,
+    /// The context of the idle task, which is the scheduling loop running on the boot stack.
+    /// The idle task is not a real task, it has no process and is never accounted or listed.
     idle_task_ctx: TaskContext,
+    /// Whether the scheduling loop is running, the tasks can only be switched after it started
+    scheduling: bool,
 }
 impl Processor {
     /// Create a new empty processor
@@ -28,6 +46,7 @@ impl Processor {
             current: None,
             empty_task_ctx,
             idle_task_ctx: empty_task_ctx,
+            scheduling: false,
         }
     }
 
@@ -57,7 +76,33 @@ impl PROCESSOR {
             .ok_or(KernelError::ProcessHaveNotTask)
     }
 
+    /// Get the task which was currently run without panic,
+    /// None will be returned if there is no running task or the processor was borrowed exclusively.
+    pub(crate) fn try_current_task(&self) -> Option<Arc<TaskControlBlock>> {
+        self.try_access()?.current()
+    }
+
+    /// Check whether the scheduling loop is running, so that the current task can be blocked.
+    /// The tests run on the boot stack without the scheduler, the tasks are never switched there.
+    pub(crate) fn is_scheduling(&self) -> bool {
+        self.access().scheduling
+    }
+
+    /// Replace the current task and return the previous one,
+    /// so the tests can run the syscalls on behalf of the specific task.
+    ///
+    /// - Arguments
+    ///     - task: the new current task, None means no task is running
+    #[cfg(test)]
+    pub(crate) fn replace_current_task(
+        &self,
+        task: Option<Arc<TaskControlBlock>>,
+    ) -> Option<Arc<TaskControlBlock>> {
+        core::mem::replace(&mut self.exclusive_access().current, task)
+    }
+
     /// switch current process to idle task context
+    #[cfg(not(test))]
     pub(crate) fn switch_from(&self, current_task_ctx_ptr: *mut TaskContext) {
         let mut processor = self.exclusive_access();
         let next_task_ctx_ptr = processor.get_idle_task_ctx_ptr();
@@ -67,10 +112,41 @@ impl PROCESSOR {
         }
     }
 
-    /// Fetch a runnable task and switch current process to it
+    /// There is no idle task context in the tests, which drive the scheduling loop by themselves,
+    /// so the suspended task returns at once and the processor is left without a current task
+    #[cfg(test)]
+    pub(crate) fn switch_from(&self, _current_task_ctx_ptr: *mut TaskContext) {}
+
+    /// Make the hart to sleep until an interrupt arrives, such as the timer or the inter-processor interrupt.
+    /// The hart will not sleep if some task became ready before it was marked as idle.
+    ///
+    /// - Arguments
+    ///     - hart_id: the id of the current hart
+    fn idle(&self, hart_id: usize) {
+        if TASK_SCHEDULER.enter_idle(hart_id) {
+            unsafe { SBI::wait_for_interrupt() };
+        }
+        TASK_SCHEDULER.leave_idle(hart_id);
+        trap::handler::acknowledge_idle_interrupts();
+    }
+
+    /// Fetch a runnable task and switch current process to it.
+    /// When there is no ready task but some tasks are sleeping or blocked,
+    /// the idle task makes the hart sleep until the timer tick or the inter-processor interrupt,
+    /// and then fetches the task which became ready immediately.
+    /// If all the tasks are blocked without timers, the possible deadlock is reported once.
+    ///
+    /// - Arguments
+    ///     - hart_id: the id of the current hart
     #[inline(always)]
-    pub(crate) fn schedule(&self) -> ! {
+    pub(crate) fn schedule(&self, hart_id: usize) -> ! {
+        self.exclusive_access().scheduling = true;
         loop {
+            // the orphans adopted by the initial process will never be waited, reap them in the idle path
+            INIT_PROC.reap_zombie_children();
+            // the cold pages are evicted while no task is running and no space is being borrowed,
+            // the preempted tasks come back here in each timer tick while the free frames are low
+            FRAME_ALLOCATOR.reclaim();
             TASK_SCHEDULER.check_timers();
             if let Some(task) = TASK_SCHEDULER.pop_ready_task() {
                 if task.is_zombie() {
@@ -85,8 +161,18 @@ impl PROCESSOR {
                 unsafe {
                     switch::_fn_switch_task(current_task_ctx_ptr, next_task_ctx_ptr);
                 }
+            } else if TASK_SCHEDULER.advance_idle_clock() {
+                continue;
+            } else if TASK_SCHEDULER.has_sleep_task() {
+                self.idle(hart_id);
+            } else if TASK_SCHEDULER.has_blocked_task() {
+                // no timer will wake the blocked tasks up, only the interrupts can
+                TASK_SCHEDULER.report_stall();
+                self.idle(hart_id);
             } else {
-                panic!("There was no task available in the task queue")
+                // all tasks have exited, the written data must be flushed before shutdown
+                error!("There was no task available in the task queue");
+                crate::fs::sync_and_shutdown()
             }
         }
     }
@@ -96,11 +182,46 @@ impl PROCESSOR {
     /// - Errors
     ///     - ProcessHaveNotTask
     pub(crate) fn suspend_current_and_run_other_task(&self) -> Result<()> {
+        self.suspend_current(Suspension::Yielded)
+    }
+
+    /// Mark current task as suspended and run other runable task,
+    /// the other tasks in the same level will run before the current task in the next round.
+    ///
+    /// - Errors
+    ///     - ProcessHaveNotTask
+    pub(crate) fn lower_current_and_run_other_task(&self) -> Result<()> {
+        self.suspend_current(Suspension::Lowered)
+    }
+
+    /// Mark current task as suspended because it's time slice was used up,
+    /// and run other runable task. The current task will drop to the lower level queue.
+    ///
+    /// - Errors
+    ///     - ProcessHaveNotTask
+    pub(crate) fn preempt_current_and_run_other_task(&self) -> Result<()> {
+        self.suspend_current(Suspension::Preempted)
+    }
+
+    /// Mark current task as suspended and put it back into the ready queues
+    ///
+    /// - Arguments
+    ///     - suspension: the reason why the current task gives up the processor
+    ///
+    /// - Errors
+    ///     - ProcessHaveNotTask
+    fn suspend_current(&self, suspension: Suspension) -> Result<()> {
         let mut processor = self.exclusive_access();
         if let Some(task) = processor.current.take() {
+            task.account_cpu_time(false);
+            task.account_switch(suspension != Suspension::Preempted);
             task.mark_suspended();
             let current_task_ctx_ptr = task.task_ctx_ptr() as *mut TaskContext;
-            TASK_SCHEDULER.put_read_task(task);
+            match suspension {
+                Suspension::Yielded => TASK_SCHEDULER.put_read_task(task),
+                Suspension::Preempted => TASK_SCHEDULER.put_preempted_task(task),
+                Suspension::Lowered => TASK_SCHEDULER.put_lowered_task(task),
+            }
             drop(processor);
             self.switch_from(current_task_ctx_ptr);
             Ok(())
@@ -117,6 +238,8 @@ impl PROCESSOR {
     pub(crate) fn block_current_and_run_other_task(&self, f: impl FnOnce(Arc<TaskControlBlock>) -> Result<()>) -> Result<()> {
         let mut processor = self.exclusive_access();
         if let Some(task) = processor.current.take() {
+            task.account_cpu_time(false);
+            task.account_switch(true);
             task.mark_blocked();
             let current_task_ctx_ptr = task.task_ctx_ptr() as *mut TaskContext;
             f(task)?;
@@ -171,6 +294,24 @@ impl PROCESSOR {
         }
     }
 
+    /// Send signal caused by the memory fault to current task.
+    ///
+    /// - Arguments
+    ///     - signal: which signal will be setted
+    ///     - addr: the faulting virtual address
+    ///
+    /// - Errors
+    ///     - ProcessHaveNotTask
+    ///     - DuplicateSignal(signal)
+    pub(crate) fn send_current_task_fault_signal(&self, signal: Signal, addr: usize) -> Result<()> {
+        let processor = self.access();
+        if let Some(task) = &processor.current {
+            task.process().kill_with_fault_addr(signal, addr)
+        } else {
+            Err(KernelError::ProcessHaveNotTask)
+        }
+    }
+
     /// All received signals are processed until all signals have been processed,
     /// or the current service needs to be terminated/suspended
     ///
@@ -187,6 +328,8 @@ impl PROCESSOR {
         loop {
             let processor = self.access();
             if let Some(task) = &processor.current {
+                // the signals sent to the task are handled before the process-wide ones
+                task.handle_own_signals()?;
                 let process = task.process();
                 let (killed, frozen) = process.handle_all_signals()?;
                 if !frozen || killed {
@@ -201,6 +344,8 @@ impl PROCESSOR {
         }
         let processor = self.access();
         if let Some(task) = &processor.current {
+            // the interrupted syscall which was not restarted by the handler keeps its result
+            task.clear_interrupted_syscall();
             Ok(task.process().check_bad_signals())
         } else {
             Err(KernelError::ProcessHaveNotTask)
@@ -6,12 +6,23 @@
 // use other mods
 use alloc::collections::{BinaryHeap, VecDeque};
 use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicUsize, Ordering};
 
 // use self mods
-use super::model::{ProcessControlBlock, TaskControlBlock, ROOT_PID};
-use super::process::PROCESSOR;
+use super::model::TaskControlBlock;
+use crate::configs;
 use crate::lang::container::UserPromiseRefCell;
-use crate::lang::timer::get_timer_us;
+use crate::lang::timer::now_us;
+use crate::sbi::*;
+
+/// The bit mask of the harts which are sleeping and waiting for the ready tasks,
+/// the bit `n` represent the hart `n`.
+static IDLE_HART_MASK: AtomicUsize = AtomicUsize::new(0);
+
+/// The count of the live tasks which are blocked, including the sleeping tasks.
+/// It is kept in step with the status of the tasks, so the idle path never scans the processes.
+static BLOCKED_TASK_COUNT: AtomicUsize = AtomicUsize::new(0);
 
 /// A wrapper class for organizing storage blocking tasks that are not actively scheduled until the timeout requirements are met.
 pub(crate) struct TimerCondVar {
@@ -39,21 +50,244 @@ impl Ord for TimerCondVar {
     }
 }
 
-/// Task queue that contains all ready tasks which are waiting for running
+/// The preemption trigger of the deterministic scheduling mode.
+/// It counts the syscalls and asks for preemption at fixed syscall-count boundaries,
+/// and also drives a virtual clock, so that the sleeping tasks are woken up
+/// at the same point of the execution no matter how fast the real time goes.
+pub(crate) struct DeterministicTicker {
+    quantum: usize,
+    syscalls: usize,
+    virtual_us: usize,
+}
+impl DeterministicTicker {
+    /// Create a new ticker
+    ///
+    /// - Arguments
+    ///     - quantum: the count of syscalls between two preemptions
+    pub(crate) fn new(quantum: usize) -> Self {
+        Self {
+            quantum: quantum.max(1),
+            syscalls: 0,
+            virtual_us: 0,
+        }
+    }
+
+    /// Count one syscall and advance the virtual clock.
+    ///
+    /// - Returns
+    ///     - true: the current task reach the syscall-count boundary and should be preempted
+    ///     - false: the current task can continue running
+    pub(crate) fn tick(&mut self) -> bool {
+        self.syscalls += 1;
+        self.virtual_us += configs::SCHED_DETERMINISTIC_SYSCALL_US;
+        self.syscalls % self.quantum == 0
+    }
+
+    /// Get the virtual microseconds
+    pub(crate) fn now_us(&self) -> usize {
+        self.virtual_us
+    }
+
+    /// Advance the virtual clock to the specified microseconds,
+    /// the virtual clock never goes back.
+    ///
+    /// - Arguments
+    ///     - us: the target virtual microseconds
+    pub(crate) fn advance_to(&mut self, us: usize) {
+        self.virtual_us = self.virtual_us.max(us);
+    }
+}
+
+/// The statistics of the scheduler shared with the user space
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub(crate) struct SchedStats {
+    /// The count of the context switches since the system was started
+    pub(crate) context_switches: usize,
+    /// The count of the tasks in the ready queue
+    pub(crate) ready_tasks: usize,
+    /// The count of the sleeping tasks in the timer heap
+    pub(crate) sleeping_tasks: usize,
+    /// The count of the tasks which are blocked by the other events, excluding the sleeping tasks
+    pub(crate) blocked_tasks: usize,
+}
+
+/// Task queues that contains all ready tasks which are waiting for running.
+/// The ready tasks are organized as multi-level feedback queues,
+/// the tasks which use up their whole time slice will drop a level,
+/// and the tasks which yield or block early will stay in their level.
+/// If the feature `sched_fifo` was enabled, only the highest level is used,
+/// so that it will degenerate into a simple FIFO queue.
+/// The tasks which yield politely are kept aside in their level for one round,
+/// with the count of the fetches from their level which must happen before they rejoin it.
 pub(crate) struct TaskScheduler {
-    ready: VecDeque<Arc<TaskControlBlock>>,
+    ready: [VecDeque<Arc<TaskControlBlock>>; configs::SCHED_MLFQ_LEVEL_COUNT],
+    lowered: [VecDeque<(Arc<TaskControlBlock>, usize)>; configs::SCHED_MLFQ_LEVEL_COUNT],
     timer: BinaryHeap<TimerCondVar>,
+    deterministic: bool,
+    ticker: DeterministicTicker,
+    switches: usize,
+    boosted_us: usize,
+    directed_yields: usize,
+    stall_reported: bool,
 }
 
 impl TaskScheduler {
-    /// Put a task into the ready queue
+    /// Get the lowest level which the ready tasks can be put into
+    fn lowest_level() -> usize {
+        if cfg!(feature = "sched_fifo") {
+            0
+        } else {
+            configs::SCHED_MLFQ_LEVEL_COUNT - 1
+        }
+    }
+
+    /// Put a task into the ready queue of it's current level
     fn put_as_ready(&mut self, task: Arc<TaskControlBlock>) {
-        self.ready.push_back(task);
+        let level = task.sched_level().min(Self::lowest_level());
+        self.ready[level].push_back(task);
     }
 
-    /// Fetch and pop the first ready task from the queue
+    /// Put a task which use up it's whole time slice into the ready queue of the next lower level
+    fn put_as_preempted(&mut self, task: Arc<TaskControlBlock>) {
+        self.directed_yields = 0;
+        let level = (task.sched_level() + 1).min(Self::lowest_level());
+        task.set_sched_level(level);
+        self.ready[level].push_back(task);
+    }
+
+    /// Put a task which yields politely aside in it's own level for one round,
+    /// so that all the other tasks which are ready in it's level will run first,
+    /// including the tasks which become ready during the round.
+    /// It is still fetched before the tasks in the lower levels,
+    /// and it's level is not changed, so the penalty is over after the round.
+    fn put_as_lowered(&mut self, task: Arc<TaskControlBlock>) {
+        let level = task.sched_level().min(Self::lowest_level());
+        let round = self.ready[level].len();
+        if round == 0 {
+            self.ready[level].push_back(task);
+        } else {
+            self.lowered[level].push_back((task, round));
+        }
+    }
+
+    /// Count one fetch from the level for the lowered tasks,
+    /// the tasks whose round is over will rejoin the tail of the ready queue of the level.
+    ///
+    /// - Arguments
+    ///     - level: the level which the task was fetched from
+    fn count_lowered_round(&mut self, level: usize) {
+        let mut index = 0;
+        while index < self.lowered[level].len() {
+            let round = &mut self.lowered[level][index].1;
+            *round -= 1;
+            if *round == 0 {
+                let (task, _) = self.lowered[level].remove(index).unwrap();
+                self.ready[level].push_back(task);
+            } else {
+                index += 1;
+            }
+        }
+    }
+
+    /// Pop the first ready task of the level,
+    /// the lowered tasks of the level are only popped when there is no other ready task in it.
+    ///
+    /// - Arguments
+    ///     - level: the level of the queues
+    fn pop_level(&mut self, level: usize) -> Option<Arc<TaskControlBlock>> {
+        if let Some(task) = self.ready[level].pop_front() {
+            self.count_lowered_round(level);
+            Some(task)
+        } else {
+            self.lowered[level].pop_front().map(|(task, _)| task)
+        }
+    }
+
+    /// Fetch and pop the first ready task from the highest non-empty level,
+    /// each fetched task will be switched to, so the context switch is counted here
     fn pop_ready(&mut self) -> Option<Arc<TaskControlBlock>> {
-        self.ready.pop_front()
+        let task = (0..self.ready.len()).find_map(|level| self.pop_level(level));
+        if task.is_some() {
+            self.switches += 1;
+            self.stall_reported = false;
+        }
+        task
+    }
+
+    /// Get the count of the ready tasks, including the lowered tasks
+    fn count_ready(&self) -> usize {
+        let ready: usize = self.ready.iter().map(|queue| queue.len()).sum();
+        let lowered: usize = self.lowered.iter().map(|queue| queue.len()).sum();
+        ready + lowered
+    }
+
+    /// Move the specified ready task to the head of the highest level, so that it will be fetched next.
+    /// The level of the task will not be changed.
+    ///
+    /// - Arguments
+    ///     - task: the task which will be run next
+    ///
+    /// - Returns
+    ///     - true: the task was ready and moved to the head
+    ///     - false: the task was not ready or the count of consecutive directed yields reached the limit
+    fn fetch_specific(&mut self, task: &Arc<TaskControlBlock>) -> bool {
+        if self.directed_yields >= configs::SCHED_MAX_DIRECTED_YIELD_COUNT {
+            self.directed_yields = 0;
+            return false;
+        }
+        // the lowered task is ready too, it's penalty is cancelled by the directed yield
+        for level in 0..self.lowered.len() {
+            let position = self.lowered[level]
+                .iter()
+                .position(|(other, _)| Arc::ptr_eq(other, task));
+            if let Some(index) = position {
+                let (task, _) = self.lowered[level].remove(index).unwrap();
+                self.ready[level].push_back(task);
+            }
+        }
+        let position = self.ready.iter().enumerate().find_map(|(level, queue)| {
+            queue
+                .iter()
+                .position(|other| Arc::ptr_eq(other, task))
+                .map(|index| (level, index))
+        });
+        if let Some((level, index)) = position {
+            let task = self.ready[level].remove(index).unwrap();
+            self.ready[0].push_front(task);
+            self.directed_yields += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Move all ready tasks to the highest level if the boost interval has elapsed,
+    /// so that the tasks in the lower levels will never be starved.
+    ///
+    /// - Arguments
+    ///     - now_us: the current microseconds of the scheduler's clock
+    ///
+    /// - Returns
+    ///     - true: the ready tasks were boosted
+    ///     - false: the boost interval has not elapsed yet
+    fn boost(&mut self, now_us: usize) -> bool {
+        if now_us.saturating_sub(self.boosted_us) < configs::SCHED_MLFQ_BOOST_INTERVAL_US {
+            return false;
+        }
+        self.boosted_us = now_us;
+        for level in 0..self.ready.len() {
+            while let Some((task, _)) = self.lowered[level].pop_front() {
+                self.ready[level].push_back(task);
+            }
+        }
+        for level in 1..self.ready.len() {
+            while let Some(task) = self.ready[level].pop_front() {
+                task.set_sched_level(0);
+                self.ready[0].push_back(task);
+            }
+        }
+        true
     }
 
     /// Put a tasks into the timer heap
@@ -69,24 +303,29 @@ impl TaskScheduler {
         self.timer.pop().map(|cv| cv.task)
     }
 
-    /// Get the task control block according to pid and return the root task control block
-    ///
-    /// - Arguments
-    ///     - pid: the process's unique id
-    fn get_root_ready(&self, pid: usize) -> Option<Arc<TaskControlBlock>> {
-        for task in self.ready.iter() {
-            if task.tid() == 0 && task.process().pid() == pid {
-                return Some(Arc::clone(task));
-            }
+    /// Get the statistics of the queues, the zombie tasks in the timer heap are not counted.
+    /// The blocked tasks are not tracked by the scheduler, so it is always zero here.
+    fn stats(&self) -> SchedStats {
+        SchedStats {
+            context_switches: self.switches,
+            ready_tasks: self.count_ready(),
+            sleeping_tasks: self.timer.iter().filter(|cv| !cv.task.is_zombie()).count(),
+            blocked_tasks: 0,
         }
-        None
     }
 
     /// Create a new task controller, which will load the task code and create the virtual address space
     pub(crate) fn new() -> Self {
         Self {
-            ready: VecDeque::new(),
+            ready: core::array::from_fn(|_| VecDeque::new()),
+            lowered: core::array::from_fn(|_| VecDeque::new()),
             timer: BinaryHeap::new(),
+            deterministic: cfg!(feature = "sched_deterministic"),
+            ticker: DeterministicTicker::new(configs::SCHED_DETERMINISTIC_SYSCALL_QUANTUM),
+            switches: 0,
+            boosted_us: 0,
+            directed_yields: 0,
+            stall_reported: false,
         }
     }
 }
@@ -98,26 +337,191 @@ lazy_static! {
     };
 }
 impl TASK_SCHEDULER {
-    /// Put ready task into dqueue tail
+    /// Put ready task into dqueue tail, the idle harts will be woken up to fetch it
     pub(crate) fn put_read_task(&self, task: Arc<TaskControlBlock>) {
         self.exclusive_access().put_as_ready(task);
+        self.notify_idle_harts();
+    }
+
+    /// Send the inter-processor interrupt to all idle harts,
+    /// so that they will wake up and go back to the scheduling loop.
+    fn notify_idle_harts(&self) {
+        // pairs with the marking in [`TASK_SCHEDULER::enter_idle`],
+        // either the idle hart sees the ready task or we see the idle hart
+        let mask = IDLE_HART_MASK.load(Ordering::SeqCst);
+        if mask != 0 {
+            SBI::send_ipi(mask);
+        }
+    }
+
+    /// Mark the hart as idle, the hart will be notified when any task become ready.
+    ///
+    /// - Arguments
+    ///     - hart_id: the id of the current hart
+    ///
+    /// - Returns
+    ///     - true: there is no ready task and the hart can sleep
+    ///     - false: some task became ready before the hart was marked as idle
+    pub(crate) fn enter_idle(&self, hart_id: usize) -> bool {
+        IDLE_HART_MASK.fetch_or(1 << hart_id, Ordering::SeqCst);
+        self.access().count_ready() == 0
+    }
+
+    /// Unmark the idle hart after it was woken up
+    ///
+    /// - Arguments
+    ///     - hart_id: the id of the current hart
+    pub(crate) fn leave_idle(&self, hart_id: usize) {
+        IDLE_HART_MASK.fetch_and(!(1 << hart_id), Ordering::SeqCst);
+    }
+
+    /// Check if there are any tasks sleeping in the timer heap
+    pub(crate) fn has_sleep_task(&self) -> bool {
+        !self.access().timer.is_empty()
+    }
+
+    /// Check if there are any tasks blocked by the other events in the live processes,
+    /// they will become ready again by the interrupts or the other harts
+    pub(crate) fn has_blocked_task(&self) -> bool {
+        BLOCKED_TASK_COUNT.load(Ordering::Acquire) > 0
+    }
+
+    /// Count the task which becomes blocked or leaves the blocked status
+    ///
+    /// - Arguments
+    ///     - blocked: if true the task becomes blocked, otherwise it leaves the blocked status
+    pub(crate) fn account_blocked_task(&self, blocked: bool) {
+        if blocked {
+            BLOCKED_TASK_COUNT.fetch_add(1, Ordering::AcqRel);
+        } else {
+            BLOCKED_TASK_COUNT.fetch_sub(1, Ordering::AcqRel);
+        }
+    }
+
+    /// Report that all the live tasks are blocked and no timer is pending,
+    /// only the interrupts can wake them up now, otherwise the system is deadlocked.
+    /// It is reported once until the next task is fetched.
+    pub(crate) fn report_stall(&self) {
+        let mut inner = self.exclusive_access();
+        if !inner.stall_reported {
+            inner.stall_reported = true;
+            warn!(
+                "All the {} live tasks are blocked without timers, the system may be deadlocked",
+                BLOCKED_TASK_COUNT.load(Ordering::Acquire)
+            );
+        }
     }
 
-    /// Pop ready task from dqueue head
+    /// See [`TaskScheduler::fetch_specific`]
+    pub(crate) fn fetch_specific(&self, task: &Arc<TaskControlBlock>) -> bool {
+        self.exclusive_access().fetch_specific(task)
+    }
+
+    /// Put the task which was preempted after using up it's time slice into the lower level queue
+    pub(crate) fn put_preempted_task(&self, task: Arc<TaskControlBlock>) {
+        self.exclusive_access().put_as_preempted(task);
+    }
+
+    /// See [`TaskScheduler::put_as_lowered`]
+    pub(crate) fn put_lowered_task(&self, task: Arc<TaskControlBlock>) {
+        self.exclusive_access().put_as_lowered(task);
+        self.notify_idle_harts();
+    }
+
+    /// Pop ready task from the highest non-empty level,
+    /// the ready tasks will be boosted periodically before popping.
     pub(crate) fn pop_ready_task(&self) -> Option<Arc<TaskControlBlock>> {
-        self.exclusive_access().pop_ready()
+        let now_us = self.now_us();
+        let mut inner = self.exclusive_access();
+        inner.boost(now_us);
+        inner.pop_ready()
+    }
+
+    /// Get the statistics of the scheduler.
+    /// The sleeping tasks are also marked as blocked,
+    /// so they are excluded from the count of the blocked tasks.
+    pub(crate) fn stats(&self) -> SchedStats {
+        let mut stats = self.access().stats();
+        let blocked = BLOCKED_TASK_COUNT.load(Ordering::Acquire);
+        stats.blocked_tasks = blocked.saturating_sub(stats.sleeping_tasks);
+        stats
+    }
+
+    /// Get the current microseconds of the scheduler's clock.
+    /// In deterministic mode, the clock is virtual and only advanced by syscalls,
+    /// otherwise it is the real timer.
+    pub(crate) fn now_us(&self) -> usize {
+        let inner = self.access();
+        if inner.deterministic {
+            inner.ticker.now_us()
+        } else {
+            now_us()
+        }
+    }
+
+    /// Check whether the scheduler runs in deterministic mode,
+    /// which is enabled by the feature `sched_deterministic`
+    pub(crate) fn is_deterministic(&self) -> bool {
+        self.access().deterministic
+    }
+
+    /// Switch the deterministic mode and restart the syscall counting with the quantum,
+    /// the virtual clock keeps going so that the timers never go back.
+    ///
+    /// - Arguments
+    ///     - deterministic: whether the deterministic mode is enabled
+    ///     - quantum: the count of syscalls between two preemptions
+    ///
+    /// - Returns
+    ///     - the previous mode
+    #[cfg(test)]
+    pub(crate) fn set_deterministic(&self, deterministic: bool, quantum: usize) -> bool {
+        let mut inner = self.exclusive_access();
+        let mut ticker = DeterministicTicker::new(quantum);
+        ticker.advance_to(inner.ticker.now_us());
+        inner.ticker = ticker;
+        core::mem::replace(&mut inner.deterministic, deterministic)
+    }
+
+    /// Count one syscall of the current task.
+    ///
+    /// - Returns
+    ///     - true: in deterministic mode and the current task should be preempted
+    ///     - false: the current task can continue running
+    pub(crate) fn tick_syscall(&self) -> bool {
+        let mut inner = self.exclusive_access();
+        inner.deterministic && inner.ticker.tick()
+    }
+
+    /// Advance the virtual clock to the closest timer when there is no ready task.
+    /// Because the virtual clock will not move forward if no task make syscalls.
+    ///
+    /// - Returns
+    ///     - true: in deterministic mode and some sleeping tasks can be woken up
+    ///     - false: nothing was changed
+    pub(crate) fn advance_idle_clock(&self) -> bool {
+        let mut inner = self.exclusive_access();
+        if !inner.deterministic {
+            return false;
+        }
+        if let Some(expire_us) = inner.timer.peek().map(|cv| cv.expire_us) {
+            inner.ticker.advance_to(expire_us);
+            true
+        } else {
+            false
+        }
     }
 
     /// Put block task into binary heap
     pub(crate) fn put_sleep_task(&self, us: usize, task: Arc<TaskControlBlock>) {
-        let expire_us = get_timer_us() + us;
+        let expire_us = self.now_us().saturating_add(us);
         self.exclusive_access().put_as_timer(expire_us, task)
     }
 
     /// Check all timers if it was timeout.
     /// Any timer timeout will be pop out from heap. 
     pub(crate) fn check_timers(&self) {
-        let current_us = get_timer_us();
+        let current_us = self.now_us();
         let mut inner = self.exclusive_access();
         while let Some(cv) = inner.timer.peek() {
             if cv.task.is_zombie() {
@@ -145,25 +549,298 @@ impl TASK_SCHEDULER {
         inner.timer.append(&mut temp);
     }
 
-    /// Get the process control block according to pid and return the root task control block
+    /// Wake up the sleeping task before it's timer was timeout,
+    /// the task will be removed from the timer heap and put into the ready queue.
     ///
     /// - Arguments
-    ///     - pid: the process's unique id
-    pub(crate) fn get_process(&self, pid: isize) -> Option<Arc<ProcessControlBlock>> {
-        if let Ok(task) = PROCESSOR.current_task() {
-            let process = task.process();
-            if pid < ROOT_PID as isize {
-                return Some(process);
+    ///     - task: the task which may be sleeping
+    ///
+    /// - Returns
+    ///     - true: the task was sleeping and now it is ready
+    ///     - false: the task was not sleeping
+    pub(crate) fn wake_sleep_task(&self, task: &Arc<TaskControlBlock>) -> bool {
+        self.wake_sleep_tasks(core::slice::from_ref(task)) != 0
+    }
+
+    /// Wake up the sleeping tasks before their timers were timeout in one pass of the timer heap,
+    /// the sleeping tasks which are not given are kept in the timer heap untouched.
+    ///
+    /// - Arguments
+    ///     - tasks: the tasks which may be sleeping
+    ///
+    /// - Returns
+    ///     - the count of the tasks which were sleeping and now are ready
+    pub(crate) fn wake_sleep_tasks(&self, tasks: &[Arc<TaskControlBlock>]) -> usize {
+        let mut inner = self.exclusive_access();
+        let mut woken = Vec::new();
+        inner.timer.retain(|cv| {
+            let found = tasks.iter().any(|task| Arc::ptr_eq(task, &cv.task));
+            if found {
+                woken.push(Arc::clone(&cv.task));
             }
-            if process.pid() == pid as usize {
-                Some(process)
-            } else {
-                None
+            !found
+        });
+        for task in woken.iter() {
+            task.mark_suspended();
+            inner.put_as_ready(Arc::clone(task));
+        }
+        drop(inner);
+        if !woken.is_empty() {
+            self.notify_idle_harts();
+        }
+        woken.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task::model::{ForkedProcess, TaskStatus};
+    use crate::task::{tick_current_syscall, PROCESSOR};
+
+    /// Run two tasks which increase a shared counter without lock through the task scheduler,
+    /// each increment is split into a load syscall and a store syscall,
+    /// and each syscall goes through the syscall-tick hook which preempts the current task.
+    /// The other ready tasks are kept aside while the racing tasks are running.
+    /// Return the interleaving of (task index, counter value) for each store.
+    fn interleave_racing_tasks() -> Vec<(usize, usize)> {
+        let mode = TASK_SCHEDULER.set_deterministic(true, 3);
+        let processes: Vec<_> = (0..2).map(|_| ForkedProcess::new()).collect();
+        let tasks: Vec<_> = processes
+            .iter()
+            .map(|process| process.inner_access().root_task())
+            .collect();
+        for task in tasks.iter() {
+            TASK_SCHEDULER.put_read_task(Arc::clone(task));
+        }
+        let previous = PROCESSOR.replace_current_task(None);
+        let mut others = Vec::new();
+        let mut remains = [10, 10];
+        let mut loaded = [None, None];
+        let mut counter = 0;
+        let mut trace = Vec::new();
+        // the scheduling loop runs the ready task until it is preempted or exits
+        while let Some(task) = TASK_SCHEDULER.pop_ready_task() {
+            let index = match tasks.iter().position(|other| Arc::ptr_eq(other, &task)) {
+                Some(index) => index,
+                None => {
+                    others.push(task);
+                    continue;
+                }
+            };
+            task.mark_running();
+            PROCESSOR.replace_current_task(Some(task));
+            while remains[index] > 0 && PROCESSOR.try_current_task().is_some() {
+                if let Some(value) = loaded[index].take() {
+                    counter = value + 1;
+                    trace.push((index, counter));
+                    remains[index] -= 1;
+                } else {
+                    loaded[index] = Some(counter);
+                }
+                tick_current_syscall().unwrap();
             }
-        } else if let Some(task) = self.access().get_root_ready(pid as usize) {
-            Some(task.process())
-        } else {
-            None
+            // the finished task exits, the preempted one has been put back into the ready queue
+            PROCESSOR.replace_current_task(None);
+        }
+        PROCESSOR.replace_current_task(previous);
+        for task in others {
+            TASK_SCHEDULER.put_read_task(task);
+        }
+        TASK_SCHEDULER.set_deterministic(mode, configs::SCHED_DETERMINISTIC_SYSCALL_QUANTUM);
+        trace
+    }
+
+    #[test_case]
+    fn test_deterministic_ticker_preempt_boundary() {
+        let mut ticker = DeterministicTicker::new(4);
+        for i in 1..=16 {
+            assert_eq!(ticker.tick(), i % 4 == 0);
+        }
+        assert_eq!(ticker.now_us(), 16 * configs::SCHED_DETERMINISTIC_SYSCALL_US);
+        ticker.advance_to(0);
+        assert_eq!(ticker.now_us(), 16 * configs::SCHED_DETERMINISTIC_SYSCALL_US);
+        ticker.advance_to(usize::MAX);
+        assert_eq!(ticker.now_us(), usize::MAX);
+    }
+
+    #[test_case]
+    fn test_scheduler_stats_count_context_switches() {
+        let mut scheduler = TaskScheduler::new();
+        assert_eq!(scheduler.stats(), SchedStats::default());
+        let processes: Vec<_> = (0..3).map(|_| ForkedProcess::new()).collect();
+        for process in processes.iter() {
+            scheduler.put_as_ready(process.inner_access().root_task());
+        }
+        assert_eq!(scheduler.stats().ready_tasks, 3);
+        // each round every task is fetched and yields back to the ready queue
+        for _ in 0..4 {
+            for _ in 0..processes.len() {
+                let task = scheduler.pop_ready().unwrap();
+                scheduler.put_as_ready(task);
+            }
+        }
+        let stats = scheduler.stats();
+        assert_eq!(stats.context_switches, 12);
+        assert_eq!(stats.ready_tasks, 3);
+        assert_eq!(stats.sleeping_tasks, 0);
+        let task = scheduler.pop_ready().unwrap();
+        scheduler.put_as_timer(usize::MAX, task);
+        let stats = scheduler.stats();
+        assert_eq!(stats.context_switches, 13);
+        assert_eq!(stats.ready_tasks, 2);
+        assert_eq!(stats.sleeping_tasks, 1);
+        for process in processes.iter() {
+            process.mark_zombie(0);
+        }
+        assert_eq!(scheduler.stats().sleeping_tasks, 0);
+    }
+
+    #[test_case]
+    fn test_scheduler_has_blocked_task() {
+        let process = ForkedProcess::new();
+        let task = process.inner_access().root_task();
+        let blocked = BLOCKED_TASK_COUNT.load(Ordering::Acquire);
+        task.mark_blocked();
+        task.mark_blocked();
+        assert_eq!(BLOCKED_TASK_COUNT.load(Ordering::Acquire), blocked + 1);
+        // the idle task keeps the hart sleeping instead of shutting down
+        assert!(TASK_SCHEDULER.has_blocked_task());
+        let stats = TASK_SCHEDULER.stats();
+        assert_eq!(stats.blocked_tasks + stats.sleeping_tasks, blocked + 1);
+        process.mark_zombie(0);
+        assert_eq!(process.count_tasks(TaskStatus::Blocked), 0);
+        assert_eq!(BLOCKED_TASK_COUNT.load(Ordering::Acquire), blocked);
+        drop(task);
+    }
+
+    #[test_case]
+    #[cfg(not(feature = "sched_fifo"))]
+    fn test_scheduler_multi_level_feedback_queues() {
+        let mut scheduler = TaskScheduler::new();
+        let cpu_bound = ForkedProcess::new();
+        let io_bound = ForkedProcess::new();
+        let cpu_task = cpu_bound.inner_access().root_task();
+        let io_task = io_bound.inner_access().root_task();
+        // the cpu bound task use up it's time slices and drop to the lowest level
+        scheduler.put_as_ready(Arc::clone(&cpu_task));
+        for _ in 0..configs::SCHED_MLFQ_LEVEL_COUNT {
+            let task = scheduler.pop_ready().unwrap();
+            scheduler.put_as_preempted(task);
+        }
+        scheduler.put_as_ready(Arc::clone(&io_task));
+        assert_eq!(io_task.sched_level(), 0);
+        assert_eq!(cpu_task.sched_level(), TaskScheduler::lowest_level());
+        // the io bound task which stay in the highest level always runs first
+        for _ in 0..3 {
+            let task = scheduler.pop_ready().unwrap();
+            assert!(Arc::ptr_eq(&task, &io_task));
+            scheduler.put_as_ready(task);
         }
+        assert!(!scheduler.boost(configs::SCHED_MLFQ_BOOST_INTERVAL_US - 1));
+        assert!(scheduler.boost(configs::SCHED_MLFQ_BOOST_INTERVAL_US));
+        assert_eq!(cpu_task.sched_level(), 0);
+        let first = scheduler.pop_ready().unwrap();
+        let second = scheduler.pop_ready().unwrap();
+        assert!(scheduler.pop_ready().is_none());
+        assert!(!Arc::ptr_eq(&first, &second));
+        scheduler.put_as_ready(first);
+        scheduler.put_as_ready(second);
+        drop(cpu_task);
+        drop(io_task);
+        cpu_bound.mark_zombie(0);
+        io_bound.mark_zombie(0);
+    }
+
+    #[test_case]
+    #[cfg(not(feature = "sched_fifo"))]
+    fn test_scheduler_lowered_yield_restores_level() {
+        let mut scheduler = TaskScheduler::new();
+        let polite_process = ForkedProcess::new();
+        let other_process = ForkedProcess::new();
+        let lower_process = ForkedProcess::new();
+        let polite_task = polite_process.inner_access().root_task();
+        let other_task = other_process.inner_access().root_task();
+        let lower_task = lower_process.inner_access().root_task();
+        lower_task.set_sched_level(1);
+        scheduler.put_as_ready(Arc::clone(&polite_task));
+        scheduler.put_as_ready(Arc::clone(&other_task));
+        scheduler.put_as_ready(Arc::clone(&lower_task));
+        // the polite task runs after the other task in the same level for one round
+        let task = scheduler.pop_ready().unwrap();
+        assert!(Arc::ptr_eq(&task, &polite_task));
+        scheduler.put_as_lowered(task);
+        assert_eq!(polite_task.sched_level(), 0);
+        assert_eq!(scheduler.stats().ready_tasks, 3);
+        let task = scheduler.pop_ready().unwrap();
+        assert!(Arc::ptr_eq(&task, &other_task));
+        // but it still runs before the task in the lower level
+        let mut polite = scheduler.pop_ready().unwrap();
+        assert!(Arc::ptr_eq(&polite, &polite_task));
+        // the repeated lowered yields never sink the task
+        for _ in 0..configs::SCHED_MLFQ_LEVEL_COUNT {
+            scheduler.put_as_lowered(polite);
+            polite = scheduler.pop_ready().unwrap();
+            assert!(Arc::ptr_eq(&polite, &polite_task));
+        }
+        assert_eq!(polite_task.sched_level(), 0);
+        // the task is put back into it's own level by the next normal yield
+        scheduler.put_as_ready(polite);
+        scheduler.put_as_ready(task);
+        let task = scheduler.pop_ready().unwrap();
+        assert!(Arc::ptr_eq(&task, &polite_task));
+        let task = scheduler.pop_ready().unwrap();
+        assert!(Arc::ptr_eq(&task, &other_task));
+        let task = scheduler.pop_ready().unwrap();
+        assert!(Arc::ptr_eq(&task, &lower_task));
+        assert!(scheduler.pop_ready().is_none());
+        drop(task);
+        drop(polite_task);
+        drop(other_task);
+        drop(lower_task);
+        polite_process.mark_zombie(0);
+        other_process.mark_zombie(0);
+        lower_process.mark_zombie(0);
+    }
+
+    #[test_case]
+    fn test_scheduler_fetch_specific() {
+        let mut scheduler = TaskScheduler::new();
+        let processes: Vec<_> = (0..3).map(|_| ForkedProcess::new()).collect();
+        let tasks: Vec<_> = processes
+            .iter()
+            .map(|process| process.inner_access().root_task())
+            .collect();
+        assert!(!scheduler.fetch_specific(&tasks[2]));
+        for task in tasks.iter() {
+            scheduler.put_as_ready(Arc::clone(task));
+        }
+        for _ in 0..configs::SCHED_MAX_DIRECTED_YIELD_COUNT {
+            assert!(scheduler.fetch_specific(&tasks[2]));
+            let task = scheduler.pop_ready().unwrap();
+            assert!(Arc::ptr_eq(&task, &tasks[2]));
+            scheduler.put_as_ready(task);
+        }
+        // the consecutive directed yields are capped
+        assert!(!scheduler.fetch_specific(&tasks[2]));
+        let task = scheduler.pop_ready().unwrap();
+        assert!(Arc::ptr_eq(&task, &tasks[0]));
+        scheduler.put_as_ready(task);
+        assert!(scheduler.fetch_specific(&tasks[2]));
+        assert_eq!(scheduler.stats().ready_tasks, 3);
+        drop(tasks);
+        for process in processes.iter() {
+            process.mark_zombie(0);
+        }
+    }
+
+    #[test_case]
+    fn test_deterministic_ticker_reproducible_interleaving() {
+        let first = interleave_racing_tasks();
+        let second = interleave_racing_tasks();
+        assert_eq!(first.len(), 20);
+        assert_eq!(first, second);
+        // the race must be observed, otherwise the interleaving is meaningless
+        assert!(first.last().unwrap().1 < 20);
     }
 }
@@ -4,12 +4,55 @@
 // self mods
 
 // use other mods
+use alloc::collections::VecDeque;
 use frontier_lib::model::signal::{Signal, SignalAction, SignalFlags, SingalTable};
 
 // use self mods
+use crate::configs;
 use crate::prelude::*;
 use crate::trap::context::TrapContext;
 
+/// The count of the real-time signals
+const RT_SIGNAL_COUNT: usize = configs::SIGNAL_RT_MAX - configs::SIGNAL_RT_MIN + 1;
+
+/// Check whether the signal number is in the range of the real-time signals
+///
+/// - Arguments
+///     - signum: the number of the signal
+pub(crate) fn is_rt_signum(signum: usize) -> bool {
+    (configs::SIGNAL_RT_MIN..=configs::SIGNAL_RT_MAX).contains(&signum)
+}
+
+/// The alternate stack for the signal handlers shared with the user space.
+/// The signals whose handlers will run on the alternate stack are marked by the onstack flags,
+/// so the stack overflow can still be handled by the SEGV handler.
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub(crate) struct SignalStack {
+    /// The lowest virtual address of the alternate stack
+    pub(crate) base: usize,
+    /// The byte size of the alternate stack, zero means the alternate stack is disabled
+    pub(crate) size: usize,
+    /// The bits of the [`SignalFlags`] whose handlers will run on the alternate stack
+    pub(crate) onstack: u32,
+}
+
+/// The flag of the signal action which makes the syscall interrupted by the signal
+/// be executed again after the handler returns, such as the blocking read, write, wait or sleep
+pub(crate) const SA_RESTART: usize = 1 << 0;
+
+/// The signal action shared with the user space,
+/// which is the [`SignalAction`] followed by the flags of the action.
+/// The flags of the real-time signal actions are ignored.
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub(crate) struct UserSignalAction {
+    /// The handler and the mask of the action
+    pub(crate) action: SignalAction,
+    /// The flags of the action, only [`SA_RESTART`] is supported now
+    pub(crate) flags: usize,
+}
+
 /// The control block for signal mechanism, each process have only one signal control block.
 #[derive(Debug)]
 pub(crate) struct SignalControlBlock {
@@ -18,10 +61,26 @@ pub(crate) struct SignalControlBlock {
     setted: SignalFlags,
     /// The mask of the signals which should not be active
     masked: SignalFlags,
+    /// The previous mask which was replaced by the temporary mask of the sigsuspend syscall
+    suspended_mask: Option<SignalFlags>,
     /// The functions for handler signals, the index of the action is the signal value
     actions: SingalTable,
+    /// The signals whose handlers will restart the interrupted syscalls after they return
+    restart: SignalFlags,
+    /// The real-time signal being processed
+    rt_handling: Option<usize>,
+    /// The queued values of the real-time signals, the index of the queue is the offset from RTMIN.
+    /// The real-time signals cannot be blocked by the mask flags
+    rt_queues: [VecDeque<usize>; RT_SIGNAL_COUNT],
+    /// The functions for handler real-time signals, the index of the action is the offset from RTMIN
+    rt_actions: [SignalAction; RT_SIGNAL_COUNT],
     /// The backup value of normal trap context saved when handing signal
     trap_ctx_backup: Option<TrapContext>,
+    /// The alternate stack for the signal handlers
+    alt_stack: SignalStack,
+    /// The faulting virtual address which caused the SEGV signal,
+    /// it will be passed to the handler as the second argument
+    fault_addr: Option<usize>,
     /// If killed is true, the current task will be exit in the after
     /// see [`crate::task::process::PROCESSOR::handle_current_task_signals`]
     killed: bool,
@@ -36,8 +95,15 @@ impl SignalControlBlock {
             handling: None,
             setted: SignalFlags::empty(),
             masked: SignalFlags::empty(),
+            suspended_mask: None,
             actions: SingalTable::new(),
+            restart: SignalFlags::empty(),
+            rt_handling: None,
+            rt_queues: core::array::from_fn(|_| VecDeque::new()),
+            rt_actions: core::array::from_fn(|_| SignalAction::default()),
             trap_ctx_backup: None,
+            alt_stack: SignalStack::default(),
+            fault_addr: None,
             killed: false,
             frozen: false,
         }
@@ -56,6 +122,7 @@ impl SignalControlBlock {
         let flag = signal.into();
         self.setted.contains(flag)
             && !self.masked.contains(flag)
+            && self.rt_handling.is_none()
             && match self.handling {
                 Some(handing_signal) => !self
                     .actions
@@ -66,12 +133,76 @@ impl SignalControlBlock {
             }
     }
 
+    /// Check whether any real-time signal is pending.
+    /// The real-time signals will only be handled when no other signal is being processed.
+    pub(crate) fn is_pending_rt_signal(&self) -> bool {
+        self.handling.is_none()
+            && self.rt_handling.is_none()
+            && self.rt_queues.iter().any(|queue| !queue.is_empty())
+    }
+
+    /// Check whether any signal is pending, see [`SignalControlBlock::is_pending_signal`]
+    pub(crate) fn has_pending_signal(&self) -> bool {
+        Signal::iter().any(|signal| self.is_pending_signal(signal)) || self.is_pending_rt_signal()
+    }
+
+    /// Check whether any pending signal will take effect once it is handled,
+    /// the signals blocked by the mask or ignored by the default actions are not counted.
+    /// The bad signals always take effect, see [`SignalControlBlock::get_bad_signal`]
+    ///
+    /// - Arguments
+    ///     - sent: the signals sent to the task specifically, which are not blocked by the mask
+    pub(crate) fn has_deliverable_signal(&self, sent: SignalFlags) -> bool {
+        if self.get_bad_signal().is_some() {
+            return true;
+        }
+        let standard = Signal::iter().any(|signal| {
+            (self.is_pending_signal(signal) || sent.contains(signal.into()))
+                && (matches!(
+                    signal,
+                    Signal::KILL | Signal::DEF | Signal::STOP | Signal::CONT | Signal::TRAP
+                ) || !self.get_action(signal).handler().is_null())
+        });
+        standard
+            || self.is_pending_rt_signal()
+                && self
+                    .rt_queues
+                    .iter()
+                    .zip(self.rt_actions.iter())
+                    .any(|(queue, action)| !queue.is_empty() && !action.handler().is_null())
+    }
+
+    /// Get the signals which were setted but blocked by the mask
+    pub(crate) fn blocked_signals(&self) -> SignalFlags {
+        self.setted & self.masked
+    }
+
+    /// Get the mask of the signals which should not be active
+    pub(crate) fn masked(&self) -> SignalFlags {
+        self.masked
+    }
+
+    /// Check whether any standard or real-time signal is being processed by the root task
+    pub(crate) fn is_handling(&self) -> bool {
+        self.handling.is_some() || self.rt_handling.is_some()
+    }
+
     /// Change current signal control block to frozen status
     pub(crate) fn freeze(&mut self) {
         self.setted ^= SignalFlags::STOP;
         self.frozen = true;
     }
 
+    /// Change current signal control block to frozen status by the signal other than STOP,
+    /// such as the TRAP signal which stops the traced process
+    ///
+    /// - Arguments
+    ///     - signal: the signal which stops the process
+    pub(crate) fn freeze_by(&mut self, signal: Signal) {
+        self.setted.remove(signal.into());
+        self.frozen = true;
+    }
+
     /// Change current signal control block continue to run
     pub(crate) fn cont(&mut self) {
         self.setted ^= SignalFlags::CONT;
@@ -99,6 +230,43 @@ impl SignalControlBlock {
         }
     }
 
+    /// Queue a real-time signal instance, the same signal will not be collapsed
+    ///
+    /// - Arguments
+    ///     - signum: the number of the real-time signal
+    ///     - value: the value which will be passed to the handler
+    ///
+    /// - Errors
+    ///     - SignalQueueFull(signum)
+    pub(crate) fn try_kill_rt(&mut self, signum: usize, value: usize) -> Result<()> {
+        let queue = &mut self.rt_queues[signum - configs::SIGNAL_RT_MIN];
+        if queue.len() >= configs::MAX_RT_SIGNAL_QUEUE_LEN {
+            Err(KernelError::SignalQueueFull(signum))
+        } else {
+            queue.push_back(value);
+            Ok(())
+        }
+    }
+
+    /// Pop the first instance of the pending real-time signal with the lowest number
+    ///
+    /// - Returns
+    ///     - Some(signum, value)
+    ///     - None: there is no pending real-time signal
+    pub(crate) fn pop_rt_signal(&mut self) -> Option<(usize, usize)> {
+        if !self.is_pending_rt_signal() {
+            return None;
+        }
+        self.rt_queues
+            .iter_mut()
+            .enumerate()
+            .find_map(|(offset, queue)| {
+                queue
+                    .pop_front()
+                    .map(|value| (offset + configs::SIGNAL_RT_MIN, value))
+            })
+    }
+
     /// Change current signal control block's mask flags settings,
     /// and return the previous mask flags
     pub(crate) fn mask(&mut self, masking: SignalFlags) -> SignalFlags {
@@ -107,6 +275,27 @@ impl SignalControlBlock {
         return old_mask;
     }
 
+    /// Install a temporary mask flags for waiting signals,
+    /// the previous mask flags will be restored after the signal was handled.
+    /// If there is already a temporary mask, the first previous mask will be kept.
+    ///
+    /// - Arguments
+    ///     - masking: the temporary mask flags
+    pub(crate) fn suspend(&mut self, masking: SignalFlags) {
+        let old_mask = self.mask(masking);
+        self.suspended_mask.get_or_insert(old_mask);
+    }
+
+    /// Restore the previous mask flags replaced by [`SignalControlBlock::suspend`],
+    /// it will do nothing if some signal is still being handled.
+    pub(crate) fn restore_suspended_mask(&mut self) {
+        if self.handling.is_none() && self.rt_handling.is_none() {
+            if let Some(masking) = self.suspended_mask.take() {
+                self.masked = masking;
+            }
+        }
+    }
+
     /// Back up the current trap context and use the specified signal as the currently processing
     ///
     /// - Arguments
@@ -118,21 +307,41 @@ impl SignalControlBlock {
         self.trap_ctx_backup.replace(trap_ctx);
     }
 
-    /// Roll back to a no-signal state or return the backup trap context and signal
-    /// 
+    /// Back up the current trap context and use the specified real-time signal as the currently processing
+    ///
     /// - Arguments
-    ///     - Some(backup signal, backup trap context)
-    pub(crate) fn rollback(&mut self) -> Option<(Signal, TrapContext)> {
-        if let (Some(signal), Some(trap_ctx)) = (self.handling.take(), self.trap_ctx_backup.take())
+    ///     - signum: currently processing real-time signal number
+    ///     - trap_ctx: the trap context wait to backup
+    pub(crate) fn backup_rt(&mut self, signum: usize, trap_ctx: TrapContext) {
+        self.rt_handling.replace(signum);
+        self.trap_ctx_backup.replace(trap_ctx);
+    }
+
+    /// Roll back to a no-signal state or return the backup trap context
+    /// 
+    /// - Returns
+    ///     - Some(backup trap context)
+    pub(crate) fn rollback(&mut self) -> Option<TrapContext> {
+        if self.rt_handling.take().is_some() {
+            let trap_ctx = self.trap_ctx_backup.take();
+            self.restore_suspended_mask();
+            trap_ctx
+        } else if let (Some(signal), Some(trap_ctx)) =
+            (self.handling.take(), self.trap_ctx_backup.take())
         {
             self.setted.remove(signal.into());
-            Some((signal, trap_ctx))
+            if matches!(signal, Signal::SEGV) {
+                self.fault_addr.take();
+            }
+            self.restore_suspended_mask();
+            Some(trap_ctx)
         } else {
             None
         }
     }
 
-    /// Get the bad signal which was setted into signal control block
+    /// Get the bad signal which was setted into signal control block,
+    /// the TRAP signal is only bad when there is no custom handler for it
     pub(crate) fn get_bad_signal(&self) -> Option<Signal> {
         if self.setted.contains(SignalFlags::INT) {
             Some(Signal::INT)
@@ -144,6 +353,10 @@ impl SignalControlBlock {
             Some(Signal::FPE)
         } else if self.setted.contains(SignalFlags::SEGV) {
             Some(Signal::SEGV)
+        } else if self.setted.contains(SignalFlags::TRAP)
+            && self.get_action(Signal::TRAP).handler().is_null()
+        {
+            Some(Signal::TRAP)
         } else {
             None
         }
@@ -159,6 +372,74 @@ impl SignalControlBlock {
         self.actions.set(signal as usize, action)
     }
 
+    /// Set whether the syscall interrupted by the signal will be restarted after handling
+    ///
+    /// - Arguments
+    ///     - signal: the signal which has custom handler
+    ///     - restart: true if the interrupted syscall should be executed again
+    pub(crate) fn set_restart(&mut self, signal: Signal, restart: bool) {
+        self.restart.set(signal.into(), restart)
+    }
+
+    /// Check whether the syscall interrupted by the signal will be restarted
+    pub(crate) fn is_restart(&self, signal: Signal) -> bool {
+        self.restart.contains(signal.into())
+    }
+
+    /// Exchange the alternate stack and return the previous one
+    ///
+    /// - Arguments
+    ///     - stack: the new alternate stack
+    pub(crate) fn exchange_alt_stack(&mut self, stack: SignalStack) -> SignalStack {
+        core::mem::replace(&mut self.alt_stack, stack)
+    }
+
+    /// Get the alternate stack
+    pub(crate) fn alt_stack(&self) -> SignalStack {
+        self.alt_stack
+    }
+
+    /// Get the stack top of the alternate stack if the handler of the signal should run on it
+    ///
+    /// - Arguments
+    ///     - signal: the signal which will be handled
+    ///
+    /// - Returns
+    ///     - Some(stack top virtual address): aligned down to 16 bytes as the calling convention required
+    ///     - None: the handler should run on the current user stack
+    pub(crate) fn alt_stack_top(&self, signal: Signal) -> Option<usize> {
+        let flag: SignalFlags = signal.into();
+        let onstack = SignalFlags::from_bits_truncate(self.alt_stack.onstack);
+        if self.alt_stack.size == 0 || !onstack.contains(flag) {
+            return None;
+        }
+        Some((self.alt_stack.base + self.alt_stack.size) & !0xf)
+    }
+
+    /// Record the faulting virtual address of the SEGV signal,
+    /// the first address will be kept until the handler returns.
+    ///
+    /// - Arguments
+    ///     - addr: the faulting virtual address
+    pub(crate) fn set_fault_addr(&mut self, addr: usize) {
+        self.fault_addr.get_or_insert(addr);
+    }
+
+    /// Get the faulting virtual address of the SEGV signal
+    pub(crate) fn fault_addr(&self) -> Option<usize> {
+        self.fault_addr
+    }
+
+    /// Get the action according to real-time signal number
+    pub(crate) fn get_rt_action(&self, signum: usize) -> SignalAction {
+        self.rt_actions[signum - configs::SIGNAL_RT_MIN]
+    }
+
+    /// Set the action by real-time signal number
+    pub(crate) fn set_rt_action(&mut self, signum: usize, action: SignalAction) {
+        self.rt_actions[signum - configs::SIGNAL_RT_MIN] = action
+    }
+
     /// Check if killed
     pub(crate) fn is_killed(&self) -> bool {
         self.killed
@@ -170,10 +451,117 @@ impl SignalControlBlock {
     }
 }
 
+/// The signal states of one task, which keep the signals sent to the task specifically.
+/// The handlers of these signals run in the task's own trap context,
+/// while the process-wide signals are still handled by the root task.
+#[derive(Debug)]
+pub(crate) struct TaskSignalBlock {
+    /// The signals which were sent to the task by tkill syscall
+    setted: SignalFlags,
+    /// The signal being processed by the task
+    handling: Option<Signal>,
+    /// The backup value of the task's trap context saved when handling signal
+    trap_ctx_backup: Option<TrapContext>,
+}
+impl TaskSignalBlock {
+    /// Create a new empty task signal block
+    pub(crate) fn new() -> Self {
+        Self {
+            setted: SignalFlags::empty(),
+            handling: None,
+            trap_ctx_backup: None,
+        }
+    }
+
+    /// Set the signal as pending for the task
+    ///
+    /// - Arguments
+    ///     - signal: the signal which will be setted
+    ///
+    /// - Errors
+    ///     - DuplicateSignal(signal)
+    pub(crate) fn try_kill(&mut self, signal: Signal) -> Result<()> {
+        if self.setted.contains(signal.into()) {
+            Err(KernelError::DuplicateSignal(signal))
+        } else {
+            self.setted.insert(signal.into());
+            Ok(())
+        }
+    }
+
+    /// Take the pending signal with the lowest number which is not blocked by the mask,
+    /// nothing will be taken when the task is handling another signal.
+    ///
+    /// - Arguments
+    ///     - masked: the mask of the signals which should not be active
+    pub(crate) fn pop_signal(&mut self, masked: SignalFlags) -> Option<Signal> {
+        if self.handling.is_some() {
+            return None;
+        }
+        let signal = Signal::iter().find(|signal| {
+            let flag: SignalFlags = (*signal).into();
+            self.setted.contains(flag) && !masked.contains(flag)
+        })?;
+        self.setted.remove(signal.into());
+        Some(signal)
+    }
+
+    /// Check whether the task has pending signals which are not blocked by the mask
+    ///
+    /// - Arguments
+    ///     - masked: the mask of the signals which should not be active
+    pub(crate) fn has_pending_signal(&self, masked: SignalFlags) -> bool {
+        !self.pending_signals(masked).is_empty()
+    }
+
+    /// Get the pending signals of the task which are not blocked by the mask
+    ///
+    /// - Arguments
+    ///     - masked: the mask of the signals which should not be active
+    pub(crate) fn pending_signals(&self, masked: SignalFlags) -> SignalFlags {
+        self.setted - masked
+    }
+
+    /// Check whether the task is handling a signal in its own trap context
+    pub(crate) fn is_handling(&self) -> bool {
+        self.handling.is_some()
+    }
+
+    /// Back up the task's trap context and use the signal as the currently processing
+    ///
+    /// - Arguments
+    ///     - signal: currently processing signal
+    ///     - trap_ctx: the trap context wait to backup
+    pub(crate) fn backup(&mut self, signal: Signal, trap_ctx: TrapContext) {
+        self.handling.replace(signal);
+        self.trap_ctx_backup.replace(trap_ctx);
+    }
+
+    /// Roll back to a no-signal state and return the backup trap context
+    ///
+    /// - Returns
+    ///     - Some(backup trap context)
+    ///     - None: the task is not handling any signal
+    pub(crate) fn rollback(&mut self) -> Option<TrapContext> {
+        self.handling.take()?;
+        self.trap_ctx_backup.take()
+    }
+}
+
+/// Create the signal action with the custom handler for the tests,
+/// the handler is the first word of the action which is shared with the user space
+#[cfg(test)]
+pub(crate) fn custom_action(handler: usize) -> SignalAction {
+    let mut action = SignalAction::default();
+    unsafe { (&mut action as *mut SignalAction as *mut usize).write(handler) };
+    assert_eq!(action.handler() as usize, handler);
+    action
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    
+
     #[test_case]
     fn test_freeze_and_continue() {
         let mut scb = SignalControlBlock::new();
@@ -221,6 +609,121 @@ mod tests {
         }
     }
 
+    #[test_case]
+    fn test_suspend_and_restore_mask() {
+        let mut scb = SignalControlBlock::new();
+        let trap_ctx = TrapContext::default();
+        scb.mask(SignalFlags::INT | SignalFlags::USR1);
+        assert!(scb.try_kill(Signal::INT).is_ok());
+        assert!(!scb.has_pending_signal());
+        assert_eq!(scb.blocked_signals(), SignalFlags::INT);
+        scb.suspend(SignalFlags::USR1);
+        assert!(scb.has_pending_signal());
+        assert!(scb.blocked_signals().is_empty());
+        scb.suspend(SignalFlags::empty());
+        scb.backup(Signal::INT, trap_ctx);
+        scb.restore_suspended_mask();
+        assert!(scb.blocked_signals().is_empty());
+        assert!(scb.rollback().is_some());
+        assert!(scb.try_kill(Signal::INT).is_ok());
+        assert_eq!(scb.blocked_signals(), SignalFlags::INT);
+        assert!(!scb.has_pending_signal());
+    }
+
+    #[test_case]
+    fn test_fault_addr_cleared_after_rollback() {
+        let mut scb = SignalControlBlock::new();
+        let trap_ctx = TrapContext::default();
+        assert!(scb.fault_addr().is_none());
+        scb.set_fault_addr(0x1000);
+        scb.set_fault_addr(0x2000);
+        assert!(scb.try_kill(Signal::SEGV).is_ok());
+        assert_eq!(scb.fault_addr(), Some(0x1000));
+        scb.backup(Signal::SEGV, trap_ctx);
+        assert_eq!(scb.fault_addr(), Some(0x1000));
+        assert!(scb.rollback().is_some());
+        assert!(scb.fault_addr().is_none());
+    }
+
+    #[test_case]
+    fn test_alt_stack_top() {
+        let mut scb = SignalControlBlock::new();
+        assert!(scb.alt_stack_top(Signal::SEGV).is_none());
+        let stack = SignalStack {
+            base: 0x1000,
+            size: 0x1ff8,
+            onstack: SignalFlags::SEGV.bits(),
+        };
+        assert_eq!(scb.exchange_alt_stack(stack), SignalStack::default());
+        assert_eq!(scb.alt_stack(), stack);
+        assert_eq!(scb.alt_stack_top(Signal::SEGV), Some(0x2ff0));
+        assert!(scb.alt_stack_top(Signal::INT).is_none());
+        scb.exchange_alt_stack(SignalStack::default());
+        assert!(scb.alt_stack_top(Signal::SEGV).is_none());
+    }
+
+    #[test_case]
+    fn test_rt_signals_are_queued() {
+        let mut scb = SignalControlBlock::new();
+        let trap_ctx = TrapContext::default();
+        assert!(!is_rt_signum(configs::SIGNAL_RT_MIN - 1));
+        assert!(is_rt_signum(configs::SIGNAL_RT_MIN));
+        assert!(is_rt_signum(configs::SIGNAL_RT_MAX));
+        assert!(!scb.is_pending_rt_signal());
+        for value in 0..3 {
+            assert!(scb.try_kill_rt(configs::SIGNAL_RT_MAX, value).is_ok());
+        }
+        assert!(scb.try_kill_rt(configs::SIGNAL_RT_MIN, 3).is_ok());
+        assert!(scb.has_pending_signal());
+        // the lowest signal is delivered first, and only one at a time
+        assert_eq!(scb.pop_rt_signal(), Some((configs::SIGNAL_RT_MIN, 3)));
+        scb.backup_rt(configs::SIGNAL_RT_MIN, trap_ctx);
+        assert!(scb.pop_rt_signal().is_none());
+        assert!(scb.rollback().is_some());
+        for value in 0..3 {
+            assert_eq!(scb.pop_rt_signal(), Some((configs::SIGNAL_RT_MAX, value)));
+        }
+        assert!(scb.pop_rt_signal().is_none());
+        for value in 0..configs::MAX_RT_SIGNAL_QUEUE_LEN {
+            assert!(scb.try_kill_rt(configs::SIGNAL_RT_MIN, value).is_ok());
+        }
+        assert!(scb
+            .try_kill_rt(configs::SIGNAL_RT_MIN, 0)
+            .is_err_and(|e| e.is_signalqueuefull()));
+    }
+
+    #[test_case]
+    fn test_set_restart() {
+        let mut scb = SignalControlBlock::new();
+        assert!(!scb.is_restart(Signal::USR1));
+        scb.set_restart(Signal::USR1, true);
+        assert!(scb.is_restart(Signal::USR1));
+        assert!(!scb.is_restart(Signal::USR2));
+        scb.set_restart(Signal::USR1, false);
+        assert!(!scb.is_restart(Signal::USR1));
+    }
+
+    #[test_case]
+    fn test_task_signal_block() {
+        let mut tsb = TaskSignalBlock::new();
+        let trap_ctx = TrapContext::default();
+        assert!(tsb.pop_signal(SignalFlags::empty()).is_none());
+        assert!(tsb.try_kill(Signal::USR2).is_ok());
+        assert!(tsb.try_kill(Signal::USR1).is_ok());
+        assert!(tsb.try_kill(Signal::USR1).is_err_and(|e| e.is_duplicatesignal()));
+        assert!(!tsb.has_pending_signal(SignalFlags::USR1 | SignalFlags::USR2));
+        assert!(tsb.has_pending_signal(SignalFlags::USR1));
+        // the masked signal is kept pending
+        assert_eq!(tsb.pop_signal(SignalFlags::USR1), Some(Signal::USR2));
+        tsb.backup(Signal::USR2, trap_ctx);
+        assert!(tsb.is_handling());
+        assert!(tsb.pop_signal(SignalFlags::empty()).is_none());
+        assert!(tsb.rollback().is_some());
+        assert!(tsb.rollback().is_none());
+        assert_eq!(tsb.pop_signal(SignalFlags::empty()), Some(Signal::USR1));
+        assert!(!tsb.has_pending_signal(SignalFlags::empty()));
+    }
+
     #[test_case]
     fn test_handle_and_is_pending_signal() {
         let mut scb = SignalControlBlock::new();
@@ -237,4 +740,16 @@ mod tests {
             assert!(!scb.is_pending_signal(signal));
         }
     }
+
+    #[test_case]
+    fn test_trap_is_bad_only_without_handler() {
+        let mut scb = SignalControlBlock::new();
+        assert!(scb.try_kill(Signal::TRAP).is_ok());
+        assert_eq!(scb.get_bad_signal(), Some(Signal::TRAP));
+        // the handler of the TRAP signal will run instead of terminating the process
+        scb.set_action(Signal::TRAP, custom_action(0x1000));
+        assert_eq!(scb.get_bad_signal(), None);
+        scb.set_action(Signal::TRAP, SignalAction::default());
+        assert_eq!(scb.get_bad_signal(), Some(Signal::TRAP));
+    }
 }
@@ -0,0 +1,225 @@
+// @author:    olinex
+// @time:      2024/08/16
+
+// self mods
+
+// use other mods
+use alloc::vec::Vec;
+
+// use self mods
+use super::model::ProcessControlBlock;
+use crate::prelude::*;
+use crate::trap::context::UserRegs;
+
+/// The compressed breakpoint instruction, which is small enough to replace any instruction
+pub(crate) const C_EBREAK: u16 = 0x9002;
+
+/// The opcode of the jump and link instruction
+const OPCODE_JAL: u32 = 0x6f;
+/// The opcode of the jump and link register instruction
+const OPCODE_JALR: u32 = 0x67;
+/// The opcode of the conditional branch instructions
+const OPCODE_BRANCH: u32 = 0x63;
+
+/// Extract the bits from the instruction
+///
+/// - Arguments
+///     - inst: the instruction
+///     - high: the index of the highest bit, included
+///     - low: the index of the lowest bit, included
+pub(crate) fn bits(inst: u32, high: u32, low: u32) -> u32 {
+    (inst >> low) & ((1 << (high - low + 1)) - 1)
+}
+
+/// Sign-extend the immediate whose highest bit is the sign bit
+///
+/// - Arguments
+///     - imm: the immediate
+///     - width: the bit width of the immediate
+fn sign_extend(imm: u32, width: u32) -> isize {
+    let shift = 32 - width;
+    ((imm << shift) as i32 >> shift) as isize
+}
+
+/// Get the byte length of the instruction by it's lowest bits
+///
+/// - Arguments
+///     - inst: the instruction, only the lowest half word is needed
+pub(crate) fn instruction_len(inst: u32) -> usize {
+    if inst & 0b11 == 0b11 {
+        4
+    } else {
+        2
+    }
+}
+
+/// Decode the instruction to find all the addresses which may be executed next.
+/// Only the control transfer instructions have the target other than the following instruction,
+/// and the conditional branches have both of them.
+///
+/// - Arguments
+///     - inst: the instruction at the program counter, the compressed one only uses the lower half
+///     - pc: the program counter of the instruction
+///     - regs: the registers before the instruction is executed
+pub(crate) fn next_pcs(inst: u32, pc: usize, regs: &UserRegs) -> Vec<usize> {
+    let next = pc + instruction_len(inst);
+    let offset = |imm: isize| pc.wrapping_add_signed(imm);
+    if instruction_len(inst) == 4 {
+        return match bits(inst, 6, 0) {
+            OPCODE_JAL => {
+                let imm = bits(inst, 31, 31) << 20
+                    | bits(inst, 19, 12) << 12
+                    | bits(inst, 20, 20) << 11
+                    | bits(inst, 30, 21) << 1;
+                vec![offset(sign_extend(imm, 21))]
+            }
+            OPCODE_JALR => {
+                let rs1 = regs.x[bits(inst, 19, 15) as usize];
+                vec![rs1.wrapping_add_signed(sign_extend(bits(inst, 31, 20), 12)) & !1]
+            }
+            OPCODE_BRANCH => {
+                let imm = bits(inst, 31, 31) << 12
+                    | bits(inst, 7, 7) << 11
+                    | bits(inst, 30, 25) << 5
+                    | bits(inst, 11, 8) << 1;
+                vec![next, offset(sign_extend(imm, 13))]
+            }
+            _ => vec![next],
+        };
+    }
+    let quadrant = bits(inst, 1, 0);
+    let funct3 = bits(inst, 15, 13);
+    match (quadrant, funct3) {
+        // c.j
+        (0b01, 0b101) => {
+            let imm = bits(inst, 12, 12) << 11
+                | bits(inst, 8, 8) << 10
+                | bits(inst, 10, 9) << 8
+                | bits(inst, 6, 6) << 7
+                | bits(inst, 7, 7) << 6
+                | bits(inst, 2, 2) << 5
+                | bits(inst, 11, 11) << 4
+                | bits(inst, 5, 3) << 1;
+            vec![offset(sign_extend(imm, 12))]
+        }
+        // c.beqz and c.bnez
+        (0b01, 0b110) | (0b01, 0b111) => {
+            let imm = bits(inst, 12, 12) << 8
+                | bits(inst, 6, 5) << 6
+                | bits(inst, 2, 2) << 5
+                | bits(inst, 11, 10) << 3
+                | bits(inst, 4, 3) << 1;
+            vec![next, offset(sign_extend(imm, 9))]
+        }
+        // c.jr and c.jalr, the c.mv, c.add and c.ebreak have the same funct3
+        (0b10, 0b100) if bits(inst, 6, 2) == 0 && bits(inst, 11, 7) != 0 => {
+            vec![regs.x[bits(inst, 11, 7) as usize] & !1]
+        }
+        _ => vec![next],
+    }
+}
+
+/// Read a half word in the space of the process, the address must be aligned to two bytes
+///
+/// - Arguments
+///     - process: the process whose space will be read
+///     - addr: the virtual address of the half word
+///
+/// - Errors
+///     - UserRangeNotAccessible(va, len)
+///     - FrameExhausted
+///     - SwapIOFailed(slot)
+///     - VPNNotMapped(vpn)
+pub(crate) fn read_half(process: &ProcessControlBlock, addr: usize) -> Result<u16> {
+    let (word_addr, shift) = split_half(addr)?;
+    Ok((process.peek_word(word_addr)? >> shift) as u16)
+}
+
+/// Write a half word in the space of the process, the address must be aligned to two bytes
+///
+/// - Arguments
+///     - process: the process whose space will be written
+///     - addr: the virtual address of the half word
+///     - value: the new value of the half word
+///
+/// - Errors
+///     - UserRangeNotAccessible(va, len)
+///     - FrameExhausted
+///     - SwapIOFailed(slot)
+///     - VPNNotMapped(vpn)
+pub(crate) fn write_half(process: &ProcessControlBlock, addr: usize, value: u16) -> Result<()> {
+    let (word_addr, shift) = split_half(addr)?;
+    let word = process.peek_word(word_addr)?;
+    let word = (word & !(0xffff << shift)) | (value as usize) << shift;
+    process.poke_word(word_addr, word)
+}
+
+/// Split the half word address into the address of the word which contains it and the bit offset
+///
+/// - Arguments
+///     - addr: the virtual address of the half word
+///
+/// - Errors
+///     - UserRangeNotAccessible(va, len)
+fn split_half(addr: usize) -> Result<(usize, usize)> {
+    if addr % 2 != 0 {
+        return Err(KernelError::UserRangeNotAccessible(addr, 2));
+    }
+    let word_size = core::mem::size_of::<usize>();
+    Ok((addr & !(word_size - 1), (addr % word_size) * 8))
+}
+
+/// Read the instruction at the program counter,
+/// the full instruction may cross the word boundary so it is read by half words.
+///
+/// - Arguments
+///     - process: the process whose space will be read
+///     - pc: the program counter of the instruction
+///
+/// - Errors
+///     - UserRangeNotAccessible(va, len)
+///     - FrameExhausted
+///     - SwapIOFailed(slot)
+///     - VPNNotMapped(vpn)
+pub(crate) fn read_instruction(process: &ProcessControlBlock, pc: usize) -> Result<u32> {
+    let low = read_half(process, pc)? as u32;
+    if instruction_len(low) == 2 {
+        return Ok(low);
+    }
+    let high = read_half(process, pc + 2)? as u32;
+    Ok(high << 16 | low)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test_case]
+    fn test_next_pcs_of_full_instructions() {
+        let mut regs = UserRegs::default();
+        // addi a0, a0, 1
+        assert_eq!(next_pcs(0x00150513, 0x1000, &regs), vec![0x1004]);
+        // jal ra, -16
+        assert_eq!(next_pcs(0xff1ff0ef, 0x1000, &regs), vec![0xff0]);
+        // beq a0, a1, 8
+        assert_eq!(next_pcs(0x00b50463, 0x1000, &regs), vec![0x1004, 0x1008]);
+        // jalr zero, 3(a0), the lowest bit of the target is cleared
+        regs.x[10] = 0x2000;
+        assert_eq!(next_pcs(0x00350067, 0x1000, &regs), vec![0x2002]);
+    }
+
+    #[test_case]
+    fn test_next_pcs_of_compressed_instructions() {
+        let mut regs = UserRegs::default();
+        // c.addi a0, 1
+        assert_eq!(next_pcs(0x0505, 0x1000, &regs), vec![0x1002]);
+        // c.j -2
+        assert_eq!(next_pcs(0xbffd, 0x1000, &regs), vec![0xffe]);
+        // c.beqz a0, 4
+        assert_eq!(next_pcs(0xc111, 0x1000, &regs), vec![0x1002, 0x1004]);
+        // c.jr ra
+        regs.x[1] = 0x3000;
+        assert_eq!(next_pcs(0x8082, 0x1000, &regs), vec![0x3000]);
+        assert_eq!(instruction_len(C_EBREAK as u32), 2);
+    }
+}
@@ -48,7 +48,36 @@ cfg_if! {
             pub(crate) kernel_sp_va: usize,
         }
 
+        /// The registers of the user mode which are shared with the debugger
+        #[repr(C)]
+        #[derive(Debug, Clone, Copy, Default, PartialEq)]
+        pub(crate) struct UserRegs {
+            /// general purpose registers, the x0 register is always zero
+            pub(crate) x: [usize; 32],
+            /// the program counter where the user mode will resume
+            pub(crate) pc: usize,
+        }
+
         impl TrapContext {
+            /// Get the registers of the user mode
+            pub(crate) fn user_regs(&self) -> UserRegs {
+                UserRegs {
+                    x: self.x,
+                    pc: self.sepc,
+                }
+            }
+
+            /// Replace the registers of the user mode,
+            /// the status and the kernel fields are never changed by the user.
+            ///
+            /// - Arguments
+            ///     - regs: the new registers, the value of the x0 register is ignored
+            pub(crate) fn set_user_regs(&mut self, regs: &UserRegs) {
+                self.x = regs.x;
+                self.x[0] = 0;
+                self.sepc = regs.pc;
+            }
+
             /// Write value to x2 register (sp)
             ///
             /// - Arguments
@@ -57,6 +86,14 @@ cfg_if! {
                 self.x[2] = sp;
             }
 
+            /// Write value to x4 register (tp)
+            ///
+            /// - Arguments
+            ///     - tp: the thread pointer which usually points to the thread local storage
+            pub(crate) fn set_tp(&mut self, tp: usize) {
+                self.x[4] = tp;
+            }
+
             /// Write value to argument register(a0 ~ a7[x10 ~ x17])
             ///
             /// - Arguments
@@ -82,6 +119,13 @@ cfg_if! {
                 self.sepc
             }
 
+            /// Make supervisor exception program counter back to the ecall instruction,
+            /// so the syscall will be executed again after returning to user mode
+            pub(crate) fn sepc_to_prev_instruction(&mut self) -> usize {
+                self.sepc -= 4;
+                self.sepc
+            }
+
             /// Unfortunately, riscv crate's Sstatus structure doesn't support any method to set sstatus's bits
             /// so we have to read every bits out and change it by ourselves :(
             fn read_sstatus_bits() -> usize {
@@ -16,8 +16,10 @@ use riscv::register::{
 use crate::{lang::timer, println};
 use crate::syscall::syscall;
 use crate::task::TASK_SCHEDULER;
+use crate::drivers::{self, plic};
 use crate::{configs, task};
-use crate::{memory::space::Space, sbi::*};
+use crate::memory::space::{FaultKind, Space};
+use crate::sbi::*;
 
 // enable the time interrput and the first timer trigger
 // when system was trap with timer interrupt, it will set other trigger by itself
@@ -27,11 +29,42 @@ pub(crate) fn init_timer_interrupt() {
     timer::set_next_trigger();
 }
 
+// enable the software interrupt, so that the idle hart can be woken up by the inter-processor interrupt
+#[inline(always)]
+pub(crate) fn init_software_interrupt() {
+    unsafe { SBI::enable_software_interrupt() };
+}
+
+// enable the external interrupt, so that the devices can signal the completion of the requests
+#[inline(always)]
+pub(crate) fn init_external_interrupt() {
+    plic::init();
+    unsafe { SBI::enable_external_interrupt() };
+}
+
+/// Acknowledge the interrupts which wake the idle hart up from waiting in the kernel.
+/// Because the global interrupt is disabled in the kernel, the pending interrupts will never be trapped,
+/// they must be cleared here, otherwise the hart will be woken up immediately in the next waiting.
+#[inline(always)]
+pub(crate) fn acknowledge_idle_interrupts() {
+    if SBI::is_timer_interrupt_pending() {
+        timer::set_next_trigger();
+    }
+    unsafe { SBI::clear_software_interrupt() };
+    if SBI::is_external_interrupt_pending() {
+        drivers::handle_external_interrupts();
+    }
+}
+
 /// Set `trap_from_kernel` function as the trap handler entry point
-/// This function just panic so that we force disable the ability of the trap
+/// This function just panic so that we force disable the ability of the trap.
+/// The entry switches to the emergency stack first, see [assembly/kernel_trap.asm].
 #[inline(always)]
 pub(crate) fn set_kernel_trap_entry() {
-    unsafe { SBI::set_direct_trap_vector(trap_from_kernel as usize) }
+    extern "C" {
+        fn _fn_trap_from_kernel();
+    }
+    unsafe { SBI::set_direct_trap_vector(_fn_trap_from_kernel as usize) }
 }
 
 /// Set trampoline code as the trap handler entry point which code is written in the file [assembly/trampoline.asm].
@@ -47,6 +80,20 @@ pub(crate) fn trap_from_kernel() -> ! {
     println!("a trap from kernel");
     let scause = scause::read();
     let stval = stval::read();
+    // the page fault in the guard pages means the kernel stack of the current task was overflowed
+    if let Trap::Exception(Exception::LoadPageFault | Exception::StorePageFault) = scause.cause() {
+        if let Some(task) = task::PROCESSOR.try_current_task() {
+            if task.is_kernel_stack_guard(stval) {
+                match task.try_ids() {
+                    Some((pid, tid)) => panic!(
+                        "kernel stack overflow in task {} of process {}: {:#x}",
+                        tid, pid, stval
+                    ),
+                    None => panic!("kernel stack overflow in current task: {:#x}", stval),
+                }
+            }
+        }
+    }
     panic!("cause with {}: {}", scause.bits(), stval);
 }
 
@@ -54,6 +101,7 @@ cfg_if! {
     if #[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))] {
 
         global_asm!(include_str!("../assembly/riscv64/trampoline.asm"));
+        global_asm!(include_str!("../assembly/riscv64/kernel_trap.asm"));
         // init the supervisor trap vector base address register(stvec)'s value,
         // which was the address of the symbol '_fn_save_all_registers_before_trap'
         // this symbol was point to some assembly code that does two main things:
@@ -64,6 +112,8 @@ cfg_if! {
         pub(crate) fn trap_return() -> ! {
             set_user_trap_entry();
             let task = task::PROCESSOR.current_task().unwrap();
+            // the time elapsed since entering the kernel is the kernel time
+            task.account_cpu_time(false);
             let trap_ctx_va = Space::get_task_trap_ctx_bottom_va(task.tid());
             let process = task.process();
             let user_mmu_token = process.user_token();
@@ -104,19 +154,20 @@ cfg_if! {
                     let task_inner = task.inner_access();
                     let process = task.process();
                     let process_inner = process.inner_access();
-                    let (syscall_id, arg1, arg2, arg3) = task_inner.modify_trap_ctx(process_inner.space(), |trap_ctx| {
+                    let (syscall_id, arg1, arg2, arg3, arg4) = task_inner.modify_trap_ctx(process_inner.space(), |trap_ctx| {
                         let syscall_id = trap_ctx.get_arg(7);
                         let arg1 = trap_ctx.get_arg(0);
                         let arg2 = trap_ctx.get_arg(1);
                         let arg3 = trap_ctx.get_arg(2);
+                        let arg4 = trap_ctx.get_arg(3);
                         trap_ctx.sepc_to_next_instruction();
-                        Ok((syscall_id, arg1, arg2, arg3))
+                        Ok((syscall_id, arg1, arg2, arg3, arg4))
                     }).unwrap();
                     drop(process_inner);
                     drop(process);
                     drop(task_inner);
                     drop(task);
-                    match syscall(syscall_id, arg1, arg2, arg3) {
+                    match syscall(syscall_id, arg1, arg2, arg3, arg4) {
                         Ok(return_back) => {
                             let task = task::PROCESSOR.current_task().unwrap();
                             let task_inner = task.inner_access();
@@ -130,6 +181,8 @@ cfg_if! {
                             drop(process);
                             drop(task_inner);
                             drop(task);
+                            // in deterministic mode, the preemption is triggered at fixed syscall-count boundaries
+                            task::tick_current_syscall().unwrap();
                         },
                         Err(error) => {
                             let task = task::PROCESSOR.current_task().unwrap();
@@ -156,14 +209,57 @@ cfg_if! {
                 | Exception::InstructionPageFault
                 | Exception::LoadFault
                 | Exception::LoadPageFault => {
-                    error!("Fault {:?} in application, kernel send signal.", exception);
-                    task::send_current_task_signal(SignalFlags::SEGV.trunc()).unwrap()
+                    let task = task::PROCESSOR.current_task().unwrap();
+                    let process = task.process();
+                    let pid = process.pid();
+                    // the pages released by madvise or evicted to the swap device are mapped again when they are accessed
+                    match task.refault(stval) {
+                        Ok(true) => return,
+                        Ok(false) => (),
+                        Err(error) => warn!(
+                            "Refault {:#x} in process {} failed cause: {}",
+                            stval, pid, error
+                        ),
+                    }
+                    let process_inner = process.inner_access();
+                    let kind = process_inner.space().classify_fault(stval);
+                    drop(process_inner);
+                    drop(process);
+                    drop(task);
+                    match kind {
+                        FaultKind::StackOverflow(tid) => error!(
+                            "Fault {:?} at {:#x}: stack overflow in task {} of process {}, kernel send signal.",
+                            exception, stval, tid, pid
+                        ),
+                        FaultKind::Protection => error!(
+                            "Fault {:?} at {:#x}: permission denied in process {}, kernel send signal.",
+                            exception, stval, pid
+                        ),
+                        FaultKind::Unmapped => error!(
+                            "Fault {:?} at {:#x}: wild pointer dereference in process {}, kernel send signal.",
+                            exception, stval, pid
+                        ),
+                    }
+                    task::send_current_task_fault_signal(SignalFlags::SEGV.trunc(), stval).unwrap()
                 }
-                // apllcation run some illegal instruction
+                // apllcation run some illegal instruction,
+                // the reading of the counters which are not permitted in user mode is emulated
                 Exception::IllegalInstruction => {
+                    match task::emulate_current_instruction() {
+                        Ok(true) => return,
+                        Ok(false) => (),
+                        Err(error) => warn!("Emulate illegal instruction cause: {}", error),
+                    }
                     error!("IllegalInstruction in application, kernel send signal.");
                     task::send_current_task_signal(SignalFlags::ILL.trunc()).unwrap()
                 }
+                // the breakpoint inserted by the single-step or the ebreak in application
+                Exception::Breakpoint => {
+                    if let Err(error) = task::handle_current_breakpoint() {
+                        error!("Handle breakpoint cause: {}", error);
+                        task::exit_current_and_run_other_task(-1).unwrap();
+                    }
+                }
                 _ => {
                     panic!(
                         "Unsupported exception trap {:?}, stval = {:#x}!",
@@ -176,12 +272,35 @@ cfg_if! {
         fn interrupt_trap_handler(interrupt: Interrupt) {
             match interrupt {
                 Interrupt::SupervisorTimer => {
-                    TASK_SCHEDULER.check_timers();
                     timer::set_next_trigger();
-                    task::suspend_current_and_run_other_task().unwrap();
+                    // the resident frames are sampled in each tick for the resource usage
+                    if let Err(error) = task::account_current_resident_frames() {
+                        warn!("Account resident frames failed cause: {}", error);
+                    }
+                    // the transparent huge page scanner runs in the background of the timer ticks,
+                    // failing to promote will never affect the current task
+                    if configs::MEMORY_TRANSPARENT_HUGE_PAGE {
+                        if let Err(error) = task::promote_current_huge_pages() {
+                            warn!("Promote huge pages failed cause: {}", error);
+                        }
+                    }
+                    // the timers keep firing by the clock of the scheduler,
+                    // but the timer ticks never preempt the current task in deterministic mode,
+                    // so that the interleaving of tasks only depends on the syscalls
+                    TASK_SCHEDULER.check_timers();
+                    if !TASK_SCHEDULER.is_deterministic() {
+                        task::preempt_current_and_run_other_task().unwrap();
+                    }
                 },
+                // the inter-processor interrupt only wakes the hart up,
+                // the ready tasks will be fetched when the hart goes back to the scheduling loop
+                Interrupt::SupervisorSoft => {
+                    unsafe { SBI::clear_software_interrupt() };
+                },
+                // the completions of the devices are acknowledged, the current task keeps running
+                Interrupt::SupervisorExternal => drivers::handle_external_interrupts(),
                 _ => {
-                    unimplemented!("Unimplemented interrupt handler, which was only implemented supervisor timer");
+                    unimplemented!("Unimplemented interrupt handler, which was only implemented supervisor timer, software and external");
                 }
             }
         }
@@ -192,6 +311,10 @@ cfg_if! {
             // now we cannot handle trap from S mode to S mode
             // so we just make it panic here
             set_kernel_trap_entry();
+            // the time elapsed since returning to user mode is the user time
+            if let Ok(task) = task::PROCESSOR.current_task() {
+                task.account_cpu_time(true);
+            }
             // read the trap cause from register
             let scause = scause::read();
             // read the trap specific info value from register
@@ -205,6 +328,8 @@ cfg_if! {
             };
             // each time before return back to user-mode execution,
             // we try to check all pending signals and do some other action.
+            // signals must be checked after the syscall return value was written,
+            // so that an interrupted syscall (e.g. nanosleep) returns it's result after the handler
             match task::handle_current_task_signals() {
                 // do noting when no signal is pending
                 Ok(None) => (),
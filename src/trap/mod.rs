@@ -20,4 +20,6 @@ cfg_if! {
 pub(crate) fn init() {
     handler::set_kernel_trap_entry();
     handler::init_timer_interrupt();
+    handler::init_software_interrupt();
+    handler::init_external_interrupt();
 }